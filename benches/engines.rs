@@ -0,0 +1,60 @@
+//! Compare the virtual machine to the tree-walking interpreter on compute heavy scripts.
+//! Run with `cargo bench`, which builds the interpreter with optimizations.
+
+use std::{
+	path::Path,
+	process::Command,
+	time::{Duration, Instant},
+};
+
+
+/// How many times each script is run. The fastest run is reported.
+const RUNS: usize = 5;
+
+const SCRIPTS: &[&str] = &["fib", "strings", "concat", "dicts", "literals", "fields", "recursion", "calls"];
+
+
+/// Run the script with the given interpreter flags, returning the fastest run.
+fn measure(script: &Path, flags: &[&str]) -> Duration {
+	(0 .. RUNS)
+		.map(
+			|_| {
+				let start = Instant::now();
+				let status = Command::new(env!("CARGO_BIN_EXE_hush"))
+					.args(flags)
+					.arg("--no-warnings")
+					.arg(script)
+					.status()
+					.expect("failed to run hush");
+				let elapsed = start.elapsed();
+
+				assert!(status.success(), "{} failed", script.display());
+
+				elapsed
+			}
+		)
+		.min()
+		.expect("no runs")
+}
+
+
+fn main() {
+	let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/data");
+
+	println!("{:<10} {:>12} {:>12} {:>8}", "script", "tree-walker", "vm", "speedup");
+
+	for script in SCRIPTS {
+		let path = data.join(script).with_extension("hsh");
+
+		let tree_walker = measure(&path, &["--tree-walker"]);
+		let vm = measure(&path, &[]);
+
+		println!(
+			"{:<10} {:>10.1}ms {:>10.1}ms {:>7.2}x",
+			script,
+			tree_walker.as_secs_f64() * 1000.0,
+			vm.as_secs_f64() * 1000.0,
+			tree_walker.as_secs_f64() / vm.as_secs_f64(),
+		);
+	}
+}
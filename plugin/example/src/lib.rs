@@ -0,0 +1,49 @@
+//! An example plugin, used by the tests.
+
+#![cfg_attr(feature = "incompatible", allow(dead_code))]
+
+use hush_plugin::{Args, Registrar, Return, Value};
+
+
+#[cfg(not(feature = "incompatible"))]
+hush_plugin::export!(register);
+
+
+/// Pretend to be built against another version of the interface.
+#[cfg(feature = "incompatible")]
+#[no_mangle]
+pub extern "C" fn hush_plugin_abi_version() -> u32 {
+	hush_plugin::ABI_VERSION + 1
+}
+
+
+fn register(registrar: &mut Registrar) {
+	registrar.register("add", 2, add);
+	registrar.register("greet", 1, greet);
+}
+
+
+extern "C" fn add(args: Args, ret: &mut Return) {
+	match args.as_slice() {
+		[ Value::Int(a), Value::Int(b) ] => match a.checked_add(*b) {
+			Some(sum) => ret.value(&Value::Int(sum)),
+			None => ret.error("overflow"),
+		},
+
+		[ Value::Float(a), Value::Float(b) ] => ret.value(&Value::Float(a + b)),
+
+		_ => ret.error("expected two ints or two floats"),
+	}
+}
+
+
+extern "C" fn greet(args: Args, ret: &mut Return) {
+	match args.as_slice() {
+		[ Value::String(name) ] => {
+			let greeting = [ b"hello, ", name.as_bytes() ].concat();
+			ret.value(&Value::String(greeting.as_slice().into()))
+		}
+
+		_ => ret.error("expected string"),
+	}
+}
@@ -0,0 +1,214 @@
+//! The interface between Hush and native plugins, loaded by scripts with `std.load`.
+//!
+//! A plugin is a shared library (`crate-type = ["cdylib"]`) which exports its registration
+//! function with the `export!` macro. When loaded, Hush first checks the plugin's ABI
+//! version, refusing plugins built against an incompatible version of this crate, and then
+//! calls the registration function, through which the plugin adds its functions:
+//!
+//! ```ignore
+//! use hush_plugin::{Args, Registrar, Return, Value};
+//!
+//! hush_plugin::export!(register);
+//!
+//! fn register(registrar: &mut Registrar) {
+//!     registrar.register("double", 1, double);
+//! }
+//!
+//! extern "C" fn double(args: Args, ret: &mut Return) {
+//!     match args.as_slice() {
+//!         [ Value::Int(i) ] => ret.value(&Value::Int(i * 2)),
+//!         _ => ret.error("expected int"),
+//!     }
+//! }
+//! ```
+//!
+//! # Safety
+//!
+//! Everything crossing the library boundary is `#[repr(C)]`, so that the host and the plugin
+//! may be built by different compilers. Borrowed data, such as arguments and strings, is
+//! only valid for the duration of the call in which it is provided, and must not be kept
+//! by either side. Functions must not unwind across the boundary: a panic in a plugin
+//! function aborts the process. Plugins are never unloaded.
+
+use std::{ffi::c_void, marker::PhantomData};
+
+
+/// The version of the interface. It is bumped on every incompatible change to the types in
+/// this crate, and plugins built against another version are refused.
+pub const ABI_VERSION: u32 = 1;
+
+/// The name of the exported function returning the plugin's ABI version.
+pub const VERSION_SYMBOL: &str = "hush_plugin_abi_version";
+
+/// The name of the exported registration function.
+pub const REGISTER_SYMBOL: &str = "hush_plugin_register";
+
+
+/// The signature of the exported function returning the plugin's ABI version. This must
+/// never change, regardless of the version.
+pub type VersionFn = extern "C" fn() -> u32;
+
+/// The signature of the exported registration function.
+pub type RegisterFn = unsafe extern "C" fn(registrar: *mut Registrar);
+
+/// The signature of plugin functions. The function must set its result through `ret`,
+/// otherwise it returns nil.
+pub type Function = extern "C" fn(args: Args, ret: &mut Return);
+
+
+/// Export the given registration function, of type `fn(&mut Registrar)`, along with the
+/// ABI version of the plugin.
+#[macro_export]
+macro_rules! export {
+	($register: path) => {
+		#[no_mangle]
+		pub extern "C" fn hush_plugin_abi_version() -> u32 {
+			$crate::ABI_VERSION
+		}
+
+		/// # Safety
+		/// The registrar must be a valid pointer, provided by the host.
+		#[no_mangle]
+		pub unsafe extern "C" fn hush_plugin_register(registrar: *mut $crate::Registrar) {
+			$register(&mut *registrar)
+		}
+	};
+}
+
+
+/// A borrowed byte string.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Bytes<'a> {
+	ptr: *const u8,
+	len: usize,
+	_marker: PhantomData<&'a [u8]>,
+}
+
+
+impl<'a> Bytes<'a> {
+	pub fn as_bytes(&self) -> &'a [u8] {
+		// SAFETY: bytes are only constructed from slices of the same lifetime.
+		unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+	}
+}
+
+
+impl<'a> From<&'a [u8]> for Bytes<'a> {
+	fn from(bytes: &'a [u8]) -> Self {
+		Self { ptr: bytes.as_ptr(), len: bytes.len(), _marker: PhantomData }
+	}
+}
+
+
+impl<'a> From<&'a str> for Bytes<'a> {
+	fn from(string: &'a str) -> Self {
+		string.as_bytes().into()
+	}
+}
+
+
+/// The values that may be exchanged with plugins. Collections and functions are not
+/// supported.
+#[repr(C, u8)]
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+	Nil,
+	Bool(bool),
+	Int(i64),
+	Float(f64),
+	String(Bytes<'a>),
+}
+
+
+/// The arguments of a plugin function.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Args<'a> {
+	ptr: *const Value<'a>,
+	len: usize,
+	_marker: PhantomData<&'a [Value<'a>]>,
+}
+
+
+impl<'a> Args<'a> {
+	pub fn as_slice(&self) -> &'a [Value<'a>] {
+		// SAFETY: args are only constructed from slices of the same lifetime.
+		unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+	}
+}
+
+
+impl<'a> From<&'a [Value<'a>]> for Args<'a> {
+	fn from(args: &'a [Value<'a>]) -> Self {
+		Self { ptr: args.as_ptr(), len: args.len(), _marker: PhantomData }
+	}
+}
+
+
+/// The result of a plugin function, which is set through the host.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Return {
+	context: *mut c_void,
+	/// Set the result, copying the value. If error is true, the value is the description of
+	/// an error value.
+	set: extern "C" fn(context: *mut c_void, value: &Value, error: bool),
+}
+
+
+impl Return {
+	/// Construct the result handle. This is meant for the host.
+	///
+	/// # Safety
+	/// The context must remain valid for as long as the handle is in use, and `set` must be
+	/// safe to call with it.
+	pub unsafe fn new(
+		context: *mut c_void,
+		set: extern "C" fn(context: *mut c_void, value: &Value, error: bool),
+	) -> Self {
+		Self { context, set }
+	}
+
+
+	/// Return a value.
+	pub fn value(&mut self, value: &Value) {
+		(self.set)(self.context, value, false)
+	}
+
+
+	/// Return an error value with the given description.
+	pub fn error(&mut self, description: &str) {
+		(self.set)(self.context, &Value::String(description.into()), true)
+	}
+}
+
+
+/// The handle through which a plugin registers its functions.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Registrar {
+	context: *mut c_void,
+	register: extern "C" fn(context: *mut c_void, name: Bytes, arity: u32, function: Function),
+}
+
+
+impl Registrar {
+	/// Construct the registrar. This is meant for the host.
+	///
+	/// # Safety
+	/// The context must remain valid for as long as the registrar is in use, and `register`
+	/// must be safe to call with it.
+	pub unsafe fn new(
+		context: *mut c_void,
+		register: extern "C" fn(context: *mut c_void, name: Bytes, arity: u32, function: Function),
+	) -> Self {
+		Self { context, register }
+	}
+
+
+	/// Add a function taking exactly `arity` arguments, which is checked by the host.
+	pub fn register(&mut self, name: &str, arity: u32, function: Function) {
+		(self.register)(self.context, name.into(), arity, function)
+	}
+}
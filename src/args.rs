@@ -1,27 +1,103 @@
-use std::{ffi::{OsStr, OsString}, os::unix::ffi::OsStrExt, path::{Path, PathBuf}};
+use std::{ffi::{OsStr, OsString}, path::{Path, PathBuf}};
 
 use clap::{AppSettings, clap_app, crate_authors, crate_description, crate_version};
 
+use crate::{diagnostic, ffi::OsStrExt, syntax::format, term::color};
+
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Command {
 	Help(Box<str>),
 	Version(Box<str>),
+	/// Describe the stdlib as JSON, with `--print-std`.
+	PrintStd,
 	Run(Args)
 }
 
 
+/// Where the script is read from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Script {
+	/// A script file.
+	Path(PathBuf),
+	/// Source code given with `-c`.
+	Command(Box<[u8]>),
+	/// The whole stdin, given as `-`.
+	Stdin,
+	/// No script was given, in which case the interactive mode is used if stdin is a
+	/// terminal, and stdin is read otherwise.
+	None,
+}
+
+
+impl Script {
+	/// The path of the script file, if any.
+	pub fn path(&self) -> Option<&Path> {
+		match self {
+			Self::Path(path) => Some(path),
+			_ => None,
+		}
+	}
+}
+
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Args {
-	pub script_path: Option<PathBuf>,
-	/// Check program with static analysis, but don't run.
+	pub script: Script,
+	/// Check program with static analysis, but don't run. With `--fmt`, check that the
+	/// scripts are formatted instead.
 	pub check: bool,
+	/// Format the scripts, printing them to stdout.
+	pub format: bool,
+	/// With `--fmt`, write the formatted scripts in place.
+	pub write: bool,
+	/// The maximum line width for `--fmt`.
+	pub width: usize,
+	/// The scripts given to `--check` or `--fmt`, where `-` is stdin.
+	pub paths: Box<[PathBuf]>,
+	/// Run the registered test cases after executing the program.
+	pub test: bool,
+	/// Use the value of the script as the exit status, if it's an int, false or an error.
+	pub status_from_result: bool,
+	/// Run the script again whenever it or the modules it imported change.
+	pub watch: bool,
+	/// Compile the program to the given path, instead of running it.
+	pub compile: Option<PathBuf>,
+	/// Use a compiled program cached next to the script, if up to date.
+	pub cache: bool,
+	/// Execute with the tree-walking interpreter instead of the virtual machine.
+	pub tree_walker: bool,
+	/// Directories searched by std.import, before those in HUSH_PATH.
+	pub module_path: Box<[PathBuf]>,
+	/// Profile function calls and command blocks, reporting to stderr after executing.
+	pub profile: bool,
+	/// With `--profile`, also write the report as JSON to the given path.
+	pub profile_out: Option<PathBuf>,
+	/// Execute in a step debugger, reading commands from stdin, or from the terminal if the
+	/// script is read from stdin.
+	pub debug: bool,
 	/// Print the lexemes.
 	pub print_lexemes: bool,
 	/// Print the AST.
 	pub print_ast: bool,
 	/// Print the program.
 	pub print_program: bool,
+	/// Print the tokens, and stop before parsing.
+	pub dump_tokens: bool,
+	/// Print the analyzed program, and stop before executing.
+	pub dump_ast: bool,
+	/// Don't report semantic warnings.
+	pub no_warnings: bool,
+	/// Don't warn about variables shadowing variables of enclosing functions.
+	pub allow_shadowing: bool,
+	/// Fail instead of running if there are any warnings.
+	pub deny_warnings: bool,
+	/// The maximum number of reported warnings, if any.
+	pub max_warnings: Option<usize>,
+	/// When to color diagnostics.
+	pub color: color::Mode,
+	/// How to report diagnostics.
+	pub diagnostics: diagnostic::Format,
 	/// Arguments for the script.
 	pub script_args: Box<[Box<[u8]>]>
 }
@@ -38,10 +114,34 @@ where
 				(version: crate_version!())
 				(author: crate_authors!())
 				(about: crate_description!())
-				(@arg check: --check "Perform only static analysis instead of executing.")
+				(@arg command: -c +takes_value "Execute the given source code, passing all remaining arguments to it")
+				(@arg check: --check "Perform only static analysis of the given scripts (- for stdin) instead of executing, or with --fmt, fail if any script is not formatted")
+				(@arg fmt: --fmt "Format the given scripts (- for stdin), printing them to stdout")
+				(@arg write: --write requires[fmt] "With --fmt, write the formatted scripts in place")
+				(@arg width: --width +takes_value requires[fmt] {validate_width} "The maximum line width for --fmt (default: 100)")
+				(@arg test: --test "Run the test cases registered with std.test.case after executing.")
+				(@arg status_from_result: --("status-from-result") "Exit with the value of the script if it's an int from 0 to 125, or with 1 if it's false, an error or another int")
+				(@arg watch: --watch "Run the script again whenever it or the modules it imports change, until interrupted")
+				(@arg compile: --compile "Compile the script to a binary program, without executing")
+				(@arg output: -o --output +takes_value "The path of the compiled program (default: the script path with the hushc extension)")
+				(@arg cache: --cache "Run the compiled program cached next to the script, compiling it if missing or stale")
+				(@arg tree_walker: --("tree-walker") "Execute with the tree-walking interpreter instead of the virtual machine")
+				(@arg module_path: --("module-path") +takes_value +multiple number_of_values(1) "A directory where std.import searches for modules, before those in HUSH_PATH (may be repeated)")
+				(@arg profile: --profile "Print the time spent in each function and command block to stderr after executing")
+				(@arg profile_out: --("profile-out") +takes_value requires[profile] "With --profile, also write the report as JSON to the given path")
+				(@arg debug: --debug "Execute in a step debugger, reading breakpoints and commands from stdin (or the terminal, if the script is read from stdin)")
 				(@arg lex: --lex "Print the lexemes")
 				(@arg ast: --ast "Print the AST")
 				(@arg program: --program "Print the PROGAM")
+				(@arg dump_tokens: --("dump-tokens") "Print the tokens with their kinds and positions, without executing")
+				(@arg dump_ast: --("dump-ast") "Print the analyzed program, without executing")
+				(@arg no_warnings: --("no-warnings") "Don't report warnings, such as unused variables")
+				(@arg allow_shadowing: --("allow-shadowing") "Don't warn about variables shadowing variables of enclosing functions")
+				(@arg deny_warnings: --("deny-warnings") "Treat warnings as errors, failing before executing")
+				(@arg max_warnings: --("max-warnings") +takes_value {validate_count} "The maximum number of reported warnings, 0 for no limit (default: 100)")
+				(@arg color: --color +takes_value possible_value[always never auto] "When to color diagnostics (default: auto, which honors NO_COLOR)")
+				(@arg diagnostics: --diagnostics +takes_value possible_value[human json] "How to report errors and warnings: human readable, or one JSON object per line")
+				(@arg print_std: --("print-std") "Print the name, arity and description of every stdlib member as JSON, without executing")
 				// The script path must not be a separate parameter because we must prevent clap
 				// from parsing flags to the right of the script path.
 				(@arg arguments: ... +allow_hyphen_values "Script and/or arguments")
@@ -49,6 +149,8 @@ where
 		.setting(AppSettings::TrailingVarArg);
 
 	match app.get_matches_from_safe(args) {
+		Ok(matches) if matches.is_present("print_std") => Ok(Command::PrintStd),
+
 		Ok(matches) => {
 			let mut arguments = matches
 				.values_of_os("arguments")
@@ -56,31 +158,130 @@ where
 				.flatten()
 				.map(OsStrExt::as_bytes);
 
-			let mut script_args = Vec::new();
-			let script_path = match arguments.next() {
-				None => None,
-				Some(b"-") => None,
-				Some(arg) => {
-					let path = Path::new(OsStr::from_bytes(arg));
-					if path.is_file() {
-						Some(path.to_owned())
-					} else {
-						script_args.push(arg.into());
-						None
-					}
+			// When checking or formatting, all arguments are scripts, and none is executed.
+			let paths: Box<[PathBuf]> = if matches.is_present("check") || matches.is_present("fmt") {
+				let paths: Vec<PathBuf> = arguments
+					.by_ref()
+					.map(|arg| PathBuf::from(OsStr::from_bytes(arg)))
+					.collect();
+
+				if paths.is_empty() {
+					Box::new([ PathBuf::from("-") ])
+				} else {
+					paths.into()
 				}
+			} else {
+				Box::default()
+			};
+
+			let mut script_args: Vec<Box<[u8]>> = Vec::new();
+			let script = match matches.value_of_os("command") {
+				Some(command) => Script::Command(command.as_bytes().into()),
+
+				None => match arguments.next() {
+					None => Script::None,
+					Some(b"-") => Script::Stdin,
+					Some(arg) => {
+						let path = Path::new(OsStr::from_bytes(arg));
+						if path.is_file() {
+							Script::Path(path.to_owned())
+						} else {
+							script_args.push(arg.into());
+							Script::None
+						}
+					}
+				},
 			};
 
 			script_args.extend(arguments.map(Into::into));
 
+			// The output may be given after the script path, as in `--compile foo.hsh -o foo.hushc`,
+			// in which case it's been collected as a script argument.
+			let mut output = matches.value_of_os("output").map(PathBuf::from);
+			if matches.is_present("compile") && output.is_none() {
+				if let [ flag, path ] = script_args.as_slice() {
+					if flag.as_ref() == b"-o" || flag.as_ref() == b"--output" {
+						output = Some(PathBuf::from(OsStr::from_bytes(path)));
+						script_args.clear();
+					}
+				}
+			}
+
+			if matches.is_present("watch") && script.path().is_none() {
+				return Err(
+					clap::Error::with_description(
+						"--watch requires a script path",
+						clap::ErrorKind::MissingRequiredArgument,
+					)
+				);
+			}
+
+			let compile = if matches.is_present("compile") {
+				match (output, script.path()) {
+					(Some(output), _) => Some(output),
+					(None, Some(path)) => Some(path.with_extension("hushc")),
+					(None, None) => return Err(
+						clap::Error::with_description(
+							"--compile requires a script path or an output path",
+							clap::ErrorKind::MissingRequiredArgument,
+						)
+					),
+				}
+			} else {
+				None
+			};
+
 			Ok(
 				Command::Run(
 					Args {
-						script_path,
+						script,
 						check: matches.is_present("check"),
+						format: matches.is_present("fmt"),
+						write: matches.is_present("write"),
+						// Clap has already validated the value.
+						width: matches
+							.value_of("width")
+							.and_then(|width| width.parse().ok())
+							.unwrap_or(format::DEFAULT_WIDTH),
+						paths,
+						test: matches.is_present("test"),
+						status_from_result: matches.is_present("status_from_result"),
+						watch: matches.is_present("watch"),
+						compile,
+						cache: matches.is_present("cache"),
+						tree_walker: matches.is_present("tree_walker"),
+						module_path: matches
+							.values_of_os("module_path")
+							.into_iter()
+							.flatten()
+							.map(PathBuf::from)
+							.collect(),
+						profile: matches.is_present("profile"),
+						profile_out: matches.value_of_os("profile_out").map(PathBuf::from),
+						debug: matches.is_present("debug"),
 						print_lexemes: matches.is_present("lex"),
 						print_ast: matches.is_present("ast"),
 						print_program: matches.is_present("program"),
+						dump_tokens: matches.is_present("dump_tokens"),
+						dump_ast: matches.is_present("dump_ast"),
+						no_warnings: matches.is_present("no_warnings"),
+						allow_shadowing: matches.is_present("allow_shadowing"),
+						deny_warnings: matches.is_present("deny_warnings"),
+						// Clap has already validated the value.
+						max_warnings: match matches.value_of("max_warnings").map(str::parse) {
+							Some(Ok(0)) => None,
+							Some(Ok(max)) => Some(max),
+							_ => Some(diagnostic::DEFAULT_MAX_WARNINGS),
+						},
+						// Clap has already validated the value.
+						color: matches
+							.value_of("color")
+							.and_then(|mode| mode.parse().ok())
+							.unwrap_or_default(),
+						diagnostics: matches
+							.value_of("diagnostics")
+							.and_then(|format| format.parse().ok())
+							.unwrap_or_default(),
 						script_args: script_args.into_boxed_slice(),
 					}
 				)
@@ -98,3 +299,21 @@ where
 		}
 	}
 }
+
+
+/// Validate a non-negative integer argument.
+fn validate_count(value: String) -> Result<(), String> {
+	value
+		.parse::<usize>()
+		.map(|_| ())
+		.map_err(|_| format!("invalid count: {}", value))
+}
+
+
+/// Validate a positive integer argument.
+fn validate_width(value: String) -> Result<(), String> {
+	match value.parse::<usize>() {
+		Ok(width) if width > 0 => Ok(()),
+		_ => Err(format!("invalid width: {}", value)),
+	}
+}
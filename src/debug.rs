@@ -0,0 +1,403 @@
+//! A step debugger built on the trace hook of the runtime. Breakpoints are read before
+//! executing, and commands whenever the script is paused, one per line.
+
+use std::{
+	collections::HashMap,
+	ffi::OsStr,
+	io::{self, BufRead, Write},
+	path::Path,
+};
+
+use crate::{
+	ffi::OsStrExt,
+	fmt,
+	runtime::{Directive, Frame, Runtime, SourcePos, TraceOptions, TracePoint},
+	symbol::{self, Symbol},
+};
+
+
+/// How many lines are shown before and after the current one.
+const CONTEXT_LINES: u32 = 2;
+
+
+const HELP: &str = "\
+break [file:]line  pause before the statements at the line
+step               pause at the next statement
+next               pause at the next statement, without entering calls
+finish             pause after returning from the current function
+continue           pause at the next breakpoint
+print name         show a variable of the current function
+backtrace          show the function calls in progress
+quit               stop the script";
+
+
+/// When to pause next, besides breakpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+	/// Only at breakpoints.
+	Continue,
+	/// At the next statement.
+	Step,
+	/// At the next statement at most as deep as the given call depth.
+	Next(usize),
+	/// At the next statement shallower than the given call depth.
+	Finish(usize),
+	/// Never, as there are no more commands.
+	Detached,
+}
+
+
+/// Pause before the statements at the given line.
+#[derive(Debug)]
+struct Breakpoint {
+	/// The path of the script, or a suffix of it after a slash.
+	file: Box<[u8]>,
+	line: u32,
+}
+
+
+impl Breakpoint {
+	/// Parse a breakpoint as `file:line`, or `line` in the given script.
+	fn parse(spec: &str, script: &[u8]) -> Option<Self> {
+		let (file, line) = match spec.rfind(':') {
+			Some(ix) => (&spec.as_bytes()[.. ix], &spec[ix + 1 ..]),
+			None => (script, spec),
+		};
+
+		match line.parse() {
+			Ok(line) if line > 0 && !file.is_empty() => Some(Self { file: file.into(), line }),
+			_ => None,
+		}
+	}
+
+
+	fn matches(&self, path: &[u8], line: u32) -> bool {
+		let file = self.file.as_ref();
+
+		self.line == line && (
+			path == file
+				|| path.ends_with(file) && path[.. path.len() - file.len()].ends_with(b"/")
+		)
+	}
+}
+
+
+/// The outcome of a command.
+enum Outcome {
+	/// Keep reading commands.
+	Stay,
+	/// Resume the script, which pauses according to the mode.
+	Resume(Mode),
+	/// Stop the script.
+	Quit,
+}
+
+
+/// The state of a debugging session.
+pub struct Debugger {
+	input: Box<dyn BufRead + Send>,
+	output: Box<dyn Write + Send>,
+	/// The path of the main script, for breakpoints without a file.
+	script: Box<[u8]>,
+	breakpoints: Vec<Breakpoint>,
+	mode: Mode,
+	/// The source code of each script, or None if unreadable.
+	sources: HashMap<Symbol, Option<Box<[u8]>>>,
+}
+
+
+impl Debugger {
+	/// Read breakpoints until `run` or `step` is given, and then attach to the runtime. The
+	/// source code of the main script is given, as it may not be read again, unless the
+	/// script is compiled.
+	pub fn attach<I, O>(
+		runtime: &mut Runtime,
+		script: Symbol,
+		source: Option<&[u8]>,
+		input: I,
+		output: O,
+	) -> io::Result<()>
+	where
+		I: BufRead + Send + 'static,
+		O: Write + Send + 'static,
+	{
+		let path = runtime
+			.interner()
+			.resolve(script)
+			.unwrap_or_default();
+
+		let mut debugger = Self {
+			input: Box::new(input),
+			output: Box::new(output),
+			script: path.into(),
+			breakpoints: Vec::new(),
+			mode: Mode::Continue,
+			sources: HashMap::new(),
+		};
+
+		debugger.sources.insert(script, source.map(Into::into));
+		debugger.setup()?;
+
+		runtime.set_trace_hook(
+			move |point| debugger.trace(point),
+			TraceOptions { locals: true },
+		);
+
+		Ok(())
+	}
+
+
+	/// Read commands before the script starts.
+	fn setup(&mut self) -> io::Result<()> {
+		loop {
+			let line = match self.read_command()? {
+				Some(line) => line,
+				None => return Ok(()),
+			};
+
+			let (command, argument) = split_command(&line);
+
+			match command {
+				"" => (),
+				"b" | "break" => self.add_breakpoint(argument)?,
+				"r" | "run" | "c" | "continue" => return Ok(()),
+				"s" | "step" => {
+					self.mode = Mode::Step;
+					return Ok(());
+				}
+				"h" | "help" => writeln!(self.output, "{}", HELP)?,
+				_ => writeln!(self.output, "the script is not running, use break, step or run")?,
+			}
+		}
+	}
+
+
+	/// Pause before the statement if needed, reading commands until resumed.
+	fn trace(&mut self, point: &TracePoint) -> Directive {
+		let pause = match self.mode {
+			Mode::Detached => return Directive::Continue,
+			Mode::Step => true,
+			Mode::Next(depth) => point.depth <= depth,
+			Mode::Finish(depth) => point.depth < depth,
+			Mode::Continue => false,
+		};
+
+		let path = point.interner
+			.resolve(point.pos.path)
+			.unwrap_or_default();
+
+		let pause = pause
+			|| self.breakpoints
+				.iter()
+				.any(|breakpoint| breakpoint.matches(path, point.pos.line));
+
+		if !pause {
+			return Directive::Continue;
+		}
+
+		// Failing to talk to the user leaves the script running to completion.
+		match self.pause(point) {
+			Ok(Outcome::Resume(mode)) => {
+				self.mode = mode;
+				Directive::Continue
+			}
+			Ok(Outcome::Quit) => Directive::Abort,
+			Ok(Outcome::Stay) | Err(_) => {
+				self.mode = Mode::Detached;
+				Directive::Continue
+			}
+		}
+	}
+
+
+	/// Show where the script is paused, and read commands until resumed. Returns Stay if
+	/// the input is over.
+	fn pause(&mut self, point: &TracePoint) -> io::Result<Outcome> {
+		self.show_position(point.pos, point.interner)?;
+
+		loop {
+			let line = match self.read_command()? {
+				Some(line) => line,
+				None => return Ok(Outcome::Stay),
+			};
+
+			match self.command(&line, point)? {
+				Outcome::Stay => (),
+				outcome => return Ok(outcome),
+			}
+		}
+	}
+
+
+	/// Execute a command while paused.
+	fn command(&mut self, line: &str, point: &TracePoint) -> io::Result<Outcome> {
+		let (command, argument) = split_command(line);
+
+		let outcome = match command {
+			"s" | "step" => Outcome::Resume(Mode::Step),
+			"n" | "next" => Outcome::Resume(Mode::Next(point.depth)),
+			"f" | "finish" => Outcome::Resume(Mode::Finish(point.depth)),
+			"c" | "continue" => Outcome::Resume(Mode::Continue),
+			"q" | "quit" => Outcome::Quit,
+
+			"p" | "print" => {
+				let value = point.locals
+					.as_ref()
+					.and_then(|locals| locals.get(argument));
+
+				match value {
+					Some(value) => writeln!(
+						self.output,
+						"{} = {}",
+						argument,
+						fmt::Show(&value, point.interner)
+					)?,
+					None => writeln!(self.output, "no variable {} in the current function", argument)?,
+				}
+
+				Outcome::Stay
+			}
+
+			"bt" | "backtrace" => {
+				self.show_backtrace(point)?;
+				Outcome::Stay
+			}
+
+			"b" | "break" => {
+				self.add_breakpoint(argument)?;
+				Outcome::Stay
+			}
+
+			"h" | "help" => {
+				writeln!(self.output, "{}", HELP)?;
+				Outcome::Stay
+			}
+
+			"" => Outcome::Stay,
+
+			other => {
+				writeln!(self.output, "unknown command: {}, try help", other)?;
+				Outcome::Stay
+			}
+		};
+
+		Ok(outcome)
+	}
+
+
+	/// Read the next command, or None if the input is over.
+	fn read_command(&mut self) -> io::Result<Option<String>> {
+		write!(self.output, "(debug) ")?;
+		self.output.flush()?;
+
+		let mut line = String::new();
+		if self.input.read_line(&mut line)? == 0 {
+			writeln!(self.output)?;
+			return Ok(None);
+		}
+
+		Ok(Some(line.trim().to_owned()))
+	}
+
+
+	fn add_breakpoint(&mut self, spec: &str) -> io::Result<()> {
+		match Breakpoint::parse(spec, &self.script) {
+			Some(breakpoint) => {
+				writeln!(
+					self.output,
+					"breakpoint {} at {}:{}",
+					self.breakpoints.len() + 1,
+					String::from_utf8_lossy(&breakpoint.file),
+					breakpoint.line,
+				)?;
+				self.breakpoints.push(breakpoint);
+			}
+
+			None => writeln!(self.output, "invalid breakpoint: {}, expected [file:]line", spec)?,
+		}
+
+		Ok(())
+	}
+
+
+	/// Show the position, and the surrounding lines of source code.
+	fn show_position(&mut self, pos: &SourcePos, interner: &symbol::Interner) -> io::Result<()> {
+		writeln!(self.output, "paused at {}", fmt::Show(pos, interner))?;
+
+		let source = self.sources
+			.entry(pos.path)
+			.or_insert_with(
+				|| interner
+					.resolve(pos.path)
+					.and_then(|path| std::fs::read(Path::new(OsStr::from_bytes(path))).ok())
+					.map(Into::into)
+			);
+
+		let source = match source {
+			Some(source) => source,
+			None => return Ok(()),
+		};
+
+		let first = pos.line.saturating_sub(CONTEXT_LINES).max(1);
+		let last = pos.line + CONTEXT_LINES;
+
+		let lines = source
+			.split(|&c| c == b'\n')
+			.zip(1 ..)
+			.skip(first as usize - 1)
+			.take_while(|(_, number)| *number <= last);
+
+		for (line, number) in lines {
+			let marker = if number == pos.line { "=>" } else { "  " };
+			writeln!(
+				self.output,
+				"{} {:>4} | {}",
+				marker,
+				number,
+				String::from_utf8_lossy(line).trim_end(),
+			)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Show the calls in progress, from the innermost to the outermost.
+	fn show_backtrace(&mut self, point: &TracePoint) -> io::Result<()> {
+		let function = |frame: Option<&Frame>| match frame {
+			Some(frame) => fmt::Show(&frame.function, point.interner).to_string(),
+			None => "<main>".to_owned(),
+		};
+
+		writeln!(
+			self.output,
+			"#0 {} in {}",
+			fmt::Show(point.pos, point.interner),
+			function(point.frames.last()),
+		)?;
+
+		// Each call is made from the function of the enclosing frame.
+		for (ix, frame) in point.frames.iter().enumerate().rev() {
+			let caller = ix.checked_sub(1).and_then(|ix| point.frames.get(ix));
+
+			writeln!(
+				self.output,
+				"#{} {} in {}",
+				point.frames.len() - ix,
+				fmt::Show(&frame.pos, point.interner),
+				function(caller),
+			)?;
+		}
+
+		Ok(())
+	}
+}
+
+
+/// Split a command line into the command and its argument.
+fn split_command(line: &str) -> (&str, &str) {
+	match line.split_once(char::is_whitespace) {
+		Some((command, argument)) => (command, argument.trim()),
+		None => (line, ""),
+	}
+}
@@ -0,0 +1,315 @@
+//! Machine-readable diagnostics, for editor integrations.
+//! Each diagnostic is serialized as a single line of JSON.
+
+use std::collections::HashSet;
+
+use serde_json::json;
+
+use crate::{
+	fmt::{self, FmtString},
+	runtime::Panic,
+	semantic,
+	symbol,
+	syntax::{self, SourcePos},
+	term::color,
+};
+
+
+/// How diagnostics are reported by the CLI.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+	/// Colored text with snippets.
+	#[default]
+	Human,
+	/// One JSON object per line.
+	Json,
+}
+
+
+impl std::str::FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"human" => Ok(Self::Human),
+			"json" => Ok(Self::Json),
+			other => Err(format!("invalid diagnostics format: {}", other)),
+		}
+	}
+}
+
+
+/// The severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+	Error,
+	Warning,
+}
+
+
+impl Severity {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Error => "error",
+			Self::Warning => "warning",
+		}
+	}
+}
+
+
+/// A diagnostic, independent of the phase which produced it.
+/// Positions are single points, as the parser doesn't track spans yet.
+#[derive(Debug)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	/// A stable identifier for the kind of diagnostic.
+	pub code: &'static str,
+	/// The message, without the position.
+	pub message: String,
+	/// Positions are absent for errors that don't refer to a specific place.
+	pub pos: Option<SourcePos>,
+	/// Other positions related to the diagnostic, with a description of each.
+	pub related: Vec<(&'static str, SourcePos)>,
+}
+
+
+impl Diagnostic {
+	/// Build a diagnostic for a syntax error.
+	pub fn syntax(error: &syntax::Error, interner: &symbol::Interner) -> Self {
+		match error {
+			syntax::Error::Lexer(error) => Self {
+				severity: Severity::Error,
+				code: error.error.code(),
+				message: error.error.to_string(),
+				pos: Some(error.pos),
+				related: Vec::new(),
+			},
+
+			syntax::Error::Parser(error) => {
+				let related = match error {
+					syntax::parser::Error::UnexpectedEof { open: Some(token), .. } => {
+						vec![ ("opened here", token.pos) ]
+					}
+
+					_ => Vec::new(),
+				};
+
+				Self {
+					severity: Severity::Error,
+					code: error.code(),
+					message: plain(error.message(), interner),
+					pos: error.pos(),
+					related,
+				}
+			}
+		}
+	}
+
+
+	/// Build a diagnostic for a semantic error.
+	pub fn semantic(error: &semantic::Error, interner: &symbol::Interner) -> Self {
+		use semantic::ErrorKind;
+
+		let related = match &error.kind {
+			// Implicit variables, like std, have no position.
+			ErrorKind::DuplicateVariable(_, first) if *first == SourcePos::default() => Vec::new(),
+			ErrorKind::DuplicateVariable(_, first) => vec![ ("first declared here", *first) ],
+			ErrorKind::DuplicateKey(_, first) => vec![ ("first defined here", *first) ],
+			ErrorKind::ArityMismatch { definition, .. } => vec![ ("defined here", *definition) ],
+			_ => Vec::new(),
+		};
+
+		Self {
+			severity: Severity::Error,
+			code: error.kind.code(),
+			message: plain(&error.kind, interner),
+			pos: Some(error.pos),
+			related,
+		}
+	}
+
+
+	/// Build a diagnostic for a semantic warning.
+	pub fn warning(warning: &semantic::Warning, interner: &symbol::Interner) -> Self {
+		use semantic::WarningKind;
+
+		let related = match &warning.kind {
+			WarningKind::ShadowedVariable(_, shadowed)
+				| WarningKind::ShadowedOuterVariable(_, shadowed) => vec![ ("shadowed declaration", *shadowed) ],
+			_ => Vec::new(),
+		};
+
+		Self {
+			severity: Severity::Warning,
+			code: warning.kind.code(),
+			message: plain(&warning.kind, interner),
+			pos: Some(warning.pos),
+			related,
+		}
+	}
+
+
+	/// Build a diagnostic for a runtime panic. The calls in the traceback, if any, are
+	/// related positions, from the outermost to the innermost.
+	pub fn panic(panic: &Panic, interner: &symbol::Interner) -> Self {
+		let related = panic
+			.traceback()
+			.into_iter()
+			.flat_map(|traceback| traceback.frames())
+			.map(|frame| ("called here", (&frame.pos).into()))
+			.collect();
+
+		Self {
+			severity: Severity::Error,
+			code: panic.kind().code(),
+			message: plain(panic.message(), interner),
+			pos: Some(panic.pos().into()),
+			related,
+		}
+	}
+
+
+	/// Serialize the diagnostic as JSON.
+	pub fn to_json(&self, interner: &symbol::Interner) -> serde_json::Value {
+		let file = |pos: &SourcePos| plain(pos.path, interner);
+
+		let mut object = json!({
+			"severity": self.severity.as_str(),
+			"code": self.code,
+			"message": self.message,
+		});
+
+		if let Some(pos) = &self.pos {
+			object["file"] = file(pos).into();
+			object["line"] = pos.line.into();
+			object["column"] = pos.column.into();
+		}
+
+		if !self.related.is_empty() {
+			object["related"] = self.related
+				.iter()
+				.map(
+					|(message, pos)| json!({
+						"message": message,
+						"file": file(pos),
+						"line": pos.line,
+						"column": pos.column,
+					})
+				)
+				.collect();
+		}
+
+		object
+	}
+}
+
+
+/// Format without colors, as JSON consumers are not terminals.
+fn plain<'a, T>(value: T, interner: &'a symbol::Interner) -> String
+where
+	T: fmt::Display<'a, Context = &'a symbol::Interner>,
+{
+	color::plain(|| value.fmt_string(interner))
+}
+
+
+/// The default cap on reported warnings.
+pub const DEFAULT_MAX_WARNINGS: usize = 100;
+
+
+/// Reports warnings, deduplicating them by code and position, and capping how many are
+/// reported. Summaries of the omitted warnings are reported by `finish`.
+#[derive(Debug)]
+pub struct Sink<W> {
+	writer: W,
+	format: Format,
+	/// The maximum number of reported warnings, if any.
+	max_warnings: Option<usize>,
+	/// The warnings reported so far.
+	seen: HashSet<(&'static str, SourcePos)>,
+	/// How many warnings were omitted for being repeated.
+	duplicates: usize,
+	/// How many warnings were omitted for exceeding the cap.
+	suppressed: usize,
+}
+
+
+impl<W> Sink<W>
+where
+	W: std::io::Write,
+{
+	pub fn new(writer: W, format: Format, max_warnings: Option<usize>) -> Self {
+		Self {
+			writer,
+			format,
+			max_warnings,
+			seen: HashSet::new(),
+			duplicates: 0,
+			suppressed: 0,
+		}
+	}
+
+
+	/// Report a semantic warning, unless it is repeated or exceeds the cap.
+	pub fn warning(&mut self, warning: &semantic::Warning, interner: &symbol::Interner) {
+		if !self.seen.insert((warning.kind.code(), warning.pos)) {
+			self.duplicates += 1;
+			return;
+		}
+
+		if self.max_warnings.is_some_and(|max| self.seen.len() > max) {
+			self.suppressed += 1;
+			return;
+		}
+
+		let result = match self.format {
+			Format::Human => writeln!(self.writer, "{}", fmt::Show(warning, interner)),
+			Format::Json => writeln!(
+				self.writer,
+				"{}",
+				Diagnostic::warning(warning, interner).to_json(interner),
+			),
+		};
+
+		// There is nothing sensible to do if stderr is gone.
+		result.ok();
+	}
+
+
+	/// How many distinct warnings were received, including the ones omitted by the cap.
+	pub fn count(&self) -> usize {
+		self.seen.len()
+	}
+
+
+	/// Report how many warnings were omitted, if any.
+	pub fn finish(&mut self) {
+		let summaries = [
+			(self.suppressed, "more warnings suppressed"),
+			(self.duplicates, "duplicate warnings suppressed"),
+		];
+
+		for &(count, summary) in summaries.iter() {
+			if count == 0 {
+				continue;
+			}
+
+			let message = format!("{} {}", count, summary);
+
+			let result = match self.format {
+				Format::Human => writeln!(self.writer, "{}: {}", color::Fg(color::Yellow, "Warning"), message),
+				Format::Json => writeln!(
+					self.writer,
+					"{}",
+					json!({
+						"severity": Severity::Warning.as_str(),
+						"code": "warnings-suppressed",
+						"message": message,
+					})
+				),
+			};
+
+			result.ok();
+		}
+	}
+}
@@ -0,0 +1,162 @@
+//! A convenience API to compile and run Hush source code, for embedders that don't need
+//! to drive each phase on their own.
+
+use std::rc::Rc;
+
+use crate::{
+	fmt::{self, Display},
+	runtime::{value::{Str, Value}, Panic, Runtime},
+	semantic::{self, program::Program},
+	symbol,
+	syntax,
+};
+
+
+/// The error of compiling or running a program. Each error carries its position.
+#[derive(Debug)]
+pub enum Error {
+	/// Lexical or syntax errors.
+	Syntax(syntax::Errors),
+	/// Semantic errors, such as undeclared variables.
+	Semantic(semantic::Errors),
+	/// The program panicked.
+	Panic(Panic),
+}
+
+
+impl From<Panic> for Error {
+	fn from(panic: Panic) -> Self {
+		Self::Panic(panic)
+	}
+}
+
+
+impl<'a> Display<'a> for Error {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Syntax(errors) => errors.fmt(
+				f,
+				syntax::AnalysisDisplayContext { max_errors: None, interner: context },
+			),
+
+			Self::Semantic(errors) => errors.fmt(
+				f,
+				semantic::ErrorsDisplayContext { max_errors: None, interner: context },
+			),
+
+			Self::Panic(panic) => panic.fmt(f, context),
+		}
+	}
+}
+
+
+/// Compiles and runs Hush source code. The runtime is kept between evaluations, so that
+/// values returned by previous evaluations remain usable.
+#[derive(Debug)]
+pub struct Engine {
+	runtime: Runtime,
+	/// The scope of the inputs evaluated with `eval_input`.
+	session: semantic::Session,
+}
+
+
+impl Engine {
+	/// The path attributed to source code evaluated with `eval_str`.
+	pub const PATH: &'static str = "<eval>";
+
+
+	/// Create an engine, with no script arguments.
+	pub fn new() -> Self {
+		Self::with_args(std::iter::empty::<&str>())
+	}
+
+
+	/// Create an engine, with the given script arguments.
+	pub fn with_args<A, S>(args: A) -> Self
+	where
+		A: IntoIterator<Item = S>,
+		S: Into<Str>,
+	{
+		let mut interner = symbol::Interner::new();
+		let session = semantic::Session::new(&mut interner);
+
+		Self { runtime: Runtime::new(args, interner), session }
+	}
+
+
+	/// Compile the given source code, attributing it to the given path in positions. The
+	/// compiled program may be evaluated many times. Warnings are not reported.
+	pub fn compile(&mut self, path: &str, source: &[u8]) -> Result<Rc<Program>, Error> {
+		let interner = self.runtime.interner_mut();
+		let path = interner.get_or_intern(path);
+		let source = syntax::Source { path, contents: source.into() };
+
+		let analysis = syntax::Analysis::analyze(&source, interner);
+		if !analysis.is_ok() {
+			return Err(Error::Syntax(analysis.errors));
+		}
+
+		let (program, _) = semantic::Analyzer::analyze(analysis.ast, interner)
+			.map_err(Error::Semantic)?;
+
+		Ok(Rc::new(program))
+	}
+
+
+	/// Evaluate a compiled program.
+	pub fn eval(&mut self, program: &Rc<Program>) -> Result<Value, Error> {
+		Ok(self.runtime.eval(program.clone())?)
+	}
+
+
+	/// Compile and evaluate the given source code.
+	pub fn eval_str(&mut self, source: &str) -> Result<Value, Error> {
+		let program = self.compile(Self::PATH, source.as_bytes())?;
+		self.eval(&program)
+	}
+
+
+	/// Compile and evaluate the given source code as the next input of a persistent session,
+	/// as in an interactive mode. Unlike `eval_str`, variables declared by previous inputs
+	/// are in scope, and the ones declared by this input are kept for later inputs. Inputs
+	/// with errors declare nothing. The compiled input is owned by the runtime, which keeps
+	/// it alive for as long as the functions it defines.
+	pub fn eval_input(&mut self, source: &str) -> Result<Value, Error> {
+		let interner = self.runtime.interner_mut();
+		let path = interner.get_or_intern(Self::PATH);
+		let source = syntax::Source { path, contents: source.as_bytes().into() };
+
+		let analysis = syntax::Analysis::analyze(&source, interner);
+		if !analysis.is_ok() {
+			return Err(Error::Syntax(analysis.errors));
+		}
+
+		let (program, _) = semantic::Analyzer
+			::analyze_session(analysis.ast, interner, &mut self.session)
+			.map_err(Error::Semantic)?;
+
+		Ok(self.runtime.eval_session(program)?)
+	}
+
+
+	/// Format an error, including the source snippet when available.
+	pub fn format_error(&self, error: &Error) -> String {
+		fmt::Show(error, self.runtime.interner()).to_string()
+	}
+
+
+	/// Get the runtime, for instance to register native functions and host values, or to
+	/// call values.
+	pub fn runtime(&mut self) -> &mut Runtime {
+		&mut self.runtime
+	}
+}
+
+
+impl Default for Engine {
+	fn default() -> Self {
+		Self::new()
+	}
+}
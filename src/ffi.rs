@@ -0,0 +1,54 @@
+//! Conversions between OS strings and bytes. The standard library only provides these on
+//! unix and wasi, but other targets, such as wasm32-unknown-unknown, represent OS strings
+//! as arbitrary bytes as well.
+
+#[cfg(unix)]
+pub use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+#[cfg(target_os = "wasi")]
+pub use std::os::wasi::ffi::{OsStrExt, OsStringExt};
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub use bytes::{OsStrExt, OsStringExt};
+
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+mod bytes {
+	use std::ffi::{OsStr, OsString};
+
+
+	pub trait OsStrExt {
+		fn from_bytes(bytes: &[u8]) -> &Self;
+		fn as_bytes(&self) -> &[u8];
+	}
+
+
+	impl OsStrExt for OsStr {
+		fn from_bytes(bytes: &[u8]) -> &Self {
+			// SAFETY: OS strings are plain bytes in the targets covered by this module.
+			unsafe { OsStr::from_encoded_bytes_unchecked(bytes) }
+		}
+
+		fn as_bytes(&self) -> &[u8] {
+			self.as_encoded_bytes()
+		}
+	}
+
+
+	pub trait OsStringExt {
+		fn from_vec(vec: Vec<u8>) -> Self;
+		fn into_vec(self) -> Vec<u8>;
+	}
+
+
+	impl OsStringExt for OsString {
+		fn from_vec(vec: Vec<u8>) -> Self {
+			// SAFETY: OS strings are plain bytes in the targets covered by this module.
+			unsafe { OsString::from_encoded_bytes_unchecked(vec) }
+		}
+
+		fn into_vec(self) -> Vec<u8> {
+			self.into_encoded_bytes()
+		}
+	}
+}
@@ -125,3 +125,16 @@ where
 
 	Ok(())
 }
+
+
+/// Whether a character would disrupt the surrounding text if displayed as is. This
+/// includes control characters, and invisible formatting characters such as bidirectional
+/// overrides.
+pub fn is_unprintable(c: char) -> bool {
+	c.is_control()
+		|| matches!(
+			c,
+			'\u{061C}' | '\u{200B}' ..= '\u{200F}' | '\u{202A}' ..= '\u{202E}'
+				| '\u{2060}' ..= '\u{2069}' | '\u{FEFF}'
+		)
+}
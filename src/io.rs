@@ -1,10 +1,55 @@
-use std::os::unix::prelude::{AsRawFd, RawFd};
+#[cfg(any(unix, target_os = "wasi"))]
+use std::os::fd::AsRawFd;
 
 
-pub type FileDescriptor = RawFd;
+#[cfg(any(unix, target_os = "wasi"))]
+pub type FileDescriptor = std::os::fd::RawFd;
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub type FileDescriptor = i32;
 
 
 /// Get the file descriptor for stdout.
+#[cfg(any(unix, target_os = "wasi"))]
 pub fn stdout_fd() -> FileDescriptor {
 	std::io::stdout().as_raw_fd()
 }
+
+
+/// Get the file descriptor for stdout, which is conventionally 1 where there are no file
+/// descriptors.
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub fn stdout_fd() -> FileDescriptor {
+	1
+}
+
+
+/// A replacement for stdin after the script has been read from it. Reading fails, so that
+/// scripts don't silently get an empty input.
+#[derive(Debug)]
+pub struct ConsumedStdin;
+
+
+impl std::io::Read for ConsumedStdin {
+	fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+		Err(
+			std::io::Error::new(
+				std::io::ErrorKind::NotConnected,
+				"stdin is unavailable, as the script was read from it",
+			)
+		)
+	}
+}
+
+
+/// Open the controlling terminal for reading.
+#[cfg(unix)]
+pub fn open_tty() -> std::io::Result<std::fs::File> {
+	std::fs::File::open("/dev/tty")
+}
+
+
+/// Without a controlling terminal, there is nothing to open.
+#[cfg(not(unix))]
+pub fn open_tty() -> std::io::Result<std::fs::File> {
+	Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no controlling terminal"))
+}
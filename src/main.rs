@@ -1,8 +1,14 @@
 #![allow(dead_code)] // This is temporarily used for the inital development.
 
 mod args;
+mod debug;
+mod diagnostic;
+mod engine;
+mod ffi;
 mod fmt;
 mod io;
+mod profile;
+mod repl;
 mod runtime;
 mod semantic;
 mod symbol;
@@ -10,21 +16,56 @@ mod syntax;
 mod term;
 #[cfg(test)]
 mod tests;
+mod watch;
 
-use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 
+use ffi::OsStrExt;
 use term::color;
 
-use args::{Args, Command};
-use runtime::{Panic, SourcePos, Runtime};
+use args::{Args, Command, Script};
+use debug::Debugger;
+use diagnostic::Diagnostic;
+use profile::Profiler;
+use runtime::{value::Value, Interpreter, Panic, PanicKind, SourcePos, Runtime};
+use semantic::program::binary;
 
 
-#[derive(Debug)]
+/// The native stack size of the interpreter thread. Calls recurse on the native stack, so it
+/// must fit the maximum call depth, with room for unoptimized builds. Only the pages
+/// actually used are committed.
+const STACK_SIZE: usize = 1 << 30;
+
+
+/// The maximum depth of nested calls in the interpreter thread, which is much deeper than the
+/// runtime's default, as that must fit in the stack of any thread.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+
+/// The exit status of the interpreter, so that wrapper scripts can tell error classes apart.
+/// Scripts that finish succeed regardless of their value, unless `--status-from-result` is
+/// given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ExitStatus {
 	Success,
-	InvalidArgs,
-	StaticError,
+	/// The script panicked.
 	Panic,
+	/// The value of the script denotes a failure, with `--status-from-result`.
+	Result(u8),
+	/// The script called std.exit with the status.
+	Exit(u8),
+	/// Lexical or syntax errors.
+	SyntaxError,
+	/// Semantic errors, such as undeclared variables.
+	SemanticError,
+	/// Some test cases failed.
+	TestFailure,
+	/// Some scripts are not formatted, with `--fmt --check`.
+	Unformatted,
+	/// Invalid command line arguments.
+	InvalidArgs,
+	/// A bug in the interpreter.
+	InternalError,
 }
 
 
@@ -32,9 +73,30 @@ impl From<ExitStatus> for i32 {
 	fn from(status: ExitStatus) -> Self {
 		match status {
 			ExitStatus::Success => 0,
-			ExitStatus::InvalidArgs => 1,
-			ExitStatus::StaticError => 2,
-			ExitStatus::Panic => 127,
+			ExitStatus::Panic => 1,
+			ExitStatus::Result(code) => code.into(),
+			ExitStatus::Exit(code) => code.into(),
+			ExitStatus::SyntaxError => 2,
+			ExitStatus::SemanticError => 3,
+			ExitStatus::TestFailure => 4,
+			ExitStatus::Unformatted => 5,
+			ExitStatus::InvalidArgs => 64,
+			ExitStatus::InternalError => 101,
+		}
+	}
+}
+
+
+impl ExitStatus {
+	/// The exit status for the value of a script, with `--status-from-result`. Ints from 0 to
+	/// 125 are used as is, as greater codes have special meanings for shells. False, errors
+	/// and other ints are failures, while any other value is a success.
+	fn from_result(value: &Value) -> Self {
+		match value {
+			Value::Int(0) => Self::Success,
+			Value::Int(code @ 1 ..= 125) => Self::Result(*code as u8),
+			Value::Int(_) | Value::Bool(false) | Value::Error(_) => Self::Result(1),
+			_ => Self::Success,
 		}
 	}
 }
@@ -50,28 +112,163 @@ fn main() -> ! {
 	};
 
 	let exit_status = match command {
-		Command::Run(args) => run(args),
+		// Rust panics are bugs in the interpreter, which are reported by the panic hook.
+		Command::Run(args) => std::thread::Builder::new()
+			.name("main".into())
+			.stack_size(STACK_SIZE)
+			.spawn(move || run(args))
+			.expect("failed to spawn interpreter thread")
+			.join()
+			.unwrap_or(ExitStatus::InternalError),
 		Command::Help(msg) | Command::Version(msg) => {
 			println!("{}", msg);
 			ExitStatus::Success
 		},
+		Command::PrintStd => {
+			println!("{}", std_json());
+			ExitStatus::Success
+		},
 	};
 
 	std::process::exit(exit_status.into())
 }
 
 
+/// Describe every stdlib member, for shell completion and editor tooling. Functions have a
+/// minimum and maximum number of arguments, the latter being null if variadic.
+fn std_json() -> serde_json::Value {
+	let entries: Vec<serde_json::Value> = runtime::std_entries()
+		.into_iter()
+		.map(
+			|entry| match entry.arity {
+				Some(arity) => serde_json::json!({
+					"name": entry.name,
+					"kind": "function",
+					"min_args": arity.min,
+					"max_args": arity.max,
+					"variadic": arity.max.is_none(),
+					"doc": entry.doc,
+				}),
+
+				None => serde_json::json!({
+					"name": entry.name,
+					"kind": "constant",
+					"doc": entry.doc,
+				}),
+			}
+		)
+		.collect();
+
+	entries.into()
+}
+
+
 fn run(args: Args) -> ExitStatus {
-	let mut interner = symbol::Interner::new();
+	color::set_mode(args.color);
+
+	let interner = symbol::Interner::new();
+
+	if args.format {
+		return format(&args, interner);
+	}
+
+	if args.check {
+		return check(&args, interner);
+	}
+
+	if args.script == Script::None && stdin_is_tty() {
+		return repl(args, interner);
+	}
+
+	if args.watch {
+		return watch_script(args, watch::Options::default(), |_, _| true);
+	}
+
+	execute(args, interner).0
+}
+
+
+/// Run the script, and run it again whenever it or the modules it imported change. The
+/// callback is invoked after each run with its number and exit status, and watching stops
+/// when it returns false, in which case the status of the last run is returned.
+fn watch_script<F>(args: Args, options: watch::Options, mut finished: F) -> ExitStatus
+where
+	F: FnMut(usize, ExitStatus) -> bool,
+{
+	let script = args.script
+		.path()
+		.map(Path::to_owned)
+		.expect("--watch requires a script path"); // Validated when parsing the arguments.
 
-	let (source, path) = match args.script_path {
-		Some(path) => {
+	for run in 1 .. {
+		eprintln!(
+			"{}: running {} (run {})",
+			color::Fg(color::Yellow, "Watch"),
+			script.display(),
+			run,
+		);
+
+		// The script is recorded before running, so that changes meanwhile are not missed.
+		let mut files = watch::Files::default();
+		files.watch(script.clone());
+
+		#[cfg_attr(not(feature = "system"), allow(unused_mut))]
+		let (status, mut runtime) = execute(args.clone(), symbol::Interner::new());
+
+		if let Some(runtime) = &runtime {
+			for import in runtime.imports() {
+				if let Some(path) = runtime.interner().resolve(import) {
+					files.watch(PathBuf::from(std::ffi::OsStr::from_bytes(path)));
+				}
+			}
+		}
+
+		eprintln!(
+			"{}: exited with status {}, waiting for changes to {} {}",
+			color::Fg(color::Yellow, "Watch"),
+			i32::from(status),
+			files.len(),
+			if files.len() == 1 { "file" } else { "files" },
+		);
+
+		let watching = finished(run, status);
+
+		if watching {
+			files.wait(options);
+		}
+
+		// Processes left running by the script must not outlive its run.
+		#[cfg(feature = "system")]
+		if let Some(runtime) = &mut runtime {
+			runtime.kill_processes();
+		}
+
+		if !watching {
+			return status;
+		}
+	}
+
+	unreachable!("runs are unbounded")
+}
+
+
+/// Execute the script. Returns the runtime along with the exit status, unless the script
+/// failed before executing.
+fn execute(args: Args, mut interner: symbol::Interner) -> (ExitStatus, Option<Runtime>) {
+	let (source, path) = match &args.script {
+		Script::Path(path) => {
 			let path = interner.get_or_intern(path.as_os_str().as_bytes());
 			let source = syntax::Source::from_path(path, &mut interner);
 			(source, path)
 		},
 
-		None => {
+		Script::Command(command) => {
+			let path = interner.get_or_intern("<command-line>");
+			let source = syntax::Source { path, contents: command.clone() };
+			(Ok(source), path)
+		},
+
+		Script::Stdin | Script::None => {
 			let path = interner.get_or_intern("<stdin>");
 			let source = syntax::Source::from_reader(path, std::io::stdin().lock());
 			(source, path)
@@ -81,41 +278,414 @@ fn run(args: Args) -> ExitStatus {
 	let source = match source {
     Ok(source) => source,
     Err(error) => {
-			eprintln!(
-				"{}",
-				fmt::Show(
-					Panic::io(error, SourcePos::file(path)),
-					&interner
-				)
-			);
-			return ExitStatus::Panic;
+			report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+			return (ExitStatus::Panic, None);
 		}
 	};
 
+	let (program, source_hash) = if binary::is_compiled(&source.contents) {
+		match load_compiled(&source.contents, &mut interner) {
+			Ok((header, program)) => (program, header.source_hash),
+			Err(error) => {
+				let error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+				report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+				return (ExitStatus::Panic, None);
+			}
+		}
+	} else {
+		let source_hash = binary::hash(&source.contents);
+
+		// The cache is not used when inspecting the analysis.
+		let inspecting = args.dump_tokens
+			|| args.print_lexemes
+			|| args.print_ast
+			|| args.print_program
+			|| args.dump_ast;
+
+		let cache = args.script
+			.path()
+			.filter(|_| args.cache && !inspecting)
+			.map(|path| path.with_extension("hushc"));
+
+		let cached = cache
+			.as_ref()
+			.and_then(|cache| load_cached(cache, &source, source_hash, &mut interner));
+
+		match cached {
+			Some(program) => (program, source_hash),
+			None => match analyze(&source, &mut interner, &args) {
+				Ok(program) => {
+					if let Some(cache) = &cache {
+						// Failing to write the cache is not an error, the script just runs slower.
+						let _ = std::fs::write(cache, binary::encode(&program, &interner, source_hash));
+					}
+
+					(program, source_hash)
+				}
+
+				Err(status) => return (status, None),
+			},
+		}
+	};
+
+	if let Some(output) = &args.compile {
+		let compiled = binary::encode(&program, &interner, source_hash);
+
+		let status = match std::fs::write(output, compiled) {
+			Ok(()) => ExitStatus::Success,
+			Err(error) => {
+				let pos = SourcePos::file(interner.get_or_intern(output.as_os_str().as_bytes()));
+				report_panic(&Panic::io(error, pos), &interner, args.diagnostics);
+				ExitStatus::Panic
+			}
+		};
+
+		return (status, None);
+	}
+
+
+	let mut runtime = Runtime::new(
+		args.script_args.into_vec(), // Use vec's owned iterator.
+		interner
+	);
+
+	runtime.set_max_call_depth(MAX_CALL_DEPTH);
+
+	if args.tree_walker {
+		runtime.set_interpreter(Interpreter::TreeWalker);
+	}
+
+	runtime.set_module_path(module_path(&args.module_path));
+
+	// Without the system feature, the runtime only uses the streams it's given.
+	#[cfg(not(feature = "system"))]
+	{
+		runtime.set_stdin(std::io::stdin());
+		runtime.set_stdout(std::io::stdout());
+		runtime.set_stderr(std::io::stderr());
+	}
+
+	// The script consumed stdin, so input must come from the terminal, if any.
+	if matches!(args.script, Script::Stdin | Script::None) {
+		match io::open_tty() {
+			Ok(tty) => runtime.set_stdin(tty),
+			Err(_) => runtime.set_stdin(io::ConsumedStdin),
+		}
+	}
+
+	if args.debug {
+		let source = Some(source.contents.as_ref()).filter(|source| !binary::is_compiled(source));
+
+		// Commands are read from stdin, unless the script was read from it.
+		let attached = match args.script {
+			Script::Stdin | Script::None => io::open_tty().and_then(
+				|tty| Debugger::attach(&mut runtime, path, source, std::io::BufReader::new(tty), std::io::stderr())
+			),
+			_ => Debugger::attach(&mut runtime, path, source, std::io::BufReader::new(std::io::stdin()), std::io::stderr()),
+		};
+
+		if let Err(error) = attached {
+			report_panic(&Panic::io(error, SourcePos::file(path)), runtime.interner(), args.diagnostics);
+			return (ExitStatus::Panic, None);
+		}
+	}
+
+	let profiler = if args.profile {
+		Some(Profiler::attach(&mut runtime))
+	} else {
+		None
+	};
+
+	let run_tests = args.test;
+	let result = runtime
+		.eval(program)
+		.and_then(
+			|value| if run_tests {
+				runtime
+					.run_tests(SourcePos::file(path))
+					.map(|failures| (value, failures))
+			} else {
+				Ok((value, 0))
+			}
+		);
+
+	let exit_status = match result {
+    Ok((_, 0)) if !args.status_from_result => ExitStatus::Success,
+    Ok((value, 0)) => ExitStatus::from_result(&value),
+    Ok(_) => ExitStatus::TestFailure,
+    Err(panic) => match panic.exit_status() {
+			// std.exit is not reported, as it's not a failure.
+			Some(status) => ExitStatus::Exit(status),
+			None => {
+				report_panic(&panic, runtime.interner(), args.diagnostics);
+				match panic.kind() {
+					PanicKind::InternalError => ExitStatus::InternalError,
+					_ => ExitStatus::Panic,
+				}
+			}
+		}
+	};
+
+	let exit_status = match profiler {
+		Some(profiler) => report_profile(&profiler, args.profile_out.as_deref(), runtime.interner_mut(), args.diagnostics).unwrap_or(exit_status),
+		None => exit_status,
+	};
+
+	(exit_status, Some(runtime))
+}
+
+
+/// Print the profile to stderr, and write it as JSON to the given output, if any. Returns
+/// the exit status if writing fails.
+fn report_profile(
+	profiler: &Profiler,
+	output: Option<&Path>,
+	interner: &mut symbol::Interner,
+	format: diagnostic::Format,
+) -> Option<ExitStatus> {
+	let report = profiler.report();
+
+	// Failing to print the table is not an error, as stderr may be closed.
+	let _ = report.write_table(&mut std::io::stderr().lock(), interner);
+
+	let output = output?;
+	let json = report.to_json(interner).to_string();
+
+	match std::fs::write(output, json + "\n") {
+		Ok(()) => None,
+		Err(error) => {
+			let pos = SourcePos::file(interner.get_or_intern(output.as_os_str().as_bytes()));
+			report_panic(&Panic::io(error, pos), interner, format);
+			Some(ExitStatus::Panic)
+		}
+	}
+}
+
+
+
+
+/// Check each script with static analysis, reporting diagnostics, without executing any.
+/// The exit status is the one of the first script that fails, if any.
+fn check(args: &Args, mut interner: symbol::Interner) -> ExitStatus {
+	let mut exit_status = ExitStatus::Success;
+
+	for script in args.paths.iter() {
+		let (source, path) = read_script(script, &mut interner);
+
+		let status = match source {
+			Ok(source) if binary::is_compiled(&source.contents) => {
+				match load_compiled(&source.contents, &mut interner) {
+					Ok(_) => ExitStatus::Success,
+					Err(error) => {
+						let error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+						report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+						ExitStatus::Panic
+					}
+				}
+			}
+
+			// Analysis stops before returning the program when checking.
+			Ok(source) => analyze(&source, &mut interner, args)
+				.err()
+				.unwrap_or(ExitStatus::Success),
+
+			Err(error) => {
+				report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+				ExitStatus::Panic
+			}
+		};
+
+		if exit_status == ExitStatus::Success {
+			exit_status = status;
+		}
+	}
+
+	exit_status
+}
+
+
+/// Format each script, printing it to stdout, or writing it in place with `--write`. With
+/// `--check`, scripts are only reported if formatting would change them. The exit status
+/// is the one of the first script that fails, if any.
+fn format(args: &Args, mut interner: symbol::Interner) -> ExitStatus {
+	let config = syntax::format::Config { width: args.width };
+	let mut exit_status = ExitStatus::Success;
+
+	for script in args.paths.iter() {
+		let (source, path) = read_script(script, &mut interner);
+
+		let source = match source {
+			Ok(source) => source,
+			Err(error) => {
+				report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+				exit_status = ExitStatus::Panic;
+				continue;
+			}
+		};
+
+		let status = match syntax::format::format(&source, &mut interner, config) {
+			Ok(formatted) if args.check => {
+				if formatted == source.contents.as_ref() {
+					ExitStatus::Success
+				} else {
+					eprintln!(
+						"{}: {} is not formatted",
+						color::Fg(color::Red, "Error"),
+						String::from_utf8_lossy(interner.resolve(path).unwrap_or_default()),
+					);
+					ExitStatus::Unformatted
+				}
+			}
+
+			Ok(formatted) => {
+				let written = if args.write && script.as_os_str() != "-" {
+					if formatted == source.contents.as_ref() {
+						Ok(())
+					} else {
+						std::fs::write(script, formatted)
+					}
+				} else {
+					std::io::Write::write_all(&mut std::io::stdout().lock(), &formatted)
+				};
+
+				match written {
+					Ok(()) => ExitStatus::Success,
+					Err(error) => {
+						report_panic(&Panic::io(error, SourcePos::file(path)), &interner, args.diagnostics);
+						ExitStatus::Panic
+					}
+				}
+			}
+
+			Err(errors) => {
+				report_syntax_errors(&errors, &interner, args.diagnostics);
+				ExitStatus::SyntaxError
+			}
+		};
+
+		if exit_status == ExitStatus::Success {
+			exit_status = status;
+		}
+	}
+
+	exit_status
+}
+
+
+/// Read a script given by path, where `-` is stdin. Returns the source along with its path.
+fn read_script(
+	script: &Path,
+	interner: &mut symbol::Interner,
+) -> (std::io::Result<syntax::Source>, symbol::Symbol) {
+	if script.as_os_str() == "-" {
+		let path = interner.get_or_intern("<stdin>");
+		(syntax::Source::from_reader(path, std::io::stdin().lock()), path)
+	} else {
+		let path = interner.get_or_intern(script.as_os_str().as_bytes());
+		(syntax::Source::from_path(path, interner), path)
+	}
+}
+
+
+/// The directories searched by std.import: those given with `--module-path`, followed by
+/// those in the HUSH_PATH environment variable, separated as in PATH.
+fn module_path(dirs: &[PathBuf]) -> Vec<PathBuf> {
+	let env = std::env::var_os("HUSH_PATH").unwrap_or_default();
+
+	dirs
+		.iter()
+		.cloned()
+		.chain(std::env::split_paths(&env))
+		.filter(|dir| !dir.as_os_str().is_empty())
+		.collect()
+}
+
+
+/// Run the interactive mode, until the end of stdin.
+fn repl(args: Args, interner: symbol::Interner) -> ExitStatus {
+	let mut runtime = Runtime::new(args.script_args.into_vec(), interner);
+
+	runtime.set_max_call_depth(MAX_CALL_DEPTH);
+
+	if args.tree_walker {
+		runtime.set_interpreter(Interpreter::TreeWalker);
+	}
+
+	runtime.set_module_path(module_path(&args.module_path));
+
+	#[cfg(not(feature = "system"))]
+	{
+		runtime.set_stdin(std::io::stdin());
+		runtime.set_stdout(std::io::stdout());
+		runtime.set_stderr(std::io::stderr());
+	}
+
+	let mut repl = repl::Repl::new(runtime);
+
+	#[cfg(feature = "line-editing")]
+	{
+		let history = repl::history::path(|name| std::env::var_os(name));
+
+		if let Err(error) = repl.enable_line_editing(history) {
+			eprintln!("{}: failed to load history: {}", color::Fg(color::Red, "Error"), error);
+		}
+	}
+
+	match repl.run() {
+		Ok(None) => ExitStatus::Success,
+		Ok(Some(status)) => ExitStatus::Exit(status),
+		Err(error) => {
+			eprintln!("{}: {}", color::Fg(color::Red, "Error"), error);
+			ExitStatus::Panic
+		}
+	}
+}
+
+
+/// Whether stdin is a terminal, in which case the interactive mode is used when no script
+/// is given.
+#[cfg(feature = "system")]
+fn stdin_is_tty() -> bool {
+	termion::is_tty(&std::io::stdin())
+}
+
+
+/// Without the system feature, scripts are always read from stdin when not given.
+#[cfg(not(feature = "system"))]
+fn stdin_is_tty() -> bool {
+	false
+}
+
+
+/// Analyze the source code, reporting errors and warnings. Returns the exit status instead
+/// of the program if execution should not proceed.
+fn analyze(
+	source: &syntax::Source,
+	interner: &mut symbol::Interner,
+	args: &Args,
+) -> Result<semantic::program::Program, ExitStatus> {
+	if args.dump_tokens {
+		return Err(dump_tokens(source, interner));
+	}
+
 	// ----------------------------------------------------------------------------------------
-	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	let syntactic_analysis = syntax::Analysis::analyze(source, interner);
 	let has_syntax_errors = !syntactic_analysis.is_ok();
 
 	if has_syntax_errors {
-		eprint!("{}", fmt::Show(
-			syntactic_analysis.errors,
-			syntax::AnalysisDisplayContext {
-				max_errors: Some(20),
-				interner: &interner,
-			}
-		));
+		report_syntax_errors(&syntactic_analysis.errors, interner, args.diagnostics);
 	}
 
 	if args.print_lexemes {
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
 
-		let cursor = syntax::lexer::Cursor::from(&source);
-		let results: Vec<_> = syntax::lexer::Lexer::new(cursor, &mut interner).collect();
+		let cursor = syntax::lexer::Cursor::from(source);
+		let results: Vec<_> = syntax::lexer::Lexer::new(cursor, interner).collect();
 
 		for result in results {
 			match result {
-				Ok(token) => println!("{}", fmt::Show(&token, &interner)),
-				Err(error) => println!("{}: {}", color::Fg(color::Red, "Error"), error)
+				Ok(token) => println!("{}", fmt::Show(&token, &*interner)),
+				Err(error) => eprintln!("{}: {}", color::Fg(color::Red, "Error"), error)
 			}
 		}
 
@@ -128,24 +698,41 @@ fn run(args: Args) -> ExitStatus {
 			"{}",
 			fmt::Show(
 				&syntactic_analysis.ast,
-				syntax::ast::fmt::Context::from(&interner)
+				syntax::ast::fmt::Context::from(&*interner)
 			)
 		);
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
 	}
 
 	// ----------------------------------------------------------------------------------------
-	let program = match semantic::Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
-		Ok(program) => program,
+	let (program, mut warnings) = match semantic::Analyzer::analyze(syntactic_analysis.ast, interner) {
+		Ok(result) => result,
 		Err(errors) => {
-			eprint!("{}", fmt::Show(
-				errors,
-				semantic::ErrorsDisplayContext {
-					max_errors: Some(20),
-					interner: &interner,
+			match args.diagnostics {
+				diagnostic::Format::Human => eprint!("{}", fmt::Show(
+					errors,
+					semantic::ErrorsDisplayContext {
+						max_errors: Some(20),
+						interner: &*interner,
+					}
+				)),
+
+				diagnostic::Format::Json => report_json(
+					errors.0
+						.iter()
+						.map(|error| Diagnostic::semantic(error, &*interner)),
+					&*interner,
+				),
+			}
+
+			// Syntax errors take precedence, as they may cause semantic errors.
+			return Err(
+				if has_syntax_errors {
+					ExitStatus::SyntaxError
+				} else {
+					ExitStatus::SemanticError
 				}
-			));
-			return ExitStatus::StaticError;
+			);
 		}
 	};
 
@@ -155,7 +742,7 @@ fn run(args: Args) -> ExitStatus {
 			"{}",
 			fmt::Show(
 				&program,
-				semantic::program::fmt::Context::from(&interner)
+				semantic::program::fmt::Context::from(&*interner)
 			)
 		);
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
@@ -163,24 +750,166 @@ fn run(args: Args) -> ExitStatus {
 
 	// ----------------------------------------------------------------------------------------
 	if has_syntax_errors {
-		return ExitStatus::StaticError;
+		return Err(ExitStatus::SyntaxError);
+	}
+
+	if args.allow_shadowing {
+		warnings.0.retain(
+			|warning| !matches!(warning.kind, semantic::WarningKind::ShadowedOuterVariable(..))
+		);
+	}
+
+	if !args.no_warnings {
+		let mut sink = diagnostic::Sink::new(std::io::stderr(), args.diagnostics, args.max_warnings);
+
+		for warning in warnings.0.iter() {
+			sink.warning(warning, &*interner);
+		}
+
+		sink.finish();
+
+		if args.deny_warnings && sink.count() > 0 {
+			if args.diagnostics == diagnostic::Format::Human {
+				eprintln!(
+					"{}: {} warnings denied by --deny-warnings",
+					color::Fg(color::Red, "Error"),
+					sink.count(),
+				);
+			}
+
+			return Err(ExitStatus::SemanticError);
+		}
+	}
+
+	if args.dump_ast {
+		println!(
+			"{}",
+			fmt::Show(
+				&program,
+				semantic::program::fmt::Context::from(&*interner)
+			)
+		);
+		return Err(ExitStatus::Success);
 	}
 
 	if args.check {
-		return ExitStatus::Success;
+		return Err(ExitStatus::Success);
 	}
 
-	let program = Box::leak(Box::new(program));
-	let mut runtime = Runtime::new(
-		args.script_args.into_vec(), // Use vec's owned iterator.
-		interner
-	);
+	Ok(program)
+}
 
-	match runtime.eval(program) {
-    Ok(_) => ExitStatus::Success,
-    Err(panic) => {
-			eprintln!("{}", fmt::Show(panic, runtime.interner()));
-			ExitStatus::Panic
+
+/// Load a compiled program. The source code is made available for error snippets if it's
+/// still in place and unchanged.
+fn load_compiled(
+	contents: &[u8],
+	interner: &mut symbol::Interner,
+) -> Result<(binary::Header, semantic::program::Program), binary::DecodeError> {
+	let (header, program) = binary::decode(contents, interner)?;
+
+	let source = interner
+		.resolve(program.source)
+		.map(|path| std::fs::read(std::ffi::OsStr::from_bytes(path)));
+
+	if let Some(Ok(source)) = source {
+		if binary::hash(&source) == header.source_hash {
+			interner.add_source(program.source, &source);
+		}
+	}
+
+	Ok((header, program))
+}
+
+
+/// Load the cached program for the source code, if it exists and is up to date.
+fn load_cached(
+	cache: &Path,
+	source: &syntax::Source,
+	source_hash: u64,
+	interner: &mut symbol::Interner,
+) -> Option<semantic::program::Program> {
+	let contents = std::fs::read(cache).ok()?;
+
+	// Check the header first, so that stale programs are not decoded.
+	if binary::header(&contents).ok()?.source_hash != source_hash {
+		return None;
+	}
+
+	let (_, program) = binary::decode(&contents, interner).ok()?;
+	interner.add_source(program.source, &source.contents);
+
+	Some(program)
+}
+
+
+/// Report syntax errors to stderr.
+fn report_syntax_errors(
+	errors: &syntax::Errors,
+	interner: &symbol::Interner,
+	format: diagnostic::Format,
+) {
+	match format {
+		diagnostic::Format::Human => eprint!("{}", fmt::Show(
+			errors,
+			syntax::AnalysisDisplayContext {
+				max_errors: Some(20),
+				interner,
+			}
+		)),
+
+		diagnostic::Format::Json => report_json(
+			errors.0
+				.iter()
+				.map(|error| Diagnostic::syntax(error, interner)),
+			interner,
+		),
+	}
+}
+
+
+/// Report a panic to stderr.
+fn report_panic(panic: &Panic, interner: &symbol::Interner, format: diagnostic::Format) {
+	match format {
+		diagnostic::Format::Human => eprintln!("{}", fmt::Show(panic, interner)),
+		diagnostic::Format::Json => report_json(
+			std::iter::once(Diagnostic::panic(panic, interner)),
+			interner,
+		),
+	}
+}
+
+
+/// Print each diagnostic as a line of JSON to stderr.
+fn report_json<I>(diagnostics: I, interner: &symbol::Interner)
+where
+	I: IntoIterator<Item = Diagnostic>,
+{
+	for diagnostic in diagnostics {
+		eprintln!("{}", diagnostic.to_json(interner));
+	}
+}
+
+
+/// Print each token with its kind and position. Lexical errors are printed to stderr.
+fn dump_tokens(source: &syntax::Source, interner: &mut symbol::Interner) -> ExitStatus {
+	// Make the source available for error snippets.
+	interner.add_source(source.path, &source.contents);
+
+	let cursor = syntax::lexer::Cursor::from(source);
+	let results: Vec<_> = syntax::lexer::Lexer::new(cursor, interner).collect();
+
+	let mut status = ExitStatus::Success;
+
+	for result in results {
+		match result {
+			Ok(token) => println!("{}", fmt::Show(&token, &*interner)),
+			Err(error) => {
+				eprintln!("{}: {}", color::Fg(color::Red, "Error"), fmt::Show(&error, &*interner));
+				status = ExitStatus::SyntaxError;
+			}
 		}
 	}
+
+	status
 }
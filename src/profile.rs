@@ -0,0 +1,254 @@
+//! A profiler built on the call hook of the runtime, which accumulates the time spent in
+//! each function and command block.
+
+use std::{
+	collections::HashMap,
+	io::{self, Write},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+	fmt::{self, Display, FmtString},
+	runtime::{value::Function, CallEvent, Runtime, SourcePos},
+	symbol,
+	term::color,
+};
+
+
+/// What is being timed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Target {
+	/// A function defined in a script, identified by the position of its definition.
+	Function(SourcePos),
+	/// A native function, identified by its name.
+	Native(&'static str),
+	/// A command block, identified by its position.
+	CommandBlock(SourcePos),
+}
+
+
+impl Target {
+	fn kind(&self) -> &'static str {
+		match self {
+			Self::Function(_) | Self::Native(_) => "function",
+			Self::CommandBlock(_) => "command",
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Target {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Function(pos) => write!(f, "function<{}>", fmt::Show(pos, context)),
+			Self::Native(name) => write!(f, "{}", name),
+			Self::CommandBlock(pos) => write!(f, "command<{}>", fmt::Show(pos, context)),
+		}
+	}
+}
+
+
+/// The accumulated measures of a target.
+#[derive(Debug, Default)]
+struct Entry {
+	calls: u64,
+	/// Time spent in the target, including the targets it called.
+	inclusive: Duration,
+	/// Time spent in the target itself.
+	exclusive: Duration,
+	/// How many calls of the target are in progress, as recursive calls must not be counted
+	/// twice in the inclusive time.
+	active: usize,
+}
+
+
+/// A call in progress.
+#[derive(Debug)]
+struct Activation {
+	target: Target,
+	start: Instant,
+	/// Time spent in the targets it called so far.
+	children: Duration,
+}
+
+
+#[derive(Debug)]
+struct State {
+	start: Instant,
+	entries: HashMap<Target, Entry>,
+	stack: Vec<Activation>,
+}
+
+
+impl State {
+	fn enter(&mut self, target: Target) {
+		self.entries.entry(target.clone()).or_default().active += 1;
+		self.stack.push(Activation { target, start: Instant::now(), children: Duration::ZERO });
+	}
+
+
+	fn exit(&mut self) {
+		let activation = match self.stack.pop() {
+			Some(activation) => activation,
+			None => return,
+		};
+
+		let elapsed = activation.start.elapsed();
+
+		if let Some(caller) = self.stack.last_mut() {
+			caller.children += elapsed;
+		}
+
+		let entry = self.entries.entry(activation.target).or_default();
+		entry.calls += 1;
+		entry.active -= 1;
+		entry.exclusive += elapsed.saturating_sub(activation.children);
+
+		if entry.active == 0 {
+			entry.inclusive += elapsed;
+		}
+	}
+}
+
+
+/// Profiles the calls of a runtime.
+#[derive(Debug, Clone)]
+pub struct Profiler(Arc<Mutex<State>>);
+
+
+impl Profiler {
+	/// Start profiling the given runtime, replacing its call hook.
+	pub fn attach(runtime: &mut Runtime) -> Self {
+		let state = Arc::new(
+			Mutex::new(
+				State { start: Instant::now(), entries: HashMap::new(), stack: Vec::new() }
+			)
+		);
+
+		let hook_state = state.clone();
+		runtime.set_call_hook(
+			move |event| {
+				let mut state = hook_state.lock().unwrap_or_else(|error| error.into_inner());
+
+				match event {
+					CallEvent::Call { function: Function::Hush(function), .. } => {
+						state.enter(Target::Function(function.pos.copy()))
+					}
+					CallEvent::Call { function: Function::Rust(function), .. } => {
+						state.enter(Target::Native(function.name()))
+					}
+					CallEvent::CommandBlock { pos } => state.enter(Target::CommandBlock(pos.copy())),
+					CallEvent::Return => state.exit(),
+				}
+			}
+		);
+
+		Self(state)
+	}
+
+
+	/// The measures so far, from the target with the most exclusive time to the least.
+	pub fn report(&self) -> Report {
+		let state = self.0.lock().unwrap_or_else(|error| error.into_inner());
+
+		let mut entries: Vec<(Target, Measures)> = state.entries
+			.iter()
+			.filter(|(_, entry)| entry.calls > 0)
+			.map(
+				|(target, entry)| (
+					target.clone(),
+					Measures { calls: entry.calls, inclusive: entry.inclusive, exclusive: entry.exclusive },
+				)
+			)
+			.collect();
+
+		entries.sort_by(
+			|(target, measures), (other_target, other)| other.exclusive
+				.cmp(&measures.exclusive)
+				.then_with(|| target.kind().cmp(other_target.kind()))
+		);
+
+		Report { total: state.start.elapsed(), entries }
+	}
+}
+
+
+/// The measures of a target in a report.
+#[derive(Debug)]
+struct Measures {
+	calls: u64,
+	inclusive: Duration,
+	exclusive: Duration,
+}
+
+
+/// A snapshot of the measures of a profiler.
+#[derive(Debug)]
+pub struct Report {
+	/// Time since profiling started.
+	total: Duration,
+	entries: Vec<(Target, Measures)>,
+}
+
+
+impl Report {
+	/// Write the report as a table, one target per line.
+	pub fn write_table(&self, out: &mut dyn Write, interner: &symbol::Interner) -> io::Result<()> {
+		let millis = |duration: Duration| format!("{:.3} ms", duration.as_secs_f64() * 1000.0);
+
+		writeln!(out, "{}: {} total", color::Fg(color::Yellow, "Profile"), millis(self.total))?;
+		writeln!(out, "{:>10} {:>14} {:>14}  target", "calls", "inclusive", "exclusive")?;
+
+		for (target, measures) in self.entries.iter() {
+			writeln!(
+				out,
+				"{:>10} {:>14} {:>14}  {}",
+				measures.calls,
+				millis(measures.inclusive),
+				millis(measures.exclusive),
+				fmt::Show(target, interner),
+			)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Serialize the report as JSON, with times in milliseconds.
+	pub fn to_json(&self, interner: &symbol::Interner) -> serde_json::Value {
+		let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+		let entries: Vec<serde_json::Value> = self.entries
+			.iter()
+			.map(
+				|(target, measures)| {
+					let mut object = json!({
+						"name": color::plain(|| target.fmt_string(interner)),
+						"kind": target.kind(),
+						"calls": measures.calls,
+						"inclusive_ms": millis(measures.inclusive),
+						"exclusive_ms": millis(measures.exclusive),
+					});
+
+					if let Target::Function(pos) | Target::CommandBlock(pos) = target {
+						object["file"] = color::plain(|| pos.path.fmt_string(interner)).into();
+						object["line"] = pos.line.into();
+						object["column"] = pos.column.into();
+					}
+
+					object
+				}
+			)
+			.collect();
+
+		json!({
+			"total_ms": millis(self.total),
+			"entries": entries,
+		})
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{runtime, syntax::lexer::Keyword};
+
+
+/// Words that are not keywords, but are completed as such.
+const RESERVED: [&str; 6] = [ "nil", "true", "false", "and", "or", "not" ];
+
+
+/// Find the completions for the word before the cursor position in the line, returning
+/// the start of the word and the sorted candidates. Members of `std` are completed after
+/// `std.`, and keywords and the given global names elsewhere.
+pub fn candidates<'a, G>(line: &str, pos: usize, globals: G) -> (usize, Vec<String>)
+where
+	G: IntoIterator<Item = &'a str>,
+{
+	let start = line[.. pos]
+		.char_indices()
+		.rev()
+		.take_while(|(_, c)| is_word(*c))
+		.last()
+		.map_or(pos, |(ix, _)| ix);
+
+	let prefix = &line[start .. pos];
+	let before = &line[.. start];
+
+	let mut candidates: Vec<String> = match before.strip_suffix('.') {
+		Some(object) if ends_with_word(object, "std") => runtime::std_members()
+			.filter(|member| member.starts_with(prefix))
+			.map(Into::into)
+			.collect(),
+
+		// Fields of other values are only known at runtime.
+		Some(_) => Vec::new(),
+
+		None => globals
+			.into_iter()
+			.chain(Keyword::ALL.iter().map(|keyword| keyword.as_str()))
+			.chain(RESERVED.iter().copied())
+			.filter(|word| word.starts_with(prefix))
+			.map(Into::into)
+			.collect(),
+	};
+
+	candidates.sort();
+	candidates.dedup();
+
+	(start, candidates)
+}
+
+
+/// Whether the character may be part of an identifier.
+fn is_word(c: char) -> bool {
+	c.is_alphanumeric() || c == '_'
+}
+
+
+/// Whether the text ends with the given word, rather than a word containing it.
+fn ends_with_word(text: &str, word: &str) -> bool {
+	text
+		.strip_suffix(word)
+		.is_some_and(|rest| !rest.chars().next_back().is_some_and(is_word))
+}
@@ -0,0 +1,136 @@
+use std::{io, path::PathBuf};
+
+use rustyline::{
+	completion::Completer,
+	config::{Config, EditMode},
+	error::ReadlineError,
+	highlight::Highlighter,
+	hint::Hinter,
+	validate::Validator,
+	Context,
+	Helper,
+};
+
+use super::{complete, history};
+
+
+/// A line editor with emacs-style keys, history search and completion.
+pub struct Editor {
+	editor: rustyline::Editor<Completion>,
+	/// Where the history is saved, if anywhere.
+	history: Option<PathBuf>,
+}
+
+
+impl Editor {
+	/// Create an editor, loading the history from the given file.
+	pub fn new(history: Option<PathBuf>) -> io::Result<Self> {
+		let config = Config::builder()
+			.edit_mode(EditMode::Emacs)
+			.auto_add_history(false)
+			.max_history_size(history::MAX_ENTRIES)
+			.build();
+
+		let mut editor = rustyline::Editor::with_config(config);
+		editor.set_helper(Some(Completion::default()));
+
+		if let Some(path) = &history {
+			for entry in history::load(path)? {
+				editor.add_history_entry(entry);
+			}
+		}
+
+		Ok(Self { editor, history })
+	}
+
+
+	/// Read a line after showing the prompt, appending it to the input along with a line
+	/// terminator. Interrupting discards the input. Returns false at the end of input.
+	pub fn read_line(&mut self, prompt: &str, input: &mut String) -> io::Result<bool> {
+		match self.editor.readline(prompt) {
+			Ok(line) => {
+				input.push_str(&line);
+				input.push('\n');
+				Ok(true)
+			}
+
+			Err(ReadlineError::Interrupted) => {
+				input.clear();
+				Ok(true)
+			}
+
+			Err(ReadlineError::Eof) => Ok(false),
+
+			Err(ReadlineError::Io(error)) => Err(error),
+
+			Err(error) => Err(io::Error::other(error)),
+		}
+	}
+
+
+	/// Set the global names offered for completion.
+	pub fn set_globals(&mut self, globals: Vec<String>) {
+		if let Some(completion) = self.editor.helper_mut() {
+			completion.globals = globals;
+		}
+	}
+
+
+	/// Add a complete input to the history.
+	pub fn add_history(&mut self, input: &str) {
+		self.editor.add_history_entry(input.trim_end());
+	}
+
+
+	/// Save the history to its file, if any.
+	pub fn save_history(&self) -> io::Result<()> {
+		match &self.history {
+			Some(path) => history::save(path, self.editor.history().iter().map(String::as_str)),
+			None => Ok(()),
+		}
+	}
+}
+
+
+impl std::fmt::Debug for Editor {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Editor")
+			.field("history", &self.history)
+			.finish()
+	}
+}
+
+
+/// Completes keywords, global names and members of `std`.
+#[derive(Debug, Default)]
+struct Completion {
+	globals: Vec<String>,
+}
+
+
+impl Completer for Completion {
+	type Candidate = String;
+
+	fn complete(
+		&self,
+		line: &str,
+		pos: usize,
+		_: &Context,
+	) -> rustyline::Result<(usize, Vec<String>)> {
+		Ok(complete::candidates(line, pos, self.globals.iter().map(String::as_str)))
+	}
+}
+
+
+impl Hinter for Completion {
+	type Hint = String;
+}
+
+
+impl Highlighter for Completion { }
+
+
+impl Validator for Completion { }
+
+
+impl Helper for Completion { }
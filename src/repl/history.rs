@@ -0,0 +1,115 @@
+use std::{
+	ffi::OsString,
+	fs,
+	io,
+	path::{Path, PathBuf},
+};
+
+
+/// The maximum number of entries kept in the history file.
+pub const MAX_ENTRIES: usize = 1000;
+
+
+/// The path of the history file, given a lookup for environment variables. This is
+/// `$XDG_DATA_HOME/hush/history` if set, and `~/.hush_history` otherwise. Relative XDG
+/// paths are ignored, as mandated by the specification.
+pub fn path<F>(var: F) -> Option<PathBuf>
+where
+	F: Fn(&str) -> Option<OsString>,
+{
+	let non_empty = |name| var(name).filter(|value| !value.is_empty());
+
+	let xdg = non_empty("XDG_DATA_HOME")
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute());
+
+	match xdg {
+		Some(mut path) => {
+			path.push("hush");
+			path.push("history");
+			Some(path)
+		}
+
+		None => non_empty("HOME").map(
+			|home| {
+				let mut path = PathBuf::from(home);
+				path.push(".hush_history");
+				path
+			}
+		),
+	}
+}
+
+
+/// Load the entries of the history file, oldest first. A missing file has no entries.
+pub fn load(path: &Path) -> io::Result<Vec<String>> {
+	let contents = match fs::read(path) {
+		Ok(contents) => contents,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error),
+	};
+
+	Ok(
+		String::from_utf8_lossy(&contents)
+			.lines()
+			.filter(|line| !line.is_empty())
+			.map(unescape)
+			.collect()
+	)
+}
+
+
+/// Save the latest entries to the history file, creating its directory if needed.
+/// Multi-line entries are escaped to a single line.
+pub fn save<'a, I>(path: &Path, entries: I) -> io::Result<()>
+where
+	I: IntoIterator<Item = &'a str>,
+{
+	let entries: Vec<&str> = entries.into_iter().collect();
+	let skip = entries.len().saturating_sub(MAX_ENTRIES);
+
+	let mut contents = String::new();
+	for entry in &entries[skip ..] {
+		contents.push_str(&escape(entry));
+		contents.push('\n');
+	}
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+
+	fs::write(path, contents)
+}
+
+
+/// Escape newlines and backslashes.
+fn escape(entry: &str) -> String {
+	entry
+		.replace('\\', "\\\\")
+		.replace('\n', "\\n")
+}
+
+
+/// Reverse the escaping of an entry.
+fn unescape(line: &str) -> String {
+	let mut entry = String::with_capacity(line.len());
+	let mut chars = line.chars();
+
+	while let Some(c) = chars.next() {
+		match (c, chars.clone().next()) {
+			('\\', Some('n')) => {
+				entry.push('\n');
+				chars.next();
+			}
+
+			('\\', Some('\\')) => {
+				entry.push('\\');
+				chars.next();
+			}
+
+			(c, _) => entry.push(c),
+		}
+	}
+
+	entry
+}
@@ -0,0 +1,240 @@
+//! The interactive mode, which reads, evaluates and prints each input in a persistent
+//! session, so that later inputs may use the variables and functions of previous ones.
+
+pub mod complete;
+#[cfg(feature = "line-editing")]
+mod editor;
+pub mod history;
+
+use std::io;
+
+use crate::{
+	engine::Error,
+	fmt,
+	runtime::{value::Value, Runtime},
+	semantic,
+	symbol::{self, Symbol},
+	syntax,
+};
+
+
+/// The outcome of compiling an input.
+#[derive(Debug)]
+enum Input {
+	/// The input is well formed.
+	Complete(semantic::program::Program),
+	/// The input ends inside an open construct, and may be completed by more lines.
+	Incomplete,
+}
+
+
+/// Reads inputs from the stdin of the runtime, writing prompts and results to its stdout,
+/// and errors to its stderr.
+#[derive(Debug)]
+pub struct Repl {
+	runtime: Runtime,
+	session: semantic::Session,
+	/// The number of evaluated inputs.
+	inputs: usize,
+	/// Reads lines from the terminal instead of the stdin of the runtime, if enabled.
+	#[cfg(feature = "line-editing")]
+	editor: Option<editor::Editor>,
+}
+
+
+impl Repl {
+	/// The prompt for a new input.
+	pub const PROMPT: &'static str = ">> ";
+	/// The prompt for the continuation of an incomplete input.
+	pub const CONTINUATION: &'static str = ".. ";
+
+
+	/// Create an interactive session in the given runtime.
+	pub fn new(mut runtime: Runtime) -> Self {
+		let session = semantic::Session::new(runtime.interner_mut());
+
+		Self {
+			runtime,
+			session,
+			inputs: 0,
+			#[cfg(feature = "line-editing")]
+			editor: None,
+		}
+	}
+
+
+	/// Read lines from the terminal with a line editor, instead of the stdin of the runtime.
+	/// The history is loaded from the given file, and saved back at the end of the session.
+	#[cfg(feature = "line-editing")]
+	pub fn enable_line_editing(&mut self, history: Option<std::path::PathBuf>) -> io::Result<()> {
+		self.editor = Some(editor::Editor::new(history)?);
+		Ok(())
+	}
+
+
+	/// Read, evaluate and print inputs until the end of stdin, or until an input calls
+	/// std.exit, in which case its status is returned. Errors and panics are reported, and
+	/// the session goes on. Only IO errors of the runtime's streams stop it.
+	pub fn run(&mut self) -> io::Result<Option<u8>> {
+		let mut input = String::new();
+
+		loop {
+			let prompt = if input.is_empty() { Self::PROMPT } else { Self::CONTINUATION };
+
+			if !self.read_line(prompt, &mut input)? {
+				// End the prompt's line.
+				self.runtime.with_stdout(|out| writeln!(out))?;
+
+				// Report an unfinished input, which can no longer be completed.
+				if !input.trim().is_empty() {
+					if let Err(error) = self.compile(&input, true) {
+						self.report(&error)?;
+					}
+				}
+
+				self.save_history()?;
+
+				return Ok(None);
+			}
+
+			if input.trim().is_empty() {
+				input.clear();
+				continue;
+			}
+
+			let result = match self.compile(&input, false) {
+				Ok(Input::Incomplete) => continue,
+				Ok(Input::Complete(program)) => self.runtime
+					.eval_session(program)
+					.map_err(Error::Panic),
+				Err(error) => Err(error),
+			};
+
+			#[cfg(feature = "line-editing")]
+			if let Some(editor) = &mut self.editor {
+				editor.add_history(&input);
+			}
+
+			input.clear();
+
+			match result {
+				Ok(Value::Nil) => (),
+				Ok(value) => {
+					let interner = self.runtime.interner();
+					self.runtime.with_stdout(|out| writeln!(out, "{}", fmt::Show(value, interner)))?
+				}
+				Err(Error::Panic(panic)) if panic.exit_status().is_some() => {
+					self.save_history()?;
+					return Ok(panic.exit_status());
+				}
+				Err(error) => self.report(&error)?,
+			}
+		}
+	}
+
+
+	/// The names of the variables declared by previous inputs, sorted.
+	pub fn globals(&self) -> Vec<String> {
+		globals(&self.session, self.runtime.interner())
+	}
+
+
+	/// Get the runtime, for instance to register native functions.
+	pub fn runtime(&mut self) -> &mut Runtime {
+		&mut self.runtime
+	}
+
+
+	/// Save the history of the line editor, if enabled.
+	fn save_history(&self) -> io::Result<()> {
+		#[cfg(feature = "line-editing")]
+		if let Some(editor) = &self.editor {
+			editor.save_history()?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Read a line after showing the prompt, appending it to the input. Returns false at
+	/// the end of input.
+	fn read_line(&mut self, prompt: &str, input: &mut String) -> io::Result<bool> {
+		#[cfg(feature = "line-editing")]
+		if let Some(editor) = &mut self.editor {
+			editor.set_globals(globals(&self.session, self.runtime.interner()));
+			return editor.read_line(prompt, input);
+		}
+
+		self.runtime.with_stdout(
+			|out| {
+				out.write_all(prompt.as_bytes())?;
+				out.flush()
+			}
+		)?;
+
+		Ok(self.runtime.read_line(input)? != 0)
+	}
+
+
+	/// Compile an input in the session. Inputs that end inside an open construct are
+	/// reported as incomplete, unless at the end of stdin.
+	fn compile(&mut self, input: &str, eof: bool) -> Result<Input, Error> {
+		let path = self.path();
+		let interner = self.runtime.interner_mut();
+		let source = syntax::Source { path, contents: input.as_bytes().into() };
+
+		let analysis = syntax::Analysis::analyze(&source, interner);
+		if !analysis.is_ok() {
+			return if analysis.errors.is_incomplete() && !eof {
+				Ok(Input::Incomplete)
+			} else {
+				Err(Error::Syntax(analysis.errors))
+			};
+		}
+
+		let (program, _) = semantic::Analyzer
+			::analyze_session(analysis.ast, interner, &mut self.session)
+			.map_err(Error::Semantic)?;
+
+		self.inputs += 1;
+
+		Ok(Input::Complete(program))
+	}
+
+
+	/// The path attributed to the current input. Each input has its own path, so that
+	/// positions in functions defined by previous inputs refer to the right source.
+	fn path(&mut self) -> Symbol {
+		let path = format!("<repl:{}>", self.inputs + 1);
+		self.runtime.interner_mut().get_or_intern(&path)
+	}
+
+
+	/// Report an error to stderr.
+	fn report(&self, error: &Error) -> io::Result<()> {
+		let interner = self.runtime.interner();
+
+		self.runtime.with_stderr(
+			|err| match error {
+				// Panics are not terminated by a newline.
+				Error::Panic(_) => writeln!(err, "{}", fmt::Show(error, interner)),
+				_ => write!(err, "{}", fmt::Show(error, interner)),
+			}
+		)
+	}
+}
+
+
+/// The names of the variables declared in the session, sorted.
+fn globals(session: &semantic::Session, interner: &symbol::Interner) -> Vec<String> {
+	let mut globals: Vec<String> = session
+		.globals()
+		.filter_map(|symbol| interner.resolve(symbol))
+		.map(|name| String::from_utf8_lossy(name).into_owned())
+		.collect();
+
+	globals.sort();
+	globals.dedup();
+
+	globals
+}
@@ -0,0 +1,111 @@
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+		Condvar,
+		Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+#[cfg(feature = "system")]
+use super::executor::Process;
+
+
+/// A handle to interrupt a running script, which may be triggered from another thread.
+/// The runtime checks for cancellation at loop iterations and function calls, raising
+/// an interrupted panic. Sleeping scripts are woken up and child processes of command
+/// blocks are killed right away.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+
+#[derive(Debug, Default)]
+struct Inner {
+	cancelled: AtomicBool,
+	/// Wakes up sleeping scripts on cancellation.
+	sleeping: (Mutex<()>, Condvar),
+	/// Running children, to be killed on cancellation.
+	#[cfg(feature = "system")]
+	children: std::sync::Mutex<Vec<Arc<dyn Process>>>,
+}
+
+
+impl CancellationToken {
+	/// Interrupt the script. The runtime remains cancelled afterwards, and subsequent
+	/// evaluations are interrupted as soon as they start.
+	pub fn cancel(&self) {
+		self.0.cancelled.store(true, Ordering::SeqCst);
+
+		// The lock is taken so that sleepers either see the flag or get notified.
+		let (lock, condvar) = &self.0.sleeping;
+		drop(lock.lock().unwrap_or_else(|error| error.into_inner()));
+		condvar.notify_all();
+
+		#[cfg(feature = "system")]
+		for child in self.children().iter() {
+			child.kill();
+		}
+	}
+
+
+	/// Whether the script has been interrupted.
+	pub fn is_cancelled(&self) -> bool {
+		self.0.cancelled.load(Ordering::Relaxed)
+	}
+
+
+	/// Block for the given duration, returning early if the script is interrupted. Returns
+	/// false if the script has been interrupted.
+	pub(super) fn sleep(&self, duration: Duration) -> bool {
+		let deadline = Instant::now() + duration;
+
+		let (lock, condvar) = &self.0.sleeping;
+		let mut guard = lock.lock().unwrap_or_else(|error| error.into_inner());
+
+		loop {
+			if self.0.cancelled.load(Ordering::SeqCst) {
+				return false;
+			}
+
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			if remaining.is_zero() {
+				return true;
+			}
+
+			guard = condvar
+				.wait_timeout(guard, remaining)
+				.unwrap_or_else(|error| error.into_inner())
+				.0;
+		}
+	}
+
+
+	/// Keep track of a child process, killing it if the script has been interrupted.
+	#[cfg(feature = "system")]
+	pub(super) fn track(&self, child: &Arc<dyn Process>) {
+		self.children().push(child.clone());
+
+		// The flag is checked after inserting, so that a concurrent cancel either sees the
+		// child or is seen here.
+		if self.0.cancelled.load(Ordering::SeqCst) {
+			child.kill();
+		}
+	}
+
+
+	/// Stop tracking a finished child process.
+	#[cfg(feature = "system")]
+	pub(super) fn untrack(&self, child: &Arc<dyn Process>) {
+		self.children().retain(|tracked| !Arc::ptr_eq(tracked, child));
+	}
+
+
+	#[cfg(feature = "system")]
+	fn children(&self) -> std::sync::MutexGuard<'_, Vec<Arc<dyn Process>>> {
+		self.0.children
+			.lock()
+			.unwrap_or_else(|error| error.into_inner())
+	}
+}
+
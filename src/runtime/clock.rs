@@ -0,0 +1,43 @@
+use std::{
+	fmt::Debug,
+	time::{Duration, Instant, SystemTime},
+};
+
+
+/// A source of time for the runtime.
+/// Embedders may provide their own implementation in order to fake time, which is useful
+/// for tests and sandboxes.
+pub trait Clock: Debug {
+	/// The current wall-clock time.
+	fn now(&self) -> SystemTime;
+	/// The elapsed time since an arbitrary, but fixed, epoch.
+	/// This must never decrease between calls.
+	fn monotonic(&self) -> Duration;
+}
+
+
+/// The default clock, which uses the operating system's time sources.
+#[derive(Debug)]
+pub struct SystemClock {
+	/// The epoch for the monotonic clock.
+	epoch: Instant,
+}
+
+
+impl Default for SystemClock {
+	fn default() -> Self {
+		Self { epoch: Instant::now() }
+	}
+}
+
+
+impl Clock for SystemClock {
+	fn now(&self) -> SystemTime {
+		SystemTime::now()
+	}
+
+
+	fn monotonic(&self) -> Duration {
+		self.epoch.elapsed()
+	}
+}
@@ -0,0 +1,45 @@
+use std::{
+	ffi::OsStr,
+	os::unix::prelude::{ExitStatusExt, OsStrExt},
+	path::Path,
+	process,
+};
+
+
+/// Status to be produced when an IO error occurs
+pub const IO_ERROR_STATUS: i32 = 0x7F;
+/// Offset of a signal status, according to Bash and Dash.
+pub const SIGNAL_STATUS_OFFSET: i32 = 0xFF;
+
+
+/// Build a command to run the given program in the given working directory.
+/// Programs containing slashes are paths, which are relative to the working directory.
+pub fn command(program: &OsStr, cwd: &Path) -> process::Command {
+	let mut command =
+		if program.as_bytes().contains(&b'/') {
+			process::Command::new(cwd.join(program))
+		} else {
+			process::Command::new(program)
+		};
+
+	command.current_dir(cwd);
+	command.env("PWD", cwd);
+
+	command
+}
+
+
+/// Get the shell exit code of a finished process.
+/// Processes killed by a signal are offset by SIGNAL_STATUS_OFFSET.
+pub fn exit_code(status: process::ExitStatus) -> i32 {
+	status
+		.code()
+		.or_else(
+			|| status
+				.signal()
+				.map(
+					|status| status + SIGNAL_STATUS_OFFSET
+				)
+		)
+		.unwrap_or(255)
+}
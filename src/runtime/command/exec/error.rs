@@ -1,12 +1,12 @@
 use std::{
 	io,
-	collections::HashMap, ffi::OsString,
+	ffi::OsString,
 };
 
 use crate::{
 	io::FileDescriptor,
 	term::color,
-	symbol, runtime::value::{self, Value}, fmt::Show,
+	symbol, runtime::value::{self, DictMap, Value}, fmt::Show,
 };
 use super::{SourcePos, ErrorStatus};
 
@@ -28,7 +28,12 @@ pub enum Panic {
 	InvalidPattern {
 		pattern: OsString,
 		pos: SourcePos,
-	}
+	},
+	/// The builtin command is currently unsupported.
+	UnsupportedBuiltin {
+		operation: &'static str,
+		pos: SourcePos,
+	},
 }
 
 
@@ -47,6 +52,11 @@ impl Panic {
 	pub fn invalid_pattern(pattern: OsString, pos: SourcePos) -> Self {
 		Self::InvalidPattern { pattern, pos }
 	}
+
+	/// The builtin command is currently unsupported.
+	pub fn unsupported_builtin(operation: &'static str, pos: SourcePos) -> Self {
+		Self::UnsupportedBuiltin { operation, pos }
+	}
 }
 
 
@@ -80,6 +90,14 @@ impl std::fmt::Display for Panic {
 					panic,
 					color::Fg(color::Yellow, pattern)
 				),
+
+			Self::UnsupportedBuiltin { operation, .. } =>
+				write!(
+					f,
+					"{}: unsupported operation ({})",
+					panic,
+					color::Fg(color::Yellow, operation)
+				),
 		}
 	}
 }
@@ -96,6 +114,7 @@ impl From<Panic> for crate::runtime::Panic {
 			Panic::InvalidArgs { object, items, pos } => P::invalid_command_args(object, items, pos),
 			Panic::UnsupportedFileDescriptor { fd, pos } => P::unsupported_fd(fd, pos),
 			Panic::InvalidPattern { pattern, pos } => P::invalid_pattern(pattern, pos),
+			Panic::UnsupportedBuiltin { operation, pos } => P::unsupported_operation(operation, pos),
 		}
 	}
 }
@@ -113,12 +132,15 @@ impl IntoValue for ErrorStatus {
 
 		let description = std::mem::take(&mut self.description).into();
 
-		let mut context = HashMap::new();
+		let mut context = DictMap::default();
 		STATUS.with(
 			|status| context.insert(status.copy(), Value::Int(self.status as i64))
 		);
 		POS.with(
-			|pos| context.insert(pos.copy(), Show(self.pos, interner).to_string().into())
+			|pos| context.insert(
+				pos.copy(),
+				color::plain(|| Show(self.pos, interner).to_string()).into()
+			)
 		);
 		let context = value::Dict::new(context).into();
 
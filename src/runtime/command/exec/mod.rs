@@ -6,22 +6,25 @@ use std::{
 	ffi::{OsStr, OsString},
 	fs::{File, OpenOptions},
 	io::{self, Write},
-	os::unix::prelude::{FromRawFd, OsStrExt, ExitStatusExt, IntoRawFd},
-	process,
+	os::unix::prelude::{FromRawFd, OsStrExt, IntoRawFd},
+	path::{Path, PathBuf},
+	sync::Arc,
 };
 
 use crate::io::FileDescriptor;
-use super::{program, SourcePos};
+use super::{
+	child::IO_ERROR_STATUS,
+	program,
+	CancellationToken,
+	CommandExecutor,
+	Process,
+	ProcessSpec,
+	SourcePos,
+};
 pub use join::Join;
 pub use error::{Panic, Error, PipelineErrors, IntoValue};
 
 
-/// Status to be produced when an IO error occurs
-const IO_ERROR_STATUS: i32 = 0x7F;
-/// Offset of a signal status, according to Bash and Dash.
-const SIGNAL_STATUS_OFFSET: i32 = 0xFF;
-
-
 /// Execution status of a single command.
 #[derive(Debug)]
 pub struct ErrorStatus {
@@ -33,9 +36,12 @@ pub struct ErrorStatus {
 
 impl ErrorStatus {
 	/// Wait a child process, and return the status.
-	fn wait_child(mut child: Child) -> Option<Self> {
-		let status = match child.process.wait() {
-			Ok(status) => status,
+	fn wait_child(child: Child, cancel: &CancellationToken) -> Option<Self> {
+		let status = child.process.wait();
+		cancel.untrack(&child.process);
+
+		let code = match status {
+			Ok(code) => code,
 			Err(error) => return Some(
 				Self {
 					description: error.to_string(),
@@ -45,17 +51,6 @@ impl ErrorStatus {
 			)
 		};
 
-		let code = status
-			.code()
-			.or_else(
-				|| status
-					.signal()
-					.map(
-						|status| status + SIGNAL_STATUS_OFFSET
-					)
-			)
-			.unwrap_or(255);
-
 		if code == 0 {
 			None
 		} else {
@@ -82,8 +77,8 @@ pub enum Argument {
 
 
 impl Argument {
-	/// Resolve the argument in the current directory.
-	pub fn resolve(self, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
+	/// Resolve the argument in the given working directory.
+	pub fn resolve(self, cwd: &Path, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
 		match self {
 			Self::Literal(lit) => Ok(Box::new([lit])),
 			Self::Pattern(pattern) => {
@@ -95,13 +90,30 @@ impl Argument {
 
 				let is_absolute = pattern_str.starts_with('/');
 
-				let entries = glob::glob(&pattern_str)
+				// Relative patterns must be matched in the working directory, but expand to
+				// relative paths.
+				let full_pattern =
+					if is_absolute {
+						pattern_str.clone()
+					} else {
+						let cwd = cwd
+							.to_str()
+							.ok_or_else(|| Panic::invalid_pattern(cwd.as_os_str().to_owned(), pos.copy()))?;
+
+						format!("{}/{}", glob::Pattern::escape(cwd), pattern_str)
+					};
+
+				let entries = glob::glob(&full_pattern)
 					.map_err(|_| Panic::invalid_pattern(pattern_str.into(), pos))?
 					.filter_map(Result::ok)
 					.map(
 						|path| if is_absolute {
 							OsString::from(path).into_boxed_os_str()
 						} else {
+							let path = path
+								.strip_prefix(cwd)
+								.expect("glob match outside of working directory");
+
 							let mut new_path = OsString::with_capacity(2 + path.as_os_str().len());
 							new_path.push("./");
 							new_path.push(path);
@@ -159,12 +171,13 @@ impl Builtin {
 	pub fn exec(
 		self,
 		arguments: Box<[Argument]>,
+		cwd: &mut PathBuf,
 		pos: SourcePos,
 	) -> Result<Option<ErrorStatus>, Error> {
 		let mut arguments = arguments.into_vec();
 
 		match self {
-			Builtin::Alias => todo!(),
+			Builtin::Alias => Err(Panic::unsupported_builtin("alias", pos).into()),
 
 			Builtin::Cd => {
 				let arg = arguments
@@ -177,11 +190,25 @@ impl Builtin {
 					);
 				}
 
-				let args = arg.resolve(pos.copy())?;
+				let args = arg.resolve(cwd, pos.copy())?;
 
 				match args.as_ref() {
-					[ dir ] => std::env::set_current_dir(dir.as_ref())
-						.map_err(|error| Error::io(error, pos.copy()))?,
+					[ dir ] => {
+						// Only the working directory of the command block is changed, the
+						// process' working directory is left untouched.
+						let dir = cwd
+							.join(dir.as_ref())
+							.canonicalize()
+							.map_err(|error| Error::io(error, pos.copy()))?;
+
+						if !dir.is_dir() {
+							return Err(
+								Error::io(io::Error::from_raw_os_error(libc::ENOTDIR), pos)
+							);
+						}
+
+						*cwd = dir;
+					}
 					other => return Err(
 						Panic::invalid_args("argument", other.len() as u32, pos).into()
 					),
@@ -204,6 +231,17 @@ impl<'a> From<&'a program::command::Builtin> for Builtin {
 }
 
 
+/// Standard streams for the commands of a block, which are copied for each command.
+#[derive(Debug)]
+pub struct Streams {
+	pub stdin: os_pipe::PipeReader,
+	pub stdout: os_pipe::PipeWriter,
+	pub stderr: os_pipe::PipeWriter,
+	/// The stderr of commands in the middle of pipelines, which is never captured.
+	pub inherited_stderr: os_pipe::PipeWriter,
+}
+
+
 #[derive(Debug)]
 pub struct Stdio {
 	pub stdin: os_pipe::PipeReader,
@@ -231,23 +269,30 @@ pub struct BasicCommand {
 
 
 impl BasicCommand {
-	pub fn exec(self, stdio: Stdio) -> Result<Child, Error> {
+	pub fn exec(
+		self,
+		cwd: &Path,
+		stdio: Stdio,
+		executor: &dyn CommandExecutor,
+		cancel: &CancellationToken,
+	) -> Result<Child, Error> {
 		let pos = self.pos.copy();
 
-		let program_args = self.program.resolve(pos.copy())?;
+		let program_args = self.program.resolve(cwd, pos.copy())?;
 
-		let mut command = match program_args.as_ref() {
-			[ program ] => process::Command::new(program),
+		let mut argv = match program_args.into_vec().as_mut_slice() {
+			[ program ] => vec![ std::mem::take(program) ],
 			other => return Err(
 				Panic::invalid_args("program", other.len() as u32, pos.copy()).into()
 			),
 		};
 
+		let mut env = Vec::with_capacity(self.env.len());
 		for (key, value) in self.env.into_vec() { // Use vec's owned iterator.
-			let value = value.resolve(pos.copy())?;
+			let value = value.resolve(cwd, pos.copy())?;
 
-			match value.as_ref() {
-				[ value ] => command.env(key, value),
+			match value.into_vec().as_mut_slice() {
+				[ value ] => env.push((key, std::mem::take(value))),
 				other => return Err(
 					Panic::invalid_args("env variable", other.len() as u32, pos.copy()).into()
 				),
@@ -255,27 +300,43 @@ impl BasicCommand {
 		}
 
 		for argument in self.arguments.into_vec() {
-			let args = argument.resolve(pos.copy())?;
-
-			for arg in args.iter() {
-				command.arg(arg);
-			}
+			let args = argument.resolve(cwd, pos.copy())?;
+			argv.extend(args.into_vec());
 		}
 
-		Self::spawn(&mut command, stdio, self.redirections, self.pos)
+		let stdio = Self::redirect(stdio, self.redirections, cwd, pos.copy())?;
+
+		let spec = ProcessSpec {
+			argv,
+			env,
+			cwd: cwd.to_owned(),
+			stdin: stdio.stdin,
+			stdout: stdio.stdout,
+			stderr: stdio.stderr,
+		};
+
+		let process: Arc<dyn Process> = executor
+			.spawn(spec)
+			.map_err(|error| Error::io(error, pos.copy()))?
+			.into();
+
+		cancel.track(&process);
+
+		Ok(Child { process, pos })
 	}
 
 
-	fn spawn(
-		command: &mut process::Command,
+	/// Apply the redirections to the standard streams.
+	fn redirect(
 		mut stdio: Stdio,
 		redirections: Box<[Redirection]>,
+		cwd: &Path,
 		pos: SourcePos,
-	) -> Result<Child, Error> {
+	) -> Result<Stdio, Error> {
 		for redirection in redirections.into_vec() { // Use vec's owned iterator.
 			match redirection {
 				Redirection::Output { source, target } => {
-					let target = Self::resolve_target(target, &stdio, pos.copy())?;
+					let target = Self::resolve_target(target, &stdio, cwd, pos.copy())?;
 
 					match source {
 						1 => stdio.stdout = target,
@@ -287,7 +348,7 @@ impl BasicCommand {
 				}
 
 				Redirection::Input { literal, source } => {
-					let args = source.resolve(pos.copy())?;
+					let args = source.resolve(cwd, pos.copy())?;
 
 					let source = match args.as_ref() {
 						[ source ] => source,
@@ -309,7 +370,7 @@ impl BasicCommand {
 
 							reader
 						} else {
-							let file = File::open(source.as_ref())
+							let file = File::open(cwd.join(source.as_ref()))
 								.map_err(|error| Error::io(error, pos.copy()))?
 								.into_raw_fd();
 
@@ -322,27 +383,25 @@ impl BasicCommand {
 			}
 		}
 
-		command.stdin(stdio.stdin);
-		command.stdout(stdio.stdout);
-		command.stderr(stdio.stderr);
-
-		let process = command.spawn()
-			.map_err(|error| Error::io(error, pos.copy()))?;
-
-		Ok(Child { process, pos })
+		Ok(stdio)
 	}
 
 
-	fn resolve_target(target: RedirectionTarget, stdio: &Stdio, pos: SourcePos) -> Result<os_pipe::PipeWriter, Error> {
+	fn resolve_target(
+		target: RedirectionTarget,
+		stdio: &Stdio,
+		cwd: &Path,
+		pos: SourcePos
+	) -> Result<os_pipe::PipeWriter, Error> {
 		let open = |arg: Argument, append| {
-			let args = arg.resolve(pos.copy())?;
+			let args = arg.resolve(cwd, pos.copy())?;
 
 			let file = match args.as_ref() {
 				[ file ] => OpenOptions::new()
 					.create(true)
 					.write(true)
 					.append(append)
-					.open(file.as_ref())
+					.open(cwd.join(file.as_ref()))
 					.map_err(|error| Error::io(error, pos.copy()))?
 					.into_raw_fd(),
 
@@ -379,7 +438,7 @@ impl BasicCommand {
 
 #[derive(Debug)]
 pub struct Child {
-	process: process::Child,
+	process: Arc<dyn Process>,
 	pos: SourcePos,
 }
 
@@ -417,12 +476,14 @@ impl Command {
 	/// Returns a pair of result value and whether to abort.
 	pub fn exec(
 		self,
-		stdout: os_pipe::PipeWriter,
-		stderr: os_pipe::PipeWriter,
+		cwd: &mut PathBuf,
+		streams: &Streams,
+		executor: &dyn CommandExecutor,
+		cancel: &CancellationToken,
 	) -> Result<CommandExec, Error> {
 		match self {
 			Command::Builtin { program, arguments, abort_on_error, pos } => {
-				let error = program.exec(arguments, pos)?;
+				let error = program.exec(arguments, cwd, pos)?;
 				let abort = abort_on_error && error.is_some();
 				Ok(
 					CommandExec {
@@ -433,8 +494,13 @@ impl Command {
 			}
 
 			Command::External { head, tail } => {
-				let mut last_stdout = stdout;
-				let mut last_stderr = stderr;
+				let pos = head.pos.copy();
+				let mut last_stdout = streams.stdout
+					.try_clone()
+					.map_err(|error| Error::io(error, pos.copy()))?;
+				let mut last_stderr = streams.stderr
+					.try_clone()
+					.map_err(|error| Error::io(error, pos.copy()))?;
 
 				let mut tail_children = Vec::new();
 				for cmd in tail.into_vec().into_iter().rev() {
@@ -444,15 +510,19 @@ impl Command {
 						.map_err(|error| Error::io(error, cmd.pos.copy()))?;
 
 					let child = cmd.exec(
+						cwd,
 						Stdio {
 							stdin: pipe_reader,
 							stdout: last_stdout,
 							stderr: last_stderr,
-						}
+						},
+						executor,
+						cancel,
 					)?;
 
 					last_stdout = pipe_writer;
-					last_stderr = os_pipe::dup_stderr()
+					last_stderr = streams.inherited_stderr
+						.try_clone()
 						.map_err(|error| Error::io(error, child.pos.copy()))?;
 
 					tail_children.push((child, child_abort_on_error));
@@ -460,29 +530,33 @@ impl Command {
 
 				let head_abort_on_error = head.abort_on_error;
 
-				let stdin = os_pipe::dup_stdin()
+				let stdin = streams.stdin
+					.try_clone()
 					.map_err(|error| Error::io(error, head.pos.copy()))?;
 
 				let head_child = head.exec(
+					cwd,
 					Stdio {
 						stdin,
 						stdout: last_stdout,
 						stderr: last_stderr,
-					}
+					},
+					executor,
+					cancel,
 				)?;
 
 				let mut abort = false;
 				let mut errors = Vec::new();
 
 				// Wait on head command.
-				if let Some(error) = ErrorStatus::wait_child(head_child) {
+				if let Some(error) = ErrorStatus::wait_child(head_child, cancel) {
 					abort |= head_abort_on_error;
 					errors.push(error);
 				}
 
 				// Wait on tail commands.
 				for (child, abort_on_error) in tail_children.into_iter().rev() {
-					if let Some(error) = ErrorStatus::wait_child(child) {
+					if let Some(error) = ErrorStatus::wait_child(child, cancel) {
 						abort |= abort_on_error;
 						errors.push(error);
 					}
@@ -512,16 +586,18 @@ impl Command {
 pub struct Block {
 	pub head: Command,
 	pub tail: Box<[Command]>,
+	/// Children are killed when the script is interrupted.
+	pub cancel: CancellationToken,
+	/// Spawns the processes.
+	pub executor: Arc<dyn CommandExecutor>,
 }
 
 
 impl Block {
-	pub fn exec<F, G>(self, stdout: F, stderr: G) -> Result<Box<[PipelineErrors]>, Panic>
-	where
-		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
-		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
-	{
-		match self._exec(stdout, stderr) {
+	/// Execute the command block in the given working directory, which may be changed by
+	/// the cd builtin.
+	pub fn exec(self, cwd: &mut PathBuf, streams: &Streams) -> Result<Box<[PipelineErrors]>, Panic> {
+		match self._exec(cwd, streams) {
 			Ok(status) => Ok(status),
 			Err(Error::Panic(panic)) => Err(panic),
 			Err(Error::Io { error, pos }) => {
@@ -537,20 +613,10 @@ impl Block {
 	}
 
 
-	fn _exec<F, G>(self, mut stdout: F, mut stderr: G,) -> Result<Box<[PipelineErrors]>, Error>
-	where
-		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
-		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
-	{
+	fn _exec(self, cwd: &mut PathBuf, streams: &Streams) -> Result<Box<[PipelineErrors]>, Error> {
 		let mut errors = Vec::new();
 
-		let pos = self.head.pos();
-		let head = self.head.exec(
-			stdout()
-				.map_err(|error| Error::io(error, pos.copy()))?,
-			stderr()
-				.map_err(|error| Error::io(error, pos.copy()))?,
-		)?;
+		let head = self.head.exec(cwd, streams, self.executor.as_ref(), &self.cancel)?;
 
 		if !head.errors.is_empty() {
 			errors.push(head.errors);
@@ -561,13 +627,11 @@ impl Block {
 		}
 
 		for command in self.tail.into_vec() { // Use vec's owned iterator.
-			let pos = command.pos();
-			let child = command.exec(
-				stdout()
-					.map_err(|error| Error::io(error, pos.copy()))?,
-				stderr()
-					.map_err(|error| Error::io(error, pos.copy()))?,
-			)?;
+			if self.cancel.is_cancelled() {
+				break;
+			}
+
+			let child = command.exec(cwd, streams, self.executor.as_ref(), &self.cancel)?;
 
 			if !child.errors.is_empty() {
 				errors.push(child.errors);
@@ -1,17 +1,22 @@
 mod arg;
+pub(super) mod child;
 mod exec;
 
 use std::{
 	borrow::Cow,
-	collections::HashMap,
 	os::unix::{ffi::OsStrExt, prelude::OsStringExt},
 	path::PathBuf,
 	ops::DerefMut, io::Read, ffi::{OsStr, OsString}
 };
 
 use super::{
+	executor::{CommandExecutor, Process, ProcessSpec},
 	program,
+	trace::CallHook,
+	CallEvent,
+	CancellationToken,
 	Dict,
+	DictMap,
 	Panic,
 	Runtime,
 	SourcePos,
@@ -27,17 +32,69 @@ impl Runtime {
 		block: &'static program::CommandBlock,
 		pos: SourcePos,
 	) -> Result<Value, Panic> {
+		if let Some(CallHook(hook)) = &mut self.call_hook {
+			hook(&CallEvent::CommandBlock { pos: &pos });
+		}
+
+		let result = self.exec_command_block(block, pos);
+
+		if let Some(CallHook(hook)) = &mut self.call_hook {
+			hook(&CallEvent::Return);
+		}
+
+		result
+	}
+
+
+	/// Execute a command block, without invoking the call hook.
+	fn exec_command_block(
+		&mut self,
+		block: &'static program::CommandBlock,
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
+		let spawned = std::iter::once(&block.head)
+			.chain(block.tail.iter())
+			.map(
+				|command| match command {
+					program::Command::Builtin { .. } => 0,
+					program::Command::External { tail, .. } => 1 + tail.len() as u64,
+				}
+			)
+			.sum();
+		self.consume_fuel(spawned, &pos)?;
+
 		let command_block = self.build_command_block(&block.head, &block.tail)?;
 
+		let mut cwd = self
+			.cwd()
+			.map_err(|error| Panic::io(error, pos.copy()))?;
+
+		let io_error = |error| Panic::io(error, pos.copy());
+		let pipes = self.stdio.pipes().map_err(io_error)?;
+		let inherited_stderr = pipes.stderr.try_clone().map_err(io_error)?;
+
 		match block.kind {
 			program::CommandBlockKind::Synchronous => {
-				command_block
-					.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
-					)
+				let streams = exec::Streams {
+					stdin: pipes.stdin,
+					stdout: pipes.stdout,
+					stderr: pipes.stderr,
+					inherited_stderr,
+				};
+
+				let result = command_block
+					.exec(&mut cwd, &streams)
 					.map(|errors| errors.into_value(self.interner()))
-					.map_err(Into::into)
+					.map_err(Into::into);
+
+				// Output must be forwarded before the script proceeds.
+				drop(streams);
+				pipes.forwarders.join();
+
+				self.set_cwd(cwd);
+				self.check_cancelled(&pos)?;
+
+				result
 			}
 
 			program::CommandBlockKind::Capture => {
@@ -53,16 +110,27 @@ impl Runtime {
 				let (mut stderr_read, stderr_write) = os_pipe::pipe()
 					.map_err(|error| Panic::io(error, pos.copy()))?;
 
+				let streams = exec::Streams {
+					stdin: pipes.stdin,
+					stdout: stdout_write,
+					stderr: stderr_write,
+					inherited_stderr,
+				};
+
 				let errors = command_block
-					.exec(
-						|| stdout_write.try_clone(),
-						|| stderr_write.try_clone(),
-					)
-					.map_err(Panic::from)?;
+					.exec(&mut cwd, &streams)
+					.map_err(Panic::from);
 
 				// We must drop all writers before attempting to read, otherwise we'll deadlock.
-				drop(stdout_write);
-				drop(stderr_write);
+				drop(streams);
+				drop(pipes.stdout);
+				drop(pipes.stderr);
+				pipes.forwarders.join();
+
+				self.set_cwd(cwd);
+				self.check_cancelled(&pos)?;
+
+				let errors = errors?;
 
 				let mut result = errors.into_value(self.interner());
 				let mut captures = {
@@ -78,7 +146,7 @@ impl Runtime {
 					let out = out.into_boxed_slice();
 					let err = err.into_boxed_slice();
 
-					let mut dict = HashMap::new();
+					let mut dict = DictMap::default();
 
 					STDOUT.with(
 						|stdout| dict.insert(stdout.copy(), out.into())
@@ -112,18 +180,31 @@ impl Runtime {
 					pub static JOIN: Value = "join".into();
 				}
 
+				// Changes to the working directory in asynchronous blocks are not visible to
+				// the script.
 				let join_handle = std::thread::spawn(
-					|| command_block.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
-					)
+					move || {
+						let streams = exec::Streams {
+							stdin: pipes.stdin,
+							stdout: pipes.stdout,
+							stderr: pipes.stderr,
+							inherited_stderr,
+						};
+
+						let result = command_block.exec(&mut cwd, &streams);
+
+						drop(streams);
+						pipes.forwarders.join();
+
+						result
+					}
 				);
 
 				let join_handle = exec::Join
 					::new(join_handle)
 					.into();
 
-				let mut dict = HashMap::new();
+				let mut dict = DictMap::default();
 
 				JOIN.with(
 					|join| dict.insert(join.copy(), join_handle)
@@ -148,7 +229,14 @@ impl Runtime {
 			)
 			.collect::<Result<_, Panic>>()?;
 
-		Ok(exec::Block { head, tail })
+		Ok(
+			exec::Block {
+				head,
+				tail,
+				cancel: self.cancel.clone(),
+				executor: self.executor.clone(),
+			}
+		)
 	}
 
 
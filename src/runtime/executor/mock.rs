@@ -0,0 +1,112 @@
+use std::{
+	collections::HashMap,
+	ffi::OsStr,
+	io::{self, Write},
+	path::PathBuf,
+	sync::{Arc, Mutex},
+};
+
+use super::{lock, CommandExecutor, Process, ProcessSpec};
+
+
+/// An executor for tests, which records invocations instead of spawning processes, and
+/// produces scripted outputs. Clones share the same state, so that a clone may be kept to
+/// inspect the invocations after handing the executor to the runtime.
+#[derive(Debug, Clone, Default)]
+pub struct MockExecutor(Arc<Mutex<MockState>>);
+
+
+#[derive(Debug, Default)]
+struct MockState {
+	invocations: Vec<Invocation>,
+	/// Responses by program. Other programs succeed without output.
+	responses: HashMap<Box<OsStr>, Response>,
+}
+
+
+/// A recorded invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+	pub argv: Vec<Box<OsStr>>,
+	pub env: Vec<(Box<OsStr>, Box<OsStr>)>,
+	pub cwd: PathBuf,
+}
+
+
+/// The scripted outcome of a program.
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+	pub stdout: Vec<u8>,
+	pub stderr: Vec<u8>,
+	/// The shell exit code.
+	pub status: i32,
+}
+
+
+impl MockExecutor {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+
+	/// Script the outcome of every invocation of the given program.
+	pub fn respond<P>(&self, program: P, response: Response)
+	where
+		P: AsRef<OsStr>,
+	{
+		lock(&self.0)
+			.responses
+			.insert(program.as_ref().into(), response);
+	}
+
+
+	/// The invocations so far, in order of spawning. Pipelines are spawned from last to
+	/// first.
+	pub fn invocations(&self) -> Vec<Invocation> {
+		lock(&self.0).invocations.clone()
+	}
+}
+
+
+impl CommandExecutor for MockExecutor {
+	fn spawn(&self, mut spec: ProcessSpec) -> io::Result<Box<dyn Process>> {
+		let mut state = lock(&self.0);
+
+		let response = spec.argv
+			.first()
+			.and_then(|program| state.responses.get(program))
+			.cloned()
+			.unwrap_or_default();
+
+		state.invocations.push(
+			Invocation {
+				argv: spec.argv,
+				env: spec.env,
+				cwd: spec.cwd,
+			}
+		);
+
+		// The reading end may have been closed already, such as in a pipeline.
+		let _ = spec.stdout.write_all(&response.stdout);
+		let _ = spec.stderr.write_all(&response.stderr);
+
+		Ok(Box::new(MockProcess { status: response.status }))
+	}
+}
+
+
+/// A process which has finished as soon as it was spawned.
+#[derive(Debug)]
+struct MockProcess {
+	status: i32,
+}
+
+
+impl Process for MockProcess {
+	fn wait(&self) -> io::Result<i32> {
+		Ok(self.status)
+	}
+
+
+	fn kill(&self) { }
+}
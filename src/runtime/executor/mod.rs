@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod mock;
+
+use std::{
+	ffi::OsStr,
+	fmt::Debug,
+	io,
+	path::PathBuf,
+	process,
+	sync::Mutex,
+};
+
+use super::command::child;
+#[cfg(test)]
+pub use mock::{Invocation, MockExecutor, Response};
+
+
+/// Spawns the processes of command blocks. Embedders may provide their own implementation
+/// in order to fake or restrict command execution, which is useful for tests and sandboxes.
+pub trait CommandExecutor: Debug + Send + Sync {
+	/// Spawn a process. Errors are reported to the script as failed commands, which allows
+	/// denying certain programs.
+	fn spawn(&self, spec: ProcessSpec) -> io::Result<Box<dyn Process>>;
+}
+
+
+/// A running process.
+pub trait Process: Debug + Send + Sync {
+	/// Wait for the process to finish, and return its shell exit code.
+	fn wait(&self) -> io::Result<i32>;
+	/// Kill the process if it's still running. This may be called from other threads, even
+	/// while waiting for the process.
+	fn kill(&self);
+}
+
+
+/// A process to be spawned.
+#[derive(Debug)]
+pub struct ProcessSpec {
+	/// The program, followed by the arguments. Programs containing slashes are paths, which
+	/// are relative to the working directory.
+	pub argv: Vec<Box<OsStr>>,
+	/// Environment variables set by the command, in addition to the inherited ones.
+	pub env: Vec<(Box<OsStr>, Box<OsStr>)>,
+	/// The working directory.
+	pub cwd: PathBuf,
+	pub stdin: os_pipe::PipeReader,
+	pub stdout: os_pipe::PipeWriter,
+	pub stderr: os_pipe::PipeWriter,
+}
+
+
+/// The default executor, which spawns operating system processes.
+#[derive(Debug, Default)]
+pub struct SystemExecutor;
+
+
+impl CommandExecutor for SystemExecutor {
+	fn spawn(&self, spec: ProcessSpec) -> io::Result<Box<dyn Process>> {
+		let (program, args) = spec.argv
+			.split_first()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing program"))?;
+
+		let mut command = child::command(program, &spec.cwd);
+		command.args(args);
+		command.envs(spec.env.iter().map(|(key, value)| (key, value)));
+		command.stdin(spec.stdin);
+		command.stdout(spec.stdout);
+		command.stderr(spec.stderr);
+
+		let child = command.spawn()?;
+
+		Ok(
+			Box::new(
+				SystemProcess {
+					pid: child.id(),
+					child: Mutex::new(child),
+					reaped: Mutex::new(false),
+				}
+			)
+		)
+	}
+}
+
+
+/// An operating system process.
+#[derive(Debug)]
+struct SystemProcess {
+	pid: u32,
+	child: Mutex<process::Child>,
+	/// Whether the process has been reaped, after which the pid may be reused and it must
+	/// not be killed.
+	reaped: Mutex<bool>,
+}
+
+
+impl Process for SystemProcess {
+	fn wait(&self) -> io::Result<i32> {
+		// Wait for the process to exit without reaping it, so that it may still be killed
+		// meanwhile.
+		loop {
+			// SAFETY: siginfo_t is plain data, which is filled by waitid.
+			let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+
+			// SAFETY: info is a valid pointer. WNOWAIT leaves the child waitable.
+			let result = unsafe {
+				libc::waitid(
+					libc::P_PID,
+					self.pid as libc::id_t,
+					&mut info,
+					libc::WEXITED | libc::WNOWAIT,
+				)
+			};
+
+			if result == 0 {
+				break;
+			}
+
+			let error = io::Error::last_os_error();
+			if error.kind() != io::ErrorKind::Interrupted {
+				return Err(error);
+			}
+		}
+
+		*lock(&self.reaped) = true;
+
+		lock(&self.child)
+			.wait()
+			.map(child::exit_code)
+	}
+
+
+	fn kill(&self) {
+		let reaped = lock(&self.reaped);
+
+		if !*reaped {
+			// SAFETY: kill has no memory safety requirements. The process has not been
+			// reaped, and can't be while the lock is held, so the pid is still ours.
+			unsafe {
+				libc::kill(self.pid as libc::pid_t, libc::SIGKILL);
+			}
+		}
+	}
+}
+
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+	mutex
+		.lock()
+		.unwrap_or_else(|error| error.into_inner())
+}
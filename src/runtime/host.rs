@@ -0,0 +1,51 @@
+use gc::{Finalize, Trace};
+
+use super::{args_count, CallContext, NativeFun, Panic, Value};
+
+
+/// The implementation of a host function.
+type HostFunImpl = Box<dyn Fn(CallContext) -> Result<Value, Panic>>;
+
+
+/// A native function registered by the embedder, such as an application specific query.
+pub struct HostFun {
+	/// The full path of the function, including the std prefix.
+	name: &'static str,
+	/// The number of arguments, checked before calling the implementation.
+	arity: u32,
+	fun: HostFunImpl,
+}
+
+
+impl HostFun {
+	pub fn new<F>(name: &'static str, arity: u32, fun: F) -> Self
+	where
+		F: Fn(CallContext) -> Result<Value, Panic> + 'static,
+	{
+		Self { name, arity, fun: Box::new(fun) }
+	}
+}
+
+
+impl Finalize for HostFun { }
+
+
+/// Host functions may not capture garbage-collected values, as these would not be traced.
+unsafe impl Trace for HostFun {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl NativeFun for HostFun {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let supplied = args_count(context.args().len());
+
+		if supplied != self.arity {
+			return Err(Panic::invalid_args(supplied, self.arity, context.pos));
+		}
+
+		(self.fun)(context)
+	}
+}
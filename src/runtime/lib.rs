@@ -1,42 +1,188 @@
 automod::dir!("src/runtime/lib");
 
+#[cfg(feature = "system")]
+pub use process::ProcessState;
+pub use iter::collection_iter;
+
+use std::ops::{RangeFrom, RangeInclusive};
+
+#[cfg(feature = "system")]
+use super::{command, temp};
 use super::{
 	keys,
+	stdio,
 	Array,
 	CallContext,
 	Dict,
+	DictMap,
+	FxBuildHasher,
 	Error,
 	Float,
 	Function,
 	NativeFun,
 	RustFun,
 	Panic,
+	SharedProgram,
+	SourcePos,
 	Str,
 	Value,
 	Type,
 };
 
 
-inventory::collect!(RustFun);
+inventory::collect!(Native);
+inventory::collect!(Constant);
+
+
+/// A function in the stdlib, along with its description.
+pub struct Native {
+	pub fun: RustFun,
+	pub arity: Arity,
+	/// A one-line description, shown by std.help and --print-std.
+	pub doc: &'static str,
+}
+
+
+impl Native {
+	pub fn new<F: NativeFun>(fun: F, arity: impl Into<Arity>, doc: &'static str) -> Self {
+		Self { fun: fun.into(), arity: arity.into(), doc }
+	}
+}
+
+
+/// A non-function value in the stdlib, such as `std.math.pi`.
+/// Nested paths are placed in sub-dicts, just like functions.
+pub struct Constant {
+	/// The full path of the value, including the std prefix.
+	pub name: &'static str,
+	/// Build the value. This is called for every stdlib instance, as values are garbage
+	/// collected.
+	pub value: fn() -> Value,
+	/// A one-line description, shown by std.help and --print-std.
+	pub doc: &'static str,
+}
+
+
+/// How many arguments a stdlib function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+	pub min: u32,
+	/// The maximum, or None if variadic.
+	pub max: Option<u32>,
+}
+
+
+impl From<u32> for Arity {
+	fn from(arity: u32) -> Self {
+		Self { min: arity, max: Some(arity) }
+	}
+}
+
+
+impl From<RangeInclusive<u32>> for Arity {
+	fn from(range: RangeInclusive<u32>) -> Self {
+		Self { min: *range.start(), max: Some(*range.end()) }
+	}
+}
+
+
+impl From<RangeFrom<u32>> for Arity {
+	fn from(range: RangeFrom<u32>) -> Self {
+		Self { min: range.start, max: None }
+	}
+}
+
+
+/// A description of a stdlib member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+	/// The full path of the member, including the std prefix.
+	pub name: &'static str,
+	/// The arity of functions, or None for constants.
+	pub arity: Option<Arity>,
+	pub doc: &'static str,
+}
+
+
+/// Describe the stdlib's functions and constants, sorted by name.
+pub fn entries() -> Vec<Entry> {
+	let functions = inventory::iter::<Native>
+		.into_iter()
+		.map(
+			|native| Entry { name: native.fun.name(), arity: Some(native.arity), doc: native.doc }
+		);
+
+	let constants = inventory::iter::<Constant>
+		.into_iter()
+		.map(
+			|constant| Entry { name: constant.name, arity: None, doc: constant.doc }
+		);
+
+	let mut entries: Vec<Entry> = functions.chain(constants).collect();
+	entries.sort_by_key(|entry| entry.name);
+
+	entries
+}
 
 
 /// Instantiate the stdlib.
 pub fn new() -> Value {
 	let mut dict = Dict::default();
 
-	for fun in inventory::iter::<RustFun> {
-		let path = fun
-			.name()
-			.strip_prefix("std.")
-			.expect("Builtin function name missing std prefix.");
+	for native in inventory::iter::<Native> {
+		insert(path(native.fun.name()), native.fun.copy().into(), &mut dict);
+	}
 
-		insert(path, fun.copy().into(), &mut dict);
+	for constant in inventory::iter::<Constant> {
+		insert(path(constant.name), (constant.value)(), &mut dict);
 	}
 
 	dict.into()
 }
 
 
+/// Add a function to a stdlib instance, replacing any member with the same name.
+pub fn register(std: &mut Dict, fun: RustFun) {
+	insert(path(fun.name()), fun.into(), std);
+}
+
+
+/// Add a value to a stdlib instance, replacing any member with the same name.
+pub fn register_value(std: &mut Dict, name: &'static str, value: Value) {
+	insert(path(name), value, std);
+}
+
+
+/// The names of the stdlib's top-level members, such as `print` or `math`.
+/// Names may be repeated, as nested members share their top-level dict.
+pub fn members() -> impl Iterator<Item = &'static str> {
+	let functions = inventory::iter::<Native>
+		.into_iter()
+		.map(|native| native.fun.name());
+
+	let constants = inventory::iter::<Constant>
+		.into_iter()
+		.map(|constant| constant.name);
+
+	functions
+		.chain(constants)
+		.map(
+			|name| path(name)
+				.split('.')
+				.next()
+				.expect("split always yields at least one item")
+		)
+}
+
+
+/// Strip the std prefix from a stdlib name.
+fn path(name: &'static str) -> &'static str {
+	name
+		.strip_prefix("std.")
+		.expect("Builtin name missing std prefix.")
+}
+
+
 fn insert(path: &str, value: Value, dict: &mut Dict) {
 	match path.split_once('.') {
 		None => dict.insert(path.into(), value),
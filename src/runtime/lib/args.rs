@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Args) }
+inventory::submit! { Native::new(Args, 0, "The arguments given to the script.") }
 
 #[derive(Trace, Finalize)]
 struct Args;
@@ -3,13 +3,13 @@ use gc::{Finalize, Trace};
 use super::{
 	CallContext,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Assert) }
+inventory::submit!{ Native::new(Assert, 1, "Panic if the value is not true.") }
 
 #[derive(Trace, Finalize)]
 struct Assert;
@@ -20,10 +20,43 @@ impl NativeFun for Assert {
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
 			[ Value::Bool(true) ] => Ok(Value::default()),
-			[ Value::Bool(false) ] => Err(Panic::assertion_failed(context.pos)),
+			[ Value::Bool(false) ] => {
+				let expression = context
+					.args_source()
+					.map(|mut args| args.remove(0).into());
 
-			[ other ] => Err(Panic::type_error(other.copy(), "bool", context.pos)),
+				Err(Panic::assertion_failed(expression, context.pos))
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "bool", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
 }
+
+
+inventory::submit!{ Native::new(AssertEq, 2, "Panic if the values are not equal.") }
+
+#[derive(Trace, Finalize)]
+struct AssertEq;
+
+impl NativeFun for AssertEq {
+	fn name(&self) -> &'static str { "std.assert_eq" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ left, right ] if left == right => Ok(Value::default()),
+			[ left, right ] => {
+				let expression = context
+					.args_source()
+					.map(|args| format!("{} == {}", args[0], args[1]).into());
+
+				Err(
+					Panic::assertion_eq_failed(expression, left.copy(), right.copy(), context.pos)
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
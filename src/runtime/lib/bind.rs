@@ -4,14 +4,16 @@ use super::{
 	CallContext,
 	Function,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Bind) }
+inventory::submit!{ Native::new(Bind, 1.., "Bind the leading arguments of a function.") }
+inventory::submit!{ Native::new(BindSelf, 2, "Bind the value of self for a function.") }
 
+/// Partial application: bind the leading arguments of a function.
 #[derive(Trace, Finalize)]
 struct Bind;
 
@@ -20,15 +22,15 @@ impl NativeFun for Bind {
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ obj, Value::Function(fun) ] => Ok(
+			[ Value::Function(fun), args @ .. ] => Ok(
 				BindImpl {
-					obj: obj.copy(),
 					function: fun.copy(),
+					args: args.iter().map(Value::copy).collect(),
 				}.into()
 			),
 
-			[ _, other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+			[ other, .. ] => Err(Panic::type_error(other.copy(), "function", context.arg_pos(0))),
+			[] => Err(Panic::invalid_args(0, 1, context.pos))
 		}
 	}
 }
@@ -36,13 +38,59 @@ impl NativeFun for Bind {
 
 #[derive(Trace, Finalize)]
 struct BindImpl {
-	obj: Value,
 	function: Function,
+	args: Vec<Value>,
 }
 
 impl NativeFun for BindImpl {
 	fn name(&self) -> &'static str { "std.bind<impl>" }
 
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		// Prepend the bound arguments to the supplied ones. The arity check is done by the
+		// target function.
+		let args_start = context.args_start;
+		context.runtime.arguments.splice(
+			args_start .. args_start,
+			self.args.iter().map(Value::copy)
+		);
+
+		context.call(context.obj.copy(), &self.function, args_start)
+	}
+}
+
+
+/// Bind the value of `self` for a function.
+#[derive(Trace, Finalize)]
+struct BindSelf;
+
+impl NativeFun for BindSelf {
+	fn name(&self) -> &'static str { "std.bind_self" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ obj, Value::Function(fun) ] => Ok(
+				BindSelfImpl {
+					obj: obj.copy(),
+					function: fun.copy(),
+				}.into()
+			),
+
+			[ _, other ] => Err(Panic::type_error(other.copy(), "function", context.arg_pos(1))),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BindSelfImpl {
+	obj: Value,
+	function: Function,
+}
+
+impl NativeFun for BindSelfImpl {
+	fn name(&self) -> &'static str { "std.bind_self<impl>" }
+
 	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
 		context.call(self.obj.copy(), &self.function, context.args_start)
 	}
@@ -0,0 +1,47 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Native,
+	NativeFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Case::Upper, 1, "Convert the ASCII letters of a string to uppercase.") }
+inventory::submit!{ Native::new(Case::Lower, 1, "Convert the ASCII letters of a string to lowercase.") }
+
+/// Case conversion of strings. Only ASCII letters are converted, leaving other bytes
+/// untouched, so that strings need not be valid UTF-8.
+#[derive(Trace, Finalize)]
+enum Case {
+	Upper,
+	Lower,
+}
+
+impl NativeFun for Case {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Upper => "std.to_uppercase",
+			Self::Lower => "std.to_lowercase",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let converted = match self {
+					Self::Upper => string.as_bytes().to_ascii_uppercase(),
+					Self::Lower => string.as_bytes().to_ascii_lowercase(),
+				};
+
+				Ok(Str::from(converted).into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -1,18 +1,18 @@
 use gc::{Finalize, Trace};
 
-use crate::fmt;
+use crate::{fmt, term::color};
 
 use super::{
 	CallContext,
 	Error,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Catch) }
+inventory::submit!{ Native::new(Catch, 1, "Call a function, converting a panic into an error value.") }
 
 #[derive(Trace, Finalize)]
 struct Catch;
@@ -28,7 +28,7 @@ impl NativeFun for Catch {
 		let fun = match context.args() {
 			[ Value::Function(fun) ] => fun.copy(),
 
-			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.arg_pos(0))),
 			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		};
 
@@ -41,10 +41,15 @@ impl NativeFun for Catch {
 		match result {
 			Ok(value) => Ok(value),
 
+			// Limits imposed by the host must not be circumvented by the script.
+			Err(panic) if panic.is_fatal() => Err(panic),
+
 			Err(panic) => {
-				let description = format!(
-					"caught panic: {}",
-					fmt::Show(panic, context.interner()),
+				let description = color::plain(
+					|| format!(
+						"caught panic: {}",
+						fmt::Show(panic.untraced(), context.interner()),
+					)
 				);
 
 				Ok(
@@ -1,18 +1,23 @@
-use std::ffi::OsStr;
+use std::{
+	ffi::OsStr,
+	path::{Path, PathBuf},
+};
 
 use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Cd) }
+inventory::submit!{ Native::new(Cd, 1, "Change the working directory of the script, returning the previous one.") }
 
+/// Change the working directory of the script, returning the previous one.
+/// The process' working directory is left untouched.
 #[derive(Trace, Finalize)]
 struct Cd;
 
@@ -20,15 +25,29 @@ impl NativeFun for Cd {
 	fn name(&self) -> &'static str { "std.cd" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		match context.args() {
-			[ Value::String(ref string) ] => Ok(
-				std::env
-					::set_current_dir(AsRef::<OsStr>::as_ref(string))
-					.into()
-			),
-
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		let path = match context.args() {
+			[ Value::String(ref string) ] => string.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let previous = context.runtime
+			.cwd()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let target = previous
+			.join(Path::new(AsRef::<OsStr>::as_ref(&path)))
+			.canonicalize()
+			.ok()
+			.filter(|target| target.is_dir());
+
+		match target {
+			Some(target) => {
+				context.runtime.set_cwd(target);
+				Ok(PathBuf::into_os_string(previous).into())
+			},
+
+			None => Err(Panic::value_error(Value::String(path), "existing directory", context.pos)),
 		}
 	}
 }
@@ -0,0 +1,62 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Cmp, 2, "Compare two values, resulting in -1, 0 or 1.") }
+
+/// Compare two values, resulting in -1, 0 or 1. The order is consistent with std.eq, so
+/// an int comes before an equal float, and NaN before every other number.
+#[derive(Trace, Finalize)]
+struct Cmp;
+
+impl NativeFun for Cmp {
+	fn name(&self) -> &'static str { "std.cmp" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ left, right ] => {
+				// The left value is not ordered if it can't even be compared with itself.
+				let ordering = left
+					.compare(right)
+					.map_err(|value| {
+						let ix = if left.compare(left).is_err() { 0 } else { 1 };
+						Panic::type_error(value, ORDERED_TYPES, context.arg_pos(ix))
+					})?;
+
+				Ok(Value::Int(ordering as i64))
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// The types supported by `Value::compare`.
+pub const ORDERED_TYPES: &str = "int, float, string, byte, bool, nil or array";
+
+
+inventory::submit!{ Native::new(Eq, 2, "Whether two values are the same, as dict keys. Unlike ==, NaN equals itself.") }
+
+/// Whether two values are the same, as dict keys. This differs from the equality
+/// operator only in that NaN equals itself.
+#[derive(Trace, Finalize)]
+struct Eq;
+
+impl NativeFun for Eq {
+	fn name(&self) -> &'static str { "std.eq" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ left, right ] => Ok(left.key_eq(right).into()),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
@@ -1,15 +1,17 @@
+use bstr::ByteSlice;
+
 use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Contains) }
+inventory::submit! { Native::new(Contains, 2, "Whether a string contains a char or substring, an array a value, or a dict a key.") }
 
 #[derive(Trace, Finalize)]
 struct Contains;
@@ -24,9 +26,16 @@ impl NativeFun for Contains {
 			[ Value::Dict(ref dict), key ] => Ok(dict.contains(key).into()),
 
 			[ Value::String(ref string), Value::Byte(byte) ] => Ok(string.contains(*byte).into()),
-			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "char", context.pos)),
-
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "string ,array or dict", context.pos)),
+			// Substrings are matched as raw bytes, so strings need not be valid UTF-8.
+			[ Value::String(ref string), Value::String(ref needle) ] => Ok(
+				string
+					.as_bytes()
+					.contains_str(needle)
+					.into()
+			),
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "char or string", context.arg_pos(1))),
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string ,array or dict", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
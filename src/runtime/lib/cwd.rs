@@ -4,15 +4,16 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Cwd) }
+inventory::submit! { Native::new(Cwd, 0, "The working directory of the script.") }
 
+/// The working directory of the script, which is tracked by the runtime.
 #[derive(Trace, Finalize)]
 struct Cwd;
 
@@ -26,8 +27,8 @@ impl NativeFun for Cwd {
 		}
 
 		Ok(
-			std::env
-				::current_dir()
+			context.runtime
+				.cwd()
 				.map(PathBuf::into_os_string)
 				.into()
 		)
@@ -0,0 +1,483 @@
+use std::{
+	io::Write,
+	time::UNIX_EPOCH,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	Native,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Now, 0, "The current date in local time.") }
+inventory::submit!{ Native::new(Utc, 0, "The current date in UTC.") }
+inventory::submit!{ Native::new(Format, 2, "Format a date dict or an epoch int.") }
+inventory::submit!{ Native::new(Parse, 2, "Parse a date string, returning its epoch int, or nil if it does not match.") }
+
+
+/// Date dict keys.
+mod keys {
+	use super::Value;
+
+	thread_local! {
+		pub static YEAR: Value = "year".into();
+		pub static MONTH: Value = "month".into();
+		pub static DAY: Value = "day".into();
+		pub static HOUR: Value = "hour".into();
+		pub static MINUTE: Value = "minute".into();
+		pub static SECOND: Value = "second".into();
+		pub static WEEKDAY: Value = "weekday".into();
+		pub static OFFSET: Value = "offset".into();
+	}
+}
+
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+
+/// A civil date and time, with an offset from UTC.
+/// This is a minimal proleptic gregorian calendar, which should suffice for most scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Date {
+	year: i64,
+	/// 1 to 12.
+	month: i64,
+	/// 1 to 31.
+	day: i64,
+	hour: i64,
+	minute: i64,
+	second: i64,
+	/// Offset east of UTC, in seconds.
+	offset: i64,
+}
+
+
+impl Date {
+	/// Whether the given year is a leap year.
+	fn is_leap_year(year: i64) -> bool {
+		year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+	}
+
+
+	/// The amount of days in the given month.
+	fn days_in_month(year: i64, month: i64) -> i64 {
+		match month {
+			2 if Self::is_leap_year(year) => 29,
+			2 => 28,
+			4 | 6 | 9 | 11 => 30,
+			_ => 31,
+		}
+	}
+
+
+	/// Days since the Unix epoch of the given civil date.
+	fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+		// Shift the year to start in march, so that the leap day is the last of the year.
+		let year = if month <= 2 { year - 1 } else { year };
+		let era = year.div_euclid(400);
+		let year_of_era = year.rem_euclid(400);
+		let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+		let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+		era * 146097 + day_of_era - 719468
+	}
+
+
+	/// Civil date of the given amount of days since the Unix epoch.
+	fn civil_from_days(days: i64) -> (i64, i64, i64) {
+		let days = days + 719468;
+		let era = days.div_euclid(146097);
+		let day_of_era = days.rem_euclid(146097);
+		let year_of_era =
+			(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+		let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+		let shifted_month = (5 * day_of_year + 2) / 153;
+		let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+		let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+		let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+		(year, month, day)
+	}
+
+
+	/// Build a date from seconds since the Unix epoch, applying the given offset.
+	fn from_epoch(epoch: i64, offset: i64) -> Self {
+		let local = epoch + offset;
+		let days = local.div_euclid(SECONDS_PER_DAY);
+		let time = local.rem_euclid(SECONDS_PER_DAY);
+		let (year, month, day) = Self::civil_from_days(days);
+
+		Self {
+			year,
+			month,
+			day,
+			hour: time / 3600,
+			minute: time % 3600 / 60,
+			second: time % 60,
+			offset,
+		}
+	}
+
+
+	/// Seconds since the Unix epoch.
+	fn epoch(&self) -> i64 {
+		Self::days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+			+ self.hour * 3600
+			+ self.minute * 60
+			+ self.second
+			- self.offset
+	}
+
+
+	/// Day of the week, from 0 (sunday) to 6 (saturday).
+	fn weekday(&self) -> i64 {
+		// The Unix epoch was a thursday.
+		(Self::days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7)
+	}
+
+
+	/// Whether all fields are in their valid ranges.
+	fn is_valid(&self) -> bool {
+		(1 ..= 12).contains(&self.month)
+			&& (1 ..= Self::days_in_month(self.year, self.month)).contains(&self.day)
+			&& (0 .. 24).contains(&self.hour)
+			&& (0 .. 60).contains(&self.minute)
+			&& (0 .. 60).contains(&self.second)
+			&& (-SECONDS_PER_DAY + 1 .. SECONDS_PER_DAY).contains(&self.offset)
+	}
+
+
+	/// Convert to a dict value.
+	fn to_value(self) -> Value {
+		let mut dict = DictMap::default();
+
+		let mut insert = |key: &'static std::thread::LocalKey<Value>, value: i64| {
+			key.with(|key| dict.insert(key.copy(), value.into()));
+		};
+
+		insert(&keys::YEAR, self.year);
+		insert(&keys::MONTH, self.month);
+		insert(&keys::DAY, self.day);
+		insert(&keys::HOUR, self.hour);
+		insert(&keys::MINUTE, self.minute);
+		insert(&keys::SECOND, self.second);
+		insert(&keys::WEEKDAY, self.weekday());
+		insert(&keys::OFFSET, self.offset);
+
+		Dict::new(dict).into()
+	}
+
+
+	/// Convert from a date dict or an epoch int.
+	/// Epochs are converted in UTC.
+	fn from_value(value: &Value, context: &CallContext) -> Result<Self, Panic> {
+		let dict = match value {
+			Value::Int(epoch) => return Ok(Self::from_epoch(*epoch, 0)),
+			Value::Dict(dict) => dict,
+			other => return Err(Panic::type_error(other.copy(), "dict or int", context.arg_pos(0))),
+		};
+
+		let field = |key: &'static std::thread::LocalKey<Value>, default: Option<i64>| {
+			match (key.with(|key| dict.get(key)), default) {
+				(Ok(Value::Int(value)), _) => Ok(value),
+				(Err(_), Some(default)) => Ok(default),
+				(Err(_), None) => Err(
+					Panic::index_out_of_bounds(key.with(Value::copy), context.arg_pos(0))
+				),
+				(Ok(other), _) => Err(Panic::type_error(other, "int", context.arg_pos(0))),
+			}
+		};
+
+		let date = Self {
+			year: field(&keys::YEAR, None)?,
+			month: field(&keys::MONTH, None)?,
+			day: field(&keys::DAY, None)?,
+			hour: field(&keys::HOUR, Some(0))?,
+			minute: field(&keys::MINUTE, Some(0))?,
+			second: field(&keys::SECOND, Some(0))?,
+			offset: field(&keys::OFFSET, Some(0))?,
+		};
+
+		if date.is_valid() {
+			Ok(date)
+		} else {
+			Err(Panic::value_error(value.copy(), "valid date", context.arg_pos(0)))
+		}
+	}
+
+
+	/// Format according to a strftime-like format string.
+	/// Returns None if the format string contains unsupported directives.
+	fn format(&self, fmt: &[u8]) -> Option<Vec<u8>> {
+		let mut output = Vec::with_capacity(fmt.len() * 2);
+		let mut iter = fmt.iter();
+
+		// Writing to a vec never fails.
+		while let Some(&byte) = iter.next() {
+			if byte != b'%' {
+				output.push(byte);
+				continue;
+			}
+
+			match iter.next()? {
+				b'Y' => write!(output, "{:04}", self.year),
+				b'm' => write!(output, "{:02}", self.month),
+				b'd' => write!(output, "{:02}", self.day),
+				b'H' => write!(output, "{:02}", self.hour),
+				b'M' => write!(output, "{:02}", self.minute),
+				b'S' => write!(output, "{:02}", self.second),
+				b'z' => {
+					let sign = if self.offset < 0 { '-' } else { '+' };
+					let offset = self.offset.abs();
+					write!(output, "{}{:02}{:02}", sign, offset / 3600, offset % 3600 / 60)
+				},
+				b'%' => write!(output, "%"),
+				_ => return None,
+			}.ok()?;
+		}
+
+		Some(output)
+	}
+
+
+	/// Parse according to a strftime-like format string.
+	/// Fields missing from the format default to the beginning of their range.
+	/// Returns Err if the format string contains unsupported directives, and Ok(None) if
+	/// the input does not match the format.
+	fn parse(input: &[u8], fmt: &[u8]) -> Result<Option<Self>, ()> {
+		/// Parse an unsigned number of at most the given amount of digits.
+		fn number(input: &mut &[u8], max_digits: usize) -> Option<i64> {
+			let digits = input
+				.iter()
+				.take(max_digits)
+				.take_while(|byte| byte.is_ascii_digit())
+				.count();
+
+			if digits == 0 {
+				return None;
+			}
+
+			let (number, rest) = input.split_at(digits);
+			*input = rest;
+
+			Some(
+				number
+					.iter()
+					.fold(0, |acc, digit| acc * 10 + (digit - b'0') as i64)
+			)
+		}
+
+		let mut date = Self {
+			year: 1970,
+			month: 1,
+			day: 1,
+			hour: 0,
+			minute: 0,
+			second: 0,
+			offset: 0,
+		};
+
+		let mut input = input;
+		let mut iter = fmt.iter();
+
+		macro_rules! expect {
+			($value: expr) => {
+				match $value {
+					Some(value) => value,
+					None => return Ok(None),
+				}
+			}
+		}
+
+		while let Some(&byte) = iter.next() {
+			if byte != b'%' {
+				let (&first, rest) = expect!(input.split_first());
+				if first != byte {
+					return Ok(None);
+				}
+				input = rest;
+				continue;
+			}
+
+			match iter.next().ok_or(())? {
+				b'Y' => {
+					let negative = input.first() == Some(&b'-');
+					if negative {
+						input = &input[1..];
+					}
+					let year = expect!(number(&mut input, 4));
+					date.year = if negative { -year } else { year };
+				},
+				b'm' => date.month = expect!(number(&mut input, 2)),
+				b'd' => date.day = expect!(number(&mut input, 2)),
+				b'H' => date.hour = expect!(number(&mut input, 2)),
+				b'M' => date.minute = expect!(number(&mut input, 2)),
+				b'S' => date.second = expect!(number(&mut input, 2)),
+				b'z' => {
+					let (&sign, rest) = expect!(input.split_first());
+					input = rest;
+
+					date.offset = match sign {
+						b'Z' => 0,
+						b'+' | b'-' => {
+							if input.len() < 4 || !input[..4].iter().all(u8::is_ascii_digit) {
+								return Ok(None);
+							}
+							let hours = expect!(number(&mut input, 2));
+							let minutes = expect!(number(&mut input, 2));
+							let offset = hours * 3600 + minutes * 60;
+							if sign == b'-' { -offset } else { offset }
+						},
+						_ => return Ok(None),
+					};
+				},
+				b'%' => {
+					let (&first, rest) = expect!(input.split_first());
+					if first != b'%' {
+						return Ok(None);
+					}
+					input = rest;
+				},
+				_ => return Err(()),
+			}
+		}
+
+		if input.is_empty() && date.is_valid() {
+			Ok(Some(date))
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+
+/// Get the current time in seconds since the Unix epoch.
+fn current_epoch(context: &CallContext) -> i64 {
+	match context.runtime.clock.now().duration_since(UNIX_EPOCH) {
+		Ok(duration) => duration.as_secs() as i64,
+		Err(error) => -(error.duration().as_secs() as i64),
+	}
+}
+
+
+/// Get the local timezone offset, in seconds east of UTC, for the given epoch.
+#[cfg(feature = "system")]
+fn local_offset(epoch: i64) -> i64 {
+	let time = epoch as libc::time_t;
+	// SAFETY: tm is a plain C struct, for which zeroed memory is a valid value, and
+	// localtime_r is thread safe.
+	unsafe {
+		let mut tm: libc::tm = std::mem::zeroed();
+		if libc::localtime_r(&time, &mut tm).is_null() {
+			0
+		} else {
+			tm.tm_gmtoff as i64
+		}
+	}
+}
+
+
+/// Without the system feature there is no timezone database, so local time is UTC.
+#[cfg(not(feature = "system"))]
+fn local_offset(_epoch: i64) -> i64 {
+	0
+}
+
+
+/// The current date in local time.
+#[derive(Trace, Finalize)]
+struct Now;
+
+impl NativeFun for Now {
+	fn name(&self) -> &'static str { "std.date.now" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let epoch = current_epoch(&context);
+
+		Ok(Date::from_epoch(epoch, local_offset(epoch)).to_value())
+	}
+}
+
+
+/// The current date in UTC.
+#[derive(Trace, Finalize)]
+struct Utc;
+
+impl NativeFun for Utc {
+	fn name(&self) -> &'static str { "std.date.utc" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(Date::from_epoch(current_epoch(&context), 0).to_value())
+	}
+}
+
+
+/// Format a date dict or an epoch int.
+/// Supported directives are %Y %m %d %H %M %S %z and %%.
+#[derive(Trace, Finalize)]
+struct Format;
+
+impl NativeFun for Format {
+	fn name(&self) -> &'static str { "std.date.format" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ date, fmt @ Value::String(ref string) ] => {
+				let date = Date::from_value(date, &context)?;
+
+				date
+					.format(string.as_bytes())
+					.map(|output| Str::from(output).into())
+					.ok_or_else(|| Panic::value_error(fmt.copy(), "valid date format", context.arg_pos(1)))
+			},
+
+			[ _, other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Parse a date string, returning it's epoch int, or nil if it doesn't match the format.
+/// Supported directives are %Y %m %d %H %M %S %z and %%.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.date.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref input), fmt @ Value::String(ref string) ] => {
+				let date = Date
+					::parse(input.as_bytes(), string.as_bytes())
+					.map_err(|()| Panic::value_error(fmt.copy(), "valid date format", context.arg_pos(1)))?;
+
+				Ok(date.map(|date| date.epoch()).into())
+			},
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	Array,
+	CallContext,
+	Dict,
+	FxBuildHasher,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { Native::new(DeepCopy, 1, "Copy a value, allocating fresh arrays and dicts all the way down.") }
+
+/// Copy a value, allocating fresh arrays and dicts all the way down.
+/// Collections reached more than once are copied only once, so sharing and cycles in the
+/// original are reproduced in the copy. Other values are shallow copied.
+#[derive(Trace, Finalize)]
+struct DeepCopy;
+
+impl NativeFun for DeepCopy {
+	fn name(&self) -> &'static str { "std.deep_copy" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(Copier::default().copy(value)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// The copies of the collections visited so far, by address of the original.
+#[derive(Default)]
+struct Copier {
+	copies: HashMap<*const (), Value, FxBuildHasher>,
+}
+
+impl Copier {
+	fn copy(&mut self, value: &Value) -> Value {
+		match value {
+			Value::Array(array) => {
+				if let Some(copy) = self.copies.get(&array.address()) {
+					return copy.copy();
+				}
+
+				// Register the copy before visiting the items, so that cycles terminate.
+				let copy = Array::new(Vec::with_capacity(array.len() as usize));
+				self.copies.insert(array.address(), copy.copy().into());
+
+				let items: Vec<Value> = array
+					.borrow()
+					.iter()
+					.map(Value::copy)
+					.collect();
+
+				for item in items {
+					let item = self.copy(&item);
+					copy.borrow_mut().push(item);
+				}
+
+				copy.into()
+			},
+
+			Value::Dict(dict) => {
+				if let Some(copy) = self.copies.get(&dict.address()) {
+					return copy.copy();
+				}
+
+				let copy = Dict::default();
+				self.copies.insert(dict.address(), copy.copy().into());
+
+				let entries: Vec<(Value, Value)> = dict
+					.borrow()
+					.iter()
+					.map(|(key, value)| (key.copy(), value.copy()))
+					.collect();
+
+				for (key, value) in entries {
+					let key = self.copy(&key);
+					let value = self.copy(&value);
+					copy.insert(key, value);
+				}
+
+				copy.into()
+			},
+
+			other => other.copy(),
+		}
+	}
+}
@@ -4,15 +4,15 @@ use crate::runtime::value::Error;
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Env) }
-inventory::submit! { RustFun::from(Export) }
+inventory::submit! { Native::new(Env, 1, "The value of an environment variable, or nil if unset.") }
+inventory::submit! { Native::new(Export, 2, "Set an environment variable.") }
 
 #[derive(Trace, Finalize)]
 struct Env;
@@ -29,7 +29,7 @@ impl NativeFun for Env {
 					.unwrap_or(Value::Nil)
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -62,8 +62,8 @@ impl NativeFun for Export {
 				Ok(ret)
 			},
 
-			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
 		}
@@ -3,14 +3,14 @@ use gc::{Finalize, Trace};
 use super::{
 	CallContext,
 	Error,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(ErrorFun) }
+inventory::submit! { Native::new(ErrorFun, 2, "Create an error value with a description and context.") }
 
 #[derive(Trace, Finalize)]
 struct ErrorFun;
@@ -26,7 +26,7 @@ impl NativeFun for ErrorFun {
 					.into()
 			),
 
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
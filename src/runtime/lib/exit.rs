@@ -0,0 +1,34 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Native,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { Native::new(Exit, 1, "Terminate the script with the given exit status.") }
+
+/// Terminate the script with the given exit status, from 0 to 255. The script is unwound
+/// rather than the process exiting immediately, so that the host decides how to exit.
+#[derive(Trace, Finalize)]
+struct Exit;
+
+impl NativeFun for Exit {
+	fn name(&self) -> &'static str { "std.exit" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(status @ 0 ..= 255) ] => Err(Panic::exit(*status as u8, context.pos)),
+
+			[ value @ Value::Int(_) ] => Err(
+				Panic::value_error(value.copy(), "exit status from 0 to 255", context.arg_pos(0))
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
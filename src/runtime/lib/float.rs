@@ -3,13 +3,13 @@ use gc::{Finalize, Trace};
 use super::{
 	CallContext,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Float) }
+inventory::submit!{ Native::new(Float, 1, "Convert an int or a numeric string to a float.") }
 
 #[derive(Trace, Finalize)]
 struct Float;
@@ -45,7 +45,7 @@ impl NativeFun for Float {
 				Ok(Value::from(float))
 			}
 
-			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -0,0 +1,856 @@
+#![cfg(feature = "system")]
+
+use std::{
+	cell::RefCell,
+	convert::TryFrom,
+	ffi::OsStr,
+	fs::{self, File, OpenOptions},
+	io::{self, BufRead, BufReader, Read as _, Seek, SeekFrom, Write as _},
+	path::{Path, PathBuf},
+	rc::Rc,
+	time::UNIX_EPOCH,
+};
+
+use gc::{Finalize, Gc, Trace};
+
+use super::{
+	keys,
+	temp::{self, TempDir},
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Native,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Query::Exists, 1, "Whether the path exists.") }
+inventory::submit!{ Native::new(Query::IsFile, 1, "Whether the path is a regular file.") }
+inventory::submit!{ Native::new(Query::IsDir, 1, "Whether the path is a directory.") }
+inventory::submit!{ Native::new(Query::IsSymlink, 1, "Whether the path is a symbolic link.") }
+inventory::submit!{ Native::new(Query::Size, 1, "The size of a file in bytes.") }
+inventory::submit!{ Native::new(Query::Modified, 1, "The modification time of a file, in seconds since the Unix epoch.") }
+inventory::submit!{ Native::new(Read, 1, "Read a whole file as a string.") }
+inventory::submit!{ Native::new(Write::Truncate, 2, "Write a string to a file, replacing its contents.") }
+inventory::submit!{ Native::new(Write::Append, 2, "Append a string to a file.") }
+inventory::submit!{ Native::new(Open, 2, "Open a file in the given mode, returning a handle.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.mkdir", op: |path| fs::create_dir(path).map(nil) }, 1, "Create a directory.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.mkdir_all", op: |path| fs::create_dir_all(path).map(nil) }, 1, "Create a directory and all its missing parents.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.remove", op: |path| fs::remove_file(path).map(nil) }, 1, "Remove a file.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.remove_dir", op: |path| fs::remove_dir(path).map(nil) }, 1, "Remove an empty directory.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.remove_all", op: |path| fs::remove_dir_all(path).map(nil) }, 1, "Remove a directory and all its contents.") }
+inventory::submit!{ Native::new(PathFun { name: "std.fs.list", op: list }, 1, "The names of the entries of a directory.") }
+inventory::submit!{ Native::new(PathPairFun { name: "std.fs.copy", op: copy }, 2, "Copy a file to the destination path.") }
+inventory::submit!{ Native::new(PathPairFun { name: "std.fs.rename", op: |src, dst| fs::rename(src, dst).map(nil) }, 2, "Move a file or directory to the destination path.") }
+inventory::submit!{ Native::new(Lines, 1, "Iterate the lines of a file.") }
+inventory::submit!{ Native::new(TempFile, 1, "Create a uniquely named temporary file, returning its path and a handle.") }
+inventory::submit!{ Native::new(MakeTempDir, 1, "Create a temporary directory, returning its path and a keep method. The directory is removed unless kept.") }
+
+
+/// Resolve a path against the working directory of the script.
+fn resolve(path: &Str, context: &CallContext) -> Result<PathBuf, Panic> {
+	let cwd = context.runtime
+		.cwd()
+		.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+	Ok(cwd.join(Path::new(AsRef::<OsStr>::as_ref(path))))
+}
+
+
+/// Build the result of an iteration step, which is finished if there's no value.
+fn iteration(next: Option<Value>) -> Value {
+	let mut iteration = DictMap::default();
+
+	keys::FINISHED.with(
+		|finished| iteration.insert(finished.copy(), next.is_none().into())
+	);
+
+	if let Some(next) = next {
+		keys::VALUE.with(|value| iteration.insert(value.copy(), next));
+	}
+
+	Dict::new(iteration).into()
+}
+
+
+/// Build an error value for a failed filesystem operation, carrying the path.
+fn error(error: io::Error, path: &Str) -> Value {
+	Error::new(error.to_string().into(), Value::String(path.copy())).into()
+}
+
+
+/// Filesystem metadata queries.
+/// Predicates are false for nonexistent paths, but other errors result in error values.
+#[derive(Trace, Finalize)]
+enum Query {
+	Exists,
+	IsFile,
+	IsDir,
+	IsSymlink,
+	/// Size in bytes.
+	Size,
+	/// Modification time, in seconds since the Unix epoch.
+	Modified,
+}
+
+impl Query {
+	fn query(&self, path: &Path) -> io::Result<Value> {
+		let metadata = match self {
+			Self::IsSymlink => fs::symlink_metadata(path),
+			_ => fs::metadata(path),
+		};
+
+		let metadata = match (self, metadata) {
+			(Self::Size, result) | (Self::Modified, result) => result?,
+			(_, Err(error)) if error.kind() == io::ErrorKind::NotFound => return Ok(false.into()),
+			(_, result) => result?,
+		};
+
+		Ok(
+			match self {
+				Self::Exists => true.into(),
+				Self::IsFile => metadata.is_file().into(),
+				Self::IsDir => metadata.is_dir().into(),
+				Self::IsSymlink => metadata.file_type().is_symlink().into(),
+
+				Self::Size => i64
+					::try_from(metadata.len())
+					.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large"))?
+					.into(),
+
+				Self::Modified => match metadata.modified()?.duration_since(UNIX_EPOCH) {
+					Ok(duration) => duration.as_secs_f64().into(),
+					Err(error) => (-error.duration().as_secs_f64()).into(),
+				},
+			}
+		)
+	}
+}
+
+impl NativeFun for Query {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Exists => "std.fs.exists",
+			Self::IsFile => "std.fs.is_file",
+			Self::IsDir => "std.fs.is_dir",
+			Self::IsSymlink => "std.fs.is_symlink",
+			Self::Size => "std.fs.size",
+			Self::Modified => "std.fs.modified",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let path = resolve(string, &context)?;
+
+				Ok(
+					self
+						.query(&path)
+						.unwrap_or_else(|err| error(err, string))
+				)
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Read a whole file as a string.
+#[derive(Trace, Finalize)]
+struct Read;
+
+impl NativeFun for Read {
+	fn name(&self) -> &'static str { "std.fs.read" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let path = resolve(string, &context)?;
+
+				// fs::read allocates the buffer once, based on the file's size.
+				Ok(
+					match fs::read(path) {
+						Ok(contents) => contents.into_boxed_slice().into(),
+						Err(err) => error(err, string),
+					}
+				)
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Write a string or char to a file, creating it if necessary.
+#[derive(Trace, Finalize)]
+enum Write {
+	/// Replace the file's contents.
+	Truncate,
+	/// Append to the file's contents.
+	Append,
+}
+
+impl Write {
+	fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+		let mut file = OpenOptions::new()
+			.create(true)
+			.write(true)
+			.truncate(matches!(self, Self::Truncate))
+			.append(matches!(self, Self::Append))
+			.open(path)?;
+
+		file.write_all(contents)
+	}
+}
+
+impl NativeFun for Write {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Truncate => "std.fs.write",
+			Self::Append => "std.fs.append",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, result) = match context.args() {
+			[ Value::String(ref path), Value::String(ref contents) ] => {
+				let (path, contents) = (path.copy(), contents.copy());
+				let result = self.write(&resolve(&path, &context)?, contents.as_bytes());
+				(path, result)
+			},
+
+			[ Value::String(ref path), Value::Byte(byte) ] => {
+				let (path, byte) = (path.copy(), *byte);
+				let result = self.write(&resolve(&path, &context)?, &[ byte ]);
+				(path, result)
+			},
+
+			[ Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "string or char", context.arg_pos(1))
+			),
+
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Ok(
+			match result {
+				Ok(()) => Value::default(),
+				Err(err) => error(err, &path),
+			}
+		)
+	}
+}
+
+
+/// Open a file, returning a handle with methods for reading and writing.
+/// Supported modes are "r", "w", "a" and "r+".
+#[derive(Trace, Finalize)]
+struct Open;
+
+impl Open {
+	fn options(mode: &[u8]) -> Option<OpenOptions> {
+		let mut options = OpenOptions::new();
+
+		match mode {
+			b"r" => options.read(true),
+			b"w" => options.write(true).create(true).truncate(true),
+			b"a" => options.append(true).create(true),
+			b"r+" => options.read(true).write(true),
+			_ => return None,
+		};
+
+		Some(options)
+	}
+
+
+	fn handle(file: File) -> Value {
+		let file = Gc::new(OpenFile(RefCell::new(Some(BufReader::new(file)))));
+
+		let methods = [
+			Method::Read,
+			Method::ReadLine,
+			Method::Write,
+			Method::Seek,
+			Method::Flush,
+			Method::Close,
+			Method::Iter,
+		];
+
+		let handle: DictMap = methods
+			.iter()
+			.map(
+				|method| (
+					method.key().into(),
+					FileMethod { method: *method, file: file.clone() }.into()
+				)
+			)
+			.collect();
+
+		Dict::new(handle).into()
+	}
+}
+
+impl NativeFun for Open {
+	fn name(&self) -> &'static str { "std.fs.open" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref path), Value::String(ref mode) ] => {
+				let (path, mode) = (path.copy(), mode.copy());
+
+				let options = Self
+					::options(mode.as_bytes())
+					.ok_or_else(
+						|| Panic::value_error(Value::String(mode), "file mode (r, w, a or r+)", context.arg_pos(1))
+					)?;
+
+				Ok(
+					match options.open(resolve(&path, &context)?) {
+						Ok(file) => Self::handle(file),
+						Err(err) => error(err, &path),
+					}
+				)
+			},
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// A file shared by the methods of a handle.
+/// The file is closed by the close method, or when the handle is garbage collected.
+#[derive(Finalize)]
+struct OpenFile(RefCell<Option<BufReader<File>>>);
+
+/// OpenFile has no garbage-collected fields.
+unsafe impl Trace for OpenFile {
+	gc::unsafe_empty_trace!();
+}
+
+
+/// Methods of file handles.
+#[derive(Debug, Clone, Copy)]
+enum Method {
+	/// Read up to the given number of bytes, or nil at the end of the file.
+	Read,
+	/// Read a line without the trailing newline, or nil at the end of the file.
+	ReadLine,
+	/// Write a string or char.
+	Write,
+	/// Move to the given offset from "start", "current" or "end", returning the new position.
+	Seek,
+	Flush,
+	Close,
+	/// Iterate the remaining lines of the file.
+	Iter,
+	/// The iterator returned by Iter.
+	Next,
+}
+
+/// Method has no garbage-collected fields.
+unsafe impl Trace for Method {
+	gc::unsafe_empty_trace!();
+}
+
+impl Finalize for Method { }
+
+impl Method {
+	fn key(&self) -> &'static str {
+		match self {
+			Self::Read => "read",
+			Self::ReadLine => "read_line",
+			Self::Write => "write",
+			Self::Seek => "seek",
+			Self::Flush => "flush",
+			Self::Close => "close",
+			Self::Iter => "iter",
+			Self::Next => "next",
+		}
+	}
+
+
+	fn read_line(file: &mut BufReader<File>) -> io::Result<Option<Value>> {
+		let mut line = Vec::new();
+
+		if file.read_until(b'\n', &mut line)? == 0 {
+			return Ok(None);
+		}
+
+		if line.last() == Some(&b'\n') {
+			line.pop();
+		}
+
+		Ok(Some(line.into_boxed_slice().into()))
+	}
+
+
+	fn write(file: &mut BufReader<File>, contents: &[u8]) -> io::Result<()> {
+		// Discard buffered input, so that the write happens at the logical position.
+		if !file.buffer().is_empty() {
+			let position = file.stream_position()?;
+			file.seek(SeekFrom::Start(position))?;
+		}
+
+		file.get_mut().write_all(contents)
+	}
+
+
+	fn call(&self, file: &mut BufReader<File>, context: &CallContext) -> Result<io::Result<Value>, Panic> {
+		let pos = context.pos.copy();
+
+		let result = match (self, context.args()) {
+			(Self::Read, [ Value::Int(size) ]) => {
+				let size = u64
+					::try_from(*size)
+					.map_err(|_| Panic::value_error(Value::Int(*size), "non-negative int", pos))?;
+
+				let mut contents = Vec::new();
+
+				file
+					.by_ref()
+					.take(size)
+					.read_to_end(&mut contents)
+					.map(
+						|read| if read == 0 && size > 0 {
+							Value::default()
+						} else {
+							contents.into_boxed_slice().into()
+						}
+					)
+			},
+			(Self::Read, [ other ]) => return Err(Panic::type_error(other.copy(), "int", pos)),
+
+			(Self::ReadLine, []) => Self
+				::read_line(file)
+				.map(Value::from),
+
+			(Self::Write, [ Value::String(ref string) ]) => Self
+				::write(file, string.as_bytes())
+				.map(|()| Value::default()),
+			(Self::Write, [ Value::Byte(byte) ]) => Self
+				::write(file, &[ *byte ])
+				.map(|()| Value::default()),
+			(Self::Write, [ other ]) => return Err(Panic::type_error(other.copy(), "string or char", pos)),
+
+			(Self::Seek, [ Value::Int(offset), Value::String(ref whence) ]) => {
+				let position = match whence.as_bytes() {
+					b"start" => SeekFrom::Start(
+						u64
+							::try_from(*offset)
+							.map_err(|_| Panic::value_error(Value::Int(*offset), "non-negative int", pos.copy()))?
+					),
+					b"current" => SeekFrom::Current(*offset),
+					b"end" => SeekFrom::End(*offset),
+					_ => return Err(
+						Panic::value_error(Value::String(whence.copy()), "start, current or end", pos)
+					),
+				};
+
+				file
+					.seek(position)
+					.map(|position| Value::Int(position as i64))
+			},
+			(Self::Seek, [ Value::Int(_), other ]) | (Self::Seek, [ other, _ ]) => return Err(
+				Panic::type_error(other.copy(), "int and string", pos)
+			),
+
+			(Self::Flush, []) => file
+				.get_mut()
+				.flush()
+				.map(|()| Value::default()),
+
+			(Self::Next, []) => Self::read_line(file).map(iteration),
+
+			(Self::Close, []) | (Self::Iter, []) => unreachable!("method handled by caller"),
+
+			(Self::Seek, args) => return Err(Panic::invalid_args(args.len() as u32, 2, pos)),
+			(Self::Read, args) | (Self::Write, args) => return Err(
+				Panic::invalid_args(args.len() as u32, 1, pos)
+			),
+			(_, args) => return Err(Panic::invalid_args(args.len() as u32, 0, pos)),
+		};
+
+		Ok(result)
+	}
+}
+
+
+/// A method bound to an open file.
+#[derive(Trace, Finalize)]
+struct FileMethod {
+	method: Method,
+	file: Gc<OpenFile>,
+}
+
+impl NativeFun for FileMethod {
+	fn name(&self) -> &'static str {
+		match self.method {
+			Method::Read => "std.fs.open<read>",
+			Method::ReadLine => "std.fs.open<read_line>",
+			Method::Write => "std.fs.open<write>",
+			Method::Seek => "std.fs.open<seek>",
+			Method::Flush => "std.fs.open<flush>",
+			Method::Close => "std.fs.open<close>",
+			Method::Iter => "std.fs.open<iter>",
+			Method::Next => "std.fs.open<next>",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let mut file = self.file.0.borrow_mut();
+
+		match (self.method, context.args()) {
+			// Closing is idempotent.
+			(Method::Close, []) => {
+				let result = file
+					.take()
+					.map(|mut file| file.get_mut().flush())
+					.unwrap_or(Ok(()));
+
+				return Ok(result.map(|()| Value::default()).into());
+			},
+
+			(Method::Iter, []) if file.is_some() => return Ok(
+				FileMethod { method: Method::Next, file: self.file.clone() }.into()
+			),
+
+			(Method::Close, args) | (Method::Iter, args) if !args.is_empty() => return Err(
+				Panic::invalid_args(args.len() as u32, 0, context.pos)
+			),
+
+			_ => (),
+		}
+
+		let file = file
+			.as_mut()
+			.ok_or_else(|| Panic::closed_file(context.pos.copy()))?;
+
+		Ok(self.method.call(file, &context)?.into())
+	}
+}
+
+
+/// Discard the result of a successful operation.
+fn nil<T>(_: T) -> Value {
+	Value::default()
+}
+
+
+/// List the names of the entries in a directory, sorted.
+fn list(path: &Path) -> io::Result<Value> {
+	let mut names = fs::read_dir(path)?
+		.map(|entry| entry.map(|entry| entry.file_name()))
+		.collect::<io::Result<Vec<_>>>()?;
+
+	names.sort();
+
+	Ok(
+		names
+			.into_iter()
+			.map(Value::from)
+			.collect::<Vec<_>>()
+			.into()
+	)
+}
+
+
+/// Copy a file, returning the number of copied bytes.
+fn copy(src: &Path, dst: &Path) -> io::Result<Value> {
+	let bytes = fs::copy(src, dst)?;
+
+	i64
+		::try_from(bytes)
+		.map(Value::Int)
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large"))
+}
+
+
+/// Iterate the lines of a file, without loading it all in memory.
+#[derive(Trace, Finalize)]
+struct Lines;
+
+impl NativeFun for Lines {
+	fn name(&self) -> &'static str { "std.fs.lines" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let path = resolve(string, &context)?;
+
+				Ok(
+					match File::open(&path) {
+						Ok(file) => LinesIter {
+							path,
+							state: RefCell::new(Some((BufReader::new(file), 0))),
+						}.into(),
+						Err(err) => error(err, string),
+					}
+				)
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// The iterator returned by std.fs.lines.
+/// The file is closed as soon as the iteration finishes or stops early.
+#[derive(Finalize)]
+struct LinesIter {
+	path: PathBuf,
+	/// The open file and the current byte offset in it.
+	state: RefCell<Option<(BufReader<File>, u64)>>,
+}
+
+/// LinesIter has no garbage-collected fields.
+unsafe impl Trace for LinesIter {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for LinesIter {
+	fn name(&self) -> &'static str { "std.fs.lines<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let mut state = self.state.borrow_mut();
+
+		let (reader, offset) = match state.as_mut() {
+			Some(state) => state,
+			None => return Ok(iteration(None)),
+		};
+
+		let mut line = Vec::new();
+
+		match reader.read_until(b'\n', &mut line) {
+			Ok(0) => {
+				*state = None;
+				Ok(iteration(None))
+			},
+
+			Ok(read) => {
+				*offset += read as u64;
+
+				if line.last() == Some(&b'\n') {
+					line.pop();
+				}
+
+				Ok(iteration(Some(line.into_boxed_slice().into())))
+			},
+
+			Err(error) => {
+				let message = format!("{}: {} (at byte {})", self.path.display(), error, offset);
+				*state = None;
+				Err(Panic::io(io::Error::new(error.kind(), message), context.pos))
+			},
+		}
+	}
+
+	fn stop_iteration(&self) {
+		self.state.replace(None);
+	}
+}
+
+
+/// Temporary entry keys.
+mod temp_keys {
+	use super::Value;
+
+	thread_local! {
+		pub static PATH: Value = "path".into();
+		pub static FILE: Value = "file".into();
+		pub static KEEP: Value = "keep".into();
+	}
+}
+
+
+/// Create a uniquely named file in the temporary directory, returning its path and a
+/// writable handle. The file is not removed automatically.
+#[derive(Trace, Finalize)]
+struct TempFile;
+
+impl NativeFun for TempFile {
+	fn name(&self) -> &'static str { "std.fs.tempfile" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let prefix = match context.args() {
+			[ Value::String(ref prefix) ] => prefix.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = temp::create_unique(
+			prefix.as_ref(),
+			|path| OpenOptions::new()
+				.read(true)
+				.write(true)
+				.create_new(true)
+				.open(path)
+				.map(|file| (path.to_owned(), file))
+		);
+
+		Ok(
+			match result {
+				Ok((path, file)) => {
+					let mut dict = DictMap::default();
+					temp_keys::PATH.with(|key| dict.insert(key.copy(), path.into_os_string().into()));
+					temp_keys::FILE.with(|key| dict.insert(key.copy(), Open::handle(file)));
+					Dict::new(dict).into()
+				},
+
+				Err(err) => error(err, &prefix),
+			}
+		)
+	}
+}
+
+
+/// Create a uniquely named directory in the temporary directory, returning its path and
+/// a keep method. The directory is recursively removed when the returned value is
+/// collected, or at the end of the evaluation, unless kept.
+/// This returns a dict instead of a bare path because strings can't own resources: the
+/// directory lives as long as the keep method, so `dir.path ++ "/file"` doesn't extend it.
+#[derive(Trace, Finalize)]
+struct MakeTempDir;
+
+impl NativeFun for MakeTempDir {
+	fn name(&self) -> &'static str { "std.fs.tempdir" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let prefix = match context.args() {
+			[ Value::String(ref prefix) ] => prefix.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let dir = match TempDir::create(prefix.as_ref()) {
+			Ok(dir) => dir,
+			Err(err) => return Ok(error(err, &prefix)),
+		};
+
+		context.runtime.temp_dirs.push(Rc::downgrade(&dir));
+
+		let mut dict = DictMap::default();
+		temp_keys::PATH.with(
+			|key| dict.insert(key.copy(), dir.path().as_os_str().to_owned().into())
+		);
+		temp_keys::KEEP.with(|key| dict.insert(key.copy(), KeepTempDir(dir).into()));
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Prevent a temporary directory from being removed.
+/// This also owns the directory, so that it's removed when the handle is collected.
+#[derive(Finalize)]
+struct KeepTempDir(Rc<TempDir>);
+
+/// KeepTempDir has no garbage-collected fields.
+unsafe impl Trace for KeepTempDir {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for KeepTempDir {
+	fn name(&self) -> &'static str { "std.fs.tempdir<keep>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		self.0.keep();
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Filesystem operations on a single path.
+#[derive(Finalize)]
+struct PathFun {
+	name: &'static str,
+	op: fn(&Path) -> io::Result<Value>,
+}
+
+/// PathFun has no garbage-collected fields.
+unsafe impl Trace for PathFun {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for PathFun {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let path = resolve(string, &context)?;
+				Ok((self.op)(&path).unwrap_or_else(|err| error(err, string)))
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Filesystem operations from a source to a destination path.
+/// Errors carry both paths, as either may be the offending one.
+#[derive(Finalize)]
+struct PathPairFun {
+	name: &'static str,
+	op: fn(&Path, &Path) -> io::Result<Value>,
+}
+
+/// PathPairFun has no garbage-collected fields.
+unsafe impl Trace for PathPairFun {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for PathPairFun {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref src), Value::String(ref dst) ] => {
+				let (src, dst) = (src.copy(), dst.copy());
+
+				Ok(
+					match (self.op)(&resolve(&src, &context)?, &resolve(&dst, &context)?) {
+						Ok(value) => value,
+						Err(err) => Error::new(
+							err.to_string().into(),
+							vec![ Value::String(src), Value::String(dst) ].into(),
+						).into(),
+					}
+				)
+			},
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
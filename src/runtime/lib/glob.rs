@@ -1,31 +1,281 @@
+#![cfg(feature = "system")]
+
+use std::{
+	ffi::OsStr,
+	fs,
+	io,
+	os::unix::ffi::OsStrExt,
+	path::{Path, PathBuf},
+};
+
 use gc::{Finalize, Trace};
+use glob::{MatchOptions, Pattern};
 
 use super::{
 	CallContext,
-	RustFun,
+	Dict,
+	DictMap,
+	Native,
 	NativeFun,
 	Panic,
+	Str,
 	Value,
 	Error,
 };
 
 
-inventory::submit! { RustFun::from(Glob) }
+inventory::submit! { Native::new(Glob, 1..=2, "The paths matching a glob pattern.") }
+
+
+/// Glob option and result keys.
+mod keys {
+	use super::Value;
+
+	thread_local! {
+		pub static HIDDEN: Value = "hidden".into();
+		pub static FOLLOW_SYMLINKS: Value = "follow_symlinks".into();
+		pub static REPORT_ERRORS: Value = "report_errors".into();
+		pub static PATHS: Value = "paths".into();
+		pub static ERRORS: Value = "errors".into();
+	}
+}
+
+
+/// Options for filesystem globbing.
+#[derive(Debug, Default)]
+struct Options {
+	/// Whether wildcards should match names starting with a dot.
+	hidden: bool,
+	/// Whether recursive wildcards should descend into symlinked directories.
+	follow_symlinks: bool,
+	/// Whether to return errors found during the walk alongside the paths.
+	report_errors: bool,
+}
+
+
+impl Options {
+	fn from_dict(dict: &Dict, context: &CallContext) -> Result<Self, Panic> {
+		let option = |key: &'static std::thread::LocalKey<Value>| {
+			match key.with(|key| dict.get(key)) {
+				Ok(Value::Bool(b)) => Ok(b),
+				Ok(other) => Err(Panic::type_error(other, "bool", context.arg_pos(1))),
+				Err(_) => Ok(false),
+			}
+		};
+
+		Ok(
+			Self {
+				hidden: option(&keys::HIDDEN)?,
+				follow_symlinks: option(&keys::FOLLOW_SYMLINKS)?,
+				report_errors: option(&keys::REPORT_ERRORS)?,
+			}
+		)
+	}
+}
+
+
+/// A filesystem walk matching a glob pattern.
+struct Walk<'a> {
+	/// Directory against which relative paths are resolved.
+	root: &'a Path,
+	components: Vec<Component<'a>>,
+	options: &'a Options,
+	paths: Vec<PathBuf>,
+	errors: Vec<(PathBuf, io::Error)>,
+}
+
+
+/// A glob pattern component, delimited by slashes.
+enum Component<'a> {
+	/// A component without wildcards.
+	Literal(&'a str),
+	/// A component with wildcards.
+	Pattern(Pattern),
+	/// The recursive wildcard.
+	Recursive,
+}
+
+
+impl<'a> Walk<'a> {
+	fn new(
+		pattern: &'a str,
+		root: &'a Path,
+		options: &'a Options,
+	) -> Result<(PathBuf, Self), glob::PatternError> {
+		let base = if pattern.starts_with('/') { PathBuf::from("/") } else { PathBuf::new() };
+
+		let components = pattern
+			.split('/')
+			.filter(|component| !component.is_empty())
+			.map(
+				|component| match component {
+					"**" => Ok(Component::Recursive),
+					component if component.contains(&['*', '?', '['][..]) => Pattern
+						::new(component)
+						.map(Component::Pattern),
+					component => Ok(Component::Literal(component)),
+				}
+			)
+			.collect::<Result<_, _>>()?;
+
+		Ok(
+			(
+				base,
+				Self {
+					root,
+					components,
+					options,
+					paths: Vec::new(),
+					errors: Vec::new(),
+				}
+			)
+		)
+	}
+
+
+	fn match_options(&self) -> MatchOptions {
+		MatchOptions {
+			case_sensitive: true,
+			require_literal_separator: true,
+			require_literal_leading_dot: !self.options.hidden,
+		}
+	}
+
 
+	/// List a directory, collecting errors instead of failing.
+	fn read_dir(&mut self, path: &Path) -> Vec<fs::DirEntry> {
+		match fs::read_dir(self.root.join(path)) {
+			Ok(entries) => entries
+				.filter_map(
+					|entry| entry
+						.map_err(|error| self.errors.push((path.to_owned(), error)))
+						.ok()
+				)
+				.collect(),
+
+			Err(error) => {
+				self.errors.push((path.to_owned(), error));
+				Vec::new()
+			}
+		}
+	}
+
+
+	/// Match the components starting at the given index against the given path.
+	fn walk(&mut self, path: PathBuf, ix: usize) {
+		let is_last = ix + 1 >= self.components.len();
+
+		match self.components.get(ix) {
+			None => self.paths.push(path),
+
+			Some(Component::Literal(name)) => {
+				let path = path.join(name);
+				let full_path = self.root.join(&path);
+				if fs::symlink_metadata(&full_path).is_ok() && (is_last || full_path.is_dir()) {
+					self.walk(path, ix + 1);
+				}
+			}
+
+			Some(Component::Pattern(pattern)) => {
+				let pattern = pattern.clone();
+				let options = self.match_options();
+
+				for entry in self.read_dir(&path) {
+					let name = entry.file_name();
+					let matches = name
+						.to_str()
+						.map(|name| pattern.matches_with(name, options))
+						.unwrap_or(false);
+
+					if matches && (is_last || entry.path().is_dir()) {
+						self.walk(path.join(name), ix + 1);
+					}
+				}
+			}
+
+			Some(Component::Recursive) => {
+				// Match zero directories.
+				self.walk(path.clone(), ix + 1);
+
+				// Match one or more directories.
+				for entry in self.read_dir(&path) {
+					let name = entry.file_name();
+
+					if !self.options.hidden && name.as_bytes().starts_with(b".") {
+						continue;
+					}
+
+					let is_dir = match entry.file_type() {
+						Ok(file_type) if file_type.is_symlink() => {
+							self.options.follow_symlinks && entry.path().is_dir()
+						},
+						Ok(file_type) => file_type.is_dir(),
+						Err(error) => {
+							self.errors.push((entry.path(), error));
+							false
+						}
+					};
+
+					if is_dir {
+						self.walk(path.join(name), ix);
+					}
+				}
+			}
+		}
+	}
+}
+
+
+/// Match filesystem paths against a glob pattern.
+/// Supports `*`, `?`, character classes and `**` for recursive descent. Errors during
+/// the walk, such as permission errors, cause the offending directory to be skipped.
+/// Relative patterns are matched in the working directory of the script.
 #[derive(Trace, Finalize)]
 struct Glob;
 
 impl Glob {
-	fn glob(pattern: &[u8]) -> Result<Value, Error> {
-		let pattern = std::str::from_utf8(pattern).map_err(|_| Error::new("Invalid pattern".into(), Value::default()))?;
-		let paths = glob::glob(pattern).map_err(|error| Error::new("Invalid pattern".into(), error.msg.into()))?;
-		let paths: Vec<Value> = paths
-			.map(|result| result
-				 .map(|path| Value::String(path.into()))
-				 .map_err(|error| error.into_error().into())
+	fn glob(pattern: &Str, cwd: &Path, options: Options) -> Value {
+		let pattern_str = match std::str::from_utf8(pattern.as_bytes()) {
+			Ok(pattern) => pattern,
+			Err(_) => return Error::new("invalid pattern".into(), Value::String(pattern.copy())).into(),
+		};
+
+		let (base, mut walk) = match Walk::new(pattern_str, cwd, &options) {
+			Ok(walk) => walk,
+			Err(error) => return Error::new("invalid pattern".into(), error.msg.into()).into(),
+		};
+
+		walk.walk(base, 0);
+
+		let mut paths = walk.paths;
+		paths.sort();
+		paths.dedup();
+
+		let paths: Value = paths
+			.into_iter()
+			.map(|path| Value::String(path.into()))
+			.collect::<Vec<_>>()
+			.into();
+
+		if !options.report_errors {
+			return paths;
+		}
+
+		let errors: Vec<Value> = walk.errors
+			.into_iter()
+			.map(
+				|(path, error)| Error::new(
+					error.to_string().into(),
+					Value::from(OsStr::new(&path).to_owned()),
+				).into()
 			)
-			.collect::<Result<_, Error>>()?;
-		Ok(paths.into())
+			.collect();
+
+		let mut dict = DictMap::default();
+		keys::PATHS.with(|key| dict.insert(key.copy(), paths));
+		keys::ERRORS.with(|key| dict.insert(key.copy(), errors.into()));
+
+		Dict::new(dict).into()
 	}
 }
 
@@ -33,13 +283,22 @@ impl NativeFun for Glob {
 	fn name(&self) -> &'static str { "std.glob" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let cwd = context.runtime
+			.cwd()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
 		match context.args() {
-		    [ Value::String(ref string) ] => {
-				let result = Self::glob(string.as_ref());
-				Ok(result.unwrap_or_else(Into::into))
+			[ Value::String(ref string) ] => Ok(Self::glob(string, &cwd, Options::default())),
+
+			[ Value::String(ref string), Value::Dict(ref dict) ] => {
+				let options = Options::from_dict(dict, &context)?;
+				Ok(Self::glob(string, &cwd, options))
 			},
-		    [ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-		    args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "dict", context.arg_pos(1))),
+			[ other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
 }
@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(HasError) }
+inventory::submit! { Native::new(HasError, 1, "Whether a value is or contains an error.") }
 
 #[derive(Trace, Finalize)]
 struct HasError;
@@ -0,0 +1,41 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	entries,
+	CallContext,
+	Function,
+	Native,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Help, 1, "The description of a stdlib member, given by name or as a function.") }
+
+/// The description of a stdlib member, such as `std.help("std.json.parse")`, or nil if
+/// there is no such member. Stdlib functions may also be given directly, while other
+/// functions have no description.
+#[derive(Trace, Finalize)]
+struct Help;
+
+impl NativeFun for Help {
+	fn name(&self) -> &'static str { "std.help" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let name: &[u8] = match context.args() {
+			[ Value::String(ref string) ] => string.as_ref(),
+			[ Value::Function(Function::Rust(ref fun)) ] => fun.name().as_bytes(),
+			[ Value::Function(Function::Hush(_)) ] => return Ok(Value::default()),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string or function", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let doc = entries()
+			.into_iter()
+			.find(|entry| entry.name.as_bytes() == name)
+			.map(|entry| entry.doc.into());
+
+		Ok(doc.unwrap_or_default())
+	}
+}
@@ -1,79 +1,139 @@
 use std::{
-	io,
-	path::{Path, PathBuf},
-	ffi::OsStr,
-	os::unix::ffi::OsStrExt,
+	path::{Component, Path, PathBuf},
+	rc::Rc,
+	ffi::{OsStr, OsString},
 };
 
 use gc::{Finalize, Trace};
 
 use crate::{
+	ffi::OsStrExt,
 	fmt,
 	syntax,
 	semantic,
-	symbol::{self, Symbol}
+	symbol::Symbol,
 };
 use super::{
 	CallContext,
-	RustFun,
+	Error,
+	Native,
 	NativeFun,
 	Panic,
+	SharedProgram,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Import) }
+inventory::submit! { Native::new(Import, 1, "Execute a script, returning its value, which is cached for later imports.") }
+inventory::submit! { Native::new(TryImport, 1, "Like std.import, but returning nil if the module is not found.") }
+inventory::submit! { Native::new(ImportResolved, 1, "The path a module would be imported from, or an error with the searched paths.") }
+
+
+/// Where a module was found, or which paths were searched in vain.
+enum Resolution {
+	Found(Symbol),
+	NotFound(Box<[PathBuf]>),
+}
+
+
+/// Get the module name from the arguments.
+fn module_name(context: &CallContext) -> Result<PathBuf, Panic> {
+	match context.args() {
+		[ Value::String(ref string) ] => Ok(Path::new(string).to_owned()),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Search for a module. Names starting with `./` or `../` are relative to the importing
+/// script, and other relative names are also searched in the module path. A missing
+/// extension may be omitted.
+fn resolve(name: &Path, context: &mut CallContext) -> Resolution {
+	let mut dir = PathBuf::from(
+		OsStr::from_bytes(
+			context.runtime
+				.interner()
+				.resolve(context.pos.path)
+				.expect("failed to resolve symbol")
+		)
+	);
+	dir.pop(); // Remove the file name.
+
+	let explicitly_relative = matches!(
+		name.components().next(),
+		Some(Component::CurDir | Component::ParentDir)
+	);
+
+	let mut dirs = vec![ dir ];
+	if !explicitly_relative && !name.is_absolute() {
+		dirs.extend(context.runtime.module_path.iter().cloned());
+	}
+
+	let mut tried = Vec::new();
+
+	for dir in dirs {
+		let path = dir.join(name);
+
+		let mut candidates = vec![ path.clone() ];
+		if name.extension().is_none() {
+			let mut with_extension = OsString::from(path);
+			with_extension.push(".hsh");
+			candidates.push(with_extension.into());
+		}
+
+		for candidate in candidates {
+			if candidate.is_file() {
+				if let Ok(path) = candidate.canonicalize() {
+					let symbol = context.runtime
+						.interner_mut()
+						.get_or_intern(path.as_os_str().as_bytes());
+
+					return Resolution::Found(symbol);
+				}
+			}
+
+			tried.push(candidate);
+		}
+	}
+
+	Resolution::NotFound(tried.into())
+}
+
 
 #[derive(Trace, Finalize)]
 struct Import;
 
 impl Import {
-	fn import(module_path: &Path, mut context: CallContext) -> Result<Value, Panic> {
-		let path = Self
-			::resolve_path(
-				module_path,
-				context.pos.path,
-				context.runtime.interner_mut()
-			)
-			.map_err(
-				|error| Panic::io(error, context.pos.copy())
-			)?;
+	fn import(path: Symbol, mut context: CallContext) -> Result<Value, Panic> {
+		context.runtime.imports.insert(path);
 
-		match context.runtime.modules.get(&path) {
-			Some(module) => Ok(module.copy()), // Don't reload module if cached.
-			None => {
-				let module = Self::load(path, &mut context)?;
-				context.runtime.modules.insert(path, module.copy());
-				Ok(module)
-			}
+		if let Some(module) = context.runtime.modules.get(&path) {
+			return Ok(module.copy()); // Don't reload module if cached.
 		}
-	}
 
+		// Modules are only cached once loaded, so a module being loaded must not be
+		// imported again.
+		if let Some(ix) = context.runtime.loading.iter().position(|&loading| loading == path) {
+			let cycle = context.runtime.loading[ix..]
+				.iter()
+				.copied()
+				.chain(std::iter::once(path))
+				.collect();
 
-	fn resolve_path(
-		target_path: &Path,
-		current_path: Symbol,
-		interner: &mut symbol::Interner,
-	) -> io::Result<Symbol> {
-		let mut path_buf = PathBuf::from(
-			OsStr::from_bytes(
-				interner
-					.resolve(current_path)
-					.expect("failed to resolve symbol")
-			).to_owned()
-		);
-		path_buf.pop(); // Remove the file name.
-		path_buf.push(target_path);
+			return Err(Panic::import_cycle(cycle, context.pos));
+		}
 
-		let path = path_buf.canonicalize()?;
+		context.runtime.loading.push(path);
+		let result = Self::load(path, &mut context);
+		context.runtime.loading.pop();
 
-		let path_symbol = interner.get_or_intern(
-			path
-				.as_os_str()
-				.as_bytes()
-		);
+		let module = result?;
+		context.runtime.modules.insert(path, module.copy());
 
-		Ok(path_symbol)
+		Ok(module)
 	}
 
 
@@ -92,56 +152,108 @@ impl Import {
 			&source,
 			context.runtime.interner_mut()
 		);
-		let has_syntax_errors = !syntactic_analysis.is_ok();
-
-		if has_syntax_errors {
-			eprint!("{}", fmt::Show(
-				syntactic_analysis.errors,
-				syntax::AnalysisDisplayContext {
-					max_errors: Some(20),
-					interner: context.runtime.interner(),
-				}
-			));
-			return Err(Panic::import_failed(path, context.pos.copy()));
+		if let Some(error) = syntactic_analysis.errors.0.first() {
+			// Only the first line, without the source snippet.
+			let message = fmt::Show(error, context.runtime.interner())
+				.to_string()
+				.lines()
+				.next()
+				.unwrap_or_default()
+				.into();
+			let more = syntactic_analysis.errors.0.len() - 1;
+
+			return Err(Panic::import_failed(path, message, more, context.pos.copy()));
 		}
 
-		// Semantics.
-		let program = semantic::Analyzer
+		// Semantics. Warnings are only reported for the main script.
+		let (program, _) = semantic::Analyzer
 			::analyze(
 				syntactic_analysis.ast, context.runtime.interner_mut()
 			)
 			.map_err(
 				|errors| {
-					eprint!("{}", fmt::Show(
-						errors,
-						semantic::ErrorsDisplayContext {
-							max_errors: Some(20),
-							interner: context.runtime.interner(),
-						}
-					));
-
-					Panic::import_failed(path, context.pos.copy())
+					let interner = context.runtime.interner();
+					let message = match errors.0.first() {
+						Some(error) => format!(
+							"{} - {}",
+							fmt::Show(error.pos, interner),
+							fmt::Show(&error.kind, interner),
+						),
+						None => String::new(),
+					};
+					let more = errors.0.len().saturating_sub(1);
+
+					Panic::import_failed(path, message.into(), more, context.pos.copy())
 				}
 			)?;
 
 		// Eval.
-		let program = Box::leak(Box::new(program));
-		context.runtime.eval(program)
+		context.runtime.eval_module(SharedProgram::new(Rc::new(program)))
 	}
 }
 
 impl NativeFun for Import {
 	fn name(&self) -> &'static str { "std.import" }
 
-	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		let path = match context.args() {
-			[ Value::String(ref string) ] => Path::new(string).to_owned(),
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let name = module_name(&context)?;
+
+		match resolve(&name, &mut context) {
+			Resolution::Found(path) => Self::import(path, context),
+			Resolution::NotFound(tried) => Err(Panic::module_not_found(name, tried, context.pos)),
+		}
+	}
+}
+
+
+/// Import a module, if found. Modules that fail to load still panic.
+#[derive(Trace, Finalize)]
+struct TryImport;
 
-			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+impl NativeFun for TryImport {
+	fn name(&self) -> &'static str { "std.try_import" }
 
-			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
-		};
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let name = module_name(&context)?;
 
-		Self::import(&path, context)
+		match resolve(&name, &mut context) {
+			Resolution::Found(path) => Import::import(path, context),
+			Resolution::NotFound(_) => Ok(Value::default()),
+		}
+	}
+}
+
+
+/// The canonical path of the module that would be imported, or an error whose context is
+/// the array of searched paths.
+#[derive(Trace, Finalize)]
+struct ImportResolved;
+
+impl NativeFun for ImportResolved {
+	fn name(&self) -> &'static str { "std.import_resolved" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let name = module_name(&context)?;
+
+		match resolve(&name, &mut context) {
+			Resolution::Found(path) => {
+				let path: &[u8] = context.runtime
+					.interner()
+					.resolve(path)
+					.expect("failed to resolve symbol");
+
+				Ok(Value::String(path.into()))
+			}
+
+			Resolution::NotFound(tried) => {
+				let tried: Value = tried
+					.iter()
+					.map(|path| Value::String(path.as_os_str().as_bytes().into()))
+					.collect::<Vec<_>>()
+					.into();
+
+				Ok(Error::new("module not found".into(), tried).into())
+			}
+		}
 	}
 }
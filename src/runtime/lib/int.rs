@@ -3,13 +3,13 @@ use gc::{Finalize, Trace};
 use super::{
 	CallContext,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Int) }
+inventory::submit!{ Native::new(Int, 1, "Convert a float or a numeric string to an int.") }
 
 #[derive(Trace, Finalize)]
 struct Int;
@@ -27,6 +27,10 @@ impl NativeFun for Int {
 				Value::Int(f.into())
 			),
 
+			[ Value::Byte(b) ] => Ok(
+				Value::Int((*b).into())
+			),
+
 			[ value @ Value::String(ref string) ] => {
 				let parse_error = || Panic::value_error(
 					value.copy(),
@@ -45,7 +49,7 @@ impl NativeFun for Int {
 				Ok(Value::from(int))
 			}
 
-			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "int, float, char or string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
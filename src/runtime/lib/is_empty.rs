@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(IsEmpty) }
+inventory::submit! { Native::new(IsEmpty, 1, "Whether a string, array or dict is empty.") }
 
 #[derive(Trace, Finalize)]
 struct IsEmpty;
@@ -25,7 +25,7 @@ impl NativeFun for IsEmpty {
 
 			[ Value::String(ref string) ] => Ok(string.is_empty().into()),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
 use super::{
@@ -7,7 +5,9 @@ use super::{
 	Array,
 	CallContext,
 	Dict,
-	RustFun,
+	DictMap,
+	Function,
+	Native,
 	NativeFun,
 	Panic,
 	Str,
@@ -15,7 +15,7 @@ use super::{
 };
 
 
-inventory::submit! { RustFun::from(Iter) }
+inventory::submit! { Native::new(Iter, 1, "Iterate the elements of a string, array or dict.") }
 
 #[derive(Trace, Finalize)]
 struct Iter;
@@ -23,46 +23,139 @@ struct Iter;
 impl NativeFun for Iter {
 	fn name(&self) -> &'static str { "std.iter" }
 
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => collection_iter(value)
+				.map(Into::into)
+				.ok_or_else(|| Panic::type_error(value.copy(), "string, array or dict", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+inventory::submit! { Native::new(Enumerate, 1, "Iterate the elements of an array along with their index.") }
+
+/// Iterate the elements of an array as `@[ index, value ]` dicts, with the same rules as
+/// std.iter.
+#[derive(Trace, Finalize)]
+struct Enumerate;
+
+impl NativeFun for Enumerate {
+	fn name(&self) -> &'static str { "std.enumerate" }
+
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
 			[ Value::Array(ref array) ] => Ok(
 				IterImpl::Array {
 					array: array.copy(),
 					ix: GcCell::new(0),
+					len: array.len(),
+					indexed: true,
 				}.into()
 			),
 
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+inventory::submit! { Native::new(Keys, 1, "Iterate the keys of a dict.") }
+
+/// Iterate the keys of a dict, in unspecified order. The keys are collected upfront, so
+/// modifying the dict while iterating does not affect the iteration.
+#[derive(Trace, Finalize)]
+struct Keys;
+
+impl NativeFun for Keys {
+	fn name(&self) -> &'static str { "std.keys" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
 			[ Value::Dict(ref dict) ] => Ok(
-				IterImpl::Dict {
-					entries: GcCell::new(
-						dict
-							.borrow()
-							.iter()
-							.map(|(k, v)| (k.copy(), v.copy()))
-							.collect()
-					)
+				IterImpl::Items {
+					items: GcCell::new(dict.borrow().keys().map(Value::copy).collect()),
 				}.into()
 			),
 
-			[ Value::String(ref string) ] => Ok(
-				IterImpl::String {
-					string: string.copy(),
-					ix: GcCell::new(0),
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+inventory::submit! { Native::new(Values, 1, "Iterate the values of a dict.") }
+
+/// Iterate the values of a dict, in unspecified order. The values are collected upfront,
+/// so modifying the dict while iterating does not affect the iteration.
+#[derive(Trace, Finalize)]
+struct Values;
+
+impl NativeFun for Values {
+	fn name(&self) -> &'static str { "std.values" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict) ] => Ok(
+				IterImpl::Items {
+					items: GcCell::new(dict.borrow().values().map(Value::copy).collect()),
 				}.into()
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
 }
 
 
+/// The iterator over the elements of a string, array or dict, or None for other values.
+/// Arrays are iterated up to their length when the iteration started, so that appending
+/// while iterating terminates. Dict entries are collected upfront, in unspecified order.
+pub fn collection_iter(value: &Value) -> Option<Function> {
+	let iter = match value {
+		Value::Array(ref array) => IterImpl::Array {
+			array: array.copy(),
+			ix: GcCell::new(0),
+			len: array.len(),
+			indexed: false,
+		},
+
+		Value::Dict(ref dict) => IterImpl::Dict {
+			entries: GcCell::new(
+				dict
+					.borrow()
+					.iter()
+					.map(|(k, v)| (k.copy(), v.copy()))
+					.collect()
+			)
+		},
+
+		Value::String(ref string) => IterImpl::String {
+			string: string.copy(),
+			ix: GcCell::new(0),
+		},
+
+		_ => return None,
+	};
+
+	Some(iter.into())
+}
+
+
 #[derive(Trace, Finalize)]
 enum IterImpl {
 	Array {
 		array: Array,
 		ix: GcCell<i64>,
+		/// The length when the iteration started.
+		len: i64,
+		/// Whether to yield `@[ index, value ]` dicts rather than the elements.
+		indexed: bool,
 	},
 	String {
 		string: Str,
@@ -70,7 +163,10 @@ enum IterImpl {
 	},
 	Dict {
 		entries: GcCell<Vec<(Value, Value)>>,
-	}
+	},
+	Items {
+		items: GcCell<Vec<Value>>,
+	},
 }
 
 impl NativeFun for IterImpl {
@@ -82,14 +178,31 @@ impl NativeFun for IterImpl {
 			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
 		}
 
-		let mut iteration = HashMap::new();
+		let mut iteration = DictMap::default();
 
 		let next = match self {
-			IterImpl::Array { array, ix } => {
+			IterImpl::Array { array, ix, len, indexed } => {
 				let mut ix = ix.borrow_mut();
-				if let Ok(value) = array.index(*ix) {
+				if *ix >= *len {
+					None
+				} else if let Ok(value) = array.index(*ix) {
 					*ix += 1;
-					Some(value)
+
+					if *indexed {
+						let mut entry = DictMap::default();
+
+						keys::INDEX.with(
+							|index| entry.insert(index.copy(), Value::Int(*ix - 1))
+						);
+
+						keys::VALUE.with(
+							|key| entry.insert(key.copy(), value)
+						);
+
+						Some(Dict::new(entry).into())
+					} else {
+						Some(value)
+					}
 				} else {
 					None
 				}
@@ -110,7 +223,7 @@ impl NativeFun for IterImpl {
 				.pop()
 				.map(
 					|(k, v)| {
-						let mut entry = HashMap::new();
+						let mut entry = DictMap::default();
 
 						keys::KEY.with(
 							|key| entry.insert(key.copy(), k)
@@ -122,7 +235,9 @@ impl NativeFun for IterImpl {
 
 						Dict::new(entry).into()
 					}
-				)
+				),
+
+			IterImpl::Items { items } => items.borrow_mut().pop(),
 		};
 
 		keys::FINISHED.with(
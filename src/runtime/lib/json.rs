@@ -1,29 +1,48 @@
-use std::{fmt, collections::HashMap, convert::TryFrom};
+use std::{cell::RefCell, fmt, fmt::Write, convert::TryFrom};
 
 use gc::{Finalize, Trace};
 use serde::{
 	Deserialize,
 	Serialize,
 	de::{self, Visitor, SeqAccess, MapAccess},
-	ser::{self, SerializeMap},
+	ser::{self, SerializeMap, SerializeSeq},
 	Serializer,
 	Deserializer
 };
 
 use super::{
 	Dict,
+	DictMap,
 	Error,
 	Float,
 	NativeFun,
 	Panic,
-	RustFun,
+	Native,
+	SourcePos,
 	Value,
 	CallContext,
 };
 
 
-inventory::submit! { RustFun::from(Dump) }
-inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { Native::new(Dump, 1, "Serialize a value as a JSON string.") }
+inventory::submit! { Native::new(Parse, 1, "Parse a JSON string into a value.") }
+inventory::submit! {
+	Native::new(
+		Encode,
+		1,
+		"Encode a value as a compact JSON string. Panics on functions, errors, non-string dict \
+		 keys, strings which are not valid UTF-8, non-finite floats and cyclic values."
+	)
+}
+inventory::submit! {
+	Native::new(
+		Decode,
+		1,
+		"Decode a JSON string into a value, or return an error with the byte offset of the \
+		 syntax error as context. Numbers without a fraction or exponent decode as ints when \
+		 they fit, and as floats otherwise."
+	)
+}
 
 #[derive(Trace, Finalize)]
 struct Dump;
@@ -63,12 +82,210 @@ impl NativeFun for Parse {
 					)
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Trace, Finalize)]
+struct Encode;
+
+impl NativeFun for Encode {
+	fn name(&self) -> &'static str { "std.json.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let mut encoder = Encoder {
+					output: String::new(),
+					path: String::from("value"),
+					ancestors: Vec::new(),
+					pos: context.pos.copy(),
+				};
+
+				encoder.encode(value)?;
+
+				Ok(encoder.output.into())
+			}
+
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
 }
 
+
+/// A compact JSON encoder, which reports unencodable values with their path in the
+/// encoded value.
+struct Encoder {
+	output: String,
+	/// The index expression reaching the value being encoded, rooted at `value`.
+	path: String,
+	/// The addresses of the collections containing the value being encoded.
+	ancestors: Vec<*const ()>,
+	pos: SourcePos,
+}
+
+
+impl Encoder {
+	fn encode(&mut self, value: &Value) -> Result<(), Panic> {
+		match value {
+			Value::Nil => self.output.push_str("null"),
+			Value::Bool(b) => write!(self.output, "{}", b).expect("string write can't fail"),
+			Value::Int(n) => write!(self.output, "{}", n).expect("string write can't fail"),
+
+			Value::Float(Float(n)) if n.is_finite() => self.output.push_str(
+				&serde_json::to_string(n).expect("finite floats are encodable")
+			),
+			Value::Float(_) => return Err(self.value_error(value, "finite float")),
+
+			Value::Byte(b) => self.encode_str(value, &[ *b ])?,
+			Value::String(s) => self.encode_str(value, s.as_bytes())?,
+
+			Value::Array(array) => {
+				self.enter(array.address())?;
+				self.output.push('[');
+
+				for (ix, item) in array.borrow().iter().enumerate() {
+					if ix > 0 {
+						self.output.push(',');
+					}
+
+					let len = self.path.len();
+					write!(self.path, "[{}]", ix).expect("string write can't fail");
+					self.encode(item)?;
+					self.path.truncate(len);
+				}
+
+				self.output.push(']');
+				self.ancestors.pop();
+			}
+
+			Value::Dict(dict) => {
+				self.enter(dict.address())?;
+				self.output.push('{');
+
+				for (ix, (key, item)) in dict.borrow().iter().enumerate() {
+					if ix > 0 {
+						self.output.push(',');
+					}
+
+					if !matches!(key, Value::String(_)) {
+						return Err(self.value_error(key, "string key in json object"));
+					}
+
+					let start = self.output.len();
+					self.encode(key)?;
+					let key = self.output[start..].to_owned();
+					self.output.push(':');
+
+					let len = self.path.len();
+					write!(self.path, "[{}]", key).expect("string write can't fail");
+					self.encode(item)?;
+					self.path.truncate(len);
+				}
+
+				self.output.push('}');
+				self.ancestors.pop();
+			}
+
+			Value::Function(_) | Value::Error(_) => return Err(
+				self.value_error(value, "nil, bool, byte, int, float, string, array or dict")
+			),
+		}
+
+		Ok(())
+	}
+
+
+	/// Strings must be valid UTF-8, as JSON has no representation for arbitrary bytes.
+	fn encode_str(&mut self, value: &Value, bytes: &[u8]) -> Result<(), Panic> {
+		let string = std::str::from_utf8(bytes)
+			.map_err(|_| self.value_error(value, "valid UTF-8 string"))?;
+
+		self.output.push_str(
+			&serde_json::to_string(string).expect("strings are encodable")
+		);
+
+		Ok(())
+	}
+
+
+	/// Register the collection at the given address as an ancestor, failing if it already
+	/// is one.
+	fn enter(&mut self, address: *const ()) -> Result<(), Panic> {
+		if self.ancestors.contains(&address) {
+			return Err(Panic::cyclic_value(self.path.as_str().into(), self.pos.copy()));
+		}
+
+		self.ancestors.push(address);
+
+		Ok(())
+	}
+
+
+	fn value_error(&self, value: &Value, expected: &str) -> Panic {
+		Panic::value_error(
+			value.copy(),
+			format!("{} at {}", expected, self.path),
+			self.pos.copy(),
+		)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Decode;
+
+impl NativeFun for Decode {
+	fn name(&self) -> &'static str { "std.json.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let input = string.as_bytes();
+
+				Ok(
+					serde_json::from_slice(input)
+						.unwrap_or_else(|error| syntax_error(input, error).into())
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Convert a JSON syntax error to an error value, with the byte offset of the error as
+/// context.
+fn syntax_error(input: &[u8], error: serde_json::Error) -> Error {
+	// Serde reports the line, and the column one past the offending byte, or the length of
+	// the line when the input ends early.
+	let offset = if error.is_eof() {
+		input.len()
+	} else {
+		let line_start: usize = input
+			.split(|&byte| byte == b'\n')
+			.take(error.line().saturating_sub(1))
+			.map(|line| line.len() + 1)
+			.sum();
+
+		(line_start + error.column().saturating_sub(1)).min(input.len())
+	};
+
+	let message = error.to_string();
+	let location = format!(" at line {} column {}", error.line(), error.column());
+	let message = message.strip_suffix(&location).unwrap_or(&message);
+
+	Error::new(
+		format!("{} at byte {}", message, offset).into(),
+		Value::Int(offset as i64),
+	)
+}
+
+
 impl<'de> Deserialize<'de> for Value {
 	fn deserialize<D>(deserializer: D) -> Result<Value, D::Error>
 	where
@@ -95,9 +312,12 @@ impl<'de> Deserialize<'de> for Value {
 			where
 				E: de::Error,
 			{
-				i64::try_from(value)
-					.map(Into::into)
-					.map_err(|error| de::Error::custom(error))
+				// Integers out of range are approximated, like other JSON numbers.
+				Ok(
+					i64::try_from(value)
+						.map(Into::into)
+						.unwrap_or_else(|_| Value::Float((value as f64).into()))
+				)
 			}
 
 			fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
@@ -115,6 +335,14 @@ impl<'de> Deserialize<'de> for Value {
 				Ok(value.into())
 			}
 
+			fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+				Ok(value.into())
+			}
+
+			fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E> {
+				Ok(value.into())
+			}
+
 			fn visit_none<E>(self) -> Result<Value, E> {
 				Ok(Value::Nil)
 			}
@@ -134,7 +362,7 @@ impl<'de> Deserialize<'de> for Value {
 			where
 				V: SeqAccess<'de>,
 			{
-				let mut vec = Vec::new();
+				let mut vec: Vec<Value> = Vec::new();
 
 				while let Some(elem) = visitor.next_element()? {
 					vec.push(elem);
@@ -149,7 +377,7 @@ impl<'de> Deserialize<'de> for Value {
 			{
 				match visitor.next_key()? {
 					Some(key) => {
-						let mut values = HashMap::new();
+						let mut values = DictMap::default();
 
 						values.insert(key, visitor.next_value()?);
 						while let Some((key, value)) = visitor.next_entry()? {
@@ -168,27 +396,100 @@ impl<'de> Deserialize<'de> for Value {
 	}
 }
 
+/// Strings which are not valid UTF-8 are serialized as bytes, which some formats, such as
+/// JSON, represent as arrays of integers. Dicts must only have string keys, and cycles
+/// and functions can't be serialized.
 impl Serialize for Value {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
 		S: Serializer,
 	{
-		match self {
+		let ancestors = RefCell::new(Vec::new());
+		Serializable { value: self, ancestors: &ancestors }.serialize(serializer)
+	}
+}
+
+
+/// A value being serialized, along with the addresses of the collections containing it,
+/// so that cycles are detected.
+struct Serializable<'a> {
+	value: &'a Value,
+	ancestors: &'a RefCell<Vec<*const ()>>,
+}
+
+
+impl<'a> Serializable<'a> {
+	fn nested(&self, value: &'a Value) -> Self {
+		Self { value, ancestors: self.ancestors }
+	}
+
+
+	/// Register the collection at the given address as an ancestor, failing if it already
+	/// is one.
+	fn enter<E>(&self, address: *const ()) -> Result<(), E>
+	where
+		E: ser::Error,
+	{
+		let mut ancestors = self.ancestors.borrow_mut();
+
+		if ancestors.contains(&address) {
+			return Err(ser::Error::custom("can't serialize cyclic value"));
+		}
+
+		ancestors.push(address);
+
+		Ok(())
+	}
+
+
+	fn leave(&self) {
+		self.ancestors.borrow_mut().pop();
+	}
+}
+
+
+impl<'a> Serialize for Serializable<'a> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.value {
 			Value::Nil => serializer.serialize_unit(),
 			Value::Bool(b) => serializer.serialize_bool(*b),
 			Value::Byte(b) => serializer.serialize_str(&String::from_utf8_lossy(&[*b])),
 			Value::Int(n) => n.serialize(serializer),
 			Value::Float(Float(n)) => n.serialize(serializer),
-			Value::String(s) => serializer.serialize_str(&String::from_utf8_lossy(s.as_bytes())),
-			Value::Array(v) => v.borrow().serialize(serializer),
+			Value::String(s) => match std::str::from_utf8(s.as_bytes()) {
+				Ok(s) => serializer.serialize_str(s),
+				Err(_) => serializer.serialize_bytes(s.as_bytes()),
+			},
+
+			Value::Array(v) => {
+				self.enter(v.address())?;
+
+				let v = v.borrow();
+				let mut seq = serializer.serialize_seq(Some(v.len()))?;
+				for value in v.iter() {
+					seq.serialize_element(&self.nested(value))?;
+				}
+
+				self.leave();
+				seq.end()
+			}
+
 			Value::Dict(m) => {
-				let mut map = serializer.serialize_map(Some(m.borrow().len()))?;
-				for (key, value) in m.borrow().iter() {
+				self.enter(m.address())?;
+
+				let m = m.borrow();
+				let mut map = serializer.serialize_map(Some(m.len()))?;
+				for (key, value) in m.iter() {
 					match key {
-						Value::String(_) => map.serialize_entry(key, value)?,
+						Value::String(_) => map.serialize_entry(&self.nested(key), &self.nested(value))?,
 						_ => return Err(ser::Error::custom("json object key must be string")),
 					}
 				}
+
+				self.leave();
 				map.end()
 			}
 
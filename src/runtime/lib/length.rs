@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Length) }
+inventory::submit! { Native::new(Length, 1, "The length of a string in bytes, or the number of elements of an array or dict.") }
 
 #[derive(Trace, Finalize)]
 struct Length;
@@ -22,7 +22,7 @@ impl NativeFun for Length {
 			[ Value::Array(ref array) ] => Ok(Value::Int(array.len())),
 			[ Value::Dict(ref dict) ] => Ok(Value::Int(dict.len())),
 			[ Value::String(ref string) ] => Ok(Value::Int(string.len() as i64)),
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -0,0 +1,241 @@
+#![cfg(feature = "plugins")]
+
+use std::{
+	ffi::{c_void, CStr, CString, OsStr},
+	os::unix::ffi::OsStrExt,
+	path::Path,
+};
+
+use gc::{Finalize, Trace};
+use hush_plugin as plugin;
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	RustFun,
+	Native,
+	NativeFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { Native::new(Load, 1, "Load a native plugin, returning a dict with its functions.") }
+
+/// Load a native plugin, returning a dict with its functions.
+#[derive(Trace, Finalize)]
+struct Load;
+
+
+impl Load {
+	/// Load the plugin in the given path. Plugins are never unloaded, as their functions
+	/// may be referenced for as long as the process lives.
+	fn load(path: &Path) -> Result<Dict, Str> {
+		let c_path = CString::new(path.as_os_str().as_bytes())
+			.map_err(|_| Str::from("invalid path"))?;
+
+		// SAFETY: loading a library runs its initializers, which is the contract of std.load.
+		let library = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+		if library.is_null() {
+			return Err(dl_error());
+		}
+
+		let refuse = |description: Str| {
+			// SAFETY: no symbols of the library have been used.
+			unsafe { libc::dlclose(library) };
+			Err(description)
+		};
+
+		// The version is checked before anything else in the library is touched, as the
+		// remaining symbols may have an incompatible signature.
+		let version = match symbol(library, plugin::VERSION_SYMBOL) {
+			// SAFETY: the signature of the version function never changes.
+			Some(version) => unsafe { std::mem::transmute::<*mut c_void, plugin::VersionFn>(version) },
+			None => return refuse("not a hush plugin".into()),
+		};
+		let version = version();
+
+		if version != plugin::ABI_VERSION {
+			return refuse(
+				format!(
+					"incompatible plugin abi version {}, expected {}",
+					version,
+					plugin::ABI_VERSION
+				).into()
+			);
+		}
+
+		let register = match symbol(library, plugin::REGISTER_SYMBOL) {
+			// SAFETY: the abi version matches, so the signature is the expected one.
+			Some(register) => unsafe { std::mem::transmute::<*mut c_void, plugin::RegisterFn>(register) },
+			None => return refuse("not a hush plugin".into()),
+		};
+
+		let mut functions: Vec<PluginFun> = Vec::new();
+
+		// SAFETY: the context points to the functions vector, which outlives the registrar.
+		let mut registrar = unsafe {
+			plugin::Registrar::new(&mut functions as *mut _ as *mut c_void, register_function)
+		};
+
+		// SAFETY: the registrar is valid for the duration of the call.
+		unsafe { register(&mut registrar) };
+
+		let dict = Dict::default();
+		for function in functions {
+			dict.insert(function.name.into(), RustFun::from(function).into());
+		}
+
+		Ok(dict)
+	}
+}
+
+
+impl NativeFun for Load {
+	fn name(&self) -> &'static str { "std.load" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let cwd = context.runtime
+					.cwd()
+					.map_err(|error| Panic::io(error, context.pos.copy()))?;
+				let path = cwd.join(Path::new(AsRef::<OsStr>::as_ref(string)));
+
+				Ok(
+					match Self::load(&path) {
+						Ok(dict) => dict.into(),
+						Err(description) => Error::new(description, Value::String(string.copy())).into(),
+					}
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// A function registered by a plugin.
+#[derive(Finalize)]
+struct PluginFun {
+	/// Leaked, as plugin functions are never dropped in practice.
+	name: &'static str,
+	arity: u32,
+	function: plugin::Function,
+}
+
+
+/// Plugin functions hold no garbage-collected values.
+unsafe impl Trace for PluginFun {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl NativeFun for PluginFun {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let supplied = context.args().len() as u32;
+
+		if supplied != self.arity {
+			return Err(Panic::invalid_args(supplied, self.arity, context.pos));
+		}
+
+		let mut args = Vec::with_capacity(context.args().len());
+		for (ix, arg) in context.args().iter().enumerate() {
+			args.push(
+				match arg {
+					Value::Nil => plugin::Value::Nil,
+					Value::Bool(b) => plugin::Value::Bool(*b),
+					Value::Int(int) => plugin::Value::Int(*int),
+					Value::Float(float) => plugin::Value::Float(float.0),
+					Value::String(string) => plugin::Value::String(string.as_bytes().into()),
+					other => return Err(
+						Panic::type_error(
+							other.copy(),
+							"nil, bool, int, float or string",
+							context.arg_pos(ix),
+						)
+					),
+				}
+			);
+		}
+
+		let mut result = Value::default();
+
+		// SAFETY: the context points to the result, which outlives the handle.
+		let mut ret = unsafe {
+			plugin::Return::new(&mut result as *mut Value as *mut c_void, set_result)
+		};
+
+		(self.function)(args.as_slice().into(), &mut ret);
+
+		Ok(result)
+	}
+}
+
+
+/// Look up a symbol in a library.
+fn symbol(library: *mut c_void, name: &str) -> Option<*mut c_void> {
+	let name = CString::new(name).expect("symbol names have no nul bytes");
+	// SAFETY: the library handle is valid and the name is nul terminated.
+	let symbol = unsafe { libc::dlsym(library, name.as_ptr()) };
+
+	if symbol.is_null() {
+		None
+	} else {
+		Some(symbol)
+	}
+}
+
+
+/// The description of the last dynamic loading error.
+fn dl_error() -> Str {
+	// SAFETY: the returned string, if any, is valid until the next call to dlerror.
+	let error = unsafe { libc::dlerror() };
+
+	if error.is_null() {
+		"failed to load plugin".into()
+	} else {
+		// SAFETY: checked for null above.
+		unsafe { CStr::from_ptr(error) }.to_bytes().into()
+	}
+}
+
+
+extern "C" fn register_function(
+	context: *mut c_void,
+	name: plugin::Bytes,
+	arity: u32,
+	function: plugin::Function,
+) {
+	// SAFETY: the context is the functions vector, as set up in Load::load.
+	let functions = unsafe { &mut *(context as *mut Vec<PluginFun>) };
+	let name = String::from_utf8_lossy(name.as_bytes()).into_owned();
+
+	functions.push(PluginFun { name: Box::leak(name.into_boxed_str()), arity, function });
+}
+
+
+extern "C" fn set_result(context: *mut c_void, value: &plugin::Value, error: bool) {
+	// SAFETY: the context is the result, as set up in PluginFun::call.
+	let result = unsafe { &mut *(context as *mut Value) };
+
+	let value = match value {
+		plugin::Value::Nil => Value::Nil,
+		plugin::Value::Bool(b) => Value::Bool(*b),
+		plugin::Value::Int(int) => Value::Int(*int),
+		plugin::Value::Float(float) => Value::Float((*float).into()),
+		plugin::Value::String(string) => Value::String(string.as_bytes().into()),
+	};
+
+	*result = match (error, &value) {
+		(false, _) => value,
+		(true, Value::String(description)) => Error::new(description.copy(), Value::Nil).into(),
+		(true, _) => Error::new("plugin error".into(), value).into(),
+	};
+}
@@ -0,0 +1,305 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	Array,
+	CallContext,
+	Constant,
+	NativeFun,
+	Native,
+	Panic,
+	SourcePos,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Rounding { name: "std.math.floor", op: f64::floor }, 1, "Round a number down.") }
+inventory::submit!{ Native::new(Rounding { name: "std.math.ceil", op: f64::ceil }, 1, "Round a number up.") }
+inventory::submit!{ Native::new(Rounding { name: "std.math.round", op: f64::round }, 1, "Round a number to the nearest integer, away from zero on ties.") }
+inventory::submit!{ Native::new(Rounding { name: "std.math.trunc", op: f64::trunc }, 1, "Round a number towards zero.") }
+inventory::submit!{ Native::new(Abs, 1, "The absolute value of a number.") }
+inventory::submit!{ Native::new(Sqrt, 1, "The square root of a number.") }
+inventory::submit!{ Native::new(Pow, 2, "Raise a number to the given power.") }
+inventory::submit!{ Native::new(Extremum::Min, 1..=2, "The minimum of two numbers or an array of numbers.") }
+inventory::submit!{ Native::new(Extremum::Max, 1..=2, "The maximum of two numbers or an array of numbers.") }
+inventory::submit!{ Native::new(Clamp, 3, "Constrain a number to the given bounds.") }
+
+
+/// Rounding functions, which are the identity for ints.
+#[derive(Finalize)]
+struct Rounding {
+	name: &'static str,
+	op: fn(f64) -> f64,
+}
+
+/// Rounding has no garbage-collected fields.
+unsafe impl Trace for Rounding {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Rounding {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(i) ] => Ok(Value::Int(*i)),
+			[ Value::Float(f) ] => Ok((self.op)(f.0).into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Absolute value.
+#[derive(Trace, Finalize)]
+struct Abs;
+
+impl NativeFun for Abs {
+	fn name(&self) -> &'static str { "std.math.abs" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(i) ] => i
+				.checked_abs()
+				.map(Value::Int)
+				.ok_or_else(|| Panic::integer_overflow(context.pos.copy())),
+
+			[ Value::Float(f) ] => Ok(f.0.abs().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Square root, which is NaN for negative numbers.
+#[derive(Trace, Finalize)]
+struct Sqrt;
+
+impl NativeFun for Sqrt {
+	fn name(&self) -> &'static str { "std.math.sqrt" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(i) ] => Ok((*i as f64).sqrt().into()),
+			[ Value::Float(f) ] => Ok(f.0.sqrt().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Exponentiation.
+/// Ints raised to non-negative ints result in ints, unless the result overflows. Everything
+/// else results in floats.
+#[derive(Trace, Finalize)]
+struct Pow;
+
+impl NativeFun for Pow {
+	fn name(&self) -> &'static str { "std.math.pow" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let float = |ix: usize, value: &Value| match value {
+			Value::Int(i) => Ok(*i as f64),
+			Value::Float(f) => Ok(f.0),
+			other => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(ix))),
+		};
+
+		match context.args() {
+			[ Value::Int(base), Value::Int(exp) ] if *exp >= 0 => Ok(
+				u32::try_from(*exp)
+					.ok()
+					.and_then(|exp| base.checked_pow(exp))
+					.map(Value::Int)
+					.unwrap_or_else(|| (*base as f64).powf(*exp as f64).into())
+			),
+
+			[ base, exp ] => Ok(float(0, base)?.powf(float(1, exp)?).into()),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Minimum or maximum of two numbers or an array of numbers.
+/// Mixing ints and floats promotes the result to float.
+#[derive(Trace, Finalize)]
+enum Extremum {
+	Min,
+	Max,
+}
+
+impl Extremum {
+	/// Select among the values, where `pos` gives the source position of each value.
+	fn select<F>(&self, values: &[Value], pos: F, context: &CallContext) -> Result<Value, Panic>
+	where
+		F: Fn(usize) -> SourcePos,
+	{
+		let mut iter = values.iter().enumerate();
+
+		let mut result = match iter.next() {
+			Some((_, value @ Value::Int(_))) | Some((_, value @ Value::Float(_))) => value.copy(),
+			Some((ix, other)) => return Err(Panic::type_error(other.copy(), "int or float", pos(ix))),
+			None => return Err(Panic::empty_collection(context.pos.copy())),
+		};
+
+		for (ix, value) in iter {
+			let numbers = util::Numbers
+				::promote([result.copy(), value.copy()])
+				.map_err(|(_, value)| Panic::type_error(value, "int or float", pos(ix)))?;
+
+			result = match (self, numbers) {
+				(Self::Min, util::Numbers::Ints([ a, b ])) => a.min(b).into(),
+				(Self::Max, util::Numbers::Ints([ a, b ])) => a.max(b).into(),
+				(Self::Min, util::Numbers::Floats([ a, b ])) => a.0.min(b.0).into(),
+				(Self::Max, util::Numbers::Floats([ a, b ])) => a.0.max(b.0).into(),
+			};
+		}
+
+		Ok(result)
+	}
+}
+
+impl NativeFun for Extremum {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Min => "std.math.min",
+			Self::Max => "std.math.max",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array) ] => {
+				let array: Array = array.copy();
+				let values = array.borrow();
+				self.select(&values, |_| context.arg_pos(0), &context)
+			},
+
+			args @ [ _, _ ] => self.select(args, |ix| context.arg_pos(ix), &context),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Constrain a number to the given bounds.
+#[derive(Trace, Finalize)]
+struct Clamp;
+
+impl NativeFun for Clamp {
+	fn name(&self) -> &'static str { "std.math.clamp" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, low, high ] => {
+				let numbers = util::Numbers
+					::promote([value.copy(), low.copy(), high.copy()])
+					.map_err(|(ix, value)| Panic::type_error(value, "int or float", context.arg_pos(ix)))?;
+
+				match numbers {
+					util::Numbers::Ints([ value, low, high ]) if low <= high => Ok(
+						value.clamp(low, high).into()
+					),
+
+					util::Numbers::Floats([ value, low, high ]) if low <= high => Ok(
+						value.0.max(low.0).min(high.0).into()
+					),
+
+					_ => Err(
+						Panic::value_error(
+							high.copy(),
+							"number not lower than the lower bound",
+							context.pos.copy()
+						)
+					),
+				}
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
+
+
+inventory::submit!{ Native::new(FloatFun { name: "std.math.sin", op: f64::sin }, 1, "The sine of an angle in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.cos", op: f64::cos }, 1, "The cosine of an angle in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.tan", op: f64::tan }, 1, "The tangent of an angle in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.asin", op: f64::asin }, 1, "The arcsine of a number, in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.acos", op: f64::acos }, 1, "The arccosine of a number, in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.atan", op: f64::atan }, 1, "The arctangent of a number, in radians.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.exp", op: f64::exp }, 1, "The exponential of a number.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.ln", op: f64::ln }, 1, "The natural logarithm of a number.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.log2", op: f64::log2 }, 1, "The base 2 logarithm of a number.") }
+inventory::submit!{ Native::new(FloatFun { name: "std.math.log10", op: f64::log10 }, 1, "The base 10 logarithm of a number.") }
+inventory::submit!{ Native::new(Atan2, 2, "The four quadrant arctangent of y / x, in radians.") }
+
+inventory::submit!{ Constant { name: "std.math.pi", value: || std::f64::consts::PI.into(), doc: "The ratio of a circle's circumference to its diameter." } }
+inventory::submit!{ Constant { name: "std.math.e", value: || std::f64::consts::E.into(), doc: "Euler's number." } }
+inventory::submit!{ Constant { name: "std.math.inf", value: || f64::INFINITY.into(), doc: "Positive infinity." } }
+inventory::submit!{ Constant { name: "std.math.nan", value: || f64::NAN.into(), doc: "A float that is not a number." } }
+
+
+/// Functions that always result in floats, promoting ints if necessary.
+#[derive(Finalize)]
+struct FloatFun {
+	name: &'static str,
+	op: fn(f64) -> f64,
+}
+
+/// FloatFun has no garbage-collected fields.
+unsafe impl Trace for FloatFun {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for FloatFun {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(i) ] => Ok((self.op)(*i as f64).into()),
+			[ Value::Float(f) ] => Ok((self.op)(f.0).into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Four quadrant arctangent of y / x.
+#[derive(Trace, Finalize)]
+struct Atan2;
+
+impl NativeFun for Atan2 {
+	fn name(&self) -> &'static str { "std.math.atan2" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ y, x ] => {
+				let numbers = util::Numbers
+					::promote([y.copy(), x.copy()])
+					.map_err(|(ix, value)| Panic::type_error(value, "int or float", context.arg_pos(ix)))?;
+
+				let (y, x) = match numbers {
+					util::Numbers::Ints([ y, x ]) => (y as f64, x as f64),
+					util::Numbers::Floats([ y, x ]) => (y.0, x.0),
+				};
+
+				Ok(y.atan2(x).into())
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
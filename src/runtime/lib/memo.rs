@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use gc::{Finalize, GcCell, Trace};
+
+use super::{
+	CallContext,
+	DictMap,
+	Function,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Memo, 1..=2, "Memoize a function, optionally limiting the number of cached results.") }
+
+/// Memoize a function, caching results by argument list.
+/// An optional maximum number of entries may be given, in which case the least recently
+/// used entries are evicted.
+#[derive(Trace, Finalize)]
+struct Memo;
+
+impl NativeFun for Memo {
+	fn name(&self) -> &'static str { "std.memo" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (function, capacity) = match context.args() {
+			[ Value::Function(fun) ] => (fun.copy(), None),
+
+			[ Value::Function(fun), Value::Int(capacity) ] if *capacity > 0 => {
+				(fun.copy(), Some(*capacity as usize))
+			},
+
+			[ Value::Function(_), value @ Value::Int(_) ] => return Err(
+				Panic::value_error(value.copy(), "positive int", context.pos)
+			),
+
+			[ Value::Function(_), other ] => return Err(
+				Panic::type_error(other.copy(), "int", context.arg_pos(1))
+			),
+
+			[ other ] | [ other, _ ] => return Err(
+				Panic::type_error(other.copy(), "function", context.arg_pos(0))
+			),
+
+			[] => return Err(Panic::invalid_args(0, 1, context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		Ok(
+			MemoImpl {
+				function,
+				capacity,
+				cache: GcCell::new(Cache::default()),
+			}.into()
+		)
+	}
+}
+
+
+/// A cached result, along with the tick of its last use.
+#[derive(Trace, Finalize)]
+struct Entry {
+	value: Value,
+	tick: u64,
+}
+
+
+#[derive(Default)]
+#[derive(Trace, Finalize)]
+struct Cache {
+	entries: DictMap<Vec<Value>, Entry>,
+	/// The keys of the entries, by tick of last use.
+	recency: BTreeMap<u64, Vec<Value>>,
+	tick: u64,
+}
+
+impl Cache {
+	fn get(&mut self, key: &[Value]) -> Option<Value> {
+		let tick = self.tick;
+		let entry = self.entries.get_mut(key)?;
+
+		if let Some(key) = self.recency.remove(&entry.tick) {
+			self.recency.insert(tick, key);
+		}
+
+		entry.tick = tick;
+		self.tick += 1;
+
+		Some(entry.value.copy())
+	}
+
+
+	fn insert(&mut self, key: Vec<Value>, value: Value, capacity: Option<usize>) {
+		let tick = self.tick;
+		self.tick += 1;
+
+		let recency_key = key.iter().map(Value::copy).collect();
+
+		if let Some(previous) = self.entries.insert(key, Entry { value, tick }) {
+			// The function may have been reentered with the same arguments.
+			self.recency.remove(&previous.tick);
+		}
+		self.recency.insert(tick, recency_key);
+
+		if let Some(capacity) = capacity {
+			while self.entries.len() > capacity {
+				let oldest = match self.recency.keys().next() {
+					Some(&oldest) => oldest,
+					None => break,
+				};
+
+				if let Some(key) = self.recency.remove(&oldest) {
+					self.entries.remove(&key);
+				}
+			}
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct MemoImpl {
+	function: Function,
+	#[unsafe_ignore_trace]
+	capacity: Option<usize>,
+	cache: GcCell<Cache>,
+}
+
+impl MemoImpl {
+	/// Only immutable values may be used as keys. Arrays and dicts could be mutated after
+	/// being cached, so calls including them pass through uncached.
+	fn cacheable(value: &Value) -> bool {
+		!matches!(value, Value::Array(_) | Value::Dict(_) | Value::Error(_))
+	}
+}
+
+impl NativeFun for MemoImpl {
+	fn name(&self) -> &'static str { "std.memo<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let args_start = context.args_start;
+		let obj = context.obj.copy();
+
+		if !context.args().iter().all(Self::cacheable) {
+			return context.call(obj, &self.function, args_start);
+		}
+
+		let key: Vec<Value> = context.args().iter().map(Value::copy).collect();
+
+		if let Some(value) = self.cache.borrow_mut().get(&key) {
+			return Ok(value);
+		}
+
+		// The cache must not be borrowed during the call, as the function may recurse.
+		let value = context.call(obj, &self.function, args_start)?;
+
+		self.cache
+			.borrow_mut()
+			.insert(key, value.copy(), self.capacity);
+
+		Ok(value)
+	}
+}
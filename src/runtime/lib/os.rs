@@ -0,0 +1,188 @@
+use std::path::MAIN_SEPARATOR;
+#[cfg(feature = "system")]
+use std::{
+	ffi::{CStr, OsString},
+	os::unix::ffi::OsStringExt,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Constant,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Info::Pid, 0, "The id of the current process.") }
+inventory::submit!{ Native::new(Info::Ppid, 0, "The id of the parent process.") }
+inventory::submit!{ Native::new(Info::Hostname, 0, "The name of the host.") }
+inventory::submit!{ Native::new(Info::Username, 0, "The name of the effective user.") }
+inventory::submit!{ Native::new(Info::Home, 0, "The home directory of the user.") }
+inventory::submit!{ Native::new(IsTty, 1, "Whether the given standard stream is a terminal.") }
+
+inventory::submit!{ Constant { name: "std.os.name", value: || std::env::consts::OS.into(), doc: "The name of the operating system, such as \"linux\"." } }
+inventory::submit!{ Constant { name: "std.os.family", value: || std::env::consts::FAMILY.into(), doc: "The family of the operating system, such as \"unix\"." } }
+inventory::submit!{ Constant { name: "std.os.arch", value: || std::env::consts::ARCH.into(), doc: "The architecture of the processor, such as \"x86_64\"." } }
+inventory::submit!{ Constant { name: "std.os.path_sep", value: || MAIN_SEPARATOR.to_string().as_str().into(), doc: "The separator of path components." } }
+inventory::submit!{ Constant { name: "std.os.line_sep", value: || LINE_SEPARATOR.into(), doc: "The line separator of the platform." } }
+
+
+#[cfg(windows)]
+const LINE_SEPARATOR: &str = "\r\n";
+#[cfg(not(windows))]
+const LINE_SEPARATOR: &str = "\n";
+
+
+/// Get a field from the password database entry of the effective user.
+#[cfg(feature = "system")]
+fn passwd_field<F>(field: F) -> Option<OsString>
+where
+	F: FnOnce(&libc::passwd) -> *const libc::c_char,
+{
+	let mut buffer = vec![0 as libc::c_char; 4096];
+	let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+	let mut result = std::ptr::null_mut();
+
+	let status = unsafe {
+		libc::getpwuid_r(
+			libc::geteuid(),
+			&mut passwd,
+			buffer.as_mut_ptr(),
+			buffer.len(),
+			&mut result,
+		)
+	};
+
+	if status != 0 || result.is_null() {
+		return None;
+	}
+
+	let field = field(&passwd);
+	if field.is_null() {
+		return None;
+	}
+
+	let field = unsafe { CStr::from_ptr(field) };
+
+	Some(OsString::from_vec(field.to_bytes().to_vec()))
+}
+
+
+#[cfg(feature = "system")]
+fn hostname() -> Option<OsString> {
+	let mut buffer = vec![0 as libc::c_char; 256];
+
+	let status = unsafe { libc::gethostname(buffer.as_mut_ptr(), buffer.len()) };
+	if status != 0 {
+		return None;
+	}
+
+	// The name may be truncated without a nul terminator.
+	let last = buffer.len() - 1;
+	buffer[last] = 0;
+
+	let hostname = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+
+	Some(OsString::from_vec(hostname.to_bytes().to_vec()))
+}
+
+
+/// Information about the current process and host.
+/// Failures result in nil, as does everything but the pid and home without the system
+/// feature.
+#[derive(Trace, Finalize)]
+enum Info {
+	Pid,
+	/// The parent process id.
+	Ppid,
+	Hostname,
+	/// The name of the effective user.
+	Username,
+	/// The home directory, from the HOME variable or the user database.
+	Home,
+}
+
+impl NativeFun for Info {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Pid => "std.os.pid",
+			Self::Ppid => "std.os.ppid",
+			Self::Hostname => "std.os.hostname",
+			Self::Username => "std.os.username",
+			Self::Home => "std.os.home",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self {
+				Self::Pid => Value::Int(std::process::id().into()),
+				#[cfg(feature = "system")]
+				Self::Ppid => Value::Int(unsafe { libc::getppid() }.into()),
+				#[cfg(feature = "system")]
+				Self::Hostname => hostname().into(),
+				#[cfg(feature = "system")]
+				Self::Username => passwd_field(|passwd| passwd.pw_name).into(),
+				#[cfg(not(feature = "system"))]
+				Self::Ppid | Self::Hostname | Self::Username => Value::Nil,
+				Self::Home => {
+					let home = std::env::var_os("HOME").filter(|home| !home.is_empty());
+					#[cfg(feature = "system")]
+					let home = home.or_else(|| passwd_field(|passwd| passwd.pw_dir));
+					home.into()
+				}
+			}
+		)
+	}
+}
+
+
+/// Whether the given standard stream ("stdin", "stdout" or "stderr") is a terminal.
+#[derive(Trace, Finalize)]
+struct IsTty;
+
+impl NativeFun for IsTty {
+	fn name(&self) -> &'static str { "std.os.is_tty" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref stream) ] => match is_tty(stream.as_bytes()) {
+				Some(is_tty) => Ok(Value::Bool(is_tty)),
+				None => Err(Panic::value_error(value.copy(), "stream name", context.pos)),
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Whether the given standard stream is a terminal, or None if the name is invalid.
+#[cfg(feature = "system")]
+fn is_tty(stream: &[u8]) -> Option<bool> {
+	let fd = match stream {
+		b"stdin" => libc::STDIN_FILENO,
+		b"stdout" => libc::STDOUT_FILENO,
+		b"stderr" => libc::STDERR_FILENO,
+		_ => return None,
+	};
+
+	Some(unsafe { libc::isatty(fd) } == 1)
+}
+
+
+/// Without the system feature, the standard streams are never terminals.
+#[cfg(not(feature = "system"))]
+fn is_tty(stream: &[u8]) -> Option<bool> {
+	matches!(stream, b"stdin" | b"stdout" | b"stderr").then(|| false)
+}
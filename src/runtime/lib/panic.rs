@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(UserPanic) }
+inventory::submit! { Native::new(UserPanic, 1, "Panic with the given value.") }
 
 #[derive(Trace, Finalize)]
 struct UserPanic;
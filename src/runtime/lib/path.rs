@@ -0,0 +1,156 @@
+use std::{
+	ffi::OsStr,
+	path::{Path, PathBuf},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::ffi::OsStrExt;
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	Native,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Join, 0.., "Join paths with the platform separator.") }
+inventory::submit!{ Native::new(Query { name: "std.path.basename", op: basename }, 1, "The last component of a path.") }
+inventory::submit!{ Native::new(Query { name: "std.path.dirname", op: dirname }, 1, "A path without its last component.") }
+inventory::submit!{ Native::new(Query { name: "std.path.ext", op: ext }, 1, "The extension of a path.") }
+inventory::submit!{ Native::new(Query { name: "std.path.is_absolute", op: |path| path.is_absolute().into() }, 1, "Whether a path is absolute.") }
+inventory::submit!{ Native::new(Query { name: "std.path.split", op: split }, 1, "The components of a path.") }
+inventory::submit!{ Native::new(Canonicalize, 1, "Make a path absolute, resolving symlinks and `..` components.") }
+
+
+fn as_path(string: &Str) -> &Path {
+	Path::new(AsRef::<OsStr>::as_ref(string))
+}
+
+
+fn to_value(path: &Path) -> Value {
+	path.as_os_str().as_bytes().into()
+}
+
+
+/// The last component of the path, or nil if there is none.
+fn basename(path: &Path) -> Value {
+	path
+		.file_name()
+		.map(|name| Value::from(name.as_bytes()))
+		.into()
+}
+
+
+/// The path without its last component.
+/// This is "." for single component relative paths, and "/" for the root.
+fn dirname(path: &Path) -> Value {
+	match path.parent() {
+		Some(parent) if parent.as_os_str().is_empty() => ".".into(),
+		Some(parent) => to_value(parent),
+		None => to_value(path),
+	}
+}
+
+
+/// The extension of the last component, without the dot, or nil if there is none.
+fn ext(path: &Path) -> Value {
+	path
+		.extension()
+		.map(|ext| Value::from(ext.as_bytes()))
+		.into()
+}
+
+
+/// The components of the path, where the root is represented by the separator.
+fn split(path: &Path) -> Value {
+	path
+		.components()
+		.map(|component| Value::from(component.as_os_str().as_bytes()))
+		.collect::<Vec<_>>()
+		.into()
+}
+
+
+/// Join paths with the platform separator.
+/// Absolute paths replace everything before them.
+#[derive(Trace, Finalize)]
+struct Join;
+
+impl NativeFun for Join {
+	fn name(&self) -> &'static str { "std.path.join" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let mut path = PathBuf::new();
+
+		for (ix, arg) in context.args().iter().enumerate() {
+			match arg {
+				Value::String(ref string) => path.push(as_path(string)),
+				other => return Err(Panic::type_error(other.copy(), "string", context.arg_pos(ix))),
+			}
+		}
+
+		Ok(to_value(&path))
+	}
+}
+
+
+/// Pure path queries, which don't access the filesystem.
+#[derive(Finalize)]
+struct Query {
+	name: &'static str,
+	op: fn(&Path) -> Value,
+}
+
+/// Query has no garbage-collected fields.
+unsafe impl Trace for Query {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Query {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok((self.op)(as_path(string))),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Make a path absolute, resolving symlinks and `..` components.
+/// Relative paths are resolved against the working directory of the script.
+#[derive(Trace, Finalize)]
+struct Canonicalize;
+
+impl NativeFun for Canonicalize {
+	fn name(&self) -> &'static str { "std.path.canonicalize" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let string = string.copy();
+
+				let cwd = context.runtime
+					.cwd()
+					.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+				Ok(
+					match cwd.join(as_path(&string)).canonicalize() {
+						Ok(path) => to_value(&path),
+						Err(error) => Error::new(error.to_string().into(), Value::String(string)).into(),
+					}
+				)
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,71 @@
+use gc::{Finalize, Trace};
+
+use crate::{fmt, term::color};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(PCall, 1, "Call a function, returning whether it succeeded along with its value or panic.") }
+
+#[derive(Trace, Finalize)]
+struct PCall;
+
+mod keys {
+	use super::Value;
+
+	thread_local! {
+		pub static OK: Value = "ok".into();
+		pub static VALUE: Value = "value".into();
+		pub static ERROR: Value = "error".into();
+	}
+}
+
+impl NativeFun for PCall {
+	fn name(&self) -> &'static str { "std.pcall" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let fun = match context.args() {
+			[ Value::Function(fun) ] => fun.copy(),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = context.call(
+			Value::default(),
+			&fun,
+			context.args_start + 1
+		);
+
+		let mut dict = DictMap::default();
+
+		match result {
+			Ok(value) => {
+				keys::OK.with(|key| dict.insert(key.copy(), true.into()));
+				keys::VALUE.with(|key| dict.insert(key.copy(), value));
+			}
+
+			// Limits imposed by the host must not be circumvented by the script.
+			Err(panic) if panic.is_fatal() => return Err(panic),
+
+			Err(panic) => {
+				let description = color::plain(
+					|| fmt::Show(panic.untraced(), context.interner()).to_string()
+				);
+
+				keys::OK.with(|key| dict.insert(key.copy(), false.into()));
+				keys::ERROR.with(|key| dict.insert(key.copy(), description.into()));
+			}
+		}
+
+		Ok(Dict::new(dict).into())
+	}
+}
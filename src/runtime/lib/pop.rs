@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Pop) }
+inventory::submit! { Native::new(Pop, 1, "Remove and return the last element of an array.") }
 
 #[derive(Trace, Finalize)]
 struct Pop;
@@ -27,7 +27,7 @@ impl NativeFun for Pop {
 				Ok(value)
 			},
 
-			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -4,15 +4,16 @@ use gc::{Finalize, Trace};
 
 use crate::{fmt, symbol};
 use super::{
+	stdio::Output,
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Print) }
+inventory::submit! { Native::new(Print, 0.., "Print the arguments to stdout, separated by tabs.") }
 
 #[derive(Trace, Finalize)]
 struct Print;
@@ -26,6 +27,31 @@ impl Print {
 			value => write!(writer, "{}", fmt::Show(value, interner)),
 		}
 	}
+
+
+	/// Print the arguments separated by tabs, followed by a newline.
+	fn print_args(context: &CallContext, output: &Output) -> Result<Value, Panic> {
+		output
+			.with(
+				|writer| {
+					let mut iter = context.args().iter();
+
+					if let Some(value) = iter.next() {
+						Self::print(value, context.interner(), &mut *writer)?;
+					}
+
+					for value in iter {
+						write!(writer, "\t")?;
+						Self::print(value, context.interner(), &mut *writer)?;
+					}
+
+					writeln!(writer)
+				}
+			)
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		Ok(Value::default())
+	}
 }
 
 
@@ -33,27 +59,21 @@ impl NativeFun for Print {
 	fn name(&self) -> &'static str { "std.print" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		let stdout = io::stdout();
-		let mut stdout = stdout.lock();
+		Print::print_args(&context, &context.runtime.stdio.stdout)
+	}
+}
 
-		let mut iter = context.args().iter();
 
-		if let Some(value) = iter.next() {
-			Self::print(value, context.interner(), &mut stdout)
-				.map_err(|error| Panic::io(error, context.pos.copy()))?;
-		}
+inventory::submit! { Native::new(Eprint, 0.., "Print the arguments to stderr, separated by tabs.") }
 
-		for value in iter {
-			write!(stdout, "\t")
-				.map_err(|error| Panic::io(error, context.pos.copy()))?;
+#[derive(Trace, Finalize)]
+struct Eprint;
 
-			Self::print(value, context.interner(), &mut stdout)
-				.map_err(|error| Panic::io(error, context.pos.copy()))?;
-		}
 
-		writeln!(stdout)
-			.map_err(|error| Panic::io(error, context.pos))?;
+impl NativeFun for Eprint {
+	fn name(&self) -> &'static str { "std.eprint" }
 
-		Ok(Value::default())
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Print::print_args(&context, &context.runtime.stdio.stderr)
 	}
 }
@@ -0,0 +1,461 @@
+#![cfg(feature = "system")]
+
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	io::{self, Read, Write},
+	path::Path,
+	process::{self, Stdio},
+	rc::Rc,
+};
+
+use gc::{Finalize, Gc, Trace};
+
+use super::{
+	command::child,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Native,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Spawn, 1..=2, "Spawn a process from an array of arguments, returning a process object.") }
+inventory::submit!{ Native::new(Exec, 1..=2, "Replace the interpreter with the given program.") }
+
+
+/// Process option and method keys.
+mod keys {
+	use super::Value;
+
+	thread_local! {
+		pub static CWD: Value = "cwd".into();
+		pub static ENV: Value = "env".into();
+		pub static STDIN: Value = "stdin".into();
+		pub static STDOUT: Value = "stdout".into();
+		pub static STDERR: Value = "stderr".into();
+		pub static PID: Value = "pid".into();
+	}
+}
+
+
+/// How many bytes to read at most in a single read call.
+const READ_SIZE: usize = 64 * 1024;
+
+
+/// Collect environment variables from a dict of strings.
+fn env_vars(env: &Dict, context: &CallContext) -> Result<Vec<(Str, Str)>, Panic> {
+	env
+		.borrow()
+		.iter()
+		.map(
+			|(key, value)| match (key, value) {
+				(Value::String(ref key), Value::String(ref value)) => Ok((key.copy(), value.copy())),
+				(Value::String(_), other) | (other, _) => Err(
+					Panic::type_error(other.copy(), "string", context.arg_pos(1))
+				),
+			}
+		)
+		.collect()
+}
+
+
+/// Spawn a process from an array of arguments, returning a process object.
+/// Options may set the working directory, additional environment variables, and whether
+/// each standard stream is "pipe", "null" or "inherit" (the default).
+#[derive(Trace, Finalize)]
+struct Spawn;
+
+impl Spawn {
+	fn argv(array: &[Value], context: &CallContext) -> Result<Vec<Str>, Panic> {
+		if array.is_empty() {
+			return Err(
+				Panic::value_error(Vec::<Value>::new().into(), "non-empty array", context.arg_pos(0))
+			);
+		}
+
+		array
+			.iter()
+			.map(
+				|arg| match arg {
+					Value::String(ref string) => Ok(string.copy()),
+					other => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+				}
+			)
+			.collect()
+	}
+
+
+	fn stdio(
+		options: &Dict,
+		key: &'static std::thread::LocalKey<Value>,
+		context: &CallContext,
+	) -> Result<Stdio, Panic> {
+		match key.with(|key| options.get(key)) {
+			Err(_) => Ok(Stdio::inherit()),
+			Ok(Value::String(ref string)) => match string.as_bytes() {
+				b"pipe" => Ok(Stdio::piped()),
+				b"null" => Ok(Stdio::null()),
+				b"inherit" => Ok(Stdio::inherit()),
+				_ => Err(
+					Panic::value_error(
+						Value::String(string.copy()),
+						"pipe, null or inherit",
+						context.arg_pos(1)
+					)
+				),
+			},
+			Ok(other) => Err(Panic::type_error(other, "string", context.arg_pos(1))),
+		}
+	}
+
+
+	fn command(
+		argv: &[Str],
+		options: &Dict,
+		context: &CallContext,
+	) -> Result<process::Command, Panic> {
+		let mut cwd = context.runtime
+			.cwd()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		match keys::CWD.with(|key| options.get(key)) {
+			Err(_) => (),
+			Ok(Value::String(ref dir)) => cwd.push(Path::new(AsRef::<OsStr>::as_ref(dir))),
+			Ok(other) => return Err(Panic::type_error(other, "string", context.arg_pos(1))),
+		}
+
+		let mut command = child::command(argv[0].as_ref(), &cwd);
+		command.args(argv[1..].iter().map(AsRef::<OsStr>::as_ref));
+
+		match keys::ENV.with(|key| options.get(key)) {
+			Err(_) => (),
+
+			Ok(Value::Dict(ref env)) => {
+				for (key, value) in env_vars(env, context)? {
+					command.env(AsRef::<OsStr>::as_ref(&key), AsRef::<OsStr>::as_ref(&value));
+				}
+			},
+
+			Ok(other) => return Err(Panic::type_error(other, "dict", context.arg_pos(1))),
+		}
+
+		command
+			.stdin(Self::stdio(options, &keys::STDIN, context)?)
+			.stdout(Self::stdio(options, &keys::STDOUT, context)?)
+			.stderr(Self::stdio(options, &keys::STDERR, context)?);
+
+		Ok(command)
+	}
+
+
+	fn object(process: process::Child, context: &mut CallContext) -> Value {
+		let pid = process.id();
+
+		let state = Rc::new(RefCell::new(ProcessState { process, status: None }));
+		context.runtime.processes.push(state.clone());
+
+		let process = Gc::new(Process(state));
+
+		let methods = [
+			Method::Write,
+			Method::CloseStdin,
+			Method::Read,
+			Method::ReadErr,
+			Method::Wait,
+			Method::Kill,
+		];
+
+		let mut object: DictMap = methods
+			.iter()
+			.map(
+				|method| (
+					method.key().into(),
+					ProcessMethod { method: *method, process: process.clone() }.into()
+				)
+			)
+			.collect();
+
+		keys::PID.with(|key| object.insert(key.copy(), Value::Int(pid.into())));
+
+		Dict::new(object).into()
+	}
+}
+
+impl NativeFun for Spawn {
+	fn name(&self) -> &'static str { "std.process.spawn" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (argv, options) = match context.args() {
+			[ Value::Array(ref array) ] => (array.copy(), Dict::default()),
+			[ Value::Array(ref array), Value::Dict(ref dict) ] => (array.copy(), dict.copy()),
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.arg_pos(1))),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let argv = Self::argv(&argv.borrow(), &context)?;
+		let mut command = Self::command(&argv, &options, &context)?;
+
+		Ok(
+			match command.spawn() {
+				Ok(process) => Self::object(process, &mut context),
+				Err(error) => Error::new(error.to_string().into(), Value::String(argv[0].copy())).into(),
+			}
+		)
+	}
+}
+
+
+/// A spawned process, shared by the methods of a process object and the runtime.
+#[derive(Finalize)]
+struct Process(Rc<RefCell<ProcessState>>);
+
+/// Process has no garbage-collected fields.
+unsafe impl Trace for Process {
+	gc::unsafe_empty_trace!();
+}
+
+
+#[derive(Debug)]
+pub struct ProcessState {
+	process: process::Child,
+	/// The exit code, once the process has been waited.
+	status: Option<i32>,
+}
+
+impl ProcessState {
+	/// Kill the process and wait for it to exit, unless it has already been waited.
+	pub fn terminate(&mut self) {
+		if self.status.is_none() {
+			// The process may have exited meanwhile, in which case there's nothing to kill.
+			let _ = self.process.kill();
+			self.status = self.process
+				.wait()
+				.map(child::exit_code)
+				.ok();
+		}
+	}
+}
+
+
+/// Methods of process objects.
+#[derive(Debug, Clone, Copy)]
+enum Method {
+	/// Write a string or char to stdin.
+	Write,
+	/// Close stdin, signaling the end of the input.
+	CloseStdin,
+	/// Read the available output, blocking until there is some. Nil at the end.
+	Read,
+	/// Read the available error output, blocking until there is some. Nil at the end.
+	ReadErr,
+	/// Close stdin and wait for the process to exit, returning the exit code.
+	Wait,
+	/// Kill the process.
+	Kill,
+}
+
+/// Method has no garbage-collected fields.
+unsafe impl Trace for Method {
+	gc::unsafe_empty_trace!();
+}
+
+impl Finalize for Method { }
+
+impl Method {
+	fn key(&self) -> &'static str {
+		match self {
+			Self::Write => "write",
+			Self::CloseStdin => "close_stdin",
+			Self::Read => "read",
+			Self::ReadErr => "read_err",
+			Self::Wait => "wait",
+			Self::Kill => "kill",
+		}
+	}
+
+
+	fn read<R: Read>(
+		reader: Option<&mut R>,
+		stream: &'static str,
+		context: &CallContext,
+	) -> Result<io::Result<Value>, Panic> {
+		let reader = reader
+			.ok_or_else(|| Panic::value_error(stream.into(), "piped stream", context.pos.copy()))?;
+
+		let mut buffer = vec![0; READ_SIZE];
+
+		Ok(
+			reader
+				.read(&mut buffer)
+				.map(
+					|read| if read == 0 {
+						Value::default()
+					} else {
+						buffer.truncate(read);
+						buffer.into_boxed_slice().into()
+					}
+				)
+		)
+	}
+
+
+	fn call(
+		&self,
+		state: &mut ProcessState,
+		context: &CallContext,
+	) -> Result<io::Result<Value>, Panic> {
+		let pos = &context.pos;
+
+		let result = match (self, context.args()) {
+			(Self::Write, [ value ]) => {
+				let stdin = state.process.stdin
+					.as_mut()
+					.ok_or_else(|| Panic::value_error("stdin".into(), "piped stream", pos.copy()))?;
+
+				match value {
+					Value::String(ref string) => stdin.write_all(string.as_bytes()),
+					Value::Byte(byte) => stdin.write_all(&[ *byte ]),
+					other => return Err(Panic::type_error(other.copy(), "string or char", pos.copy())),
+				}
+				.and_then(|()| stdin.flush())
+				.map(|()| Value::default())
+			},
+
+			(Self::CloseStdin, []) => {
+				state.process.stdin = None;
+				Ok(Value::default())
+			},
+
+			(Self::Read, []) => return Self::read(state.process.stdout.as_mut(), "stdout", context),
+			(Self::ReadErr, []) => return Self::read(state.process.stderr.as_mut(), "stderr", context),
+
+			(Self::Wait, []) => match state.status {
+				Some(status) => Ok(Value::Int(status.into())),
+				None => {
+					// Close stdin, otherwise processes reading it would never finish.
+					state.process.stdin = None;
+
+					state.process
+						.wait()
+						.map(
+							|status| {
+								let status = child::exit_code(status);
+								state.status = Some(status);
+								Value::Int(status.into())
+							}
+						)
+				},
+			},
+
+			(Self::Kill, []) => match state.status {
+				Some(_) => Ok(Value::default()),
+				None => state.process
+					.kill()
+					.map(|()| Value::default()),
+			},
+
+			(Self::Write, args) => return Err(Panic::invalid_args(args.len() as u32, 1, pos.copy())),
+			(_, args) => return Err(Panic::invalid_args(args.len() as u32, 0, pos.copy())),
+		};
+
+		Ok(result)
+	}
+}
+
+
+/// A method bound to a process.
+#[derive(Trace, Finalize)]
+struct ProcessMethod {
+	method: Method,
+	process: Gc<Process>,
+}
+
+impl NativeFun for ProcessMethod {
+	fn name(&self) -> &'static str {
+		match self.method {
+			Method::Write => "std.process.spawn<write>",
+			Method::CloseStdin => "std.process.spawn<close_stdin>",
+			Method::Read => "std.process.spawn<read>",
+			Method::ReadErr => "std.process.spawn<read_err>",
+			Method::Wait => "std.process.spawn<wait>",
+			Method::Kill => "std.process.spawn<kill>",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let mut state = self.process.0.borrow_mut();
+		Ok(self.method.call(&mut state, &context)?.into())
+	}
+}
+
+
+/// Replace the interpreter with the given program, optionally replacing the environment.
+/// This never returns on success. On failure, an error is returned so that the script may
+/// fall back to something else.
+#[derive(Trace, Finalize)]
+struct Exec;
+
+impl Exec {
+	#[cfg(unix)]
+	fn exec(mut command: process::Command) -> io::Error {
+		use std::os::unix::process::CommandExt;
+
+		command.exec()
+	}
+
+
+	/// Emulate exec by running the program and exiting with its status.
+	#[cfg(not(unix))]
+	fn exec(mut command: process::Command) -> io::Error {
+		match command.status() {
+			Ok(status) => process::exit(child::exit_code(status)),
+			Err(error) => error,
+		}
+	}
+}
+
+impl NativeFun for Exec {
+	fn name(&self) -> &'static str { "std.process.exec" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (argv, env) = match context.args() {
+			[ Value::Array(ref array) ] => (array.copy(), None),
+			[ Value::Array(ref array), Value::Dict(ref dict) ] => (array.copy(), Some(dict.copy())),
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.arg_pos(1))),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let argv = Spawn::argv(&argv.borrow(), &context)?;
+
+		let cwd = context.runtime
+			.cwd()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let mut command = child::command(argv[0].as_ref(), &cwd);
+		command.args(argv[1..].iter().map(AsRef::<OsStr>::as_ref));
+
+		if let Some(env) = env {
+			command.env_clear();
+
+			for (key, value) in env_vars(&env, &context)? {
+				command.env(AsRef::<OsStr>::as_ref(&key), AsRef::<OsStr>::as_ref(&value));
+			}
+		}
+
+		// Buffered output would be lost when the process is replaced.
+		let _ = context.runtime.stdio.stdout.with(|stdout| stdout.flush());
+		let _ = context.runtime.stdio.stderr.with(|stderr| stderr.flush());
+
+		let error = Self::exec(command);
+
+		Ok(Error::new(error.to_string().into(), Value::String(argv[0].copy())).into())
+	}
+}
@@ -2,14 +2,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Push) }
+inventory::submit! { Native::new(Push, 2, "Append a value to an array.") }
 
 #[derive(Trace, Finalize)]
 struct Push;
@@ -24,7 +24,7 @@ impl NativeFun for Push {
 				Ok(Value::Nil)
 			},
 
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
@@ -0,0 +1,139 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(RandomFloat, 0, "A random float in [0, 1).") }
+inventory::submit!{ Native::new(RandomInt, 2, "A random int in [low, high].") }
+inventory::submit!{ Native::new(Choice, 1, "A random element of an array, or nil if empty.") }
+inventory::submit!{ Native::new(Shuffle, 1, "Shuffle an array in place.") }
+inventory::submit!{ Native::new(Seed, 1, "Seed the random number generator, making it reproducible.") }
+
+
+/// A random float in [0, 1).
+#[derive(Trace, Finalize)]
+struct RandomFloat;
+
+impl NativeFun for RandomFloat {
+	fn name(&self) -> &'static str { "std.random.float" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(context.runtime.rng.next_f64().into())
+	}
+}
+
+
+/// A random int in [low, high].
+#[derive(Trace, Finalize)]
+struct RandomInt;
+
+impl NativeFun for RandomInt {
+	fn name(&self) -> &'static str { "std.random.int" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(low), Value::Int(high) ] if high < low => Err(
+				Panic::value_error(Value::Int(*high), "int not lower than the lower bound", context.arg_pos(1))
+			),
+
+			[ Value::Int(low), Value::Int(high) ] => {
+				let (low, high) = (*low, *high);
+				Ok(context.runtime.rng.between(low, high).into())
+			},
+
+			[ Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(0))),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// A random element of an array, or nil if the array is empty.
+#[derive(Trace, Finalize)]
+struct Choice;
+
+impl NativeFun for Choice {
+	fn name(&self) -> &'static str { "std.random.choice" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let array = match context.args() {
+			[ Value::Array(ref array) ] => array.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		if array.is_empty() {
+			return Ok(Value::default());
+		}
+
+		let ix = context.runtime.rng.below(array.len() as u64);
+
+		Ok(
+			array
+				.index(ix as i64)
+				.expect("random index out of bounds")
+		)
+	}
+}
+
+
+/// Shuffle an array in place.
+#[derive(Trace, Finalize)]
+struct Shuffle;
+
+impl NativeFun for Shuffle {
+	fn name(&self) -> &'static str { "std.random.shuffle" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let array = match context.args() {
+			[ Value::Array(ref array) ] => array.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let mut array = array.borrow_mut();
+
+		// Fisher-Yates.
+		for ix in (1 .. array.len()).rev() {
+			let other = context.runtime.rng.below(ix as u64 + 1) as usize;
+			array.swap(ix, other);
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Seed the random number generator, making the generated sequence reproducible.
+#[derive(Trace, Finalize)]
+struct Seed;
+
+impl NativeFun for Seed {
+	fn name(&self) -> &'static str { "std.random.seed" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(seed) ] => {
+				let seed = *seed as u64;
+				context.runtime.rng.seed(seed);
+				Ok(Value::default())
+			},
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
 use super::{
@@ -7,15 +5,18 @@ use super::{
 	keys,
 	CallContext,
 	Dict,
-	RustFun,
+	DictMap,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Range) }
+inventory::submit! { Native::new(Range, 2..=3, "Iterate ints from start up to, but not including, end, by the given step.") }
 
+/// Iterate numbers from start up to, but not including, end. The step defaults to one,
+/// and counts down when negative. A zero step would never finish, and panics.
 #[derive(Trace, Finalize)]
 struct Range;
 
@@ -23,31 +24,41 @@ impl NativeFun for Range {
 	fn name(&self) -> &'static str { "std.range" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		match context.args() {
-			[ from, to, step ] => {
-				let numbers = util::Numbers
-					::promote([from.copy(), to.copy(), step.copy()])
-					.map_err(|value| Panic::type_error(value, "int or float", context.pos))?;
-
-				Ok(
-					match numbers {
-						util::Numbers::Ints([ from, to, step ]) => RangeImpl {
-							from: GcCell::new(from),
-							to,
-							step
-						}.into(),
-
-						util::Numbers::Floats([ from, to, step ]) => RangeImpl {
-							from: GcCell::new(from),
-							to,
-							step
-						}.into(),
-					}
-				)
-			},
-
-			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		let (from, to, step) = match context.args() {
+			[ from, to ] => (from, to, Value::Int(1)),
+			[ from, to, step ] => (from, to, step.copy()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let stalled = match step {
+			Value::Int(step) => step == 0,
+			Value::Float(ref step) => step.0 == 0.0 || step.is_nan(),
+			_ => false,
+		};
+
+		if stalled {
+			return Err(Panic::value_error(step, "non-zero step", context.arg_pos(2)));
 		}
+
+		let numbers = util::Numbers
+			::promote([from.copy(), to.copy(), step])
+			.map_err(|(ix, value)| Panic::type_error(value, "int or float", context.arg_pos(ix)))?;
+
+		Ok(
+			match numbers {
+				util::Numbers::Ints([ from, to, step ]) => RangeImpl {
+					from: GcCell::new(from),
+					to,
+					step
+				}.into(),
+
+				util::Numbers::Floats([ from, to, step ]) => RangeImpl {
+					from: GcCell::new(from),
+					to,
+					step
+				}.into(),
+			}
+		)
 	}
 }
 
@@ -74,7 +85,7 @@ where
 		}
 
 		let mut from = self.from.borrow_mut();
-		let mut iteration = HashMap::new();
+		let mut iteration = DictMap::default();
 
 		let finished =
 			if self.step > T::default() { // Step is positive.
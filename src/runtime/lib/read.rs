@@ -1,27 +1,27 @@
-use std::io::{self, Write};
+use std::io;
 
 use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Read) }
+inventory::submit! { Native::new(Read, 0..=1, "Read a line from stdin, printing the prompt if given.") }
 
 #[derive(Trace, Finalize)]
 struct Read;
 
 
 impl Read {
-	fn read() -> io::Result<Value> {
+	fn read(context: &mut CallContext) -> io::Result<Value> {
 		let mut input = String::new();
 
-		io::stdin()
+		context.runtime.stdio.stdin
 			.read_line(&mut input)
 			.map(|_| input.into())
 	}
@@ -31,28 +31,28 @@ impl Read {
 impl NativeFun for Read {
 	fn name(&self) -> &'static str { "std.read" }
 
-	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ ] => Self::read()
+			[ ] => Self::read(&mut context)
 				.map_err(|error| Panic::io(error, context.pos)),
 
 			[ Value::String(ref string) ] => {
-				let stdout = io::stdout();
-				let mut stdout = stdout.lock();
-
-				stdout
-					.write_all(string.as_ref())
-					.map_err(|error| Panic::io(error, context.pos.copy()))?;
-
-				stdout
-					.flush()
+				let prompt = string.copy();
+
+				context.runtime.stdio.stdout
+					.with(
+						|stdout| {
+							stdout.write_all(prompt.as_ref())?;
+							stdout.flush()
+						}
+					)
 					.map_err(|error| Panic::io(error, context.pos.copy()))?;
 
-				Self::read()
+				Self::read(&mut context)
 					.map_err(|error| Panic::io(error, context.pos))
 			},
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
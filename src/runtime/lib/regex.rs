@@ -1,4 +1,4 @@
-use std::{rc::Rc, collections::HashMap, borrow::Cow};
+use std::{rc::Rc, borrow::Cow};
 
 use gc::{Finalize, Trace};
 use regex::bytes::Regex;
@@ -7,7 +7,8 @@ use super::{
 	Error,
 	CallContext,
 	Dict,
-	RustFun,
+	DictMap,
+	Native,
 	NativeFun,
 	Panic,
 	Str,
@@ -15,7 +16,7 @@ use super::{
 };
 
 
-inventory::submit! { RustFun::from(StdRegex) }
+inventory::submit! { Native::new(StdRegex, 1, "Compile a regular expression, returning an object with match, split and replace methods.") }
 
 #[derive(Trace, Finalize)]
 struct StdRegex;
@@ -38,7 +39,7 @@ impl StdRegex {
 			pub static REPLACE: Value = "replace".into();
 		}
 
-		let mut dict = HashMap::new();
+		let mut dict = DictMap::default();
 
 		MATCH.with(
 			|name| dict.insert(name.copy(), RegexMatchImpl { pattern: pattern.clone() }.into())
@@ -63,7 +64,7 @@ impl NativeFun for StdRegex {
 		match context.args() {
 			[ Value::String(ref string) ] => Ok(Self::build(string.as_ref())),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -87,7 +88,7 @@ impl NativeFun for RegexMatchImpl {
 		match context.args() {
 			[ Value::String(ref string) ] => Ok(self.pattern.is_match(string.as_ref()).into()),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -117,7 +118,7 @@ impl NativeFun for RegexSplitImpl {
 					.into()
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -145,9 +146,8 @@ impl NativeFun for RegexReplaceImpl {
 				}
 			),
 
-			[ Value::String(_), other ] | [ other, _ ] => {
-				Err(Panic::type_error(other.copy(), "string", context.pos))
-			},
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
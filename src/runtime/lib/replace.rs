@@ -4,7 +4,7 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Str,
@@ -12,8 +12,10 @@ use super::{
 };
 
 
-inventory::submit! { RustFun::from(Replace) }
+inventory::submit! { Native::new(Replace, 3, "Replace all occurrences of a pattern in a string.") }
 
+/// Replace all occurrences of a pattern, which is matched as raw bytes, so that strings
+/// need not be valid UTF-8.
 #[derive(Trace, Finalize)]
 struct Replace;
 
@@ -30,9 +32,9 @@ impl NativeFun for Replace {
 				).into()
 			),
 
-			[ Value::String(_), Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			[ Value::String(_), other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			[ other, _, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ Value::String(_), Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(2))),
+			[ Value::String(_), other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 
 			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
 		}
@@ -0,0 +1,94 @@
+#![cfg(feature = "system")]
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::signal;
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Trap, 2, "Call a function whenever the given signal is received.") }
+inventory::submit!{ Native::new(Disposition::Ignore, 1, "Ignore a signal, removing any trap.") }
+inventory::submit!{ Native::new(Disposition::Default, 1, "Restore the default action of a signal, removing any trap.") }
+
+
+/// Get a signal number from its name, such as "TERM" or "SIGTERM".
+fn parse(value: &Value, context: &CallContext) -> Result<libc::c_int, Panic> {
+	match value {
+		Value::String(ref name) => signal
+			::parse(name.as_bytes())
+			.ok_or_else(|| Panic::value_error(value.copy(), "signal name", context.arg_pos(0))),
+
+		other => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+	}
+}
+
+
+/// Call a function whenever the given signal is received, passing the signal name.
+/// Handlers run between statements, not at the moment the signal is received.
+#[derive(Trace, Finalize)]
+struct Trap;
+
+impl NativeFun for Trap {
+	fn name(&self) -> &'static str { "std.signal.trap" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ name, Value::Function(ref handler) ] => {
+				let signal = parse(name, &context)?;
+				let handler = handler.copy();
+
+				signal
+					::trap(signal)
+					.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+				context.runtime.signal_handlers.insert(signal, handler);
+
+				Ok(Value::default())
+			},
+
+			[ _, other ] => Err(Panic::type_error(other.copy(), "function", context.arg_pos(1))),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Ignore a signal, or restore its default action, removing any trap.
+#[derive(Trace, Finalize)]
+enum Disposition {
+	Ignore,
+	Default,
+}
+
+impl NativeFun for Disposition {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Ignore => "std.signal.ignore",
+			Self::Default => "std.signal.default",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let signal = match context.args() {
+			[ name ] => parse(name, &context)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = match self {
+			Self::Ignore => signal::ignore(signal),
+			Self::Default => signal::reset(signal),
+		};
+
+		result.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		context.runtime.signal_handlers.remove(&signal);
+
+		Ok(Value::default())
+	}
+}
@@ -5,30 +5,55 @@ use gc::{Finalize, Trace};
 use super::{
 	CallContext,
 	NativeFun,
-	RustFun,
+	Native,
 	Panic,
 	Value,
 };
 
 
-inventory::submit!{ RustFun::from(Sleep) }
+inventory::submit!{ Native::new(Sleep, 1, "Suspend the interpreter for the given amount of seconds.") }
 
+/// Suspend the interpreter thread for the given amount of seconds.
 #[derive(Trace, Finalize)]
 struct Sleep;
 
+impl Sleep {
+	/// Sleep, unless the script is interrupted meanwhile.
+	fn sleep(duration: Duration, context: &CallContext) -> Result<Value, Panic> {
+		if !duration.is_zero() && !context.runtime.cancel.sleep(duration) {
+			return Err(Panic::interrupted(context.pos.copy()));
+		}
+
+		Ok(Value::default())
+	}
+}
+
 impl NativeFun for Sleep {
 	fn name(&self) -> &'static str { "std.sleep" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ Value::Int(i) ] if *i < 0 => Err(Panic::value_error(Value::Int(*i), "positive integer", context.pos)),
-
-			[ Value::Int(i) ] => {
-				std::thread::sleep(Duration::from_millis(*i as u64));
-				Ok(Value::default())
+			[ Value::Int(i) ] if *i < 0 => Err(
+				Panic::value_error(Value::Int(*i), "non-negative amount of seconds", context.pos)
+			),
+
+			[ Value::Int(i) ] => Self::sleep(Duration::from_secs(*i as u64), &context),
+
+			[ Value::Float(f) ] => {
+				let duration = Duration
+					::try_from_secs_f64(f.0)
+					.map_err(
+						|_| Panic::value_error(
+							Value::Float(f.copy()),
+							"non-negative amount of seconds",
+							context.pos.copy()
+						)
+					)?;
+
+				Self::sleep(duration, &context)
 			},
 
-			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.arg_pos(0))),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -0,0 +1,50 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	Array,
+	CallContext,
+	Native,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { Native::new(Slice, 3, "A new array with the elements of the given length starting at the given index.") }
+
+/// A new array with the elements of the given length starting at the given index. Unlike
+/// std.substr, out of range bounds are clamped to the array, so the result may be shorter
+/// than requested, or empty.
+#[derive(Trace, Finalize)]
+struct Slice;
+
+impl Slice {
+	/// The range of indexes, clamped to the given size.
+	fn range(size: usize, start: i64, len: i64) -> std::ops::Range<usize> {
+		let size = size as i64;
+		let start = start.clamp(0, size);
+		let end = start.saturating_add(len.max(0)).min(size);
+
+		start as usize .. end as usize
+	}
+}
+
+impl NativeFun for Slice {
+	fn name(&self) -> &'static str { "std.slice" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array), Value::Int(start), Value::Int(len) ] => {
+				let items = array.borrow();
+				let range = Self::range(items.len(), *start, *len);
+
+				Ok(Array::new(items[range].iter().map(Value::copy).collect()).into())
+			},
+			[ other, Value::Int(_), Value::Int(_) ] => Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			[ Value::Array(_), other, Value::Int(_) ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(1))),
+			[ Value::Array(_), Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(2))),
+			[ _, other, _ ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(1))),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
@@ -1,31 +1,140 @@
+use std::cmp::Ordering;
+
 use gc::{Finalize, Trace};
 
 use super::{
+	cmp,
+	Array,
 	CallContext,
-	RustFun,
+	Function,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Sort) }
+inventory::submit! { Native::new(Sort, 1..=2, "Stable sort an array in place, optionally with a comparator.") }
 
+/// Stable sort an array in place. The comparator, if given, must return an int which is
+/// negative, zero or positive. Otherwise, the values are sorted as in std.cmp.
 #[derive(Trace, Finalize)]
 struct Sort;
 
 impl NativeFun for Sort {
 	fn name(&self) -> &'static str { "std.sort" }
 
-	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
-		match context.args_mut() {
-			[ Value::Array(ref mut array) ] => {
-				array.sort();
-				Ok(Value::default())
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (array, comparator) = match context.args() {
+			[ Value::Array(ref array) ] => (array.copy(), None),
+			[ Value::Array(ref array), Value::Function(ref fun) ] => (array.copy(), Some(fun.copy())),
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.arg_pos(1))),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Self::sort(context, array, comparator)
+	}
+}
+
+
+inventory::submit! { Native::new(SortBy, 2, "Stable sort an array in place with a comparator.") }
+
+/// Stable sort an array in place with a comparator, as in std.sort.
+#[derive(Trace, Finalize)]
+struct SortBy;
+
+impl NativeFun for SortBy {
+	fn name(&self) -> &'static str { "std.sort_by" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (array, comparator) = match context.args() {
+			[ Value::Array(ref array), Value::Function(ref fun) ] => (array.copy(), fun.copy()),
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.arg_pos(1))),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.arg_pos(0))),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Sort::sort(context, array, Some(comparator))
+	}
+}
+
+
+impl Sort {
+	/// Sort the array with the comparator, or by std.cmp.
+	fn sort(
+		mut context: CallContext,
+		array: Array,
+		comparator: Option<Function>,
+	) -> Result<Value, Panic> {
+		// The array must not be borrowed while calling the comparator.
+		let items: Vec<Value> = array
+			.borrow()
+			.iter()
+			.map(Value::copy)
+			.collect();
+
+		let items = merge_sort(
+			items,
+			&mut |left, right| match &comparator {
+				Some(comparator) => Self::call_comparator(&mut context, comparator, left, right),
+				None => left
+					.compare(right)
+					.map_err(|value| Panic::type_error(value, cmp::ORDERED_TYPES, context.arg_pos(0))),
 			}
+		)?;
 
-			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		*array.borrow_mut() = items;
+
+		Ok(Value::default())
+	}
+
+
+	fn call_comparator(
+		context: &mut CallContext,
+		comparator: &Function,
+		left: &Value,
+		right: &Value,
+	) -> Result<Ordering, Panic> {
+		let args_start = context.runtime.arguments.len();
+		context.runtime.arguments.push(left.copy());
+		context.runtime.arguments.push(right.copy());
+
+		match context.call(Value::default(), comparator, args_start)? {
+			Value::Int(int) => Ok(int.cmp(&0)),
+			other => Err(Panic::type_error(other, "int", context.arg_pos(1))),
 		}
 	}
 }
+
+
+/// A stable merge sort with a fallible comparator.
+/// We can't use the standard library's sort, as the comparator may fail, and user
+/// provided comparators may not implement a total order.
+fn merge_sort<F>(mut items: Vec<Value>, compare: &mut F) -> Result<Vec<Value>, Panic>
+where
+	F: FnMut(&Value, &Value) -> Result<Ordering, Panic>,
+{
+	if items.len() <= 1 {
+		return Ok(items);
+	}
+
+	let right = items.split_off(items.len() / 2);
+	let left = merge_sort(items, compare)?;
+	let right = merge_sort(right, compare)?;
+
+	let mut merged = Vec::with_capacity(left.len() + right.len());
+	let mut left = left.into_iter().peekable();
+	let mut right = right.into_iter().peekable();
+
+	while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+		// Take from the left on ties to keep the sort stable.
+		let next = if compare(l, r)? == Ordering::Greater { right.next() } else { left.next() };
+		merged.extend(next);
+	}
+
+	merged.extend(left);
+	merged.extend(right);
+
+	Ok(merged)
+}
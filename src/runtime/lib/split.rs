@@ -4,15 +4,17 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Split) }
+inventory::submit! { Native::new(Split, 2, "Split a string by the given separator.") }
 
+/// Split a string by a separator, which is matched as raw bytes, so that strings need not
+/// be valid UTF-8.
 #[derive(Trace, Finalize)]
 struct Split;
 
@@ -30,8 +32,8 @@ impl NativeFun for Split {
 					.into()
 			),
 
-			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
@@ -1,26 +1,38 @@
+use std::convert::TryFrom;
+
 use gc::{Finalize, Trace};
 
 use super::{
     CallContext,
-    RustFun,
+    Native,
     NativeFun,
     Panic,
     Value,
-    Error,
 };
 
 
-inventory::submit! { RustFun::from(Substr) }
+inventory::submit! { Native::new(Substr, 3, "The substring of the given length starting at the given byte index.") }
 
+/// Strings are byte slices, so the start and length are in bytes, and the substring of a
+/// UTF-8 string may split a character.
 #[derive(Trace, Finalize)]
 struct Substr;
 
 impl Substr {
-    fn substr(string: &[u8], start: i64, len: i64) -> Result<Value, Error> {
-        let start = start as usize;
-        let end = start + (len as usize);
-        let substr = string.get(start..end);
-        Ok(substr.into())
+    /// The substring, or the index of the offending argument if it's out of bounds.
+    fn substr(string: &[u8], start: i64, len: i64) -> Result<&[u8], usize> {
+        let start = usize::try_from(start)
+            .ok()
+            .filter(|&start| start <= string.len())
+            .ok_or(1_usize)?;
+
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= string.len())
+            .ok_or(2_usize)?;
+
+        Ok(&string[start .. end])
     }
 }
 
@@ -30,15 +42,15 @@ impl NativeFun for Substr {
     fn call(&self, context: CallContext) -> Result<Value, Panic> {
         match context.args() {
             [ Value::String(ref string), Value::Int(start), Value::Int(len) ] => {
-                // TODO Panic if indexes out of bounds
-                let result = Self::substr(string.as_ref(), *start, *len);
-                Ok(result.unwrap_or_else(Into::into))
+                match Self::substr(string.as_ref(), *start, *len) {
+                    Ok(substr) => Ok(substr.into()),
+                    Err(ix) => Err(Panic::index_out_of_bounds(context.args()[ix].copy(), context.arg_pos(ix))),
+                }
             },
-            [ other, Value::Int(_), Value::Int(_) ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-            [ Value::String(_), other, Value::Int(_) ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
-            [ Value::String(_), Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+            [ other, Value::Int(_), Value::Int(_) ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+            [ Value::String(_), other, Value::Int(_) ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(1))),
+            [ Value::String(_), Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(2))),
             args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
         }
     }
 }
-
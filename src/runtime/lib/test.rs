@@ -0,0 +1,55 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Case, 2, "Register a named test case, to be executed by std.test.run.") }
+inventory::submit!{ Native::new(Run, 0, "Run the registered test cases, resulting in the number of failures.") }
+
+/// Register a test case, to be executed by std.test.run.
+#[derive(Trace, Finalize)]
+struct Case;
+
+impl NativeFun for Case {
+	fn name(&self) -> &'static str { "std.test.case" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref name), Value::Function(ref case) ] => {
+				let case = (name.copy(), case.copy());
+				context.runtime.test_cases.push(case);
+				Ok(Value::default())
+			},
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "function", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Run the registered test cases, resulting in the number of failures.
+#[derive(Trace, Finalize)]
+struct Run;
+
+impl NativeFun for Run {
+	fn name(&self) -> &'static str { "std.test.run" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let failures = context.runtime.run_test_cases(context.pos)?;
+
+		Ok(Value::Int(failures as i64))
+	}
+}
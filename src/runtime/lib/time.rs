@@ -0,0 +1,94 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Time, 0, "Wall-clock seconds since the Unix epoch, as a float.") }
+inventory::submit!{ Native::new(TimeMillis, 0, "Wall-clock milliseconds since the Unix epoch, as an int.") }
+inventory::submit!{ Native::new(StdClock, 0, "Monotonic seconds since an arbitrary epoch, for measuring durations.") }
+
+
+/// Get the elapsed wall-clock time since the Unix epoch.
+/// Times before the epoch are represented as negative durations.
+fn since_epoch(context: &CallContext) -> (bool, Duration) {
+	match context.runtime.clock.now().duration_since(UNIX_EPOCH) {
+		Ok(duration) => (false, duration),
+		Err(error) => (true, error.duration()),
+	}
+}
+
+
+/// Wall-clock seconds since the Unix epoch, as a float.
+#[derive(Trace, Finalize)]
+struct Time;
+
+impl NativeFun for Time {
+	fn name(&self) -> &'static str { "std.time" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let (negative, duration) = since_epoch(&context);
+		let seconds = duration.as_secs_f64();
+
+		Ok(
+			Value::from(if negative { -seconds } else { seconds })
+		)
+	}
+}
+
+
+/// Wall-clock milliseconds since the Unix epoch, as an int.
+#[derive(Trace, Finalize)]
+struct TimeMillis;
+
+impl NativeFun for TimeMillis {
+	fn name(&self) -> &'static str { "std.time_millis" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let (negative, duration) = since_epoch(&context);
+		let millis = duration.as_millis() as i64;
+
+		Ok(
+			Value::from(if negative { -millis } else { millis })
+		)
+	}
+}
+
+
+/// Monotonic seconds since an arbitrary epoch, as a float.
+/// Only the difference between two readings is meaningful, which makes this suitable for
+/// measuring durations.
+#[derive(Trace, Finalize)]
+struct StdClock;
+
+impl NativeFun for StdClock {
+	fn name(&self) -> &'static str { "std.clock" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			Value::from(context.runtime.clock.monotonic().as_secs_f64())
+		)
+	}
+}
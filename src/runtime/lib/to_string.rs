@@ -3,14 +3,14 @@ use gc::{Finalize, Trace};
 use crate::fmt::FmtString;
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(ToString) }
+inventory::submit! { Native::new(ToString, 1, "Convert a value to a string.") }
 
 #[derive(Trace, Finalize)]
 struct ToString;
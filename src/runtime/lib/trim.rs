@@ -4,14 +4,14 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
 };
 
 
-inventory::submit! { RustFun::from(Trim) }
+inventory::submit! { Native::new(Trim, 1, "Remove leading and trailing whitespace from a string.") }
 
 #[derive(Trace, Finalize)]
 struct Trim;
@@ -28,7 +28,7 @@ impl NativeFun for Trim {
 					.into()
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
 
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
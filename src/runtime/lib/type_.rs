@@ -2,7 +2,7 @@ use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
@@ -10,7 +10,7 @@ use super::{
 };
 
 
-inventory::submit! { RustFun::from(StdType) }
+inventory::submit! { Native::new(StdType, 1, "The name of the type of a value.") }
 
 #[derive(Trace, Finalize)]
 pub struct StdType;
@@ -1,10 +1,10 @@
 use gc::{Finalize, Trace};
 
-use crate::fmt;
+use crate::{fmt, term::color};
 
 use super::{
 	CallContext,
-	RustFun,
+	Native,
 	NativeFun,
 	Panic,
 	Value,
@@ -13,8 +13,8 @@ use super::{
 };
 
 
-inventory::submit! { RustFun::from(Typecheck) }
-inventory::submit! { RustFun::from(TryTypecheck) }
+inventory::submit! { Native::new(Typecheck, 2, "Panic if the value is not of the given type.") }
+inventory::submit! { Native::new(TryTypecheck, 2, "Return an error if the value is not of the given type.") }
 
 
 /// A typecheck result.
@@ -57,7 +57,7 @@ impl Typecheck {
 				)
 			}
 
-			[ _, other ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+			[ _, other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(1))),
 			args @ [] | args @ [_] | args @ [_, _, ..] => Err(
 				Panic::invalid_args(args.len() as u32, 2, context.pos.copy())
 			),
@@ -100,11 +100,13 @@ impl NativeFun for TryTypecheck {
 			TypeChecked::Valid(_) => Ok(Value::default()),
 
 			TypeChecked::Invalid { value, expected_type } => {
-				let description = format!(
-					"type error: expected {}, got {} ({})",
-					expected_type,
-					value.get_type(),
-					fmt::Show(&value, context.interner()),
+				let description = color::plain(
+					|| format!(
+						"type error: expected {}, got {} ({})",
+						expected_type,
+						value.get_type(),
+						fmt::Show(&value, context.interner()),
+					)
 				);
 
 				Ok(
@@ -0,0 +1,61 @@
+#![cfg(not(feature = "system"))]
+
+//! Without the system feature, the stdlib functions which require the host are replaced by
+//! functions that panic, so that scripts using them are still accepted.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Native,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ Native::new(Unsupported("std.fs.append"), 2, "Append a string to a file.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.copy"), 2, "Copy a file to the destination path.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.exists"), 1, "Whether the path exists.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.is_dir"), 1, "Whether the path is a directory.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.is_file"), 1, "Whether the path is a regular file.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.is_symlink"), 1, "Whether the path is a symbolic link.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.lines"), 1, "Iterate the lines of a file.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.list"), 1, "The names of the entries of a directory.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.mkdir"), 1, "Create a directory.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.mkdir_all"), 1, "Create a directory and all its missing parents.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.modified"), 1, "The modification time of a file, in seconds since the Unix epoch.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.open"), 2, "Open a file in the given mode, returning a handle.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.read"), 1, "Read a whole file as a string.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.remove"), 1, "Remove a file.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.remove_all"), 1, "Remove a directory and all its contents.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.remove_dir"), 1, "Remove an empty directory.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.rename"), 2, "Move a file or directory to the destination path.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.size"), 1, "The size of a file in bytes.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.tempdir"), 1, "Create a temporary directory, returning its path and a keep method. The directory is removed unless kept.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.tempfile"), 1, "Create a uniquely named temporary file, returning its path and a handle.") }
+inventory::submit!{ Native::new(Unsupported("std.fs.write"), 2, "Write a string to a file, replacing its contents.") }
+inventory::submit!{ Native::new(Unsupported("std.glob"), 1..=2, "The paths matching a glob pattern.") }
+inventory::submit!{ Native::new(Unsupported("std.process.exec"), 1..=2, "Replace the interpreter with the given program.") }
+inventory::submit!{ Native::new(Unsupported("std.process.spawn"), 1..=2, "Spawn a process from an array of arguments, returning a process object.") }
+inventory::submit!{ Native::new(Unsupported("std.signal.default"), 1, "Restore the default action of a signal, removing any trap.") }
+inventory::submit!{ Native::new(Unsupported("std.signal.ignore"), 1, "Ignore a signal, removing any trap.") }
+inventory::submit!{ Native::new(Unsupported("std.signal.trap"), 2, "Call a function whenever the given signal is received.") }
+
+
+/// A function which is not available in this build.
+#[derive(Finalize)]
+struct Unsupported(&'static str);
+
+/// Unsupported has no garbage-collected fields.
+unsafe impl Trace for Unsupported {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Unsupported {
+	fn name(&self) -> &'static str { self.0 }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Err(Panic::unsupported_operation(self.0, context.pos))
+	}
+}
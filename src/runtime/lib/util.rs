@@ -11,7 +11,8 @@ pub enum Numbers<const N: usize> {
 
 impl<const N: usize> Numbers<N> {
 	/// Promote numbers to float if necessary.
-	pub fn promote(values: [Value; N]) -> Result<Self, Value> {
+	/// Fails with the index and value of the first non-number.
+	pub fn promote(values: [Value; N]) -> Result<Self, (usize, Value)> {
 		let mut numbers = Numbers::Ints([0; N]);
 
 		for ix in 0..N {
@@ -27,7 +28,7 @@ impl<const N: usize> Numbers<N> {
 
 				(Numbers::Floats(floats), Value::Float(float)) => floats[ix] = float.copy(),
 
-				(_, value) => return Err(value.copy()),
+				(_, value) => return Err((ix, value.copy())),
 			}
 		}
 
@@ -57,6 +57,18 @@ impl Slot {
 	}
 
 
+	/// Borrow the value in the slot.
+	fn fetch_with<F, R>(&self, f: F) -> R
+	where
+		F: FnOnce(&Value) -> R,
+	{
+		match self {
+			Slot::Regular(value) => f(value),
+			Slot::Closed(value) => f(&value.deref().borrow()),
+		}
+	}
+
+
 	/// Store a new value in the slot.
 	fn store(&mut self, value: Value) {
 		match self {
@@ -98,41 +110,138 @@ impl Default for Slot {
 
 
 /// The call stack.
+/// The backing allocation only grows, so that calls near the largest depth reached never
+/// reallocate. Shrinking drops the values, but keeps the capacity.
 #[derive(Debug)]
 pub struct Stack {
 	/// The stack of slots.
 	slots: Vec<Slot>,
 	/// The maximum allowed size for the stack.
 	max_size: usize,
+	/// The largest size reached.
+	high_water_mark: usize,
+	/// The size of each frame, to check that frames are shrunk as they were extended.
+	#[cfg(debug_assertions)]
+	frames: Vec<u32>,
 }
 
 
 impl Stack {
+	/// The capacity of the first allocation, in slots.
+	const INITIAL_CAPACITY: usize = 256;
+
+
 	/// Create a new stack with the given maximum size.
 	pub fn new(max_size: usize) -> Self {
 		Self {
 			slots: Vec::new(),
 			max_size,
+			high_water_mark: 0,
+			#[cfg(debug_assertions)]
+			frames: Vec::new(),
+		}
+	}
+
+
+	/// Prepare to push a frame of the given size, reserving capacity if needed.
+	/// Returns StackOverflow if the size exceeds the maximum size.
+	#[inline]
+	fn grow(&mut self, slots: &SlotIx) -> Result<usize, StackOverflow> {
+		let new_size = self.len() + slots.0 as usize;
+
+		if new_size > self.max_size {
+			return Err(StackOverflow);
+		}
+
+		if new_size > self.slots.capacity() {
+			self.reserve(new_size);
 		}
+
+		self.high_water_mark = self.high_water_mark.max(new_size);
+
+		#[cfg(debug_assertions)]
+		self.frames.push(slots.0);
+
+		Ok(new_size)
+	}
+
+
+	/// Reserve capacity for the given size, growing geometrically, but never beyond the
+	/// maximum size.
+	#[cold]
+	fn reserve(&mut self, size: usize) {
+		let capacity = (2 * self.slots.capacity())
+			.max(Self::INITIAL_CAPACITY)
+			.max(size)
+			.min(self.max_size);
+
+		self.slots.reserve_exact(capacity - self.len());
 	}
 
 
 	/// Add the given ammount of Nil valued slots to the top of the stack.
 	/// Returns StackOverflow if the size exceeds the maximum size.
 	pub fn extend(&mut self, slots: SlotIx) -> Result<(), StackOverflow> {
-		let new_size = self.len() + slots.0 as usize;
+		let new_size = self.grow(&slots)?;
+		self.slots.resize_with(new_size, Slot::default);
+		Ok(())
+	}
+
+
+	/// Add the given ammount of slots to the top of the stack, placing the given values in
+	/// the topmost slots, in order, and Nil in the rest. This is equivalent to extending and
+	/// then storing each value, without matching on every slot.
+	/// Returns StackOverflow if the size exceeds the maximum size.
+	pub fn extend_with<I>(&mut self, slots: SlotIx, values: I) -> Result<(), StackOverflow>
+	where
+		I: DoubleEndedIterator<Item = Value> + ExactSizeIterator,
+	{
+		debug_assert!(values.len() <= slots.0 as usize);
+
+		let new_size = self.grow(&slots)?;
+		self.slots.resize_with(new_size - values.len(), Slot::default);
+		self.slots.extend(values.rev().map(Slot::Regular));
+		Ok(())
+	}
+
+
+	/// Add the given ammount of Nil valued slots to the bottom of the stack, which must
+	/// hold a single frame. As slots are indexed from the top, this grows the frame while
+	/// preserving the indexes of its slots.
+	/// Returns StackOverflow if the size exceeds the maximum size.
+	pub fn extend_bottom(&mut self, slots: SlotIx) -> Result<(), StackOverflow> {
+		#[cfg(debug_assertions)]
+		debug_assert_eq!(self.frames.len(), 1, "extending the bottom of multiple frames");
+
+		let count = slots.0 as usize;
+		let new_size = self.len() + count;
 
 		if new_size > self.max_size {
-			Err(StackOverflow)
-		} else {
-			self.slots.resize_with(new_size, Slot::default);
-			Ok(())
+			return Err(StackOverflow);
+		}
+
+		if new_size > self.slots.capacity() {
+			self.reserve(new_size);
+		}
+
+		self.high_water_mark = self.high_water_mark.max(new_size);
+
+		#[cfg(debug_assertions)]
+		{
+			self.frames[0] += slots.0;
 		}
+
+		self.slots.splice(0 .. 0, std::iter::repeat_with(Slot::default).take(count));
+		Ok(())
 	}
 
 
-	/// Remove the given ammount of elements from the top of the stack.
+	/// Remove the given ammount of elements from the top of the stack, which must be the
+	/// size of the topmost frame.
 	pub fn shrink(&mut self, slots: SlotIx) {
+		#[cfg(debug_assertions)]
+		debug_assert_eq!(self.frames.pop(), Some(slots.0), "shrinking a frame of another size");
+
 		self.slots.truncate(self.len() - slots.0 as usize);
 	}
 
@@ -145,6 +254,17 @@ impl Stack {
 	}
 
 
+	/// Borrow the value of a slot, which spares copying it when a reference suffices.
+	/// The offset is counted from the top.
+	pub fn fetch_with<F, R>(&self, slot_ix: SlotIx, f: F) -> R
+	where
+		F: FnOnce(&Value) -> R,
+	{
+		let offset = slot_ix.0 as usize;
+		self.slots[self.len() - 1 - offset].fetch_with(f)
+	}
+
+
 	/// Capture a slot.
 	pub fn capture(&mut self, slot_ix: SlotIx) -> Gc<GcCell<Value>> {
 		let len = self.len();
@@ -180,6 +300,12 @@ impl Stack {
 	pub fn len(&self) -> usize {
 		self.slots.len()
 	}
+
+
+	/// The largest length the stack has reached.
+	pub fn high_water_mark(&self) -> usize {
+		self.high_water_mark
+	}
 }
 
 
@@ -2,39 +2,105 @@
 #![allow(clippy::mutable_key_type)]
 
 
+mod cancel;
+mod clock;
+#[cfg(feature = "system")]
 mod command;
+#[cfg(feature = "system")]
+pub mod executor;
 mod flow;
+mod host;
 mod lib;
 mod mem;
 mod panic;
+mod random;
+#[cfg(feature = "system")]
+mod signal;
 mod source;
+mod stdio;
+#[cfg(feature = "system")]
+mod temp;
+mod trace;
+mod traceback;
 pub mod value;
+mod vm;
 #[cfg(test)]
 mod tests;
 
-use std::{collections::HashMap, ops::Deref};
+use std::{
+	collections::{HashMap, HashSet},
+	convert::TryFrom,
+	io::{self, Write},
+	ops::Deref,
+	path::PathBuf,
+	rc::Rc,
+};
+#[cfg(feature = "system")]
+use std::{cell::RefCell, rc::Weak, sync::Arc};
 
-use crate::symbol::{self, Symbol};
+use crate::{
+	fmt::{self, FmtString},
+	symbol::{self, Symbol},
+	term::color,
+};
 use super::semantic::program;
 use value::{
 	keys,
 	Array,
 	CallContext,
 	Dict,
+	DictMap,
+	FxBuildHasher,
 	Error,
 	Float,
 	Function,
 	HushFun,
 	RustFun,
 	NativeFun,
+	SharedProgram,
 	Str,
 	Value,
 	Type,
 };
-pub use panic::Panic;
+pub use cancel::CancellationToken;
+pub use clock::{Clock, SystemClock};
+pub use lib::{entries as std_entries, members as std_members};
+pub use panic::{Panic, PanicKind};
 pub use source::SourcePos;
+pub use trace::{CallEvent, Directive, Locals, TraceOptions, TracePoint, TraceResumer};
+pub use traceback::{Frame, Traceback};
+pub use vm::Interpreter;
+use vm::FieldCaches;
+#[cfg(feature = "system")]
+use executor::{CommandExecutor, SystemExecutor};
 use flow::Flow;
+use host::HostFun;
 use mem::Stack;
+use random::Rng;
+use stdio::Stdio;
+#[cfg(feature = "system")]
+use temp::TempDir;
+use trace::{CallHook, TraceHook};
+
+
+/// The native stack the default maximum call depth is meant to fit in: the default size of
+/// the main thread on Linux and macOS. Hosts running the runtime on smaller stacks, such as
+/// threads spawned by Rust, which have 2 MiB by default, must lower the maximum call depth
+/// with `set_max_call_depth`, or spawn threads with this size.
+pub const DEFAULT_STACK_SIZE: usize = 8 << 20;
+
+
+/// A generous estimate of the native stack used by a nested call, including the expressions
+/// it is nested in. Unoptimized builds use an order of magnitude more.
+const CALL_STACK_SIZE: usize = if cfg!(debug_assertions) { 96 << 10 } else { 12 << 10 };
+
+
+/// The default maximum depth of nested calls, which fits in `DEFAULT_STACK_SIZE`: about 85
+/// calls in unoptimized builds, and 680 in optimized ones. Calls recurse on the native
+/// stack, which must fit this many calls for deep recursion to panic instead of crashing
+/// the process. Hosts that run the runtime on a larger stack may allow deeper recursion
+/// with `set_max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = DEFAULT_STACK_SIZE / CALL_STACK_SIZE;
 
 
 /// A runtime instance to execute Hush programs.
@@ -45,9 +111,76 @@ pub struct Runtime {
 	arguments: Vec<Value>,
 	std: Value,
 	interner: symbol::Interner,
-	modules: HashMap<Symbol, Value>,
+	modules: HashMap<Symbol, Value, FxBuildHasher>,
+	/// Paths given to std.import, including modules that failed to load.
+	imports: HashSet<Symbol, FxBuildHasher>,
+	/// Modules currently being loaded, outermost first, to detect import cycles.
+	loading: Vec<Symbol>,
+	/// Directories searched by std.import for names that aren't explicitly relative.
+	module_path: Vec<PathBuf>,
 	/// Command line arguments.
 	args: Value,
+	/// Time source for the stdlib.
+	clock: Box<dyn Clock>,
+	/// Random number generator for the stdlib.
+	rng: Rng,
+	/// Working directory of the script, if changed. Commands and the stdlib resolve
+	/// relative paths against it, leaving the process' working directory untouched.
+	cwd: Option<PathBuf>,
+	/// Temporary directories created by the stdlib, removed at the end of evaluation.
+	#[cfg(feature = "system")]
+	temp_dirs: Vec<Weak<TempDir>>,
+	/// Processes spawned with std.process.spawn, which may be killed by the host.
+	#[cfg(feature = "system")]
+	processes: Vec<Rc<RefCell<lib::ProcessState>>>,
+	/// Script callbacks for trapped signals.
+	#[cfg(feature = "system")]
+	signal_handlers: HashMap<libc::c_int, Function>,
+	/// Test cases registered with std.test.case, in order of registration.
+	test_cases: Vec<(Str, Function)>,
+	/// Function calls in progress, for panic tracebacks.
+	calls: Vec<Frame>,
+	/// Maximum depth of nested calls, past which calls panic with a stack overflow.
+	max_call_depth: usize,
+	/// Callback for panics escaping the runtime.
+	panic_hook: Option<PanicHook>,
+	/// The program being executed, which is shared with the functions it defines.
+	program: Option<SharedProgram>,
+	/// Interrupts execution when triggered by the host.
+	cancel: CancellationToken,
+	/// Remaining execution budget, if limited.
+	fuel: Option<u64>,
+	/// Standard streams for the stdlib and commands.
+	stdio: Stdio,
+	/// Spawns the processes of command blocks.
+	#[cfg(feature = "system")]
+	executor: Arc<dyn CommandExecutor>,
+	/// Callback before each statement, for debuggers.
+	trace: Option<TraceHook>,
+	/// Resumes execution when paused by the trace hook.
+	resumer: TraceResumer,
+	/// Callback when entering and leaving calls, for profilers.
+	call_hook: Option<CallHook>,
+	/// Keys of the iteration protocol, for for loops.
+	keys: keys::Protocol,
+	/// Which interpreter executes programs.
+	interpreter: Interpreter,
+	/// Caches of the virtual machine's field accesses.
+	field_caches: FieldCaches,
+	/// The size of the root frame of the interactive session, if started. The frame is kept
+	/// on the stack between inputs.
+	session: Option<mem::SlotIx>,
+}
+
+
+/// A callback for panics escaping the runtime.
+struct PanicHook(Box<dyn Fn(&Panic) + Send>);
+
+
+impl std::fmt::Debug for PanicHook {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("PanicHook")
+	}
 }
 
 
@@ -69,8 +202,37 @@ impl Runtime {
 			arguments: Vec::new(),
 			interner,
 			std: lib::new(),
-			modules: HashMap::new(),
+			modules: HashMap::default(),
+			imports: HashSet::default(),
+			loading: Vec::new(),
+			module_path: Vec::new(),
 			args: args.into(),
+			clock: Box::new(SystemClock::default()),
+			rng: Rng::default(),
+			cwd: None,
+			#[cfg(feature = "system")]
+			temp_dirs: Vec::new(),
+			#[cfg(feature = "system")]
+			processes: Vec::new(),
+			#[cfg(feature = "system")]
+			signal_handlers: HashMap::new(),
+			test_cases: Vec::new(),
+			calls: Vec::new(),
+			max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+			panic_hook: None,
+			program: None,
+			cancel: CancellationToken::default(),
+			fuel: None,
+			stdio: Stdio::default(),
+			#[cfg(feature = "system")]
+			executor: Arc::new(SystemExecutor),
+			trace: None,
+			resumer: TraceResumer::default(),
+			call_hook: None,
+			keys: keys::Protocol::default(),
+			interpreter: Interpreter::default(),
+			field_caches: FieldCaches::default(),
+			session: None,
 		}
 	}
 
@@ -87,8 +249,394 @@ impl Runtime {
 	}
 
 
+	/// Replace the time source used by the stdlib.
+	pub fn set_clock<C>(&mut self, clock: C)
+	where
+		C: Clock + 'static,
+	{
+		self.clock = Box::new(clock);
+	}
+
+
+	/// Set a callback to be invoked with each panic that escapes the runtime, that is, which
+	/// is returned by `eval` or `run_tests`. The hook is invoked once per panic, right before
+	/// it is returned, and can't prevent it from being returned. Panics caught by the script,
+	/// such as with std.catch, and panics in test cases, which are reported as failures,
+	/// never reach the hook.
+	pub fn set_panic_hook<F>(&mut self, hook: F)
+	where
+		F: Fn(&Panic) + Send + 'static,
+	{
+		self.panic_hook = Some(PanicHook(Box::new(hook)));
+	}
+
+
+	/// Get a handle to interrupt scripts executed by this runtime, possibly from another
+	/// thread. Once cancelled, execution stops at the next loop iteration or function call
+	/// with an interrupted panic, and children of running command blocks are killed.
+	pub fn cancellation_token(&self) -> CancellationToken {
+		self.cancel.clone()
+	}
+
+
+	/// Limit the execution budget, or remove the limit with None. A unit of fuel is consumed
+	/// per statement, per loop iteration and per command spawned. Exhausting the fuel raises
+	/// an out of fuel panic, which can't be caught by the script.
+	pub fn set_fuel(&mut self, fuel: Option<u64>) {
+		self.fuel = fuel;
+	}
+
+
+	/// The remaining execution budget, if limited.
+	pub fn fuel(&self) -> Option<u64> {
+		self.fuel
+	}
+
+
+	/// Limit the depth of nested calls, including calls to stdlib functions. Exceeding it
+	/// raises a stack overflow panic at the call. The host must provide enough native stack
+	/// for the given depth, as calls recurse on the native stack.
+	pub fn set_max_call_depth(&mut self, depth: usize) {
+		self.max_call_depth = depth;
+	}
+
+
+	/// Set a callback to be invoked before each statement, with its position and the
+	/// current call depth. The returned directive may pause or abort the script, which
+	/// allows building step debuggers on top.
+	pub fn set_trace_hook<F>(&mut self, hook: F, options: TraceOptions)
+	where
+		F: FnMut(&TracePoint) -> Directive + Send + 'static,
+	{
+		self.trace = Some(TraceHook { hook: Box::new(hook), options });
+	}
+
+
+	/// Set a callback to be invoked when entering and leaving each function call and command
+	/// block, which allows building profilers on top. Without a hook, calls are not slowed
+	/// down.
+	pub fn set_call_hook<F>(&mut self, hook: F)
+	where
+		F: FnMut(&CallEvent) + Send + 'static,
+	{
+		self.call_hook = Some(CallHook(Box::new(hook)));
+	}
+
+
+	/// Get a handle to resume the script when paused by the trace hook, possibly from
+	/// another thread.
+	pub fn trace_resumer(&self) -> TraceResumer {
+		self.resumer.clone()
+	}
+
+
+	/// Set the stdout for the stdlib, commands and test reports, instead of the process'
+	/// stdout. Output of commands is forwarded to the writer by a separate thread.
+	pub fn set_stdout<W>(&mut self, writer: W)
+	where
+		W: Write + Send + 'static,
+	{
+		self.stdio.stdout = stdio::Output::custom(writer);
+	}
+
+
+	/// Set the stderr for the stdlib, commands and panics in tests and imported modules,
+	/// instead of the process' stderr. Output of commands is forwarded to the writer by a
+	/// separate thread.
+	pub fn set_stderr<W>(&mut self, writer: W)
+	where
+		W: Write + Send + 'static,
+	{
+		self.stdio.stderr = stdio::Output::custom(writer);
+	}
+
+
+	/// Set the stdin for the stdlib, instead of the process' stdin. Commands get an empty
+	/// stdin in that case.
+	pub fn set_stdin<R>(&mut self, reader: R)
+	where
+		R: io::Read + Send + 'static,
+	{
+		self.stdio.stdin = stdio::Input::custom(reader);
+	}
+
+
+	/// Read a line from the stdin of the runtime, including the line terminator, if any.
+	pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+		self.stdio.stdin.read_line(buf)
+	}
+
+
+	/// Write to the stdout of the runtime.
+	pub fn with_stdout<F, T>(&self, write: F) -> io::Result<T>
+	where
+		F: FnOnce(&mut dyn Write) -> io::Result<T>,
+	{
+		self.stdio.stdout.with(write)
+	}
+
+
+	/// Write to the stderr of the runtime.
+	pub fn with_stderr<F, T>(&self, write: F) -> io::Result<T>
+	where
+		F: FnOnce(&mut dyn Write) -> io::Result<T>,
+	{
+		self.stdio.stderr.with(write)
+	}
+
+
+	/// Set the executor for the processes of command blocks, in order to fake or restrict
+	/// command execution.
+	#[cfg(feature = "system")]
+	pub fn set_executor<E>(&mut self, executor: E)
+	where
+		E: CommandExecutor + 'static,
+	{
+		self.executor = Arc::new(executor);
+	}
+
+
+	/// Register a native function in the stdlib, making host functionality available to
+	/// scripts. The name must include the std prefix, and may be nested, like
+	/// `std.host.query`, in which case the intermediate dicts are created as needed. Calls
+	/// with a number of arguments other than the arity panic before reaching the function.
+	/// As the function is not traced by the garbage collector, it must not capture values.
+	pub fn register_fn<F>(&mut self, name: &'static str, arity: u32, fun: F)
+	where
+		F: Fn(CallContext) -> Result<Value, Panic> + 'static,
+	{
+		match &mut self.std {
+			Value::Dict(std) => lib::register(std, HostFun::new(name, arity, fun).into()),
+			_ => unreachable!("std is always a dict"),
+		}
+	}
+
+
+	/// Register a value in the stdlib, such as host data or a dict of native functions. The
+	/// name follows the same rules as in `register_fn`. The value is shared by every program
+	/// executed afterwards, so that changes made by scripts are visible to the host.
+	pub fn register_value(&mut self, name: &'static str, value: Value) {
+		match &mut self.std {
+			Value::Dict(std) => lib::register_value(std, name, value),
+			_ => unreachable!("std is always a dict"),
+		}
+	}
+
+
+	/// Get the working directory of the script.
+	/// Defaults to the process' working directory.
+	pub fn cwd(&self) -> io::Result<PathBuf> {
+		match &self.cwd {
+			Some(cwd) => Ok(cwd.clone()),
+			None => std::env::current_dir(),
+		}
+	}
+
+
+	/// The paths of the modules imported so far, canonicalized. Modules that failed to load
+	/// are included, so that hosts may watch them for changes.
+	pub fn imports(&self) -> impl Iterator<Item = Symbol> + '_ {
+		self.imports.iter().copied()
+	}
+
+
+	/// Set the directories searched by std.import, in order, for names that aren't
+	/// explicitly relative, such as `utils/strings`. Such names are first searched next to
+	/// the importing script, while names starting with `./` or `../` are only searched
+	/// there.
+	pub fn set_module_path(&mut self, dirs: Vec<PathBuf>) {
+		self.module_path = dirs;
+	}
+
+
+	/// Kill the processes spawned with std.process.spawn that are still running, such as
+	/// background jobs left behind by the script, and wait for them to exit.
+	#[cfg(feature = "system")]
+	pub fn kill_processes(&mut self) {
+		for process in self.processes.drain(..) {
+			process.borrow_mut().terminate();
+		}
+	}
+
+
+	/// Select the interpreter that executes programs. Both have the same observable
+	/// behavior, but the default virtual machine is faster.
+	pub fn set_interpreter(&mut self, interpreter: Interpreter) {
+		self.interpreter = interpreter;
+	}
+
+
+	/// Change the working directory of the script.
+	pub fn set_cwd(&mut self, cwd: PathBuf) {
+		self.cwd = Some(cwd);
+	}
+
+
 	/// Execute the given program.
-	pub fn eval(&mut self, program: &'static program::Program) -> Result<Value, Panic> {
+	/// Temporary directories created during execution are removed afterwards, unless
+	/// explicitly kept. The program is kept alive for as long as the functions it defines,
+	/// and dropped afterwards.
+	pub fn eval<P>(&mut self, program: P) -> Result<Value, Panic>
+	where
+		P: Into<Rc<program::Program>>,
+	{
+		let program = SharedProgram::new(program.into());
+		let result = self.eval_module(program);
+
+		#[cfg(feature = "system")]
+		self.remove_temp_dirs();
+
+		self.escape(result)
+	}
+
+
+	/// Execute the given program as the next input of an interactive session. Unlike `eval`,
+	/// the global variables are kept on the stack afterwards, so that later inputs may use
+	/// them. The programs must be analyzed in the same semantic session, in order.
+	pub fn eval_session<P>(&mut self, program: P) -> Result<Value, Panic>
+	where
+		P: Into<Rc<program::Program>>,
+	{
+		let program = SharedProgram::new(program.into());
+
+		let previous = self.program.replace(program.clone());
+		let result = self.eval_session_root(
+			&program,
+			// SAFETY: the program is kept alive by the local copy until the end of the
+			// evaluation, and by the functions it defines afterwards.
+			unsafe { program.get() }
+		);
+		self.program = previous;
+
+		#[cfg(feature = "system")]
+		self.remove_temp_dirs();
+
+		self.escape(result)
+	}
+
+
+	/// Run and unregister the test cases registered with std.test.case, printing the
+	/// outcome of each and a summary to stdout. Panics in test cases are reported as
+	/// failures, with the panic printed to stderr, and the number of failures is returned.
+	/// Other panics are raised at the given position.
+	pub fn run_tests(&mut self, pos: SourcePos) -> Result<usize, Panic> {
+		let result = self.run_test_cases(pos);
+		self.escape(result)
+	}
+
+
+	/// Run the registered test cases, without invoking the panic hook.
+	fn run_test_cases(&mut self, pos: SourcePos) -> Result<usize, Panic> {
+		let cases = std::mem::take(&mut self.test_cases);
+		let total = cases.len();
+		let mut failures = 0;
+
+		let stdout = self.stdio.stdout.clone();
+		let stderr = self.stdio.stderr.clone();
+		let io_error = |error| Panic::io(error, pos.copy());
+
+		for (name, case) in cases {
+			let name = String::from_utf8_lossy(name.as_bytes()).into_owned();
+			let args_start = self.arguments.len();
+
+			match self.call(Value::default(), &case, args_start, &[], pos.copy()) {
+				Ok(_) => stdout
+					.with(|out| writeln!(out, "{} {}", color::Fg(color::Green, "PASS"), name))
+					.map_err(io_error)?,
+
+				Err(panic) if panic.is_fatal() => return Err(panic),
+
+				Err(panic) => {
+					failures += 1;
+					stdout
+						.with(|out| writeln!(out, "{} {}", color::Fg(color::Red, "FAIL"), name))
+						.map_err(io_error)?;
+					stderr
+						.with(|err| writeln!(err, "{}", fmt::Show(panic, self.interner())))
+						.map_err(io_error)?;
+				},
+			}
+		}
+
+		stdout
+			.with(|out| writeln!(out, "{} passed, {} failed", total - failures, failures))
+			.map_err(io_error)?;
+
+		#[cfg(feature = "system")]
+		self.remove_temp_dirs();
+
+		Ok(failures)
+	}
+
+
+	/// Call a function value, such as a member of the dict returned by `eval`, with the
+	/// given arguments. Panics are raised at the fictional `<host>` position. This may be
+	/// used from native functions as well, to call back into the script, in which case
+	/// panics only reach the panic hook when returned to the host.
+	pub fn call_value(&mut self, function: &Value, args: Vec<Value>) -> Result<Value, Panic> {
+		let pos = SourcePos::file(self.interner.get_or_intern("<host>"));
+		let outermost = self.calls.is_empty();
+
+		let result = match function {
+			Value::Function(function) => {
+				let args_start = self.arguments.len();
+				self.arguments.extend(args);
+				self.call(Value::default(), function, args_start, &[], pos)
+			}
+
+			other => Err(Panic::invalid_call(other.copy(), pos)),
+		};
+
+		if outermost {
+			self.escape(result)
+		} else {
+			result
+		}
+	}
+
+
+	/// Invoke the panic hook, if the result is a panic escaping the runtime.
+	fn escape<T>(&self, result: Result<T, Panic>) -> Result<T, Panic> {
+		if let (Err(panic), Some(PanicHook(hook))) = (&result, &self.panic_hook) {
+			hook(panic);
+		}
+
+		result
+	}
+
+
+	/// Remove temporary directories that were not explicitly kept.
+	#[cfg(feature = "system")]
+	fn remove_temp_dirs(&mut self) {
+		for temp_dir in self.temp_dirs.drain(..) {
+			if let Some(temp_dir) = temp_dir.upgrade() {
+				temp_dir.remove();
+			}
+		}
+	}
+
+
+	/// Execute the given program, which may be the main program or an imported module.
+	fn eval_module(&mut self, program: SharedProgram) -> Result<Value, Panic> {
+		let previous = self.program.replace(program.clone());
+		let result = self.eval_root(
+			&program,
+			// SAFETY: the program is kept alive by the local copy until the end of the
+			// evaluation, and by the functions it defines afterwards.
+			unsafe { program.get() }
+		);
+		self.program = previous;
+
+		result
+	}
+
+
+	/// Execute the root block of the current program.
+	fn eval_root(
+		&mut self,
+		shared: &SharedProgram,
+		program: &'static program::Program,
+	) -> Result<Value, Panic> {
 		// Global variables.
 		let slots: mem::SlotIx = program.root_slots.into();
 
@@ -103,14 +651,22 @@ impl Runtime {
 		self.stack.store(mem::SlotIx(0), self.std.copy());
 
 		// Execute the program.
-		let value = match self.eval_block(&program.statements)? {
-			Flow::Regular(value) => value,
-			flow => panic!("invalid flow in root state: {:#?}", flow)
+		let flow = match self.interpreter {
+			Interpreter::TreeWalker => self.eval_block(&program.statements),
+			Interpreter::Vm => {
+				let code = shared.code(&program.statements, &mut self.field_caches);
+				self.exec(&code, |_| ())
+			}
 		};
 
-		// Drop global variables.
+		// Drop global variables, even if the program panicked.
 		self.stack.shrink(slots);
 
+		let value = match flow? {
+			Flow::Regular(value) => value,
+			flow => return Err(Self::invalid_root_flow(flow, program)),
+		};
+
 		debug_assert_eq!(self.stack.len(), initial_stack_len);
 		debug_assert_eq!(self.arguments.len(), initial_args_len);
 
@@ -118,6 +674,60 @@ impl Runtime {
 	}
 
 
+	/// Execute the root block of the current program in the root frame of the session,
+	/// growing the frame with the slots declared since the previous input.
+	fn eval_session_root(
+		&mut self,
+		shared: &SharedProgram,
+		program: &'static program::Program,
+	) -> Result<Value, Panic> {
+		let slots: mem::SlotIx = program.root_slots.into();
+		let stack_overflow = || Panic::stack_overflow(SourcePos::file(program.source));
+
+		match &self.session {
+			Some(previous) => self.stack
+				.extend_bottom(mem::SlotIx(slots.0 - previous.0))
+				.map_err(|_| stack_overflow())?,
+
+			None => {
+				self.stack
+					.extend(slots.copy())
+					.map_err(|_| stack_overflow())?;
+
+				// Stdlib.
+				self.stack.store(mem::SlotIx(0), self.std.copy());
+			}
+		}
+
+		self.session = Some(slots);
+
+		let initial_args_len = self.arguments.len();
+
+		let flow = match self.interpreter {
+			Interpreter::TreeWalker => self.eval_block(&program.statements),
+			Interpreter::Vm => {
+				let code = shared.code(&program.statements, &mut self.field_caches);
+				self.exec(&code, |_| ())
+			}
+		};
+
+		// Panics may leave arguments behind, which must not leak into the next input.
+		self.arguments.truncate(initial_args_len);
+
+		match flow? {
+			Flow::Regular(value) => Ok(value),
+			flow => Err(Self::invalid_root_flow(flow, program)),
+		}
+	}
+
+
+	/// Returns and breaks outside functions and loops are rejected by the semantic analysis.
+	fn invalid_root_flow(flow: Flow, program: &program::Program) -> Panic {
+		debug_assert!(false, "invalid flow in root state: {:#?}", flow);
+		Panic::internal_error("invalid flow in root state", SourcePos::file(program.source))
+	}
+
+
 	/// Execute a block, returning the value of the last statement, or the corresponding
 	/// control flow if returns or breaks are reached.
 	fn eval_block(&mut self, block: &'static program::Block) -> Result<Flow, Panic> {
@@ -160,6 +770,7 @@ impl Runtime {
 	/// Execute a literal.
 	/// For trivial types, this basically instatiates a corresponding value.
 	/// For compound types, sub-expressions are evaluated.
+	/// For templates, a fresh copy is instantiated.
 	/// For function types, closed-over variables are captured, if any.
 	/// For identifiers, their string is resolved.
 	fn eval_literal(
@@ -188,7 +799,7 @@ impl Runtime {
 
 			// Array.
 			program::Literal::Array(exprs) => {
-				let mut array = Vec::new();
+				let mut array = Vec::with_capacity(exprs.len());
 
 				for expr in exprs.iter() {
 					match self.eval_expr(expr)?.0 {
@@ -202,13 +813,17 @@ impl Runtime {
 
 			// Dict.
 			program::Literal::Dict(exprs) => {
-				let mut dict = HashMap::new();
+				let mut dict = DictMap::with_capacity_and_hasher(exprs.len(), Default::default());
 
-				for (symbol, expr) in exprs.iter() {
-					let key: Value = self.interner
-						.resolve(*symbol)
-						.expect("unresolved symbol")
-						.into();
+				for (key, expr) in exprs.iter() {
+					let key: Value = match key {
+						program::DictKey::Symbol(name) => name.string.copy().into(),
+
+						program::DictKey::Computed(key) => match self.eval_expr(key)?.0 {
+							Flow::Regular(value) => value,
+							flow => return Ok(flow),
+						},
+					};
 
 					match self.eval_expr(expr)?.0 {
 						Flow::Regular(value) => dict.insert(key, value),
@@ -219,6 +834,9 @@ impl Runtime {
 				Ok(Flow::Regular(Dict::new(dict).into()))
 			}
 
+			// Array or dict of constants.
+			program::Literal::Template(template) => Ok(Flow::Regular(template.into())),
+
 			// Function.
 			program::Literal::Function { params, frame_info, body } => {
 				let context = frame_info
@@ -234,64 +852,55 @@ impl Runtime {
 
 				Ok(
 					Flow::Regular(
-						HushFun::new(*params, frame_info, body, context, pos.into()).into()
+						HushFun::new(
+							*params,
+							frame_info,
+							body,
+							self.program.clone().expect("no program being executed"),
+							context,
+							pos.into(),
+						).into()
 					)
 				)
 			},
 
 			// Identifier.
-			program::Literal::Identifier(symbol) => Ok(
-				Flow::Regular(
-					self.interner
-						.resolve(*symbol)
-						.expect("unresolved symbol")
-						.into()
-				)
-			),
+			program::Literal::Identifier(name) => Ok(Flow::Regular(name.string.copy().into())),
 		}
 	}
 
 
 	/// Execute an expression.
-	/// Returns a triple of (flow, expr pos, self value) or panic.
+	/// Returns a pair of (flow, expr pos) or panic.
 	fn eval_expr(
 		&mut self,
 		expr: &'static program::Expr
-	) -> Result<(Flow, SourcePos, Value), Panic> {
+	) -> Result<(Flow, SourcePos), Panic> {
 		self.eval_tail_expr(expr, |_| ())
 	}
 
 
 	/// Execute an expression.
-	/// Returns a triple of (flow, expr pos, self value) or panic.
+	/// Returns a pair of (flow, expr pos) or panic.
 	fn eval_tail_expr<F>(
 		&mut self,
 		expr: &'static program::Expr,
 		tail_call: F,
-	) -> Result<(Flow, SourcePos, Value), Panic>
+	) -> Result<(Flow, SourcePos), Panic>
 	where
 		F: FnOnce(&mut Self),
 	{
-		macro_rules! regular_expr {
-			($expr: expr, $pos: expr) => {
-				match self.eval_expr($expr)? {
-					(Flow::Regular(value), pos, _) => (value, pos),
-					(flow, _, _) => return Ok((flow, $pos, Value::default()))
-				}
-			}
-		}
-
 		match expr {
 			// Identifier.
 			program::Expr::Identifier { slot_ix, pos } => {
 				let value = self.stack.fetch(slot_ix.into());
-				Ok((Flow::Regular(value), pos.into(), Value::default()))
+				Ok((Flow::Regular(value), pos.into()))
 			},
 
 			// Literal.
 			program::Expr::Literal { literal, pos } => {
 				let flow = self.eval_literal(literal, *pos)?;
-				Ok((flow, pos.into(), Value::default()))
+				Ok((flow, pos.into()))
 			},
 
 			// UnaryOp.
@@ -300,7 +909,7 @@ impl Runtime {
 
 				let flow = self.unary_op(op, operand)?;
 
-				Ok((flow, pos, Value::default()))
+				Ok((flow, pos))
 			}
 
 			// BinaryOp.
@@ -309,7 +918,7 @@ impl Runtime {
 
 				let flow = self.binary_op(left, op, right, &pos)?;
 
-				Ok((flow, pos, Value::default()))
+				Ok((flow, pos))
 			}
 
 			// If.
@@ -317,9 +926,9 @@ impl Runtime {
 				let pos = pos.into();
 
 				let condition = match self.eval_expr(condition)? {
-					(Flow::Regular(Value::Bool(b)), _, _) => b,
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
-					(flow, _, _) => return Ok((flow, pos, Value::default()))
+					(Flow::Regular(Value::Bool(b)), _) => b,
+					(Flow::Regular(value), pos) => return Err(Panic::invalid_condition(value, pos)),
+					(flow, _) => return Ok((flow, pos))
 				};
 
 				let value = if condition {
@@ -328,41 +937,12 @@ impl Runtime {
 					self.eval_block(otherwise)
 				}?;
 
-				Ok((value, pos, Value::default()))
+				Ok((value, pos))
 			}
 
 			// Access.
 			program::Expr::Access { object, field, pos } => {
-				let pos = pos.into();
-
-				let (obj, obj_pos) = regular_expr!(object, pos);
-				let (field, field_pos) = regular_expr!(field, pos);
-
-				let value = match (&obj, field) {
-					(Value::Dict(ref dict), field) => dict
-						.get(&field)
-						.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
-
-					(Value::Array(ref array), Value::Int(ix)) => array
-						.index(ix)
-						.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
-
-					(Value::Array(_), field) => Err(Panic::type_error(field, "int", field_pos)),
-
-					(Value::String(ref string), Value::Int(ix)) => string
-						.index(ix)
-						.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
-
-					(Value::String(_), field) => Err(Panic::type_error(field, "int", field_pos)),
-
-					(Value::Error(ref error), field) => error
-						.get(&field)
-						.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
-
-					(_, _) => return Err(Panic::type_error(obj, "string, array, dict or error", obj_pos)),
-				}?;
-
-				Ok((Flow::Regular(value), pos, obj))
+				self.eval_access(object, field, pos.into())
 			}
 
 			// Call.
@@ -370,10 +950,48 @@ impl Runtime {
 				let pos = pos.into();
 
 				// Eval function.
-				let (function, obj) = match self.eval_expr(function)? {
-					(Flow::Regular(Value::Function(ref fun)), _, obj) => (fun.copy(), obj),
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_call(value, pos)),
-					(flow, _, _) => return Ok((flow, pos, Value::default())),
+				let (function, obj) = match &**function {
+					// Functions in variables are copied directly from their slot.
+					program::Expr::Identifier { slot_ix, pos: fun_pos } => {
+						let function = self.stack.fetch_with(
+							slot_ix.into(),
+							|value| match value {
+								Value::Function(fun) => Ok(fun.copy()),
+								other => Err(Panic::invalid_call(other.copy(), fun_pos.into())),
+							}
+						)?;
+
+						(function, Value::default())
+					}
+
+					// Methods are called with the accessed object as self, which is evaluated here
+					// rather than by eval_access, as only the call needs it.
+					program::Expr::Access { object, field, pos: access_pos } => {
+						macro_rules! regular_expr {
+							($expr: expr) => {
+								match self.eval_expr($expr)? {
+									(Flow::Regular(value), pos) => (value, pos),
+									(flow, _) => return Ok((flow, pos)),
+								}
+							}
+						}
+
+						let (obj, obj_pos) = regular_expr!(object);
+						let (field, field_pos) = regular_expr!(field);
+
+						match Self::access(&obj, field, obj_pos, field_pos)? {
+							Value::Function(ref fun) => (fun.copy(), obj),
+							value => return Err(Panic::invalid_call(value, access_pos.into())),
+						}
+					}
+
+					// Other functions, such as stored bound methods or call results, are called
+					// without a self value, or with the one they were bound to.
+					function => match self.eval_expr(function)? {
+						(Flow::Regular(Value::Function(ref fun)), _) => (fun.copy(), Value::default()),
+						(Flow::Regular(value), pos) => return Err(Panic::invalid_call(value, pos)),
+						(flow, _) => return Ok((flow, pos)),
+					},
 				};
 
 				// Eval arguments.
@@ -381,89 +999,235 @@ impl Runtime {
 				// keep track of when our arguments start.
 				let args_start = self.arguments.len();
 
+				// The arguments vector is shared by all calls in progress, so it rarely needs to
+				// grow, but reserving the argument count spares growing repeatedly when it does.
+				self.arguments.reserve(args.len());
+
 				for expr in args.iter() {
 					match self.eval_expr(expr)? {
-						(Flow::Regular(value), _, _) => self.arguments.push(value),
-						(flow, _, _) => {
+						(Flow::Regular(value), _) => self.arguments.push(value),
+						(flow, _) => {
 							self.arguments.truncate(args_start);
-							return Ok((flow, pos, Value::default()));
+							return Ok((flow, pos));
 						}
 					}
 				}
 
+				let args = self.spread(args_start, args)?;
+
 				tail_call(self);
 
-				let value = self.call(obj, &function, args_start, pos.copy())?;
+				let value = self.call(obj, &function, args_start, args, pos.copy())?;
 
-				Ok((Flow::Regular(value), pos, Value::default()))
+				Ok((Flow::Regular(value), pos))
 			}
 
+			// Spread argument, which is expanded by the call.
+			program::Expr::Spread { expr, .. } => self.eval_expr(expr),
+
 			// CommandBlock.
+			#[cfg(feature = "system")]
 			program::Expr::CommandBlock { block, pos } => {
 				let value = self.eval_command_block(block, pos.into())?;
-				Ok((Flow::Regular(value), pos.into(), Value::default()))
+				Ok((Flow::Regular(value), pos.into()))
 			}
-		}
-	}
 
+			#[cfg(not(feature = "system"))]
+			program::Expr::CommandBlock { pos, .. } => {
+				Err(Panic::unsupported_operation("command blocks", pos.into()))
+			}
 
-	/// Execute a statement.
-	fn eval_statement(&mut self, statement: &'static program::Statement) -> Result<Flow, Panic> {
-		self.eval_tail_statement(statement, |_| ())
+			// Interpolated string.
+			program::Expr::Interpolation { parts, pos } => {
+				let mut string = Vec::new();
+
+				for part in parts.iter() {
+					match self.eval_expr(part)?.0 {
+						Flow::Regular(Value::String(ref part)) => string.extend_from_slice(part.as_ref()),
+						Flow::Regular(value) => string.extend_from_slice(
+							value.fmt_string(&self.interner).as_bytes()
+						),
+						flow => return Ok((flow, pos.into())),
+					}
+				}
+
+				let value = string.into_boxed_slice().into();
+				Ok((Flow::Regular(value), pos.into()))
+			}
+		}
 	}
 
 
-	/// Execute a statement.
-	fn eval_tail_statement<F>(
+	/// Execute an access expression.
+	fn eval_access(
 		&mut self,
-		statement: &'static program::Statement,
-		tail_call: F,
-	) -> Result<Flow, Panic>
-	where
-		F: FnOnce(&mut Self),
-	{
-		match statement {
-			// Assign.
-			program::Statement::Assign { left, right } => {
-				let value = match self.eval_expr(right)?.0 {
-					Flow::Regular(value) => value,
-					flow => return Ok(flow),
-				};
+		object: &'static program::Expr,
+		field: &'static program::Expr,
+		pos: SourcePos,
+	) -> Result<(Flow, SourcePos), Panic> {
+		macro_rules! regular_expr {
+			($expr: expr) => {
+				match self.eval_expr($expr)? {
+					(Flow::Regular(value), pos) => (value, pos),
+					(flow, _) => return Ok((flow, pos))
+				}
+			}
+		}
+
+		// Accessing a variable with a variable or literal field needs no copy of the object,
+		// as evaluating such fields can't change the variable.
+		if let program::Expr::Identifier { slot_ix, pos: obj_pos } = object {
+			let pure_field = matches!(
+				field,
+				program::Expr::Identifier { .. } | program::Expr::Literal { .. }
+			);
+
+			if pure_field {
+				let (field, field_pos) = regular_expr!(field);
+
+				let value = self.stack.fetch_with(
+					slot_ix.into(),
+					|obj| Self::access(obj, field, obj_pos.into(), field_pos)
+				)?;
+
+				return Ok((Flow::Regular(value), pos));
+			}
+		}
+
+		let (obj, obj_pos) = regular_expr!(object);
+		let (field, field_pos) = regular_expr!(field);
+
+		let value = Self::access(&obj, field, obj_pos, field_pos)?;
+
+		Ok((Flow::Regular(value), pos))
+	}
+
+
+	/// Get the given field of an object.
+	fn access(
+		obj: &Value,
+		field: Value,
+		obj_pos: SourcePos,
+		field_pos: SourcePos,
+	) -> Result<Value, Panic> {
+		match (obj, field) {
+			(Value::Dict(ref dict), field) => dict
+				.get(&field)
+				.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
+
+			(Value::Array(ref array), Value::Int(ix)) => array
+				.index(ix)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
+
+			(Value::Array(_), field) => Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::String(ref string), Value::Int(ix)) => string
+				.index(ix)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
+
+			(Value::String(_), field) => Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::Error(ref error), field) => error
+				.get(&field)
+				.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
+
+			(obj, _) => Err(Panic::type_error(obj.copy(), "string, array, dict or error", obj_pos)),
+		}
+	}
+
+
+	/// Prepare to execute a statement: invoke the trace hook, consume fuel and deliver
+	/// pending signals.
+	fn enter_statement(&mut self, statement: &'static program::Statement) -> Result<(), Panic> {
+		if self.trace.is_some() {
+			if let Some(pos) = statement.pos() {
+				self.trace(&pos.into())?;
+			}
+		}
+
+		// The position is only needed when the fuel is exhausted. Break statements, which
+		// have no position, are free.
+		match &mut self.fuel {
+			Some(0) => if let Some(pos) = statement.pos() {
+				return Err(Panic::out_of_fuel(pos.into()));
+			},
+			Some(fuel) => *fuel -= 1,
+			None => (),
+		}
+
+		// Signals are delivered between statements, as handlers may run arbitrary code.
+		#[cfg(feature = "system")]
+		if signal::is_pending() {
+			if let Some(pos) = statement.pos() {
+				self.deliver_signals(pos.into())?;
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Execute a statement.
+	fn eval_statement(&mut self, statement: &'static program::Statement) -> Result<Flow, Panic> {
+		self.eval_tail_statement(statement, |_| ())
+	}
 
-				match left {
-					program::Lvalue::Identifier { slot_ix, .. } => self.stack.store(slot_ix.into(), value),
 
-					program::Lvalue::Access { object, field, pos } => {
-						let (obj, obj_pos) = match self.eval_expr(object)? {
-							(Flow::Regular(obj), pos, _) => (obj, pos),
-							(flow, _, _) => return Ok(flow),
-						};
+	/// Execute a statement.
+	fn eval_tail_statement<F>(
+		&mut self,
+		statement: &'static program::Statement,
+		tail_call: F,
+	) -> Result<Flow, Panic>
+	where
+		F: FnOnce(&mut Self),
+	{
+		self.enter_statement(statement)?;
 
-						let (field, field_pos) = match self.eval_expr(field)? {
-							(Flow::Regular(field), pos, _) => (field, pos),
-							(flow, _, _) => return Ok(flow),
-						};
+		match statement {
+			// Append to a variable.
+			program::Statement::Assign {
+				left: program::Lvalue::Identifier { slot_ix, .. },
+				right: program::Expr::BinaryOp { left: operand, op: op @ program::BinaryOp::Concat, right, pos },
+			} if operand.is_variable(slot_ix) => {
+				let (left, left_pos) = (self.stack.fetch(slot_ix.into()), operand.pos());
+
+				let (right, right_pos) = match self.eval_expr(right)? {
+					(Flow::Regular(value), pos) => (value, pos),
+					(flow, _) => return Ok(flow),
+				};
 
-						match (obj, field) {
-							// Note that strings are immutable.
+				let value = self.append(slot_ix.into(), op, left, left_pos.into(), right, right_pos, &pos.into())?;
+				self.stack.store(slot_ix.into(), value);
 
-							(Value::Dict(ref dict), field) => dict.insert(field, value),
+				Ok(Flow::Regular(Value::default()))
+			}
 
-							(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
-								Panic::index_out_of_bounds(Value::Int(ix), field_pos)
-							),
+			// Assign.
+			program::Statement::Assign { left, right } => {
+				let value = match self.eval_expr(right)?.0 {
+					Flow::Regular(value) => value,
+					flow => return Ok(flow),
+				};
 
-							(Value::Array(ref array), Value::Int(ix)) => array
-								.deref()
-								.set(ix, value)
-								.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos.into()))?,
+				self.assign(left, value)
+			}
 
-							(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
+			// Multiple assign.
+			program::Statement::MultipleAssign { left, right } => {
+				let mut values = Vec::with_capacity(right.len());
 
-							(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
+				for expr in right.iter() {
+					match self.eval_expr(expr)?.0 {
+						Flow::Regular(value) => values.push(value),
+						flow => return Ok(flow),
+					}
+				}
 
-							(obj, _) => return Err(Panic::type_error(obj, "array, dict or error", obj_pos)),
-						};
+				for (left, value) in left.iter().zip(values) {
+					match self.assign(left, value)? {
+						Flow::Regular(_) => (),
+						flow => return Ok(flow),
 					}
 				}
 
@@ -484,16 +1248,19 @@ impl Runtime {
 			// While.
 			program::Statement::While { condition, block } => {
 				loop {
-					let condition = match self.eval_expr(condition)? {
-						(Flow::Regular(Value::Bool(b)), _, _) => b,
-						(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
-						(flow, _, _) => return Ok(flow)
+					let (condition, pos) = match self.eval_expr(condition)? {
+						(Flow::Regular(Value::Bool(b)), pos) => (b, pos),
+						(Flow::Regular(value), pos) => return Err(Panic::invalid_condition(value, pos)),
+						(flow, _) => return Ok(flow)
 					};
 
 					if !condition {
 						break;
 					}
 
+					self.check_cancelled(&pos)?;
+					self.consume_fuel(1, &pos)?;
+
 					match self.eval_block(block)? {
 						Flow::Regular(_) => (),
 						flow @ Flow::Return(_) => return Ok(flow),
@@ -509,46 +1276,25 @@ impl Runtime {
 				let slot_ix: mem::SlotIx = slot_ix.into();
 
 				let (iter, pos) = match self.eval_expr(expr)? {
-					(Flow::Regular(Value::Function(ref iter)), pos, _) => (iter.copy(), pos),
-					(Flow::Regular(value), pos, _) => return Err(Panic::type_error(value, "function", pos)),
-					(flow, _, _) => return Ok(flow)
+					(Flow::Regular(value), pos) => (self.iterator(value, pos.copy())?, pos),
+					(flow, _) => return Ok(flow)
 				};
 
 				loop {
-					// While evaluating arguments, we may need to call other functions, so we must
-					// keep track of when our arguments start.
-					let args_start = self.arguments.len();
-					match self.call(Value::default(), &iter, args_start, pos.copy())? {
-						Value::Dict(ref dict) => {
-							let finished = keys::FINISHED.with(
-								|finished| dict
-									.get(finished)
-									.map_err(|_| Panic::index_out_of_bounds(finished.copy(), pos.copy()))
-							)?;
-
-							match finished {
-								Value::Bool(false) => {
-									let value = keys::VALUE.with(
-										|value| dict
-											.get(value)
-											.map_err(|_| Panic::index_out_of_bounds(value.copy(), pos.copy()))
-									)?;
-
-									self.stack.store(slot_ix.copy(), value);
-								},
-
-								Value::Bool(true) => break,
-
-								other => return Err(Panic::type_error(other, "bool", pos))
-							}
+					match self.next_item(&iter, &pos)? {
+						Some(value) => self.stack.store(slot_ix.copy(), value),
+						None => break,
+					}
 
-							Value::Nil
-						},
+					let flow = self.eval_block(block);
 
-						other => return Err(Panic::type_error(other, "dict", pos)),
-					};
+					if !matches!(flow, Ok(Flow::Regular(_))) {
+						if let Function::Rust(ref fun) = iter {
+							fun.stop_iteration();
+						}
+					}
 
-					match self.eval_block(block)? {
+					match flow? {
 						Flow::Regular(_) => (),
 						flow @ Flow::Return(_) => return Ok(flow),
 						Flow::Break => break,
@@ -561,24 +1307,366 @@ impl Runtime {
 			// Expr.
 			program::Statement::Expr(expr) => self
 				.eval_tail_expr(expr, tail_call)
-				.map(|(flow, _, _)| flow)
+				.map(|(flow, _)| flow)
+		}
+	}
+
+
+	/// Get the iterator function for the value of a for loop.
+	/// Arrays, strings and dicts are iterated as in std.iter.
+	fn iterator(&mut self, value: Value, pos: SourcePos) -> Result<Function, Panic> {
+		match value {
+			Value::Function(ref iter) => Ok(iter.copy()),
+
+			// Dicts with an iter method are iterated by the function it returns, and other
+			// dicts by their entries.
+			Value::Dict(ref dict) => {
+				let method = match dict.get(&self.keys.iter) {
+					Ok(Value::Function(ref method)) => method.copy(),
+					_ => return Ok(lib::collection_iter(&value).expect("dicts are iterable")),
+				};
+
+				let args_start = self.arguments.len();
+				match self.call(dict.copy().into(), &method, args_start, &[], pos.copy())? {
+					Value::Function(ref iter) => Ok(iter.copy()),
+					other => Err(Panic::type_error(other, "function", pos)),
+				}
+			},
+
+			value => lib::collection_iter(&value)
+				.ok_or_else(|| Panic::type_error(value, "function, array, dict or string", pos)),
+		}
+	}
+
+
+	/// Call the iterator function of a for loop, returning the next item, if any.
+	fn next_item(&mut self, iter: &Function, pos: &SourcePos) -> Result<Option<Value>, Panic> {
+		self.check_cancelled(pos)?;
+		self.consume_fuel(1, pos)?;
+
+		// While evaluating arguments, we may need to call other functions, so we must
+		// keep track of when our arguments start.
+		let args_start = self.arguments.len();
+		match self.call(Value::default(), iter, args_start, &[], pos.copy())? {
+			Value::Dict(ref dict) => {
+				let finished = dict
+					.get(&self.keys.finished)
+					.map_err(|_| Panic::index_out_of_bounds(self.keys.finished.copy(), pos.copy()))?;
+
+				match finished {
+					Value::Bool(false) => dict
+						.get(&self.keys.value)
+						.map(Some)
+						.map_err(|_| Panic::index_out_of_bounds(self.keys.value.copy(), pos.copy())),
+
+					Value::Bool(true) => Ok(None),
+
+					other => Err(Panic::type_error(other, "bool", pos.copy()))
+				}
+			},
+
+			other => Err(Panic::type_error(other, "dict", pos.copy())),
+		}
+	}
+
+
+	/// Assign a value to an l-value.
+	fn assign(&mut self, left: &'static program::Lvalue, value: Value) -> Result<Flow, Panic> {
+		match left {
+			program::Lvalue::Identifier { slot_ix, .. } => self.stack.store(slot_ix.into(), value),
+
+			program::Lvalue::Array { items, rest, pos } => {
+				let array = match value {
+					Value::Array(ref array) => array.borrow().iter().map(Value::copy).collect::<Vec<_>>(),
+					value => return Err(Panic::type_error(value, "array", pos.into())),
+				};
+
+				if array.len() < items.len() {
+					return Err(Panic::invalid_destructuring(items.len(), array.len(), pos.into()));
+				}
+
+				let mut values = array.into_iter();
+
+				for (item, value) in items.iter().zip(values.by_ref()) {
+					match self.assign(item, value)? {
+						Flow::Regular(_) => (),
+						flow => return Ok(flow),
+					}
+				}
+
+				if let Some(rest) = rest {
+					return self.assign(rest, Array::new(values.collect()).into());
+				}
+			}
+
+			program::Lvalue::Dict { items, pos } => {
+				let dict = match value {
+					Value::Dict(ref dict) => dict.copy(),
+					value => return Err(Panic::type_error(value, "dict", pos.into())),
+				};
+
+				for item in items.iter() {
+					// Symbols are interned by the semantic analysis.
+					let key: Value = match self.interner.resolve(item.key) {
+						Some(key) => key.into(),
+						None => {
+							debug_assert!(false, "unresolved symbol");
+							return Err(Panic::internal_error("unresolved symbol", item.pos.into()));
+						}
+					};
+
+					// Keys that are present are used even if their value is nil.
+					let value = match (dict.get(&key), &item.default) {
+						(Ok(value), _) => value,
+
+						(Err(_), Some(default)) => match self.eval_expr(default)?.0 {
+							Flow::Regular(value) => value,
+							flow => return Ok(flow),
+						},
+
+						(Err(_), None) => {
+							let mut available: Vec<_> = dict.borrow().keys().map(Value::copy).collect();
+							available.sort();
+
+							return Err(Panic::missing_key(key, available.into(), item.pos.into()));
+						}
+					};
+
+					match self.assign(&item.lvalue, value)? {
+						Flow::Regular(_) => (),
+						flow => return Ok(flow),
+					}
+				}
+			}
+
+			program::Lvalue::Access { object, field, pos } => {
+				let (obj, obj_pos) = match self.eval_expr(object)? {
+					(Flow::Regular(obj), pos) => (obj, pos),
+					(flow, _) => return Ok(flow),
+				};
+
+				let (field, field_pos) = match self.eval_expr(field)? {
+					(Flow::Regular(field), pos) => (field, pos),
+					(flow, _) => return Ok(flow),
+				};
+
+				Self::set_field(obj, obj_pos, field, field_pos, value, pos.into())?;
+			}
+		}
+
+
+		Ok(Flow::Regular(Value::default()))
+	}
+
+
+	/// Assign a value to the given field of an object.
+	fn set_field(
+		obj: Value,
+		obj_pos: SourcePos,
+		field: Value,
+		field_pos: SourcePos,
+		value: Value,
+		pos: SourcePos,
+	) -> Result<(), Panic> {
+		match (obj, field) {
+			// Note that strings are immutable.
+
+			(Value::Dict(ref dict), field) => dict.insert(field, value),
+
+			(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
+				Panic::index_out_of_bounds(Value::Int(ix), field_pos)
+			),
+
+			(Value::Array(ref array), Value::Int(ix)) => array
+				.deref()
+				.set(ix, value)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos))?,
+
+			(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
+
+			(obj, _) => return Err(Panic::type_error(obj, "array, dict or error", obj_pos)),
+		};
+
+		Ok(())
+	}
+
+
+	/// Consume the given amount of fuel, if limited, panicking when exhausted.
+	fn consume_fuel(&mut self, amount: u64, pos: &SourcePos) -> Result<(), Panic> {
+		match &mut self.fuel {
+			Some(fuel) if *fuel < amount => {
+				*fuel = 0;
+				Err(Panic::out_of_fuel(pos.copy()))
+			}
+			Some(fuel) => {
+				*fuel -= amount;
+				Ok(())
+			}
+			None => Ok(()),
 		}
 	}
 
 
+	/// Panic if execution has been cancelled by the host.
+	fn check_cancelled(&self, pos: &SourcePos) -> Result<(), Panic> {
+		if self.cancel.is_cancelled() {
+			Err(Panic::interrupted(pos.copy()))
+		} else {
+			Ok(())
+		}
+	}
+
+
+	/// Call the handlers of pending signals, in ascending signal order.
+	/// Panics in handlers are raised at the given position.
+	#[cfg(feature = "system")]
+	fn deliver_signals(&mut self, pos: SourcePos) -> Result<(), Panic> {
+		for signal in signal::take_pending() {
+			let handler = match self.signal_handlers.get(&signal) {
+				Some(handler) => handler.copy(),
+				None => continue,
+			};
+
+			let args_start = self.arguments.len();
+			self.arguments.push(signal::name(signal).into());
+
+			self.call(Value::default(), &handler, args_start, &[], pos.copy())?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Invoke the trace hook before a statement, following its directive.
+	fn trace(&mut self, pos: &SourcePos) -> Result<(), Panic> {
+		let names = match &self.trace {
+			Some(TraceHook { options: TraceOptions { locals: true }, .. }) => Some(self.local_names()),
+			_ => None,
+		};
+
+		let (stack, interner, frames) = (&self.stack, &self.interner, &self.calls);
+		let directive = match &mut self.trace {
+			Some(TraceHook { hook, .. }) => hook(
+				&TracePoint {
+					pos,
+					depth: frames.len(),
+					frames,
+					interner,
+					locals: names.map(|names| Locals { names, stack, interner }),
+				}
+			),
+			None => return Ok(()),
+		};
+
+		match directive {
+			Directive::Continue => Ok(()),
+			Directive::Pause if self.resumer.wait(&self.cancel) => Ok(()),
+			Directive::Pause | Directive::Abort => Err(Panic::interrupted(pos.copy())),
+		}
+	}
+
+
+	/// The names of the variables in the current frame, which is either a function or the
+	/// root of the current program.
+	fn local_names(&self) -> &'static [(Symbol, program::mem::SlotIx)] {
+		match (self.calls.last(), &self.program) {
+			(Some(Frame { function: Function::Hush(function), .. }), _) => &function.frame_info.names,
+			// SAFETY: the names are only used while the program is being executed.
+			(_, Some(program)) => &unsafe { program.get() }.root_names,
+			(_, None) => &[],
+		}
+	}
+
+
+	/// Expand the spread arguments of a call, given one value per argument expression on
+	/// the self.arguments vector. Returns the argument expressions matching the expanded
+	/// values, which are none if anything was spread, so that panics point at the call.
+	fn spread(
+		&mut self,
+		args_start: usize,
+		args: &'static [program::Expr],
+	) -> Result<&'static [program::Expr], Panic> {
+		if !args.iter().any(|arg| matches!(arg, program::Expr::Spread { .. })) {
+			return Ok(args);
+		}
+
+		let values: Vec<Value> = self.arguments.drain(args_start..).collect();
+
+		for (value, expr) in values.into_iter().zip(args.iter()) {
+			match (expr, value) {
+				(program::Expr::Spread { .. }, Value::Array(ref array)) => self.arguments.extend(
+					array
+						.borrow()
+						.iter()
+						.map(Value::copy)
+				),
+
+				(program::Expr::Spread { expr, .. }, value) => {
+					self.arguments.truncate(args_start);
+					return Err(Panic::type_error(value, "array", expr.pos().into()));
+				}
+
+				(_, value) => self.arguments.push(value),
+			}
+		}
+
+		Ok(&[])
+	}
+
+
 	/// Call the given function.
 	/// The arguments are expected to be on the self.arguments vector.
+	/// Panics raised inside the call carry the call stack at the point they were raised.
 	fn call(
 		&mut self,
 		obj: Value,
 		function: &Function,
 		args_start: usize,
+		args: &'static [program::Expr],
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
+		self.check_cancelled(&pos)?;
+
+		if self.calls.len() >= self.max_call_depth {
+			self.arguments.truncate(args_start);
+			return Err(Panic::stack_overflow(pos));
+		}
+
+		if let Some(CallHook(hook)) = &mut self.call_hook {
+			hook(&CallEvent::Call { function, pos: &pos });
+		}
+
+		self.calls.push(Frame { function: function.copy(), pos: pos.copy() });
+
+		let result = self
+			.call_function(obj, function, args_start, args, pos)
+			.map_err(|panic| panic.traced(|| Traceback::capture(&self.calls)));
+
+		self.calls.pop();
+
+		if let Some(CallHook(hook)) = &mut self.call_hook {
+			hook(&CallEvent::Return);
+		}
+
+		result
+	}
+
+
+	/// Call the given function, without keeping track of the call stack.
+	fn call_function(
+		&mut self,
+		obj: Value,
+		function: &Function,
+		args_start: usize,
+		args: &'static [program::Expr],
 		pos: SourcePos,
 	) -> Result<Value, Panic> {
 
 		let value = match function {
-			Function::Hush(HushFun { params, frame_info, body, context, .. }) => {
-				let args_count = (self.arguments.len() - args_start) as u32;
+			Function::Hush(HushFun { params, frame_info, body, program, context, .. }) => {
+				let args_count = args_count(self.arguments.len() - args_start);
 
 				// Make sure we clean the arguments vector even when early returning.
 				let arguments = self.arguments.drain(args_start..);
@@ -587,14 +1675,10 @@ impl Runtime {
 					return Err(Panic::invalid_args(args_count, *params, pos));
 				}
 
+				// Place arguments in the first slots of the new frame.
 				let slots: mem::SlotIx = frame_info.slots.into();
-				self.stack.extend(slots.copy())
-					.map_err(|_| Panic::stack_overflow(pos))?;
-
-				// Place arguments
-				for (ix, value) in arguments.enumerate() {
-					self.stack.store(mem::SlotIx(ix as u32), value);
-				}
+				self.stack.extend_with(slots.copy(), arguments)
+					.map_err(|_| Panic::stack_overflow(pos.copy()))?;
 
 				// Place captured variables.
 				for (value, slot_ix) in context.iter().cloned() {
@@ -608,13 +1692,23 @@ impl Runtime {
 
 				let mut shrinked = false;
 
-				let result = self.eval_tail_block(
-					body,
-					|runtime| { // Shrink stack before tail calling.
-						runtime.stack.shrink(slots.copy());
-						shrinked = true;
+				// Functions defined in the body belong to the same program.
+				let previous = self.program.replace(program.clone());
+
+				let tail_call = |runtime: &mut Self| { // Shrink stack before tail calling.
+					runtime.stack.shrink(slots.copy());
+					shrinked = true;
+				};
+
+				let result = match self.interpreter {
+					Interpreter::TreeWalker => self.eval_tail_block(body, tail_call),
+					Interpreter::Vm => {
+						let code = program.code(body, &mut self.field_caches);
+						self.exec(&code, tail_call)
 					}
-				);
+				};
+
+				self.program = previous;
 
 				// Make sure to shrink before returning.
 				if !shrinked { // Only shrink the stack if there was no tail call.
@@ -626,19 +1720,16 @@ impl Runtime {
 				match flow {
 					Flow::Regular(value) => value,
 					Flow::Return(value) => value,
-					Flow::Break => panic!("break outside loop"),
+					Flow::Break => {
+						// Breaks outside loops are rejected by the semantic analysis.
+						debug_assert!(false, "break outside loop");
+						return Err(Panic::internal_error("break outside loop", pos));
+					}
 				}
 			}
 
 			Function::Rust(fun) => {
-				let result = fun.call(
-					CallContext {
-						runtime: self,
-						obj,
-						args_start,
-						pos
-					}
-				);
+				let result = fun.call(CallContext::new(self, obj, args_start, args, pos));
 
 				self.arguments.truncate(args_start);
 
@@ -656,20 +1747,30 @@ impl Runtime {
 		op: &'static program::UnaryOp,
 		operand: &'static program::Expr,
 	) -> Result<Flow, Panic> {
-		use program::UnaryOp::{Minus, Not, Try};
-
 		let (value, operand_pos) = match self.eval_expr(operand)? {
-			(Flow::Regular(value), pos, _) => (value, pos),
-			(flow, _, _) => return Ok(flow),
+			(Flow::Regular(value), pos) => (value, pos),
+			(flow, _) => return Ok(flow),
 		};
 
+		Self::unary_value(op, value, operand_pos)
+	}
+
+
+	/// Apply a unary operator to an evaluated operand.
+	fn unary_value(
+		op: &'static program::UnaryOp,
+		value: Value,
+		operand_pos: SourcePos,
+	) -> Result<Flow, Panic> {
+		use program::UnaryOp::{Minus, Not, Try};
+
 		match (op, value) {
 			(Minus, Value::Float(ref f)) => Ok(Flow::Regular((-f).into())),
 			(Minus, Value::Int(i)) => Ok(Flow::Regular((-i).into())),
-			(Minus, value) => Err(Panic::type_error(value, "int or float", operand_pos)),
+			(Minus, value) => Err(Panic::invalid_unary_operand(op, value, "int or float", operand_pos)),
 
 			(Not, Value::Bool(b)) => Ok(Flow::Regular((!b).into())),
-			(Not, value) => Err(Panic::type_error(value, "bool", operand_pos)),
+			(Not, value) => Err(Panic::invalid_unary_operand(op, value, "bool", operand_pos)),
 
 			(Try, value @ Value::Error(_)) => Ok(Flow::Return(value)),
 			(Try, value) => Ok(Flow::Regular(value)),
@@ -690,8 +1791,8 @@ impl Runtime {
 		macro_rules! regular_expr {
 			($expr: expr) => {
 				match self.eval_expr($expr)? {
-					(Flow::Regular(value), pos, _) => (value, pos),
-					(flow, _, _) => return Ok(flow)
+					(Flow::Regular(value), pos) => (value, pos),
+					(flow, _) => return Ok(flow)
 				}
 			}
 		}
@@ -699,63 +1800,145 @@ impl Runtime {
 		let (left, left_pos) = regular_expr!(left);
 
 		let value = match op {
-			And | Or => match (left, op) {
-				(Value::Bool(false), And) => Value::Bool(false),
-				(Value::Bool(true), Or) => Value::Bool(true),
-
-				(Value::Bool(_), _) => {
+			And | Or => match Self::logical_left(op, left, left_pos)? {
+				Ok(value) => value,
+				Err(left) => {
 					let (right, right_pos) = regular_expr!(right);
-					match right {
-						right @ Value::Bool(_) => right,
-						right => return Err(Panic::type_error(right, "bool", right_pos)),
-					}
+					Self::logical_right(op, left, right, right_pos)?
 				}
-
-				(left, _) => return Err(Panic::type_error(left, "bool", left_pos)),
 			}
 
-			Plus | Minus | Times | Div | Mod => {
+			_ => {
 				let (right, right_pos) = regular_expr!(right);
+				self.binary_value(op, left, left_pos, right, right_pos, pos)?
+			}
+		};
 
+		Ok(Flow::Regular(value))
+	}
+
+
+	/// Check the left operand of a logical operator. Returns the value of the expression if
+	/// it short circuits, or the left operand otherwise.
+	#[allow(clippy::type_complexity)]
+	fn logical_left(
+		op: &'static program::BinaryOp,
+		left: Value,
+		left_pos: SourcePos,
+	) -> Result<Result<Value, Value>, Panic> {
+		use program::BinaryOp::{And, Or};
+
+		match (left, op) {
+			(Value::Bool(false), And) => Ok(Ok(Value::Bool(false))),
+			(Value::Bool(true), Or) => Ok(Ok(Value::Bool(true))),
+			(left @ Value::Bool(_), _) => Ok(Err(left)),
+			(left, _) => Err(Panic::invalid_left_operand(op, left, None, "bool", left_pos)),
+		}
+	}
+
+
+	/// Check the right operand of a logical operator which didn't short circuit.
+	fn logical_right(
+		op: &'static program::BinaryOp,
+		left: Value,
+		right: Value,
+		right_pos: SourcePos,
+	) -> Result<Value, Panic> {
+		match right {
+			right @ Value::Bool(_) => Ok(right),
+			right => Err(Panic::invalid_right_operand(op, left, right, "bool", right_pos)),
+		}
+	}
+
+
+	/// Apply a non-logical binary operator to evaluated operands.
+	fn binary_value(
+		&mut self,
+		op: &'static program::BinaryOp,
+		left: Value,
+		left_pos: SourcePos,
+		right: Value,
+		right_pos: SourcePos,
+		pos: &SourcePos,
+	) -> Result<Value, Panic> {
+		use program::BinaryOp::*;
+
+		let value = match op {
+			And | Or => unreachable!("operator is logical"),
+
+			Plus | Minus | Times | Div | FloorDiv | Mod | Pow => {
 				self.arithmetic_op(left, left_pos, op, pos, right, right_pos)?
 			}
 
 			Greater | GreaterEquals | Lower | LowerEquals => {
-				let (right, right_pos) = regular_expr!(right);
-
 				self.ord_op(left, left_pos, op, right, right_pos)?
 			}
 
-			Equals => Value::Bool(left == regular_expr!(right).0),
-			NotEquals => Value::Bool(left != regular_expr!(right).0),
+			Equals => Value::Bool(left.op_eq(&right)),
+			NotEquals => Value::Bool(!left.op_eq(&right)),
 
-			Concat => {
-				let (right, right_pos) = regular_expr!(right);
+			Concat => match (left, right) {
+				(Value::String(ref str1), Value::String(ref str2)) => str1.concat(str2).into(),
+				(Value::String(ref string), Value::Byte(byte)) => [ string.as_bytes(), &[ byte ] ].concat().into(),
+				(Value::Byte(byte), Value::String(ref string)) => [ &[ byte ], string.as_bytes() ].concat().into(),
 
-				match (left, right) {
-					(Value::String(ref str1), Value::String(ref str2)) => {
-						let string =
-							[
-								AsRef::<[u8]>::as_ref(str1),
-								AsRef::<[u8]>::as_ref(str2),
-							]
-							.concat::<u8>();
-
-						string.into_boxed_slice().into()
-					}
+				// The items are copied to a new array, leaving both operands untouched.
+				(Value::Array(ref array1), Value::Array(ref array2)) => {
+					let items: Vec<Value> = array1
+						.borrow()
+						.iter()
+						.chain(array2.borrow().iter())
+						.map(Value::copy)
+						.collect();
 
-					(Value::String(_), right) => return Err(Panic::type_error(right, "string", right_pos)),
-					(left, _) => return Err(Panic::type_error(left, "string", left_pos)),
+					items.into()
 				}
+
+				(left @ Value::String(_), right) => return Err(
+					Panic::invalid_right_operand(op, left, right, "string or byte", right_pos)
+				),
+				(left @ Value::Byte(_), right) => return Err(
+					Panic::invalid_right_operand(op, left, right, "string", right_pos)
+				),
+				(left @ Value::Array(_), right) => return Err(
+					Panic::invalid_right_operand(op, left, right, "array", right_pos)
+				),
+				(left, right) => return Err(
+					Panic::invalid_left_operand(op, left, Some(right), "string, byte or array", left_pos)
+				),
 			}
 		};
 
-		Ok(Flow::Regular(value))
+		Ok(value)
+	}
+
+
+	/// Concatenate to the value read from the given variable, which is about to be
+	/// overwritten with the result. The variable is cleared beforehand, so that its string is
+	/// appended to in place when there are no other copies of it.
+	#[allow(clippy::too_many_arguments)]
+	fn append(
+		&mut self,
+		slot_ix: mem::SlotIx,
+		op: &'static program::BinaryOp,
+		mut left: Value,
+		left_pos: SourcePos,
+		right: Value,
+		right_pos: SourcePos,
+		pos: &SourcePos,
+	) -> Result<Value, Panic> {
+		if let (Value::String(string), Value::String(other)) = (&mut left, &right) {
+			self.stack.store(slot_ix, Value::Nil);
+			string.append(other);
+			return Ok(left);
+		}
+
+		self.binary_value(op, left, left_pos, right, right_pos, pos)
 	}
 
 
 	/// Execute a binary arithmetic operator expression.
-	/// Panics if op is not arithmetic (+, -, *, /, %).
+	/// Panics if op is not arithmetic (+, -, *, /, //, %, **).
 	fn arithmetic_op(
 		&mut self,
 		left: Value,
@@ -773,12 +1956,14 @@ impl Runtime {
 				match (left, right) {
 					// int . int
 					(Value::Int(int1), Value::Int(int2)) => {
-						let val = int1.$op_int(int2).ok_or($err_int)?;
+						let val = int1.$op_int(int2).ok_or_else(|| $err_int)?;
 						Ok(Value::Int(val))
 					},
 
 					// int . ?
-					(Value::Int(_), right) => Err(Panic::type_error(right, "int", right_pos)),
+					(left @ Value::Int(_), right) => Err(
+						Panic::invalid_right_operand(op, left, right, "int", right_pos)
+					),
 
 					// float . float
 					(Value::Float(ref float1), Value::Float(ref float2)) => {
@@ -787,10 +1972,14 @@ impl Runtime {
 					},
 
 					// float . ?
-					(Value::Float(_), right) => Err(Panic::type_error(right, "float", right_pos)),
+					(left @ Value::Float(_), right) => Err(
+						Panic::invalid_right_operand(op, left, right, "float", right_pos)
+					),
 
 					// ? . ?
-					(left, _) => Err(Panic::type_error(left, "int or float", left_pos)),
+					(left, right) => Err(
+						Panic::invalid_left_operand(op, left, Some(right), "int or float", left_pos)
+					),
 				}
 			}
 		}
@@ -826,11 +2015,58 @@ impl Runtime {
 				Panic::division_by_zero(pos.copy()) // TODO: this can be caused by overflow too.
 			),
 
+			FloorDiv => match (left, right) {
+				(Value::Int(int1), Value::Int(int2)) => program::BinaryOp
+					::floor_div(int1, int2)
+					.map(Value::Int)
+					.ok_or_else(|| Panic::division_by_zero(pos.copy())), // TODO: this can be caused by overflow too.
+
+				(left, right) => Self::float_operands(left, left_pos, op, right, right_pos)
+					.map(|(float1, float2)| (float1 / float2).floor().into()),
+			},
+
+			// Ints raised to negative ints result in floats, like in std.math.pow.
+			Pow => match (left, right) {
+				(Value::Int(base), Value::Int(exp)) if exp >= 0 => program::BinaryOp
+					::int_pow(base, exp)
+					.map(Value::Int)
+					.ok_or_else(|| Panic::integer_overflow(pos.copy())),
+
+				(left, right) => Self::float_operands(left, left_pos, op, right, right_pos)
+					.map(|(base, exp)| base.powf(exp).into()),
+			},
+
 			_ => unreachable!("operator is not arithmetic"),
 		}
 	}
 
 
+	/// Promote numeric operands to floats. Unlike the other arithmetic operators, floor
+	/// division and exponentiation accept mixed ints and floats.
+	fn float_operands(
+		left: Value,
+		left_pos: SourcePos,
+		op: &'static program::BinaryOp,
+		right: Value,
+		right_pos: SourcePos,
+	) -> Result<(f64, f64), Panic> {
+		match (left, right) {
+			(Value::Int(int1), Value::Int(int2)) => Ok((int1 as f64, int2 as f64)),
+			(Value::Int(int), Value::Float(ref float)) => Ok((int as f64, float.0)),
+			(Value::Float(ref float), Value::Int(int)) => Ok((float.0, int as f64)),
+			(Value::Float(ref float1), Value::Float(ref float2)) => Ok((float1.0, float2.0)),
+
+			(left @ Value::Int(_), right) | (left @ Value::Float(_), right) => Err(
+				Panic::invalid_right_operand(op, left, right, "int or float", right_pos)
+			),
+
+			(left, right) => Err(
+				Panic::invalid_left_operand(op, left, Some(right), "int or float", left_pos)
+			),
+		}
+	}
+
+
 	/// Execute a binary ord operator expression.
 	/// Panics if op is not ord (<, <=, >, >=).
 	fn ord_op(
@@ -845,25 +2081,43 @@ impl Runtime {
 		use std::cmp::Ordering;
 
 		let ord_operator = |order: fn(Ordering) -> bool| {
-			match (left, right) {
-				(left @ Value::Int(_), right @ Value::Int(_))
-					| (left @ Value::Float(_), right @ Value::Float(_))
-					| (left @ Value::Byte(_), right @ Value::Byte(_))
-					| (left @ Value::String(_), right @ Value::String(_))
-					=> Ok(
-						Value::Bool(
-							order(left.cmp(&right))
-						)
-					),
+			let ordering = match (&left, &right) {
+				(Value::Int(int1), Value::Int(int2)) => Some(int1.cmp(int2)),
+
+				// Unlike the total order of values, comparisons involving NaN are always false.
+				(Value::Float(float1), Value::Float(float2)) => float1.0.partial_cmp(&float2.0),
+
+				// Ints and floats follow the order of values, so that the operators agree with
+				// equality and std.cmp: an int comes before an equal float.
+				(Value::Int(_), Value::Float(float)) | (Value::Float(float), Value::Int(_)) => {
+					if float.is_nan() {
+						None
+					} else {
+						left.total_cmp(&right)
+					}
+				}
+
+				(Value::Byte(_), Value::Byte(_)) | (Value::String(_), Value::String(_)) => {
+					Some(left.cmp(&right))
+				}
 
-				(Value::Int(_), right) => Err(Panic::type_error(right, "int", right_pos)),
-				(Value::Float(_), right) => Err(Panic::type_error(right, "float", right_pos)),
-				(Value::Byte(_), right) => Err(Panic::type_error(right, "char", right_pos)),
-				(Value::String(_), right) => Err(Panic::type_error(right, "string", right_pos)),
+				_ => {
+					let expected = match left {
+						Value::Int(_) | Value::Float(_) => "int or float",
+						Value::Byte(_) => "char",
+						Value::String(_) => "string",
 
-				// ? + ?
-				(left, _) => Err(Panic::type_error(left, "int, float, byte or string", left_pos)),
-			}
+						// ? + ?
+						left => return Err(
+							Panic::invalid_left_operand(op, left, Some(right), "int, float, byte or string", left_pos)
+						),
+					};
+
+					return Err(Panic::invalid_right_operand(op, left, right, expected, right_pos));
+				}
+			};
+
+			Ok(Value::Bool(ordering.is_some_and(order)))
 		};
 
 		match op {
@@ -875,3 +2129,10 @@ impl Runtime {
 		}
 	}
 }
+
+
+/// The number of arguments of a call. Counts beyond u32 saturate rather than wrap, as
+/// they can't match the parameters of any function, which are limited by the frame size.
+fn args_count(count: usize) -> u32 {
+	u32::try_from(count).unwrap_or(u32::MAX)
+}
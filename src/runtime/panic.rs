@@ -1,12 +1,13 @@
-use std::{borrow::Cow, io, ffi::OsString};
+use std::{borrow::Cow, io, ffi::OsString, path::PathBuf};
 
 use crate::{
 	fmt::{self, Display},
 	io::FileDescriptor,
+	semantic,
 	term::color,
 	symbol::{self, Symbol},
 };
-use super::{Value, SourcePos};
+use super::{program, traceback::Traceback, Value, SourcePos};
 
 
 /// A panic is an irrecoverable error in Hush.
@@ -47,12 +48,39 @@ pub enum Panic {
 		expected: Cow<'static, str>,
 		pos: SourcePos,
 	},
+	/// Operand of unexpected type.
+	InvalidOperand {
+		operator: Operator,
+		/// The evaluated operands. The right operand of `and` and `or` is not evaluated if
+		/// the left one is invalid.
+		operands: Box<[Value]>,
+		/// The index of the invalid operand.
+		invalid: usize,
+		expected: Cow<'static, str>,
+		pos: SourcePos,
+	},
 	/// Unexpected type.
 	ValueError {
 		value: Value,
 		message: Cow<'static, str>,
 		pos: SourcePos,
 	},
+	/// Array destructuring with insufficient items.
+	InvalidDestructuring {
+		expected: usize,
+		supplied: usize,
+		pos: SourcePos,
+	},
+	/// Dict destructuring of a missing key without a default value.
+	MissingKey {
+		key: Value,
+		/// The keys present in the dict, sorted.
+		available: Box<[Value]>,
+		/// The available key closest to the missing one, if it looks like a typo. Boxed to
+		/// keep panics small, as they are returned everywhere.
+		suggestion: Option<Box<Value>>,
+		pos: SourcePos,
+	},
 	/// Attempt to assign a readonly field value.
 	AssignToReadonlyField {
 		field: Value,
@@ -80,19 +108,169 @@ pub enum Panic {
 		pos: SourcePos,
 	},
 	/// Assertion failed.
-	AssertionFailed { pos: SourcePos },
-	/// Failed to import module.
+	AssertionFailed {
+		/// The source text of the asserted expression, if available.
+		expression: Option<Box<str>>,
+		/// The compared values, for equality assertions.
+		values: Option<Box<[Value; 2]>>,
+		pos: SourcePos,
+	},
+	/// Failed to import module, due to syntax or semantic errors.
 	ImportFailed {
 		pos: SourcePos,
 		path: Symbol,
+		/// The first error, with its position.
+		error: Box<str>,
+		/// How many other errors were found.
+		more: usize,
+	},
+	/// A module imported itself, directly or through other modules.
+	ImportCycle {
+		/// The modules in the cycle, starting and ending with the same module.
+		cycle: Box<[Symbol]>,
+		pos: SourcePos,
+	},
+	/// A value containing itself was given where it must be traversed, such as when
+	/// encoding it as JSON.
+	CyclicValue {
+		/// Where the value refers back to one of its ancestors, as an index expression.
+		path: Box<str>,
+		pos: SourcePos,
+	},
+	/// No module was found for an import, in any of the searched paths.
+	ModuleNotFound {
+		name: PathBuf,
+		/// The candidate paths, in the order they were searched.
+		tried: Box<[PathBuf]>,
+		pos: SourcePos,
 	},
 	/// Attempt to call <command>.join more than once.
 	InvalidJoin { pos: SourcePos },
+	/// Attempt to use a file handle after closing it.
+	ClosedFile { pos: SourcePos },
+	/// Execution was cancelled by the host.
+	Interrupted { pos: SourcePos },
+	/// The execution budget has been exhausted.
+	OutOfFuel { pos: SourcePos },
+	/// std.exit, which unwinds the whole script so that the host may exit with the status.
+	Exit {
+		status: u8,
+		pos: SourcePos,
+	},
+	/// An operation not available in this build, such as command blocks without the system
+	/// feature, or not implemented yet, such as the alias builtin.
+	UnsupportedOperation {
+		operation: &'static str,
+		pos: SourcePos,
+	},
 	/// std.panic.
 	User {
 		context: Value,
 		pos: SourcePos,
 	},
+	/// A broken invariant of the interpreter, which is a bug in Hush or a malformed program
+	/// that bypassed the semantic analysis.
+	InternalError {
+		context: &'static str,
+		pos: SourcePos,
+	},
+	/// A panic raised inside a function call, with the call stack at that point.
+	Traced {
+		panic: Box<Panic>,
+		traceback: Traceback,
+	},
+}
+
+
+/// The message of a panic, without the position, snippet and call stack.
+#[derive(Debug, Clone, Copy)]
+pub struct Message<'a>(&'a Panic);
+
+
+/// The operator of an operation with an invalid operand.
+#[derive(Debug, Clone, Copy)]
+pub enum Operator {
+	Unary(program::UnaryOp),
+	Binary(program::BinaryOp),
+}
+
+
+/// The kind of a panic, for hosts that need to handle panics programmatically.
+/// More kinds may be added in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PanicKind {
+	StackOverflow,
+	IntegerOverflow,
+	DivisionByZero,
+	IndexOutOfBounds,
+	EmptyCollection,
+	InvalidCall,
+	InvalidArgs,
+	InvalidCondition,
+	TypeError,
+	InvalidOperand,
+	ValueError,
+	InvalidDestructuring,
+	MissingKey,
+	AssignToReadonlyField,
+	InvalidCommandArgs,
+	Io,
+	UnsupportedFileDescriptor,
+	InvalidPattern,
+	AssertionFailed,
+	ImportFailed,
+	ImportCycle,
+	CyclicValue,
+	ModuleNotFound,
+	InvalidJoin,
+	ClosedFile,
+	Interrupted,
+	OutOfFuel,
+	Exit,
+	UnsupportedOperation,
+	User,
+	InternalError,
+}
+
+
+impl PanicKind {
+	/// A stable identifier for the kind of panic.
+	pub fn code(self) -> &'static str {
+		match self {
+			Self::StackOverflow => "stack-overflow",
+			Self::IntegerOverflow => "integer-overflow",
+			Self::DivisionByZero => "division-by-zero",
+			Self::IndexOutOfBounds => "index-out-of-bounds",
+			Self::EmptyCollection => "empty-collection",
+			Self::InvalidCall => "invalid-call",
+			Self::InvalidArgs => "invalid-args",
+			Self::InvalidCondition => "invalid-condition",
+			Self::TypeError => "type-error",
+			Self::InvalidOperand => "invalid-operand",
+			Self::ValueError => "value-error",
+			Self::InvalidDestructuring => "invalid-destructuring",
+			Self::MissingKey => "missing-key",
+			Self::AssignToReadonlyField => "assign-to-readonly-field",
+			Self::InvalidCommandArgs => "invalid-command-args",
+			Self::Io => "io",
+			Self::UnsupportedFileDescriptor => "unsupported-file-descriptor",
+			Self::InvalidPattern => "invalid-pattern",
+			Self::AssertionFailed => "assertion-failed",
+			Self::ImportFailed => "import-failed",
+			Self::ImportCycle => "import-cycle",
+			Self::CyclicValue => "cyclic-value",
+			Self::ModuleNotFound => "module-not-found",
+			Self::InvalidJoin => "invalid-join",
+			Self::ClosedFile => "closed-file",
+			Self::Interrupted => "interrupted",
+			Self::OutOfFuel => "out-of-fuel",
+			Self::Exit => "exit",
+			Self::UnsupportedOperation => "unsupported-operation",
+			Self::User => "user",
+			Self::InternalError => "internal-error",
+		}
+	}
 }
 
 
@@ -104,8 +282,19 @@ impl Panic {
 
 
 	/// Assertion failed.
-	pub fn assertion_failed(pos: SourcePos) -> Self {
-		Self::AssertionFailed { pos }
+	pub fn assertion_failed(expression: Option<Box<str>>, pos: SourcePos) -> Self {
+		Self::AssertionFailed { expression, values: None, pos }
+	}
+
+
+	/// Equality assertion failed.
+	pub fn assertion_eq_failed(
+		expression: Option<Box<str>>,
+		left: Value,
+		right: Value,
+		pos: SourcePos
+	) -> Self {
+		Self::AssertionFailed { expression, values: Some(Box::new([left, right])), pos }
 	}
 
 
@@ -164,6 +353,69 @@ impl Panic {
 	}
 
 
+	/// Operand of unary operator has unexpected type.
+	pub fn invalid_unary_operand<E>(
+		op: &program::UnaryOp,
+		operand: Value,
+		expected: E,
+		pos: SourcePos,
+	) -> Self
+	where
+		E: Into<Cow<'static, str>>,
+	{
+		Self::InvalidOperand {
+			operator: Operator::Unary(*op),
+			operands: Box::new([ operand ]),
+			invalid: 0,
+			expected: expected.into(),
+			pos,
+		}
+	}
+
+
+	/// Left operand of binary operator has unexpected type. The right operand is None if
+	/// it was not evaluated.
+	pub fn invalid_left_operand<E>(
+		op: &program::BinaryOp,
+		left: Value,
+		right: Option<Value>,
+		expected: E,
+		pos: SourcePos,
+	) -> Self
+	where
+		E: Into<Cow<'static, str>>,
+	{
+		Self::InvalidOperand {
+			operator: Operator::Binary(*op),
+			operands: std::iter::once(left).chain(right).collect(),
+			invalid: 0,
+			expected: expected.into(),
+			pos,
+		}
+	}
+
+
+	/// Right operand of binary operator has unexpected type.
+	pub fn invalid_right_operand<E>(
+		op: &program::BinaryOp,
+		left: Value,
+		right: Value,
+		expected: E,
+		pos: SourcePos,
+	) -> Self
+	where
+		E: Into<Cow<'static, str>>,
+	{
+		Self::InvalidOperand {
+			operator: Operator::Binary(*op),
+			operands: Box::new([ left, right ]),
+			invalid: 1,
+			expected: expected.into(),
+			pos,
+		}
+	}
+
+
 	/// Invalid value.
 	pub fn value_error<E>(value: Value, message: E, pos: SourcePos) -> Self
 	where
@@ -200,14 +452,55 @@ impl Panic {
 	}
 
 
+	/// Array destructuring with insufficient items.
+	pub fn invalid_destructuring(expected: usize, supplied: usize, pos: SourcePos) -> Self {
+		Self::InvalidDestructuring { expected, supplied, pos }
+	}
+
+	/// Dict destructuring of a missing key without a default value.
+	/// Only string keys are suggested.
+	pub fn missing_key(key: Value, available: Box<[Value]>, pos: SourcePos) -> Self {
+		let suggestion = match &key {
+			Value::String(key) => semantic::closest(
+				&String::from_utf8_lossy(key.as_ref()),
+				available
+					.iter()
+					.filter_map(|candidate| match candidate {
+						Value::String(string) => Some(
+							(String::from_utf8_lossy(string.as_ref()).into_owned(), candidate.copy())
+						),
+						_ => None,
+					})
+			),
+			_ => None,
+		};
+
+		Self::MissingKey { key, available, suggestion: suggestion.map(Box::new), pos }
+	}
+
 	/// Attempt to assign a readonly field value.
 	pub fn assign_to_readonly_field(field: Value, pos: SourcePos) -> Self {
 		Self::AssignToReadonlyField { field, pos }
 	}
 
 	/// Failed to import module.
-	pub fn import_failed(path: Symbol, pos: SourcePos) -> Self {
-		Self::ImportFailed { path, pos }
+	pub fn import_failed(path: Symbol, error: Box<str>, more: usize, pos: SourcePos) -> Self {
+		Self::ImportFailed { path, error, more, pos }
+	}
+
+	/// A module imported itself.
+	pub fn import_cycle(cycle: Box<[Symbol]>, pos: SourcePos) -> Self {
+		Self::ImportCycle { cycle, pos }
+	}
+
+	/// A value contains itself.
+	pub fn cyclic_value(path: Box<str>, pos: SourcePos) -> Self {
+		Self::CyclicValue { path, pos }
+	}
+
+	/// No module was found for an import.
+	pub fn module_not_found(name: PathBuf, tried: Box<[PathBuf]>, pos: SourcePos) -> Self {
+		Self::ModuleNotFound { name, tried, pos }
 	}
 
 	/// Attempt to call <command>.join more than once.
@@ -215,10 +508,198 @@ impl Panic {
 		Self::InvalidJoin { pos }
 	}
 
+	/// Attempt to use a file handle after closing it.
+	pub fn closed_file(pos: SourcePos) -> Self {
+		Self::ClosedFile { pos }
+	}
+
+	/// Execution was cancelled by the host.
+	pub fn interrupted(pos: SourcePos) -> Self {
+		Self::Interrupted { pos }
+	}
+
+	/// The execution budget has been exhausted.
+	pub fn out_of_fuel(pos: SourcePos) -> Self {
+		Self::OutOfFuel { pos }
+	}
+
+	/// The script requested to exit with the given status.
+	pub fn exit(status: u8, pos: SourcePos) -> Self {
+		Self::Exit { status, pos }
+	}
+
+	/// An operation not available in this build, or not implemented yet.
+	pub fn unsupported_operation(operation: &'static str, pos: SourcePos) -> Self {
+		Self::UnsupportedOperation { operation, pos }
+	}
+
 	/// std.panic
 	pub fn user(context: Value, pos: SourcePos) -> Self {
 		Self::User { context, pos }
 	}
+
+	/// A broken invariant of the interpreter. Debug builds should assert the invariant
+	/// before resorting to this, so that bugs are caught early.
+	pub fn internal_error(context: &'static str, pos: SourcePos) -> Self {
+		Self::InternalError { context, pos }
+	}
+
+	/// Attach the call stack to the panic, unless it already has one.
+	pub fn traced<F>(self, traceback: F) -> Self
+	where
+		F: FnOnce() -> Traceback,
+	{
+		match self {
+			Self::Traced { .. } => self,
+			panic => Self::Traced { panic: Box::new(panic), traceback: traceback() },
+		}
+	}
+
+	/// The panic message, without the position, snippet and call stack.
+	pub fn message(&self) -> Message<'_> {
+		Message(self)
+	}
+
+
+	/// The panic without the call stack.
+	pub fn untraced(&self) -> &Self {
+		match self {
+			Self::Traced { panic, .. } => panic,
+			panic => panic,
+		}
+	}
+
+
+	/// The kind of the panic.
+	pub fn kind(&self) -> PanicKind {
+		match self {
+			Self::StackOverflow { .. } => PanicKind::StackOverflow,
+			Self::IntegerOverflow { .. } => PanicKind::IntegerOverflow,
+			Self::DivisionByZero { .. } => PanicKind::DivisionByZero,
+			Self::IndexOutOfBounds { .. } => PanicKind::IndexOutOfBounds,
+			Self::EmptyCollection { .. } => PanicKind::EmptyCollection,
+			Self::InvalidCall { .. } => PanicKind::InvalidCall,
+			Self::InvalidArgs { .. } => PanicKind::InvalidArgs,
+			Self::InvalidCondition { .. } => PanicKind::InvalidCondition,
+			Self::TypeError { .. } => PanicKind::TypeError,
+			Self::InvalidOperand { .. } => PanicKind::InvalidOperand,
+			Self::ValueError { .. } => PanicKind::ValueError,
+			Self::InvalidDestructuring { .. } => PanicKind::InvalidDestructuring,
+			Self::MissingKey { .. } => PanicKind::MissingKey,
+			Self::AssignToReadonlyField { .. } => PanicKind::AssignToReadonlyField,
+			Self::InvalidCommandArgs { .. } => PanicKind::InvalidCommandArgs,
+			Self::Io { .. } => PanicKind::Io,
+			Self::UnsupportedFileDescriptor { .. } => PanicKind::UnsupportedFileDescriptor,
+			Self::InvalidPattern { .. } => PanicKind::InvalidPattern,
+			Self::AssertionFailed { .. } => PanicKind::AssertionFailed,
+			Self::ImportFailed { .. } => PanicKind::ImportFailed,
+			Self::ImportCycle { .. } => PanicKind::ImportCycle,
+			Self::CyclicValue { .. } => PanicKind::CyclicValue,
+			Self::ModuleNotFound { .. } => PanicKind::ModuleNotFound,
+			Self::InvalidJoin { .. } => PanicKind::InvalidJoin,
+			Self::ClosedFile { .. } => PanicKind::ClosedFile,
+			Self::Interrupted { .. } => PanicKind::Interrupted,
+			Self::OutOfFuel { .. } => PanicKind::OutOfFuel,
+			Self::Exit { .. } => PanicKind::Exit,
+			Self::UnsupportedOperation { .. } => PanicKind::UnsupportedOperation,
+			Self::User { .. } => PanicKind::User,
+			Self::InternalError { .. } => PanicKind::InternalError,
+			Self::Traced { panic, .. } => panic.kind(),
+		}
+	}
+
+
+	/// Whether the panic must terminate the script, as it's imposed by the host, requested
+	/// by std.exit, it exhausted the stack, or the interpreter is in an invalid state. Such
+	/// panics can't be caught by the script, nor reported as test failures.
+	pub fn is_fatal(&self) -> bool {
+		matches!(
+			self.untraced(),
+			Self::Interrupted { .. }
+				| Self::OutOfFuel { .. }
+				| Self::Exit { .. }
+				| Self::StackOverflow { .. }
+				| Self::InternalError { .. }
+		)
+	}
+
+
+	/// The status given to std.exit, if the panic was raised by it.
+	pub fn exit_status(&self) -> Option<u8> {
+		match self.untraced() {
+			Self::Exit { status, .. } => Some(*status),
+			_ => None,
+		}
+	}
+
+
+	/// The value that caused the panic, if any: the index, the called value, the
+	/// condition, the mistyped or invalid value, the missing key, the readonly field, or
+	/// the argument to std.panic.
+	pub fn value(&self) -> Option<&Value> {
+		match self {
+			Self::IndexOutOfBounds { index: value, .. }
+			| Self::InvalidCall { function: value, .. }
+			| Self::InvalidCondition { value, .. }
+			| Self::TypeError { value, .. }
+			| Self::ValueError { value, .. }
+			| Self::MissingKey { key: value, .. }
+			| Self::AssignToReadonlyField { field: value, .. }
+			| Self::User { context: value, .. } => Some(value),
+			Self::InvalidOperand { operands, invalid, .. } => operands.get(*invalid),
+			Self::Traced { panic, .. } => panic.value(),
+			_ => None,
+		}
+	}
+
+
+	/// The call stack at the point the panic was raised, if it was raised inside a
+	/// function call.
+	pub fn traceback(&self) -> Option<&Traceback> {
+		match self {
+			Self::Traced { traceback, .. } => Some(traceback),
+			_ => None,
+		}
+	}
+
+
+	/// The position where the panic occurred.
+	pub fn pos(&self) -> &SourcePos {
+		match self {
+			Self::StackOverflow { pos }
+			| Self::IntegerOverflow { pos }
+			| Self::DivisionByZero { pos }
+			| Self::IndexOutOfBounds { pos, .. }
+			| Self::EmptyCollection { pos }
+			| Self::InvalidCall { pos, .. }
+			| Self::InvalidArgs { pos, .. }
+			| Self::InvalidCondition { pos, .. }
+			| Self::TypeError { pos, .. }
+			| Self::InvalidOperand { pos, .. }
+			| Self::ValueError { pos, .. }
+			| Self::InvalidDestructuring { pos, .. }
+			| Self::MissingKey { pos, .. }
+			| Self::AssignToReadonlyField { pos, .. }
+			| Self::InvalidCommandArgs { pos, .. }
+			| Self::Io { pos, .. }
+			| Self::UnsupportedFileDescriptor { pos, .. }
+			| Self::InvalidPattern { pos, .. }
+			| Self::AssertionFailed { pos, .. }
+			| Self::ImportFailed { pos, .. }
+			| Self::ImportCycle { pos, .. }
+			| Self::CyclicValue { pos, .. }
+			| Self::ModuleNotFound { pos, .. }
+			| Self::InvalidJoin { pos }
+			| Self::ClosedFile { pos }
+			| Self::Interrupted { pos }
+			| Self::OutOfFuel { pos }
+			| Self::Exit { pos, .. }
+			| Self::UnsupportedOperation { pos, .. }
+			| Self::User { pos, .. }
+			| Self::InternalError { pos, .. } => pos,
+			Self::Traced { panic, .. } => panic.pos(),
+		}
+	}
 }
 
 
@@ -226,145 +707,324 @@ impl<'a> Display<'a> for Panic {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		let panic = color::Fg(color::Red, "Panic");
-
 		match self {
-			Self::StackOverflow { pos } =>
-				write!(f, "{} in {}: stack overflow", panic, fmt::Show(pos, context)),
+			Self::Traced { panic, traceback } => {
+				Display::fmt(panic.as_ref(), f, context)?;
+
+				if traceback.is_empty() {
+					Ok(())
+				} else {
+					Display::fmt(traceback, f, context)
+				}
+			}
+
+			panic => {
+				write!(
+					f,
+					"{} in {}: ",
+					color::Fg(color::Red, "Panic"),
+					fmt::Show(panic.pos(), context),
+				)?;
+				Display::fmt(&panic.message(), f, context)?;
+				panic.pos().snippet().fmt(f, context)
+			}
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Message<'_> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self.0 {
+			Panic::Traced { panic, .. } => Display::fmt(&panic.message(), f, context),
+
+			Panic::StackOverflow { .. } =>
+				write!(f, "stack overflow"),
 
-			Self::IntegerOverflow { pos } =>
-				write!(f, "{} in {}: integer overflow", panic, fmt::Show(pos, context)),
+			Panic::IntegerOverflow { .. } =>
+				write!(f, "integer overflow"),
 
-			Self::DivisionByZero { pos } =>
-				write!(f, "{} in {}: division by zero", panic, fmt::Show(pos, context)),
+			Panic::DivisionByZero { .. } =>
+				write!(f, "division by zero"),
 
-			Self::IndexOutOfBounds { index, pos } =>
+			Panic::IndexOutOfBounds { index, .. } =>
 				write!(
 					f,
-					"{} in {}: index ({}) out of bounds",
-					panic,
-					fmt::Show(pos, context),
+					"index ({}) out of bounds",
 					color::Fg(color::Yellow, fmt::Show(index, context))
 				),
 
-			Self::EmptyCollection { pos } =>
-				write!(f, "{} in {}: collection is empty", panic, fmt::Show(pos, context)),
+			Panic::EmptyCollection { .. } =>
+				write!(f, "collection is empty"),
 
-			Self::InvalidCall { function, pos } =>
+			Panic::InvalidCall { function, .. } =>
 				write!(
 					f,
-					"{} in {}: attempt to call ({}), which is not a function",
-					panic,
-					fmt::Show(pos, context),
+					"attempt to call ({}), which is not a function",
 					color::Fg(color::Yellow, fmt::Show(function, context))
 				),
 
-			Self::InvalidArgs { supplied, expected, pos } =>
+			Panic::InvalidArgs { supplied, expected, .. } =>
 				write!(
 					f,
-					"{} in {}: incorrect amount of function parameters -- supplied {}, expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"incorrect amount of function parameters -- supplied {}, expected {}",
 					supplied,
 					expected
 				),
 
-			Self::InvalidCondition { value, pos } =>
+			Panic::InvalidCondition { value, .. } => {
 				write!(
 					f,
-					"{} in {}: condition ({}) is not a boolean",
-					panic,
-					fmt::Show(pos, context),
-					color::Fg(color::Yellow, fmt::Show(value, context))
-				),
+					"condition ({}) has type {}, expected bool",
+					color::Fg(color::Yellow, fmt::Show(value, context)),
+					value.get_type(),
+				)?;
+
+				// Users coming from languages with truthy values often test for presence or
+				// command success directly.
+				match value {
+					Value::Nil => write!(
+						f,
+						". Hint: use `value != nil` to test for presence",
+					),
 
-			Self::TypeError { value, expected, pos } =>
+					Value::Error(_) => write!(
+						f,
+						". Hint: command blocks evaluate to an error on failure, \
+						 use `std.type(value) == \"error\"` to test for failure",
+					),
+
+					_ => Ok(()),
+				}
+			}
+
+			Panic::TypeError { value, expected, .. } =>
 				write!(
 					f,
-					"{} in {}: value ({}) has unexpected type, expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"value ({}) has unexpected type, expected {}",
 					color::Fg(color::Yellow, fmt::Show(value, context)),
 					expected,
 				),
 
-			Self::ValueError { value, message, pos } =>
+			Panic::InvalidOperand { operator, operands, invalid, expected, .. } => {
+				let operand = |ix: usize| color::Fg(
+					color::Yellow,
+					operands
+						.get(ix)
+						.map(|value| truncated(value, context))
+						.unwrap_or_else(|| "...".into())
+				);
+
+				let operation = match operator {
+					Operator::Unary(op @ program::UnaryOp::Not) => format!("{} {}", op, operand(0)),
+					Operator::Unary(op) => format!("{}{}", op, operand(0)),
+					Operator::Binary(op) => format!("{} {} {}", operand(0), op, operand(1)),
+				};
+
+				write!(
+					f,
+					"invalid operand in ({}): value ({}) has unexpected type, expected {}",
+					operation,
+					operand(*invalid),
+					expected,
+				)
+			}
+
+			Panic::ValueError { value, message, .. } =>
 				write!(
 					f,
-					"{} in {}: invalid value ({}), expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"invalid value ({}), expected {}",
 					color::Fg(color::Yellow, fmt::Show(value, context)),
 					message,
 				),
 
-			Self::InvalidCommandArgs { object, items, pos } =>
+			Panic::InvalidCommandArgs { object, items, .. } =>
 				write!(
 					f,
-					"{} in {}: {} expansion resulted in {} items",
-					panic,
-					fmt::Show(pos, context),
+					"{} expansion resulted in {} items",
 					object,
 					items
 				),
 
-			Self::Io { error, pos } =>
-				write!(f, "{} in {}: {}", panic, fmt::Show(pos, context), error),
+			Panic::Io { error, .. } =>
+				write!(f, "{}", error),
 
-			Self::UnsupportedFileDescriptor { fd, pos } =>
+			Panic::UnsupportedFileDescriptor { fd, .. } =>
 				write!(
 					f,
-					"{} in {}: unsupported file descriptor ({})",
-					panic,
-					fmt::Show(pos, context),
+					"unsupported file descriptor ({})",
 					color::Fg(color::Yellow, fd)
 				),
 
-			Self::InvalidPattern { pattern, pos } =>
+			Panic::InvalidPattern { pattern, .. } =>
 				write!(
 					f,
-					"{} in {}: pattern ({:?}) has invalid UTF-8",
-					panic,
-					fmt::Show(pos, context),
+					"pattern ({:?}) has invalid UTF-8",
 					color::Fg(color::Yellow, pattern)
 				),
 
-			Self::AssignToReadonlyField { field, pos } => write!(
+			Panic::InvalidDestructuring { expected, supplied, .. } => write!(
 					f,
-					"{} in {}: attempt to assign field ({}), which is readonly",
-					panic,
-					fmt::Show(pos, context),
+					"expected at least {} elements, got {}",
+					expected,
+					supplied,
+				),
+
+			Panic::MissingKey { key, available, suggestion, .. } => {
+				write!(f, "missing key ({})", color::Fg(color::Yellow, fmt::Show(key, context)))?;
+
+				match suggestion {
+					Some(suggestion) => write!(
+						f,
+						", did you mean ({})? available keys: ",
+						color::Fg(color::Yellow, fmt::Show(suggestion.as_ref(), context)),
+					)?,
+					None => write!(f, ", available keys: ")?,
+				}
+
+				if available.is_empty() {
+					write!(f, "none")
+				} else {
+					fmt::sep_by(
+						available.iter(),
+						f,
+						|key, f| write!(f, "{}", fmt::Show(key, context)),
+						", ",
+					)
+				}
+			}
+
+			Panic::AssignToReadonlyField { field, .. } => write!(
+					f,
+					"attempt to assign field ({}), which is readonly",
 					color::Fg(color::Yellow, fmt::Show(field, context))
 				),
 
-			Self::AssertionFailed { pos } =>
-				write!(f, "{} in {}: assertion failed", panic, fmt::Show(pos, context)),
+			Panic::AssertionFailed { expression, values, .. } => {
+				write!(f, "assertion failed")?;
+
+				if let Some(expression) = expression {
+					write!(f, ": {}", expression)?;
+				}
+
+				if let Some(values) = values {
+					let [ left, right ] = values.as_ref();
+					write!(
+						f,
+						", left: ({}), right: ({})",
+						color::Fg(color::Yellow, truncated(left, context)),
+						color::Fg(color::Yellow, truncated(right, context)),
+					)?;
+				}
 
-			Self::ImportFailed { path, pos } =>
+				Ok(())
+			}
+
+			Panic::ImportFailed { path, error, more, .. } => {
 				write!(
 					f,
-					"{} in {}: failed to import module ({})",
-					panic,
-					fmt::Show(pos, context),
-					color::Fg(color::Yellow, fmt::Show(path, context))
-				),
+					"failed to import module ({}): {}",
+					color::Fg(color::Yellow, fmt::Show(path, context)),
+					error,
+				)?;
+
+				match more {
+					0 => Ok(()),
+					1 => write!(f, " (and 1 more error)"),
+					more => write!(f, " (and {} more errors)", more),
+				}
+			}
 
-			Self::InvalidJoin { pos } =>
-				write!(f, "{} in {}: attempt to call join more than once", panic, fmt::Show(pos, context)),
+			Panic::ImportCycle { cycle, .. } => {
+				write!(f, "import cycle: ")?;
 
-			Self::User { context: value, pos } =>
+				fmt::sep_by(
+					cycle.iter(),
+					f,
+					|path, f| write!(f, "{}", color::Fg(color::Yellow, fmt::Show(path, context))),
+					" -> ",
+				)
+			}
+
+			Panic::CyclicValue { path, .. } =>
+				write!(f, "cyclic value at {}", color::Fg(color::Yellow, path)),
+
+			Panic::ModuleNotFound { name, tried, .. } => {
+				write!(
+					f,
+					"module not found ({})",
+					color::Fg(color::Yellow, name.display())
+				)?;
+
+				for (ix, path) in tried.iter().enumerate() {
+					let separator = if ix == 0 { ", tried: " } else { ", " };
+					write!(f, "{}{}", separator, path.display())?;
+				}
+
+				Ok(())
+			}
+
+			Panic::InvalidJoin { .. } =>
+				write!(f, "attempt to call join more than once"),
+
+			Panic::ClosedFile { .. } =>
+				write!(f, "attempt to use a closed file"),
+
+			Panic::Interrupted { .. } =>
+				write!(f, "execution interrupted"),
+
+			Panic::OutOfFuel { .. } =>
+				write!(f, "out of fuel"),
+
+			Panic::Exit { status, .. } =>
+				write!(f, "exit with status {}", status),
+
+			Panic::UnsupportedOperation { operation, .. } =>
+				write!(f, "unsupported operation: {}", operation),
+
+			Panic::User { context: value, .. } =>
 				write!(
 					f,
-					"{} in {}: std.panic({})",
-					panic,
-					fmt::Show(pos, context),
+					"std.panic({})",
 					color::Fg(color::Yellow, fmt::Show(value, context))
 				),
+
+			Panic::InternalError { context, .. } =>
+				write!(
+					f,
+					"internal error: {}. This is a bug in hush {}, please report it at {}/issues",
+					context,
+					env!("CARGO_PKG_VERSION"),
+					env!("CARGO_PKG_REPOSITORY"),
+				),
 		}
 	}
 }
 
 
+/// Format a value for a panic message, truncating it if too long.
+fn truncated(value: &Value, interner: &symbol::Interner) -> String {
+	/// Values longer than this many characters are truncated.
+	const MAX_WIDTH: usize = 40;
+	/// Marks truncated values.
+	const ELLIPSIS: &str = "...";
+
+	let text = color::plain(|| fmt::Show(value, interner).to_string());
+
+	if text.chars().count() <= MAX_WIDTH {
+		text
+	} else {
+		text
+			.chars()
+			.take(MAX_WIDTH - ELLIPSIS.len())
+			.chain(ELLIPSIS.chars())
+			.collect()
+	}
+}
+
+
 /// We need this in order to be able to implement std::error::Error.
 impl std::fmt::Display for Panic {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
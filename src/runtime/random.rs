@@ -0,0 +1,112 @@
+use std::{
+	collections::hash_map::RandomState,
+	hash::{BuildHasher, Hasher},
+};
+
+
+/// A xoshiro256** pseudo random number generator.
+/// This is not cryptographically secure, but it's fast and has good statistical
+/// properties, which is what scripts usually need.
+#[derive(Debug)]
+pub struct Rng {
+	state: [u64; 4],
+}
+
+
+impl Rng {
+	/// Create a generator from the given seed.
+	/// The same seed always yields the same sequence.
+	pub fn from_seed(seed: u64) -> Self {
+		// Expand the seed with splitmix64, which guarantees a non-zero state.
+		let mut seed = seed;
+		let mut next = || {
+			seed = seed.wrapping_add(0x9e3779b97f4a7c15);
+			let mut z = seed;
+			z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+			z ^ (z >> 31)
+		};
+
+		Self { state: [next(), next(), next(), next()] }
+	}
+
+
+	/// Reset the generator with the given seed.
+	pub fn seed(&mut self, seed: u64) {
+		*self = Self::from_seed(seed);
+	}
+
+
+	/// Generate a random u64.
+	pub fn next_u64(&mut self) -> u64 {
+		let result = self.state[1]
+			.wrapping_mul(5)
+			.rotate_left(7)
+			.wrapping_mul(9);
+
+		let t = self.state[1] << 17;
+
+		self.state[2] ^= self.state[0];
+		self.state[3] ^= self.state[1];
+		self.state[1] ^= self.state[2];
+		self.state[0] ^= self.state[3];
+		self.state[2] ^= t;
+		self.state[3] = self.state[3].rotate_left(45);
+
+		result
+	}
+
+
+	/// Generate a random float in [0, 1).
+	pub fn next_f64(&mut self) -> f64 {
+		// Use the upper 53 bits, which is the precision of f64.
+		(self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+	}
+
+
+	/// Generate a random u64 in [0, bound). The bound must not be zero.
+	pub fn below(&mut self, bound: u64) -> u64 {
+		debug_assert!(bound != 0);
+
+		// Reject values in the incomplete last zone, in order to avoid bias.
+		let zone = u64::MAX - (u64::MAX - bound + 1) % bound;
+
+		loop {
+			let value = self.next_u64();
+			if value <= zone {
+				return value % bound;
+			}
+		}
+	}
+
+
+	/// Generate a random int in [low, high]. The low bound must not be greater than the
+	/// high bound.
+	pub fn between(&mut self, low: i64, high: i64) -> i64 {
+		debug_assert!(low <= high);
+
+		let span = high.wrapping_sub(low) as u64;
+
+		let offset =
+			if span == u64::MAX { // Full range.
+				self.next_u64()
+			} else {
+				self.below(span + 1)
+			};
+
+		low.wrapping_add(offset as i64)
+	}
+}
+
+
+impl Default for Rng {
+	/// Create a generator seeded from the operating system's randomness.
+	fn default() -> Self {
+		// The std RandomState is randomly keyed by the operating system.
+		let seed = RandomState::new()
+			.build_hasher()
+			.finish();
+
+		Self::from_seed(seed)
+	}
+}
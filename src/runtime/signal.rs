@@ -0,0 +1,123 @@
+use std::{
+	io,
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+
+/// Number of supported signal numbers.
+const SIGNALS: usize = 65;
+
+
+/// Signals received but not yet delivered to the script.
+static PENDING: [AtomicBool; SIGNALS] = {
+	#[allow(clippy::declare_interior_mutable_const)]
+	const NOT_PENDING: AtomicBool = AtomicBool::new(false);
+	[NOT_PENDING; SIGNALS]
+};
+/// Whether any entry in PENDING is set, so that checking is cheap in the common case.
+static ANY_PENDING: AtomicBool = AtomicBool::new(false);
+
+
+/// Names of the signals that may be trapped, without the SIG prefix.
+const NAMES: &[(&str, libc::c_int)] = &[
+	("HUP", libc::SIGHUP),
+	("INT", libc::SIGINT),
+	("QUIT", libc::SIGQUIT),
+	("USR1", libc::SIGUSR1),
+	("USR2", libc::SIGUSR2),
+	("PIPE", libc::SIGPIPE),
+	("ALRM", libc::SIGALRM),
+	("TERM", libc::SIGTERM),
+	("CHLD", libc::SIGCHLD),
+	("CONT", libc::SIGCONT),
+	("TSTP", libc::SIGTSTP),
+	("TTIN", libc::SIGTTIN),
+	("TTOU", libc::SIGTTOU),
+	("WINCH", libc::SIGWINCH),
+];
+
+
+/// Get the number of a signal by name, with or without the SIG prefix.
+pub fn parse(name: &[u8]) -> Option<libc::c_int> {
+	let name = name.strip_prefix(b"SIG").unwrap_or(name);
+
+	NAMES
+		.iter()
+		.find(|(signal, _)| signal.as_bytes() == name)
+		.map(|&(_, signal)| signal)
+}
+
+
+/// Get the name of a signal, without the SIG prefix.
+pub fn name(signal: libc::c_int) -> &'static str {
+	NAMES
+		.iter()
+		.find(|&&(_, number)| number == signal)
+		.map(|(name, _)| *name)
+		.unwrap_or("UNKNOWN")
+}
+
+
+/// The signal handler, which only marks the signal as pending, as nothing else would be
+/// async-signal-safe.
+extern "C" fn handler(signal: libc::c_int) {
+	if let Some(pending) = PENDING.get(signal as usize) {
+		pending.store(true, Ordering::SeqCst);
+		ANY_PENDING.store(true, Ordering::SeqCst);
+	}
+}
+
+
+fn set_action(signal: libc::c_int, action: libc::sighandler_t) -> io::Result<()> {
+	unsafe {
+		let mut sigaction: libc::sigaction = std::mem::zeroed();
+		sigaction.sa_sigaction = action;
+		sigaction.sa_flags = libc::SA_RESTART;
+		libc::sigemptyset(&mut sigaction.sa_mask);
+
+		if libc::sigaction(signal, &sigaction, std::ptr::null_mut()) == 0 {
+			Ok(())
+		} else {
+			Err(io::Error::last_os_error())
+		}
+	}
+}
+
+
+/// Mark the signal as pending when received, for later delivery.
+pub fn trap(signal: libc::c_int) -> io::Result<()> {
+	set_action(signal, handler as extern "C" fn(libc::c_int) as libc::sighandler_t)
+}
+
+
+/// Ignore the signal.
+pub fn ignore(signal: libc::c_int) -> io::Result<()> {
+	set_action(signal, libc::SIG_IGN)
+}
+
+
+/// Restore the default action for the signal.
+pub fn reset(signal: libc::c_int) -> io::Result<()> {
+	set_action(signal, libc::SIG_DFL)
+}
+
+
+/// Whether there are signals pending delivery.
+pub fn is_pending() -> bool {
+	ANY_PENDING.load(Ordering::Relaxed)
+}
+
+
+/// Take the pending signals, in ascending order.
+pub fn take_pending() -> Vec<libc::c_int> {
+	if !ANY_PENDING.swap(false, Ordering::SeqCst) {
+		return Vec::new();
+	}
+
+	PENDING
+		.iter()
+		.enumerate()
+		.filter(|(_, pending)| pending.swap(false, Ordering::SeqCst))
+		.map(|(signal, _)| signal as libc::c_int)
+		.collect()
+}
@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 
 use crate::{
-	fmt::{self, Display},
+	fmt::Display,
 	syntax,
 	symbol::{self, Symbol},
 };
@@ -10,6 +10,8 @@ use gc::{Finalize, Trace};
 
 
 /// A human readable position in the source code.
+/// Lines and columns start at 1, and columns count characters. Line zero refers to the
+/// whole file.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(Finalize)]
 pub struct SourcePos {
@@ -30,6 +32,12 @@ impl SourcePos {
 	pub fn file(path: Symbol) -> Self {
 		Self { line: 0, column: 0, path }
 	}
+
+
+	/// The snippet of source code for this position.
+	pub fn snippet(&self) -> syntax::Snippet {
+		syntax::SourcePos::from(self).snippet()
+	}
 }
 
 
@@ -72,16 +80,21 @@ impl<'a> From<&'a syntax::SourcePos> for SourcePos {
 }
 
 
+impl<'a> From<&'a SourcePos> for syntax::SourcePos {
+	fn from(pos: &'a SourcePos) -> Self {
+		Self {
+			line: pos.line,
+			column: pos.column,
+			path: pos.path,
+		}
+	}
+}
+
+
 impl<'a> Display<'a> for SourcePos {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		write!(
-			f,
-			"{} (line {}, column {})",
-			fmt::Show(self.path, context),
-			self.line,
-			self.column
-		)
+		syntax::SourcePos::from(self).fmt(f, context)
 	}
 }
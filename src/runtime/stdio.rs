@@ -0,0 +1,226 @@
+use std::{
+	io::{self, BufRead, BufReader, Read, Write},
+	sync::{Arc, Mutex, MutexGuard},
+};
+#[cfg(feature = "system")]
+use std::thread;
+
+
+/// The standard streams of the runtime, used by the stdlib and inherited by commands.
+/// Embedders may replace them in order to capture output or provide input. Without the
+/// system feature, these are the only way in or out of the runtime: input is empty and
+/// output is discarded unless the embedder provides its own streams.
+#[derive(Debug)]
+pub struct Stdio {
+	pub stdin: Input,
+	pub stdout: Output,
+	pub stderr: Output,
+}
+
+
+impl Stdio {
+	/// Get pipes to be inherited by commands. The process' streams are inherited directly,
+	/// while output to custom writers is forwarded by threads. Commands can't share a
+	/// custom reader, as they would consume input ahead of the script, so they get an empty
+	/// stdin instead.
+	#[cfg(feature = "system")]
+	pub fn pipes(&self) -> io::Result<Pipes> {
+		let mut forwarders = Vec::new();
+
+		let stdin = match self.stdin {
+			Input::Stdin => os_pipe::dup_stdin()?,
+			Input::Custom(_) => os_pipe::pipe()?.0,
+		};
+
+		let mut output = |output: &Output| match output {
+			Output::Stdout => os_pipe::dup_stdout(),
+			Output::Stderr => os_pipe::dup_stderr(),
+			Output::Custom(writer) => {
+				let (reader, pipe) = os_pipe::pipe()?;
+				let writer = writer.clone();
+				forwarders.push(thread::spawn(move || forward(reader, writer)));
+				Ok(pipe)
+			}
+		};
+
+		let stdout = output(&self.stdout)?;
+		let stderr = output(&self.stderr)?;
+
+		Ok(Pipes { stdin, stdout, stderr, forwarders: Forwarders(forwarders) })
+	}
+}
+
+
+#[cfg(feature = "system")]
+impl Default for Stdio {
+	fn default() -> Self {
+		Self {
+			stdin: Input::Stdin,
+			stdout: Output::Stdout,
+			stderr: Output::Stderr,
+		}
+	}
+}
+
+
+#[cfg(not(feature = "system"))]
+impl Default for Stdio {
+	fn default() -> Self {
+		Self {
+			stdin: Input::custom(io::empty()),
+			stdout: Output::custom(io::sink()),
+			stderr: Output::custom(io::sink()),
+		}
+	}
+}
+
+
+/// An input stream of the runtime.
+pub enum Input {
+	/// The process' stdin.
+	#[cfg(feature = "system")]
+	Stdin,
+	/// A reader provided by the embedder.
+	Custom(Box<dyn BufRead + Send>),
+}
+
+
+impl Input {
+	pub fn custom<R>(reader: R) -> Self
+	where
+		R: Read + Send + 'static,
+	{
+		Self::Custom(Box::new(BufReader::new(reader)))
+	}
+
+
+	/// Read a line, including the line terminator, if any.
+	pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+		match self {
+			#[cfg(feature = "system")]
+			Self::Stdin => io::stdin().read_line(buf),
+			Self::Custom(reader) => reader.read_line(buf),
+		}
+	}
+}
+
+
+impl std::fmt::Debug for Input {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			#[cfg(feature = "system")]
+			Self::Stdin => f.write_str("Stdin"),
+			Self::Custom(_) => f.write_str("Custom"),
+		}
+	}
+}
+
+
+/// An output stream of the runtime.
+#[derive(Clone)]
+pub enum Output {
+	/// The process' stdout.
+	#[cfg(feature = "system")]
+	Stdout,
+	/// The process' stderr.
+	#[cfg(feature = "system")]
+	Stderr,
+	/// A writer provided by the embedder, shared with the threads forwarding the output of
+	/// commands.
+	Custom(Arc<Mutex<Box<dyn Write + Send>>>),
+}
+
+
+impl Output {
+	pub fn custom<W>(writer: W) -> Self
+	where
+		W: Write + Send + 'static,
+	{
+		Self::Custom(Arc::new(Mutex::new(Box::new(writer))))
+	}
+
+
+	/// Write to the locked stream.
+	pub fn with<F, T>(&self, write: F) -> io::Result<T>
+	where
+		F: FnOnce(&mut dyn Write) -> io::Result<T>,
+	{
+		match self {
+			#[cfg(feature = "system")]
+			Self::Stdout => write(&mut io::stdout().lock()),
+			#[cfg(feature = "system")]
+			Self::Stderr => write(&mut io::stderr().lock()),
+			Self::Custom(writer) => write(&mut *lock(writer)),
+		}
+	}
+}
+
+
+impl std::fmt::Debug for Output {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			#[cfg(feature = "system")]
+			Self::Stdout => f.write_str("Stdout"),
+			#[cfg(feature = "system")]
+			Self::Stderr => f.write_str("Stderr"),
+			Self::Custom(_) => f.write_str("Custom"),
+		}
+	}
+}
+
+
+/// Pipes to the standard streams of the runtime.
+#[cfg(feature = "system")]
+#[derive(Debug)]
+pub struct Pipes {
+	pub stdin: os_pipe::PipeReader,
+	pub stdout: os_pipe::PipeWriter,
+	pub stderr: os_pipe::PipeWriter,
+	pub forwarders: Forwarders,
+}
+
+
+/// Threads forwarding output from pipes to custom writers.
+#[cfg(feature = "system")]
+#[derive(Debug)]
+pub struct Forwarders(Vec<thread::JoinHandle<()>>);
+
+
+#[cfg(feature = "system")]
+impl Forwarders {
+	/// Wait until all output has been forwarded, which happens once all copies of the
+	/// pipes' writers are closed.
+	pub fn join(self) {
+		for forwarder in self.0 {
+			let _ = forwarder.join();
+		}
+	}
+}
+
+
+/// Forward everything from the reader to the writer. The writer is only locked while
+/// writing each chunk, so that output from the script may be interleaved.
+#[cfg(feature = "system")]
+fn forward(mut reader: os_pipe::PipeReader, writer: Arc<Mutex<Box<dyn Write + Send>>>) {
+	let mut buffer = [0; 4096];
+
+	loop {
+		match reader.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(size) => {
+				if lock(&writer).write_all(&buffer[..size]).is_err() {
+					break;
+				}
+			}
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+			Err(_) => break,
+		}
+	}
+}
+
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex
+		.lock()
+		.unwrap_or_else(|error| error.into_inner())
+}
@@ -0,0 +1,90 @@
+use std::{
+	cell::Cell,
+	ffi::OsStr,
+	fs,
+	io,
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+use super::random::Rng;
+
+
+/// How many names to try before giving up on creating a temporary entry.
+const ATTEMPTS: u32 = 64;
+
+
+/// Create an entry with a unique name in the system's temporary directory.
+/// The create function must fail with `AlreadyExists` if the path exists, so that name
+/// collisions are detected atomically instead of being raced.
+pub fn create_unique<T, F>(prefix: &OsStr, mut create: F) -> io::Result<T>
+where
+	F: FnMut(&Path) -> io::Result<T>,
+{
+	// Names must not be predictable, so we don't use the runtime's generator, which
+	// scripts may seed.
+	let mut rng = Rng::default();
+	let dir = std::env::temp_dir();
+
+	for _ in 0 .. ATTEMPTS {
+		let mut name = prefix.to_owned();
+		name.push(format!("{:016x}", rng.next_u64()));
+
+		match create(&dir.join(name)) {
+			Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+			result => return result,
+		}
+	}
+
+	Err(io::Error::new(io::ErrorKind::AlreadyExists, "failed to find a unique temporary name"))
+}
+
+
+/// A temporary directory, recursively removed when dropped unless kept.
+#[derive(Debug)]
+pub struct TempDir {
+	path: PathBuf,
+	/// Whether the directory should be left in place, or has already been removed.
+	done: Cell<bool>,
+}
+
+
+impl TempDir {
+	/// Create a new temporary directory, with a name starting with the given prefix.
+	pub fn create(prefix: &OsStr) -> io::Result<Rc<Self>> {
+		let path = create_unique(
+			prefix,
+			|path| fs::create_dir(path).map(|()| path.to_owned())
+		)?;
+
+		Ok(Rc::new(Self { path, done: Cell::new(false) }))
+	}
+
+
+	/// The path of the directory.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+
+	/// Prevent the directory from being removed.
+	pub fn keep(&self) {
+		self.done.set(true);
+	}
+
+
+	/// Remove the directory, if it hasn't been kept.
+	pub fn remove(&self) {
+		if !self.done.replace(true) {
+			// There's nowhere to report errors to, as this usually happens on drop.
+			let _ = fs::remove_dir_all(&self.path);
+		}
+	}
+}
+
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		self.remove();
+	}
+}
@@ -1,7 +1,12 @@
 use std::{
+	cell::Cell,
+	cmp::Ordering,
+	collections::hash_map::DefaultHasher,
+	hash::{Hash, Hasher},
 	io,
 	path::Path,
 	os::unix::ffi::OsStrExt,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use serial_test::serial;
@@ -13,74 +18,115 @@ use crate::{
 	syntax::{self, AnalysisDisplayContext},
 	tests,
 };
-use super::{Runtime, Value, Panic};
+use super::{Clock, Interpreter, Runtime, SourcePos, Traceback, Value, Panic};
 
 
-fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
+/// Parse, analyze and evaluate the given source in the runtime.
+/// Panics if there are any syntax or semantic errors.
+fn eval<R>(runtime: &mut Runtime, path: &Path, reader: R) -> io::Result<Result<Value, Panic>>
 where
-	P: AsRef<Path>,
-	F: FnMut(&Result<Value, Panic>) -> bool,
+	R: io::Read,
 {
-	let interner = symbol::Interner::new();
-	let args = std::iter::empty::<&str>();
-	let mut runtime = Runtime::new(args, interner);
+	let program = compile(runtime, path, reader)?;
+	Ok(runtime.eval(program))
+}
 
-	tests::util::test_dir(
-		path,
-		move |path, file| {
-			let path_symbol = runtime
-				.interner_mut()
-				.get_or_intern(path.as_os_str().as_bytes());
-			let source = syntax::Source::from_reader(path_symbol, file)?;
-			let syntactic_analysis = syntax::Analysis::analyze(
-				&source,
-				runtime.interner_mut()
-			);
 
-			if !syntactic_analysis.errors.is_empty() {
-				panic!(
-					"{}",
-					fmt::Show(
-						syntactic_analysis,
-						AnalysisDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				);
-			}
+/// Parse and analyze the given source for the runtime.
+/// Panics if there are any syntax or semantic errors.
+fn compile<R>(runtime: &mut Runtime, path: &Path, reader: R) -> io::Result<semantic::program::Program>
+where
+	R: io::Read,
+{
+	let path_symbol = runtime
+		.interner_mut()
+		.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, reader)?;
+	let syntactic_analysis = syntax::Analysis::analyze(
+		&source,
+		runtime.interner_mut()
+	);
 
-			let semantic_analysis = semantic::Analyzer::analyze(
-				syntactic_analysis.ast,
-				runtime.interner_mut()
-			);
-			let program = match semantic_analysis {
-				Ok(program) => program,
-				Err(errors) => panic!(
-					"{}",
-					fmt::Show(
-						errors,
-						ErrorsDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				),
-			};
+	if !syntactic_analysis.errors.is_empty() {
+		panic!(
+			"{}",
+			fmt::Show(
+				syntactic_analysis,
+				AnalysisDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+				}
+			)
+		);
+	}
+
+	let semantic_analysis = semantic::Analyzer::analyze(
+		syntactic_analysis.ast,
+		runtime.interner_mut()
+	);
+	match semantic_analysis {
+		Ok((program, _)) => Ok(program),
+		Err(errors) => panic!(
+			"{}",
+			fmt::Show(
+				errors,
+				ErrorsDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+				}
+			)
+		),
+	}
+}
 
-			let program = Box::leak(Box::new(program));
 
-			let result = runtime.eval(program);
+/// Evaluate the given source string, panicking if the script panics.
+fn eval_str(runtime: &mut Runtime, source: &str) -> Value {
+	let result = eval(runtime, Path::new("<test>"), source.as_bytes())
+		.expect("failed to read source");
 
-			if !check(&result) {
-				match result {
-					Ok(value) => panic!(
-						"File {}: expected panic, got {}",
-						path.display(),
-						fmt::Show(value, runtime.interner())
-					),
-					Err(panic) => panic!("{}", fmt::Show(panic, runtime.interner())),
-				}
+	match result {
+		Ok(value) => value,
+		Err(panic) => panic!("{}", fmt::Show(panic, runtime.interner())),
+	}
+}
+
+
+/// Evaluate every script in the directory with both interpreters, on a large stack.
+fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
+where
+	P: AsRef<Path> + Send,
+	F: FnMut(&Result<Value, Panic>) -> bool + Send,
+{
+	tests::util::on_large_stack(
+		move || {
+			for &interpreter in &[Interpreter::TreeWalker, Interpreter::Vm] {
+				let interner = symbol::Interner::new();
+				let args = std::iter::empty::<&str>();
+				let mut runtime = Runtime::new(args, interner);
+				runtime.set_interpreter(interpreter);
+				runtime.set_max_call_depth(tests::util::LARGE_STACK_CALL_DEPTH);
+
+				tests::util::test_dir(
+					path.as_ref(),
+					|path, file| {
+						let result = eval(&mut runtime, path, file)?;
+
+						if !check(&result) {
+							match result {
+								Ok(value) => panic!(
+									"File {} ({:?}): expected panic, got {}",
+									path.display(),
+									interpreter,
+									fmt::Show(value, runtime.interner())
+								),
+								Err(panic) => panic!("{:?}: {}", interpreter, fmt::Show(panic, runtime.interner())),
+							}
+						}
+
+						Ok(())
+					}
+				)?;
 			}
 
 			Ok(())
@@ -93,8 +139,16 @@ where
 // parallel.
 
 
+/// Check that Panic is not too big, because it is returned by nearly every function.
+#[test]
+fn test_panic_size() {
+	assert!(std::mem::size_of::<Panic>() <= 96, "{}", std::mem::size_of::<Panic>());
+}
+
+
 #[test]
 #[serial]
+#[cfg(feature = "system")]
 fn test_positive() -> io::Result<()> {
 	test_dir(
 		"src/runtime/tests/data/positive",
@@ -118,6 +172,3006 @@ fn test_negative() -> io::Result<()> {
 fn test_asserts() -> io::Result<()> {
 	test_dir(
 		"src/runtime/tests/data/negative/asserts",
-		|result| matches!(result, Err(Panic::AssertionFailed { .. }))
+		|result| matches!(result.as_ref().map_err(Panic::untraced), Err(Panic::AssertionFailed { .. }))
+	)
+}
+
+
+#[test]
+#[serial]
+fn test_interpreters_agree() {
+	let sources = [
+		// Breaking in the middle of an expression stops the iterator.
+		"let xs = []\nfor i in std.range(0, 10, 1) do\n\tstd.push(xs, if i == 3 then break else i end)\nend\nxs",
+		"let f = function ()\n\tfor i in std.range(0, 10, 1) do\n\t\twhile true do\n\t\t\treturn i + 1\n\t\tend\n\tend\nend\nf()",
+		"let f = function (n)\n\tif n == 0 then\n\t\treturn \"done\"\n\tend\n\tf(n - 1)\nend\nf(10)",
+		"let f = function ()\n\tlet x = std.error(\"failed\", nil)?\n\tx\nend\nf()",
+		"let @[ a, b = a + 1 ] = @[ a: 1 ]\nlet c = nil\nlet d = nil\nc, d = b, \"${a} and ${[ b ]}\"\n@[ c: c, d: d, e: [ 1 < 2, 1 == 1.0, \"x\" ++ \"y\" ] ]",
+		"let d = @[ n: 0, inc: function () self.n = self.n + 1 end ]\nd.inc()\nd[\"inc\"]()\nd.n",
+		"let x = 1\nlet g = function ()\n\tx = 10\n\t1\nend\nlet r = [ x + g(), x - 1 < x, 2 * x == 20, \"a\" != \"b\" ]\nr",
+		"let x = 9223372036854775807\nlet y = x - 1\ny + 1 + 1",
+		"let x = 1\nlet y = true and x",
+		"let x = 1\nlet y = x or true",
+		"let f = 1\nf(std.print(\"unreachable\"))",
+		"let d = @[ a: 1 ]\nd.a()",
+		"if 1 then\n\t2\nend",
+		"let i = 0\nwhile i do\nend",
+		"for x in 1 do\nend",
+		"for x in function () nil end do\nend",
+		"let a = [ 1 ]\na[5] = 2",
+		"let a = [ 1 ]\na[\"x\"]",
+		"let d = @[ f: function (x) x end ]\nd.f(1, 2)",
+		"let f = function ()\n\tlet @[ missing ] = @[ ]\nend\nf()",
+		"let s = \"x\"\ns.y = 1",
+		"let sum = 0\nfor i in std.range(0, 100, 1) do\n\tsum = sum + i / (50 - i)\nend",
+		"let f = function (a, b) [ a, b ] end\nlet r = [ f(...[ 1 ], 2), f(...[ ], ...[ 3, 4 ]) ]\nr",
+		"let f = function (a) a end\nf(...[ 1 ], ...2)",
+		"let x = -7\nlet y = 2\nlet r = [ x // y, y ** 10, x // 2.0, y ** -1, -y ** 2 ]\nr",
+		"let x = 2\nx ** 63",
+		"let x = 0\n1 // x",
+	];
+
+	let run = |interpreter, source: &str, fuel| {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+		runtime.set_fuel(fuel);
+
+		let result = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source");
+
+		let shown = match result {
+			Ok(value) => format!("{}", fmt::Show(value, runtime.interner())),
+			Err(panic) => format!("{}", fmt::Show(panic, runtime.interner())),
+		};
+
+		(shown, runtime.fuel())
+	};
+
+	// Same values, same panics at the same positions, and same fuel consumption.
+	for source in sources.iter() {
+		for &fuel in &[None, Some(10), Some(35)] {
+			assert_eq!(
+				run(Interpreter::TreeWalker, source, fuel),
+				run(Interpreter::Vm, source, fuel),
+				"source: {}",
+				source,
+			);
+		}
+	}
+}
+
+
+/// A fake clock that advances a fixed step every time it is read.
+#[derive(Debug)]
+struct FakeClock {
+	ticks: Cell<u32>,
+	step: Duration,
+}
+
+
+impl Clock for FakeClock {
+	fn now(&self) -> SystemTime {
+		UNIX_EPOCH + Duration::from_millis(1_500)
+	}
+
+
+	fn monotonic(&self) -> Duration {
+		let ticks = self.ticks.get();
+		self.ticks.set(ticks + 1);
+		self.step * ticks
+	}
+}
+
+
+#[test]
+#[serial]
+fn test_fake_clock() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	runtime.set_clock(
+		FakeClock {
+			ticks: Cell::new(0),
+			step: Duration::from_millis(250),
+		}
+	);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			std.assert(std.time() == 1.5)
+			std.assert(std.time_millis() == 1500)
+
+			let start = std.clock()
+			let finish = std.clock()
+			finish - start
+		"#
+	);
+
+	assert_eq!(value, Value::from(0.25));
+}
+
+
+#[test]
+#[serial]
+fn test_sleep() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let start = Instant::now();
+	eval_str(&mut runtime, "std.sleep(0.05)");
+
+	assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+
+#[test]
+#[serial]
+fn test_math_functions() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let cases: &[(&str, f64)] = &[
+		("std.math.sin(1)", 1f64.sin()),
+		("std.math.cos(0.5)", 0.5f64.cos()),
+		("std.math.tan(2)", 2f64.tan()),
+		("std.math.asin(0.5)", 0.5f64.asin()),
+		("std.math.acos(0.25)", 0.25f64.acos()),
+		("std.math.atan(3)", 3f64.atan()),
+		("std.math.atan2(1, -1)", 1f64.atan2(-1.0)),
+		("std.math.exp(1.5)", 1.5f64.exp()),
+		("std.math.ln(10)", 10f64.ln()),
+		("std.math.log2(1024)", 1024f64.log2()),
+		("std.math.log10(0.001)", 0.001f64.log10()),
+		("std.math.pi", std::f64::consts::PI),
+		("std.math.e", std::f64::consts::E),
+		("std.math.inf", f64::INFINITY),
+	];
+
+	for (source, expected) in cases {
+		assert_eq!(eval_str(&mut runtime, source), Value::from(*expected), "{}", source);
+	}
+
+	match eval_str(&mut runtime, "std.math.nan") {
+		Value::Float(ref float) => assert!(float.is_nan()),
+		other => panic!("expected float, got {:?}", other),
+	}
+}
+
+
+/// The equality operator, dict keys, std.eq, std.cmp and std.sort all follow the same
+/// comparison policy, for every pair of values.
+#[test]
+fn test_comparison_policy() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let sources = [
+		"nil", "false", "true", "'a'", "'b'", "\"\"", "\"a\"", "[ ]", "[ 1 ]", "[ 1.0 ]",
+		"0", "1", "-1", "9223372036854775807", "-9223372036854775807 - 1",
+		// Ints around 2^53, where not every int is a float.
+		"9007199254740992", "9007199254740993", "9007199254740994",
+		"0.0", "-0.0", "0.5", "1.0", "-1.0",
+		"9007199254740992.0", "9007199254740994.0",
+		"9223372036854775808.0", "-9223372036854775808.0",
+		"std.math.inf", "-std.math.inf", "std.math.nan", "0.0 / 0.0",
+	];
+
+	let values: Vec<Value> = sources
+		.iter()
+		.map(|source| eval_str(&mut runtime, source))
+		.collect();
+
+	let hash = |value: &Value| {
+		let mut hasher = DefaultHasher::new();
+		value.hash(&mut hasher);
+		hasher.finish()
+	};
+
+	let ordering = |left: &Value, right: &Value| left
+		.total_cmp(right)
+		.expect("value should be ordered");
+
+	for (left_source, left) in sources.iter().zip(&values) {
+		for (right_source, right) in sources.iter().zip(&values) {
+			let pair = format!("{} and {}", left_source, right_source);
+			let equal = left.key_eq(right);
+			let nan = matches!(left, Value::Float(float) if float.is_nan());
+
+			assert_eq!(equal, ordering(left, right) == Ordering::Equal, "{}", pair);
+			assert_eq!(ordering(left, right), ordering(right, left).reverse(), "{}", pair);
+			if equal {
+				assert_eq!(hash(left), hash(right), "{}", pair);
+			}
+
+			let results = eval_str(
+				&mut runtime,
+				&format!(
+					"let a = {}\nlet b = {}\nlet dict = @[ ]\ndict[a] = true\n\
+					let results = [ a == b, a != b, std.eq(a, b), std.contains(dict, b), std.cmp(a, b) ]\n\
+					results",
+					left_source,
+					right_source,
+				),
+			);
+
+			let expected: Value = vec![
+				Value::Bool(equal && !nan),
+				Value::Bool(!equal || nan),
+				Value::Bool(equal),
+				Value::Bool(equal),
+				Value::Int(ordering(left, right) as i64),
+			].into();
+
+			assert_eq!(results, expected, "{}", pair);
+		}
+	}
+
+	for left in &values {
+		for middle in &values {
+			for right in &values {
+				if ordering(left, middle) != Ordering::Greater && ordering(middle, right) != Ordering::Greater {
+					assert_ne!(ordering(left, right), Ordering::Greater, "{:?} {:?} {:?}", left, middle, right);
+				}
+			}
+		}
+	}
+
+	// Sorting agrees with the order, whatever the initial order.
+	let reversed: Vec<&str> = sources.iter().rev().copied().collect();
+	for sources in [ &sources[..], &reversed ] {
+		let sorted = eval_str(
+			&mut runtime,
+			&format!("let array = [ {} ]\nstd.sort(array)\narray", sources.join(", ")),
+		);
+		let sorted = match sorted {
+			Value::Array(ref array) => array.borrow().iter().map(Value::copy).collect::<Vec<_>>(),
+			other => panic!("expected array, got {:?}", other),
+		};
+
+		assert_eq!(sorted.len(), values.len());
+		for pair in sorted.windows(2) {
+			assert_ne!(ordering(&pair[0], &pair[1]), Ordering::Greater, "{:?}", pair);
+		}
+	}
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_cwd_is_tracked() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let process_cwd = std::env::current_dir().expect("failed to get working directory");
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			std.cd("/")
+			{ cd tmp }
+			std.trim(${ pwd }.stdout)
+		"#
+	);
+
+	assert_eq!(value, Value::from("/tmp"));
+	assert_eq!(runtime.cwd().ok(), Some(std::path::PathBuf::from("/tmp")));
+	assert_eq!(std::env::current_dir().ok(), Some(process_cwd));
+}
+
+
+#[test]
+#[serial]
+fn test_path_separator() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let expected = format!("a{}b", std::path::MAIN_SEPARATOR);
+
+	assert_eq!(eval_str(&mut runtime, r#"std.path.join("a", "b")"#), Value::from(expected.as_str()));
+	assert_eq!(
+		eval_str(&mut runtime, r#"std.path.split(std.path.join("a", "b"))"#),
+		Value::from(vec![ Value::from("a"), Value::from("b") ])
+	);
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_temp_dir_cleanup() {
+	use std::ffi::OsStr;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	// The returned value is still alive, but evaluation is over.
+	let dir = eval_str(&mut runtime, r#"std.fs.tempdir("hush-test-")"#);
+	let path = eval_str(&mut runtime, r#"std.fs.tempdir("hush-test-").path"#);
+
+	match (&dir, &path) {
+		(Value::Dict(dict), Value::String(path)) => {
+			let dir_path = dict.get(&"path".into()).expect("missing path");
+			match dir_path {
+				Value::String(ref dir_path) => assert!(!Path::new(AsRef::<OsStr>::as_ref(dir_path)).exists()),
+				other => panic!("expected string, got {:?}", other),
+			}
+
+			assert!(!Path::new(AsRef::<OsStr>::as_ref(path)).exists());
+		},
+		other => panic!("unexpected values: {:?}", other),
+	}
+
+	let kept = eval_str(
+		&mut runtime,
+		r#"
+			let dir = std.fs.tempdir("hush-test-")
+			dir.keep()
+			dir.path
+		"#
+	);
+
+	match kept {
+		Value::String(ref kept) => {
+			let kept = Path::new(AsRef::<OsStr>::as_ref(kept));
+			assert!(kept.is_dir());
+			std::fs::remove_dir(kept).expect("failed to remove kept directory");
+		},
+		other => panic!("expected string, got {:?}", other),
+	}
+}
+
+
+/// Whether the current process has the given file open.
+fn is_open(path: &Path) -> bool {
+	std::fs::read_dir("/proc/self/fd")
+		.expect("failed to list file descriptors")
+		.filter_map(Result::ok)
+		.any(|fd| std::fs::read_link(fd.path()).map(|link| link == path).unwrap_or(false))
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_lines_closed_on_break() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let path = std::env::temp_dir().join(format!("hush-test-lines-{}", std::process::id()));
+	std::fs::write(&path, "one\ntwo\nthree\n").expect("failed to write file");
+
+	let source = format!(
+		r#"
+			let iter = std.fs.lines("{}")
+			for line in iter do
+				break
+			end
+			iter
+		"#,
+		path.display()
+	);
+
+	// The iterator is still referenced, so the file must have been closed by the loop.
+	let iter = eval_str(&mut runtime, &source);
+	assert!(!is_open(&path));
+
+	std::fs::remove_file(&path).expect("failed to remove file");
+	drop(iter);
+}
+
+
+/// Evaluate the given source in a forked process, returning its exit code.
+#[cfg(feature = "system")]
+fn eval_forked(source: &str) -> i32 {
+	eval_forked_with(source, || ())
+}
+
+
+/// Like eval_forked, but run the given setup in the child before evaluating.
+#[cfg(feature = "system")]
+fn eval_forked_with<F: FnOnce()>(source: &str, setup: F) -> i32 {
+	// The child must not return into the test harness, so it always exits.
+	match unsafe { libc::fork() } {
+		-1 => panic!("fork failed: {}", io::Error::last_os_error()),
+
+		0 => {
+			setup();
+
+			let interner = symbol::Interner::new();
+			let args = std::iter::empty::<&str>();
+			let mut runtime = Runtime::new(args, interner);
+
+			let code = match eval(&mut runtime, Path::new("<test>"), source.as_bytes()) {
+				Ok(Ok(_)) => 100,
+				_ => 101,
+			};
+
+			unsafe { libc::_exit(code) }
+		},
+
+		pid => {
+			let mut status = 0;
+			assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+			assert!(libc::WIFEXITED(status));
+			libc::WEXITSTATUS(status)
+		},
+	}
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_process_exec() {
+	assert_eq!(eval_forked(r#"std.process.exec([ "true" ])"#), 0);
+	assert_eq!(eval_forked(r#"std.process.exec([ "false" ])"#), 1);
+	assert_eq!(eval_forked(r#"std.process.exec([ "sh", "-c", "exit \$CODE" ], @[ CODE: "7" ])"#), 7);
+
+	// Failure returns to the script.
+	assert_eq!(eval_forked(r#"std.process.exec([ "/this/program/does/not/exist" ])"#), 100);
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_signal_trap() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	eval_str(
+		&mut runtime,
+		r#"
+			let count = 0
+			std.signal.trap(
+				"USR2",
+				function(name)
+					count = count + 1
+					if count == 2 then
+						std.panic(name)
+					end
+				end
+			)
+		"#
+	);
+
+	assert_eq!(unsafe { libc::raise(libc::SIGUSR2) }, 0);
+
+	// The handler runs before the first statement, and execution continues.
+	assert_eq!(eval_str(&mut runtime, "let x = 1\nx + 1"), Value::Int(2));
+
+	// Panics in handlers propagate.
+	assert_eq!(unsafe { libc::raise(libc::SIGUSR2) }, 0);
+
+	let result = eval(&mut runtime, Path::new("<test>"), "let x = 1\nx".as_bytes())
+		.expect("failed to read source");
+
+	match result.as_ref().map_err(Panic::untraced) {
+		Err(Panic::User { context, .. }) => assert_eq!(context, &Value::from("USR2")),
+		other => panic!("expected user panic, got {:?}", other),
+	}
+
+	eval_str(&mut runtime, r#"std.signal.default("USR2")"#);
+}
+
+
+#[test]
+#[serial]
+fn test_os_info() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	assert_eq!(eval_str(&mut runtime, "std.os.pid()"), Value::Int(std::process::id().into()));
+
+	if let Some(home) = std::env::var_os("HOME").filter(|home| !home.is_empty()) {
+		assert_eq!(eval_str(&mut runtime, "std.os.home()"), Value::from(home));
+	}
+
+	for function in &[ "pid", "ppid", "hostname", "username", "home" ] {
+		let source = format!(r#"std.type(std.os.{})"#, function);
+		assert_eq!(eval_str(&mut runtime, &source), Value::from("function"), "{}", function);
+	}
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_os_platform() {
+	use std::os::unix::io::AsRawFd;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	assert_eq!(eval_str(&mut runtime, "std.os.name"), Value::from(std::env::consts::OS));
+	assert_eq!(eval_str(&mut runtime, "std.os.family"), Value::from(std::env::consts::FAMILY));
+	assert_eq!(eval_str(&mut runtime, "std.os.arch"), Value::from(std::env::consts::ARCH));
+	assert_eq!(
+		eval_str(&mut runtime, "std.os.path_sep"),
+		Value::from(std::path::MAIN_SEPARATOR.to_string().as_str())
+	);
+	assert_eq!(
+		eval_str(&mut runtime, "std.os.line_sep"),
+		Value::from(if cfg!(windows) { "\r\n" } else { "\n" })
+	);
+
+	// The test harness may or may not run attached to a terminal.
+	for (stream, fd) in &[ ("stdin", 0), ("stdout", 1), ("stderr", 2) ] {
+		let source = format!(r#"std.os.is_tty("{}")"#, stream);
+		let expected = unsafe { libc::isatty(*fd) } == 1;
+		assert_eq!(eval_str(&mut runtime, &source), Value::Bool(expected), "{}", stream);
+	}
+
+	// Piped stdio is never a terminal.
+	let (reader, writer) = os_pipe::pipe().expect("failed to create pipe");
+	assert_eq!(
+		eval_forked_with(
+			r#"std.assert(not std.os.is_tty("stdout"))"#,
+			|| assert_eq!(unsafe { libc::dup2(writer.as_raw_fd(), libc::STDOUT_FILENO) }, libc::STDOUT_FILENO),
+		),
+		100
+	);
+	drop(reader);
+}
+
+
+#[test]
+#[serial]
+fn test_test_runner() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	eval_str(
+		&mut runtime,
+		r#"
+			std.test.case("passes", function () std.assert(true) end)
+			std.test.case("fails", function () std.assert(false) end)
+			std.test.case("also fails", function () std.panic("failure") end)
+		"#
+	);
+
+	let pos = SourcePos::file(runtime.interner_mut().get_or_intern("<test>"));
+	assert_eq!(runtime.run_tests(pos.copy()).expect("runner panicked"), 2);
+	assert_eq!(runtime.run_tests(pos).expect("runner panicked"), 0);
+}
+
+
+#[test]
+#[serial]
+fn test_panic_snippet() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let result = eval(
+		&mut runtime,
+		Path::new("<test>"),
+		"let array = [ 1, 2 ]\nif true then\n\tstd.print(array[5])\nend\n".as_bytes(),
+	)
+	.expect("failed to read source");
+
+	match result {
+		Err(panic) => assert_eq!(
+			fmt::Show(panic, runtime.interner()).to_string(),
+			"Panic in <test>:3:18: index (5) out of bounds\n\
+			 3 |     std.print(array[5])\n  \
+			   |                     ^",
+		),
+		Ok(value) => panic!("expected panic, got {}", fmt::Show(value, runtime.interner())),
+	}
+}
+
+
+#[test]
+#[serial]
+fn test_panic_traceback() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let result = eval(
+		&mut runtime,
+		Path::new("<test>"),
+		"let inner = function(x)\n\
+		 \tx + 1\n\
+		 end\n\
+		 let middle = function(x)\n\
+		 \tinner(x)\n\
+		 end\n\
+		 let outer = function(x)\n\
+		 \tmiddle(x)\n\
+		 end\n\
+		 outer(\"a\")\n"
+			.as_bytes(),
 	)
+	.expect("failed to read source");
+
+	match result {
+		Err(panic) => assert_eq!(
+			fmt::Show(panic, runtime.interner()).to_string(),
+			"Panic in <test>:2:2: invalid operand in (\"a\" + 1): \
+			 value (\"a\") has unexpected type, expected int or float\n\
+			 2 |     x + 1\n  \
+			   |     ^\n\
+			 traceback (most recent call last):\n  \
+			   <test>:10:6: in function<<test>:7:13>\n  \
+			   <test>:8:8: in function<<test>:4:14>\n  \
+			   <test>:5:7: in function<<test>:1:13>",
+		),
+		Ok(value) => panic!("expected panic, got {}", fmt::Show(value, runtime.interner())),
+	}
+
+	// The call stack is unwound after the panic.
+	assert!(runtime.calls.is_empty());
+}
+
+
+#[test]
+#[serial]
+fn test_panic_traceback_truncated() {
+	tests::util::on_large_stack(
+		|| {
+			let interner = symbol::Interner::new();
+			let args = std::iter::empty::<&str>();
+			let mut runtime = Runtime::new(args, interner);
+			runtime.set_max_call_depth(tests::util::LARGE_STACK_CALL_DEPTH);
+
+			// Mutual recursion alternates between calls, which can't be collapsed.
+			let result = eval(
+				&mut runtime,
+				Path::new("<test>"),
+				"let f = nil
+				 let g = function(n) f(n - 1) end
+				 f = function(n)
+				 	if n == 0 then
+				 		std.panic(\"bottom\")
+				 	else
+				 		g(n)
+				 	end
+				 end
+				 f(25)
+"
+					.as_bytes(),
+			)
+			.expect("failed to read source");
+
+			let traceback = match result {
+				Err(Panic::Traced { traceback, .. }) => traceback,
+				other => panic!("expected traced panic, got {:?}", other),
+			};
+
+			// 26 calls to f, 25 calls to g, and the call to std.panic.
+			let frames: Vec<_> = traceback.frames().collect();
+			assert_eq!(frames.len(), 2 * Traceback::MAX_FRAMES);
+			assert_eq!(traceback.omitted(), 52 - 2 * Traceback::MAX_FRAMES);
+			assert!(traceback.calls().all(|(_, count)| count == 1));
+
+			assert_eq!(frames[0].pos.line, 10);
+			assert!(frames[1 ..].iter().rev().skip(1).all(|frame| frame.pos.line == 2 || frame.pos.line == 7));
+
+			let last = frames.last().expect("empty traceback");
+			assert_eq!(last.pos.line, 5);
+			assert_eq!(
+				fmt::Show(&last.function, runtime.interner()).to_string(),
+				"std.panic",
+			);
+		}
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_panic_traceback_recursion() {
+	tests::util::on_large_stack(
+		|| {
+			use crate::term::color;
+
+			let interner = symbol::Interner::new();
+			let args = std::iter::empty::<&str>();
+			let mut runtime = Runtime::new(args, interner);
+			runtime.set_max_call_depth(tests::util::LARGE_STACK_CALL_DEPTH);
+
+			let panic = eval(
+				&mut runtime,
+				Path::new("<test>"),
+				"let f = function(n)
+				 	if n == 0 then
+				 		std.panic(\"bottom\")
+				 	else
+				 		f(n - 1)
+				 	end
+				 end
+				 f(500)
+"
+					.as_bytes(),
+			)
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+			// Recursive calls are collapsed, rather than truncated.
+			let traceback = panic.traceback().expect("missing traceback");
+			let calls: Vec<(u32, usize)> = traceback
+				.calls()
+				.map(|(frame, count)| (frame.pos.line, count))
+				.collect();
+			assert_eq!(calls, [ (8, 1), (5, 500), (3, 1) ]);
+			assert_eq!(traceback.omitted(), 0);
+
+			color::set_mode(color::Mode::Never);
+
+			let message = fmt::Show(&panic, runtime.interner()).to_string();
+			let lines: Vec<&str> = message
+				.lines()
+				.skip_while(|line| !line.starts_with("traceback"))
+				.collect();
+
+			assert_eq!(
+				lines,
+				[
+					"traceback (most recent call last):",
+					"  <test>:8:7: in function<<test>:1:9>",
+					"  <test>:5:9: in function<<test>:1:9>",
+					"  ... repeated 499 more times",
+					"  <test>:3:17: in std.panic",
+				],
+			);
+		}
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_panic_kinds() {
+	use super::PanicKind;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let mut check = |source: &str, kind: PanicKind, line: u32, column: u32, value: Option<Value>| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		assert_eq!(panic.kind(), kind, "{}", source);
+		assert_eq!((panic.pos().line, panic.pos().column), (line, column), "{}", source);
+		assert_eq!(panic.value(), value.as_ref(), "{}", source);
+	};
+
+	check("let array = [ 1, 2 ]\narray[5]", PanicKind::IndexOutOfBounds, 2, 7, Some(Value::Int(5)));
+	check("let x = 0\n1 / x", PanicKind::DivisionByZero, 2, 3, None);
+	check("9223372036854775807 + 1", PanicKind::IntegerOverflow, 1, 21, None);
+	check("if 1 then end", PanicKind::InvalidCondition, 1, 4, Some(Value::Int(1)));
+	check("let f = 1\nf()", PanicKind::InvalidCall, 2, 1, Some(Value::Int(1)));
+	check("-\"a\"", PanicKind::InvalidOperand, 1, 2, Some(Value::from("a")));
+	check("1 <\n\"a\"", PanicKind::InvalidOperand, 2, 1, Some(Value::from("a")));
+	check("let x = nil\nx >= 1", PanicKind::InvalidOperand, 2, 1, Some(Value::Nil));
+	check("1.5 > [ 1 ]", PanicKind::InvalidOperand, 1, 7, Some(Value::from(vec![ Value::Int(1) ])));
+	check("let a = [ 1 ]\na ++ \"b\"", PanicKind::InvalidOperand, 2, 6, Some(Value::from("b")));
+	check("\"a\" ++ [ 1 ]", PanicKind::InvalidOperand, 1, 8, Some(Value::from(vec![ Value::Int(1) ])));
+	check("let s = \"a\"\ns ++ nil", PanicKind::InvalidOperand, 2, 6, Some(Value::Nil));
+	check("let s = \"a\"\nnil ++ s", PanicKind::InvalidOperand, 2, 1, Some(Value::Nil));
+	check("let b = 'a'\nb ++ b", PanicKind::InvalidOperand, 2, 6, Some(Value::Byte(b'a')));
+	check("std.pop([])", PanicKind::EmptyCollection, 1, 8, None);
+
+	// Panics inside function calls keep their kind, position and value, and carry the
+	// call stack.
+	let panic = eval(
+		&mut runtime,
+		Path::new("<test>"),
+		"let f = function(x)\n\tstd.panic(x)\nend\nf(42)".as_bytes(),
+	)
+	.expect("failed to read source")
+	.expect_err("expected panic");
+
+	assert_eq!(panic.kind(), PanicKind::User);
+	assert_eq!(panic.pos().line, 2);
+	assert_eq!(panic.value(), Some(&Value::Int(42)));
+
+	let traceback = panic.traceback().expect("missing traceback");
+	let lines: Vec<u32> = traceback.frames().map(|frame| frame.pos.line).collect();
+	assert_eq!(lines, [ 4, 2 ]);
+
+	// Panics are standard errors, displayed without needing an interner.
+	let error: Box<dyn std::error::Error> = Box::new(panic);
+	assert!(error.to_string().contains("std.panic(42)"));
+}
+
+
+#[test]
+#[serial]
+fn test_panic_colors() {
+	use crate::term::color;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let panic = eval(
+		&mut runtime,
+		Path::new("<test>"),
+		"let f = function(x)\n\tx + 1\nend\nf(\"a\")".as_bytes(),
+	)
+	.expect("failed to read source")
+	.expect_err("expected panic");
+
+	// Colors are disabled unless enabled by the host.
+	let default = fmt::Show(&panic, runtime.interner()).to_string();
+
+	color::set_mode(color::Mode::Never);
+	let plain = fmt::Show(&panic, runtime.interner()).to_string();
+
+	color::set_mode(color::Mode::Always);
+	let colored = fmt::Show(&panic, runtime.interner()).to_string();
+
+	// Panic messages handed to scripts are never colored.
+	let caught = eval_str(&mut runtime, "std.catch(function() std.panic(1) end).description");
+
+	color::set_mode(color::Mode::Never);
+
+	assert_eq!(default, plain);
+	assert!(!plain.contains('\x1b'));
+	assert!(colored.contains('\x1b'));
+	assert_eq!(tests::util::strip_escapes(&colored), plain);
+
+	match &caught {
+		Value::String(description) => assert!(!description.as_bytes().contains(&b'\x1b')),
+		other => panic!("expected string, got {}", fmt::Show(other, runtime.interner())),
+	}
+}
+
+
+#[test]
+#[serial]
+fn test_invalid_operand_messages() {
+	use crate::term::color;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	color::set_mode(color::Mode::Never);
+
+	let mut message = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		let message = fmt::Show(&panic, runtime.interner()).to_string();
+		message.lines().next().unwrap_or_default().to_owned()
+	};
+
+	assert_eq!(
+		message("1 + \"a\""),
+		"Panic in <test>:1:5: invalid operand in (1 + \"a\"): \
+		 value (\"a\") has unexpected type, expected int",
+	);
+	assert_eq!(
+		message("\"a\" + 1"),
+		"Panic in <test>:1:1: invalid operand in (\"a\" + 1): \
+		 value (\"a\") has unexpected type, expected int or float",
+	);
+	assert_eq!(
+		message("-\"x\""),
+		"Panic in <test>:1:2: invalid operand in (-\"x\"): \
+		 value (\"x\") has unexpected type, expected int or float",
+	);
+	assert_eq!(
+		message("true < false"),
+		"Panic in <test>:1:1: invalid operand in (true < false): \
+		 value (true) has unexpected type, expected int, float, byte or string",
+	);
+
+	// The right operand of a short-circuiting operator is not evaluated.
+	assert_eq!(
+		message("1 and true"),
+		"Panic in <test>:1:1: invalid operand in (1 and ...): \
+		 value (1) has unexpected type, expected bool",
+	);
+
+	// Long values are truncated.
+	assert_eq!(
+		message("let s = \"0123456789012345678901234567890123456789\"\n1 + s"),
+		"Panic in <test>:2:5: invalid operand in (1 + \"012345678901234567890123456789012345...): \
+		 value (\"012345678901234567890123456789012345...) has unexpected type, expected int",
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_invalid_condition_messages() {
+	use crate::term::color;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	color::set_mode(color::Mode::Never);
+
+	let mut message = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		let message = fmt::Show(&panic, runtime.interner()).to_string();
+		message.lines().next().unwrap_or_default().to_owned()
+	};
+
+	assert_eq!(
+		message("let x = nil\nif x then end"),
+		"Panic in <test>:2:4: condition (nil) has type nil, expected bool. \
+		 Hint: use `value != nil` to test for presence",
+	);
+	assert_eq!(
+		message("let x = 1\nwhile x do end"),
+		"Panic in <test>:2:7: condition (1) has type int, expected bool",
+	);
+	assert!(
+		message("if std.error(\"failed\", nil) then end")
+			.ends_with("Hint: command blocks evaluate to an error on failure, use `std.type(value) == \"error\"` to test for failure")
+	);
+
+	// Boolean conditions are unaffected.
+	assert_eq!(
+		eval_str(&mut runtime, "let x = 1\nif x == 1 then \"yes\" else \"no\" end"),
+		Value::from("yes"),
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_missing_key_messages() {
+	use crate::term::color;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	color::set_mode(color::Mode::Never);
+
+	let mut message = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		let message = fmt::Show(&panic, runtime.interner()).to_string();
+		message.lines().next().unwrap_or_default().to_owned()
+	};
+
+	assert_eq!(
+		message("let @[ prot ] = @[ host: \"localhost\", port: 80 ]"),
+		"Panic in <test>:1:8: missing key (\"prot\"), did you mean (\"port\")? \
+		 available keys: \"host\", \"port\"",
+	);
+	assert_eq!(
+		message("let @[ timeout ] = @[ host: \"localhost\", port: 80 ]"),
+		"Panic in <test>:1:8: missing key (\"timeout\"), available keys: \"host\", \"port\"",
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_assertion_messages() {
+	use crate::term::color;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	color::set_mode(color::Mode::Never);
+
+	let mut message = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		let message = fmt::Show(&panic, runtime.interner()).to_string();
+		message.lines().next().unwrap_or_default().to_owned()
+	};
+
+	assert_eq!(
+		message("let x = 5\nstd.assert(x > 10)"),
+		"Panic in <test>:2:11: assertion failed: x > 10",
+	);
+	assert_eq!(
+		message("let x = 5\nstd.assert(\n\tx > 1 and # comment\n\t\tx  <  3\n)"),
+		"Panic in <test>:2:11: assertion failed: x > 1 and x < 3",
+	);
+	assert_eq!(
+		message("std.assert(std.contains([ \"a, (b\" ], \"c  d\"))"),
+		"Panic in <test>:1:11: assertion failed: std.contains([ \"a, (b\" ], \"c  d\")",
+	);
+	assert_eq!(
+		message("let x = [ 1, 2 ]\nstd.assert_eq(\n\tstd.len(x),\n\t3\n)"),
+		"Panic in <test>:2:14: assertion failed: std.len(x) == 3, left: (2), right: (3)",
+	);
+}
+
+
+#[test]
+#[serial]
+fn test_panic_hook() {
+	use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let count = Arc::new(AtomicUsize::new(0));
+	let hook_count = count.clone();
+	runtime.set_panic_hook(
+		move |panic| {
+			assert_eq!(panic.value(), Some(&Value::from("uncaught")));
+			hook_count.fetch_add(1, Ordering::SeqCst);
+		}
+	);
+
+	// Caught panics don't escape the runtime.
+	eval_str(&mut runtime, r#"std.catch(function() std.panic("caught") end)"#);
+	assert_eq!(count.load(Ordering::SeqCst), 0);
+
+	// Uncaught panics are passed to the hook once, and still returned.
+	let result = eval(
+		&mut runtime,
+		Path::new("<test>"),
+		r#"let f = function() std.panic("uncaught") end
+		   f()"#.as_bytes(),
+	)
+	.expect("failed to read source");
+
+	assert!(matches!(result, Err(ref panic) if panic.value() == Some(&Value::from("uncaught"))));
+	assert_eq!(count.load(Ordering::SeqCst), 1);
+
+	// Failing test cases are not escaping panics.
+	eval_str(&mut runtime, r#"std.test.case("fails", function() std.panic("test") end)"#);
+	let pos = SourcePos::file(runtime.interner_mut().get_or_intern("<test>"));
+	assert_eq!(runtime.run_tests(pos).expect("runner panicked"), 1);
+	assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+
+#[test]
+#[serial]
+fn test_argument_positions() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let mut pos = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		assert!(matches!(panic.untraced(), Panic::TypeError { .. }), "{:?}", panic);
+		let pos = panic.pos();
+		(pos.line, pos.column)
+	};
+
+	assert_eq!(pos("std.len(5)"), (1, 9));
+	assert_eq!(pos("std.substr(\"abc\", 0,\n\t\"x\")"), (2, 2));
+	assert_eq!(pos("let x = 1\nstd.replace(\"abc\", x, \"b\")"), (2, 20));
+	assert_eq!(pos("std.math.max(1, \"2\")"), (1, 17));
+	assert_eq!(pos("std.math.clamp(1, 0, nil)"), (1, 22));
+	assert_eq!(pos("std.cmp(@[], 1)"), (1, 9));
+	assert_eq!(pos("std.cmp([ 1 ], [ @[] ])"), (1, 16));
+	assert_eq!(pos("std.path.join(\"a\", \"b\", 1)"), (1, 25));
+	assert_eq!(pos("std.date.format(@[ year: 2000, month: nil, day: 1 ], \"%Y\")"), (1, 17));
+
+	// Forwarded calls keep the argument positions.
+	assert_eq!(pos("let len = std.memo(std.len, 10)\nlen(  nil)"), (2, 7));
+
+	// Bound arguments have no position, so the call position is used.
+	assert_eq!(pos("let replace = std.bind(std.replace, \"a\")\nreplace(1, \"b\")"), (2, 8));
+}
+
+
+#[test]
+fn test_string_functions() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let mut run = |source: &str| {
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+
+		let pos = panic.pos();
+		(panic.kind(), panic.value().map(Value::copy), pos.line, pos.column)
+	};
+
+	use super::PanicKind::{IndexOutOfBounds, InvalidArgs, TypeError};
+
+	// Out of range indices point at the offending argument.
+	assert_eq!(run("std.substr(\"abc\", -1, 1)"), (IndexOutOfBounds, Some(Value::Int(-1)), 1, 19));
+	assert_eq!(run("std.substr(\"abc\", 4, 0)"), (IndexOutOfBounds, Some(Value::Int(4)), 1, 19));
+	assert_eq!(run("std.substr(\"abc\", 1, -1)"), (IndexOutOfBounds, Some(Value::Int(-1)), 1, 22));
+	assert_eq!(run("std.substr(\"abc\", 1,\n\t3)"), (IndexOutOfBounds, Some(Value::Int(3)), 2, 2));
+	assert_eq!(
+		run("std.substr(\"abc\", 1, 9223372036854775807)"),
+		(IndexOutOfBounds, Some(Value::Int(i64::MAX)), 1, 22),
+	);
+
+	// Mistyped arguments and wrong counts are panics, not crashes.
+	assert_eq!(run("std.to_uppercase(@[])").0, TypeError);
+	assert_eq!(run("std.to_lowercase(\"a\", \"b\")").0, InvalidArgs);
+	assert_eq!(run("std.split(@[], \",\")").0, TypeError);
+	assert_eq!(run("std.trim([])").0, TypeError);
+	assert_eq!(run("std.replace(\"abc\", \"a\")").0, InvalidArgs);
+	assert_eq!(run("std.contains(\"abc\", 1)").0, TypeError);
+	assert_eq!(run("std.len(1.5)").0, TypeError);
+
+	// Mistyped numbers point at the offending argument.
+	assert_eq!(run("std.math.pow(2, \"x\")"), (TypeError, Some(Value::from("x")), 1, 17));
+	assert_eq!(run("std.math.pow(nil, 2)"), (TypeError, Some(Value::Nil), 1, 14));
+}
+
+
+#[test]
+#[serial]
+fn test_imported_panic_snippet() {
+	use crate::term::color;
+
+	let mut dir = std::env::temp_dir();
+	dir.push(format!("hush-import-snippet-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+
+	let mut module = dir.clone();
+	module.push("module.hsh");
+	std::fs::write(&module, "function f()\n\tstd.len(5)\nend\n\n@[ f: f ]\n")
+		.expect("failed to write module");
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	color::set_mode(color::Mode::Never);
+
+	let source = format!("let module = std.import(\"{}\")\nmodule.f()", module.display());
+	let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+		.expect("failed to read source")
+		.expect_err("expected panic");
+
+	let message = fmt::Show(&panic, runtime.interner()).to_string();
+	let lines: Vec<&str> = message.lines().collect();
+	assert!(lines[0].starts_with(&format!("Panic in {}:2:10:", module.display())), "{}", message);
+	assert_eq!(lines[1], "2 |     std.len(5)");
+	assert_eq!(lines[2], "  |             ^");
+
+	// Imported modules are reported, even if they panicked.
+	let canonical = module.canonicalize().expect("failed to canonicalize module");
+	let imports: Vec<&[u8]> = runtime
+		.imports()
+		.filter_map(|path| runtime.interner().resolve(path))
+		.collect();
+	assert_eq!(imports, [ canonical.as_os_str().as_bytes() ]);
+
+	// Sources are retained when loaded, so the snippet survives the file being removed.
+	std::fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+	assert_eq!(fmt::Show(&panic, runtime.interner()).to_string(), message);
+
+	// Positions without a retained source are rendered without a snippet.
+	let path = runtime.interner_mut().get_or_intern("missing.hsh");
+	let pos = SourcePos { line: 2, column: 10, path };
+	assert_eq!(fmt::Show(pos.snippet(), runtime.interner()).to_string(), "");
+}
+
+
+#[test]
+#[serial]
+fn test_module_path() {
+	use super::PanicKind;
+
+	let mut dir = std::env::temp_dir();
+	dir.push(format!("hush-module-path-{}", std::process::id()));
+
+	let write = |path: &str, contents: &str| {
+		let path = dir.join(path);
+		std::fs::create_dir_all(path.parent().expect("missing parent"))
+			.expect("failed to create dir");
+		std::fs::write(path, contents).expect("failed to write module");
+	};
+
+	// The same module in two entries of the search path, where the first wins.
+	write("first/utils/strings.hsh", "\"first\"");
+	write("second/utils/strings.hsh", "\"second\"");
+	write("second/utils/other.hsh", "\"other\"");
+	write("main/local.hsh", "\"local\"");
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+	runtime.set_module_path(vec![ dir.join("first"), dir.join("second") ]);
+
+	let script = dir.join("main/main.hsh");
+	let source = "
+		std.assert(std.import(\"utils/strings\") == \"first\")
+		std.assert(std.import(\"utils/other.hsh\") == \"other\")
+		std.assert(std.import(\"local\") == \"local\")
+		std.assert(std.import(\"./local.hsh\") == \"local\")
+
+		# Optional modules, and explicitly relative names, which skip the search path.
+		std.assert(std.try_import(\"utils/missing\") == nil)
+		std.assert(std.try_import(\"./utils/strings\") == nil)
+
+		let missing = std.import_resolved(\"utils/missing\")
+		std.assert(std.type(missing) == \"error\")
+		std.assert(std.len(missing.context) == 6)
+
+		std.import_resolved(\"utils/strings\")
+	";
+
+	let resolved = eval(&mut runtime, &script, source.as_bytes())
+		.expect("failed to read source")
+		.expect("unexpected panic");
+
+	let expected = dir
+		.join("first/utils/strings.hsh")
+		.canonicalize()
+		.expect("failed to canonicalize module");
+	assert_eq!(resolved, Value::from(expected.as_os_str().as_bytes()));
+
+	// Panics list the searched paths.
+	let panic = eval(&mut runtime, &script, "std.import(\"utils/missing\")".as_bytes())
+		.expect("failed to read source")
+		.expect_err("expected panic");
+	assert_eq!(panic.kind(), PanicKind::ModuleNotFound);
+
+	let message = fmt::Show(&panic, runtime.interner()).to_string();
+	for path in [ "main/utils/missing", "first/utils/missing.hsh", "second/utils/missing.hsh" ] {
+		let path = dir.join(path);
+		assert!(message.contains(&path.display().to_string()), "{}", message);
+	}
+
+	std::fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+}
+
+
+
+#[test]
+#[serial]
+fn test_import_failures() {
+	use super::PanicKind;
+	use crate::term::color;
+
+	let mut dir = std::env::temp_dir();
+	dir.push(format!("hush-import-failures-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+
+	let write = |name: &str, contents: &str| {
+		std::fs::write(dir.join(name), contents).expect("failed to write module");
+	};
+
+	write("counter.hsh", "@[ loads: 1 ]");
+	write("a.hsh", "std.import(\"./b\")");
+	write("b.hsh", "std.import(\"./a\")");
+	write("syntax.hsh", "let x = 1\nlet y = )\nlet z = (\n");
+	write("semantic.hsh", "let x = 1\nundeclared\n");
+
+	color::set_mode(color::Mode::Never);
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let script = dir.join("main.hsh");
+	let mut run = |source: &str| {
+		let panic = eval(&mut runtime, &script, source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+		let message = fmt::Show(&panic, runtime.interner()).to_string();
+
+		(panic.kind(), message)
+	};
+
+	// Modules are evaluated once, and later imports share the same value.
+	let (_, message) = run(
+		"let counter = std.import(\"./counter\")\n\
+		 counter.loads = counter.loads + 1\n\
+		 std.assert(std.import(\"./counter.hsh\").loads == 2)\n\
+		 std.panic(nil)"
+	);
+	assert!(message.contains("std.panic(nil)"), "{}", message);
+
+	let (kind, message) = run("\n\tstd.import(\"./a\")");
+	assert_eq!(kind, PanicKind::ImportCycle);
+	let cycle = [ "a.hsh", "b.hsh", "a.hsh" ]
+		.map(|name| dir.join(name).canonicalize().expect("failed to canonicalize").display().to_string())
+		.join(" -> ");
+	assert!(message.contains(&format!("import cycle: {}", cycle)), "{}", message);
+
+	// Errors are reported at the import, with the position of the first error.
+	let (kind, message) = run("\n\tstd.import(\"./syntax\")");
+	assert_eq!(kind, PanicKind::ImportFailed);
+	assert!(message.starts_with(&format!("Panic in {}:2:12:", script.display())), "{}", message);
+	assert!(
+		message.contains("syntax.hsh:2:9 - unexpected ')', expected expression (and 1 more error)"),
+		"{}",
+		message
+	);
+
+	let (kind, message) = run("std.import(\"./semantic\")");
+	assert_eq!(kind, PanicKind::ImportFailed);
+	assert!(message.contains("semantic.hsh:2:1 - undeclared variable 'undeclared'"), "{}", message);
+
+	std::fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+}
+
+#[test]
+fn test_args_count() {
+	use super::args_count;
+
+	assert_eq!(args_count(0), 0);
+	assert_eq!(args_count(3), 3);
+	assert_eq!(args_count(u32::MAX as usize), u32::MAX);
+
+	// Counts that don't fit saturate, instead of wrapping into a count that may match.
+	assert_eq!(args_count(u32::MAX as usize + 4), u32::MAX);
+	assert_eq!(args_count(usize::MAX), u32::MAX);
+}
+
+
+#[test]
+fn test_register_fn() {
+	use std::rc::Rc;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let queries = Rc::new(Cell::new(0));
+	let counter = queries.clone();
+
+	runtime.register_fn(
+		"std.host.query",
+		1,
+		move |context| {
+			counter.set(counter.get() + 1);
+
+			match context.args() {
+				[ Value::String(ref table) ] => Ok(
+					format!("rows of {}", String::from_utf8_lossy(table.as_bytes())).into()
+				),
+				[ other ] => Err(Panic::type_error(other.copy(), "string", context.arg_pos(0))),
+				_ => unreachable!("arity is checked before calling"),
+			}
+		}
+	);
+
+	assert_eq!(
+		eval_str(&mut runtime, "std.host.query(\"users\") ++ \", \" ++ std.host.query(\"posts\")"),
+		Value::from("rows of users, rows of posts"),
+	);
+	assert_eq!(queries.get(), 2);
+
+	// Arity violations produce the standard panic, without reaching the function.
+	let panic = eval(&mut runtime, Path::new("<test>"), "std.host.query()".as_bytes())
+		.expect("failed to read source")
+		.expect_err("expected panic");
+	assert!(
+		matches!(panic.untraced(), Panic::InvalidArgs { supplied: 0, expected: 1, .. }),
+		"{:?}",
+		panic,
+	);
+	assert_eq!(queries.get(), 2);
+
+	// The stdlib is untouched.
+	assert_eq!(eval_str(&mut runtime, "std.len(\"abc\")"), Value::Int(3));
+}
+
+
+#[test]
+fn test_field_cache_invalidation() {
+	use super::value::Dict;
+
+	// Stamps are unique, shared by copies, and renewed by mutable borrows only.
+	let dict = Dict::default();
+	let other = Dict::default();
+	assert_ne!(dict.stamp(), other.stamp());
+	assert_eq!(dict.copy().stamp(), dict.stamp());
+
+	let stamp = dict.stamp();
+	let _ = dict.get(&"key".into());
+	assert_eq!(dict.stamp(), stamp);
+
+	dict.insert("key".into(), Value::Int(1));
+	assert_ne!(dict.stamp(), stamp);
+
+	let stamp = dict.stamp();
+
+	dict.borrow_mut().remove(&"key".into());
+	assert_ne!(dict.stamp(), stamp);
+
+	// Removals by the host are seen by cached accesses.
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+
+		runtime.register_fn(
+			"std.host.remove",
+			2,
+			|context| match context.args() {
+				[ Value::Dict(ref dict), key ] => {
+					dict.borrow_mut().remove(key);
+					Ok(Value::Nil)
+				}
+				[ other, _ ] => Err(Panic::type_error(other.copy(), "dict", context.arg_pos(0))),
+				_ => unreachable!("arity is checked before calling"),
+			}
+		);
+
+		let source = "let d = @[ a: 1, b: 2 ]
+let get = function () d.a end
+std.assert(get() + get() == 2)
+std.host.remove(d, \"a\")
+get()";
+
+		let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic");
+		assert!(
+			matches!(panic.untraced(), Panic::IndexOutOfBounds { .. }),
+			"{:?}: {:?}",
+			interpreter,
+			panic,
+		);
+	}
+}
+
+
+#[test]
+fn test_stack_bounds() {
+	use super::mem::{SlotIx, Stack};
+
+	let mut stack = Stack::new(10);
+
+	stack.extend(SlotIx(4)).expect("within bounds");
+	stack
+		.extend_with(SlotIx(6), vec![ Value::Int(1), Value::Int(2) ].into_iter())
+		.expect("within bounds");
+	assert_eq!(stack.len(), 10);
+	assert_eq!(stack.fetch(SlotIx(0)), Value::Int(1));
+	assert_eq!(stack.fetch(SlotIx(1)), Value::Int(2));
+	assert_eq!(stack.fetch(SlotIx(2)), Value::Nil);
+
+	// Overflows leave the stack untouched.
+	assert!(stack.extend(SlotIx(1)).is_err());
+	assert!(stack.extend_with(SlotIx(1), std::iter::empty()).is_err());
+	assert_eq!(stack.len(), 10);
+
+	stack.shrink(SlotIx(6));
+	stack.shrink(SlotIx(4));
+	assert!(stack.is_empty());
+	assert_eq!(stack.high_water_mark(), 10);
+
+	// Gigantic frames are rejected before allocating.
+	let mut stack = Stack::default();
+	assert!(stack.extend(SlotIx(u32::MAX)).is_err());
+	assert_eq!(stack.high_water_mark(), 0);
+
+	// The bound is enforced for scripts too.
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+	runtime.stack = Stack::new(64);
+
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		runtime.set_interpreter(interpreter);
+
+		let panic = eval(
+			&mut runtime,
+			Path::new("<test>"),
+			"let f = function (n) 1 + f(n + 1) end\nf(0)".as_bytes(),
+		)
+			.expect("failed to read source")
+			.expect_err("expected panic");
+		assert!(
+			matches!(panic.untraced(), Panic::StackOverflow { .. }),
+			"{:?}: {:?}",
+			interpreter,
+			panic,
+		);
+
+		// Panics unwind every frame, including the root.
+		assert!(runtime.stack.is_empty());
+		assert!(runtime.stack.high_water_mark() <= 64);
+	}
+}
+
+
+#[test]
+fn test_call_depth() {
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+		runtime.set_max_call_depth(20);
+
+		// Both tail and non-tail recursion are bounded.
+		for source in &[
+			"let f = function (n) return f(n + 1) end\nf(0)",
+			"let f = function (n) 1 + f(n + 1) end\nf(0)",
+		] {
+			let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+				.expect("failed to read source")
+				.expect_err("expected panic");
+			assert!(
+				matches!(panic.untraced(), Panic::StackOverflow { .. }),
+				"{:?}: {:?}",
+				interpreter,
+				panic,
+			);
+			assert_eq!(panic.pos().line, 1, "{:?}", interpreter);
+		}
+
+		// Recursion within the limit is unaffected, and the runtime remains usable.
+		let value = eval_str(
+			&mut runtime,
+			"let f = function (n) if n == 0 then 0 else 1 + f(n - 1) end end\nf(15)",
+		);
+		assert_eq!(value, Value::Int(15), "{:?}", interpreter);
+	}
+}
+
+
+#[test]
+fn test_string_append() {
+	use super::value::Str;
+
+	// Strings without other copies are appended to in place.
+	let mut string = Str::from("abc");
+	let address = string.address();
+	string.append(&"def".into());
+	assert_eq!(string.as_bytes(), b"abcdef");
+	assert_eq!(string.address(), address);
+
+	// Shared strings are copied, leaving the other copies untouched.
+	let copy = string.copy();
+	string.append(&"!".into());
+	assert_eq!(string.as_bytes(), b"abcdef!");
+	assert_eq!(copy.as_bytes(), b"abcdef");
+	assert_ne!(string.address(), copy.address());
+
+	// Appending in a loop takes linear time. Copying the string on every iteration would
+	// take minutes.
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+
+		let start = Instant::now();
+		let value = eval_str(
+			&mut runtime,
+			"let text = \"\"\n\
+			 for i in std.range(0, 100000, 1) do\n\
+			 	text = text ++ \"ab\"\n\
+			 end\n\
+			 std.len(text)",
+		);
+
+		assert_eq!(value, Value::Int(200_000), "{:?}", interpreter);
+		assert!(start.elapsed() < Duration::from_secs(10), "{:?}: {:?}", interpreter, start.elapsed());
+	}
+}
+
+
+#[test]
+fn test_protected_call() {
+	use super::mem::Stack;
+
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+
+		// The panic is raised three frames deep, while the arguments of each call are being
+		// evaluated.
+		let value = eval_str(
+			&mut runtime,
+			r#"let inner = function (a, b) [ 1, 2, 3 ][a + b] end
+			let middle = function (a) std.len([ a, inner(a, a) ]) end
+			let outer = function (a) middle(a) + middle(a + 1) end
+
+			let result = std.pcall(function () outer(1) end)
+			std.assert(not result.ok)
+
+			let total = 0
+			for i in std.range(0, 3, 1) do
+				std.pcall(function () outer(i + 1) end)
+				total = total + outer(0)
+			end
+			[ result.error, total, std.pcall(function () outer(0) end).value ]"#,
+		);
+
+		match value {
+			Value::Array(ref array) => {
+				let array = array.borrow();
+				match &array[0] {
+					Value::String(error) => assert!(
+						String::from_utf8_lossy(error.as_bytes()).contains("index (4) out of bounds"),
+						"{:?}: {}",
+						interpreter,
+						fmt::Show(&value, runtime.interner()),
+					),
+					other => panic!("expected string, got {}", fmt::Show(other, runtime.interner())),
+				}
+				assert_eq!(array[1], Value::Int(12), "{:?}", interpreter);
+				assert_eq!(array[2], Value::Int(4), "{:?}", interpreter);
+			}
+			other => panic!("expected array, got {}", fmt::Show(other, runtime.interner())),
+		}
+
+		// Nothing is left behind by the caught panic.
+		assert!(runtime.arguments.is_empty(), "{:?}", interpreter);
+		assert!(runtime.calls.is_empty(), "{:?}", interpreter);
+
+		// Stack overflows can't be caught, neither with std.pcall nor with std.catch.
+		runtime.stack = Stack::new(64);
+
+		for catch in [ "std.pcall", "std.catch" ] {
+			let source = format!(
+				"let f = function (n) 1 + f(n + 1) end\n{}(function () f(0) end)\nnil",
+				catch,
+			);
+
+			let panic = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+				.expect("failed to read source")
+				.expect_err("expected panic");
+			assert!(
+				matches!(panic.untraced(), Panic::StackOverflow { .. }),
+				"{:?}: {:?}",
+				interpreter,
+				panic,
+			);
+
+			assert!(runtime.stack.is_empty());
+			assert!(runtime.arguments.is_empty());
+		}
+	}
+}
+
+
+/// Evaluate a malformed program, which breaks an invariant of the semantic analysis. Debug
+/// builds assert the invariant, while release builds result in an internal error.
+fn eval_malformed(mut runtime: Runtime, program: semantic::program::Program, context: &str) {
+	use std::panic::{catch_unwind, AssertUnwindSafe};
+
+	let interpreter = runtime.interpreter;
+
+	match catch_unwind(AssertUnwindSafe(|| runtime.eval(program))) {
+		#[cfg(not(debug_assertions))]
+		Ok(Err(panic)) => {
+			assert_eq!(panic.kind(), super::PanicKind::InternalError, "{:?}", interpreter);
+			assert!(panic.is_fatal());
+
+			let message = fmt::Show(&panic, runtime.interner()).to_string();
+			assert!(message.contains(context), "{:?}: {}", interpreter, message);
+			assert!(message.contains(env!("CARGO_PKG_VERSION")), "{:?}: {}", interpreter, message);
+		}
+
+		#[cfg(debug_assertions)]
+		Err(payload) => {
+			let message = payload
+				.downcast_ref::<String>()
+				.map(String::as_str)
+				.or_else(|| payload.downcast_ref::<&str>().copied())
+				.unwrap_or_default();
+			assert!(message.contains(context), "{:?}: {}", interpreter, message);
+		}
+
+		#[cfg(debug_assertions)]
+		Ok(Err(panic)) => panic!(
+			"{:?}: expected assertion failure, got {}",
+			interpreter,
+			fmt::Show(panic, runtime.interner())
+		),
+
+		Ok(Ok(value)) => panic!(
+			"{:?}: expected internal error, got {}",
+			interpreter,
+			fmt::Show(value, runtime.interner())
+		),
+
+		#[cfg(not(debug_assertions))]
+		Err(_) => panic!("{:?}: unexpected Rust panic", interpreter),
+	}
+}
+
+
+#[test]
+fn test_internal_error() {
+	use semantic::program::{Block, Expr, Literal, Lvalue, Statement};
+
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let compile_str = |source: &str| {
+			let interner = symbol::Interner::new();
+			let mut runtime = Runtime::new(std::iter::empty::<&str>(), interner);
+			runtime.set_interpreter(interpreter);
+
+			let program = compile(&mut runtime, Path::new("<test>"), source.as_bytes())
+				.expect("failed to read source");
+
+			(program, runtime)
+		};
+
+		// A break in the root block.
+		let (mut program, runtime) = compile_str("nil");
+		program.statements = Block(Box::new([ Statement::Break ]));
+		eval_malformed(runtime, program, "invalid flow in root state");
+
+		// A break in a function body, outside loops.
+		let (mut program, runtime) = compile_str("let f = function () nil end\nf()");
+		match &mut program.statements.0[0] {
+			Statement::Assign { right: Expr::Literal { literal: Literal::Function { body, .. }, .. }, .. } =>
+				*body = Block(Box::new([ Statement::Break ])),
+			other => panic!("unexpected statement: {:?}", other),
+		}
+		eval_malformed(runtime, program, "break outside loop");
+
+		// A dict destructuring with a key missing from the interner.
+		let (mut program, runtime) = compile_str("let @[ a ] = @[ a: 1 ]\na");
+		let mut interner = symbol::Interner::new();
+		let unknown = (0 ..= runtime.interner().len())
+			.map(|ix| interner.get_or_intern(ix.to_string()))
+			.last()
+			.expect("empty range");
+		match &mut program.statements.0[0] {
+			Statement::Assign { left: Lvalue::Dict { items, .. }, .. } => items[0].key = unknown,
+			other => panic!("unexpected statement: {:?}", other),
+		}
+		eval_malformed(runtime, program, "unresolved symbol");
+	}
+}
+
+
+#[test]
+fn test_value_conversions() {
+	use std::{collections::HashMap, convert::TryFrom};
+	use super::value::{ConversionError, Type};
+
+	// Scalars.
+	assert_eq!(i64::try_from(Value::from(42_i64)), Ok(42));
+	assert_eq!(f64::try_from(Value::from(1.5)), Ok(1.5));
+	assert_eq!(bool::try_from(Value::from(true)), Ok(true));
+	assert_eq!(String::try_from(Value::from("hello")), Ok("hello".to_owned()));
+	assert_eq!(String::try_from(Value::from("hello".to_owned())), Ok("hello".to_owned()));
+	assert_eq!(Value::from(b"bytes".to_vec()).as_bytes(), Ok(b"bytes".as_ref()));
+	assert_eq!(Value::from(None::<i64>), Value::Nil);
+	assert_eq!(Value::from(Some(1_i64)), Value::Int(1));
+
+	// Nested structures.
+	let mut dict = HashMap::new();
+	dict.insert("primes".to_owned(), vec![ vec![ 2_i64, 3 ], vec![ 5, 7 ] ]);
+	dict.insert("empty".to_owned(), Vec::new());
+
+	let value = Value::from(
+		dict
+			.iter()
+			.map(|(key, arrays)| {
+				let arrays: Vec<Value> = arrays
+					.iter()
+					.map(|array| array.iter().copied().map(Value::from).collect::<Vec<_>>().into())
+					.collect();
+				(key.clone(), Value::from(arrays))
+			})
+			.collect::<HashMap<_, _>>()
+	);
+
+	assert_eq!(HashMap::<String, Vec<Vec<i64>>>::try_from(value.copy()), Ok(dict));
+	assert_eq!(
+		HashMap::<String, Value>::try_from(value.copy()).map(|dict| dict.len()),
+		Ok(2),
+	);
+
+	let array = Value::from(vec![ Value::from(1_i64), Value::from("two") ]);
+	assert_eq!(Vec::<Value>::try_from(array.copy()).map(|array| array.len()), Ok(2));
+
+	// Errors.
+	let unexpected = |expected, found| Some(ConversionError::UnexpectedType { expected, found });
+
+	assert_eq!(i64::try_from(Value::from(1.5)).err(), unexpected(Type::Int, Type::Float));
+	assert_eq!(f64::try_from(Value::from(1_i64)).err(), unexpected(Type::Float, Type::Int));
+	assert_eq!(bool::try_from(Value::Nil).err(), unexpected(Type::Bool, Type::Nil));
+	assert_eq!(String::try_from(Value::from(true)).err(), unexpected(Type::String, Type::Bool));
+	assert_eq!(Value::from(1_i64).as_bytes().err(), unexpected(Type::String, Type::Int));
+	assert_eq!(Vec::<i64>::try_from(Value::from("abc")).err(), unexpected(Type::Array, Type::String));
+	assert_eq!(Vec::<i64>::try_from(array).err(), unexpected(Type::Int, Type::String));
+	assert_eq!(
+		HashMap::<String, i64>::try_from(Value::from(vec![ Value::Nil ])).err(),
+		unexpected(Type::Dict, Type::Array),
+	);
+	let int_keys: HashMap<i64, Value> = vec![ (1, Value::from(Vec::<Value>::new())) ]
+		.into_iter()
+		.collect();
+	assert_eq!(
+		HashMap::<String, Vec<Vec<i64>>>::try_from(Value::from(int_keys)).err(),
+		unexpected(Type::String, Type::Int),
+	);
+	assert_eq!(
+		String::try_from(Value::from(b"\xff\xfe".to_vec())),
+		Err(ConversionError::InvalidUtf8(b"\xff\xfe".to_vec().into())),
+	);
+	assert_eq!(
+		ConversionError::UnexpectedType { expected: Type::Int, found: Type::Nil }.to_string(),
+		"expected int, found nil",
+	);
+}
+
+
+#[test]
+fn test_call_value() {
+	use std::sync::{Arc, Mutex};
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	// Call a function argument from Rust, re-entering the runtime.
+	runtime.register_fn(
+		"std.host.apply",
+		2,
+		|context| {
+			let (function, arg) = match context.args() {
+				[ function, arg ] => (function.copy(), arg.copy()),
+				_ => unreachable!("arity is checked before calling"),
+			};
+
+			context.runtime.call_value(&function, vec![ arg ])
+		}
+	);
+
+	let hooked = Arc::new(Mutex::new(0));
+	let hook_count = hooked.clone();
+	runtime.set_panic_hook(move |_| *hook_count.lock().unwrap() += 1);
+
+	let module = eval_str(
+		&mut runtime,
+		r#"
+			let count = 0
+
+			@[
+				add: function (a, b)
+					count = count + 1
+					a + b
+				end,
+				count: function ()
+					count
+				end,
+				twice: function (f, x)
+					std.host.apply(f, std.host.apply(f, x))
+				end,
+			]
+		"#
+	);
+
+	let member = |name: &str| match &module {
+		Value::Dict(dict) => dict.get(&name.into()).expect("missing member"),
+		other => panic!("expected dict, got {:?}", other),
+	};
+
+	let add = member("add");
+	assert_eq!(runtime.call_value(&add, vec![ 1_i64.into(), 2_i64.into() ]).ok(), Some(Value::Int(3)));
+	assert_eq!(runtime.call_value(&add, vec![ 1.5.into(), 2.0.into() ]).ok(), Some(Value::from(3.5)));
+	assert_eq!(runtime.call_value(&member("count"), Vec::new()).ok(), Some(Value::Int(2)));
+
+	// Rust -> Hush -> Rust -> Hush.
+	let increment = eval_str(&mut runtime, "function (x) x + 1 end");
+	assert_eq!(
+		runtime.call_value(&member("twice"), vec![ increment.copy(), 1_i64.into() ]).ok(),
+		Some(Value::Int(3)),
+	);
+
+	// Panics in nested callbacks reach the hook once, when escaping to the host.
+	let panic = runtime
+		.call_value(&member("twice"), vec![ increment, "a".into() ])
+		.expect_err("expected panic");
+	assert!(matches!(panic.untraced(), Panic::InvalidOperand { .. }), "{:?}", panic);
+	assert_eq!(*hooked.lock().unwrap(), 1);
+
+	// Arity violations and non-function values.
+	let panic = runtime.call_value(&add, Vec::new()).expect_err("expected panic");
+	assert!(matches!(panic.untraced(), Panic::InvalidArgs { supplied: 0, expected: 2, .. }));
+	let panic = runtime.call_value(&Value::Int(1), Vec::new()).expect_err("expected panic");
+	assert!(matches!(panic, Panic::InvalidCall { .. }));
+	assert_eq!(*hooked.lock().unwrap(), 3);
+
+	// The runtime is left in a usable state.
+	assert_eq!(runtime.call_value(&member("count"), Vec::new()).ok(), Some(Value::Int(2)));
+}
+
+
+#[test]
+fn test_programs_are_dropped() {
+	use std::rc::Rc;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let mut programs = Vec::new();
+	let mut callbacks = Vec::new();
+
+	for ix in 0 .. 10_000 {
+		let source = format!(
+			"let n = {}\nlet add = function (x) x + n end\n@[ add: add, value: add(1) ]",
+			ix,
+		);
+		let program = Rc::new(
+			compile(&mut runtime, Path::new("<test>"), source.as_bytes())
+				.expect("failed to read source")
+		);
+		programs.push(Rc::downgrade(&program));
+
+		let module = runtime.eval(program).expect("unexpected panic");
+		let member = |name: &str| match &module {
+			Value::Dict(dict) => dict.get(&name.into()).expect("missing member"),
+			other => panic!("expected dict, got {:?}", other),
+		};
+		assert_eq!(member("value"), Value::Int(ix + 1));
+
+		// Functions keep their program alive after evaluation.
+		if ix % 1000 == 0 {
+			callbacks.push((ix, member("add")));
+		}
+	}
+
+	gc::force_collect();
+
+	for (ix, add) in &callbacks {
+		assert_eq!(runtime.call_value(add, vec![ 1_i64.into() ]).ok(), Some(Value::Int(ix + 1)));
+	}
+
+	let alive = programs.iter().filter(|program| program.upgrade().is_some()).count();
+	assert_eq!(alive, callbacks.len());
+
+	drop(callbacks);
+	gc::force_collect();
+
+	let alive = programs.iter().filter(|program| program.upgrade().is_some()).count();
+	assert_eq!(alive, 0);
+}
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_cancellation() {
+	use std::{sync::mpsc, thread};
+
+	use super::PanicKind;
+
+	// The runtime is not Send, so it's created in the evaluating thread, which sends the
+	// token back.
+	let run = |source: &'static str, cancel_before: bool| {
+		let (token_sender, token_receiver) = mpsc::channel();
+		let (result_sender, result_receiver) = mpsc::channel();
+
+		let handle = thread::spawn(
+			move || {
+				let interner = symbol::Interner::new();
+				let args = std::iter::empty::<&str>();
+				let mut runtime = Runtime::new(args, interner);
+
+				let token = runtime.cancellation_token();
+				if cancel_before {
+					token.cancel();
+				}
+				token_sender.send(token).unwrap();
+
+				let result = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+					.expect("failed to read source");
+				result_sender.send(result.map(|_| ()).map_err(|panic| panic.kind())).unwrap();
+			}
+		);
+
+		let token = token_receiver.recv().unwrap();
+		thread::sleep(Duration::from_millis(20));
+
+		let start = Instant::now();
+		token.cancel();
+
+		let result = result_receiver
+			.recv_timeout(Duration::from_secs(5))
+			.expect("script was not interrupted");
+		handle.join().unwrap();
+
+		assert!(start.elapsed() < Duration::from_secs(1));
+
+		result
+	};
+
+	assert_eq!(run("while true do end", false), Err(PanicKind::Interrupted));
+
+	assert_eq!(
+		run(
+			r#"
+				let f = function () end
+				while true do
+					f()
+				end
+			"#,
+			false,
+		),
+		Err(PanicKind::Interrupted)
+	);
+
+	assert_eq!(
+		run(
+			r#"
+				let forever = function ()
+					@[ finished: false, value: nil ]
+				end
+
+				for _ in forever do end
+			"#,
+			false,
+		),
+		Err(PanicKind::Interrupted)
+	);
+
+	// Interrupts can't be caught by the script.
+	assert_eq!(
+		run(
+			r#"
+				while true do
+					std.catch(function () while true do end end)
+				end
+			"#,
+			false,
+		),
+		Err(PanicKind::Interrupted)
+	);
+
+	// Sleeps are cut short.
+	assert_eq!(run("std.sleep(60)\nstd.print(\"unreachable\")", false), Err(PanicKind::Interrupted));
+	assert_eq!(run("std.sleep(60.5)", false), Err(PanicKind::Interrupted));
+
+	// Children of command blocks are killed.
+	assert_eq!(run("{ sleep 10; echo unreachable }", false), Err(PanicKind::Interrupted));
+	assert_eq!(run("${ sleep 10 | cat }", false), Err(PanicKind::Interrupted));
+
+	// Cancelling before evaluation interrupts it right away.
+	assert_eq!(run("while true do end", true), Err(PanicKind::Interrupted));
+}
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_kill_processes() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	// The process outlives both the evaluation and its object.
+	let pid = match eval_str(&mut runtime, "std.process.spawn([ \"sleep\", \"30\" ]).pid") {
+		Value::Int(pid) => pid as libc::pid_t,
+		other => panic!("expected int, got {}", fmt::Show(other, runtime.interner())),
+	};
+
+	// SAFETY: kill has no memory safety requirements. The signal 0 only checks the pid.
+	let alive = || unsafe { libc::kill(pid, 0) } == 0;
+
+	assert!(alive());
+
+	// Processes are reaped when killed, so the pid no longer exists.
+	runtime.kill_processes();
+	assert!(!alive());
+
+	// Waited processes are left alone.
+	eval_str(&mut runtime, "std.process.spawn([ \"true\" ]).wait()");
+	runtime.kill_processes();
+}
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_fuel() {
+	use super::PanicKind;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let bounded = r#"
+		let i = 0
+		while i < 10 do
+			i = i + 1
+		end
+		i
+	"#;
+
+	// Unlimited by default.
+	assert_eq!(eval_str(&mut runtime, bounded), Value::Int(10));
+	assert_eq!(runtime.fuel(), None);
+
+	// Two statements, plus an iteration and a statement per loop, plus the result.
+	runtime.set_fuel(Some(1000));
+	assert_eq!(eval_str(&mut runtime, bounded), Value::Int(10));
+	assert_eq!(runtime.fuel(), Some(1000 - 23));
+
+	let mut run = |source: &str, fuel| {
+		runtime.set_fuel(Some(fuel));
+		eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.map(|_| runtime.fuel())
+			.map_err(|panic| (panic.kind(), panic.pos().line))
+	};
+
+	assert_eq!(run(bounded, 22).err(), Some((PanicKind::OutOfFuel, 6)));
+	assert_eq!(run("while true do end", 1000).err(), Some((PanicKind::OutOfFuel, 1)));
+
+	// Fuel exhaustion can't be caught by the script.
+	assert_eq!(
+		run("std.catch(function () while true do end end)\nnil", 1000).err(),
+		Some((PanicKind::OutOfFuel, 1)),
+	);
+
+	// Spawned commands consume fuel, builtins don't.
+	assert_eq!(run("{ true | true; cd .; true }", 4), Ok(Some(0)));
+	assert_eq!(run("{ true | true; cd .; true }", 3).err(), Some((PanicKind::OutOfFuel, 1)));
+}
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_stdio() {
+	use std::{io::Write, sync::{Arc, Mutex}};
+
+	#[derive(Clone, Default)]
+	struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+	impl Buffer {
+		fn contents(&self) -> String {
+			String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+		}
+	}
+
+	impl Write for Buffer {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().unwrap().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let stdout = Buffer::default();
+	let stderr = Buffer::default();
+	runtime.set_stdout(stdout.clone());
+	runtime.set_stderr(stderr.clone());
+	runtime.set_stdin(io::Cursor::new("alice\nbob\n"));
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			std.print("hello", 1)
+			std.eprint("oops")
+
+			let name = std.trim(std.read("name? "))
+			std.print("hi " ++ name)
+
+			{ echo from command; echo to stderr 1>2; echo a | cat | tr a b }
+
+			# Commands don't consume the script's input.
+			let cat = ${ cat }
+			std.assert(cat.stdout == "")
+
+			# Captured output doesn't reach the writers.
+			let captured = ${ echo captured; echo captured stderr 1>2 }
+			std.assert(captured.stdout == "captured\n")
+			std.assert(captured.stderr == "captured stderr\n")
+
+			let join = &{ echo async }.join()
+			std.assert(join == nil)
+
+			std.read()
+		"#,
+	);
+
+	assert_eq!(value, Value::from("bob\n"));
+	assert_eq!(stdout.contents(), "hello\t1\nname? hi alice\nfrom command\nb\nasync\n");
+	assert_eq!(stderr.contents(), "oops\nto stderr\n");
+}
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_command_executor() {
+	use std::{ffi::OsStr, path::PathBuf};
+
+	use super::executor::{
+		CommandExecutor,
+		Invocation,
+		MockExecutor,
+		Process,
+		ProcessSpec,
+		Response,
+	};
+
+	/// Denies removing files, delegating other commands.
+	#[derive(Debug)]
+	struct Policy(MockExecutor);
+
+	impl CommandExecutor for Policy {
+		fn spawn(&self, spec: ProcessSpec) -> io::Result<Box<dyn Process>> {
+			if spec.argv.first().map(AsRef::as_ref) == Some(OsStr::new("rm")) {
+				Err(io::Error::from(io::ErrorKind::PermissionDenied))
+			} else {
+				self.0.spawn(spec)
+			}
+		}
+	}
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let executor = MockExecutor::new();
+	executor.respond("ls", Response { stdout: b"a.txt\n".to_vec(), ..Response::default() });
+	executor.respond("grep", Response { stderr: b"oops\n".to_vec(), status: 2, ..Response::default() });
+	runtime.set_executor(Policy(executor.clone()));
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let listing = ${ ls -l *.none "quoted arg" }
+			std.assert(listing.stdout == "a.txt\n")
+
+			let failed = ${ echo hi | grep bye }
+			std.assert(std.type(failed) == "error")
+			std.assert(failed.context.stderr == "oops\n")
+
+			let denied = { rm -rf / }
+			std.assert(std.type(denied) == "error")
+
+			{
+				cd /;
+				GREETING=hello make build
+			}
+
+			@[ listing: listing.stdout, status: failed.context.error.status ]
+		"#,
+	);
+
+	let member = |name: &str| match &value {
+		Value::Dict(dict) => dict.get(&name.into()).expect("missing member"),
+		other => panic!("expected dict, got {:?}", other),
+	};
+	assert_eq!(member("listing"), Value::from("a.txt\n"));
+	assert_eq!(member("status"), Value::Int(2));
+
+	let cwd = std::env::current_dir().unwrap();
+	let os = |s: &str| Box::<OsStr>::from(OsStr::new(s));
+	let argv = |args: &[&str]| args.iter().map(|arg| os(arg)).collect::<Vec<_>>();
+
+	// Pipelines are spawned from last to first, and denied commands are not recorded.
+	assert_eq!(
+		executor.invocations(),
+		vec![
+			Invocation { argv: argv(&["ls", "-l", "quoted arg"]), env: Vec::new(), cwd: cwd.clone() },
+			Invocation { argv: argv(&["grep", "bye"]), env: Vec::new(), cwd: cwd.clone() },
+			Invocation { argv: argv(&["echo", "hi"]), env: Vec::new(), cwd },
+			Invocation {
+				argv: argv(&["make", "build"]),
+				env: vec![ (os("GREETING"), os("hello")) ],
+				cwd: PathBuf::from("/"),
+			},
+		]
+	);
+}
+
+
+#[test]
+fn test_value_serde() {
+	use serde_json::json;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	// Collections may be shared, as long as there are no cycles.
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let shared = [ 1, 2 ]
+			@[
+				nested: @[ list: [ 1, 2.5, true, nil, "text" ], empty: @[] ],
+				shared: [ shared, shared ],
+			]
+		"#,
+	);
+
+	let expected = json!({
+		"nested": { "list": [ 1, 2.5, true, null, "text" ], "empty": {} },
+		"shared": [ [ 1, 2 ], [ 1, 2 ] ],
+	});
+
+	assert_eq!(serde_json::to_value(&value).unwrap(), expected);
+
+	let text = serde_json::to_string(&value).unwrap();
+	let parsed: Value = serde_json::from_str(&text).unwrap();
+	assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+	// Strings which are not valid UTF-8 are serialized as bytes.
+	let bytes = Value::from(vec![ 0xff_u8, 0x00 ]);
+	assert_eq!(serde_json::to_value(&bytes).unwrap(), json!([ 255, 0 ]));
+	assert_eq!(serde_json::from_str::<Value>(r#""ÿ""#).unwrap(), Value::from("\u{ff}"));
+
+	let mut error = |source: &str| {
+		let value = eval_str(&mut runtime, source);
+		serde_json::to_string(&value).unwrap_err().to_string()
+	};
+
+	assert_eq!(error("function () end"), "can't serialize function");
+	assert_eq!(error("let a = [] std.push(a, a) a"), "can't serialize cyclic value");
+	assert_eq!(error("let d = @[] d[1] = 2 d"), "json object key must be string");
+}
+
+
+#[test]
+fn test_json_encode_errors() {
+	use super::PanicKind;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let mut run = |source: &str| {
+		eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.expect_err("expected panic")
+	};
+
+	// The offending key is the panic value.
+	let panic = run("std.json.encode(@[ a: @[ [2.5]: 1 ] ])");
+	assert_eq!(panic.kind(), PanicKind::ValueError);
+	assert_eq!(panic.value(), Some(&Value::Float(2.5.into())));
+
+	let panic = run("let a = [] std.push(a, @[ b: a ]) std.json.encode(@[ root: a ])");
+	assert!(
+		matches!(
+			panic.untraced(),
+			Panic::CyclicValue { path, .. } if &**path == r#"value["root"][0]["b"]"#
+		),
+		"{:?}",
+		panic,
+	);
+}
+
+
+#[test]
+fn test_value_snapshot() {
+	use super::value::ConversionError;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let shared = [ 1, 2 ]
+			@[
+				nested: @[ list: [ 1, 2.5, true, nil, "text" ], error: std.error("oops", 42) ],
+				shared: [ shared, shared ],
+				[ 3 ]: "key",
+			]
+		"#,
+	);
+
+	let snapshot = value.snapshot().unwrap();
+	let rebuilt = std::thread::spawn(move || snapshot)
+		.join()
+		.unwrap()
+		.to_value();
+
+	assert_eq!(rebuilt, value);
+
+	// Sharing is preserved.
+	let shared = eval_str(&mut runtime, "let s = [] let t = [ s, s ] t");
+	match &shared.snapshot().unwrap().to_value() {
+		Value::Array(array) => {
+			let array = array.borrow();
+			match (&array[0], &array[1]) {
+				(Value::Array(a), Value::Array(b)) => assert_eq!(a.address(), b.address()),
+				_ => panic!("expected arrays"),
+			}
+		}
+		_ => panic!("expected array"),
+	}
+
+	// Cycles are preserved.
+	let cyclic = eval_str(&mut runtime, "let a = [] std.push(a, a) a");
+	match &cyclic.snapshot().unwrap().to_value() {
+		Value::Array(array) => match &array.borrow()[0] {
+			Value::Array(inner) => assert_eq!(inner.address(), array.address()),
+			_ => panic!("expected array"),
+		},
+		_ => panic!("expected array"),
+	}
+
+	let function = eval_str(&mut runtime, "[ function () end ]");
+	assert!(matches!(function.snapshot(), Err(ConversionError::Function)));
+}
+
+
+#[test]
+fn test_trace_hook() {
+	use std::{sync::{mpsc, Arc, Mutex}, thread};
+
+	use super::{Directive, PanicKind, TraceOptions};
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let trace = Arc::new(Mutex::new(Vec::new()));
+	let recorded = trace.clone();
+	runtime.set_trace_hook(
+		move |point| {
+			let local = point.locals
+				.as_ref()
+				.and_then(|locals| match locals.get("a") {
+					Some(Value::Int(a)) => Some(a),
+					_ => None,
+				});
+
+			let call = point.frames.last().map(|frame| frame.pos.line);
+			assert_eq!(point.frames.len(), point.depth);
+
+			recorded.lock().unwrap().push((point.pos.line, point.depth, local, call));
+			Directive::Continue
+		},
+		TraceOptions { locals: true },
+	);
+
+	eval_str(
+		&mut runtime,
+		r#"let f = function (a)
+			return a + 1
+		end
+		let x = f(1)
+		x"#,
+	);
+
+	assert_eq!(
+		*trace.lock().unwrap(),
+		[ (1, 0, None, None), (4, 0, None, None), (2, 1, Some(1), Some(4)), (5, 0, None, None) ],
+	);
+
+	// Abort from the hook mid loop.
+	let statements = Arc::new(Mutex::new(0));
+	let counter = statements.clone();
+	runtime.set_trace_hook(
+		move |_| {
+			let mut count = counter.lock().unwrap();
+			*count += 1;
+			if *count == 10 { Directive::Abort } else { Directive::Continue }
+		},
+		TraceOptions::default(),
+	);
+
+	let source = "let i = 0 while true do i = i + 1 end";
+	let result = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+		.expect("failed to read source");
+	assert_eq!(result.map(|_| ()).map_err(|panic| panic.kind()), Err(PanicKind::Interrupted));
+	assert_eq!(*statements.lock().unwrap(), 10);
+
+	// Pause until resumed from another thread.
+	let (sender, receiver) = mpsc::channel();
+	runtime.set_trace_hook(
+		move |point| {
+			sender.send(point.pos.line).unwrap();
+			Directive::Pause
+		},
+		TraceOptions::default(),
+	);
+
+	let resumer = runtime.trace_resumer();
+	let handle = thread::spawn(
+		move || {
+			let mut lines = Vec::new();
+			while let Ok(line) = receiver.recv_timeout(Duration::from_secs(5)) {
+				lines.push(line);
+				resumer.resume();
+			}
+			lines
+		}
+	);
+
+	assert_eq!(eval_str(&mut runtime, "let x = 1\nx + 1"), Value::Int(2));
+	runtime.set_trace_hook(|_| Directive::Continue, TraceOptions::default());
+
+	assert_eq!(handle.join().unwrap(), [ 1, 2 ]);
+}
+
+
+#[test]
+fn test_call_hook() {
+	use std::sync::{Arc, Mutex};
+
+	use super::{value::Function, CallEvent};
+
+	for interpreter in [Interpreter::TreeWalker, Interpreter::Vm] {
+		let interner = symbol::Interner::new();
+		let args = std::iter::empty::<&str>();
+		let mut runtime = Runtime::new(args, interner);
+		runtime.set_interpreter(interpreter);
+
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let recorded = events.clone();
+		runtime.set_call_hook(
+			move |event| {
+				let event = match event {
+					CallEvent::Call { function: Function::Hush(function), pos } => {
+						format!("call {} at {}", function.pos.line, pos.line)
+					}
+					CallEvent::Call { function: Function::Rust(function), .. } => {
+						format!("call {}", function.name())
+					}
+					CallEvent::CommandBlock { pos } => format!("command {}", pos.line),
+					CallEvent::Return => "return".to_owned(),
+				};
+
+				recorded.lock().unwrap().push(event);
+			}
+		);
+
+		eval_str(
+			&mut runtime,
+			r#"let f = function (n)
+				if n == 0 then
+					std.assert(false)
+				end
+				n
+			end
+			let g = function () f(1) end
+			g()
+			std.catch(function () f(0) end)"#,
+		);
+
+		// Calls that panic return as well.
+		assert_eq!(
+			*events.lock().unwrap(),
+			[
+				"call 7 at 8", "call 1 at 7", "return", "return",
+				"call std.catch", "call 9 at 9", "call 1 at 9", "call std.assert", "return", "return",
+				"return", "return",
+			],
+			"{:?}",
+			interpreter,
+		);
+	}
+}
+
+
+#[test]
+fn test_literal_names() {
+	use std::collections::HashSet;
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let dicts = []
+			let i = 0
+			while i < 1000 do
+				let dict = @[ key: i, other: i * 2 ]
+				std.assert(dict.key * 2 == dict.other)
+				std.push(dicts, dict)
+				i = i + 1
+			end
+			dicts
+		"#
+	);
+
+	// Keys are resolved once, during analysis, so every evaluation shares their strings.
+	let mut addresses = HashSet::new();
+	match &value {
+		Value::Array(dicts) => {
+			let dicts = dicts.borrow();
+			assert_eq!(dicts.len(), 1000);
+
+			for (ix, dict) in dicts.iter().enumerate() {
+				match dict {
+					Value::Dict(dict) => {
+						assert_eq!(dict.get(&"key".into()).ok(), Some(Value::Int(ix as i64)));
+
+						for key in dict.borrow().keys() {
+							match key {
+								Value::String(key) => addresses.insert(key.address()),
+								other => panic!("expected string, got {:?}", other),
+							};
+						}
+					}
+					other => panic!("expected dict, got {:?}", other),
+				}
+			}
+		}
+		other => panic!("expected array, got {:?}", other),
+	}
+	assert_eq!(addresses.len(), 2);
+}
+
+
+#[test]
+fn test_string_sharing() {
+	use super::value::Str;
+
+	// Copies share the allocation instead of duplicating the contents.
+	let string = Value::from(vec![b'x'; 1 << 20]);
+	let copies: Vec<Value> = (0..1000).map(|_| string.copy()).collect();
+	for copy in &copies {
+		match (&string, copy) {
+			(Value::String(string), Value::String(copy)) => assert_eq!(string.address(), copy.address()),
+			other => panic!("expected strings, got {:?}", other),
+		}
+	}
+	assert_eq!(Value::from(vec![b'x'; 1 << 20]), string);
+	assert_ne!(Value::from(vec![b'y'; 1 << 20]), string);
+
+	let hello = Str::from("hello");
+	let world = hello.concat(&", world".into());
+	assert_eq!(hello.as_bytes(), b"hello");
+	assert_eq!(world.as_bytes(), b"hello, world");
+
+	// Operations on a shared string must not be observable through its other copies.
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let key = "name"
+			let alias = key
+			alias = alias ++ "s"
+			let dict = @[ name: 1 ]
+			dict[alias] = 2
+			std.assert(key == "name")
+			std.assert(alias == "names")
+			std.assert(dict[key] == 1 and dict["names"] == 2)
+			std.len(dict)
+		"#
+	);
+	assert_eq!(value, Value::Int(2));
+}
+
+
+#[test]
+fn test_dict_hashing() {
+	use super::value::DictMap;
+
+	let keys: Vec<Value> = (0..1000)
+		.map(|ix| Value::from(format!("key{}", ix)))
+		.collect();
+
+	// A million operations on short keys.
+	let mut dict = DictMap::default();
+	for round in 0..500_i64 {
+		for (ix, key) in keys.iter().enumerate() {
+			let previous = dict.get(key).map_or(0, |value| match value {
+				Value::Int(int) => *int,
+				other => panic!("expected int, got {:?}", other),
+			});
+			assert_eq!(previous, if round == 0 { 0 } else { ix as i64 + round - 1 });
+
+			dict.insert(key.copy(), Value::Int(ix as i64 + round));
+		}
+	}
+	assert_eq!(dict.len(), 1000);
+
+	// Keys are found regardless of their allocation.
+	assert_eq!(dict.get(&"key999".into()), Some(&Value::Int(999 + 499)));
+	assert_eq!(dict.get(&"key1000".into()), None);
+
+	// Ints and floats are distinct keys, and every NaN is the same key.
+	let mut dict = DictMap::default();
+	dict.insert(Value::Int(1), Value::Bool(true));
+	dict.insert(Value::from(1.0), Value::Bool(false));
+	dict.insert(Value::from(f64::NAN), Value::Nil);
+	dict.insert(Value::from(-f64::NAN), Value::Int(2));
+	assert_eq!(dict.len(), 3);
+	assert_eq!(dict.get(&Value::Int(1)), Some(&Value::Bool(true)));
+	assert_eq!(dict.get(&Value::from(1.0)), Some(&Value::Bool(false)));
+	assert_eq!(dict.get(&Value::from(f64::NAN)), Some(&Value::Int(2)));
+}
+
+
+#[test]
+fn test_access_and_call_paths() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let array = [ 10, 20, 30 ]
+			let dict = @[ key: "value", count: 0 ]
+
+			# Closed over variables are borrowed from their cell.
+			let read = function(ix) array[ix] end
+			std.assert(read(1) == 20)
+			std.assert(dict.key == "value" and dict["key"] == "value")
+
+			# The object is fetched before a field which reassigns it.
+			let swap = function()
+				array = [ 1, 2, 3 ]
+				0
+			end
+			std.assert(array[swap()] == 10)
+			std.assert(array[0] == 1)
+
+			# Methods receive the accessed object.
+			dict.incr = function() self.count = self.count + 1 end
+			dict.incr()
+			dict["incr"]()
+
+			let sum = function(a, b, c, d) a * 1000 + b * 100 + c * 10 + d end
+			sum(1, 2, 3, 4) + dict.count
+		"#
+	);
+	assert_eq!(value, Value::Int(1236));
+
+	let source = "let x = 1\nx()";
+	let result = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+		.expect("failed to read source");
+	match result {
+		Err(Panic::InvalidCall { function, pos }) => {
+			assert_eq!(function, Value::Int(1));
+			assert_eq!(pos.line, 2);
+		}
+		other => panic!("expected invalid call, got {:?}", other),
+	}
+}
+
+
+#[test]
+fn test_arguments_staging() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let add = function(a, b) a + b end
+			let total = 0
+			let i = 0
+			while i < 1000 do
+				let pair = [ add(i, add(1, 2)), std.len([ i, i, i ]) ]
+				let entry = @[ left: pair[0], right: pair[1] ]
+				total = add(total, add(entry.left, entry.right))
+				i = i + 1
+			end
+			total
+		"#
+	);
+	assert_eq!(value, Value::Int((0..1000).map(|i| i + 3 + 3).sum()));
+
+	// Arguments are popped after every call, and the vector does not grow across calls.
+	assert!(runtime.arguments.is_empty());
+	assert!(runtime.arguments.capacity() <= 16, "{}", runtime.arguments.capacity());
+}
+
+
+#[test]
+fn test_for_iteration_keys() {
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let sum = 0
+			for i in std.range(0, 100000, 1) do
+				sum = sum + i
+			end
+
+			let counter = @[
+				n: 0,
+				iter: function()
+					let n = self.n
+					let i = 0
+					function()
+						i = i + 1
+						if i > n then
+							@[ finished: true ]
+						else
+							@[ finished: false, value: i ]
+						end
+					end
+				end
+			]
+			counter.n = 1000
+			for i in counter do
+				sum = sum - i
+			end
+
+			sum
+		"#
+	);
+	assert_eq!(value, Value::Int(4_999_950_000 - 500_500));
+
+	let source = "for x in function() @[ value: 1 ] end do end";
+	let result = eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+		.expect("failed to read source");
+	match result {
+		Err(Panic::IndexOutOfBounds { index, .. }) => assert_eq!(index, "finished".into()),
+		other => panic!("expected index out of bounds, got {:?}", other),
+	}
+}
+
+
+#[test]
+#[cfg(feature = "plugins")]
+fn test_plugin_load() {
+	use std::{env::consts, path::PathBuf, process::Command};
+
+	use super::value::Error;
+
+	// Build the example plugin in a separate target directory, so that concurrent builds of
+	// the test binary are not blocked.
+	let build = |name: &str, features: &[&str]| -> PathBuf {
+		let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+		let target_dir = root.join("target").join("plugin-tests").join(name);
+
+		let status = Command::new(env!("CARGO"))
+			.current_dir(root)
+			.args(&[ "build", "--quiet", "-p", "hush-plugin-example", "--target-dir" ])
+			.arg(&target_dir)
+			.args(features.iter().flat_map(|feature| vec![ "--features", feature ]))
+			.status()
+			.expect("failed to run cargo");
+		assert!(status.success(), "failed to build example plugin");
+
+		target_dir
+			.join("debug")
+			.join(format!("{}hush_plugin_example{}", consts::DLL_PREFIX, consts::DLL_SUFFIX))
+	};
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let plugin = build("compatible", &[]);
+	let source = format!(
+		r#"
+			let plugin = std.load("{}")
+			let results = [
+				plugin.add(1, 2),
+				plugin.add(1.5, 2.5),
+				plugin.greet("world"),
+				plugin.add(1, "two"),
+			]
+			results
+		"#,
+		plugin.display(),
+	);
+
+	let expected: Value = vec![
+		Value::Int(3),
+		Value::Float(4.0.into()),
+		"hello, world".into(),
+		Error::new("expected two ints or two floats".into(), Value::Nil).into(),
+	].into();
+
+	assert_eq!(eval_str(&mut runtime, &source), expected);
+
+	let plugin = build("incompatible", &[ "hush-plugin-example/incompatible" ]);
+	let source = format!(r#"std.load("{}")"#, plugin.display());
+
+	match &eval_str(&mut runtime, &source) {
+		Value::Error(error) => assert_eq!(
+			error.description,
+			"incompatible plugin abi version 2, expected 1".into(),
+		),
+		other => panic!("expected error, got {:?}", other),
+	}
+
+	match eval_str(&mut runtime, r#"std.load("/nonexistent.so")"#) {
+		Value::Error(_) => (),
+		other => panic!("expected error, got {:?}", other),
+	}
+}
+
+
+#[test]
+#[cfg(not(feature = "system"))]
+fn test_without_system() {
+	use std::{io::Write, sync::{Arc, Mutex}};
+
+	use super::PanicKind;
+
+	#[derive(Clone, Default)]
+	struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for Buffer {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().unwrap().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	let interner = symbol::Interner::new();
+	let args = std::iter::empty::<&str>();
+	let mut runtime = Runtime::new(args, interner);
+
+	let value = eval_str(
+		&mut runtime,
+		r#"
+			let counter = function ()
+				let count = 0
+				return function ()
+					count = count + 1
+					count
+				end
+			end
+
+			let next = counter()
+			next()
+
+			let dict = @[ a: 2 * 3 + 1, b: 10 % 4 ]
+			dict.c = next()
+			std.print(dict.a, dict.b, dict.c)
+
+			dict.a + dict.b + dict.c
+		"#
+	);
+	assert_eq!(value, Value::Int(11));
+
+	// Output is discarded, unless a writer is provided.
+	let stdout = Buffer::default();
+	runtime.set_stdout(stdout.clone());
+	eval_str(&mut runtime, r#"std.print("x", 1.5)"#);
+	assert_eq!(String::from_utf8(stdout.0.lock().unwrap().clone()).unwrap(), "x\t1.5\n");
+
+	let mut run = |source: &str| {
+		eval(&mut runtime, Path::new("<test>"), source.as_bytes())
+			.expect("failed to read source")
+			.map_err(|panic| (panic.kind(), panic.pos().line))
+	};
+
+	// Scripts using unavailable features are accepted, but panic when they get there.
+	assert_eq!(run("std.print(1)\n{ echo hello }").err(), Some((PanicKind::UnsupportedOperation, 2)));
+	assert_eq!(run(r#"std.fs.read("file")"#).err(), Some((PanicKind::UnsupportedOperation, 1)));
+	assert_eq!(run(r#"std.process.spawn([ "ls" ])"#).err(), Some((PanicKind::UnsupportedOperation, 1)));
 }
@@ -0,0 +1,163 @@
+use std::{
+	sync::{Arc, Condvar, Mutex},
+	time::Duration,
+};
+
+use crate::symbol::{self, Symbol};
+use super::{mem, program, CancellationToken, Frame, Function, SourcePos, Value};
+
+
+/// A callback invoked before each statement, on which step debuggers and profilers may be
+/// built.
+pub(super) struct TraceHook {
+	pub hook: Box<dyn FnMut(&TracePoint) -> Directive + Send>,
+	pub options: TraceOptions,
+}
+
+
+impl std::fmt::Debug for TraceHook {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("TraceHook")
+			.field("options", &self.options)
+			.finish()
+	}
+}
+
+
+/// A callback invoked when entering and leaving function calls and command blocks, on
+/// which profilers may be built.
+pub(super) struct CallHook(pub Box<dyn FnMut(&CallEvent) + Send>);
+
+
+impl std::fmt::Debug for CallHook {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("CallHook")
+	}
+}
+
+
+/// A function call or command block being entered or left. Every entry is followed by
+/// exactly one return, even when panicking, so that calls are properly nested.
+#[derive(Debug)]
+pub enum CallEvent<'a> {
+	/// A function is about to be called at the given position.
+	Call { function: &'a Function, pos: &'a SourcePos },
+	/// The command block at the given position is about to be executed.
+	CommandBlock { pos: &'a SourcePos },
+	/// The innermost call or command block has finished.
+	Return,
+}
+
+
+/// What the trace hook gets to see.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceOptions {
+	/// Whether the local variables of the current frame are provided. This requires
+	/// resolving the current frame before each statement.
+	pub locals: bool,
+}
+
+
+/// How the interpreter should proceed after the trace hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+	/// Execute the statement.
+	Continue,
+	/// Block until resumed through the runtime's `TraceResumer`, then execute the
+	/// statement.
+	Pause,
+	/// Stop the script with an interrupted panic.
+	Abort,
+}
+
+
+/// The statement about to be executed.
+#[derive(Debug)]
+pub struct TracePoint<'a> {
+	/// The position of the statement.
+	pub pos: &'a SourcePos,
+	/// How many function calls are in progress.
+	pub depth: usize,
+	/// The function calls in progress, from the outermost to the innermost.
+	pub frames: &'a [Frame],
+	/// The interner of the runtime, to resolve paths and show values.
+	pub interner: &'a symbol::Interner,
+	/// The local variables of the current frame, if enabled in the options.
+	pub locals: Option<Locals<'a>>,
+}
+
+
+/// The local variables of the current frame.
+#[derive(Debug)]
+pub struct Locals<'a> {
+	pub(super) names: &'static [(Symbol, program::mem::SlotIx)],
+	pub(super) stack: &'a mem::Stack,
+	pub(super) interner: &'a symbol::Interner,
+}
+
+
+impl<'a> Locals<'a> {
+	/// Get the value of the variable with the given name.
+	pub fn get(&self, name: &str) -> Option<Value> {
+		let symbol = self.interner.get(name)?;
+
+		self.names
+			.iter()
+			.find(|(local, _)| *local == symbol)
+			.map(|(_, slot_ix)| self.stack.fetch(slot_ix.into()))
+	}
+
+
+	/// The variables in order of declaration, by name. Variables declared in blocks that
+	/// have been exited may still hold their last values.
+	pub fn iter(&self) -> impl Iterator<Item = (&'a [u8], Value)> + '_ {
+		self.names
+			.iter()
+			.filter_map(
+				move |(symbol, slot_ix)| self.interner
+					.resolve(*symbol)
+					.map(|name| (name, self.stack.fetch(slot_ix.into())))
+			)
+	}
+}
+
+
+/// A handle to resume the interpreter when paused by the trace hook, which may be used from
+/// another thread.
+#[derive(Debug, Clone, Default)]
+pub struct TraceResumer(Arc<(Mutex<bool>, Condvar)>);
+
+
+impl TraceResumer {
+	/// Resume the interpreter. If it's not paused, the next pause returns immediately.
+	pub fn resume(&self) {
+		let (resumed, condvar) = &*self.0;
+		*resumed.lock().unwrap_or_else(|error| error.into_inner()) = true;
+		condvar.notify_all();
+	}
+
+
+	/// Block until resumed. Returns false if the script was interrupted meanwhile.
+	pub(super) fn wait(&self, cancel: &CancellationToken) -> bool {
+		/// How often to check for cancellation while paused.
+		const POLL: Duration = Duration::from_millis(50);
+
+		let (resumed, condvar) = &*self.0;
+		let mut resumed = resumed.lock().unwrap_or_else(|error| error.into_inner());
+
+		while !*resumed {
+			if cancel.is_cancelled() {
+				return false;
+			}
+
+			resumed = condvar
+				.wait_timeout(resumed, POLL)
+				.unwrap_or_else(|error| error.into_inner())
+				.0;
+		}
+
+		*resumed = false;
+
+		true
+	}
+}
@@ -0,0 +1,161 @@
+use crate::{
+	fmt::{self, Display},
+	symbol,
+};
+use super::{Function, SourcePos};
+
+
+/// A function call in progress.
+#[derive(Debug)]
+pub struct Frame {
+	/// The called function.
+	pub function: Function,
+	/// The position of the call.
+	pub pos: SourcePos,
+}
+
+
+impl Frame {
+	/// Shallow copy.
+	pub fn copy(&self) -> Self {
+		Self {
+			function: self.function.copy(),
+			pos: self.pos.copy(),
+		}
+	}
+
+
+	/// Whether the other frame calls the same function from the same position. Functions
+	/// are compared by their definition, as closures are displayed the same regardless of
+	/// their captured values, and comparing those may be costly.
+	fn is_repeated_by(&self, other: &Self) -> bool {
+		let same_function = match (&self.function, &other.function) {
+			(Function::Hush(function), Function::Hush(other)) => function.pos == other.pos,
+			(Function::Rust(function), Function::Rust(other)) => function.name() == other.name(),
+			_ => false,
+		};
+
+		same_function && self.pos == other.pos
+	}
+}
+
+
+/// A call repeated consecutively, as in recursive functions.
+#[derive(Debug)]
+struct Run {
+	frame: Frame,
+	/// How many consecutive times the call was made, at least once.
+	count: usize,
+}
+
+
+/// A snapshot of the call stack, from the outermost to the innermost call. Consecutive
+/// repetitions of a call, as in recursive functions, are kept once. Very deep stacks are
+/// truncated, keeping only the outermost and innermost calls.
+#[derive(Debug, Default)]
+pub struct Traceback {
+	/// The outermost calls.
+	first: Box<[Run]>,
+	/// The innermost calls.
+	last: Box<[Run]>,
+	/// How many calls were omitted between the outermost and innermost ones.
+	omitted: usize,
+}
+
+
+impl Traceback {
+	/// How many of the outermost and innermost distinct calls are kept.
+	pub const MAX_FRAMES: usize = 20;
+
+
+	/// Take a snapshot of the given call stack.
+	pub fn capture(frames: &[Frame]) -> Self {
+		let mut runs: Vec<Run> = Vec::new();
+
+		for frame in frames {
+			match runs.last_mut() {
+				Some(run) if run.frame.is_repeated_by(frame) => run.count += 1,
+				_ => runs.push(Run { frame: frame.copy(), count: 1 }),
+			}
+		}
+
+		if runs.len() <= 2 * Self::MAX_FRAMES {
+			return Self { first: runs.into(), last: Box::default(), omitted: 0 };
+		}
+
+		let last = runs.split_off(runs.len() - Self::MAX_FRAMES);
+		let omitted = runs
+			.drain(Self::MAX_FRAMES ..)
+			.map(|run| run.count)
+			.sum();
+
+		Self { first: runs.into(), last: last.into(), omitted }
+	}
+
+
+	/// Whether there are no calls in the traceback.
+	pub fn is_empty(&self) -> bool {
+		self.first.is_empty()
+	}
+
+
+	/// The kept calls, from the outermost to the innermost, along with how many consecutive
+	/// times each was made.
+	pub fn calls(&self) -> impl Iterator<Item = (&Frame, usize)> {
+		self.first
+			.iter()
+			.chain(self.last.iter())
+			.map(|run| (&run.frame, run.count))
+	}
+
+
+	/// The kept calls, from the outermost to the innermost, without repetitions.
+	pub fn frames(&self) -> impl Iterator<Item = &Frame> {
+		self.calls().map(|(frame, _)| frame)
+	}
+
+
+	/// How many calls were omitted.
+	pub fn omitted(&self) -> usize {
+		self.omitted
+	}
+}
+
+
+impl<'a> Display<'a> for Traceback {
+	type Context = &'a symbol::Interner;
+
+	/// Each line is preceded by a line break, so that it may be appended to a panic message.
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let run = |f: &mut std::fmt::Formatter, run: &Run| {
+			write!(
+				f,
+				"\n  {}: in {}",
+				fmt::Show(&run.frame.pos, context),
+				fmt::Show(&run.frame.function, context),
+			)?;
+
+			if run.count > 1 {
+				write!(f, "\n  ... repeated {} more times", run.count - 1)?;
+			}
+
+			Ok(())
+		};
+
+		write!(f, "\ntraceback (most recent call last):")?;
+
+		for call in self.first.iter() {
+			run(f, call)?;
+		}
+
+		if self.omitted > 0 {
+			write!(f, "\n  ... {} calls omitted ...", self.omitted)?;
+		}
+
+		for call in self.last.iter() {
+			run(f, call)?;
+		}
+
+		Ok(())
+	}
+}
@@ -28,6 +28,12 @@ impl Array {
 	}
 
 
+	/// The address of the underlying allocation, which is shared by all copies.
+	pub fn address(&self) -> *const () {
+		self.0.deref() as *const GcCell<Vec<Value>> as *const ()
+	}
+
+
 	/// Borrow the inner Vec.
 	pub fn borrow(&self) -> GcCellRef<Vec<Value>> {
 		self.0.deref().borrow()
@@ -0,0 +1,165 @@
+//! The single policy for comparing values, shared by the equality and relational
+//! operators, dict keys, std.eq, std.cmp and std.sort:
+//!
+//! - Values of different types are never equal. In particular, `1` and `1.0` are
+//!   different values, and different dict keys, and `1 < 1.0`.
+//! - Floats are equal when numerically equal, so `0.0` and `-0.0` are the same key. NaN
+//!   is equal to itself, so that it can be used as a dict key and found again. The only
+//!   exception are the operators, which follow IEEE 754 for NaN.
+//! - The order is consistent with equality. Numbers come first, compared exactly across
+//!   ints and floats, without rounding ints to floats. When an int and a float are
+//!   numerically equal, the int comes first. NaN comes before every other number. Then
+//!   come strings (bytewise), bytes, bools, nil, and arrays (lexicographically,
+//!   element-wise). Dicts, functions and errors are not ordered.
+
+use std::{
+	cmp::Ordering,
+	hash::{Hash, Hasher},
+};
+
+use super::Value;
+
+
+impl Value {
+	/// Whether both values are the same according to the comparison policy. This is the
+	/// equality of dict keys.
+	pub fn key_eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Self::Nil, Self::Nil) => true,
+			(Self::Bool(left), Self::Bool(right)) => left == right,
+			(Self::Byte(left), Self::Byte(right)) => left == right,
+			(Self::Int(left), Self::Int(right)) => left == right,
+			(Self::Float(left), Self::Float(right)) => left == right,
+			(Self::String(left), Self::String(right)) => left == right,
+			(Self::Array(left), Self::Array(right)) => left == right,
+			(Self::Dict(left), Self::Dict(right)) => left == right,
+			(Self::Function(left), Self::Function(right)) => left == right,
+			(Self::Error(left), Self::Error(right)) => left == right,
+			_ => false,
+		}
+	}
+
+
+	/// Hash the value consistently with `key_eq`.
+	pub fn key_hash<H: Hasher>(&self, state: &mut H) {
+		std::mem::discriminant(self).hash(state);
+
+		match self {
+			Self::Nil => (),
+			Self::Bool(bool) => bool.hash(state),
+			Self::Byte(byte) => byte.hash(state),
+			Self::Int(int) => int.hash(state),
+			Self::Float(float) => float.hash(state),
+			Self::String(string) => string.hash(state),
+			Self::Array(array) => array.hash(state),
+			Self::Dict(dict) => dict.hash(state),
+			Self::Function(function) => function.hash(state),
+			Self::Error(error) => error.hash(state),
+		}
+	}
+
+
+	/// The equality operator, which is `key_eq` except that NaN is different from every
+	/// value, including itself.
+	pub fn op_eq(&self, other: &Self) -> bool {
+		match self {
+			Self::Float(float) if float.is_nan() => false,
+			_ => self.key_eq(other),
+		}
+	}
+
+
+	/// The total order of the comparison policy, or None if any of the values, or any of
+	/// their elements, is not ordered.
+	pub fn total_cmp(&self, other: &Self) -> Option<Ordering> {
+		self.compare(other).ok()
+	}
+
+
+	/// Like `total_cmp`, but returning the value which is not ordered.
+	pub fn compare(&self, other: &Self) -> Result<Ordering, Value> {
+		let left_rank = rank(self).ok_or_else(|| self.copy())?;
+		let right_rank = rank(other).ok_or_else(|| other.copy())?;
+
+		match (self, other) {
+			(Self::Int(left), Self::Int(right)) => Ok(left.cmp(right)),
+			(Self::Float(left), Self::Float(right)) => Ok(left.cmp(right)),
+			(Self::Int(left), Self::Float(right)) => Ok(
+				cmp_int_float(*left, right.0).then(Ordering::Less)
+			),
+			(Self::Float(left), Self::Int(right)) => Ok(
+				cmp_int_float(*right, left.0).reverse().then(Ordering::Greater)
+			),
+
+			(Self::Array(left), Self::Array(right)) => {
+				let left = left.borrow();
+				let right = right.borrow();
+
+				for (left, right) in left.iter().zip(right.iter()) {
+					match left.compare(right)? {
+						Ordering::Equal => continue,
+						ordering => return Ok(ordering),
+					}
+				}
+
+				Ok(left.len().cmp(&right.len()))
+			},
+
+			_ if left_rank == right_rank => Ok(self.cmp(other)),
+
+			_ => Ok(left_rank.cmp(&right_rank)),
+		}
+	}
+}
+
+
+impl PartialEq for Value {
+	fn eq(&self, other: &Self) -> bool {
+		self.key_eq(other)
+	}
+}
+
+
+impl Eq for Value { }
+
+
+impl Hash for Value {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.key_hash(state)
+	}
+}
+
+
+/// The position of each type in the total order.
+fn rank(value: &Value) -> Option<u8> {
+	match value {
+		Value::Int(_) | Value::Float(_) => Some(0),
+		Value::String(_) => Some(1),
+		Value::Byte(_) => Some(2),
+		Value::Bool(_) => Some(3),
+		Value::Nil => Some(4),
+		Value::Array(_) => Some(5),
+		Value::Dict(_) | Value::Function(_) | Value::Error(_) => None,
+	}
+}
+
+
+/// Compare the numeric values of an int and a float exactly. Converting the int to a
+/// float would round it above 2^53. NaN is lower than every int.
+fn cmp_int_float(int: i64, float: f64) -> Ordering {
+	// 2^63, the first float above every int.
+	const BOUND: f64 = 9_223_372_036_854_775_808.0;
+
+	if float.is_nan() || float < -BOUND {
+		Ordering::Greater
+	} else if float >= BOUND {
+		Ordering::Less
+	} else {
+		// The integral part is within bounds, and exactly convertible.
+		let integral = float.trunc();
+
+		int
+			.cmp(&(integral as i64))
+			.then_with(|| integral.partial_cmp(&float).expect("non-nan float comparison failed"))
+	}
+}
@@ -1,13 +1,14 @@
 use std::{
+	cell::Cell,
 	cmp::Ordering,
-	collections::{HashMap, BTreeMap},
+	collections::BTreeMap,
 	hash::{Hash, Hasher},
 	ops::Deref,
 };
 
 use gc::{Gc, GcCell, GcCellRef, GcCellRefMut, Finalize, Trace};
 
-use super::{IndexOutOfBounds, Value};
+use super::{DictMap, IndexOutOfBounds, Value};
 
 
 /// Common dict keys
@@ -17,24 +18,99 @@ pub mod keys {
 	thread_local! {
 		/// FINISHED string key.
 		pub static FINISHED: Value = "finished".into();
-		/// FINISHED string key.
+		/// KEY string key.
 		pub static KEY: Value = "key".into();
 		/// VALUE string key.
 		pub static VALUE: Value = "value".into();
+		/// INDEX string key.
+		pub static INDEX: Value = "index".into();
+		/// ITER string key.
+		pub static ITER: Value = "iter".into();
+	}
+
+
+	/// Keys of the iteration protocol, built once per runtime so that loops need not access
+	/// the thread locals on every iteration.
+	#[derive(Debug)]
+	pub struct Protocol {
+		/// The iter method of iterable dicts.
+		pub iter: Value,
+		/// Whether an iteration has finished.
+		pub finished: Value,
+		/// The value of an iteration.
+		pub value: Value,
+	}
+
+
+	impl Default for Protocol {
+		fn default() -> Self {
+			Self {
+				iter: "iter".into(),
+				finished: "finished".into(),
+				value: "value".into(),
+			}
+		}
 	}
 }
 
 
+thread_local! {
+	/// The last stamp given to a dict.
+	static STAMP: Cell<u64> = const { Cell::new(0) };
+}
+
+
+/// Get a stamp that was never given to any dict.
+fn fresh_stamp() -> u64 {
+	STAMP.with(
+		|stamp| {
+			let next = stamp.get() + 1;
+			stamp.set(next);
+			next
+		}
+	)
+}
+
+
 /// A dict in the language.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq)]
 #[derive(Trace, Finalize)]
-pub struct Dict(Gc<GcCell<HashMap<Value, Value>>>);
+pub struct Dict(Gc<Stamped>);
+
+
+/// A dict's contents, stamped so that caches can tell whether it changed.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+struct Stamped {
+	/// Renewed on every mutable borrow. As stamps are never reused, the address and
+	/// stamp of a dict identify its contents, even if its allocation is later reused.
+	#[unsafe_ignore_trace]
+	stamp: Cell<u64>,
+	dict: GcCell<DictMap>,
+}
+
+
+impl PartialEq for Stamped {
+	fn eq(&self, other: &Self) -> bool {
+		self.dict == other.dict
+	}
+}
+
+
+impl Eq for Stamped { }
 
 
 impl Dict {
 	/// Crate a new empty dict.
-	pub fn new(dict: HashMap<Value, Value>) -> Self {
-		Self(Gc::new(GcCell::new(dict)))
+	pub fn new(dict: DictMap) -> Self {
+		Self(
+			Gc::new(
+				Stamped {
+					stamp: Cell::new(fresh_stamp()),
+					dict: GcCell::new(dict),
+				}
+			)
+		)
 	}
 
 
@@ -44,15 +120,30 @@ impl Dict {
 	}
 
 
+	/// The address of the underlying allocation, which is shared by all copies.
+	pub fn address(&self) -> *const () {
+		self.0.deref() as *const Stamped as *const ()
+	}
+
+
+	/// The stamp of the current contents, which changes whenever the dict is borrowed
+	/// mutably.
+	pub fn stamp(&self) -> u64 {
+		self.0.stamp.get()
+	}
+
+
 	/// Borrow the hashmap.
-	pub fn borrow(&self) -> GcCellRef<HashMap<Value, Value>> {
-		self.0.deref().borrow()
+	pub fn borrow(&self) -> GcCellRef<DictMap> {
+		self.0.dict.borrow()
 	}
 
 
-	/// Borrow the hashmap mutably.
-	pub fn borrow_mut(&self) -> GcCellRefMut<HashMap<Value, Value>> {
-		self.0.deref().borrow_mut()
+	/// Borrow the hashmap mutably. This renews the stamp.
+	pub fn borrow_mut(&self) -> GcCellRefMut<DictMap> {
+		let dict = self.0.dict.borrow_mut();
+		self.0.stamp.set(fresh_stamp());
+		dict
 	}
 
 
@@ -93,6 +184,13 @@ impl Dict {
 }
 
 
+impl Default for Dict {
+	fn default() -> Self {
+		Self::new(DictMap::default())
+	}
+}
+
+
 /// We need PartialOrd in order to be able to store dicts as keys in other dicts.
 impl PartialOrd for Dict {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
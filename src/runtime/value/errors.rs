@@ -1,4 +1,9 @@
-use std::fmt::{self, Display};
+use std::{
+	convert::Infallible,
+	fmt::{self, Display},
+};
+
+use super::Type;
 
 
 /// Collection index out of bounds.
@@ -29,3 +34,37 @@ impl Display for EmptyCollection {
 
 
 impl std::error::Error for EmptyCollection { }
+
+
+/// Failed to convert a value to a Rust type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+	/// The value, or one of its elements, has an unexpected type.
+	UnexpectedType { expected: Type, found: Type },
+	/// The string is not valid UTF-8. The bytes are kept, as they may still be useful.
+	InvalidUtf8(Box<[u8]>),
+	/// Functions can't be moved out of the runtime.
+	Function,
+}
+
+
+impl Display for ConversionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::UnexpectedType { expected, found } => write!(f, "expected {}, found {}", expected, found),
+      Self::InvalidUtf8(_) => write!(f, "string is not valid utf-8"),
+      Self::Function => write!(f, "functions can't be moved out of the runtime"),
+    }
+  }
+}
+
+
+impl std::error::Error for ConversionError { }
+
+
+/// Conversions to Value itself never fail.
+impl From<Infallible> for ConversionError {
+  fn from(infallible: Infallible) -> Self {
+    match infallible { }
+  }
+}
@@ -9,8 +9,8 @@ use gc::{Finalize, Trace};
 
 /// Hush's float type.
 /// This type supports full ordering and hashing.
-/// NaN is lower and different than every other value, including itself, but the hash is
-/// the same for all NaN values.
+/// NaN is lower than every other value, and equal to itself, as are all NaN values.
+/// Zero is equal to negative zero. The hash is consistent with equality.
 #[derive(Debug, Default, Clone)]
 #[derive(Trace, Finalize)]
 pub struct Float(pub f64);
@@ -32,9 +32,11 @@ impl Float {
 
 impl PartialEq for Float {
 	fn eq(&self, other: &Self) -> bool {
-		!self.is_nan()
-			&& !other.is_nan()
-			&& self.0 == other.0
+		match (self.is_nan(), other.is_nan()) {
+			(true, true) => true,
+			(false, false) => self.0 == other.0,
+			_ => false,
+		}
 	}
 }
 
@@ -52,7 +54,8 @@ impl PartialOrd for Float {
 impl Ord for Float {
 	fn cmp(&self, other: &Self) -> Ordering {
 		match (self.is_nan(), other.is_nan()) {
-			(true, _) => Ordering::Less,
+			(true, true) => Ordering::Equal,
+			(true, false) => Ordering::Less,
 			(false, true) => Ordering::Greater,
 			(false, false) => self.0
 				.partial_cmp(&other.0)
@@ -67,6 +70,8 @@ impl Hash for Float {
 		let float =
 			if self.is_nan() {
 				f64::NAN // Make sure that the hash equals for all NaN values.
+			} else if self.0 == 0.0 {
+				0.0 // Negative zero must hash as zero.
 			} else {
 				self.0
 			};
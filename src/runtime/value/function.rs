@@ -1,15 +1,20 @@
 use std::{
+	cell::RefCell,
 	cmp::Ordering,
+	collections::HashMap,
 	fmt::{self, Debug},
 	hash::{Hash, Hasher},
+	rc::Rc,
 };
 
 use gc::{Gc, GcCell, Finalize, Trace};
 
-use crate::symbol;
+use crate::{symbol, syntax};
 use super::{
+	super::vm::{Code, FieldCaches},
 	mem,
 	program,
+	FxBuildHasher,
 	Panic,
 	Runtime,
 	SourcePos,
@@ -59,6 +64,70 @@ impl<T: NativeFun> From<T> for Function {
 }
 
 
+/// A program shared by the runtime and the functions it defines, so that it is kept alive
+/// for as long as any of them may execute it.
+#[derive(Debug, Clone)]
+#[derive(Finalize)]
+pub struct SharedProgram(Rc<Shared>);
+
+
+/// A program, and its blocks lowered for the virtual machine.
+#[derive(Debug)]
+struct Shared {
+	program: Rc<program::Program>,
+	/// Blocks are lowered on first execution, and identified by address, which is stable
+	/// as the program is never mutated.
+	code: RefCell<HashMap<*const program::Block, Rc<Code>, FxBuildHasher>>,
+}
+
+
+impl SharedProgram {
+	pub fn new(program: Rc<program::Program>) -> Self {
+		Self(
+			Rc::new(
+				Shared {
+					program,
+					code: RefCell::default(),
+				}
+			)
+		)
+	}
+
+
+	/// Get the lowered code of a block of the program, allocating its access caches in the
+	/// given caches when first lowered.
+	pub(in super::super) fn code(
+		&self,
+		block: &'static program::Block,
+		caches: &mut FieldCaches,
+	) -> Rc<Code> {
+		self.0.code
+			.borrow_mut()
+			.entry(block)
+			.or_insert_with(|| Rc::new(Code::new(block, caches)))
+			.clone()
+	}
+
+
+	/// Get an unbounded reference to the program, as the interpreter references the
+	/// program's nodes from values and call frames.
+	///
+	/// # Safety
+	/// The reference, and any references derived from it, must not be used after every
+	/// copy of the shared program has been dropped. The runtime keeps a copy while
+	/// executing the program, and so does every function defined in it.
+	pub unsafe fn get(&self) -> &'static program::Program {
+		&*Rc::as_ptr(&self.0.program)
+	}
+}
+
+
+/// Programs have no garbage-collected fields.
+unsafe impl Trace for SharedProgram {
+	gc::unsafe_empty_trace!();
+}
+
+
 /// A function object implemented in Hush code.
 /// May contain captured variables.
 #[derive(Debug)]
@@ -66,8 +135,11 @@ impl<T: NativeFun> From<T> for Function {
 pub struct HushFun {
 	/// How many parameters the function expects.
 	pub params: u32,
+	/// These refer to nodes of the program, which is kept alive by the function.
 	pub frame_info: &'static program::mem::FrameInfo,
 	pub body: &'static program::Block,
+	/// The program defining the function.
+	pub program: SharedProgram,
 	/// Captured variables, if any.
 	#[allow(clippy::type_complexity)]
 	pub context: Gc<Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>>,
@@ -80,6 +152,7 @@ impl HushFun {
 		params: u32,
 		frame_info: &'static program::mem::FrameInfo,
 		body: &'static program::Block,
+		program: SharedProgram,
 		context: Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>,
 		pos: SourcePos,
 	) -> Self {
@@ -87,6 +160,7 @@ impl HushFun {
 			params,
 			frame_info,
 			body,
+			program,
 			context: Gc::new(context),
 			pos,
 		}
@@ -99,6 +173,7 @@ impl HushFun {
 			params: self.params,
 			frame_info: self.frame_info,
 			body: self.body,
+			program: self.program.clone(),
 			context: self.context.clone(),
 			pos: self.pos.copy(),
 		}
@@ -154,12 +229,27 @@ pub struct CallContext<'a> {
 	/// The offset in the runtime's argument vector where the arguments for this call are
 	/// placed.
 	pub args_start: usize,
+	/// The argument expressions, when called directly from the source code. Calls from
+	/// native functions have no argument expressions.
+	arg_exprs: &'static [program::Expr],
 	/// The source position of the call, which allows proper location of panics.
 	pub pos: SourcePos,
 }
 
 
 impl<'a> CallContext<'a> {
+	/// Create the context for a call with the given argument expressions.
+	pub(in super::super) fn new(
+		runtime: &'a mut Runtime,
+		obj: Value,
+		args_start: usize,
+		arg_exprs: &'static [program::Expr],
+		pos: SourcePos,
+	) -> Self {
+		Self { runtime, obj, args_start, arg_exprs, pos }
+	}
+
+
 	/// Get the slice of arguments.
 	pub fn args(&self) -> &[Value] {
 		&self.runtime.arguments[self.args_start..]
@@ -172,19 +262,53 @@ impl<'a> CallContext<'a> {
 	}
 
 
+	/// Get the source position of the given argument, which allows panics to point at the
+	/// offending argument. Defaults to the position of the call.
+	pub fn arg_pos(&self, ix: usize) -> SourcePos {
+		self.arg_exprs()
+			.get(ix)
+			.map(|expr| expr.pos().into())
+			.unwrap_or_else(|| self.pos.copy())
+	}
+
+
+	/// Get the argument expressions, which are empty for calls from native functions.
+	pub fn arg_exprs(&self) -> &[program::Expr] {
+		self.arg_exprs
+	}
+
+
 	/// Get the symbol interner.
 	pub fn interner(&self) -> & symbol::Interner {
 		&self.runtime.interner
 	}
 
 
+	/// Get the source text of each argument of the call, if the source is available.
+	pub fn args_source(&self) -> Option<Vec<String>> {
+		let source = self.interner().source(self.pos.path)?;
+		let arguments = syntax::SourcePos::from(&self.pos).call_arguments(source)?;
+
+		// Calls through bind or pipes don't match the source arguments.
+		if arguments.len() == self.args().len() {
+			Some(arguments)
+		} else {
+			None
+		}
+	}
+
+
 	pub fn call(
 		&mut self,
 		obj: Value,
 		function: &Function,
 		args_start: usize,
 	) -> Result<Value, Panic> {
-		self.runtime.call(obj, function, args_start, self.pos.copy())
+		// Arguments forwarded unchanged keep their positions.
+		let forwarded = args_start == self.args_start && self.args().len() == self.arg_exprs().len();
+		let args = if forwarded { self.arg_exprs } else { &[] };
+
+		self.runtime.call(obj, function, args_start, args, self.pos.copy())
 	}
 }
 
@@ -197,6 +321,9 @@ pub trait NativeFun: Trace + Finalize + 'static {
 	fn name(&self) -> &'static str;
 	/// Invoke the function.
 	fn call(&self, context: CallContext) -> Result<Value, Panic>;
+	/// Called when a for loop stops iterating this function before it finished, due to
+	/// a break, return or panic. Iterators over external resources may release them here.
+	fn stop_iteration(&self) { }
 }
 
 
@@ -222,6 +349,12 @@ impl RustFun {
 	pub fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		self.0.call(context)
 	}
+
+
+	/// Notify the function that a for loop stopped iterating it early.
+	pub fn stop_iteration(&self) {
+		self.0.stop_iteration()
+	}
 }
 
 
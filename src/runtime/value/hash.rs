@@ -0,0 +1,92 @@
+use std::{
+	collections::HashMap,
+	hash::{BuildHasherDefault, Hasher},
+};
+
+use super::Value;
+
+
+/// A fast non-cryptographic hasher, using the algorithm from Firefox and rustc.
+/// Dict keys are mostly short strings and small integers, for which SipHash is needlessly
+/// slow. This hasher is not resistant to collision attacks, which is why dicts can use the
+/// standard hasher instead with the `secure-hash` feature, for scripts that handle
+/// untrusted input as dict keys.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxHasher {
+	hash: u64,
+}
+
+
+impl FxHasher {
+	const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+	fn add(&mut self, word: u64) {
+		self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+	}
+}
+
+
+impl Hasher for FxHasher {
+	fn write(&mut self, bytes: &[u8]) {
+		// Hash word-sized chunks, so that short keys take only a few rounds.
+		let mut chunks = bytes.chunks_exact(8);
+		for chunk in &mut chunks {
+			let mut word = [0; 8];
+			word.copy_from_slice(chunk);
+			self.add(u64::from_le_bytes(word));
+		}
+
+		let mut rest = chunks.remainder();
+		if rest.len() >= 4 {
+			let mut word = [0; 4];
+			word.copy_from_slice(&rest[..4]);
+			self.add(u32::from_le_bytes(word).into());
+			rest = &rest[4..];
+		}
+
+		for &byte in rest {
+			self.add(byte.into());
+		}
+	}
+
+	fn write_u8(&mut self, int: u8) {
+		self.add(int.into());
+	}
+
+	fn write_u16(&mut self, int: u16) {
+		self.add(int.into());
+	}
+
+	fn write_u32(&mut self, int: u32) {
+		self.add(int.into());
+	}
+
+	fn write_u64(&mut self, int: u64) {
+		self.add(int);
+	}
+
+	fn write_usize(&mut self, int: usize) {
+		self.add(int as u64);
+	}
+
+	fn finish(&self) -> u64 {
+		self.hash
+	}
+}
+
+
+/// Hasher for interpreter-internal maps, whose keys are not controlled by scripts.
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+
+/// Hasher for dicts.
+#[cfg(not(feature = "secure-hash"))]
+pub type DictHasher = FxBuildHasher;
+
+/// Hasher for dicts, resistant to collision attacks.
+#[cfg(feature = "secure-hash")]
+pub type DictHasher = std::collections::hash_map::RandomState;
+
+
+/// The hashmap backing dicts.
+pub type DictMap<K = Value, V = Value> = HashMap<K, V, DictHasher>;
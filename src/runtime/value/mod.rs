@@ -1,15 +1,24 @@
 #[macro_use]
 mod ops;
 mod array;
+mod cmp;
 mod dict;
 mod error;
 mod errors;
 mod float;
 mod fmt;
 mod function;
+mod hash;
+mod snapshot;
 mod string;
 
-use std::{ffi::OsString, fmt::Display};
+use std::{
+	collections::HashMap,
+	convert::TryFrom,
+	ffi::OsString,
+	fmt::Display,
+	hash::Hash,
+};
 
 use gc::{Finalize, Trace};
 
@@ -23,9 +32,10 @@ use super::{
 pub use array::Array;
 pub use dict::{keys, Dict};
 pub use error::Error;
-pub use function::{CallContext, Function, HushFun, RustFun, NativeFun};
+pub use function::{CallContext, Function, HushFun, RustFun, NativeFun, SharedProgram};
 pub use float::Float;
-pub use errors::{EmptyCollection, IndexOutOfBounds};
+pub use hash::{DictMap, FxBuildHasher};
+pub use errors::{ConversionError, EmptyCollection, IndexOutOfBounds};
 pub use string::Str;
 
 
@@ -101,7 +111,8 @@ impl<'a> From<&'a Value> for Type {
 
 
 /// A value of dynamic type in the language.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Equality and hashing follow the comparison policy, in the cmp module.
+#[derive(Debug, PartialOrd, Ord)]
 #[derive(Trace, Finalize)]
 pub enum Value {
 	Nil,
@@ -244,6 +255,64 @@ impl From<Vec<Value>> for Value {
 }
 
 
+impl From<Vec<u8>> for Value {
+	fn from(string: Vec<u8>) -> Self {
+		let string: Str = string.into();
+		string.into()
+	}
+}
+
+
+impl<K, V, S> From<HashMap<K, V, S>> for Value
+where
+	K: Into<Value>,
+	V: Into<Value>,
+{
+	fn from(dict: HashMap<K, V, S>) -> Self {
+		let dict = dict
+			.into_iter()
+			.map(|(key, value)| (key.into(), value.into()))
+			.collect();
+
+		Self::Dict(Dict::new(dict))
+	}
+}
+
+
+/// Instantiate a template. Containers are always fresh, as they are mutable.
+impl<'a> From<&'a program::Template> for Value {
+	fn from(template: &'a program::Template) -> Self {
+		match template {
+			program::Template::Nil => Self::Nil,
+			program::Template::Bool(b) => Self::Bool(*b),
+			program::Template::Int(int) => Self::Int(*int),
+			program::Template::Float(float) => (*float).into(),
+			program::Template::Byte(byte) => Self::Byte(*byte),
+			program::Template::String(string) => string.copy().into(),
+
+			program::Template::Array(items) => {
+				let array = items
+					.iter()
+					.map(Into::into)
+					.collect::<Vec<Value>>();
+
+				Self::Array(Array::new(array))
+			}
+
+			program::Template::Dict(items) => {
+				let mut dict = DictMap::with_capacity_and_hasher(items.len(), Default::default());
+
+				for (key, value) in items.iter() {
+					dict.insert(key.into(), value.into());
+				}
+
+				Self::Dict(Dict::new(dict))
+			}
+		}
+	}
+}
+
+
 impl From<HushFun> for Value {
 	fn from(fun: HushFun) -> Self {
 		let fun: Function = fun.into();
@@ -290,3 +359,105 @@ where
 		}
 	}
 }
+
+
+impl Value {
+	/// Borrow the bytes of a string value.
+	pub fn as_bytes(&self) -> Result<&[u8], ConversionError> {
+		match self {
+			Self::String(string) => Ok(string.as_bytes()),
+			other => Err(other.unexpected(Type::String)),
+		}
+	}
+
+
+	/// The error for converting this value to the given type.
+	fn unexpected(&self, expected: Type) -> ConversionError {
+		ConversionError::UnexpectedType { expected, found: self.get_type() }
+	}
+}
+
+
+macro_rules! try_from_variant {
+	($variant: ident, $type: ty, $convert: expr) => {
+		impl TryFrom<Value> for $type {
+			type Error = ConversionError;
+
+			fn try_from(value: Value) -> Result<Self, Self::Error> {
+				match &value {
+					Value::$variant(value) => Ok($convert(value)),
+					other => Err(other.unexpected(Type::$variant)),
+				}
+			}
+		}
+	}
+}
+
+try_from_variant!(Bool, bool, |value: &bool| *value);
+try_from_variant!(Int, i64, |value: &i64| *value);
+try_from_variant!(Float, f64, |value: &Float| value.0);
+try_from_variant!(Byte, u8, |value: &u8| *value);
+
+
+impl TryFrom<Value> for String {
+	type Error = ConversionError;
+
+	fn try_from(value: Value) -> Result<Self, Self::Error> {
+		let bytes = value.as_bytes()?;
+
+		std::str::from_utf8(bytes)
+			.map(ToOwned::to_owned)
+			.map_err(|_| ConversionError::InvalidUtf8(bytes.into()))
+	}
+}
+
+
+/// Arrays are converted element-wise. The array is copied, as it may be shared.
+impl<T> TryFrom<Value> for Vec<T>
+where
+	T: TryFrom<Value>,
+	T::Error: Into<ConversionError>,
+{
+	type Error = ConversionError;
+
+	fn try_from(value: Value) -> Result<Self, Self::Error> {
+		match &value {
+			Value::Array(array) => array
+				.borrow()
+				.iter()
+				.map(|item| T::try_from(item.copy()).map_err(Into::into))
+				.collect(),
+
+			other => Err(other.unexpected(Type::Array)),
+		}
+	}
+}
+
+
+/// Dicts are converted entry-wise. The dict is copied, as it may be shared.
+impl<K, V> TryFrom<Value> for HashMap<K, V>
+where
+	K: TryFrom<Value> + Eq + Hash,
+	K::Error: Into<ConversionError>,
+	V: TryFrom<Value>,
+	V::Error: Into<ConversionError>,
+{
+	type Error = ConversionError;
+
+	fn try_from(value: Value) -> Result<Self, Self::Error> {
+		match &value {
+			Value::Dict(dict) => dict
+				.borrow()
+				.iter()
+				.map(
+					|(key, value)| Ok((
+						K::try_from(key.copy()).map_err(Into::into)?,
+						V::try_from(value.copy()).map_err(Into::into)?,
+					))
+				)
+				.collect(),
+
+			other => Err(other.unexpected(Type::Dict)),
+		}
+	}
+}
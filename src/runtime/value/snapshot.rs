@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use super::{Array, ConversionError, Dict, Error, FxBuildHasher, Value};
+
+
+/// A deep copy of a value, which may be sent to other threads and rebuilt there, even in
+/// another runtime. Arrays and dicts are stored in a table, so that sharing and cycles are
+/// preserved. Functions can't be snapshotted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+	root: Node,
+	collections: Vec<Collection>,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+	Nil,
+	Bool(bool),
+	Byte(u8),
+	Int(i64),
+	Float(f64),
+	String(Box<[u8]>),
+	Error {
+		description: Box<[u8]>,
+		context: Box<Node>,
+	},
+	/// Index in the collection table.
+	Collection(usize),
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum Collection {
+	Array(Box<[Node]>),
+	Dict(Box<[(Node, Node)]>),
+}
+
+
+impl Value {
+	/// Take a snapshot of the value, which may be sent to other threads.
+	pub fn snapshot(&self) -> Result<Snapshot, ConversionError> {
+		let mut builder = SnapshotBuilder::default();
+		let root = builder.node(self)?;
+
+		Ok(
+			Snapshot {
+				root,
+				collections: builder.collections
+					.into_iter()
+					.map(|collection| collection.expect("unfinished collection"))
+					.collect(),
+			}
+		)
+	}
+}
+
+
+impl Snapshot {
+	/// Rebuild the value in the current thread.
+	pub fn to_value(&self) -> Value {
+		let mut values = Vec::new();
+		values.resize_with(self.collections.len(), || None);
+		self.value(&self.root, &mut values)
+	}
+
+
+	/// Rebuild a node. Collections are filled depth first, so that collections used as
+	/// dict keys are complete before being hashed, unless there is a cycle.
+	fn value(&self, node: &Node, values: &mut Vec<Option<Value>>) -> Value {
+		match node {
+			Node::Nil => Value::Nil,
+			Node::Bool(b) => Value::Bool(*b),
+			Node::Byte(byte) => Value::Byte(*byte),
+			Node::Int(int) => Value::Int(*int),
+			Node::Float(float) => Value::Float((*float).into()),
+			Node::String(string) => Value::String(string.as_ref().into()),
+			Node::Error { description, context } => {
+				let context = self.value(context, values);
+				Error::new(description.as_ref().into(), context).into()
+			}
+
+			Node::Collection(ix) => {
+				if let Some(value) = &values[*ix] {
+					return value.copy();
+				}
+
+				match &self.collections[*ix] {
+					Collection::Array(items) => {
+						let array = Array::new(Vec::with_capacity(items.len()));
+						values[*ix] = Some(array.copy().into());
+
+						for item in items.iter() {
+							let item = self.value(item, values);
+							array.borrow_mut().push(item);
+						}
+
+						array.into()
+					}
+
+					Collection::Dict(entries) => {
+						let dict = Dict::default();
+						values[*ix] = Some(dict.copy().into());
+
+						for (key, value) in entries.iter() {
+							let key = self.value(key, values);
+							let value = self.value(value, values);
+							dict.insert(key, value);
+						}
+
+						dict.into()
+					}
+				}
+			}
+		}
+	}
+}
+
+
+#[derive(Default)]
+struct SnapshotBuilder {
+	/// Collections are None while being visited.
+	collections: Vec<Option<Collection>>,
+	/// Indices of visited collections by address.
+	visited: HashMap<*const (), usize, FxBuildHasher>,
+}
+
+
+impl SnapshotBuilder {
+	fn node(&mut self, value: &Value) -> Result<Node, ConversionError> {
+		Ok(
+			match value {
+				Value::Nil => Node::Nil,
+				Value::Bool(b) => Node::Bool(*b),
+				Value::Byte(byte) => Node::Byte(*byte),
+				Value::Int(int) => Node::Int(*int),
+				Value::Float(float) => Node::Float(float.0),
+				Value::String(string) => Node::String(string.as_bytes().into()),
+				Value::Error(error) => Node::Error {
+					description: error.description.as_bytes().into(),
+					context: Box::new(self.node(&error.context.borrow())?),
+				},
+
+				Value::Array(array) => {
+					let ix = match self.enter(array.address()) {
+						Ok(ix) => ix,
+						Err(ix) => return Ok(Node::Collection(ix)),
+					};
+
+					let items = array
+						.borrow()
+						.iter()
+						.map(|item| self.node(item))
+						.collect::<Result<_, _>>()?;
+
+					self.collections[ix] = Some(Collection::Array(items));
+					Node::Collection(ix)
+				}
+
+				Value::Dict(dict) => {
+					let ix = match self.enter(dict.address()) {
+						Ok(ix) => ix,
+						Err(ix) => return Ok(Node::Collection(ix)),
+					};
+
+					let entries = dict
+						.borrow()
+						.iter()
+						.map(|(key, value)| Ok((self.node(key)?, self.node(value)?)))
+						.collect::<Result<_, ConversionError>>()?;
+
+					self.collections[ix] = Some(Collection::Dict(entries));
+					Node::Collection(ix)
+				}
+
+				Value::Function(_) => return Err(ConversionError::Function),
+			}
+		)
+	}
+
+
+	/// Register a collection, returning its index, or the existing index as an error if it
+	/// has been visited already.
+	fn enter(&mut self, address: *const ()) -> Result<usize, usize> {
+		if let Some(&ix) = self.visited.get(&address) {
+			return Err(ix);
+		}
+
+		let ix = self.collections.len();
+		self.collections.push(None);
+		self.visited.insert(address, ix);
+
+		Ok(ix)
+	}
+}
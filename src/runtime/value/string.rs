@@ -1,20 +1,31 @@
 use std::{
     convert::TryInto,
     ffi::{OsString, OsStr},
+    hash::{Hash, Hasher},
     ops::Deref,
-    os::unix::ffi::{OsStringExt, OsStrExt},
     path::PathBuf,
+    rc::Rc,
 };
 
-use gc::{Gc, Finalize, Trace};
+use gc::{Finalize, Trace};
 
+use crate::ffi::{OsStringExt, OsStrExt};
 use super::{IndexOutOfBounds, Value};
 
 
-/// Strings in Hush are immutable.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[derive(Trace, Finalize)]
-pub struct Str(Gc<Box<[u8]>>);
+/// Strings in Hush are immutable. As they can't reference other values, they are reference
+/// counted rather than garbage collected, which allows programs to hold strings. Copies share
+/// the same allocation, so copying a string is cheap regardless of its length. The bytes
+/// are kept in a vector, so that a string which isn't shared may be appended to in place.
+#[derive(Debug, PartialOrd, Ord)]
+#[derive(Finalize)]
+pub struct Str(Rc<Vec<u8>>);
+
+
+/// Str has no garbage-collected fields.
+unsafe impl Trace for Str {
+	gc::unsafe_empty_trace!();
+}
 
 
 impl Str {
@@ -24,6 +35,33 @@ impl Str {
 	}
 
 
+	/// The address of the underlying allocation, which is shared by all copies.
+	pub fn address(&self) -> *const () {
+		Rc::as_ptr(&self.0) as *const ()
+	}
+
+
+	/// Create a new string with the contents of both strings, in a single allocation.
+	pub fn concat(&self, other: &Self) -> Self {
+		let mut bytes = Vec::with_capacity(self.len() + other.len());
+		bytes.extend_from_slice(&self.0);
+		bytes.extend_from_slice(&other.0);
+
+		bytes.into()
+	}
+
+
+	/// Append the contents of the other string. The allocation is reused if this is the only
+	/// copy of the string, which makes repeated appends take amortized linear time.
+	/// Otherwise, the copies are left untouched, and this becomes a new string.
+	pub fn append(&mut self, other: &Self) {
+		match Rc::get_mut(&mut self.0) {
+			Some(bytes) => bytes.extend_from_slice(&other.0),
+			None => *self = self.concat(other),
+		}
+	}
+
+
 	/// Get the underlying slice.
 	pub fn as_bytes(&self) -> &[u8] {
 		self.as_ref()
@@ -63,9 +101,29 @@ impl Str {
 }
 
 
+impl PartialEq for Str {
+	fn eq(&self, other: &Self) -> bool {
+		// Copies of the same string share the allocation, which spares comparing the contents.
+		Rc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+	}
+}
+
+
+impl Eq for Str { }
+
+
+impl Hash for Str {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// Hash the whole slice at once, which the dict hasher consumes in word-sized chunks.
+		state.write_usize(self.0.len());
+		state.write(&self.0);
+	}
+}
+
+
 impl AsRef<[u8]> for Str {
 	fn as_ref(&self) -> &[u8] {
-		self.0.deref().deref()
+		self.0.deref()
 	}
 }
 
@@ -79,25 +137,21 @@ impl AsRef<OsStr> for Str {
 
 impl<'a> From<&'a [u8]> for Str {
 	fn from(string: &'a [u8]) -> Self {
-		Self(
-			Gc::new(string.into())
-		)
+		string.to_vec().into()
 	}
 }
 
 
 impl From<Box<[u8]>> for Str {
 	fn from(string: Box<[u8]>) -> Self {
-		Self(
-			Gc::new(string)
-		)
+		string.into_vec().into()
 	}
 }
 
 
 impl From<Vec<u8>> for Str {
 	fn from(vec: Vec<u8>) -> Self {
-		Self::from(vec.into_boxed_slice())
+		Self(Rc::new(vec))
 	}
 }
 
@@ -119,14 +173,14 @@ impl From<Box<str>> for Str {
 
 impl From<String> for Str {
 	fn from(string: String) -> Self {
-		string.into_boxed_str().into()
+		string.into_bytes().into()
 	}
 }
 
 
 impl From<OsString> for Str {
 	fn from(string: OsString) -> Self {
-		string.into_vec().into_boxed_slice().into()
+		string.into_vec().into()
 	}
 }
 
@@ -0,0 +1,563 @@
+use std::mem;
+
+use crate::fmt::FmtString;
+use super::{
+	super::{
+		flow::Flow,
+		mem::SlotIx,
+		program,
+		Array,
+		Dict,
+		DictMap,
+		Function,
+		Panic,
+		Runtime,
+		Value,
+	},
+	Code,
+	Instruction,
+	Operand,
+};
+
+
+/// A loop in progress.
+struct Loop {
+	/// The iterator of for loops, which is stopped when leaving the loop early.
+	iter: Option<Function>,
+	/// How many operands were on the stack when entering the loop.
+	base: usize,
+	/// The instruction following the loop.
+	exit: u32,
+}
+
+
+impl Loop {
+	/// Stop the iterator, if any.
+	fn stop(&self) {
+		if let Some(Function::Rust(ref fun)) = self.iter {
+			fun.stop_iteration();
+		}
+	}
+}
+
+
+impl Runtime {
+	/// Execute lowered code, returning the value of the block, or the returned value.
+	/// The tail call callback is invoked right before the call in the last statement, if any.
+	pub(in super::super) fn exec<F>(&mut self, code: &Code, tail_call: F) -> Result<Flow, Panic>
+	where
+		F: FnOnce(&mut Self),
+	{
+		let base = self.arguments.len();
+		let mut loops = Vec::new();
+
+		let result = self.run(code, &mut loops, tail_call);
+
+		// Loops left by returning or panicking stop their iterators, innermost first.
+		for record in loops.iter().rev() {
+			record.stop();
+		}
+
+		self.arguments.truncate(base);
+
+		result
+	}
+
+
+	/// The interpreter loop.
+	fn run<F>(&mut self, code: &Code, loops: &mut Vec<Loop>, tail_call: F) -> Result<Flow, Panic>
+	where
+		F: FnOnce(&mut Self),
+	{
+		let mut tail_call = Some(tail_call);
+		let mut pc = 0;
+
+		macro_rules! pop {
+			() => { self.arguments.pop().expect("operand stack underflow") }
+		}
+
+		// Handle the flow of code delegated to the tree-walker.
+		macro_rules! flow {
+			($flow: expr) => {
+				match $flow {
+					Flow::Regular(value) => value,
+					Flow::Return(value) => return Ok(Flow::Return(value)),
+					Flow::Break => match self.break_loop(loops) {
+						Some(exit) => {
+							pc = exit;
+							continue;
+						}
+						None => return Ok(Flow::Break),
+					}
+				}
+			}
+		}
+
+		while let Some(instruction) = code.instructions.get(pc) {
+			pc += 1;
+
+			match instruction {
+				Instruction::Statement(statement) => self.enter_statement(statement)?,
+
+				Instruction::Nil => self.arguments.push(Value::Nil),
+
+				Instruction::Constant(ix) => self.arguments.push(code.constants[*ix as usize].copy()),
+
+				Instruction::Load(slot_ix) => {
+					let value = self.stack.fetch(slot_ix.copy());
+					self.arguments.push(value);
+				}
+
+				Instruction::Store(slot_ix) => {
+					let value = pop!();
+					self.stack.store(slot_ix.copy(), value);
+				}
+
+				Instruction::Pop => { pop!(); },
+
+				Instruction::Jump(target) => pc = *target as usize,
+
+				Instruction::Condition { condition, otherwise } => match pop!() {
+					Value::Bool(true) => (),
+					Value::Bool(false) => pc = *otherwise as usize,
+					value => return Err(Panic::invalid_condition(value, condition.pos().into())),
+				},
+
+				Instruction::While { exit } => loops.push(
+					Loop { iter: None, base: self.arguments.len(), exit: *exit }
+				),
+
+				Instruction::WhileCondition(condition) => {
+					let pos = condition.pos().into();
+
+					match pop!() {
+						Value::Bool(true) => {
+							self.check_cancelled(&pos)?;
+							self.consume_fuel(1, &pos)?;
+						}
+
+						Value::Bool(false) => pc = Self::exit_loop(loops),
+
+						value => {
+							loops.pop();
+							return Err(Panic::invalid_condition(value, pos));
+						}
+					}
+				}
+
+				Instruction::For { expr, exit } => {
+					let value = pop!();
+					let iter = self.iterator(value, expr.pos().into())?;
+
+					loops.push(Loop { iter: Some(iter), base: self.arguments.len(), exit: *exit });
+				}
+
+				Instruction::ForNext { expr, slot_ix } => {
+					let iter = match loops.last() {
+						Some(Loop { iter: Some(iter), .. }) => iter,
+						_ => unreachable!("for loop without iterator"),
+					};
+
+					match self.next_item(iter, &expr.pos().into()) {
+						Ok(Some(value)) => self.stack.store(slot_ix.copy(), value),
+						Ok(None) => pc = Self::exit_loop(loops),
+
+						// Iterators that fail are not stopped.
+						Err(panic) => {
+							loops.pop();
+							return Err(panic);
+						}
+					}
+				}
+
+				Instruction::Break => match self.break_loop(loops) {
+					Some(exit) => pc = exit,
+					None => return Ok(Flow::Break),
+				},
+
+				Instruction::Return => return Ok(Flow::Return(pop!())),
+
+				Instruction::Function(expr) => {
+					let value = match expr {
+						program::Expr::Literal { literal, pos } => flow!(self.eval_literal(literal, *pos)?),
+						_ => unreachable!("function literal expected"),
+					};
+
+					self.arguments.push(value);
+				}
+
+				Instruction::Array(count) => {
+					let items = self.arguments.split_off(self.arguments.len() - *count as usize);
+					self.arguments.push(Array::new(items).into());
+				}
+
+				Instruction::Dict(count) => {
+					let start = self.arguments.len() - 2 * *count as usize;
+					let mut dict = DictMap::with_capacity_and_hasher(*count as usize, Default::default());
+
+					let mut items = self.arguments.drain(start..);
+					while let (Some(key), Some(value)) = (items.next(), items.next()) {
+						dict.insert(key, value);
+					}
+					drop(items);
+
+					self.arguments.push(Dict::new(dict).into());
+				}
+
+				Instruction::Template(template) => self.arguments.push((*template).into()),
+
+				Instruction::Interpolation(count) => {
+					let start = self.arguments.len() - *count as usize;
+					let mut string = Vec::new();
+
+					for part in self.arguments.drain(start..) {
+						match part {
+							Value::String(ref part) => string.extend_from_slice(part.as_ref()),
+							value => string.extend_from_slice(value.fmt_string(&self.interner).as_bytes()),
+						}
+					}
+
+					self.arguments.push(string.into_boxed_slice().into());
+				}
+
+				Instruction::Unary(expr) => {
+					let value = match expr {
+						program::Expr::UnaryOp { op, operand, .. } => {
+							let value = pop!();
+							flow!(Self::unary_value(op, value, operand.pos().into())?)
+						}
+						_ => unreachable!("unary operator expected"),
+					};
+
+					self.arguments.push(value);
+				}
+
+				Instruction::Binary { expr, left: left_operand, right: right_operand } => {
+					let (left_operand, right_operand) = (*left_operand, *right_operand);
+
+					let value = match expr {
+						program::Expr::BinaryOp { left, op, right, pos } => {
+							// The right operand is on top of the left one, if both are on the stack.
+							let left_depth = match right_operand {
+								Operand::Stack => 1,
+								_ => 0,
+							};
+
+							let ints = (
+								self.int_operand(code, left_operand, left_depth),
+								self.int_operand(code, right_operand, 0),
+							);
+
+							let int_value = match ints {
+								(Some(left_int), Some(right_int)) => Self::int_op(op, left_int, right_int),
+								_ => None,
+							};
+
+							match int_value {
+								Some(value) => {
+									self.pop_operand(right_operand);
+									self.pop_operand(left_operand);
+									value
+								}
+
+								None => {
+									let right_value = self.operand(code, right_operand);
+									let left_value = self.operand(code, left_operand);
+
+									self.binary_value(
+										op,
+										left_value,
+										left.pos().into(),
+										right_value,
+										right.pos().into(),
+										&pos.into(),
+									)?
+								}
+							}
+						}
+						_ => unreachable!("binary operator expected"),
+					};
+
+					self.arguments.push(value);
+				}
+
+				Instruction::Append { expr, slot_ix, left: left_operand, right: right_operand } => match expr {
+					program::Expr::BinaryOp { left, op, right, pos } => {
+						let right_value = self.operand(code, *right_operand);
+						let left_value = self.operand(code, *left_operand);
+
+						let value = self.append(
+							slot_ix.copy(),
+							op,
+							left_value,
+							left.pos().into(),
+							right_value,
+							right.pos().into(),
+							&pos.into(),
+						)?;
+
+						self.stack.store(slot_ix.copy(), value);
+					}
+					_ => unreachable!("binary operator expected"),
+				},
+
+				Instruction::LogicalLeft { expr, end } => match expr {
+					program::Expr::BinaryOp { left, op, .. } => {
+						let value = pop!();
+
+						match Self::logical_left(op, value, left.pos().into())? {
+							Ok(value) => {
+								self.arguments.push(value);
+								pc = *end as usize;
+							}
+							Err(left) => self.arguments.push(left),
+						}
+					}
+					_ => unreachable!("binary operator expected"),
+				},
+
+				Instruction::LogicalRight(expr) => match expr {
+					program::Expr::BinaryOp { op, right, .. } => {
+						let right_value = pop!();
+						let left_value = pop!();
+
+						let value = Self::logical_right(op, left_value, right_value, right.pos().into())?;
+						self.arguments.push(value);
+					}
+					_ => unreachable!("binary operator expected"),
+				},
+
+				Instruction::Access { expr, cache } => match expr {
+					program::Expr::Access { object, field, .. } => {
+						let field_value = pop!();
+						let obj = pop!();
+
+						let value = self.field_caches.access(
+							*cache,
+							&obj,
+							field_value,
+							object.pos().into(),
+							field.pos().into(),
+						)?;
+						self.arguments.push(value);
+					}
+					_ => unreachable!("access expected"),
+				},
+
+				Instruction::AccessLocal { expr, slot_ix, cache } => match expr {
+					program::Expr::Access { object, field, .. } => {
+						let field_value = pop!();
+						let caches = &mut self.field_caches;
+
+						let value = self.stack.fetch_with(
+							slot_ix.copy(),
+							|obj| caches.access(*cache, obj, field_value, object.pos().into(), field.pos().into())
+						)?;
+						self.arguments.push(value);
+					}
+					_ => unreachable!("access expected"),
+				},
+
+				Instruction::Method { expr, cache } => match expr {
+					program::Expr::Access { object, field, .. } => {
+						let field_value = pop!();
+						let obj = self.arguments.last().expect("operand stack underflow");
+
+						let value = self.field_caches.access(
+							*cache,
+							obj,
+							field_value,
+							object.pos().into(),
+							field.pos().into(),
+						)?;
+						self.arguments.push(value);
+					}
+					_ => unreachable!("access expected"),
+				},
+
+				Instruction::LoadCallee { function, slot_ix } => {
+					let value = self.stack.fetch_with(
+						slot_ix.copy(),
+						|value| match value {
+							Value::Function(fun) => Ok(Value::Function(fun.copy())),
+							other => Err(Panic::invalid_call(other.copy(), function.pos().into())),
+						}
+					)?;
+
+					self.arguments.push(value);
+				}
+
+				Instruction::Callee(function) => {
+					if !matches!(self.arguments.last(), Some(Value::Function(_))) {
+						return Err(Panic::invalid_call(pop!(), function.pos().into()));
+					}
+				}
+
+				Instruction::Call { expr, method, tail } => {
+					let (args, pos) = match expr {
+						program::Expr::Call { args, pos, .. } => (args, pos),
+						_ => unreachable!("call expected"),
+					};
+
+					let args_start = self.arguments.len() - args.len();
+					let args = self.spread(args_start, args)?;
+					let function = mem::take(&mut self.arguments[args_start - 1]);
+					let obj = if *method {
+						mem::take(&mut self.arguments[args_start - 2])
+					} else {
+						Value::default()
+					};
+
+					if *tail {
+						if let Some(tail_call) = tail_call.take() {
+							tail_call(self);
+						}
+					}
+
+					let value = match function {
+						Value::Function(ref function) => self.call(obj, function, args_start, args, pos.into())?,
+						_ => unreachable!("callee is checked before the arguments"),
+					};
+
+					self.arguments.truncate(args_start - 1 - *method as usize);
+					self.arguments.push(value);
+				}
+
+				Instruction::SetField(left) => match left {
+					program::Lvalue::Access { object, field, pos } => {
+						let field_value = pop!();
+						let obj = pop!();
+						let value = pop!();
+
+						Self::set_field(
+							obj,
+							object.pos().into(),
+							field_value,
+							field.pos().into(),
+							value,
+							pos.into(),
+						)?;
+					}
+					_ => unreachable!("access expected"),
+				},
+
+				Instruction::Assign(left) => {
+					let value = pop!();
+					flow!(self.assign(left, value)?);
+				}
+
+				Instruction::MultipleAssign(left) => {
+					flow!(self.multiple_assign(left)?);
+				}
+
+				#[cfg(feature = "system")]
+				Instruction::CommandBlock(expr) => match expr {
+					program::Expr::CommandBlock { block, pos } => {
+						let value = self.eval_command_block(block, pos.into())?;
+						self.arguments.push(value);
+					}
+					_ => unreachable!("command block expected"),
+				},
+
+				#[cfg(not(feature = "system"))]
+				Instruction::CommandBlock(expr) => return Err(
+					Panic::unsupported_operation("command blocks", expr.pos().into())
+				),
+			}
+		}
+
+		Ok(Flow::Regular(pop!()))
+	}
+
+
+	/// Get an operand of a binary operator, popping it if it's on the stack.
+	fn operand(&mut self, code: &Code, operand: Operand) -> Value {
+		match operand {
+			Operand::Stack => self.arguments.pop().expect("operand stack underflow"),
+			Operand::Slot(slot_ix) => self.stack.fetch(SlotIx(slot_ix)),
+			Operand::Constant(ix) => code.constants[ix as usize].copy(),
+		}
+	}
+
+
+	/// Discard an operand of a binary operator, if it's on the stack.
+	fn pop_operand(&mut self, operand: Operand) {
+		if let Operand::Stack = operand {
+			self.arguments.pop();
+		}
+	}
+
+
+	/// Peek an integer operand of a binary operator, without copying it. Operands on the
+	/// stack are found at the given depth.
+	fn int_operand(&self, code: &Code, operand: Operand, depth: usize) -> Option<i64> {
+		let int = |value: &Value| match value {
+			Value::Int(int) => Some(*int),
+			_ => None,
+		};
+
+		match operand {
+			Operand::Stack => int(&self.arguments[self.arguments.len() - 1 - depth]),
+			Operand::Slot(slot_ix) => self.stack.fetch_with(SlotIx(slot_ix), int),
+			Operand::Constant(ix) => int(&code.constants[ix as usize]),
+		}
+	}
+
+
+	/// Apply a binary operator to integers, which dominate loop counters and indices,
+	/// without going through the generic operators. Returns None for other operators, and
+	/// when the result overflows, so that the generic path panics.
+	fn int_op(op: &program::BinaryOp, left: i64, right: i64) -> Option<Value> {
+		use program::BinaryOp::*;
+
+		match op {
+			Plus => left.checked_add(right).map(Value::Int),
+			Minus => left.checked_sub(right).map(Value::Int),
+			Times => left.checked_mul(right).map(Value::Int),
+			FloorDiv => program::BinaryOp::floor_div(left, right).map(Value::Int),
+			Pow => program::BinaryOp::int_pow(left, right).map(Value::Int),
+			Equals => Some(Value::Bool(left == right)),
+			NotEquals => Some(Value::Bool(left != right)),
+			Lower => Some(Value::Bool(left < right)),
+			LowerEquals => Some(Value::Bool(left <= right)),
+			Greater => Some(Value::Bool(left > right)),
+			GreaterEquals => Some(Value::Bool(left >= right)),
+			_ => None,
+		}
+	}
+
+
+	/// Pop a value for each l-value, and assign them in order.
+	fn multiple_assign(&mut self, left: &'static [program::Lvalue]) -> Result<Flow, Panic> {
+		let start = self.arguments.len() - left.len();
+		let values: Vec<Value> = self.arguments.drain(start..).collect();
+
+		for (left, value) in left.iter().zip(values) {
+			match self.assign(left, value)? {
+				Flow::Regular(_) => (),
+				flow => return Ok(flow),
+			}
+		}
+
+		Ok(Flow::Regular(Value::default()))
+	}
+
+
+	/// Leave the innermost loop when finished, returning the instruction following it.
+	fn exit_loop(loops: &mut Vec<Loop>) -> usize {
+		let record = loops.pop().expect("loop without record");
+		record.exit as usize
+	}
+
+
+	/// Break out of the innermost loop, returning the instruction following it. Without a
+	/// loop in the code, the break is left to the enclosing code, as in the tree-walker.
+	fn break_loop(&mut self, loops: &mut Vec<Loop>) -> Option<usize> {
+		let record = loops.pop()?;
+
+		record.stop();
+		self.arguments.truncate(record.base);
+
+		Some(record.exit as usize)
+	}
+}
@@ -0,0 +1,575 @@
+mod exec;
+
+use super::{mem, program, Panic, Runtime, SourcePos, Value};
+
+
+/// Which interpreter executes programs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Interpreter {
+	/// Evaluate the program tree recursively. Kept as a fallback for the virtual machine.
+	TreeWalker,
+	/// Lower each block to instructions for a stack machine, and execute them in a loop.
+	#[default]
+	Vm,
+}
+
+
+/// An instruction of the stack machine.
+/// Operands are kept on the runtime's arguments vector, so that the arguments of calls are
+/// already in place when evaluated. Instructions that may panic refer to the program node
+/// they were lowered from, where the positions are found.
+#[derive(Debug)]
+enum Instruction {
+	/// Invoke the trace hook, consume fuel and deliver signals before a statement.
+	Statement(&'static program::Statement),
+	/// Push nil.
+	Nil,
+	/// Push a constant.
+	Constant(u32),
+	/// Push the value of a variable.
+	Load(mem::SlotIx),
+	/// Pop a value into a variable.
+	Store(mem::SlotIx),
+	/// Discard the top value.
+	Pop,
+	/// Continue at the given instruction.
+	Jump(u32),
+	/// Pop the condition of an if expression, jumping to the else block if false.
+	Condition { condition: &'static program::Expr, otherwise: u32 },
+	/// Enter a while loop, which exits at the given instruction.
+	While { exit: u32 },
+	/// Pop the condition of a while loop, exiting the loop if false.
+	WhileCondition(&'static program::Expr),
+	/// Pop the value of a for loop, and enter the loop, which exits at the given instruction.
+	For { expr: &'static program::Expr, exit: u32 },
+	/// Store the next item of a for loop in the variable, or exit the loop if finished.
+	ForNext { expr: &'static program::Expr, slot_ix: mem::SlotIx },
+	/// Exit the innermost loop.
+	Break,
+	/// Pop the returned value, and return.
+	Return,
+	/// Push a function literal, capturing variables.
+	Function(&'static program::Expr),
+	/// Pop the given amount of items, and push an array of them.
+	Array(u32),
+	/// Pop the given amount of key-value pairs, and push a dict of them.
+	Dict(u32),
+	/// Push a fresh instance of the template.
+	Template(&'static program::Template),
+	/// Pop the given amount of parts, and push the interpolated string.
+	Interpolation(u32),
+	/// Apply the unary operator of the expression to the top value.
+	Unary(&'static program::Expr),
+	/// Apply the binary operator of the expression to its operands.
+	Binary { expr: &'static program::Expr, left: Operand, right: Operand },
+	/// Concatenate the operands of the expression into the variable, which is the left
+	/// operand, reusing its string if not shared.
+	Append { expr: &'static program::Expr, slot_ix: mem::SlotIx, left: Operand, right: Operand },
+	/// Check the left operand of a logical operator, jumping to the end if it short circuits.
+	LogicalLeft { expr: &'static program::Expr, end: u32 },
+	/// Check the right operand of a logical operator.
+	LogicalRight(&'static program::Expr),
+	/// Pop a field and an object, and push the accessed value.
+	Access { expr: &'static program::Expr, cache: Option<u32> },
+	/// Pop a field, and push its value in the object in the variable.
+	AccessLocal { expr: &'static program::Expr, slot_ix: mem::SlotIx, cache: Option<u32> },
+	/// Pop a field, and push its value in the object, keeping the object as self.
+	Method { expr: &'static program::Expr, cache: Option<u32> },
+	/// Push the function in the variable, panicking if it isn't callable.
+	LoadCallee { function: &'static program::Expr, slot_ix: mem::SlotIx },
+	/// Panic if the top value isn't callable.
+	Callee(&'static program::Expr),
+	/// Call the function below the arguments, and push the returned value.
+	Call { expr: &'static program::Expr, method: bool, tail: bool },
+	/// Pop a field, an object and a value, and assign the value to the field.
+	SetField(&'static program::Lvalue),
+	/// Pop a value, and assign it to the destructuring l-value.
+	Assign(&'static program::Lvalue),
+	/// Pop a value for each l-value, and assign them in order.
+	MultipleAssign(&'static [program::Lvalue]),
+	/// Execute a command block, and push the result.
+	CommandBlock(&'static program::Expr),
+}
+
+
+/// Where an operand of a binary operator is found. Variables and literals are read in
+/// place, sparing copies through the operand stack.
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+	/// Popped from the operand stack.
+	Stack,
+	/// The value of a variable.
+	Slot(u32),
+	/// A constant.
+	Constant(u32),
+}
+
+
+/// Caches of field accesses with constant fields, indexed by access site. The caches are
+/// owned by the runtime, as they hold values, and sites get their entry when lowered.
+#[derive(Debug, Default)]
+pub struct FieldCaches(Vec<FieldCache>);
+
+
+/// The last field read through an access site from a dict.
+#[derive(Debug)]
+struct FieldCache {
+	/// The dict's address and stamp, which identify its contents.
+	address: *const (),
+	stamp: u64,
+	value: Value,
+}
+
+
+impl FieldCaches {
+	/// Allocate the cache of an access site.
+	fn allocate(&mut self) -> u32 {
+		self.0.push(
+			FieldCache {
+				address: std::ptr::null(),
+				stamp: 0,
+				value: Value::Nil,
+			}
+		);
+
+		self.0.len() as u32 - 1
+	}
+
+
+	/// Get the given field of an object, through the site's cache if it has one. Only
+	/// fields of dicts are cached.
+	fn access(
+		&mut self,
+		cache: Option<u32>,
+		obj: &Value,
+		field: Value,
+		obj_pos: SourcePos,
+		field_pos: SourcePos,
+	) -> Result<Value, Panic> {
+		let (cache, dict) = match (cache, obj) {
+			(Some(cache), Value::Dict(dict)) => (&mut self.0[cache as usize], dict),
+			_ => return Runtime::access(obj, field, obj_pos, field_pos),
+		};
+
+		let address = dict.address();
+		let stamp = dict.stamp();
+
+		if cache.address == address && cache.stamp == stamp {
+			return Ok(cache.value.copy());
+		}
+
+		let value = Runtime::access(obj, field, obj_pos, field_pos)?;
+
+		*cache = FieldCache { address, stamp, value: value.copy() };
+
+		Ok(value)
+	}
+}
+
+
+/// A block lowered to instructions. Executing the instructions pushes the value of the
+/// block, or returns.
+#[derive(Debug)]
+pub struct Code {
+	instructions: Box<[Instruction]>,
+	/// Literals of trivial types, which never hold garbage collected values.
+	constants: Box<[Value]>,
+}
+
+
+impl Code {
+	/// Lower a block. Calls in the last statement are lowered as tail calls. Access sites
+	/// get their caches allocated in the given caches.
+	pub fn new(block: &'static program::Block, caches: &mut FieldCaches) -> Self {
+		let mut lowering = Lowering {
+			instructions: Vec::new(),
+			constants: Vec::new(),
+			caches,
+		};
+		lowering.block(block, true, true);
+
+		Self {
+			instructions: lowering.instructions.into(),
+			constants: lowering.constants.into(),
+		}
+	}
+}
+
+
+/// The state of lowering a block.
+struct Lowering<'a> {
+	instructions: Vec<Instruction>,
+	constants: Vec<Value>,
+	caches: &'a mut FieldCaches,
+}
+
+
+impl<'a> Lowering<'a> {
+	/// The index of the next instruction.
+	fn label(&self) -> u32 {
+		self.instructions.len() as u32
+	}
+
+
+	/// Append an instruction, returning its index.
+	fn emit(&mut self, instruction: Instruction) -> usize {
+		self.instructions.push(instruction);
+		self.instructions.len() - 1
+	}
+
+
+	/// Set the jump target of the instruction at the given index to the next instruction.
+	fn patch(&mut self, ix: usize) {
+		let label = self.label();
+
+		match &mut self.instructions[ix] {
+			Instruction::Jump(target)
+				| Instruction::Condition { otherwise: target, .. }
+				| Instruction::While { exit: target }
+				| Instruction::For { exit: target, .. }
+				| Instruction::LogicalLeft { end: target, .. } => *target = label,
+
+			instruction => unreachable!("instruction has no jump target: {:?}", instruction),
+		}
+	}
+
+
+	/// Append an instruction to push a constant.
+	fn constant(&mut self, value: Value) {
+		self.constants.push(value);
+		self.emit(Instruction::Constant(self.constants.len() as u32 - 1));
+	}
+
+
+	/// Lower a block, pushing its value only if requested.
+	fn block(&mut self, block: &'static program::Block, value: bool, tail: bool) {
+		match block.0.split_last() {
+			Some((last, init)) => {
+				for statement in init {
+					self.statement(statement, false, false);
+				}
+
+				self.statement(last, value, tail);
+			}
+
+			None if value => { self.emit(Instruction::Nil); },
+			None => (),
+		}
+	}
+
+
+	/// Lower a statement, pushing its value only if requested.
+	fn statement(&mut self, statement: &'static program::Statement, value: bool, tail: bool) {
+		self.emit(Instruction::Statement(statement));
+
+		match statement {
+			program::Statement::Assign {
+				left: program::Lvalue::Identifier { slot_ix, .. },
+				right: expr @ program::Expr::BinaryOp { left: operand, op: program::BinaryOp::Concat, right, .. },
+			} if operand.is_variable(slot_ix) => {
+				// The variable is read in place if evaluating the right operand can't change it.
+				let right_operand = self.operand(right);
+				let left_operand = match right_operand {
+					Operand::Stack => Operand::Stack,
+					_ => Operand::Slot(slot_ix.0),
+				};
+
+				if let Operand::Stack = right_operand {
+					self.emit(Instruction::Load(slot_ix.into()));
+					self.expr(right, false);
+				}
+
+				self.emit(Instruction::Append { expr, slot_ix: slot_ix.into(), left: left_operand, right: right_operand });
+			}
+
+			program::Statement::Assign { left, right } => {
+				self.expr(right, false);
+
+				match left {
+					program::Lvalue::Identifier { slot_ix, .. } => {
+						self.emit(Instruction::Store(slot_ix.into()));
+					}
+
+					program::Lvalue::Access { object, field, .. } => {
+						self.expr(object, false);
+						self.expr(field, false);
+						self.emit(Instruction::SetField(left));
+					}
+
+					_ => { self.emit(Instruction::Assign(left)); },
+				}
+			}
+
+			program::Statement::MultipleAssign { left, right } => {
+				for expr in right.iter() {
+					self.expr(expr, false);
+				}
+
+				self.emit(Instruction::MultipleAssign(left));
+			}
+
+			program::Statement::Return { expr } => {
+				self.expr(expr, tail);
+				self.emit(Instruction::Return);
+				return;
+			}
+
+			program::Statement::Break => {
+				self.emit(Instruction::Break);
+				return;
+			}
+
+			program::Statement::While { condition, block } => {
+				let enter = self.emit(Instruction::While { exit: 0 });
+				let start = self.label();
+
+				self.expr(condition, false);
+				self.emit(Instruction::WhileCondition(condition));
+				self.block(block, false, false);
+				self.emit(Instruction::Jump(start));
+
+				self.patch(enter);
+			}
+
+			program::Statement::For { slot_ix, expr, block } => {
+				self.expr(expr, false);
+
+				let enter = self.emit(Instruction::For { expr, exit: 0 });
+				let start = self.label();
+
+				self.emit(Instruction::ForNext { expr, slot_ix: slot_ix.into() });
+				self.block(block, false, false);
+				self.emit(Instruction::Jump(start));
+
+				self.patch(enter);
+			}
+
+			program::Statement::Expr(expr) => {
+				self.expr(expr, tail);
+
+				if !value {
+					self.emit(Instruction::Pop);
+				}
+
+				return;
+			}
+		}
+
+		// Assignments and loops evaluate to nil.
+		if value {
+			self.emit(Instruction::Nil);
+		}
+	}
+
+
+	/// Lower an expression, pushing its value.
+	fn expr(&mut self, expr: &'static program::Expr, tail: bool) {
+		match expr {
+			program::Expr::Identifier { slot_ix, .. } => {
+				self.emit(Instruction::Load(slot_ix.into()));
+			}
+
+			program::Expr::Literal { literal, .. } => self.literal(expr, literal),
+
+			program::Expr::UnaryOp { operand, .. } => {
+				self.expr(operand, false);
+				self.emit(Instruction::Unary(expr));
+			}
+
+			program::Expr::BinaryOp { left, op, right, .. } => {
+				if matches!(op, program::BinaryOp::And | program::BinaryOp::Or) {
+					self.expr(left, false);
+					let left = self.emit(Instruction::LogicalLeft { expr, end: 0 });
+					self.expr(right, false);
+					self.emit(Instruction::LogicalRight(expr));
+					self.patch(left);
+				} else {
+					// The left operand may only be read in place if evaluating the right one
+					// can't change it.
+					let right_operand = self.operand(right);
+					let left_operand = match right_operand {
+						Operand::Stack => Operand::Stack,
+						_ => self.operand(left),
+					};
+
+					if let Operand::Stack = left_operand {
+						self.expr(left, false);
+					}
+
+					if let Operand::Stack = right_operand {
+						self.expr(right, false);
+					}
+
+					self.emit(Instruction::Binary { expr, left: left_operand, right: right_operand });
+				}
+			}
+
+			program::Expr::If { condition, then, otherwise, .. } => {
+				self.expr(condition, false);
+
+				let condition = self.emit(Instruction::Condition { condition, otherwise: 0 });
+				self.block(then, true, false);
+				let end = self.emit(Instruction::Jump(0));
+
+				self.patch(condition);
+				self.block(otherwise, true, false);
+				self.patch(end);
+			}
+
+			program::Expr::Access { object, field, .. } => {
+				// Accessing a variable with a variable or literal field needs no copy of the
+				// object, as evaluating such fields can't change the variable.
+				let pure_field = matches!(
+					**field,
+					program::Expr::Identifier { .. } | program::Expr::Literal { .. }
+				);
+
+				let cache = self.cache(field);
+
+				match &**object {
+					program::Expr::Identifier { slot_ix, .. } if pure_field => {
+						self.expr(field, false);
+						self.emit(Instruction::AccessLocal { expr, slot_ix: slot_ix.into(), cache });
+					}
+
+					_ => {
+						self.expr(object, false);
+						self.expr(field, false);
+						self.emit(Instruction::Access { expr, cache });
+					}
+				}
+			}
+
+			program::Expr::Call { function, args, .. } => {
+				let method = match &**function {
+					// Functions in variables are copied directly from their slot.
+					program::Expr::Identifier { slot_ix, .. } => {
+						self.emit(Instruction::LoadCallee { function, slot_ix: slot_ix.into() });
+						false
+					}
+
+					// Methods are called with the accessed object as self.
+					program::Expr::Access { object, field, .. } => {
+						let cache = self.cache(field);
+
+						self.expr(object, false);
+						self.expr(field, false);
+						self.emit(Instruction::Method { expr: function, cache });
+						self.emit(Instruction::Callee(function));
+						true
+					}
+
+					_ => {
+						self.expr(function, false);
+						self.emit(Instruction::Callee(function));
+						false
+					}
+				};
+
+				for arg in args.iter() {
+					self.expr(arg, false);
+				}
+
+				self.emit(Instruction::Call { expr, method, tail });
+			}
+
+			// Spread arguments are expanded by the call.
+			program::Expr::Spread { expr, .. } => self.expr(expr, false),
+
+			program::Expr::CommandBlock { .. } => {
+				self.emit(Instruction::CommandBlock(expr));
+			}
+
+			program::Expr::Interpolation { parts, .. } => {
+				for part in parts.iter() {
+					self.expr(part, false);
+				}
+
+				self.emit(Instruction::Interpolation(parts.len() as u32));
+			}
+		}
+	}
+
+
+	/// Allocate a cache for an access site, if the field is constant, as a cached field
+	/// is only valid for the same key.
+	fn cache(&mut self, field: &'static program::Expr) -> Option<u32> {
+		match field {
+			program::Expr::Literal {
+				literal: program::Literal::Nil
+					| program::Literal::Bool(_)
+					| program::Literal::Int(_)
+					| program::Literal::Float(_)
+					| program::Literal::Byte(_)
+					| program::Literal::String(_)
+					| program::Literal::Identifier(_),
+				..
+			} => Some(self.caches.allocate()),
+
+			_ => None,
+		}
+	}
+
+
+	/// Get where an operand can be read in place, if it's a variable or a trivial literal.
+	fn operand(&mut self, expr: &'static program::Expr) -> Operand {
+		let value: Value = match expr {
+			program::Expr::Identifier { slot_ix, .. } => return Operand::Slot(slot_ix.0),
+
+			program::Expr::Literal { literal, .. } => match literal {
+				program::Literal::Nil => Value::Nil,
+				program::Literal::Bool(b) => (*b).into(),
+				program::Literal::Int(int) => (*int).into(),
+				program::Literal::Float(float) => (*float).into(),
+				program::Literal::Byte(byte) => (*byte).into(),
+				program::Literal::String(string) => string.as_ref().into(),
+				program::Literal::Identifier(name) => name.string.copy().into(),
+				_ => return Operand::Stack,
+			},
+
+			_ => return Operand::Stack,
+		};
+
+		self.constants.push(value);
+		Operand::Constant(self.constants.len() as u32 - 1)
+	}
+
+
+	/// Lower a literal expression.
+	fn literal(&mut self, expr: &'static program::Expr, literal: &'static program::Literal) {
+		match literal {
+			program::Literal::Nil => { self.emit(Instruction::Nil); },
+			program::Literal::Bool(b) => self.constant((*b).into()),
+			program::Literal::Int(int) => self.constant((*int).into()),
+			program::Literal::Float(float) => self.constant((*float).into()),
+			program::Literal::Byte(byte) => self.constant((*byte).into()),
+			program::Literal::String(string) => self.constant(string.as_ref().into()),
+			program::Literal::Identifier(name) => self.constant(name.string.copy().into()),
+
+			program::Literal::Array(exprs) => {
+				for expr in exprs.iter() {
+					self.expr(expr, false);
+				}
+
+				self.emit(Instruction::Array(exprs.len() as u32));
+			}
+
+			program::Literal::Dict(items) => {
+				for (key, expr) in items.iter() {
+					match key {
+						program::DictKey::Symbol(name) => self.constant(name.string.copy().into()),
+						program::DictKey::Computed(key) => self.expr(key, false),
+					}
+
+					self.expr(expr, false);
+				}
+
+				self.emit(Instruction::Dict(items.len() as u32));
+			}
+
+			program::Literal::Template(template) => { self.emit(Instruction::Template(template)); },
+
+			program::Literal::Function { .. } => { self.emit(Instruction::Function(expr)); },
+		}
+	}
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display as _;
 
-use super::{Errors, Error, ErrorKind};
+use super::{Errors, Error, ErrorKind, SourcePos};
 use crate::{
 	fmt::{self, Display},
 	symbol::{self},
@@ -23,27 +23,64 @@ impl<'a> Display<'a> for ErrorKind {
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
-			Self::UndeclaredVariable(symbol) => {
+			Self::UndeclaredVariable(symbol, suggestion) => {
 				"undeclared variable '".fmt(f)?;
 				symbol.fmt(f, context)?;
-				"'".fmt(f)
+				"'".fmt(f)?;
+
+				if let Some(suggestion) = suggestion {
+					"; did you mean '".fmt(f)?;
+					suggestion.fmt(f, context)?;
+					"'?".fmt(f)?;
+				}
+
+				Ok(())
 			}
 
-			Self::DuplicateVariable(symbol) => {
+			Self::DuplicateVariable(symbol, first) => {
 				"duplicate variable '".fmt(f)?;
 				symbol.fmt(f, context)?;
-				"'".fmt(f)
+				"'".fmt(f)?;
+
+				// Implicit variables, like std, have no position.
+				if *first != SourcePos::default() {
+					", first declared at ".fmt(f)?;
+					first.fmt(f, context)?;
+				}
+
+				Ok(())
 			}
 
-			Self::DuplicateKey(symbol) => {
+			Self::DuplicateKey(symbol, first) => {
 				"duplicate key '".fmt(f)?;
 				symbol.fmt(f, context)?;
-				"'".fmt(f)
+				"', first defined at ".fmt(f)?;
+				first.fmt(f, context)
+			}
+
+			Self::ReadOnlyVariable(symbol) => {
+				"'".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"' is read-only, it may not be assigned or redeclared".fmt(f)
+			}
+
+			Self::ArityMismatch { symbol, params, args, definition } => {
+				"function '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				write!(
+					f,
+					"' takes {} argument{} but {} {} given, defined at ",
+					params,
+					if *params == 1 { "" } else { "s" },
+					args,
+					if *args == 1 { "was" } else { "were" },
+				)?;
+				definition.fmt(f, context)
 			}
 
 			Self::ReturnOutsideFunction => write!(f, "return statement outside function"),
 
-			Self::SelfOutsideFunction => write!(f, "self keyword outside function"),
+			Self::SelfOutsideFunction => write!(f, "self is only available inside functions called as methods"),
 
 			Self::TryOutsideFunction => write!(f, "try operator outside function"),
 
@@ -51,7 +88,23 @@ impl<'a> Display<'a> for ErrorKind {
 
 			Self::InvalidAssignment => write!(f, "invalid assignment"),
 
+			Self::AssignmentCountMismatch { targets, values } => write!(
+				f,
+				"assignment to {} target{} with {} value{}",
+				targets,
+				if *targets == 1 { "" } else { "s" },
+				values,
+				if *values == 1 { "" } else { "s" },
+			),
+
 			Self::AsyncBuiltin => write!(f, "use of built-in command in async context"),
+
+			Self::FrameTooLarge { slots, max } => write!(
+				f,
+				"too many variables, {} slots exceed the limit of {} per function",
+				slots,
+				max,
+			),
 		}
 	}
 }
@@ -62,7 +115,8 @@ impl<'a> Display<'a> for Error {
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		write!(f, "{}: {} - ", color::Fg(color::Red, "Error"), fmt::Show(self.pos, context))?;
-		self.kind.fmt(f, context)
+		self.kind.fmt(f, context)?;
+		self.pos.snippet().fmt(f, context)
 	}
 }
 
@@ -85,7 +139,7 @@ impl<'a> Display<'a> for Errors {
 					writeln!(
 						f,
 						"{} {}",
-						color::Fg(color::Red, max),
+						color::Fg(color::Red, self.0.len() - max),
 						color::Fg(color::Red, "more supressed semantic errors"),
 					)?;
 
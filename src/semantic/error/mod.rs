@@ -7,13 +7,23 @@ pub use fmt::ErrorsDisplayContext;
 /// The kind of a semantic error.
 #[derive(Debug)]
 pub enum ErrorKind {
-	/// Variable usage before variable declaration.
-	UndeclaredVariable(Symbol),
-	/// Variable declared with the same name twice in the same scope.
-	/// Includes function parameters.
-	DuplicateVariable(Symbol),
-	/// Duplicate keys in dict literal.
-	DuplicateKey(Symbol),
+	/// Variable usage before variable declaration, with the closest visible name, if any.
+	UndeclaredVariable(Symbol, Option<Symbol>),
+	/// Variable declared with the same name twice in the same scope, with the position of
+	/// the first declaration. Includes function parameters.
+	DuplicateVariable(Symbol, SourcePos),
+	/// Duplicate keys in dict literal or pattern, with the position of the first occurrence.
+	DuplicateKey(Symbol, SourcePos),
+	/// Assignment to or redeclaration of a read-only variable, like `std`.
+	ReadOnlyVariable(Symbol),
+	/// Call with the wrong number of arguments through a variable holding a function
+	/// literal, with the position of the literal.
+	ArityMismatch {
+		symbol: Symbol,
+		params: u32,
+		args: u32,
+		definition: SourcePos,
+	},
 	/// Return statement outside function.
 	ReturnOutsideFunction,
 	/// Self keyword outside function.
@@ -24,9 +34,41 @@ pub enum ErrorKind {
 	BreakOutsideLoop,
 	/// Invalid assignment l-value.
 	InvalidAssignment,
+	/// Multiple assignment with different numbers of l-values and values.
+	AssignmentCountMismatch {
+		targets: usize,
+		values: usize,
+	},
 	/// Built-in command used in async context.
 	/// Async contexts include pipes, redirections and capture or async blocks.
 	AsyncBuiltin,
+	/// Function or program with more variables than fit in a frame.
+	FrameTooLarge {
+		slots: u32,
+		max: u32,
+	},
+}
+
+
+impl ErrorKind {
+	/// A stable identifier for the kind of error.
+	pub fn code(&self) -> &'static str {
+		match self {
+			Self::UndeclaredVariable(..) => "undeclared-variable",
+			Self::DuplicateVariable(..) => "duplicate-variable",
+			Self::DuplicateKey(..) => "duplicate-key",
+			Self::ReadOnlyVariable(_) => "read-only-variable",
+			Self::ArityMismatch { .. } => "arity-mismatch",
+			Self::ReturnOutsideFunction => "return-outside-function",
+			Self::SelfOutsideFunction => "self-outside-function",
+			Self::TryOutsideFunction => "try-outside-function",
+			Self::BreakOutsideLoop => "break-outside-loop",
+			Self::InvalidAssignment => "invalid-assignment",
+			Self::AssignmentCountMismatch { .. } => "assignment-count-mismatch",
+			Self::AsyncBuiltin => "async-builtin",
+			Self::FrameTooLarge { .. } => "frame-too-large",
+		}
+	}
 }
 
 
@@ -40,9 +82,9 @@ pub struct Error {
 
 impl Error {
 	/// Variable usage before variable declaration.
-	pub fn undeclared_variable(symbol: Symbol, pos: SourcePos) -> Self {
+	pub fn undeclared_variable(symbol: Symbol, suggestion: Option<Symbol>, pos: SourcePos) -> Self {
 		Self {
-			kind: ErrorKind::UndeclaredVariable(symbol),
+			kind: ErrorKind::UndeclaredVariable(symbol, suggestion),
 			pos
 		}
 	}
@@ -50,18 +92,42 @@ impl Error {
 
 	/// Variable declared with the same name twice in the same scope.
 	/// Includes function parameters.
-	pub fn duplicate_variable(symbol: Symbol, pos: SourcePos) -> Self {
+	pub fn duplicate_variable(symbol: Symbol, first: SourcePos, pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::DuplicateVariable(symbol, first),
+			pos
+		}
+	}
+
+
+	/// Duplicate keys in dict literal or pattern.
+	pub fn duplicate_key(symbol: Symbol, first: SourcePos, pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::DuplicateKey(symbol, first),
+			pos
+		}
+	}
+
+
+	/// Assignment to or redeclaration of a read-only variable.
+	pub fn read_only_variable(symbol: Symbol, pos: SourcePos) -> Self {
 		Self {
-			kind: ErrorKind::DuplicateVariable(symbol),
+			kind: ErrorKind::ReadOnlyVariable(symbol),
 			pos
 		}
 	}
 
 
-	/// Duplicate keys in dict literal.
-	pub fn duplicate_key(symbol: Symbol, pos: SourcePos) -> Self {
+	/// Call with the wrong number of arguments.
+	pub fn arity_mismatch(
+		symbol: Symbol,
+		params: u32,
+		args: u32,
+		definition: SourcePos,
+		pos: SourcePos
+	) -> Self {
 		Self {
-			kind: ErrorKind::DuplicateKey(symbol),
+			kind: ErrorKind::ArityMismatch { symbol, params, args, definition },
 			pos
 		}
 	}
@@ -112,6 +178,15 @@ impl Error {
 	}
 
 
+	/// Multiple assignment with different numbers of l-values and values.
+	pub fn assignment_count_mismatch(targets: usize, values: usize, pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::AssignmentCountMismatch { targets, values },
+			pos
+		}
+	}
+
+
 	/// Built-in command used in async context.
 	/// Async contexts include pipes redirections and capture or async blocks.
 	pub fn async_builtin(pos: SourcePos) -> Self {
@@ -120,6 +195,15 @@ impl Error {
 			pos
 		}
 	}
+
+
+	/// Function or program with more variables than fit in a frame.
+	pub fn frame_too_large(slots: u32, max: u32, pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::FrameTooLarge { slots, max },
+			pos
+		}
+	}
 }
 
 
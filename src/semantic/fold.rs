@@ -0,0 +1,210 @@
+//! Constant folding for operators whose operands are literals, and for array and dict
+//! literals whose items are all constant.
+//!
+//! Folding must be indistinguishable from runtime evaluation. Therefore, only operations
+//! that are certain to succeed are folded: integer overflow and division by zero are left
+//! unfolded, so that they panic at runtime with the same position as before. Floats are
+//! never folded, as their comparison and NaN semantics are defined by the runtime.
+
+use super::program::{BinaryOp, DictKey, Expr, Literal, SourcePos, Template, UnaryOp};
+
+
+/// Build a unary operator expression, folding it if the operand is a literal.
+pub fn unary_op(op: UnaryOp, operand: Expr, pos: SourcePos) -> Expr {
+	let literal = match (&op, &operand) {
+		(UnaryOp::Minus, Expr::Literal { literal: Literal::Int(i), .. }) => i
+			.checked_neg()
+			.map(Literal::Int),
+
+		(UnaryOp::Not, Expr::Literal { literal: Literal::Bool(b), .. }) => Some(Literal::Bool(!b)),
+
+		_ => None,
+	};
+
+	match literal {
+		Some(literal) => Expr::Literal { literal, pos },
+		None => Expr::UnaryOp { op, operand: Box::new(operand), pos },
+	}
+}
+
+
+/// Build a binary operator expression, folding it if both operands are literals.
+pub fn binary_op(left: Expr, op: BinaryOp, right: Expr, pos: SourcePos) -> Expr {
+	let literal = match (&left, &right) {
+		(Expr::Literal { literal: left, .. }, Expr::Literal { literal: right, .. }) => {
+			fold_binary(left, &op, right)
+		}
+
+		_ => None,
+	};
+
+	match literal {
+		Some(literal) => Expr::Literal { literal, pos },
+		None => Expr::BinaryOp {
+			left: Box::new(left),
+			op,
+			right: Box::new(right),
+			pos,
+		},
+	}
+}
+
+
+/// Build an array literal, as a template if all items are constant.
+pub fn array(items: Box<[Expr]>) -> Literal {
+	if items.iter().all(is_constant) {
+		Literal::Template(
+			Template::Array(
+				items
+					.into_vec()
+					.into_iter()
+					.map(template)
+					.collect()
+			)
+		)
+	} else {
+		Literal::Array(items)
+	}
+}
+
+
+/// Build a dict literal, as a template if all keys and values are constant.
+pub fn dict(items: Box<[(DictKey, Expr)]>) -> Literal {
+	let constant = items
+		.iter()
+		.all(
+			|(key, expr)| match key {
+				DictKey::Symbol(_) => is_constant(expr),
+				DictKey::Computed(key) => is_constant(key) && is_constant(expr),
+			}
+		);
+
+	if constant {
+		Literal::Template(
+			Template::Dict(
+				items
+					.into_vec()
+					.into_iter()
+					.map(
+						|(key, expr)| {
+							let key = match key {
+								DictKey::Symbol(name) => Template::String(name.string),
+								DictKey::Computed(key) => template(key),
+							};
+
+							(key, template(expr))
+						}
+					)
+					.collect()
+			)
+		)
+	} else {
+		Literal::Dict(items)
+	}
+}
+
+
+/// Whether the expression is a literal that can be part of a template.
+fn is_constant(expr: &Expr) -> bool {
+	matches!(
+		expr,
+		Expr::Literal {
+			literal: Literal::Nil
+				| Literal::Bool(_)
+				| Literal::Int(_)
+				| Literal::Float(_)
+				| Literal::Byte(_)
+				| Literal::String(_)
+				| Literal::Template(_),
+			..
+		}
+	)
+}
+
+
+/// Convert a constant expression to a template.
+fn template(expr: Expr) -> Template {
+	match expr {
+		Expr::Literal { literal, .. } => match literal {
+			Literal::Nil => Template::Nil,
+			Literal::Bool(b) => Template::Bool(b),
+			Literal::Int(int) => Template::Int(int),
+			Literal::Float(float) => Template::Float(float),
+			Literal::Byte(byte) => Template::Byte(byte),
+			Literal::String(string) => Template::String(string.as_ref().into()),
+			Literal::Template(template) => template,
+			_ => unreachable!("non-constant literal in template"),
+		},
+
+		_ => unreachable!("non-constant expression in template"),
+	}
+}
+
+
+/// Fold a binary operator on literals, if possible.
+fn fold_binary(left: &Literal, op: &BinaryOp, right: &Literal) -> Option<Literal> {
+	use std::cmp::Ordering;
+
+	let ordering = || match (left, right) {
+		(Literal::Int(left), Literal::Int(right)) => Some(left.cmp(right)),
+		(Literal::Byte(left), Literal::Byte(right)) => Some(left.cmp(right)),
+		(Literal::String(left), Literal::String(right)) => Some(left.cmp(right)),
+		_ => None,
+	};
+
+	// Values of different types are never equal. Floats are excluded because of NaN.
+	let equals = || match (left, right) {
+		(Literal::Nil, Literal::Nil) => Some(true),
+		(Literal::Bool(left), Literal::Bool(right)) => Some(left == right),
+		(Literal::Int(left), Literal::Int(right)) => Some(left == right),
+		(Literal::Byte(left), Literal::Byte(right)) => Some(left == right),
+		(Literal::String(left), Literal::String(right)) => Some(left == right),
+		_ => None,
+	};
+
+	let int = |fold: fn(i64, i64) -> Option<i64>| match (left, right) {
+		(Literal::Int(left), Literal::Int(right)) => fold(*left, *right).map(Literal::Int),
+		_ => None,
+	};
+
+	match op {
+		BinaryOp::Plus => int(i64::checked_add),
+		BinaryOp::Minus => int(i64::checked_sub),
+		BinaryOp::Times => int(i64::checked_mul),
+		BinaryOp::Div => int(i64::checked_div),
+		BinaryOp::FloorDiv => int(BinaryOp::floor_div),
+		BinaryOp::Mod => int(i64::checked_rem),
+		BinaryOp::Pow => int(BinaryOp::int_pow),
+
+		BinaryOp::Equals => equals().map(Literal::Bool),
+		BinaryOp::NotEquals => equals().map(|equals| Literal::Bool(!equals)),
+
+		BinaryOp::Greater => ordering().map(|ord| Literal::Bool(ord == Ordering::Greater)),
+		BinaryOp::GreaterEquals => ordering().map(|ord| Literal::Bool(ord != Ordering::Less)),
+		BinaryOp::Lower => ordering().map(|ord| Literal::Bool(ord == Ordering::Less)),
+		BinaryOp::LowerEquals => ordering().map(|ord| Literal::Bool(ord != Ordering::Greater)),
+
+		BinaryOp::And => match (left, right) {
+			(Literal::Bool(left), Literal::Bool(right)) => Some(Literal::Bool(*left && *right)),
+			_ => None,
+		},
+
+		BinaryOp::Or => match (left, right) {
+			(Literal::Bool(left), Literal::Bool(right)) => Some(Literal::Bool(*left || *right)),
+			_ => None,
+		},
+
+		BinaryOp::Concat => match (left, right) {
+			(Literal::String(left), Literal::String(right)) => Some(
+				Literal::String([ left.as_ref(), right.as_ref() ].concat().into())
+			),
+			(Literal::String(left), Literal::Byte(right)) => Some(
+				Literal::String([ left.as_ref(), &[ *right ] ].concat().into())
+			),
+			(Literal::Byte(left), Literal::String(right)) => Some(
+				Literal::String([ &[ *left ], right.as_ref() ].concat().into())
+			),
+			_ => None,
+		},
+	}
+}
@@ -1,11 +1,13 @@
 mod error;
+mod fold;
 mod scope;
+mod warning;
 pub mod program;
 #[cfg(test)]
 mod tests;
 
 use std::{
-	collections::HashSet,
+	collections::hash_map::{HashMap, Entry},
 	convert::TryFrom,
 };
 
@@ -24,15 +26,55 @@ use program::{
 	Block,
 	Command,
 	CommandBlock,
+	DictKey,
+	DictLvalueItem,
 	Expr,
 	Literal,
 	Lvalue,
+	Name,
 	Program,
 	Redirection,
 	RedirectionTarget,
 	Statement,
 };
 pub use error::{Error, ErrorKind, Errors, ErrorsDisplayContext};
+pub use warning::{Warning, WarningKind, Warnings};
+pub use scope::closest;
+use scope::Declaration;
+
+
+/// The root scope of an interactive session, which persists between analyses. Each input
+/// is analyzed in a new block nested in the previous ones, so that it may use and shadow
+/// earlier declarations.
+#[derive(Debug)]
+pub struct Session {
+	scope: scope::Stack,
+}
+
+
+impl Session {
+	/// Start a session, where only the stdlib is declared.
+	pub fn new(interner: &mut symbol::Interner) -> Self {
+		let mut scope = scope::Stack::default();
+		Analyzer::enter_root(&mut scope, interner);
+
+		Self { scope }
+	}
+
+
+	/// The names of the variables declared by previous inputs, including the stdlib. Names
+	/// are repeated when shadowed.
+	pub fn globals(&self) -> impl Iterator<Item = Symbol> + '_ {
+		self.scope.visible()
+	}
+}
+
+
+impl Drop for Session {
+	fn drop(&mut self) {
+		self.scope.discard();
+	}
+}
 
 
 /// Static semantic analyzer.
@@ -40,10 +82,10 @@ pub use error::{Error, ErrorKind, Errors, ErrorsDisplayContext};
 pub struct Analyzer<'a> {
 	/// Collected errors.
 	errors: &'a mut Errors,
+	/// Collected warnings.
+	warnings: &'a mut Warnings,
 	/// Scope stack to check declared variables.
 	scope: &'a mut scope::Stack,
-	/// Hashset to check duplicate symbols in dict keys.
-	dict_keys: &'a mut HashSet<Symbol>,
 	/// The symbol interner.
 	interner: &'a mut symbol::Interner,
 	/// Whether the analyzer is inside a function.
@@ -59,27 +101,46 @@ impl<'a> Analyzer<'a> {
 	/// Perform static semantic analysis in the given AST.
 	/// If the AST contains any ill-formed parts, the Program won't be produced, but no
 	/// error will be reported for such parts, as those errors were already reported by the
-	/// syntactic analysis.
-	pub fn analyze(ast: ast::Ast, interner: &mut symbol::Interner) -> Result<Program, Errors> {
+	/// syntactic analysis. Warnings are only produced along with the program.
+	pub fn analyze(
+		ast: ast::Ast,
+		interner: &mut symbol::Interner
+	) -> Result<(Program, Warnings), Errors> {
 		let mut scope = scope::Stack::default();
-		let mut dict_keys = HashSet::default();
 		let mut errors = Errors::default();
+		let mut warnings = Warnings::default();
 
 		let (result, root_frame) = {
-			let mut analyzer = Analyzer::new(interner, &mut scope, &mut dict_keys, &mut errors);
+			let mut analyzer = Analyzer::new(
+				interner,
+				&mut scope,
+				&mut errors,
+				&mut warnings,
+			);
 			let result = analyzer.analyze_block(ast.statements);
-			let root_frame = analyzer.exit_frame();
+			let root_frame = analyzer.exit_frame(
+				SourcePos { line: 1, column: 1, path: ast.source }
+			);
 			(result, root_frame)
 			// Drop analyzer before proceeding, making sure everything is clean.
 		};
 
+		// Some diagnostics are only produced when exiting scopes, which happens innermost
+		// first, so sort them to follow the source.
+		errors.0.sort_by_key(|error| (error.pos.line, error.pos.column));
+		warnings.0.sort_by_key(|warning| (warning.pos.line, warning.pos.column));
+
 		match result {
 			Some(statements) if errors.0.is_empty() => Ok(
-				Program {
-					source: ast.source,
-					statements,
-					root_slots: root_frame.slots,
-				}
+				(
+					Program {
+						source: ast.source,
+						statements,
+						root_slots: root_frame.slots,
+						root_names: root_frame.names,
+					},
+					warnings,
+				)
 			),
 
 			_ => Err(errors)
@@ -87,6 +148,60 @@ impl<'a> Analyzer<'a> {
 	}
 
 
+	/// Perform static semantic analysis in the given AST, as the next input of the session.
+	/// The program's root slots include the ones of previous inputs. If any errors are
+	/// detected, the session is left as it was. Unused variables are not reported, as later
+	/// inputs may use them.
+	pub fn analyze_session(
+		ast: ast::Ast,
+		interner: &mut symbol::Interner,
+		session: &mut Session,
+	) -> Result<(Program, Warnings), Errors> {
+		let mut errors = Errors::default();
+		let mut warnings = Warnings::default();
+
+		let (slots, _) = session.scope.frame_info();
+		session.scope.enter_block();
+
+		let result = Analyzer {
+			errors: &mut errors,
+			warnings: &mut warnings,
+			scope: &mut session.scope,
+			interner,
+			in_function: false,
+			in_loop: false,
+			dropped: true, // The block is kept open for later inputs.
+		}
+			.analyze_block(ast.statements);
+
+		errors.0.sort_by_key(|error| (error.pos.line, error.pos.column));
+		warnings.0.sort_by_key(|warning| (warning.pos.line, warning.pos.column));
+
+		match result {
+			Some(statements) if errors.0.is_empty() => {
+				let (root_slots, root_names) = session.scope.frame_info();
+
+				Ok(
+					(
+						Program {
+							source: ast.source,
+							statements,
+							root_slots,
+							root_names,
+						},
+						warnings,
+					)
+				)
+			}
+
+			_ => {
+				session.scope.discard_block(slots);
+				Err(errors)
+			}
+		}
+	}
+
+
 	/// Analyze a block.
 	/// None is returned if any error is detected.
 	fn analyze_block(&mut self, block: ast::Block) -> Option<Block> {
@@ -94,6 +209,19 @@ impl<'a> Analyzer<'a> {
 			ast::Block::IllFormed => None,
 
 			ast::Block::Block(block) => {
+				// Only statements directly in the block are considered, as conditionals are not
+				// evaluated statically. Statements following a return are rejected by the parser.
+				let unreachable = block
+					.iter()
+					.position(|statement| matches!(statement, ast::Statement::Break { .. }))
+					.and_then(|ix| block.get(ix + 1))
+					.map(ast::Statement::pos)
+					.filter(|pos| !pos.is_ill_formed());
+
+				if let Some(pos) = unreachable {
+					self.warn(Warning::unreachable_code(pos));
+				}
+
 				let block = self.analyze_items(
 					Self::analyze_statement,
 					block.into_vec(), // Use vec's owned iterator.
@@ -111,13 +239,20 @@ impl<'a> Analyzer<'a> {
 		match statement {
 			// Let.
 			ast::Statement::Let { identifier, init, pos } => {
-				let slot_ix = self.scope
-					.declare(identifier, pos)
+				let slot_ix = self
+					.declare(identifier, Declaration::Variable, pos)
 					.map_err(
 						|error| self.report(error)
 					)
 					.ok();
 
+				// Calls through variables holding function literals can be checked.
+				if let ast::Expr::Literal { literal: ast::Literal::Function { params, .. }, pos } = &init {
+					if slot_ix.is_some() {
+						self.scope.define_function(identifier, params.len() as u32, *pos);
+					}
+				}
+
 				let init = self.analyze_expr(init);
 
 				let (slot_ix, right) = slot_ix.zip(init)?;
@@ -145,6 +280,45 @@ impl<'a> Analyzer<'a> {
 				Some(Statement::Assign { left, right })
 			}
 
+			// Multiple assign.
+			ast::Statement::MultipleAssign { left, right, pos } => {
+				let count =
+					if left.len() == right.len() {
+						Some(())
+					} else {
+						self.report(Error::assignment_count_mismatch(left.len(), right.len(), pos));
+						None
+					};
+
+				let left = self.analyze_items(
+					|analyzer, left| analyzer
+						.analyze_lvalue(left)
+						.map_err(
+							|lvalue| if !lvalue {
+								analyzer.report(Error::invalid_assignment(pos));
+							}
+						)
+						.ok(),
+					left.into_vec(), // Use vec's owned iterator.
+				);
+
+				let right = self.analyze_items(Self::analyze_expr, right.into_vec());
+
+				let ((left, right), _) = left.zip(right).zip(count)?;
+
+				Some(Statement::MultipleAssign { left, right })
+			}
+
+			// Destructure.
+			ast::Statement::Destructure { pattern, declare, right, .. } => {
+				let left = self.analyze_pattern(pattern, declare);
+				let right = self.analyze_expr(right);
+
+				let (left, right) = left.zip(right)?;
+
+				Some(Statement::Assign { left, right })
+			}
+
 			// Return.
 			ast::Statement::Return { expr, pos } => {
 				let ret =
@@ -173,7 +347,11 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// While.
-			ast::Statement::While { condition, block, .. } => {
+			ast::Statement::While { condition, block, pos } => {
+				if let ast::Expr::Literal { literal: ast::Literal::Bool(false), .. } = condition {
+					self.warn(Warning::loop_never_runs(pos));
+				}
+
 				let condition = self.analyze_expr(condition);
 				let block = {
 					self.enter_loop().analyze_block(block)
@@ -194,8 +372,8 @@ impl<'a> Analyzer<'a> {
 						if identifier.is_ill_formed() {
 							None
 						} else {
-							analyzer.scope
-								.declare(identifier, pos)
+							analyzer
+								.declare(identifier, Declaration::Variable, pos)
 								.map_err(
 									|error| analyzer.report(error)
 								)
@@ -258,7 +436,7 @@ impl<'a> Analyzer<'a> {
 
 			// Literal.
 			ast::Expr::Literal { literal, pos } => {
-				let literal = self.analyze_literal(literal)?;
+				let literal = self.analyze_literal(literal, pos)?;
 				Some(Expr::Literal { literal, pos })
 			}
 
@@ -273,13 +451,7 @@ impl<'a> Analyzer<'a> {
 						None
 					}
 
-					op => Some(
-						Expr::UnaryOp {
-							op: op.into(),
-							operand: Box::new(operand),
-							pos,
-						}
-					)
+					op => Some(fold::unary_op(op.into(), operand, pos))
 				}
 			}
 
@@ -290,14 +462,7 @@ impl<'a> Analyzer<'a> {
 
 				let (left, right) = left.zip(right)?;
 
-				Some(
-					Expr::BinaryOp {
-						left: Box::new(left),
-						op: op.into(),
-						right: Box::new(right),
-						pos,
-					}
-				)
+				Some(fold::binary_op(left, op.into(), right, pos))
 			}
 
 			// If.
@@ -340,7 +505,25 @@ impl<'a> Analyzer<'a> {
 
 			// Call.
 			ast::Expr::Call { function, args, pos } => {
-				let function = self.analyze_expr(*function);
+				// The argument count is only known without spread arguments.
+				let args_count = if args.iter().any(|arg| matches!(arg, ast::Expr::Spread { .. })) {
+					None
+				} else {
+					Some(args.len() as u32)
+				};
+
+				let function = match *function {
+					// Calls through variables are registered for arity checking.
+					ast::Expr::Identifier { identifier, pos } if !identifier.is_ill_formed() => self.scope
+						.resolve_call(identifier, args_count, pos, self.interner)
+						.map_err(
+							|error| self.report(error)
+						)
+						.ok()
+						.map(|slot_ix| Expr::Identifier { slot_ix, pos }),
+
+					function => self.analyze_expr(function),
+				};
 
 				let args = self.analyze_items(
 					Self::analyze_expr,
@@ -358,6 +541,13 @@ impl<'a> Analyzer<'a> {
 				)
 			}
 
+			// Spread argument.
+			ast::Expr::Spread { expr, pos } => {
+				let expr = self.analyze_expr(*expr)?;
+
+				Some(Expr::Spread { expr: Box::new(expr), pos })
+			}
+
 			// Command block.
 			ast::Expr::CommandBlock { block, pos } => {
 				let block = self.analyze_command_block(block)?;
@@ -365,6 +555,16 @@ impl<'a> Analyzer<'a> {
 				Some(Expr::CommandBlock { block, pos })
 			},
 
+			// Interpolated string.
+			ast::Expr::Interpolation { parts, pos } => {
+				let parts = self.analyze_items(
+					Self::analyze_expr,
+					parts.into_vec(), // Use vec's owned iterator.
+				)?;
+
+				Some(Expr::Interpolation { parts, pos })
+			}
+
 			// Ill-formed.
 			ast::Expr::IllFormed => None,
 		}
@@ -383,7 +583,7 @@ impl<'a> Analyzer<'a> {
 						Err(false)
 					} else {
 						self.scope
-							.resolve(identifier, pos, self.interner)
+							.resolve_assignment(identifier, pos, self.interner)
 							.map_err(
 								|error| {
 									self.report(error);
@@ -397,11 +597,27 @@ impl<'a> Analyzer<'a> {
 
 			// Access.
 			ast::Expr::Access { object, field, pos } => {
+				// Fields of read-only variables may not be assigned, even if nested.
+				let mut root = &*object;
+				while let ast::Expr::Access { object, .. } = root {
+					root = object;
+				}
+
+				let writable = match root {
+					ast::Expr::Identifier { identifier, pos } if self.scope.is_read_only(*identifier) => {
+						self.report(Error::read_only_variable(*identifier, *pos));
+						None
+					}
+
+					_ => Some(()),
+				};
+
 				let object = self.analyze_expr(*object);
 				let field = self.analyze_expr(*field);
 
-				let (object, field) = object
+				let ((object, field), _) = object
 					.zip(field)
+					.zip(writable)
 					.ok_or(true)?;
 
 				Ok(
@@ -422,9 +638,98 @@ impl<'a> Analyzer<'a> {
 	}
 
 
+	/// Analyze a destructuring pattern, declaring its identifiers if requested.
+	/// None is returned if any error is detected.
+	fn analyze_pattern(&mut self, pattern: ast::Pattern, declare: bool) -> Option<Lvalue> {
+		match pattern {
+			// Identifier.
+			ast::Pattern::Identifier { identifier, .. } if identifier.is_ill_formed() => None,
+
+			ast::Pattern::Identifier { identifier, pos } => {
+				let slot_ix =
+					if declare {
+						self.declare(identifier, Declaration::Variable, pos)
+					} else {
+						self.scope.resolve_assignment(identifier, pos, self.interner)
+					};
+
+				let slot_ix = slot_ix
+					.map_err(|error| self.report(error))
+					.ok()?;
+
+				Some(Lvalue::Identifier { slot_ix, pos })
+			}
+
+			// Array.
+			ast::Pattern::Array { items, rest, pos } => {
+				let items = self.analyze_items(
+					|analyzer, item| analyzer.analyze_pattern(item, declare),
+					items.into_vec(), // Use vec's owned iterator.
+				);
+
+				let rest = match rest {
+					Some(rest) => Some(Box::new(self.analyze_pattern(*rest, declare)?)),
+					None => None,
+				};
+
+				Some(Lvalue::Array { items: items?, rest, pos })
+			}
+
+			// Dict.
+			ast::Pattern::Dict { items, pos } => {
+				let mut keys = HashMap::new();
+
+				let items = self.analyze_items(
+					|analyzer, item| {
+						let key = match item.key {
+							key if key.is_ill_formed() => None,
+
+							key => match keys.entry(key) {
+								Entry::Vacant(entry) => {
+									entry.insert(item.pos);
+									Some(key)
+								}
+
+								Entry::Occupied(entry) => { // Duplicate key.
+									analyzer.report(Error::duplicate_key(key, *entry.get(), item.pos));
+									None
+								}
+							}
+						};
+
+						// Defaults are analyzed before the item is declared, as they are evaluated
+						// before it is assigned.
+						let default = match item.default {
+							Some(default) => analyzer.analyze_expr(default).map(Some),
+							None => Some(None),
+						};
+
+						let lvalue = analyzer.analyze_pattern(item.pattern, declare);
+
+						Some(
+							DictLvalueItem {
+								key: key?,
+								lvalue: lvalue?,
+								default: default?,
+								pos: item.pos,
+							}
+						)
+					},
+					items.into_vec(), // Use vec's owned iterator.
+				);
+
+				Some(Lvalue::Dict { items: items?, pos })
+			}
+
+			// Ill-formed.
+			ast::Pattern::IllFormed => None,
+		}
+	}
+
+
 	/// Analyze a literal.
 	/// None is returned if any error is detected.
-	fn analyze_literal(&mut self, literal: ast::Literal) -> Option<Literal> {
+	fn analyze_literal(&mut self, literal: ast::Literal, pos: SourcePos) -> Option<Literal> {
 		match literal {
 			// Nil.
 			ast::Literal::Nil => Some(Literal::Nil),
@@ -451,35 +756,46 @@ impl<'a> Analyzer<'a> {
 					array.into_vec(), // Use vec's owned iterator.
 				)?;
 
-				Some(Literal::Array(array))
+				Some(fold::array(array))
 			}
 
 			// Dict.
 			ast::Literal::Dict(items) => {
-				self.dict_keys.clear();
+				// Only identifier keys are known statically, and therefore checked for duplicates.
+				let mut keys = HashMap::new();
 
 				let items = self.analyze_items(
-					|analyzer, ((symbol, pos), expr)| {
-						let symbol =
-							if symbol.is_ill_formed() {
-								None
-							} else if analyzer.dict_keys.insert(symbol) {
-								Some(symbol)
-							} else { // Duplicate symbol.
-								analyzer.report(Error::duplicate_key(symbol, pos));
-								None
-							};
+					|analyzer, (key, expr)| {
+						let key = match key {
+							ast::DictKey::Identifier(symbol, _) if symbol.is_ill_formed() => None,
+
+							ast::DictKey::Identifier(symbol, pos) => match keys.entry(symbol) {
+								Entry::Vacant(entry) => {
+									entry.insert(pos);
+									Some(DictKey::Symbol(Name::new(symbol, analyzer.interner)))
+								}
+
+								Entry::Occupied(entry) => { // Duplicate symbol.
+									analyzer.report(Error::duplicate_key(symbol, *entry.get(), pos));
+									None
+								}
+							}
+
+							ast::DictKey::Computed(expr) => analyzer
+								.analyze_expr(expr)
+								.map(DictKey::Computed),
+						};
 
 						let expr = analyzer.analyze_expr(expr);
 
-						let (symbol, expr) = symbol.zip(expr)?;
+						let (key, expr) = key.zip(expr)?;
 
-						Some((symbol, expr))
+						Some((key, expr))
 					},
 					items.into_vec(), // Use vec's owned iterator.
 				)?;
 
-				Some(Literal::Dict(items))
+				Some(fold::dict(items))
 			}
 
 			// Function.
@@ -494,8 +810,8 @@ impl<'a> Analyzer<'a> {
 							let result = if symbol.is_ill_formed() {
 								None
 							} else {
-								analyzer.scope
-									.declare(symbol, pos)
+								analyzer
+									.declare(symbol, Declaration::Parameter, pos)
 									.map_err(
 										|error| analyzer.report(error)
 									)
@@ -509,7 +825,7 @@ impl<'a> Analyzer<'a> {
 
 				let body = analyzer.analyze_block(body);
 
-				let frame_info = analyzer.exit_frame();
+				let frame_info = analyzer.exit_frame(pos);
 
 				let (_, body) = params_result.zip(body)?;
 
@@ -527,7 +843,7 @@ impl<'a> Analyzer<'a> {
 				if identifier.is_ill_formed() {
 					None
 				} else {
-					Some(Literal::Identifier(identifier))
+					Some(Literal::Identifier(Name::new(identifier, self.interner)))
 				}
 			}
 		}
@@ -793,20 +1109,15 @@ impl<'a> Analyzer<'a> {
 	fn new(
 		interner: &'a mut symbol::Interner,
 		scope: &'a mut scope::Stack,
-		dict_keys: &'a mut HashSet<Symbol>,
-		errors: &'a mut Errors
+		errors: &'a mut Errors,
+		warnings: &'a mut Warnings,
 	) -> Self {
-		let std_symbol = interner.get_or_intern("std");
-
-		scope.enter_frame();
-		scope
-			.declare(std_symbol, SourcePos::default())
-			.expect("failed to insert std symbol");
+		Self::enter_root(scope, interner);
 
 		Self {
 			errors,
+			warnings,
 			scope,
-			dict_keys,
 			interner,
 			in_function: false,
 			in_loop: false,
@@ -815,14 +1126,25 @@ impl<'a> Analyzer<'a> {
 	}
 
 
+	/// Enter the root frame, where the stdlib is declared.
+	fn enter_root(scope: &mut scope::Stack, interner: &mut symbol::Interner) {
+		let std_symbol = interner.get_or_intern("std");
+
+		scope.enter_frame();
+		scope
+			.declare(std_symbol, Declaration::ReadOnly, SourcePos::default())
+			.expect("failed to insert std symbol");
+	}
+
+
 	/// Enter a new block scope.
 	fn enter_block(&mut self) -> Analyzer {
 		self.scope.enter_block();
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
-			dict_keys: self.dict_keys,
 			interner: self.interner,
 			in_function: self.in_function,
 			in_loop: self.in_loop,
@@ -837,8 +1159,8 @@ impl<'a> Analyzer<'a> {
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
-			dict_keys: self.dict_keys,
 			interner: self.interner,
 			in_function: self.in_function,
 			in_loop: true,
@@ -853,8 +1175,8 @@ impl<'a> Analyzer<'a> {
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
-			dict_keys: self.dict_keys,
 			interner: self.interner,
 			in_function: true,
 			in_loop: false,
@@ -864,9 +1186,46 @@ impl<'a> Analyzer<'a> {
 
 
 	/// Exit a function, dropping it's scope and returning the generated FrameInfo.
-	fn exit_frame(mut self) -> mem::FrameInfo {
+	/// Frames with too many slots are reported at the given position.
+	fn exit_frame(mut self, pos: SourcePos) -> mem::FrameInfo {
 		self.dropped = true;
-		self.scope.exit_frame()
+		let frame_info = self.scope.exit_frame(
+			scope::Diagnostics {
+				errors: self.errors,
+				warnings: self.warnings,
+				interner: self.interner,
+			}
+		);
+
+		if frame_info.slots.0 > mem::FrameInfo::MAX_SLOTS {
+			self.report(
+				Error::frame_too_large(frame_info.slots.0, mem::FrameInfo::MAX_SLOTS, pos)
+			);
+		}
+
+		frame_info
+	}
+
+
+	/// Declare a variable in the current scope, warning if it shadows another declaration.
+	/// Names starting with an underscore may be freely shadowed.
+	fn declare(
+		&mut self,
+		symbol: Symbol,
+		declaration: Declaration,
+		pos: SourcePos
+	) -> Result<mem::SlotIx, Error> {
+		let deliberate = self.interner
+			.resolve(symbol)
+			.is_some_and(|name| name.starts_with(b"_"));
+
+		if !deliberate {
+			if let Some((shadowed, outer)) = self.scope.shadowed(symbol) {
+				self.warn(Warning::shadowed_variable(symbol, shadowed, outer, pos));
+			}
+		}
+
+		self.scope.declare(symbol, declaration, pos)
 	}
 
 
@@ -874,13 +1233,25 @@ impl<'a> Analyzer<'a> {
 	fn report(&mut self, error: Error) {
 		self.errors.0.push(error);
 	}
+
+
+	/// Report a warning.
+	fn warn(&mut self, warning: Warning) {
+		self.warnings.0.push(warning);
+	}
 }
 
 
 impl<'a> Drop for Analyzer<'a> {
 	fn drop(&mut self) {
 		if !self.dropped {
-			self.scope.exit_block();
+			self.scope.exit_block(
+				scope::Diagnostics {
+					errors: self.errors,
+					warnings: self.warnings,
+					interner: self.interner,
+				}
+			);
 		}
 	}
 }
@@ -0,0 +1,877 @@
+//! A compact binary format for analyzed programs, so that scripts may skip parsing and
+//! analysis on startup.
+//!
+//! The format starts with a header, containing the format version, the version of the
+//! interpreter and a hash of the source code, followed by the table of used symbols and the
+//! program itself. Symbols are stored as indices in the table, and are interned again when
+//! decoding, so that positions keep pointing at the original file.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use crate::{runtime::value::Str, symbol, syntax::parser};
+use super::{
+	mem::{Capture, FrameInfo, SlotIx},
+	ArgPart,
+	ArgUnit,
+	Argument,
+	BasicCommand,
+	BinaryOp,
+	Block,
+	Builtin,
+	Command,
+	CommandBlock,
+	CommandBlockKind,
+	DictKey,
+	DictLvalueItem,
+	Expr,
+	Literal,
+	Lvalue,
+	Name,
+	Program,
+	Redirection,
+	RedirectionTarget,
+	SourcePos,
+	Statement,
+	Symbol,
+	Template,
+	UnaryOp,
+};
+
+
+/// Identifies compiled programs.
+const MAGIC: &[u8] = b"\x7fHUSHC\0";
+
+/// The version of the format, bumped on every change to the encoding.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// The interpreter version, as compiled programs depend on the analyzer's output.
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The maximum nesting depth of decoded variants. As the decoder is recursive, deeper
+/// inputs would overflow the stack. Programs come from parsed source, whose nesting is
+/// limited by the parser, but chains of operators nest without recursing in the parser,
+/// so this leaves room for those.
+const MAX_DEPTH: usize = 16 * parser::MAX_DEPTH;
+
+
+/// Whether the given contents are a compiled program rather than source code.
+pub fn is_compiled(contents: &[u8]) -> bool {
+	contents.starts_with(MAGIC)
+}
+
+
+/// Hash source code, in order to detect stale compiled programs. This uses FNV-1a, which
+/// is stable across builds.
+pub fn hash(source: &[u8]) -> u64 {
+	source
+		.iter()
+		.fold(
+			0xcbf29ce484222325,
+			|hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+		)
+}
+
+
+/// The header of a compiled program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+	/// The hash of the source code the program was compiled from.
+	pub source_hash: u64,
+}
+
+
+/// Why a compiled program can't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+	/// The contents are not a compiled program.
+	NotCompiled,
+	/// The program was compiled with another version of the format.
+	FormatVersion(u32),
+	/// The program was compiled by another version of the interpreter.
+	CrateVersion(Box<str>),
+	/// The program is truncated or malformed.
+	Corrupt,
+	/// The program exceeds the maximum nesting depth.
+	TooDeep,
+}
+
+
+impl std::fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NotCompiled => write!(f, "not a compiled program"),
+			Self::FormatVersion(version) => write!(
+				f,
+				"compiled program has format version {}, expected {}",
+				version,
+				FORMAT_VERSION
+			),
+			Self::CrateVersion(version) => write!(
+				f,
+				"program was compiled by hush {}, this is hush {}",
+				version,
+				CRATE_VERSION
+			),
+			Self::Corrupt => write!(f, "compiled program is corrupt"),
+			Self::TooDeep => write!(f, "compiled program is nested too deeply"),
+		}
+	}
+}
+
+
+impl std::error::Error for DecodeError { }
+
+
+/// Encode a program, along with the symbols it uses.
+pub fn encode(program: &Program, interner: &symbol::Interner, source_hash: u64) -> Vec<u8> {
+	let mut encoder = Encoder {
+		buffer: Vec::new(),
+		symbols: Vec::new(),
+		indices: HashMap::new(),
+	};
+
+	program.encode(&mut encoder);
+
+	let body = std::mem::take(&mut encoder.buffer);
+
+	encoder.buffer.extend_from_slice(MAGIC);
+	encoder.buffer.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+	encoder.bytes(CRATE_VERSION.as_bytes());
+	encoder.buffer.extend_from_slice(&source_hash.to_le_bytes());
+
+	encoder.varint(encoder.symbols.len() as u64);
+	for symbol in std::mem::take(&mut encoder.symbols) {
+		let value = interner
+			.resolve(symbol)
+			.expect("failed to resolve symbol");
+		encoder.bytes(value);
+	}
+
+	encoder.buffer.extend(body);
+	encoder.buffer
+}
+
+
+/// Read the header of a compiled program, checking its versions.
+pub fn header(contents: &[u8]) -> Result<Header, DecodeError> {
+	Decoder::new(contents).header()
+}
+
+
+/// Decode a compiled program, interning its symbols in the given interner.
+pub fn decode(
+	contents: &[u8],
+	interner: &mut symbol::Interner,
+) -> Result<(Header, Program), DecodeError> {
+	let mut decoder = Decoder::new(contents);
+	let header = decoder.header()?;
+
+	let count = decoder.len()?;
+	for _ in 0 .. count {
+		let value = decoder.bytes()?;
+		decoder.symbols.push((interner.get_or_intern(value), value));
+	}
+
+	let program = Program::decode(&mut decoder)?;
+
+	if decoder.contents.is_empty() {
+		Ok((header, program))
+	} else {
+		Err(DecodeError::Corrupt)
+	}
+}
+
+
+struct Encoder {
+	buffer: Vec<u8>,
+	/// The used symbols, in order of first use.
+	symbols: Vec<Symbol>,
+	/// The index of each used symbol.
+	indices: HashMap<Symbol, u32>,
+}
+
+
+impl Encoder {
+	/// LEB128 encoding, as most numbers are small.
+	fn varint(&mut self, mut value: u64) {
+		loop {
+			let byte = (value & 0x7f) as u8;
+			value >>= 7;
+
+			if value == 0 {
+				self.buffer.push(byte);
+				break;
+			}
+
+			self.buffer.push(byte | 0x80);
+		}
+	}
+
+
+	fn tag(&mut self, tag: u8) {
+		self.buffer.push(tag);
+	}
+
+
+	fn bytes(&mut self, bytes: &[u8]) {
+		self.varint(bytes.len() as u64);
+		self.buffer.extend_from_slice(bytes);
+	}
+
+
+	fn symbol(&mut self, symbol: Symbol) {
+		let next = self.symbols.len() as u32;
+		let index = *self.indices.entry(symbol).or_insert(next);
+
+		if index == next {
+			self.symbols.push(symbol);
+		}
+
+		self.varint(index as u64);
+	}
+}
+
+
+struct Decoder<'a> {
+	contents: &'a [u8],
+	/// The symbols in the table, interned, along with their values.
+	symbols: Vec<(Symbol, &'a [u8])>,
+	/// The current nesting depth, see `MAX_DEPTH`.
+	depth: usize,
+}
+
+
+impl<'a> Decoder<'a> {
+	fn new(contents: &'a [u8]) -> Self {
+		Self { contents, symbols: Vec::new(), depth: 0 }
+	}
+
+
+	fn header(&mut self) -> Result<Header, DecodeError> {
+		if !is_compiled(self.contents) {
+			return Err(DecodeError::NotCompiled);
+		}
+		self.take(MAGIC.len())?;
+
+		let version = u32::from_le_bytes(self.array()?);
+		if version != FORMAT_VERSION {
+			return Err(DecodeError::FormatVersion(version));
+		}
+
+		let crate_version = self.bytes()?;
+		if crate_version != CRATE_VERSION.as_bytes() {
+			return Err(
+				DecodeError::CrateVersion(String::from_utf8_lossy(crate_version).into())
+			);
+		}
+
+		let source_hash = u64::from_le_bytes(self.array()?);
+
+		Ok(Header { source_hash })
+	}
+
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+		if len > self.contents.len() {
+			return Err(DecodeError::Corrupt);
+		}
+
+		let (taken, rest) = self.contents.split_at(len);
+		self.contents = rest;
+
+		Ok(taken)
+	}
+
+
+	fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+		let mut array = [0; N];
+		array.copy_from_slice(self.take(N)?);
+		Ok(array)
+	}
+
+
+	fn varint(&mut self) -> Result<u64, DecodeError> {
+		let mut value = 0;
+
+		for shift in (0 .. 64).step_by(7) {
+			let byte = self.take(1)?[0];
+			value |= ((byte & 0x7f) as u64) << shift;
+
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+		}
+
+		Err(DecodeError::Corrupt)
+	}
+
+
+	fn tag(&mut self) -> Result<u8, DecodeError> {
+		Ok(self.take(1)?[0])
+	}
+
+
+	/// A length, which can't exceed the remaining contents, as every item takes at least a
+	/// byte. This prevents huge allocations from corrupt lengths.
+	fn len(&mut self) -> Result<usize, DecodeError> {
+		let len = self.varint()?;
+
+		if len > self.contents.len() as u64 {
+			Err(DecodeError::Corrupt)
+		} else {
+			Ok(len as usize)
+		}
+	}
+
+
+	fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+		let len = self.len()?;
+		self.take(len)
+	}
+
+
+	fn symbol(&mut self) -> Result<Symbol, DecodeError> {
+		self.symbol_value().map(|(symbol, _)| symbol)
+	}
+
+
+	fn symbol_value(&mut self) -> Result<(Symbol, &'a [u8]), DecodeError> {
+		let index = self.varint()?;
+
+		self.symbols
+			.get(index as usize)
+			.copied()
+			.ok_or(DecodeError::Corrupt)
+	}
+
+
+	/// Decode a nested variant, failing if the maximum nesting depth is exceeded.
+	fn nested<F, T>(&mut self, decode: F) -> Result<T, DecodeError>
+	where
+		F: FnOnce(&mut Self) -> Result<T, DecodeError>,
+	{
+		if self.depth >= MAX_DEPTH {
+			return Err(DecodeError::TooDeep);
+		}
+
+		self.depth += 1;
+		let result = decode(self);
+		self.depth -= 1;
+
+		result
+	}
+}
+
+
+trait Encode {
+	fn encode(&self, encoder: &mut Encoder);
+}
+
+
+trait Decode: Sized {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError>;
+}
+
+
+impl Encode for u32 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.varint(*self as u64)
+	}
+}
+
+
+impl Decode for u32 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		let value = decoder.varint()?;
+		u32::try_from(value).map_err(|_| DecodeError::Corrupt)
+	}
+}
+
+
+impl Encode for i64 {
+	/// Zigzag encoding, so that small negative numbers are small as well.
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.varint(((self << 1) ^ (self >> 63)) as u64)
+	}
+}
+
+
+impl Decode for i64 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		let value = decoder.varint()?;
+		Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+	}
+}
+
+
+/// File descriptors.
+impl Encode for i32 {
+	fn encode(&self, encoder: &mut Encoder) {
+		(*self as i64).encode(encoder)
+	}
+}
+
+
+impl Decode for i32 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		i32::try_from(i64::decode(decoder)?).map_err(|_| DecodeError::Corrupt)
+	}
+}
+
+
+impl Encode for u8 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.tag(*self)
+	}
+}
+
+
+impl Decode for u8 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		decoder.tag()
+	}
+}
+
+
+impl Encode for bool {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.tag(*self as u8)
+	}
+}
+
+
+impl Decode for bool {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		match decoder.tag()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(DecodeError::Corrupt),
+		}
+	}
+}
+
+
+impl Encode for f64 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.buffer.extend_from_slice(&self.to_le_bytes())
+	}
+}
+
+
+impl Decode for f64 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		Ok(f64::from_le_bytes(decoder.array()?))
+	}
+}
+
+
+impl Encode for Symbol {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.symbol(*self)
+	}
+}
+
+
+impl Decode for Symbol {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		decoder.symbol()
+	}
+}
+
+
+/// Names are stored as their symbol, and resolved again when decoding.
+impl Encode for Name {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.symbol(self.symbol)
+	}
+}
+
+
+impl Decode for Name {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		let (symbol, value) = decoder.symbol_value()?;
+		Ok(Self { symbol, string: value.into() })
+	}
+}
+
+
+impl Encode for Str {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.bytes(self.as_bytes())
+	}
+}
+
+
+impl Decode for Str {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		decoder.bytes().map(Into::into)
+	}
+}
+
+
+impl Encode for Box<[u8]> {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.bytes(self)
+	}
+}
+
+
+impl Decode for Box<[u8]> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		decoder.bytes().map(Into::into)
+	}
+}
+
+
+impl<T: Encode> Encode for Box<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.as_ref().encode(encoder)
+	}
+}
+
+
+impl<T: Decode> Decode for Box<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		T::decode(decoder).map(Box::new)
+	}
+}
+
+
+/// Byte strings are handled separately, as they are encoded as a whole.
+trait Item { }
+
+impl Item for Statement { }
+impl Item for Expr { }
+impl Item for Template { }
+impl Item for Lvalue { }
+impl Item for DictLvalueItem { }
+impl Item for Capture { }
+impl Item for Command { }
+impl Item for BasicCommand { }
+impl Item for Argument { }
+impl Item for ArgPart { }
+impl Item for ArgUnit { }
+impl Item for Redirection { }
+impl<A, B> Item for (A, B) { }
+
+
+impl<T: Encode + Item> Encode for Box<[T]> {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.varint(self.len() as u64);
+
+		for item in self.iter() {
+			item.encode(encoder);
+		}
+	}
+}
+
+
+impl<T: Decode + Item> Decode for Box<[T]> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		let len = decoder.len()?;
+		let mut items = Vec::with_capacity(len);
+
+		for _ in 0 .. len {
+			items.push(T::decode(decoder)?);
+		}
+
+		Ok(items.into())
+	}
+}
+
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			None => encoder.tag(0),
+			Some(value) => {
+				encoder.tag(1);
+				value.encode(encoder);
+			}
+		}
+	}
+}
+
+
+impl<T: Decode> Decode for Option<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		match decoder.tag()? {
+			0 => Ok(None),
+			1 => T::decode(decoder).map(Some),
+			_ => Err(DecodeError::Corrupt),
+		}
+	}
+}
+
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.0.encode(encoder);
+		self.1.encode(encoder);
+	}
+}
+
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		Ok((A::decode(decoder)?, B::decode(decoder)?))
+	}
+}
+
+
+/// Implement encoding for structs, field by field.
+macro_rules! record {
+	($type: ident { $($field: ident),* $(,)? }) => {
+		impl Encode for $type {
+			fn encode(&self, encoder: &mut Encoder) {
+				$( self.$field.encode(encoder); )*
+			}
+		}
+
+		impl Decode for $type {
+			fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+				Ok(Self { $( $field: Decode::decode(decoder)?, )* })
+			}
+		}
+	};
+}
+
+
+/// Implement encoding for enums, tagging each variant with its index.
+macro_rules! variants {
+	(
+		$type: ident {
+			$( $tag: literal => $variant: ident $( { $($field: ident),* $(,)? } )? $( ( $($item: ident),* ) )? ),* $(,)?
+		}
+	) => {
+		impl Encode for $type {
+			fn encode(&self, encoder: &mut Encoder) {
+				match self {
+					$(
+						$type::$variant $( { $($field),* } )? $( ( $($item),* ) )? => {
+							encoder.tag($tag);
+							$( $( $field.encode(encoder); )* )?
+							$( $( $item.encode(encoder); )* )?
+						}
+					)*
+				}
+			}
+		}
+
+		impl Decode for $type {
+			fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+				decoder.nested(
+					|decoder| match decoder.tag()? {
+						$(
+							$tag => Ok(
+								$type::$variant
+									$( { $($field: Decode::decode(decoder)?),* } )?
+									$( ( $( variants!(@decode decoder, $item) ),* ) )?
+							),
+						)*
+						_ => Err(DecodeError::Corrupt),
+					}
+				)
+			}
+		}
+	};
+
+	(@decode $decoder: ident, $item: ident) => { Decode::decode($decoder)? };
+}
+
+
+record!(SourcePos { line, column, path });
+record!(Capture { from, to });
+record!(FrameInfo { slots, captures, self_slot, names });
+record!(DictLvalueItem { key, lvalue, default, pos });
+record!(Argument { parts, pos });
+record!(BasicCommand { program, env, arguments, redirections, abort_on_error, pos });
+record!(CommandBlock { kind, head, tail });
+record!(Program { source, statements, root_slots, root_names });
+
+
+impl Encode for SlotIx {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.0.encode(encoder)
+	}
+}
+
+
+impl Decode for SlotIx {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		u32::decode(decoder).map(SlotIx)
+	}
+}
+
+
+impl Encode for Block {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.0.encode(encoder)
+	}
+}
+
+
+impl Decode for Block {
+	fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+		Decode::decode(decoder).map(Block)
+	}
+}
+
+
+variants!(
+	Statement {
+		0 => Assign { left, right },
+		1 => MultipleAssign { left, right },
+		2 => Return { expr },
+		3 => Break,
+		4 => While { condition, block },
+		5 => For { slot_ix, expr, block },
+		6 => Expr(expr),
+	}
+);
+
+variants!(
+	Expr {
+		0 => Identifier { slot_ix, pos },
+		1 => Literal { literal, pos },
+		2 => UnaryOp { op, operand, pos },
+		3 => BinaryOp { left, op, right, pos },
+		4 => If { condition, then, otherwise, pos },
+		5 => Access { object, field, pos },
+		6 => Call { function, args, pos },
+		7 => CommandBlock { block, pos },
+		8 => Interpolation { parts, pos },
+		9 => Spread { expr, pos },
+	}
+);
+
+variants!(
+	Literal {
+		0 => Nil,
+		1 => Bool(b),
+		2 => Int(int),
+		3 => Float(float),
+		4 => Byte(byte),
+		5 => String(string),
+		6 => Array(items),
+		7 => Dict(items),
+		8 => Function { params, frame_info, body },
+		9 => Identifier(name),
+		10 => Template(template),
+	}
+);
+
+variants!(
+	Template {
+		0 => Nil,
+		1 => Bool(b),
+		2 => Int(int),
+		3 => Float(float),
+		4 => Byte(byte),
+		5 => String(string),
+		6 => Array(items),
+		7 => Dict(items),
+	}
+);
+
+variants!(
+	DictKey {
+		0 => Symbol(name),
+		1 => Computed(expr),
+	}
+);
+
+variants!(
+	UnaryOp {
+		0 => Minus,
+		1 => Not,
+		2 => Try,
+	}
+);
+
+variants!(
+	BinaryOp {
+		0 => Plus,
+		1 => Minus,
+		2 => Times,
+		3 => Div,
+		4 => Mod,
+		5 => Equals,
+		6 => NotEquals,
+		7 => Greater,
+		8 => GreaterEquals,
+		9 => Lower,
+		10 => LowerEquals,
+		11 => And,
+		12 => Or,
+		13 => Concat,
+		14 => FloorDiv,
+		15 => Pow,
+	}
+);
+
+variants!(
+	Lvalue {
+		0 => Identifier { slot_ix, pos },
+		1 => Access { object, field, pos },
+		2 => Array { items, rest, pos },
+		3 => Dict { items, pos },
+	}
+);
+
+variants!(
+	CommandBlockKind {
+		0 => Synchronous,
+		1 => Asynchronous,
+		2 => Capture,
+	}
+);
+
+variants!(
+	Command {
+		0 => Builtin { program, arguments, abort_on_error, pos },
+		1 => External { head, tail },
+	}
+);
+
+variants!(
+	Builtin {
+		0 => Alias,
+		1 => Cd,
+	}
+);
+
+variants!(
+	ArgUnit {
+		0 => Literal(literal),
+		1 => Dollar { slot_ix, pos },
+	}
+);
+
+variants!(
+	ArgPart {
+		0 => Unit(unit),
+		1 => Home,
+		2 => Range(from, to),
+		3 => Collection(units),
+		4 => Star,
+		5 => Percent,
+		6 => CharClass(class),
+	}
+);
+
+variants!(
+	RedirectionTarget {
+		0 => Fd(fd),
+		1 => Overwrite(argument),
+		2 => Append(argument),
+	}
+);
+
+variants!(
+	Redirection {
+		0 => Output { source, target },
+		1 => Input { literal, source },
+	}
+);
+
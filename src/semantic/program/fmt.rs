@@ -14,12 +14,15 @@ use super::{
 	Command,
 	CommandBlock,
 	CommandBlockKind,
+	DictKey,
+	DictLvalueItem,
 	Expr,
 	Literal,
 	Lvalue,
 	Redirection,
 	RedirectionTarget,
 	Statement,
+	Template,
 	UnaryOp,
 };
 use crate::{
@@ -82,6 +85,23 @@ impl<'a> Display<'a> for Block {
 }
 
 
+impl<'a> Display<'a> for DictKey {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Symbol(name) => name.symbol.fmt(f, context.interner),
+
+			Self::Computed(expr) => {
+				"[".fmt(f)?;
+				expr.fmt(f, context)?;
+				"]".fmt(f)
+			}
+		}
+	}
+}
+
+
 impl<'a> Display<'a> for Literal {
 	type Context = Context<'a>;
 
@@ -135,7 +155,7 @@ impl<'a> Display<'a> for Literal {
 					f,
 					|(k, v), f| {
 						step(f, nested)?;
-						k.fmt(f, nested.interner)?;
+						k.fmt(f, nested)?;
 						": ".fmt(f)?;
 						v.fmt(f, nested)
 					},
@@ -149,6 +169,8 @@ impl<'a> Display<'a> for Literal {
 				"]".fmt(f)
 			},
 
+			Self::Template(template) => template.fmt(f, context),
+
 			Self::Function { params, frame_info, body } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
@@ -174,7 +196,79 @@ impl<'a> Display<'a> for Literal {
 				Keyword::End.fmt(f)
 			}
 
-			Self::Identifier(identifier) => identifier.fmt(f, context.interner),
+			Self::Identifier(name) => name.symbol.fmt(f, context.interner),
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Template {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Nil => color::Fg(color::Blue, "nil").fmt(f),
+
+			Self::Bool(b) => color::Fg(color::Blue, b).fmt(f),
+
+			Self::Int(i) => i.fmt(f),
+
+			Self::Float(n) => n.fmt(f),
+
+			Self::Byte(c) => write!(f, "'{}'", color::Bold((*c as char).escape_debug())),
+
+			Self::String(s) => write!(
+				f,
+				"\"{}\"",
+				color::Bold(String::from_utf8_lossy(s.as_bytes()).escape_debug())
+			),
+
+			Self::Array(arr) => {
+				let nested = context.indent();
+
+				"[".fmt(f)?;
+
+				fmt::sep_by(
+					arr.iter(),
+					f,
+					|item, f| {
+						step(f, nested)?;
+						item.fmt(f, nested)
+					},
+					",",
+				)?;
+
+				if !arr.is_empty() {
+					step(f, context)?;
+				}
+
+				"]".fmt(f)
+			},
+
+			Self::Dict(dict) => {
+				let nested = context.indent();
+
+				"@[".fmt(f)?;
+
+				fmt::sep_by(
+					dict.iter(),
+					f,
+					|(k, v), f| {
+						step(f, nested)?;
+						"[".fmt(f)?;
+						k.fmt(f, nested)?;
+						"]: ".fmt(f)?;
+						v.fmt(f, nested)
+					},
+					",",
+				)?;
+
+				if !dict.is_empty() {
+					step(f, context)?;
+				}
+
+				"]".fmt(f)
+			},
 		}
 	}
 }
@@ -198,7 +292,9 @@ impl std::fmt::Display for BinaryOp {
 			Self::Minus => Operator::Minus.fmt(f),
 			Self::Times => Operator::Times.fmt(f),
 			Self::Div => Operator::Div.fmt(f),
+			Self::FloorDiv => Operator::FloorDiv.fmt(f),
 			Self::Mod => Operator::Mod.fmt(f),
+			Self::Pow => Operator::Pow.fmt(f),
 			Self::Equals => Operator::Equals.fmt(f),
 			Self::NotEquals => Operator::NotEquals.fmt(f),
 			Self::Greater => Operator::Greater.fmt(f),
@@ -314,7 +410,32 @@ impl<'a> Display<'a> for Expr {
 				")".fmt(f)
 			}
 
+			Self::Spread { expr, .. } => {
+				"...".fmt(f)?;
+				expr.fmt(f, context.inlined())
+			}
+
 			Self::CommandBlock { block, .. } => block.fmt(f, context),
+
+			Self::Interpolation { parts, .. } => {
+				"\"".fmt(f)?;
+
+				for part in parts.iter() {
+					match part {
+						Self::Literal { literal: Literal::String(string), .. } => {
+							String::from_utf8_lossy(string).escape_debug().fmt(f)?;
+						}
+
+						expr => {
+							"${".fmt(f)?;
+							expr.fmt(f, context.inlined())?;
+							"}".fmt(f)?;
+						}
+					}
+				}
+
+				"\"".fmt(f)
+			}
 		}
 	}
 }
@@ -333,11 +454,63 @@ impl<'a> Display<'a> for Lvalue {
 				field.fmt(f, context.inlined())?;
 				"]".fmt(f)
 			}
+
+			Self::Array { items, rest, .. } => {
+				"[".fmt(f)?;
+
+				fmt::sep_by(
+					items.iter(),
+					f,
+					|item, f| item.fmt(f, context),
+					", ",
+				)?;
+
+				if let Some(rest) = rest {
+					if !items.is_empty() {
+						", ".fmt(f)?;
+					}
+					"...".fmt(f)?;
+					rest.fmt(f, context)?;
+				}
+
+				"]".fmt(f)
+			}
+
+			Self::Dict { items, .. } => {
+				"@[".fmt(f)?;
+
+				fmt::sep_by(
+					items.iter(),
+					f,
+					|item, f| item.fmt(f, context),
+					", ",
+				)?;
+
+				"]".fmt(f)
+			}
 		}
 	}
 }
 
 
+impl<'a> Display<'a> for DictLvalueItem {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		self.key.fmt(f, context.interner)?;
+		": ".fmt(f)?;
+		self.lvalue.fmt(f, context)?;
+
+		if let Some(default) = &self.default {
+			" = ".fmt(f)?;
+			default.fmt(f, context.inlined())?;
+		}
+
+		Ok(())
+	}
+}
+
+
 impl<'a> Display<'a> for Statement {
 	type Context = Context<'a>;
 
@@ -349,6 +522,12 @@ impl<'a> Display<'a> for Statement {
 				right.fmt(f, context)
 			}
 
+			Self::MultipleAssign { left, right } => {
+				fmt::sep_by(left.iter(), f, |item, f| item.fmt(f, context.inlined()), ", ")?;
+				" = ".fmt(f)?;
+				fmt::sep_by(right.iter(), f, |item, f| item.fmt(f, context.inlined()), ", ")
+			}
+
 			Self::Return { expr } => {
 				Keyword::Return.fmt(f)?;
 				" ".fmt(f)?;
@@ -662,6 +841,7 @@ impl<'a> Display<'a> for Program {
 			slots: self.root_slots,
 			captures: Box::default(),
 			self_slot: None,
+			names: Box::default(),
 		};
 
 		root_frame.fmt(f, context.indentation)?;
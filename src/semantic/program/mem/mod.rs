@@ -1,6 +1,7 @@
 mod fmt;
 
 use super::lexer;
+use crate::symbol::Symbol;
 
 
 /// The index of a memory slot in the activation record.
@@ -36,4 +37,13 @@ pub struct FrameInfo {
 	pub captures: Box<[Capture]>,
 	/// Where to insert `self`.
 	pub self_slot: Option<SlotIx>,
+	/// The names of the variables in the activation record, for debuggers.
+	pub names: Box<[(Symbol, SlotIx)]>,
+}
+
+
+impl FrameInfo {
+	/// The maximum number of slots in an activation record. Larger frames are rejected by
+	/// the semantic analysis, as they would hardly fit in the stack of the runtime.
+	pub const MAX_SLOTS: u32 = 65_535;
 }
@@ -1,8 +1,13 @@
+pub mod binary;
 pub mod command;
 pub mod fmt;
 pub mod mem;
+pub mod visit;
+
+use std::convert::TryFrom;
 
 use super::{ast, lexer};
+use crate::{runtime::value::Str, symbol};
 pub use crate::{
 	syntax::SourcePos,
 	symbol::Symbol,
@@ -12,6 +17,7 @@ pub use command::{
 	ArgUnit,
 	Argument,
 	BasicCommand,
+	Builtin,
 	Command,
 	CommandBlock,
 	CommandBlockKind,
@@ -32,6 +38,37 @@ impl From<Box<[Statement]>> for Block {
 }
 
 
+/// An identifier used as a literal string. The string is resolved during analysis, so
+/// that evaluation is just a copy.
+#[derive(Debug)]
+pub struct Name {
+	pub symbol: Symbol,
+	pub string: Str,
+}
+
+
+impl Name {
+	pub fn new(symbol: Symbol, interner: &symbol::Interner) -> Self {
+		let string = interner
+			.resolve(symbol)
+			.expect("unresolved symbol")
+			.into();
+
+		Self { symbol, string }
+	}
+}
+
+
+/// Keys in dict literals.
+#[derive(Debug)]
+pub enum DictKey {
+	/// A plain identifier key, which is a literal string.
+	Symbol(Name),
+	/// A computed key, evaluated when the literal is constructed.
+	Computed(Expr),
+}
+
+
 /// Literals of all types in the language.
 /// Note that there are no literals for the error type.
 #[derive(Debug)]
@@ -43,7 +80,9 @@ pub enum Literal {
 	Byte(u8),
 	String(Box<[u8]>),
 	Array(Box<[Expr]>),
-	Dict(Box<[(Symbol, Expr)]>),
+	Dict(Box<[(DictKey, Expr)]>),
+	/// An array or dict literal whose items are all constant, built during analysis.
+	Template(Template),
 	Function {
 		/// The number of parameters.
 		params: u32,
@@ -53,12 +92,28 @@ pub enum Literal {
 	/// For the dot access operator, we want to be able to have identifiers as literal
 	/// strings instead of names for variables. This variant should only be used in such
 	/// case.
-	Identifier(Symbol),
+	Identifier(Name),
 }
 
 
-/// Unary operators.
+/// A constant value, built once during analysis. Containers are instantiated anew on each
+/// evaluation, so that mutations never leak between evaluations of the same literal.
 #[derive(Debug)]
+pub enum Template {
+	Nil,
+	Bool(bool),
+	Int(i64),
+	Float(f64),
+	Byte(u8),
+	String(Str),
+	Array(Box<[Template]>),
+	/// Keys are kept in source order, so that later duplicates override earlier ones.
+	Dict(Box<[(Template, Template)]>),
+}
+
+
+/// Unary operators.
+#[derive(Debug, Clone, Copy)]
 pub enum UnaryOp {
 	Minus, // -
 	Not,   // not
@@ -87,13 +142,15 @@ impl From<ast::UnaryOp> for UnaryOp {
 /// Binary operators.
 /// Assignment/Access are not represented as operators, but directly as
 /// statements/expressions instead.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum BinaryOp {
-	Plus,  // +
-	Minus, // -
-	Times, // *
-	Div,   // /
-	Mod,   // %
+	Plus,     // +
+	Minus,    // -
+	Times,    // *
+	Div,      // /
+	FloorDiv, // //
+	Mod,      // %
+	Pow,      // **
 
 	Equals,        // ==
 	NotEquals,     // !=
@@ -116,7 +173,9 @@ impl From<ast::BinaryOp> for BinaryOp {
 			ast::BinaryOp::Minus => BinaryOp::Minus,
 			ast::BinaryOp::Times => BinaryOp::Times,
 			ast::BinaryOp::Div => BinaryOp::Div,
+			ast::BinaryOp::FloorDiv => BinaryOp::FloorDiv,
 			ast::BinaryOp::Mod => BinaryOp::Mod,
+			ast::BinaryOp::Pow => BinaryOp::Pow,
 			ast::BinaryOp::Equals => BinaryOp::Equals,
 			ast::BinaryOp::NotEquals => BinaryOp::NotEquals,
 			ast::BinaryOp::Greater => BinaryOp::Greater,
@@ -131,6 +190,29 @@ impl From<ast::BinaryOp> for BinaryOp {
 }
 
 
+impl BinaryOp {
+	/// Integer floor division, rounding towards negative infinity, such that `-7 // 2` is
+	/// -4. None on division by zero or overflow.
+	pub fn floor_div(left: i64, right: i64) -> Option<i64> {
+		let quotient = left.checked_div(right)?;
+
+		if left % right != 0 && (left < 0) != (right < 0) {
+			Some(quotient - 1)
+		} else {
+			Some(quotient)
+		}
+	}
+
+
+	/// Integer exponentiation. None on negative exponents, which result in floats, or
+	/// overflow.
+	pub fn int_pow(base: i64, exp: i64) -> Option<i64> {
+		let exp = u32::try_from(exp).ok()?;
+		base.checked_pow(exp)
+	}
+}
+
+
 /// Expressions of all kinds in the language, except for l-values.
 #[derive(Debug)]
 pub enum Expr {
@@ -174,10 +256,47 @@ pub enum Expr {
 		args: Box<[Expr]>,
 		pos: SourcePos,
 	},
+	/// Spread argument (...), only found in function calls, where the array is expanded
+	/// into consecutive arguments.
+	Spread {
+		expr: Box<Expr>,
+		pos: SourcePos,
+	},
 	CommandBlock {
 		block: CommandBlock,
 		pos: SourcePos,
 	},
+	/// Interpolated string. The parts alternate between string literals and embedded
+	/// expressions, starting and ending with a string literal.
+	Interpolation {
+		parts: Box<[Expr]>,
+		pos: SourcePos,
+	},
+}
+
+
+impl Expr {
+	/// The position of the expression in the source code.
+	pub fn pos(&self) -> &SourcePos {
+		match self {
+			Self::Identifier { pos, .. } => pos,
+			Self::Literal { pos, .. } => pos,
+			Self::UnaryOp { pos, .. } => pos,
+			Self::BinaryOp { pos, .. } => pos,
+			Self::If { pos, .. } => pos,
+			Self::Access { pos, .. } => pos,
+			Self::Call { pos, .. } => pos,
+			Self::Spread { pos, .. } => pos,
+			Self::CommandBlock { pos, .. } => pos,
+			Self::Interpolation { pos, .. } => pos,
+		}
+	}
+
+
+	/// Whether the expression reads the given variable.
+	pub fn is_variable(&self, slot_ix: &mem::SlotIx) -> bool {
+		matches!(self, Self::Identifier { slot_ix: ix, .. } if ix == slot_ix)
+	}
 }
 
 
@@ -196,6 +315,29 @@ pub enum Lvalue {
 		field: Box<Expr>,
 		pos: SourcePos,
 	},
+	/// Array destructuring.
+	Array {
+		items: Box<[Lvalue]>,
+		/// The l-value that collects the remaining items, if any.
+		rest: Option<Box<Lvalue>>,
+		pos: SourcePos,
+	},
+	/// Dict destructuring.
+	Dict {
+		items: Box<[DictLvalueItem]>,
+		pos: SourcePos,
+	},
+}
+
+
+/// A single item in a dict destructuring.
+#[derive(Debug)]
+pub struct DictLvalueItem {
+	pub key: Symbol,
+	pub lvalue: Lvalue,
+	/// The value to be used when the key is missing in the dict.
+	pub default: Option<Expr>,
+	pub pos: SourcePos,
 }
 
 
@@ -206,6 +348,12 @@ pub enum Statement {
 		left: Lvalue,
 		right: Expr,
 	},
+	/// Assignment of multiple values. All values are evaluated before any l-value is
+	/// assigned.
+	MultipleAssign {
+		left: Box<[Lvalue]>,
+		right: Box<[Expr]>,
+	},
 	Return {
 		expr: Expr,
 	},
@@ -225,6 +373,22 @@ pub enum Statement {
 }
 
 
+impl Statement {
+	/// The position of the statement in the source code, if it has any expression.
+	pub fn pos(&self) -> Option<&SourcePos> {
+		match self {
+			Self::Assign { right, .. } => Some(right.pos()),
+			Self::MultipleAssign { right, .. } => right.first().map(Expr::pos),
+			Self::Return { expr } => Some(expr.pos()),
+			Self::Break => None,
+			Self::While { condition, .. } => Some(condition.pos()),
+			Self::For { expr, .. } => Some(expr.pos()),
+			Self::Expr(expr) => Some(expr.pos()),
+		}
+	}
+}
+
+
 /// A statically correct (syntactically and semantically) Hush program.
 #[derive(Debug)]
 pub struct Program {
@@ -234,4 +398,6 @@ pub struct Program {
 	pub statements: Block,
 	/// How many slots in the root scope.
 	pub root_slots: mem::SlotIx,
+	/// The names of the variables in the root scope, for debuggers.
+	pub root_names: Box<[(Symbol, mem::SlotIx)]>,
 }
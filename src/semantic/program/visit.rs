@@ -0,0 +1,371 @@
+//! Traversal of programs, for tools such as linters and doc extractors.
+//!
+//! Implementors of `Visitor` override only the methods for the nodes they are interested
+//! in, calling the corresponding `walk_*` function to keep descending into children.
+
+use super::{
+	ArgPart,
+	ArgUnit,
+	Argument,
+	BasicCommand,
+	Block,
+	Command,
+	CommandBlock,
+	DictKey,
+	DictLvalueItem,
+	Expr,
+	Literal,
+	Lvalue,
+	Program,
+	Redirection,
+	RedirectionTarget,
+	SourcePos,
+	Statement,
+};
+
+
+/// A visitor over the nodes of a program. Every method defaults to walking the children
+/// of the node.
+pub trait Visitor<'a> {
+	fn visit_program(&mut self, program: &'a Program) {
+		walk_program(self, program)
+	}
+
+	fn visit_block(&mut self, block: &'a Block) {
+		walk_block(self, block)
+	}
+
+	fn visit_statement(&mut self, statement: &'a Statement) {
+		walk_statement(self, statement)
+	}
+
+	fn visit_expr(&mut self, expr: &'a Expr) {
+		walk_expr(self, expr)
+	}
+
+	/// Literals have no position of their own, so the position of the enclosing literal
+	/// expression is provided.
+	fn visit_literal(&mut self, literal: &'a Literal, pos: &'a SourcePos) {
+		walk_literal(self, literal, pos)
+	}
+
+	fn visit_dict_key(&mut self, key: &'a DictKey) {
+		walk_dict_key(self, key)
+	}
+
+	fn visit_lvalue(&mut self, lvalue: &'a Lvalue) {
+		walk_lvalue(self, lvalue)
+	}
+
+	fn visit_dict_lvalue_item(&mut self, item: &'a DictLvalueItem) {
+		walk_dict_lvalue_item(self, item)
+	}
+
+	fn visit_command_block(&mut self, block: &'a CommandBlock, pos: &'a SourcePos) {
+		walk_command_block(self, block, pos)
+	}
+
+	fn visit_command(&mut self, command: &'a Command) {
+		walk_command(self, command)
+	}
+
+	fn visit_basic_command(&mut self, command: &'a BasicCommand) {
+		walk_basic_command(self, command)
+	}
+
+	fn visit_argument(&mut self, argument: &'a Argument) {
+		walk_argument(self, argument)
+	}
+
+	fn visit_arg_unit(&mut self, _unit: &'a ArgUnit) { }
+
+	fn visit_redirection(&mut self, redirection: &'a Redirection) {
+		walk_redirection(self, redirection)
+	}
+}
+
+
+pub fn walk_program<'a, V>(visitor: &mut V, program: &'a Program)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	visitor.visit_block(&program.statements)
+}
+
+
+pub fn walk_block<'a, V>(visitor: &mut V, block: &'a Block)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	for statement in block.0.iter() {
+		visitor.visit_statement(statement);
+	}
+}
+
+
+pub fn walk_statement<'a, V>(visitor: &mut V, statement: &'a Statement)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match statement {
+		Statement::Assign { left, right } => {
+			visitor.visit_lvalue(left);
+			visitor.visit_expr(right);
+		}
+
+		Statement::MultipleAssign { left, right } => {
+			for lvalue in left.iter() {
+				visitor.visit_lvalue(lvalue);
+			}
+
+			for expr in right.iter() {
+				visitor.visit_expr(expr);
+			}
+		}
+
+		Statement::Return { expr } => visitor.visit_expr(expr),
+
+		Statement::Break => (),
+
+		Statement::While { condition, block } => {
+			visitor.visit_expr(condition);
+			visitor.visit_block(block);
+		}
+
+		Statement::For { expr, block, .. } => {
+			visitor.visit_expr(expr);
+			visitor.visit_block(block);
+		}
+
+		Statement::Expr(expr) => visitor.visit_expr(expr),
+	}
+}
+
+
+pub fn walk_expr<'a, V>(visitor: &mut V, expr: &'a Expr)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match expr {
+		Expr::Identifier { .. } => (),
+
+		Expr::Literal { literal, pos } => visitor.visit_literal(literal, pos),
+
+		Expr::UnaryOp { operand, .. } => visitor.visit_expr(operand),
+
+		Expr::BinaryOp { left, right, .. } => {
+			visitor.visit_expr(left);
+			visitor.visit_expr(right);
+		}
+
+		Expr::If { condition, then, otherwise, .. } => {
+			visitor.visit_expr(condition);
+			visitor.visit_block(then);
+			visitor.visit_block(otherwise);
+		}
+
+		Expr::Access { object, field, .. } => {
+			visitor.visit_expr(object);
+			visitor.visit_expr(field);
+		}
+
+		Expr::Call { function, args, .. } => {
+			visitor.visit_expr(function);
+
+			for arg in args.iter() {
+				visitor.visit_expr(arg);
+			}
+		}
+
+		Expr::Spread { expr, .. } => visitor.visit_expr(expr),
+
+		Expr::CommandBlock { block, pos } => visitor.visit_command_block(block, pos),
+
+		Expr::Interpolation { parts, .. } => {
+			for part in parts.iter() {
+				visitor.visit_expr(part);
+			}
+		}
+	}
+}
+
+
+pub fn walk_literal<'a, V>(visitor: &mut V, literal: &'a Literal, _pos: &'a SourcePos)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match literal {
+		Literal::Array(items) => {
+			for item in items.iter() {
+				visitor.visit_expr(item);
+			}
+		}
+
+		Literal::Dict(items) => {
+			for (key, value) in items.iter() {
+				visitor.visit_dict_key(key);
+				visitor.visit_expr(value);
+			}
+		}
+
+		Literal::Function { body, .. } => visitor.visit_block(body),
+
+		Literal::Nil
+			| Literal::Bool(_)
+			| Literal::Int(_)
+			| Literal::Float(_)
+			| Literal::Byte(_)
+			| Literal::String(_)
+			| Literal::Template(_)
+			| Literal::Identifier(_) => (),
+	}
+}
+
+
+pub fn walk_dict_key<'a, V>(visitor: &mut V, key: &'a DictKey)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match key {
+		DictKey::Symbol(_) => (),
+		DictKey::Computed(expr) => visitor.visit_expr(expr),
+	}
+}
+
+
+pub fn walk_lvalue<'a, V>(visitor: &mut V, lvalue: &'a Lvalue)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match lvalue {
+		Lvalue::Identifier { .. } => (),
+
+		Lvalue::Access { object, field, .. } => {
+			visitor.visit_expr(object);
+			visitor.visit_expr(field);
+		}
+
+		Lvalue::Array { items, rest, .. } => {
+			for item in items.iter() {
+				visitor.visit_lvalue(item);
+			}
+
+			if let Some(rest) = rest {
+				visitor.visit_lvalue(rest);
+			}
+		}
+
+		Lvalue::Dict { items, .. } => {
+			for item in items.iter() {
+				visitor.visit_dict_lvalue_item(item);
+			}
+		}
+	}
+}
+
+
+pub fn walk_dict_lvalue_item<'a, V>(visitor: &mut V, item: &'a DictLvalueItem)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	visitor.visit_lvalue(&item.lvalue);
+
+	if let Some(default) = &item.default {
+		visitor.visit_expr(default);
+	}
+}
+
+
+pub fn walk_command_block<'a, V>(visitor: &mut V, block: &'a CommandBlock, _pos: &'a SourcePos)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	visitor.visit_command(&block.head);
+
+	for command in block.tail.iter() {
+		visitor.visit_command(command);
+	}
+}
+
+
+pub fn walk_command<'a, V>(visitor: &mut V, command: &'a Command)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match command {
+		Command::Builtin { arguments, .. } => {
+			for argument in arguments.iter() {
+				visitor.visit_argument(argument);
+			}
+		}
+
+		Command::External { head, tail } => {
+			visitor.visit_basic_command(head);
+
+			for command in tail.iter() {
+				visitor.visit_basic_command(command);
+			}
+		}
+	}
+}
+
+
+pub fn walk_basic_command<'a, V>(visitor: &mut V, command: &'a BasicCommand)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	for (key, value) in command.env.iter() {
+		visitor.visit_arg_unit(key);
+		visitor.visit_argument(value);
+	}
+
+	visitor.visit_argument(&command.program);
+
+	for argument in command.arguments.iter() {
+		visitor.visit_argument(argument);
+	}
+
+	for redirection in command.redirections.iter() {
+		visitor.visit_redirection(redirection);
+	}
+}
+
+
+pub fn walk_argument<'a, V>(visitor: &mut V, argument: &'a Argument)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	for part in argument.parts.iter() {
+		match part {
+			ArgPart::Unit(unit) => visitor.visit_arg_unit(unit),
+
+			ArgPart::Collection(units) => {
+				for unit in units.iter() {
+					visitor.visit_arg_unit(unit);
+				}
+			}
+
+			ArgPart::Home
+				| ArgPart::Range(_, _)
+				| ArgPart::Star
+				| ArgPart::Percent
+				| ArgPart::CharClass(_) => (),
+		}
+	}
+}
+
+
+pub fn walk_redirection<'a, V>(visitor: &mut V, redirection: &'a Redirection)
+where
+	V: Visitor<'a> + ?Sized,
+{
+	match redirection {
+		Redirection::Output { target, .. } => match target {
+			RedirectionTarget::Fd(_) => (),
+			RedirectionTarget::Overwrite(argument) => visitor.visit_argument(argument),
+			RedirectionTarget::Append(argument) => visitor.visit_argument(argument),
+		},
+
+		Redirection::Input { source, .. } => visitor.visit_argument(source),
+	}
+}
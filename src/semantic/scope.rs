@@ -1,40 +1,185 @@
 use std::collections::hash_map::{HashMap, Entry};
 
-use crate::symbol::{self, Symbol};
+use crate::{runtime, symbol::{self, Symbol}};
 use super::{
 	mem::{Capture, FrameInfo, SlotIx},
 	Error,
+	Errors,
 	SourcePos,
+	Warning,
+	Warnings,
 };
 
 
+/// How a variable was introduced, which determines how it is reported when unused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Declaration {
+	/// Let statements, for loops and destructuring.
+	Variable,
+	/// Function parameters.
+	Parameter,
+	/// Variables introduced by the analyzer, like captures, which are never reported.
+	Implicit,
+	/// Globals like `std`, which may not be assigned or redeclared.
+	ReadOnly,
+}
+
+
+impl Declaration {
+	/// Whether the variable was introduced by the analyzer rather than the program.
+	fn is_implicit(self) -> bool {
+		matches!(self, Self::Implicit | Self::ReadOnly)
+	}
+}
+
+
+/// How a variable is accessed.
+#[derive(Debug, Clone, Copy)]
+enum Access {
+	/// Used as a value.
+	Read,
+	/// Assigned.
+	Write,
+	/// Called with the given number of arguments, if known.
+	Call { args: Option<u32>, pos: SourcePos },
+}
+
+
+/// A declared variable.
+#[derive(Debug)]
+struct Variable {
+	slot_ix: SlotIx,
+	declaration: Declaration,
+	pos: SourcePos,
+	/// Whether the variable is read anywhere, including nested functions.
+	read: bool,
+	/// The number of parameters and the position of the function literal the variable was
+	/// initialized with, if any.
+	function: Option<(u32, SourcePos)>,
+	/// Whether the variable is assigned or used other than by calling it. Escaped variables
+	/// may hold any function, and therefore their calls are not checked.
+	escaped: bool,
+	/// The number of arguments and the position of the calls through the variable.
+	calls: Vec<(u32, SourcePos)>,
+}
+
+
+impl Variable {
+	fn new(slot_ix: SlotIx, declaration: Declaration, pos: SourcePos) -> Self {
+		Self {
+			slot_ix,
+			declaration,
+			pos,
+			read: false,
+			function: None,
+			escaped: false,
+			calls: Vec::new(),
+		}
+	}
+}
+
+
 /// A lexical scope, which translates identifiers to slot indexes.
 #[derive(Debug, Default)]
 struct Scope {
-	variables: HashMap<Symbol, SlotIx>,
+	variables: HashMap<Symbol, Variable>,
 }
 
 
 impl Scope {
 	/// Try to declare a variable in the current scope with the given index.
-	/// If the variable is already declared, returns false.
-	fn declare(&mut self, symbol: Symbol, ix: SlotIx) -> bool {
+	/// If the variable is already declared, returns the position of the existing declaration.
+	fn declare(&mut self, symbol: Symbol, variable: Variable) -> Result<(), SourcePos> {
 		match self.variables.entry(symbol) {
-			Entry::Occupied(_) => false,
+			Entry::Occupied(entry) => Err(entry.get().pos),
 
 			Entry::Vacant(entry) => {
-				entry.insert(ix);
-				true
+				entry.insert(variable);
+				Ok(())
 			}
 		}
 	}
 
 
-	/// Resolve the index for an already declared variable.
-	fn resolve(&mut self, symbol: Symbol) -> Option<SlotIx> {
-		self.variables
-			.get(&symbol)
-			.copied()
+	/// Resolve the index for an already declared variable, registering the access.
+	fn resolve(&mut self, symbol: Symbol, access: Access) -> Option<SlotIx> {
+		let variable = self.variables.get_mut(&symbol)?;
+
+		match access {
+			Access::Read => {
+				variable.read = true;
+				variable.escaped = true;
+			}
+
+			Access::Write => variable.escaped = true,
+
+			Access::Call { args, pos } => {
+				variable.read = true;
+
+				if let Some(args) = args {
+					variable.calls.push((args, pos));
+				}
+			}
+		}
+
+		Some(variable.slot_ix)
+	}
+
+
+	/// The symbols declared in this scope.
+	fn symbols(&self) -> impl Iterator<Item = Symbol> + '_ {
+		self.variables.keys().copied()
+	}
+
+
+	/// Report the calls with the wrong number of arguments through variables that
+	/// definitely hold a function literal.
+	fn report_arity(&self, errors: &mut Errors) {
+		let mut mismatches: Vec<_> = self.variables
+			.iter()
+			.filter(|(_, variable)| !variable.escaped)
+			.filter_map(
+				|(symbol, variable)| variable.function.map(
+					|(params, definition)| variable.calls
+						.iter()
+						.filter(move |(args, _)| *args != params)
+						.map(
+							move |&(args, pos)| Error::arity_mismatch(*symbol, params, args, definition, pos)
+						)
+				)
+			)
+			.flatten()
+			.collect();
+
+		mismatches.sort_by_key(|error| (error.pos.line, error.pos.column));
+
+		errors.extend(mismatches);
+	}
+
+
+	/// Report the variables that were never read. Variables whose names start with an
+	/// underscore are deliberately unused, and therefore not reported.
+	fn report_unused(self, warnings: &mut Warnings, interner: &symbol::Interner) {
+		let mut unused: Vec<_> = self.variables
+			.into_iter()
+			.filter(|(_, variable)| !variable.read)
+			.filter(
+				|(symbol, _)| interner
+					.resolve(*symbol)
+					.is_some_and(|name| !name.starts_with(b"_"))
+			)
+			.filter_map(
+				|(symbol, variable)| match variable.declaration {
+					Declaration::Variable => Some(Warning::unused_variable(symbol, variable.pos)),
+					Declaration::Parameter => Some(Warning::unused_parameter(symbol, variable.pos)),
+					Declaration::Implicit | Declaration::ReadOnly => None,
+				}
+			)
+			.collect();
+
+		unused.sort_by_key(|warning| (warning.pos.line, warning.pos.column));
+
+		warnings.extend(unused);
 	}
 }
 
@@ -49,6 +194,8 @@ struct Frame {
 	captures: Vec<Capture>,
 	/// The slot index of `self`.
 	self_slot: Option<SlotIx>,
+	/// Names of the slots, for debuggers.
+	names: Vec<(Symbol, SlotIx)>,
 	/// Stack of scopes in the frame.
 	scopes: Vec<Scope>,
 }
@@ -61,6 +208,7 @@ impl Frame {
 			slots: SlotIx(0),
 			captures: Vec::new(),
 			self_slot: None,
+			names: Vec::new(),
 			scopes: Vec::new(),
 		}
 	}
@@ -72,50 +220,92 @@ impl Frame {
 	}
 
 
-	/// Exit the current scope.
+	/// Exit the current scope, reporting its unused variables and mismatched calls.
 	/// Panics if the stack is empty.
-	fn exit_block(&mut self) {
-		self.scopes
+	fn exit_block(&mut self, diagnostics: Diagnostics) {
+		let scope = self.scopes
 			.pop()
 			.expect("attempt to exit empty stack");
+
+		scope.report_arity(diagnostics.errors);
+		scope.report_unused(diagnostics.warnings, diagnostics.interner);
 	}
 
 
 	/// Declares a symbol in the current scope.
 	/// Panics if the stack is empty.
-	fn declare(&mut self, symbol: Symbol, pos: SourcePos) -> Result<SlotIx, Error> {
+	fn declare(
+		&mut self,
+		symbol: Symbol,
+		declaration: Declaration,
+		pos: SourcePos
+	) -> Result<SlotIx, Error> {
 		let scope = self.scopes.last_mut().expect("attempt to declare in empty stack");
+		let variable = Variable::new(self.slots, declaration, pos);
+
+		match scope.declare(symbol, variable) {
+			Ok(()) => {
+				if declaration != Declaration::ReadOnly {
+					self.names.push((symbol, self.slots));
+				}
+
+				Ok(self.slots.bump())
+			}
 
-		if scope.declare(symbol, self.slots) {
-			Ok(self.slots.bump())
-		} else {
-			Err(Error::duplicate_variable(symbol, pos))
+			Err(first) => Err(Error::duplicate_variable(symbol, first, pos)),
 		}
 	}
 
 
-	/// Resolve a symbol in the current frame.
-	fn resolve(&mut self, symbol: Symbol) -> Option<SlotIx> {
+	/// Find the declaration of a symbol in the enclosing scopes, excluding the current one.
+	/// Implicit variables are ignored.
+	fn shadowed(&self, symbol: Symbol) -> Option<SourcePos> {
+		let (_, enclosing) = self.scopes.split_last()?;
+
+		enclosing
+			.iter()
+			.rev()
+			.find_map(|scope| scope.variables.get(&symbol))
+			.filter(|variable| !variable.declaration.is_implicit())
+			.map(|variable| variable.pos)
+	}
+
+
+	/// Resolve a symbol in the current frame, registering the access.
+	fn resolve(&mut self, symbol: Symbol, access: Access) -> Option<SlotIx> {
 		self.scopes
 			.iter_mut()
 			.rev()
 			.find_map(
-				|scope| scope.resolve(symbol)
+				|scope| scope.resolve(symbol, access)
 			)
 	}
 
 
+	/// The symbols visible in the current frame, including captured ones.
+	fn symbols(&self) -> impl Iterator<Item = Symbol> + '_ {
+		self.scopes
+			.iter()
+			.flat_map(Scope::symbols)
+	}
+
+
 	/// Capture a variable from a parent scope.
 	/// If the variable was already captured, just returns the slot index.
+	/// Captures are only installed when a variable is resolved, so a function captures
+	/// exactly the free variables referenced by its body and by its nested functions.
 	fn capture(&mut self, symbol: Symbol, parent_slot_ix: SlotIx) -> SlotIx {
 		let scope = self.scopes.first_mut().expect("frame missing root scope");
 
 		match scope.variables.entry(symbol) {
-			Entry::Occupied(entry) => *entry.get(),
+			Entry::Occupied(entry) => entry.get().slot_ix,
 
 			Entry::Vacant(entry) => {
 				let slot_ix = self.slots.bump();
-				entry.insert(slot_ix);
+				let mut variable = Variable::new(slot_ix, Declaration::Implicit, SourcePos::default());
+				variable.read = true;
+				entry.insert(variable);
+				self.names.push((symbol, slot_ix));
 				self.captures.push(
 					Capture {
 						from: parent_slot_ix,
@@ -157,11 +347,21 @@ impl From<Frame> for FrameInfo {
 			slots: frame.slots,
 			captures: std::mem::take(&mut frame.captures).into(),
 			self_slot: frame.self_slot,
+			names: std::mem::take(&mut frame.names).into(),
 		}
 	}
 }
 
 
+/// The sinks for the diagnostics produced when exiting scopes.
+#[derive(Debug)]
+pub struct Diagnostics<'a> {
+	pub errors: &'a mut Errors,
+	pub warnings: &'a mut Warnings,
+	pub interner: &'a symbol::Interner,
+}
+
+
 /// A function scope stack.
 #[derive(Debug, Default)]
 pub struct Stack {
@@ -182,12 +382,12 @@ impl Stack {
 
 	/// Exit the current frame scope, returning the produced frame info.
 	/// Panics if the stack is empty.
-	pub fn exit_frame(&mut self) -> FrameInfo {
+	pub fn exit_frame(&mut self, diagnostics: Diagnostics) -> FrameInfo {
 		let mut frame = self.frames
 			.pop()
 			.expect("attempt to exit empty stack");
 
-		frame.exit_block();
+		frame.exit_block(diagnostics);
 
 		debug_assert!(frame.scopes.is_empty());
 
@@ -201,20 +401,104 @@ impl Stack {
 	}
 
 
-	/// Exit a new block in the current frame.
-	pub fn exit_block(&mut self) {
-		self.top().exit_block()
+	/// Exit the current block in the current frame without reporting diagnostics, freeing
+	/// the slots allocated after the given amount. This rolls back a rejected block.
+	pub fn discard_block(&mut self, slots: SlotIx) {
+		let frame = self.top();
+
+		frame.scopes
+			.pop()
+			.expect("attempt to exit empty stack");
+
+		frame.slots = slots;
+		frame.names.retain(|(_, slot_ix)| slot_ix.0 < slots.0);
+	}
+
+
+	/// The amount of slots and the names of the current frame, which is kept open.
+	pub fn frame_info(&mut self) -> (SlotIx, Box<[(Symbol, SlotIx)]>) {
+		let frame = self.top();
+		(frame.slots, frame.names.clone().into())
+	}
+
+
+	/// The symbols declared in the current frame that are visible from the current block.
+	pub fn visible(&self) -> impl Iterator<Item = Symbol> + '_ {
+		self.frames
+			.last()
+			.into_iter()
+			.flat_map(|frame| frame.scopes.iter())
+			.flat_map(|scope| scope.variables.keys().copied())
+	}
+
+
+	/// Exit all frames without reporting diagnostics.
+	pub fn discard(&mut self) {
+		for mut frame in self.frames.drain(..) {
+			frame.scopes.clear();
+		}
+	}
+
+
+	/// Exit a new block in the current frame, reporting its unused variables and mismatched
+	/// calls.
+	pub fn exit_block(&mut self, diagnostics: Diagnostics) {
+		self.top().exit_block(diagnostics)
 	}
 
 
 	/// Declares a symbol in the current scope.
 	/// Panics if the stack is empty.
-	pub fn declare(&mut self, symbol: Symbol, pos: SourcePos) -> Result<SlotIx, Error> {
-		self.top().declare(symbol, pos)
+	pub fn declare(
+		&mut self,
+		symbol: Symbol,
+		declaration: Declaration,
+		pos: SourcePos
+	) -> Result<SlotIx, Error> {
+		if self.is_read_only(symbol) {
+			return Err(Error::read_only_variable(symbol, pos));
+		}
+
+		self.top().declare(symbol, declaration, pos)
+	}
+
+
+	/// Whether the symbol refers to a read-only variable.
+	pub fn is_read_only(&self, symbol: Symbol) -> bool {
+		self.frames
+			.iter()
+			.rev()
+			.flat_map(|frame| frame.scopes.iter().rev())
+			.find_map(|scope| scope.variables.get(&symbol))
+			.is_some_and(|variable| variable.declaration == Declaration::ReadOnly)
+	}
+
+
+	/// Find the declaration that a new declaration of the symbol in the current scope would
+	/// shadow, if any. The boolean indicates whether the shadowed declaration belongs to an
+	/// enclosing function.
+	pub fn shadowed(&self, symbol: Symbol) -> Option<(SourcePos, bool)> {
+		let (current, enclosing) = self.frames.split_last()?;
+
+		if let Some(pos) = current.shadowed(symbol) {
+			return Some((pos, false));
+		}
+
+		enclosing
+			.iter()
+			.rev()
+			.find_map(
+				|frame| frame.scopes
+					.iter()
+					.rev()
+					.find_map(|scope| scope.variables.get(&symbol))
+			)
+			.filter(|variable| !variable.declaration.is_implicit())
+			.map(|variable| (variable.pos, true))
 	}
 
 
-	/// Resolve a symbol in the current scope.
+	/// Resolve a symbol in the current scope, for reading.
 	/// If the symbol is being closed from a parent frame, the capturing of such symbol is
 	/// installed.
 	pub fn resolve(
@@ -222,6 +506,62 @@ impl Stack {
 		symbol: Symbol,
 		pos: SourcePos,
 		interner: &mut symbol::Interner,
+	) -> Result<SlotIx, Error> {
+		self.resolve_access(symbol, Access::Read, pos, interner)
+	}
+
+
+	/// Resolve a symbol in the current scope, for assignment. Unlike `resolve`, this doesn't
+	/// count as a use of the variable.
+	pub fn resolve_assignment(
+		&mut self,
+		symbol: Symbol,
+		pos: SourcePos,
+		interner: &mut symbol::Interner,
+	) -> Result<SlotIx, Error> {
+		if self.is_read_only(symbol) {
+			return Err(Error::read_only_variable(symbol, pos));
+		}
+
+		self.resolve_access(symbol, Access::Write, pos, interner)
+	}
+
+
+	/// Resolve a symbol in the current scope, for calling it with the given number of
+	/// arguments. Calls are checked against the function the variable was defined with, if
+	/// any, unless the number of arguments is unknown due to spreading.
+	pub fn resolve_call(
+		&mut self,
+		symbol: Symbol,
+		args: Option<u32>,
+		pos: SourcePos,
+		interner: &mut symbol::Interner,
+	) -> Result<SlotIx, Error> {
+		self.resolve_access(symbol, Access::Call { args, pos }, pos, interner)
+	}
+
+
+	/// Register that a variable in the current scope holds a function literal with the given
+	/// number of parameters.
+	pub fn define_function(&mut self, symbol: Symbol, params: u32, pos: SourcePos) {
+		let variable = self.top()
+			.scopes
+			.last_mut()
+			.and_then(|scope| scope.variables.get_mut(&symbol));
+
+		if let Some(variable) = variable {
+			variable.function = Some((params, pos));
+		}
+	}
+
+
+	/// Resolve a symbol in the current scope, registering the access.
+	fn resolve_access(
+		&mut self,
+		symbol: Symbol,
+		access: Access,
+		pos: SourcePos,
+		interner: &mut symbol::Interner,
 	) -> Result<SlotIx, Error> {
 		// Resolve the frame and slot where the symbol originates from.
 		let (frame_ix, mut slot_ix) = self.frames
@@ -230,12 +570,12 @@ impl Stack {
 			.rev()
 			.find_map(
 				|(frame_ix, frame)| {
-					let slot_ix = frame.resolve(symbol)?;
+					let slot_ix = frame.resolve(symbol, access)?;
 					Some((frame_ix, slot_ix))
 				}
 			)
 			.ok_or_else(
-				|| Error::undeclared_variable(symbol, pos)
+				|| Error::undeclared_variable(symbol, self.suggest(symbol, interner), pos)
 			)?;
 
 		if frame_ix == self.frames.len() - 1 { // Symbol found in the local frame.
@@ -274,6 +614,30 @@ impl Stack {
 	}
 
 
+	/// Find the closest name to an undeclared symbol, among the variables visible in the
+	/// current scope and the members of the stdlib.
+	fn suggest(&self, symbol: Symbol, interner: &mut symbol::Interner) -> Option<Symbol> {
+		let name = String::from_utf8_lossy(interner.resolve(symbol)?).into_owned();
+
+		let variables = self.frames
+			.iter()
+			.flat_map(Frame::symbols)
+			.filter_map(|symbol| interner.resolve(symbol))
+			.filter(|variable| !variable.contains(&b'@')) // Skip closed variables.
+			.map(|variable| {
+				let variable = String::from_utf8_lossy(variable).into_owned();
+				(variable.clone(), variable)
+			});
+
+		let members = runtime::std_members()
+			.map(|member| (member.to_owned(), format!("std.{}", member)));
+
+		let suggestion = closest(&name, variables.chain(members))?;
+
+		Some(interner.get_or_intern(suggestion))
+	}
+
+
 	/// Get the top frame in the stack.
 	fn top(&mut self) -> &mut Frame {
 		self.frames.last_mut().expect("empty stack")
@@ -286,3 +650,63 @@ impl Drop for Stack {
 		debug_assert!(self.frames.is_empty())
 	}
 }
+
+
+/// Find the suggestion whose candidate is closest to a misspelled name, if any is close
+/// enough. Candidates are pairs of the name to compare and the suggestion to make.
+pub fn closest<T, I>(name: &str, candidates: I) -> Option<T>
+where
+	T: Ord,
+	I: IntoIterator<Item = (String, T)>,
+{
+	let length = name.chars().count();
+	let max_distance = (length / 3).max(1);
+
+	let (_, suggestion) = candidates
+		.into_iter()
+		.filter_map(|(candidate, suggestion)| {
+			let distance = edit_distance(name, &candidate);
+			(distance <= max_distance && distance < length).then_some((distance, suggestion))
+		})
+		.min()?; // Ties are broken by suggestion, to keep suggestions deterministic.
+
+	Some(suggestion)
+}
+
+
+/// The edit distance between two strings, counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters.
+fn edit_distance(left: &str, right: &str) -> usize {
+	let left: Vec<char> = left.chars().collect();
+	let right: Vec<char> = right.chars().collect();
+
+	// distances[i][j] is the distance between the first i characters of left and the first j
+	// characters of right.
+	let mut distances = vec![vec![0; right.len() + 1]; left.len() + 1];
+
+	for (i, row) in distances.iter_mut().enumerate() {
+		row[0] = i;
+	}
+
+	for (j, distance) in distances[0].iter_mut().enumerate() {
+		*distance = j;
+	}
+
+	for i in 1 ..= left.len() {
+		for j in 1 ..= right.len() {
+			let cost = usize::from(left[i - 1] != right[j - 1]);
+
+			let mut distance = (distances[i - 1][j] + 1)
+				.min(distances[i][j - 1] + 1)
+				.min(distances[i - 1][j - 1] + cost);
+
+			if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+				distance = distance.min(distances[i - 2][j - 2] + 1);
+			}
+
+			distances[i][j] = distance;
+		}
+	}
+
+	distances[left.len()][right.len()]
+}
@@ -5,13 +5,13 @@ use std::{
 };
 
 use crate::{fmt, semantic::ErrorsDisplayContext, symbol, syntax::{self, AnalysisDisplayContext}, tests};
-use super::{program, Analyzer, Program, Errors};
+use super::{program, Analyzer, ErrorKind, Program, Errors, WarningKind, Warnings};
 
 
 fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
 where
 	P: AsRef<Path>,
-	F: FnMut(&Result<Program, Errors>) -> bool,
+	F: FnMut(&Result<(Program, Warnings), Errors>) -> bool,
 {
 	let mut interner = symbol::Interner::new();
 
@@ -39,7 +39,7 @@ where
 
 			if !check(&result) {
 				match result {
-					Ok(program) => panic!(
+					Ok((program, _)) => panic!(
 						"{}",
 						fmt::Show(
 							program,
@@ -91,3 +91,871 @@ fn test_negative() -> io::Result<()> {
 		Result::is_err,
 	)
 }
+
+
+/// The first line of a diagnostic, without the snippet.
+fn first_line(message: String) -> String {
+	message
+		.lines()
+		.next()
+		.unwrap_or_default()
+		.to_owned()
+}
+
+
+#[test]
+fn test_interpolation_error_position() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let x = 1\nlet s = \"value: ${x + missing}\"\nlet t = \"$other\"\n".as_ref().into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let errors = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect_err("undeclared variables should be reported");
+
+	let positions: Vec<_> = errors
+		.into_iter()
+		.map(|error| (error.pos.line, error.pos.column))
+		.collect();
+
+	assert_eq!(positions, [ (2, 23), (3, 11) ]);
+}
+
+
+#[test]
+fn test_self_outside_method() {
+	let analyze = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+	};
+
+	// Top level.
+	let errors = analyze(b"let x = self\n").expect_err("self at top level should be rejected");
+	let errors: Vec<_> = errors.into_iter().map(|error| (error.kind, error.pos.line, error.pos.column)).collect();
+	assert!(matches!(errors[..], [ (ErrorKind::SelfOutsideFunction, 1, 9) ]));
+
+	// Plain functions bind self to nil when called without a receiver.
+	assert!(analyze(b"function plain()\n\tself\nend\n").is_ok());
+
+	// Methods.
+	assert!(analyze(b"let obj = @[ get: function () self end ]\n").is_ok());
+}
+
+
+#[test]
+fn test_assignment_requires_declaration() {
+	let errors = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		match Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+			Ok(_) => Vec::new(),
+			Err(errors) => errors
+				.into_iter()
+				.map(|error| first_line(fmt::Show(&error, &interner).to_string()))
+				.collect(),
+		}
+	};
+
+	// Declared, then assigned.
+	assert!(errors(b"let count = 0\ncount = count + 1\nstd.print(count)\n").is_empty());
+
+	// Assignments never declare variables, so typos are always caught.
+	assert_eq!(
+		errors(b"let count = 0\ncountre = count + 1\nstd.print(count)\n"),
+		[ "Error: <test>:2:1 - undeclared variable 'countre'; did you mean 'count'?" ],
+	);
+
+	// Declarations in inner scopes don't leak.
+	assert_eq!(
+		errors(b"if true then\n\tlet x = 1\n\tstd.print(x)\nend\nx = 2\n"),
+		[ "Error: <test>:5:1 - undeclared variable 'x'" ],
+	);
+}
+
+
+#[test]
+fn test_undeclared_suggestions() {
+	let suggestion = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		let errors = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+			.expect_err("undeclared variables should be reported");
+
+		let error = errors.into_iter().next().expect("missing error");
+		fmt::Show(&error.kind, &interner).to_string()
+	};
+
+	// Local variable, including captures from enclosing functions.
+	assert_eq!(
+		suggestion(b"let counter = 0\nfunction f()\n\tcountr + 1\nend\n"),
+		"undeclared variable 'countr'; did you mean 'counter'?",
+	);
+
+	// Stdlib member.
+	assert_eq!(
+		suggestion(b"pritn(1)\n"),
+		"undeclared variable 'pritn'; did you mean 'std.print'?",
+	);
+
+	// Nothing close.
+	assert_eq!(
+		suggestion(b"let value = 1\nzyxwvut\n"),
+		"undeclared variable 'zyxwvut'",
+	);
+}
+
+
+#[test]
+fn test_unused_warnings() {
+	let warnings = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		let (_, warnings) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+			.expect("program should be valid");
+
+		warnings
+			.into_iter()
+			.map(|warning| fmt::Show(&warning, &interner).to_string())
+			.collect::<Vec<_>>()
+	};
+
+	// Unused local, even if assigned.
+	assert_eq!(
+		warnings(b"let x = 1\nx = 2\nlet y = 3\nstd.print(y)\n"),
+		[ "Warning: <test>:1:5 - unused variable 'x'" ],
+	);
+
+	// Unused parameter and loop variable.
+	assert_eq!(
+		warnings(b"let f = function (a, b)\n\tfor i in a do end\nend\nf(1, 2)\n"),
+		[
+			"Warning: <test>:1:22 - unused parameter 'b'",
+			"Warning: <test>:2:6 - unused variable 'i'",
+		],
+	);
+
+	// Names starting with an underscore are deliberately unused.
+	assert!(warnings(b"let _x = 1\nlet f = function (_a) end\nf(_x)\n").is_empty());
+
+	// Variables read only inside a nested closure are used, but assigning is not a read.
+	assert_eq!(
+		warnings(b"let a = 1\nlet b = 2\nlet f = function ()\n\tb = a\nend\nf()\n"),
+		[ "Warning: <test>:2:5 - unused variable 'b'" ],
+	);
+}
+
+
+#[test]
+fn test_unreachable_warnings() {
+	let warnings = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		let (_, warnings) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+			.expect("program should be valid");
+
+		warnings
+			.into_iter()
+			.map(|warning| fmt::Show(&warning, &interner).to_string())
+			.collect::<Vec<_>>()
+	};
+
+	// Code after return is a syntax error, as the return expression is optional.
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"function f()\n\treturn 1\n\tstd.print(2)\nend\n".as_ref().into(),
+	};
+	assert!(!syntax::Analysis::analyze(&source, &mut interner).errors.is_empty());
+
+	// Code after break, only the first unreachable statement is reported.
+	assert_eq!(
+		warnings(b"while true do\n\tbreak\n\tlet x = 1 + 2\n\tstd.print(x)\nend\n"),
+		[ "Warning: <test>:3:6 - unreachable code" ],
+	);
+
+	// Returns inside conditionals don't make the rest of the block unreachable.
+	assert!(
+		warnings(b"let f = function (x)\n\tif x then\n\t\treturn 1\n\tend\n\treturn 2\nend\nf(true)\n")
+			.is_empty()
+	);
+
+	// Loops that never run.
+	assert_eq!(
+		warnings(b"while false do\n\tstd.print(1)\nend\n"),
+		[ "Warning: <test>:1:1 - loop never runs, the condition is always false" ],
+	);
+}
+
+
+#[test]
+fn test_shadowing() {
+	let diagnostics = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		match Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+			Ok((_, warnings)) => warnings
+				.into_iter()
+				.filter(
+					|warning| !matches!(
+						warning.kind,
+						WarningKind::UnusedVariable(_) | WarningKind::UnusedParameter(_)
+					)
+				)
+				.map(|warning| fmt::Show(&warning, &interner).to_string())
+				.collect::<Vec<_>>(),
+
+			Err(errors) => errors
+				.into_iter()
+				.map(|error| first_line(fmt::Show(&error, &interner).to_string()))
+				.collect(),
+		}
+	};
+
+	// Same scope redeclaration.
+	assert_eq!(
+		diagnostics(b"let x = 1\nlet x = 2\n"),
+		[ "Error: <test>:2:5 - duplicate variable 'x', first declared at <test>:1:5" ],
+	);
+
+	// Inner block shadowing.
+	assert_eq!(
+		diagnostics(b"let x = 1\nif true then\n\tlet x = 2\nend\n"),
+		[ "Warning: <test>:3:6 - variable 'x' shadows the declaration at <test>:1:5" ],
+	);
+
+	// Parameters shadowed by locals, in nested blocks and in the function body itself.
+	assert_eq!(
+		diagnostics(b"let f = function (n)\n\tfor n in std.iter([]) do end\nend\n"),
+		[ "Warning: <test>:2:6 - variable 'n' shadows the declaration at <test>:1:19" ],
+	);
+	assert_eq!(
+		diagnostics(b"let f = function (n)\n\tlet n = 1\nend\n"),
+		[ "Error: <test>:2:6 - duplicate variable 'n', first declared at <test>:1:19" ],
+	);
+
+	// Across functions.
+	assert_eq!(
+		diagnostics(b"let x = 1\nlet f = function ()\n\tlet x = 2\nend\n"),
+		[
+			"Warning: <test>:3:6 - variable 'x' shadows the declaration of an enclosing function \
+			at <test>:1:5",
+		],
+	);
+
+	// Names starting with an underscore may be shadowed.
+	assert!(diagnostics(b"let _x = 1\nif true then\n\tlet _x = 2\nend\n").is_empty());
+}
+
+
+#[test]
+fn test_arity_mismatch() {
+	let errors = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		match Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+			Ok(_) => Vec::new(),
+			Err(errors) => errors
+				.into_iter()
+				.map(|error| first_line(fmt::Show(&error, &interner).to_string()))
+				.collect(),
+		}
+	};
+
+	// Calls through variables holding function literals, including from closures.
+	assert_eq!(
+		errors(b"function add(a, b)\n\ta + b\nend\nadd(1)\nlet f = function ()\n\tadd(1, 2, 3)\nend\nf()\n"),
+		[
+			"Error: <test>:4:1 - function 'add' takes 2 arguments but 1 was given, defined at <test>:1:1",
+			"Error: <test>:6:2 - function 'add' takes 2 arguments but 3 were given, defined at <test>:1:1",
+		],
+	);
+
+	// Reassignment, even after the call, may change the function.
+	assert!(errors(b"let f = function () nil end\nf(1)\nf = function (x) x end\n").is_empty());
+
+	// So may passing the function elsewhere.
+	assert!(errors(b"let f = function () nil end\nstd.print(f)\nf(1)\n").is_empty());
+
+	// Calls with spread arguments can't be checked, but other calls still are.
+	assert_eq!(
+		errors(b"let f = function (a) a end\nf(...[ 1, 2 ])\nf(1, 2)\n"),
+		[ "Error: <test>:3:1 - function 'f' takes 1 argument but 2 were given, defined at <test>:1:9" ],
+	);
+
+	// Only function literals are checked, as builtins take variable arguments.
+	assert!(errors(b"let print = std.print\nprint(1, 2, 3)\nprint()\n").is_empty());
+}
+
+
+#[test]
+fn test_all_errors_reported() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"std.print(undeclared)\nbreak\nlet d = @[ a: 1, a: 2 ]\n".as_ref().into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let errors = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect_err("program should be invalid");
+
+	let errors: Vec<_> = errors
+		.into_iter()
+		.map(|error| fmt::Show(&error, &interner).to_string())
+		.collect();
+
+	assert_eq!(
+		errors,
+		[
+			"Error: <test>:1:11 - undeclared variable 'undeclared'\n\
+			 1 | std.print(undeclared)\n  \
+			   |           ^",
+			"Error: <test>:2:1 - break statement outside loop\n\
+			 2 | break\n  \
+			   | ^",
+			"Error: <test>:3:18 - duplicate key 'a', first defined at <test>:3:12\n\
+			 3 | let d = @[ a: 1, a: 2 ]\n  \
+			   |                  ^",
+		],
+	);
+}
+
+
+#[test]
+fn test_duplicate_dict_keys() {
+	let duplicates = |contents: &'static [u8]| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		match Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+			Ok(_) => Vec::new(),
+			Err(errors) => errors
+				.into_iter()
+				.map(
+					|error| match error.kind {
+						ErrorKind::DuplicateKey(_, first) => (
+							(first.line, first.column),
+							(error.pos.line, error.pos.column),
+						),
+						kind => panic!("unexpected error: {:?}", kind),
+					}
+				)
+				.collect(),
+		}
+	};
+
+	// Duplicate.
+	assert_eq!(
+		duplicates(b"@[ port: 80, host: \"a\", port: 8080 ]\n"),
+		[ ((1, 4), (1, 25)) ],
+	);
+
+	// Triplicate: both repetitions point at the first occurrence.
+	assert_eq!(
+		duplicates(b"@[ a: 1, a: 2, a: 3 ]\n"),
+		[ ((1, 4), (1, 10)), ((1, 4), (1, 16)) ],
+	);
+
+	// Keys are case sensitive.
+	assert!(duplicates(b"@[ key: 1, Key: 2, KEY: 3 ]\n").is_empty());
+
+	// Separate and nested literals have independent keys.
+	assert!(duplicates(b"@[ a: 1 ]\n@[ a: 2 ]\n").is_empty());
+	assert!(duplicates(b"@[ a: @[ b: 1 ], b: 2 ]\n").is_empty());
+}
+
+
+#[test]
+fn test_frame_too_large() {
+	let errors = |contents: String| {
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = syntax::Source { path, contents: contents.into_bytes().into() };
+
+		let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+		assert!(syntactic_analysis.errors.is_empty());
+
+		match Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+			Ok(_) => Vec::new(),
+			Err(errors) => errors
+				.into_iter()
+				.map(
+					|error| match error.kind {
+						ErrorKind::FrameTooLarge { slots, .. } => (slots, error.pos.line, error.pos.column),
+						kind => panic!("unexpected error: {:?}", kind),
+					}
+				)
+				.collect(),
+		}
+	};
+
+	let variables = |count: u32| -> String {
+		(0 .. count)
+			.map(|ix| format!("\tlet _v{} = {}\n", ix, ix))
+			.collect()
+	};
+
+	let max = program::mem::FrameInfo::MAX_SLOTS;
+
+	// Functions may use all slots.
+	let function = |count| format!("let f = function ()\n{}end\nf()\n", variables(count));
+	assert!(errors(function(max)).is_empty());
+	assert_eq!(errors(function(max + 1)), [ (max + 1, 1, 9) ]);
+
+	// The program's frame includes the slot of std.
+	assert_eq!(errors(variables(max)), [ (max + 1, 1, 1) ]);
+}
+
+
+#[test]
+fn test_constant_folding() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let day = 60 * 60 * 24
+let s = \"a\" ++ \"b\"
+let c = not (1 < 2) or -3 == 0 - 3
+let x = day * 2
+let o = 9223372036854775807 + 1
+let z = 1 % 0
+let f = 1.5 + 2.5
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	// Overflows, divisions by zero and floats are left for the runtime.
+	assert_eq!(
+		fmt::Show(&program, program::fmt::Context::from(&interner)).to_string(),
+		"Program for <test>
+let #0: auto
+let #1: auto
+let #2: auto
+let #3: auto
+let #4: auto
+let #5: auto
+let #6: auto
+let #7: auto
+#1 = 86400
+#2 = \"ab\"
+#3 = true
+#4 = (#1 * 2)
+#5 = (9223372036854775807 + 1)
+#6 = (1 % 0)
+#7 = (1.5 + 2.5)"
+	);
+}
+
+
+#[test]
+fn test_constant_literals() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let a = [ 1, \"x\", [ 2 * 3 ], @[ k: nil, [1]: 'c' ] ]
+let b = [ a, 1 ]
+let c = @[ k: [], [b]: 1 ]
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	// Only literals whose items are all constant become templates.
+	let literals: Vec<&program::Literal> = program.statements.0
+		.iter()
+		.filter_map(
+			|statement| match statement {
+				program::Statement::Assign { right: program::Expr::Literal { literal, .. }, .. } => Some(literal),
+				_ => None,
+			}
+		)
+		.collect();
+
+	assert!(matches!(
+		literals.as_slice(),
+		[
+			program::Literal::Template(program::Template::Array(_)),
+			program::Literal::Array(_),
+			program::Literal::Dict(_),
+		]
+	));
+
+	assert_eq!(
+		fmt::Show(&program, program::fmt::Context::from(&interner)).to_string(),
+		"Program for <test>
+let #0: auto
+let #1: auto
+let #2: auto
+let #3: auto
+#1 = [
+	1,
+	\"x\",
+	[
+		6
+	],
+	@[
+		[\"k\"]: nil,
+		[1]: 'c'
+	]
+]
+#2 = [
+	#1,
+	1
+]
+#3 = @[
+	k: [],
+	[#2]: 1
+]"
+	);
+}
+
+
+#[test]
+fn test_minimal_captures() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let a = 1
+let b = 2
+let c = 3
+let d = 4
+let e = 5
+for i in std.range(0, 10, 1) do
+	let f = function ()
+		let g = function () c end
+		g()
+	end
+	f()
+end
+std.print(a, b, d, e)
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	// Only `c` is captured, by both functions, even though all variables are in scope.
+	assert_eq!(
+		fmt::Show(&program, program::fmt::Context::from(&interner)).to_string(),
+		"Program for <test>
+let #0: auto
+let #1: auto
+let #2: auto
+let #3: auto
+let #4: auto
+let #5: auto
+let #6: auto
+let #7: auto
+#1 = 1
+#2 = 2
+#3 = 3
+#4 = 4
+#5 = 5
+for #6 in #0.range(0, 10, 1) do
+	#7 = function(0)
+		let #0: auto
+		let #1: capture #3
+		#0 = function(0)
+			let #0: capture #1
+			#0
+		end
+		#0()
+	end
+	#7()
+end
+#0.print(#1, #2, #4, #5)"
+	);
+}
+
+
+#[test]
+fn test_program_dump() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let x = 1
+function counter(step)
+	let count = 0
+	return function()
+		count = count + step * x
+		return count
+	end
+end
+let out = ${ echo $x | tr 1 2 }
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	assert_eq!(
+		fmt::Show(&program, program::fmt::Context::from(&interner)).to_string(),
+		"Program for <test>
+let #0: auto
+let #1: auto
+let #2: auto
+let #3: auto
+#1 = 1
+#2 = function(1)
+	let #0: auto
+	let #1: auto
+	let #2: capture #1
+	#1 = 0
+	return function(0)
+		let #0: capture #1
+		let #1: capture #0
+		let #2: capture #2
+		#0 = (#0 + (#1 * #2))
+		return #0
+	end
+end
+#3 = ${
+	\"echo\" \"${#1}\" | \"tr\" \"1\" \"2\"
+}"
+	);
+}
+
+
+#[test]
+fn test_visitor() {
+	use program::{visit::{self, Visitor}, Literal, SourcePos};
+
+	#[derive(Default)]
+	struct Collector<'a> {
+		functions: usize,
+		strings: Vec<(&'a [u8], u32)>,
+	}
+
+	impl<'a> Visitor<'a> for Collector<'a> {
+		fn visit_literal(&mut self, literal: &'a Literal, pos: &'a SourcePos) {
+			match literal {
+				Literal::Function { .. } => self.functions += 1,
+				Literal::String(string) => self.strings.push((string, pos.line)),
+				_ => (),
+			}
+
+			visit::walk_literal(self, literal, pos)
+		}
+	}
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let greet = function (name)
+	return \"hello \" ++ name
+end
+let obj = @[
+	method: function () self end,
+	list: [ \"a\", function () end ],
+]
+while obj.list == nil do
+	let [ x, y ] = [ \"b\", \"c\" ]
+	std.print(x, y)
+end
+{ echo \"arg\" }
+if greet(\"world\") == nil then
+	obj.method()
+end
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	let mut collector = Collector::default();
+	collector.visit_program(&program);
+
+	assert_eq!(collector.functions, 3);
+	assert_eq!(
+		collector.strings,
+		[
+			(b"hello ".as_ref(), 2),
+			(b"a", 6),
+			// [ "b", "c" ] is constant, and therefore a template rather than nested literals.
+			(b"world", 13),
+		],
+	);
+}
+
+
+#[test]
+fn test_binary_round_trip() {
+	use program::binary::{self, DecodeError};
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = syntax::Source {
+		path,
+		contents: b"let obj = @[
+	value: 1.5,
+	method: function (x)
+		let [ a, b ] = self.list
+		return a ++ x
+	end,
+	list: [ \"a\", 'b', nil, true ],
+]
+for item in std.iter(obj.list) do
+	if item == nil then break end
+end
+let result = nil
+result = { echo \"$result\" *.rs 2>&1 | cat >> out; cd ~ }
+std.print(obj.method(\"x\"), result)
+"
+			.as_ref()
+			.into(),
+	};
+
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	assert!(syntactic_analysis.errors.is_empty());
+
+	let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+		.expect("program should be valid");
+
+	let source_hash = binary::hash(&source.contents);
+	let compiled = binary::encode(&program, &interner, source_hash);
+	assert!(binary::is_compiled(&compiled));
+	assert_eq!(binary::header(&compiled).map(|header| header.source_hash), Ok(source_hash));
+
+	// Decode into a fresh interner, so that symbols are renumbered.
+	let mut decoded_interner = symbol::Interner::new();
+	decoded_interner.get_or_intern("unrelated");
+	let (_, decoded) = binary::decode(&compiled, &mut decoded_interner)
+		.expect("program should decode");
+
+	assert_eq!(
+		fmt::Show(&decoded, program::fmt::Context::from(&decoded_interner)).to_string(),
+		fmt::Show(&program, program::fmt::Context::from(&interner)).to_string(),
+	);
+
+	assert_eq!(
+		binary::decode(&compiled[.. compiled.len() - 1], &mut decoded_interner).err(),
+		Some(DecodeError::Corrupt),
+	);
+
+	assert_eq!(
+		binary::decode(&source.contents, &mut decoded_interner).err(),
+		Some(DecodeError::NotCompiled),
+	);
+
+	let mut outdated = compiled.clone();
+	outdated[7 .. 11].copy_from_slice(&0u32.to_le_bytes());
+	assert_eq!(binary::header(&outdated), Err(DecodeError::FormatVersion(0)));
+}
+
+
+#[test]
+fn test_binary_too_deep() {
+	use program::binary::{self, DecodeError};
+
+	tests::util::on_large_stack(
+		|| {
+			let compile = |terms: usize| {
+				let mut interner = symbol::Interner::new();
+				let path = interner.get_or_intern("<test>");
+
+				// Operator chains nest without being limited by the parser.
+				let mut contents = String::from("let x = 1\nlet y = x");
+				contents.push_str(&" + x".repeat(terms));
+				let source = syntax::Source { path, contents: contents.into_bytes().into() };
+
+				let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+				assert!(syntactic_analysis.errors.is_empty());
+
+				let (program, _) = Analyzer::analyze(syntactic_analysis.ast, &mut interner)
+					.expect("program should be valid");
+
+				binary::encode(&program, &interner, binary::hash(&source.contents))
+			};
+
+			let mut interner = symbol::Interner::new();
+
+			assert!(binary::decode(&compile(1000), &mut interner).is_ok());
+
+			assert_eq!(
+				binary::decode(&compile(5000), &mut interner).err(),
+				Some(DecodeError::TooDeep),
+			);
+		}
+	);
+}
@@ -0,0 +1,70 @@
+use std::fmt::Display as _;
+
+use super::{Warnings, Warning, WarningKind};
+use crate::{
+	fmt::{self, Display},
+	symbol,
+	term::color
+};
+
+
+impl<'a> Display<'a> for WarningKind {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::UnusedVariable(symbol) => {
+				"unused variable '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"'".fmt(f)
+			}
+
+			Self::UnusedParameter(symbol) => {
+				"unused parameter '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"'".fmt(f)
+			}
+
+			Self::UnreachableCode => write!(f, "unreachable code"),
+
+			Self::LoopNeverRuns => write!(f, "loop never runs, the condition is always false"),
+
+			Self::ShadowedVariable(symbol, shadowed) => {
+				"variable '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"' shadows the declaration at ".fmt(f)?;
+				shadowed.fmt(f, context)
+			}
+
+			Self::ShadowedOuterVariable(symbol, shadowed) => {
+				"variable '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"' shadows the declaration of an enclosing function at ".fmt(f)?;
+				shadowed.fmt(f, context)
+			}
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Warning {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		write!(f, "{}: {} - ", color::Fg(color::Yellow, "Warning"), fmt::Show(self.pos, context))?;
+		self.kind.fmt(f, context)
+	}
+}
+
+
+impl<'a> Display<'a> for Warnings {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		for warning in self.0.iter() {
+			writeln!(f, "{}", fmt::Show(warning, context))?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,130 @@
+mod fmt;
+
+use super::{Symbol, SourcePos};
+
+
+/// The kind of a semantic warning.
+#[derive(Debug)]
+pub enum WarningKind {
+	/// Variable declared or assigned, but never read.
+	UnusedVariable(Symbol),
+	/// Function parameter never read.
+	UnusedParameter(Symbol),
+	/// Statement following a break in the same block.
+	UnreachableCode,
+	/// While loop whose condition is the literal `false`.
+	LoopNeverRuns,
+	/// Declaration shadowing another one in an enclosing scope of the same function, with
+	/// the position of the shadowed declaration.
+	ShadowedVariable(Symbol, SourcePos),
+	/// Declaration shadowing another one in an enclosing function, with the position of the
+	/// shadowed declaration.
+	ShadowedOuterVariable(Symbol, SourcePos),
+}
+
+
+impl WarningKind {
+	/// A stable identifier for the kind of warning.
+	pub fn code(&self) -> &'static str {
+		match self {
+			Self::UnusedVariable(_) => "unused-variable",
+			Self::UnusedParameter(_) => "unused-parameter",
+			Self::UnreachableCode => "unreachable-code",
+			Self::LoopNeverRuns => "loop-never-runs",
+			Self::ShadowedVariable(..) => "shadowed-variable",
+			Self::ShadowedOuterVariable(..) => "shadowed-outer-variable",
+		}
+	}
+}
+
+
+/// A semantic warning. Unlike errors, warnings don't prevent the program from running.
+#[derive(Debug)]
+pub struct Warning {
+	pub kind: WarningKind,
+	pub pos: SourcePos,
+}
+
+
+impl Warning {
+	/// Variable declared or assigned, but never read.
+	pub fn unused_variable(symbol: Symbol, pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::UnusedVariable(symbol),
+			pos
+		}
+	}
+
+
+	/// Function parameter never read.
+	pub fn unused_parameter(symbol: Symbol, pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::UnusedParameter(symbol),
+			pos
+		}
+	}
+
+
+	/// Statement following a break in the same block.
+	pub fn unreachable_code(pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::UnreachableCode,
+			pos
+		}
+	}
+
+
+	/// While loop whose condition is the literal `false`.
+	pub fn loop_never_runs(pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::LoopNeverRuns,
+			pos
+		}
+	}
+
+
+	/// Declaration shadowing another one in an enclosing scope.
+	pub fn shadowed_variable(symbol: Symbol, shadowed: SourcePos, outer: bool, pos: SourcePos) -> Self {
+		Self {
+			kind: if outer {
+				WarningKind::ShadowedOuterVariable(symbol, shadowed)
+			} else {
+				WarningKind::ShadowedVariable(symbol, shadowed)
+			},
+			pos
+		}
+	}
+}
+
+
+/// A collection of semantic warnings.
+#[derive(Debug, Default)]
+pub struct Warnings(pub Vec<Warning>);
+
+
+impl Warnings {
+	/// Whether there are no warnings.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+
+impl IntoIterator for Warnings {
+	type Item = Warning;
+	type IntoIter = std::vec::IntoIter<Warning>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+
+impl Extend<Warning> for Warnings {
+	fn extend<T>(&mut self, iter: T)
+	where
+		T : IntoIterator<Item = Warning>,
+	{
+		self.0.extend(iter)
+	}
+}
@@ -1,5 +1,7 @@
 mod fmt;
 
+use std::collections::HashMap;
+
 use intaglio::{Symbol as SymbolInner, bytes::SymbolTable};
 
 
@@ -25,8 +27,14 @@ impl From<Symbol> for usize {
 
 
 /// A symbol interner, used to store identifiers, paths, etc.
+/// The source code of each analyzed path, including imported modules, is kept as well, so
+/// that error messages may display the offending snippet. As the code is kept as loaded,
+/// snippets match the executed code even if the file is later changed or removed.
 #[derive(Debug)]
-pub struct Interner(SymbolTable);
+pub struct Interner {
+	symbols: SymbolTable,
+	sources: HashMap<Symbol, Box<[u8]>>,
+}
 
 
 impl Interner {
@@ -37,17 +45,16 @@ impl Interner {
 		interner
 			.intern(b"<invalid symbol>".as_ref())
 			.expect("failed to intern symbol");
-		Self(interner)
+		Self { symbols: interner, sources: HashMap::new() }
 	}
 
 
-	/// Get the symbol for a value.
-	#[cfg(test)]
+	/// Get the symbol for a value, if interned.
 	pub fn get<T>(&self, value: T) -> Option<Symbol>
 	where
 		T: AsRef<[u8]>,
 	{
-		self.0
+		self.symbols
 			.check_interned(value.as_ref())
 			.map(Symbol)
 	}
@@ -61,7 +68,7 @@ impl Interner {
 		let value = value.as_ref().to_owned();
 
 		Symbol(
-			self.0
+			self.symbols
 				.intern(value)
 				.expect("failed to intern symbol")
 		)
@@ -70,7 +77,21 @@ impl Interner {
 
 	/// Resolve the string for a symbol.
 	pub fn resolve(&self, symbol: Symbol) -> Option<&[u8]> {
-		self.0.get(symbol.0)
+		self.symbols.get(symbol.0)
+	}
+
+
+	/// Store the source code for a path symbol, replacing any previous contents.
+	pub fn add_source(&mut self, path: Symbol, contents: &[u8]) {
+		self.sources.insert(path, contents.into());
+	}
+
+
+	/// Get the source code for a path symbol, if any.
+	pub fn source(&self, path: Symbol) -> Option<&[u8]> {
+		self.sources
+			.get(&path)
+			.map(AsRef::as_ref)
 	}
 
 
@@ -78,6 +99,6 @@ impl Interner {
 	/// This does not include the dummy symbol.
 	#[cfg(test)]
 	pub fn len(&self) -> usize {
-		self.0.len() - 1
+		self.symbols.len() - 1
 	}
 }
@@ -13,9 +13,12 @@ use super::{
 	Command,
 	CommandBlock,
 	CommandBlockKind,
+	DictKey,
+	DictPatternItem,
 	Expr,
 	IllFormed,
 	Literal,
+	Pattern,
 	Redirection,
 	RedirectionTarget,
 	Statement,
@@ -89,6 +92,23 @@ impl<'a> Display<'a> for Block {
 }
 
 
+impl<'a> Display<'a> for DictKey {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Identifier(symbol, _) => symbol.fmt(f, context.interner),
+
+			Self::Computed(expr) => {
+				"[".fmt(f)?;
+				expr.fmt(f, context)?;
+				"]".fmt(f)
+			}
+		}
+	}
+}
+
+
 impl<'a> Display<'a> for Literal {
 	type Context = Context<'a>;
 
@@ -140,9 +160,9 @@ impl<'a> Display<'a> for Literal {
 				fmt::sep_by(
 					dict.iter(),
 					f,
-					|((k, _), v), f| {
+					|(k, v), f| {
 						step(f, nested)?;
-						k.fmt(f, nested.interner)?;
+						k.fmt(f, nested)?;
 						": ".fmt(f)?;
 						v.fmt(f, nested)
 					},
@@ -204,7 +224,9 @@ impl std::fmt::Display for BinaryOp {
 			Self::Minus => Operator::Minus.fmt(f),
 			Self::Times => Operator::Times.fmt(f),
 			Self::Div => Operator::Div.fmt(f),
+			Self::FloorDiv => Operator::FloorDiv.fmt(f),
 			Self::Mod => Operator::Mod.fmt(f),
+			Self::Pow => Operator::Pow.fmt(f),
 			Self::Equals => Operator::Equals.fmt(f),
 			Self::NotEquals => Operator::NotEquals.fmt(f),
 			Self::Greater => Operator::Greater.fmt(f),
@@ -324,12 +346,102 @@ impl<'a> Display<'a> for Expr {
 				")".fmt(f)
 			}
 
+			Self::Spread { expr, .. } => {
+				"...".fmt(f)?;
+				expr.fmt(f, context.inlined())
+			}
+
 			Self::CommandBlock { block, .. } => block.fmt(f, context),
+
+			Self::Interpolation { parts, .. } => {
+				"\"".fmt(f)?;
+
+				for part in parts.iter() {
+					match part {
+						Self::Literal { literal: Literal::String(string), .. } => {
+							String::from_utf8_lossy(string).escape_debug().fmt(f)?;
+						}
+
+						expr => {
+							"${".fmt(f)?;
+							expr.fmt(f, context.inlined())?;
+							"}".fmt(f)?;
+						}
+					}
+				}
+
+				"\"".fmt(f)
+			}
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Pattern {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::IllFormed => ILL_FORMED.fmt(f),
+
+			Self::Identifier { identifier, .. } => identifier.fmt(f, context.interner),
+
+			Self::Array { items, rest, .. } => {
+				"[".fmt(f)?;
+
+				fmt::sep_by(
+					items.iter(),
+					f,
+					|item, f| item.fmt(f, context),
+					", ",
+				)?;
+
+				if let Some(rest) = rest {
+					if !items.is_empty() {
+						", ".fmt(f)?;
+					}
+					"...".fmt(f)?;
+					rest.fmt(f, context)?;
+				}
+
+				"]".fmt(f)
+			}
+
+			Self::Dict { items, .. } => {
+				"@[".fmt(f)?;
+
+				fmt::sep_by(
+					items.iter(),
+					f,
+					|item, f| item.fmt(f, context),
+					", ",
+				)?;
+
+				"]".fmt(f)
+			}
 		}
 	}
 }
 
 
+impl<'a> Display<'a> for DictPatternItem {
+	type Context = Context<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		self.key.fmt(f, context.interner)?;
+		": ".fmt(f)?;
+		self.pattern.fmt(f, context)?;
+
+		if let Some(default) = &self.default {
+			" = ".fmt(f)?;
+			default.fmt(f, context.inlined())?;
+		}
+
+		Ok(())
+	}
+}
+
+
 impl<'a> Display<'a> for Statement {
 	type Context = Context<'a>;
 
@@ -351,6 +463,22 @@ impl<'a> Display<'a> for Statement {
 				right.fmt(f, context)
 			}
 
+			Self::MultipleAssign { left, right, .. } => {
+				fmt::sep_by(left.iter(), f, |item, f| item.fmt(f, context.inlined()), ", ")?;
+				" = ".fmt(f)?;
+				fmt::sep_by(right.iter(), f, |item, f| item.fmt(f, context.inlined()), ", ")
+			}
+
+			Self::Destructure { pattern, declare, right, .. } => {
+				if *declare {
+					Keyword::Let.fmt(f)?;
+					" ".fmt(f)?;
+				}
+				pattern.fmt(f, context)?;
+				" = ".fmt(f)?;
+				right.fmt(f, context)
+			}
+
 			Self::Return { expr, .. } => {
 				Keyword::Return.fmt(f)?;
 				" ".fmt(f)?;
@@ -115,6 +115,30 @@ impl IllFormed for Block {
 }
 
 
+/// Keys in dict literals.
+#[derive(Debug)]
+pub enum DictKey {
+	/// A plain identifier key, which is a literal string: `@[ key: value ]`.
+	Identifier(Symbol, SourcePos),
+	/// A computed key, evaluated when the literal is constructed: `@[ [expr]: value ]`.
+	Computed(Expr),
+}
+
+
+impl IllFormed for DictKey {
+	fn ill_formed() -> Self {
+		Self::Computed(Expr::IllFormed)
+	}
+
+	fn is_ill_formed(&self) -> bool {
+		match self {
+			Self::Identifier(symbol, pos) => symbol.is_ill_formed() || pos.is_ill_formed(),
+			Self::Computed(expr) => expr.is_ill_formed(),
+		}
+	}
+}
+
+
 /// Literals of all types in the language.
 /// Note that there are no literals for the error type.
 #[derive(Debug)]
@@ -126,7 +150,7 @@ pub enum Literal {
 	Byte(u8),
 	String(Box<[u8]>),
 	Array(Box<[Expr]>),
-	Dict(Box<[((Symbol, SourcePos), Expr)]>),
+	Dict(Box<[(DictKey, Expr)]>),
 	Function {
 		/// A list of parameters (identifiers).
 		params: Box<[(Symbol, SourcePos)]>,
@@ -195,11 +219,13 @@ impl From<lexer::Operator> for UnaryOp {
 /// statements/expressions instead.
 #[derive(Debug)]
 pub enum BinaryOp {
-	Plus,  // +
-	Minus, // -
-	Times, // *
-	Div,   // /
-	Mod,   // %
+	Plus,     // +
+	Minus,    // -
+	Times,    // *
+	Div,      // /
+	FloorDiv, // //
+	Mod,      // %
+	Pow,      // **
 
 	Equals,        // ==
 	NotEquals,     // !=
@@ -223,7 +249,9 @@ impl From<lexer::Operator> for BinaryOp {
 			lexer::Operator::Minus => BinaryOp::Minus,
 			lexer::Operator::Times => BinaryOp::Times,
 			lexer::Operator::Div => BinaryOp::Div,
+			lexer::Operator::FloorDiv => BinaryOp::FloorDiv,
 			lexer::Operator::Mod => BinaryOp::Mod,
+			lexer::Operator::Pow => BinaryOp::Pow,
 			lexer::Operator::Equals => BinaryOp::Equals,
 			lexer::Operator::NotEquals => BinaryOp::NotEquals,
 			lexer::Operator::Greater => BinaryOp::Greater,
@@ -286,10 +314,44 @@ pub enum Expr {
 		args: Box<[Expr]>,
 		pos: SourcePos,
 	},
+	/// Spread argument (...), only found in function calls, where the array is expanded
+	/// into consecutive arguments.
+	Spread {
+		expr: Box<Expr>,
+		pos: SourcePos,
+	},
 	CommandBlock {
 		block: CommandBlock,
 		pos: SourcePos,
 	},
+	/// Interpolated string. The parts alternate between string literals and embedded
+	/// expressions, starting and ending with a string literal.
+	Interpolation {
+		parts: Box<[Expr]>,
+		pos: SourcePos,
+	},
+}
+
+
+impl Expr {
+	/// The position where the expression starts.
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::IllFormed => SourcePos::ill_formed(),
+			Self::UnaryOp { op, operand, .. } if op.is_postfix() => operand.pos(),
+			Self::BinaryOp { left, .. } => left.pos(),
+			Self::Access { object, .. } => object.pos(),
+			Self::Call { function, .. } => function.pos(),
+			Self::Self_ { pos }
+			| Self::Identifier { pos, .. }
+			| Self::Literal { pos, .. }
+			| Self::UnaryOp { pos, .. }
+			| Self::If { pos, .. }
+			| Self::Spread { pos, .. }
+			| Self::CommandBlock { pos, .. }
+			| Self::Interpolation { pos, .. } => *pos,
+		}
+	}
 }
 
 
@@ -304,6 +366,70 @@ impl IllFormed for Expr {
 }
 
 
+/// Destructuring patterns, used in assignments and declarations.
+#[derive(Debug)]
+pub enum Pattern {
+	/// An ill-formed pattern, produced by a parse error.
+	IllFormed,
+	Identifier {
+		identifier: Symbol,
+		pos: SourcePos,
+	},
+	/// Array destructuring, such as `[ a, b, ...rest ]`.
+	Array {
+		items: Box<[Pattern]>,
+		/// The pattern that collects the remaining items, if any.
+		rest: Option<Box<Pattern>>,
+		pos: SourcePos,
+	},
+	/// Dict destructuring, such as `@[ host, port: p, timeout = 30 ]`.
+	Dict {
+		items: Box<[DictPatternItem]>,
+		pos: SourcePos,
+	},
+}
+
+
+/// A single item in a dict destructuring pattern.
+#[derive(Debug)]
+pub struct DictPatternItem {
+	pub key: Symbol,
+	/// The pattern that receives the value. For shorthand items, this is an identifier
+	/// named after the key.
+	pub pattern: Pattern,
+	/// The value to be used when the key is missing in the dict.
+	pub default: Option<Expr>,
+	pub pos: SourcePos,
+}
+
+
+impl IllFormed for DictPatternItem {
+	fn ill_formed() -> Self {
+		Self {
+			key: Symbol::ill_formed(),
+			pattern: Pattern::IllFormed,
+			default: None,
+			pos: SourcePos::ill_formed(),
+		}
+	}
+
+	fn is_ill_formed(&self) -> bool {
+		self.key.is_ill_formed() || self.pattern.is_ill_formed()
+	}
+}
+
+
+impl IllFormed for Pattern {
+	fn ill_formed() -> Self {
+		Self::IllFormed
+	}
+
+	fn is_ill_formed(&self) -> bool {
+		matches!(self, Self::IllFormed)
+	}
+}
+
+
 /// Statements of all kinds in the language.
 #[derive(Debug)]
 pub enum Statement {
@@ -320,6 +446,19 @@ pub enum Statement {
 		right: Expr,
 		pos: SourcePos,
 	},
+	/// Assignment of multiple values, such as `a, b = b, a`.
+	MultipleAssign {
+		left: Box<[Expr]>,
+		right: Box<[Expr]>,
+		pos: SourcePos,
+	},
+	/// Destructuring assignment. Introduces the identifiers in the pattern if declaring.
+	Destructure {
+		pattern: Pattern,
+		declare: bool,
+		right: Expr,
+		pos: SourcePos,
+	},
 	Return {
 		expr: Expr,
 		pos: SourcePos,
@@ -344,6 +483,26 @@ pub enum Statement {
 }
 
 
+impl Statement {
+	/// The position of the statement. Assignments and expressions are positioned where they
+	/// start.
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::IllFormed => SourcePos::ill_formed(),
+			Self::Assign { left, .. } => left.pos(),
+			Self::MultipleAssign { left, pos, .. } => left.first().map_or(*pos, Expr::pos),
+			Self::Expr(expr) => expr.pos(),
+			Self::Let { pos, .. }
+			| Self::Destructure { pos, .. }
+			| Self::Return { pos, .. }
+			| Self::Break { pos }
+			| Self::While { pos, .. }
+			| Self::For { pos, .. } => *pos,
+		}
+	}
+}
+
+
 impl IllFormed for Statement {
 	fn ill_formed() -> Self {
 		Self::IllFormed
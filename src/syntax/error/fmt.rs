@@ -36,7 +36,7 @@ impl<'a> Display<'a> for Errors {
 					writeln!(
 						f,
 						"{} {}",
-						color::Fg(color::Red, max),
+						color::Fg(color::Red, self.0.len() - max),
 						color::Fg(color::Red, "more supressed syntax errors"),
 					)?;
 
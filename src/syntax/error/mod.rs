@@ -24,4 +24,30 @@ impl Errors {
 	pub fn is_empty(&self) -> bool {
 		self.0.is_empty()
 	}
+
+
+	/// Check if the errors are only due to the source ending inside an open construct, such
+	/// as a block or a string, in which case more input could complete it.
+	pub fn is_incomplete(&self) -> bool {
+		let unterminated = |error: &Error| match error {
+			Error::Parser(parser::Error::UnexpectedEof { open, .. }) => open.is_some(),
+
+			Error::Lexer(error) => matches!(
+				error.error,
+				lexer::ErrorKind::UnexpectedEof
+					| lexer::ErrorKind::UnterminatedString
+					| lexer::ErrorKind::UnterminatedInterpolation
+					| lexer::ErrorKind::UnterminatedRawString
+					| lexer::ErrorKind::UnterminatedBlockComment
+			),
+
+			Error::Parser(_) => false,
+		};
+
+		// Unterminated tokens make the parser reach the end of the source as well.
+		self.0.iter().any(unterminated) && self.0.iter().all(
+			|error| unterminated(error)
+				|| matches!(error, Error::Parser(parser::Error::UnexpectedEof { .. }))
+		)
+	}
 }
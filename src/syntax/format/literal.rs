@@ -0,0 +1,167 @@
+//! Canonical spelling of string and byte literals.
+
+use std::fmt::Write;
+
+
+/// A character of a quoted literal.
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+	/// A byte that was written as is, and is kept as such.
+	Verbatim(u8),
+	/// A character written as an escape sequence.
+	Escaped(char),
+	/// A byte written as an escape sequence, which may not be a valid character by itself.
+	EscapedByte(u8),
+}
+
+
+/// Respell a quoted literal, such as `"..."` or `'...'`, with canonical escape sequences:
+/// escapes are only used for characters that require them, or that would be invisible
+/// otherwise. Characters written as is are kept untouched.
+pub fn quoted(text: &[u8]) -> Vec<u8> {
+	let quote = text[0];
+	let contents = &text[1 .. text.len() - 1];
+
+	let units = decode(contents);
+
+	// Whether a dollar must be escaped depends on the following character, which is why
+	// units are spelled backwards.
+	let mut spelled: Vec<Vec<u8>> = Vec::with_capacity(units.len());
+	for unit in units.iter().rev() {
+		let next = spelled.last().and_then(|next| next.first().copied());
+		spelled.push(spell(*unit, quote, next));
+	}
+
+	let mut literal = Vec::with_capacity(text.len());
+	literal.push(quote);
+	literal.extend(spelled.into_iter().rev().flatten());
+	literal.push(quote);
+
+	literal
+}
+
+
+/// Respell a raw string literal, such as `r#"..."#`, with as few hashes as possible.
+pub fn raw(text: &[u8]) -> Vec<u8> {
+	let hashes = text[1 ..]
+		.iter()
+		.take_while(|&&c| c == b'#')
+		.count();
+
+	let contents = &text[hashes + 2 .. text.len() - hashes - 1];
+
+	// The literal ends at the first quote followed by as many hashes as the delimiter.
+	let needed = contents
+		.iter()
+		.enumerate()
+		.filter(|(_, &c)| c == b'"')
+		.map(
+			|(ix, _)| 1 + contents[ix + 1 ..]
+				.iter()
+				.take_while(|&&c| c == b'#')
+				.count()
+		)
+		.max()
+		.unwrap_or(0);
+
+	let mut literal = Vec::with_capacity(contents.len() + 2 * needed + 3);
+	literal.push(b'r');
+	literal.resize(1 + needed, b'#');
+	literal.push(b'"');
+	literal.extend_from_slice(contents);
+	literal.push(b'"');
+	literal.resize(literal.len() + needed, b'#');
+
+	literal
+}
+
+
+/// Decode the contents of a literal, which has already been validated by the lexer.
+fn decode(contents: &[u8]) -> Vec<Unit> {
+	let mut units = Vec::with_capacity(contents.len());
+	let mut ix = 0;
+
+	while ix < contents.len() {
+		if contents[ix] != b'\\' {
+			units.push(Unit::Verbatim(contents[ix]));
+			ix += 1;
+			continue;
+		}
+
+		let (unit, len) = match contents[ix + 1] {
+			b'x' => {
+				let digits = std::str::from_utf8(&contents[ix + 2 .. ix + 4]).unwrap_or_default();
+				let byte = u8::from_str_radix(digits, 16).unwrap_or_default();
+				(Unit::EscapedByte(byte), 4)
+			}
+
+			b'u' => {
+				let close = contents[ix ..]
+					.iter()
+					.position(|&c| c == b'}')
+					.map_or(contents.len(), |close| ix + close);
+
+				let digits = std::str::from_utf8(&contents[ix + 3 .. close]).unwrap_or_default();
+				let c = u32::from_str_radix(digits, 16)
+					.ok()
+					.and_then(char::from_u32)
+					.unwrap_or_default();
+
+				(Unit::Escaped(c), close + 1 - ix)
+			}
+
+			b'n' => (Unit::EscapedByte(b'\n'), 2),
+			b't' => (Unit::EscapedByte(b'\t'), 2),
+			b'r' => (Unit::EscapedByte(b'\r'), 2),
+			b'0' => (Unit::EscapedByte(b'\0'), 2),
+			c => (Unit::EscapedByte(c), 2),
+		};
+
+		units.push(unit);
+		ix += len;
+	}
+
+	units
+}
+
+
+/// Spell a unit in a literal delimited by the given quote, given the first byte spelled
+/// after it, if any.
+fn spell(unit: Unit, quote: u8, next: Option<u8>) -> Vec<u8> {
+	match unit {
+		// A dollar may only be verbatim in a string if it doesn't start an interpolation.
+		Unit::Verbatim(b'$') | Unit::Escaped('$') | Unit::EscapedByte(b'$') => {
+			let interpolates = quote == b'"'
+				&& next.is_some_and(|c| c == b'{' || c == b'_' || !c.is_ascii() || c.is_ascii_alphabetic());
+
+			if interpolates {
+				b"\\$".to_vec()
+			} else {
+				b"$".to_vec()
+			}
+		}
+
+		Unit::Verbatim(c) => vec![c],
+
+		Unit::Escaped(c) if c.is_ascii() => spell(Unit::EscapedByte(c as u8), quote, next),
+
+		Unit::Escaped(c) if c.is_control() || c.is_whitespace() => {
+			let mut escape = String::new();
+			let _ = write!(escape, "\\u{{{:x}}}", c as u32);
+			escape.into_bytes()
+		}
+
+		Unit::Escaped(c) => c.to_string().into_bytes(),
+
+		Unit::EscapedByte(c) => match c {
+			b'\n' => b"\\n".to_vec(),
+			b'\t' => b"\\t".to_vec(),
+			b'\r' => b"\\r".to_vec(),
+			b'\0' => b"\\0".to_vec(),
+			b'\\' => b"\\\\".to_vec(),
+			c if c == quote => vec![b'\\', c],
+			b' ' ..= b'~' => vec![c],
+			c => format!("\\x{:02x}", c).into_bytes(),
+		},
+	}
+}
@@ -0,0 +1,619 @@
+mod literal;
+#[cfg(test)]
+mod tests;
+
+use super::{
+	lexer::{Cursor, Keyword, Lexer, Literal, Operator, TokenKind},
+	Analysis,
+	Errors,
+	Source,
+};
+use crate::symbol;
+
+
+/// The default maximum line width.
+pub const DEFAULT_WIDTH: usize = 100;
+
+/// The width of an indentation level, when measuring lines.
+const TAB_WIDTH: usize = 4;
+
+
+/// Formatting options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+	/// The maximum width of lines, over which argument lists and literals are split in
+	/// one item per line.
+	pub width: usize,
+}
+
+
+impl Default for Config {
+	fn default() -> Self {
+		Self { width: DEFAULT_WIDTH }
+	}
+}
+
+
+/// Format the source code, which is printed back with canonical indentation, spacing and
+/// literals. Comments and line breaks are preserved, except for consecutive blank lines.
+/// Formatting is idempotent, and only done if there are no syntax errors.
+pub fn format(
+	source: &Source,
+	interner: &mut symbol::Interner,
+	config: Config,
+) -> Result<Vec<u8>, Errors> {
+	let analysis = Analysis::analyze(source, interner);
+	if !analysis.is_ok() {
+		return Err(analysis.errors);
+	}
+
+	let mut items = tokenize(source, interner);
+	classify(&mut items);
+	trailing_commas(&mut items);
+
+	loop {
+		let lines = layout(&items);
+
+		let overflow = lines
+			.iter()
+			.filter(|line| line.width(&items) > config.width)
+			.find_map(|line| line.splittable(&items));
+
+		match overflow {
+			Some((open, close)) => {
+				split(&mut items, open, close);
+				trailing_commas(&mut items);
+			}
+
+			None => return Ok(render(&items, &lines)),
+		}
+	}
+}
+
+
+/// A token, along with its spelling and surroundings.
+#[derive(Debug)]
+struct Item {
+	kind: TokenKind,
+	/// The canonical spelling of the token.
+	text: Vec<u8>,
+	/// The number of line breaks preceding the token, where two means a blank line.
+	breaks: usize,
+	/// Whether the token was preceded by whitespace.
+	spaced: bool,
+	/// Whether the token is part of a command block, excluding the opening brace.
+	command: bool,
+	/// For brackets, the kind of group and the index of the matching bracket.
+	group: Option<(Group, usize)>,
+	/// Whether the token is a prefix operator.
+	prefix: bool,
+	/// Whether the token is a keyword used as a field name or dict key, such as `x.end`.
+	field: bool,
+}
+
+
+impl Item {
+	fn new(kind: TokenKind, text: Vec<u8>) -> Self {
+		Self {
+			kind,
+			text,
+			breaks: 0,
+			spaced: false,
+			command: false,
+			group: None,
+			prefix: false,
+			field: false,
+		}
+	}
+
+
+	fn is_comment(&self) -> bool {
+		matches!(self.kind, TokenKind::Comment(_))
+	}
+
+
+	/// Whether the token starts a construct that is indented until its closing token.
+	fn opens(&self) -> bool {
+		self.kind.closing().is_some() && !self.field
+	}
+
+
+	fn closes(&self) -> bool {
+		!self.field && matches!(
+			self.kind,
+			TokenKind::Keyword(Keyword::End)
+				| TokenKind::CloseParens
+				| TokenKind::CloseBracket
+				| TokenKind::CloseCommand
+		)
+	}
+
+
+	/// Whether the token may end an operand, after which brackets are postfix operators.
+	fn ends_operand(&self) -> bool {
+		match self.kind {
+			TokenKind::Keyword(_) if self.field => true,
+			TokenKind::CloseParens => !matches!(self.group, Some((Group::Parameters, _))),
+			TokenKind::Identifier(_)
+				| TokenKind::Literal(_)
+				| TokenKind::InterpolationEnd(_)
+				| TokenKind::Keyword(Keyword::Self_ | Keyword::End)
+				| TokenKind::Operator(Operator::Try)
+				| TokenKind::CloseBracket
+				| TokenKind::CloseCommand => true,
+			_ => false,
+		}
+	}
+}
+
+
+/// The kind of a bracketed group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+	/// Parenthesized expressions.
+	Parens,
+	/// Function parameters.
+	Parameters,
+	/// Call arguments.
+	Arguments,
+	/// Subscript operators.
+	Subscript,
+	/// Computed dict keys, such as `@[ [key]: value ]`.
+	Key,
+	/// Array and dict literals or patterns.
+	Literal,
+	/// Command blocks, whose content is only spaced.
+	Command,
+}
+
+
+impl Group {
+	/// Whether the group is a comma separated list, which may be split in multiple lines.
+	fn is_list(self) -> bool {
+		matches!(self, Self::Parameters | Self::Arguments | Self::Literal)
+	}
+}
+
+
+/// Lex the source code, which must be free of errors, keeping comments.
+fn tokenize(source: &Source, interner: &mut symbol::Interner) -> Vec<Item> {
+	let contents = &source.contents;
+	let mut lexer = Lexer::with_comments(Cursor::from(source), interner);
+
+	let mut items: Vec<Item> = Vec::new();
+	let mut end = 0;
+	let mut command = false;
+	// Whether the last string piece is followed by an identifier interpolation, such as in
+	// "$x", after which the next piece follows immediately.
+	let mut dollar = false;
+
+	while let Some(result) = lexer.next() {
+		let token = match result {
+			Ok(token) => token,
+			Err(_) => continue,
+		};
+
+		let text = &contents[end .. lexer.offset()];
+		end = lexer.offset();
+
+		// Only whitespace may precede a token in command blocks. Elsewhere, there may also be
+		// line continuations, which are dropped as line breaks are kept anyway.
+		let piece = matches!(token.kind, TokenKind::Interpolation(_) | TokenKind::InterpolationEnd(_));
+
+		let mut start = 0;
+		if !(piece && dollar) {
+			loop {
+				match &text[start ..] {
+					[ c, .. ] if c.is_ascii_whitespace() => start += 1,
+					[ b'\\', b'\n', .. ] if !command => start += 2,
+					[ b'\\', b'\r', b'\n', .. ] if !command => start += 3,
+					_ => break,
+				}
+			}
+		}
+
+		let (gap, text) = text.split_at(start);
+
+		if piece {
+			dollar = matches!(token.kind, TokenKind::Interpolation(_)) && !text.ends_with(b"{");
+		}
+
+		let text = match &token.kind {
+			TokenKind::Literal(Literal::String(_)) if text[0] == b'r' => literal::raw(text),
+			TokenKind::Literal(Literal::String(_) | Literal::Byte(_)) => literal::quoted(text),
+			TokenKind::Comment(_) if text.ends_with(b"]]#") => text.into(),
+			TokenKind::Comment(_) => text.trim_ascii_end().into(),
+			_ => text.into(),
+		};
+
+		let mut item = Item::new(token.kind, text);
+		item.breaks = gap.iter().filter(|&&c| c == b'\n').count().min(2);
+		item.spaced = !gap.is_empty();
+		item.command = command;
+
+		if item.kind.is_command_block_starter() {
+			command = true;
+		} else if item.kind == TokenKind::CloseCommand {
+			item.command = true;
+			command = false;
+		}
+
+		items.push(item);
+	}
+
+	if let Some(first) = items.first_mut() {
+		first.breaks = 0;
+	}
+
+	items
+}
+
+
+/// Match brackets and classify their groups, and find prefix operators.
+fn classify(items: &mut [Item]) {
+	let mut open: Vec<usize> = Vec::new();
+	// The last two tokens, other than comments.
+	let mut previous: (Option<usize>, Option<usize>) = (None, None);
+
+	let function = TokenKind::Keyword(Keyword::Function);
+
+	for ix in 0 .. items.len() {
+		if items[ix].is_comment() {
+			continue;
+		}
+
+		let (last, before_last) = (
+			previous.0.map(|previous| &items[previous]),
+			previous.1.map(|previous| &items[previous]),
+		);
+
+		let field = matches!(items[ix].kind, TokenKind::Keyword(_))
+			&& last.is_some_and(|last| last.kind == TokenKind::Operator(Operator::Dot));
+
+		let after_operand = last.is_some_and(Item::ends_operand);
+
+		let parameters = last.is_some_and(|last| last.kind == function)
+			|| (
+				last.is_some_and(|last| matches!(last.kind, TokenKind::Identifier(_)))
+					&& before_last.is_some_and(|before_last| before_last.kind == function)
+			);
+
+		let group = match &items[ix].kind {
+			TokenKind::OpenParens if parameters => Some(Group::Parameters),
+			TokenKind::OpenParens if after_operand => Some(Group::Arguments),
+			TokenKind::OpenParens => Some(Group::Parens),
+			TokenKind::OpenBracket if after_operand => Some(Group::Subscript),
+			TokenKind::OpenBracket | TokenKind::OpenDict => Some(Group::Literal),
+			kind if kind.is_command_block_starter() => Some(Group::Command),
+			_ => None,
+		};
+
+		items[ix].field = field;
+		items[ix].prefix = match items[ix].kind {
+			TokenKind::Operator(Operator::Not) => true,
+			TokenKind::Operator(Operator::Minus) => !after_operand,
+			_ => false,
+		};
+
+		if let Some(group) = group {
+			items[ix].group = Some((group, ix));
+			open.push(ix);
+		} else if matches!(
+			items[ix].kind,
+			TokenKind::CloseParens | TokenKind::CloseBracket | TokenKind::CloseCommand
+		) {
+			if let Some(opener) = open.pop() {
+				let group = items[opener].group.map_or(Group::Parens, |(group, _)| group);
+				items[opener].group = Some((group, ix));
+				items[ix].group = Some((group, opener));
+			}
+		}
+
+		previous = (Some(ix), previous.0);
+	}
+
+	let next = |items: &[Item], ix: usize| items[ix + 1 ..]
+		.iter()
+		.find(|item| !item.is_comment())
+		.map(|item| item.kind.clone());
+
+	for ix in 0 .. items.len() {
+		// Keywords followed by a colon are dict keys.
+		if matches!(items[ix].kind, TokenKind::Keyword(_)) && next(items, ix) == Some(TokenKind::Colon) {
+			items[ix].field = true;
+		}
+
+		if items[ix].kind != TokenKind::OpenBracket {
+			continue;
+		}
+
+		let (group, close) = match items[ix].group {
+			Some((group, close)) if close > ix => (group, close),
+			_ => continue,
+		};
+
+		let group = match (group, next(items, close)) {
+			// Computed keys in dicts are not array literals.
+			(Group::Literal, Some(TokenKind::Colon)) => Group::Key,
+
+			// A bracket starting a line is a destructuring pattern if followed by an assignment.
+			(Group::Subscript, Some(TokenKind::Operator(Operator::Assign))) if items[ix].breaks > 0 => {
+				Group::Literal
+			}
+
+			(group, _) => group,
+		};
+
+		items[ix].group = Some((group, close));
+		items[close].group = Some((group, ix));
+	}
+}
+
+
+/// Lists whose closing bracket is in a line of its own have a trailing comma, and other
+/// lists have none. Rest patterns, such as `...rest`, may not be followed by a comma.
+fn trailing_commas(items: &mut Vec<Item>) {
+	let mut changed = false;
+
+	// Backwards, so that insertions and removals don't affect the pending groups.
+	for close in (0 .. items.len()).rev() {
+		let open = match items[close].group {
+			Some((group, open)) if open < close && group.is_list() => open,
+			_ => continue,
+		};
+
+		let last = match (open + 1 .. close).rev().find(|&ix| !items[ix].is_comment()) {
+			Some(last) => last,
+			None => continue,
+		};
+
+		let comma = items[last].kind == TokenKind::Comma;
+		let rest = items[open + 1 ..= last]
+			.iter()
+			.rev()
+			.filter(|item| !item.is_comment())
+			.take(2)
+			.any(|item| item.kind == TokenKind::Ellipsis);
+
+		if items[close].breaks > 0 && !comma && !rest {
+			items.insert(last + 1, Item::new(TokenKind::Comma, b",".to_vec()));
+			changed = true;
+		} else if items[close].breaks == 0 && comma {
+			items.remove(last);
+			changed = true;
+		}
+	}
+
+	if changed {
+		classify(items);
+	}
+}
+
+
+/// Split a list in one item per line.
+fn split(items: &mut [Item], open: usize, close: usize) {
+	let break_line = |item: &mut Item| item.breaks = item.breaks.max(1);
+
+	break_line(&mut items[open + 1]);
+	break_line(&mut items[close]);
+
+	let mut ix = open + 1;
+	while ix < close {
+		match items[ix].group {
+			// Skip nested groups.
+			Some((_, nested)) if nested > ix => ix = nested,
+
+			_ if items[ix].kind == TokenKind::Comma && ix + 1 < close => break_line(&mut items[ix + 1]),
+
+			_ => (),
+		}
+
+		ix += 1;
+	}
+}
+
+
+/// A line of output, with the range of its tokens.
+#[derive(Debug)]
+struct Line {
+	indent: usize,
+	start: usize,
+	end: usize,
+}
+
+
+impl Line {
+	/// The width of the line, up to the first line break inside a token, if any.
+	fn width(&self, items: &[Item]) -> usize {
+		let mut text = Vec::new();
+		self.write(items, &mut text);
+
+		let text = text.split(|&c| c == b'\n').next().unwrap_or_default();
+
+		self.indent * TAB_WIDTH + String::from_utf8_lossy(text).chars().count()
+	}
+
+
+	/// The first list which is entirely in the line, if any.
+	fn splittable(&self, items: &[Item]) -> Option<(usize, usize)> {
+		(self.start .. self.end).find_map(
+			|ix| match items[ix].group {
+				Some((group, close))
+					if group.is_list()
+					&& close > ix
+					&& close < self.end
+					&& (ix + 1 .. close).any(|ix| !items[ix].is_comment()) => Some((ix, close)),
+				_ => None,
+			}
+		)
+	}
+
+
+	/// Write the tokens of the line, without indentation.
+	fn write(&self, items: &[Item], out: &mut Vec<u8>) {
+		for ix in self.start .. self.end {
+			if ix > self.start && spaced(&items[ix - 1], &items[ix]) {
+				out.push(b' ');
+			}
+
+			out.extend_from_slice(&items[ix].text);
+		}
+	}
+}
+
+
+/// Whether the token may continue an expression from the previous line.
+fn continues(item: &Item) -> bool {
+	match item.kind {
+		TokenKind::Operator(Operator::Not) => false,
+		TokenKind::Operator(_) => !item.prefix,
+		TokenKind::Pipe => true,
+		_ => false,
+	}
+}
+
+
+/// Whether the token requires an expression in the next line.
+fn continued(item: &Item) -> bool {
+	matches!(
+		item.kind,
+		TokenKind::Operator(op) if op != Operator::Try
+	) || item.kind == TokenKind::Pipe
+}
+
+
+/// Split the tokens in lines, and indent them.
+fn layout(items: &[Item]) -> Vec<Line> {
+	let mut lines = Vec::new();
+
+	// The indentation of the line of each open construct.
+	let mut open: Vec<usize> = Vec::new();
+	// The last token of the previous line, other than comments.
+	let mut previous: Option<&Item> = None;
+
+	let mut start = 0;
+	while start < items.len() {
+		let end = (start + 1 .. items.len())
+			.find(|&ix| items[ix].breaks > 0)
+			.unwrap_or(items.len());
+
+		let first = &items[start];
+
+		// Closing tokens at the beginning of the line are aligned with the line that opened
+		// the outermost of them.
+		let mut ix = start;
+		let mut closed = None;
+		while ix < end && items[ix].closes() {
+			closed = open.pop().or(closed);
+			ix += 1;
+		}
+
+		let indent = match closed {
+			Some(indent) => indent,
+
+			None if first.kind == TokenKind::Keyword(Keyword::Else) => open.last().copied().unwrap_or(0),
+
+			None => {
+				let continuation = previous.is_some_and(continued) || continues(first);
+				open.last().map_or(0, |indent| indent + 1) + continuation as usize
+			}
+		};
+
+		for item in &items[ix .. end] {
+			if item.opens() {
+				open.push(indent);
+			} else if item.closes() {
+				open.pop();
+			}
+		}
+
+		if let Some(last) = items[start .. end].iter().rev().find(|item| !item.is_comment()) {
+			previous = Some(last);
+		}
+
+		lines.push(Line { indent, start, end });
+		start = end;
+	}
+
+	lines
+}
+
+
+/// Whether there should be a space between two tokens in the same line.
+fn spaced(previous: &Item, item: &Item) -> bool {
+	if previous.is_comment() || item.is_comment() {
+		return true;
+	}
+
+	if item.command {
+		return match (&previous.kind, &item.kind) {
+			(_, TokenKind::CloseCommand) => true,
+			(kind, _) if kind.is_command_block_starter() => true,
+
+			(_, TokenKind::Semicolon) => false,
+			(_, TokenKind::CmdOperator(op)) if !op.is_redirection() => false,
+
+			// Redirections are spaced as written, as in `2>&1` or `> file`.
+			(TokenKind::Argument(parts), TokenKind::CmdOperator(_))
+				if matches!(parts.as_ref(), [ part ] if part.is_unquoted_number()) => item.spaced,
+			(TokenKind::CmdOperator(op), _) if op.is_redirection() => item.spaced,
+
+			_ => true,
+		};
+	}
+
+	let opens = previous.group.filter(|_| previous.opens()).map(|(group, _)| group);
+	let closes = item.group.filter(|_| item.closes()).map(|(group, _)| group);
+
+	match (&previous.kind, &item.kind) {
+		// String interpolations, where only the first piece starts with a quote.
+		(TokenKind::Interpolation(_), _) | (_, TokenKind::InterpolationEnd(_)) => false,
+		(_, TokenKind::Interpolation(_)) => item.text.starts_with(b"\""),
+
+		(_, TokenKind::Comma | TokenKind::Colon | TokenKind::Semicolon) => false,
+		(_, TokenKind::Operator(Operator::Try)) => false,
+
+		// Otherwise, the number would be lexed as a float.
+		(TokenKind::Literal(Literal::Int(_)), TokenKind::Operator(Operator::Dot)) => true,
+		(TokenKind::Operator(Operator::Dot), _) | (_, TokenKind::Operator(Operator::Dot)) => false,
+
+		(TokenKind::Ellipsis, _) => false,
+		(TokenKind::Operator(Operator::Minus), _) if previous.prefix => false,
+
+		(TokenKind::Keyword(Keyword::Function), _) => true,
+
+		// Only non-empty literals are padded.
+		_ if opens.is_some() && closes.is_some() => false,
+		_ if opens.is_some() => opens == Some(Group::Literal),
+		_ if closes.is_some() => closes == Some(Group::Literal),
+
+		// Calls and subscripts.
+		_ if item.opens() => !matches!(
+			item.group,
+			Some((Group::Parameters | Group::Arguments | Group::Subscript, _))
+		),
+
+		_ => true,
+	}
+}
+
+
+/// Write the lines, separated by their line breaks.
+fn render(items: &[Item], lines: &[Line]) -> Vec<u8> {
+	let mut out = Vec::new();
+
+	for line in lines {
+		if line.start > 0 {
+			out.resize(out.len() + items[line.start].breaks, b'\n');
+		}
+
+		out.resize(out.len() + line.indent, b'\t');
+		line.write(items, &mut out);
+	}
+
+	if !out.is_empty() {
+		out.push(b'\n');
+	}
+
+	out
+}
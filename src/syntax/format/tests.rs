@@ -0,0 +1,193 @@
+use std::{io::Read, os::unix::ffi::OsStrExt};
+
+use crate::{fmt, symbol, syntax::{ast, Analysis, Source}, tests};
+use super::{format, Config, DEFAULT_WIDTH};
+
+
+/// Format the given source code with the given line width.
+fn format_width(input: &str, width: usize) -> String {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+
+	let output = format(&source, &mut interner, Config { width })
+		.unwrap_or_else(|errors| panic!("failed to format: {:?}", errors));
+
+	String::from_utf8(output).expect("invalid utf-8")
+}
+
+
+/// Format the given source code with the default line width.
+fn format_default(input: &str) -> String {
+	format_width(input, DEFAULT_WIDTH)
+}
+
+
+/// Display the AST of the source code, which has no positions.
+fn show_ast(source: &Source, interner: &mut symbol::Interner) -> String {
+	let analysis = Analysis::analyze(source, interner);
+	assert!(analysis.is_ok(), "{:?}", analysis.errors);
+
+	fmt::Show(&analysis.ast, ast::fmt::Context::from(&*interner)).to_string()
+}
+
+
+#[test]
+fn test_corpus() -> std::io::Result<()> {
+	let dirs = [
+		"examples/hush",
+		"benches/data",
+		"src/syntax/tests/data/positive",
+		"src/semantic/tests/data/positive",
+		"src/runtime/tests/data/positive",
+	];
+
+	for dir in dirs.iter() {
+		tests::util::test_dir(
+			dir,
+			|path, mut file| {
+				let mut interner = symbol::Interner::new();
+				let path = interner.get_or_intern(path.as_os_str().as_bytes());
+
+				let mut contents = Vec::new();
+				file.read_to_end(&mut contents)?;
+				let source = Source { path, contents: contents.into() };
+				let ast = show_ast(&source, &mut interner);
+
+				for &width in [ DEFAULT_WIDTH, 40, 1 ].iter() {
+					let config = Config { width };
+
+					let formatted = format(&source, &mut interner, config).expect("failed to format");
+					let formatted = Source { path, contents: formatted.into() };
+
+					// Formatting preserves the program, and is idempotent.
+					assert_eq!(show_ast(&formatted, &mut interner), ast);
+
+					let reformatted = format(&formatted, &mut interner, config).expect("failed to format");
+					assert_eq!(
+						String::from_utf8_lossy(&reformatted),
+						String::from_utf8_lossy(&formatted.contents),
+						"formatting is not idempotent",
+					);
+				}
+
+				Ok(())
+			}
+		)?;
+	}
+
+	Ok(())
+}
+
+
+#[test]
+fn test_syntax_errors() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: b"let x = (1\n".to_vec().into() };
+
+	assert!(format(&source, &mut interner, Config::default()).is_err());
+}
+
+
+#[test]
+fn test_indentation() {
+	assert_eq!(
+		format_default(
+			"function f(x)\nif x then\nreturn 1\n  else if x == nil then\n\t\t\treturn 2\nelse\nreturn 3\nend end\nend\n"
+		),
+		"function f(x)\n\tif x then\n\t\treturn 1\n\telse if x == nil then\n\t\treturn 2\n\telse\n\t\treturn 3\n\tend end\nend\n",
+	);
+
+	// Continuation lines are indented once.
+	assert_eq!(
+		format_default("let x = 1 +\n2\nlet y = std.range(1, 10, 1)\n.map(f)\n  .collect()\n"),
+		"let x = 1 +\n\t2\nlet y = std.range(1, 10, 1)\n\t.map(f)\n\t.collect()\n",
+	);
+
+	// Blank lines are collapsed, and trailing whitespace is removed.
+	assert_eq!(
+		format_default("\n\nlet x = 1   \n\n\n\nlet y = 2\n\n\n"),
+		"let x = 1\n\nlet y = 2\n",
+	);
+}
+
+
+#[test]
+fn test_spacing() {
+	assert_eq!(
+		format_default("let x=-a+b*(c-1)\nlet y = not(x)and f (x)[ 0 ]. y ?\n"),
+		"let x = -a + b * (c - 1)\nlet y = not (x) and f(x)[0].y?\n",
+	);
+
+	assert_eq!(
+		format_default("let f = function(a,b) @[a:[1,2],[a]:b,end:@[ ]] end\nlet [x,...rest]=f ( )\n"),
+		"let f = function (a, b) @[ a: [ 1, 2 ], [a]: b, end: @[] ] end\nlet [ x, ...rest ] = f()\n",
+	);
+}
+
+
+#[test]
+fn test_literals() {
+	assert_eq!(
+		format_default(
+			r###"let x = [ "\x41\u{42}\'", '\x22', "\$ \${", "\u{a0}\x0a", r##"a"b"##, r#"x"# ]"###
+		),
+		concat!(
+			r###"let x = [ "AB'", '"', "$ \${", "\u{a0}\n", r#"a"b"#, r"x" ]"###,
+			"\n",
+		),
+	);
+
+	// Interpolations are kept as written.
+	assert_eq!(
+		format_default("let s = \"$x, ${ y + 1 }\\n\"\n"),
+		"let s = \"$x, ${y + 1}\\n\"\n",
+	);
+}
+
+
+#[test]
+fn test_trailing_commas() {
+	assert_eq!(
+		format_default("let x = [\n1,\n2\n]\nlet y = f(\n\t1, 2,)\nlet [\n\ta,\n\t...b\n] = x\n"),
+		"let x = [\n\t1,\n\t2,\n]\nlet y = f(\n\t1, 2)\nlet [\n\ta,\n\t...b\n] = x\n",
+	);
+}
+
+
+#[test]
+fn test_wrapping() {
+	assert_eq!(
+		format_width("let result = compute(first_argument, [ 1, 2, 3 ], @[ key: value ])\n", 40),
+		"let result = compute(\n\tfirst_argument,\n\t[ 1, 2, 3 ],\n\t@[ key: value ],\n)\n",
+	);
+
+	// Nested lists are split until they fit.
+	assert_eq!(
+		format_width("f([ 1, 2 ], @[ long_key: long_value, other_key: other_value ])\n", 30),
+		"f(\n\t[ 1, 2 ],\n\t@[\n\t\tlong_key: long_value,\n\t\tother_key: other_value,\n\t],\n)\n",
+	);
+}
+
+
+#[test]
+fn test_comments() {
+	assert_eq!(
+		format_default(
+			"# header   \nlet x = 1 # trailing\n  #[[ block\n  comment ]]#\nif x then\n# inside\nstd.print( #[[ inline ]]# x)\nend\n"
+		),
+		"# header\nlet x = 1 # trailing\n#[[ block\n  comment ]]#\nif x then\n\t# inside\n\tstd.print( #[[ inline ]]# x)\nend\n",
+	);
+}
+
+
+#[test]
+fn test_command_blocks() {
+	assert_eq!(
+		format_default(
+			"let out = ${echo  hello |tr a-z A-Z ;ls -l>/dev/null 2>&1}\n{\n  cat << 'input' > file ;\n    # comment\n  false ?\n  | grep x\n}\n&{sleep 1}\n"
+		),
+		"let out = ${ echo hello | tr a-z A-Z; ls -l >/dev/null 2>&1 }\n{\n\tcat << 'input' > file;\n\t# comment\n\tfalse?\n\t\t| grep x\n}\n&{ sleep 1 }\n",
+	);
+}
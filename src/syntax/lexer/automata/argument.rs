@@ -269,9 +269,9 @@ where
 			(&Self { braces: Some(true), .. }, Some(b'}')) => produce!(true),
 
 			// Head character:
-			(&Self { start_offset: None, .. }, Some(c)) => {
+			(&Self { start_offset: None, .. }, Some(_)) => {
 				self.start_offset = Some(cursor.offset());
-				if !c.is_word_start() {
+				if !cursor.is_word_start() {
 					self.error = true;
 				}
 				if self.braces == None {
@@ -282,8 +282,8 @@ where
 			}
 
 			// Tail character
-			(&Self { start_offset: Some(_), braces: Some(false), .. }, Some(c)) => {
-				if !c.is_word() {
+			(&Self { start_offset: Some(_), braces: Some(false), .. }, Some(_)) => {
+				if !cursor.is_word() {
 					produce!(false)
 				} else {
 					Transition::step(self)
@@ -291,8 +291,8 @@ where
 			}
 
 			// Tail character when braces
-			(&Self { start_offset: Some(_), .. }, Some(c)) => {
-				if !c.is_word() {
+			(&Self { start_offset: Some(_), .. }, Some(_)) => {
+				if !cursor.is_word() {
 					self.error = true;
 				}
 
@@ -25,7 +25,7 @@ impl Command {
 			Some(c) if c.is_ascii_whitespace() => Transition::step(self),
 
 			// Comment.
-			Some(b'#') => Transition::step(Comment::from(self)),
+			Some(b'#') => Transition::step(Comment::at(self, cursor)),
 
 			// Close command block.
 			Some(b'}') => Transition::produce(
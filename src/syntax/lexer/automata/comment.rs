@@ -1,10 +1,49 @@
-use super::{Command, Cursor, Root, State, Transition};
+use super::{Command, Cursor, Error, Root, SourcePos, State, Token, TokenKind, Transition};
 
 /// The state for lexing comments.
 /// This state is generic in the sense that it returns to the previous state once the
 /// entire comment is consumed.
+/// Line comments start with `#` and go until the end of the line. Block comments are
+/// delimited by `#[[` and `]]#`, may span multiple lines, and may be nested.
+/// A comment token is produced once the comment is consumed, which the automata discards
+/// unless comments were requested.
 #[derive(Debug)]
-pub(super) struct Comment<S>(S);
+pub(super) struct Comment<S> {
+	/// The state to return to.
+	state: S,
+	/// The kind of comment being scanned.
+	kind: CommentKind,
+	/// The offset of the opening `#`.
+	offset: usize,
+	/// The position of the opening `#`.
+	pos: SourcePos,
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum CommentKind {
+	/// The start of the comment, with the number of opening brackets scanned so far.
+	Start(u8),
+	/// A line comment.
+	Line,
+	/// A block comment, with the nesting depth and the delimiter being scanned.
+	Block(usize, Delimiter),
+}
+
+
+/// A partially scanned block comment delimiter.
+#[derive(Debug, Clone, Copy)]
+enum Delimiter {
+	None,
+	/// `#`
+	Hash,
+	/// `#[`
+	HashBracket,
+	/// `]`
+	Bracket,
+	/// `]]`
+	BracketBracket,
+}
 
 
 impl<S> Comment<S>
@@ -12,21 +51,85 @@ where
 	S: Into<State>,
 	State: From<Self>,
 {
-	pub fn visit(self, cursor: &Cursor) -> Transition {
-		match cursor.peek() {
-			// Newline marks the end of the comment.
-			Some(b'\n') => Transition::resume(self.0),
+	/// Begin a comment at the `#`.
+	pub fn at(state: S, cursor: &Cursor) -> Self {
+		Self {
+			state,
+			kind: CommentKind::Start(0),
+			offset: cursor.offset(),
+			pos: cursor.pos(),
+		}
+	}
+
+
+	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+		match (self.kind, cursor.peek()) {
+			(CommentKind::Start(0), Some(b'[')) => {
+				self.kind = CommentKind::Start(1);
+				Transition::step(self)
+			}
+
+			(CommentKind::Start(1), Some(b'[')) => {
+				self.kind = CommentKind::Block(1, Delimiter::None);
+				Transition::step(self)
+			}
+
+			// Anything else means a line comment.
+			(CommentKind::Start(_), _) => {
+				self.kind = CommentKind::Line;
+				Transition::resume(self)
+			}
+
+			// Newline or EOF marks the end of a line comment.
+			(CommentKind::Line, Some(b'\n') | None) => {
+				let token = self.token(cursor, cursor.offset());
+				Transition::resume_produce(self.state, token)
+			}
 
 			// Otherwise, eat everything.
-			_ => Transition::step(self),
+			(CommentKind::Line, _) => Transition::step(self),
+
+			// EOF inside a block comment is an error.
+			(CommentKind::Block(..), None) => {
+				let pos = self.pos;
+				Transition::error(self.state, Error::unterminated_block_comment(pos))
+			}
+
+			(CommentKind::Block(depth, delimiter), Some(c)) => {
+				self.kind = match (delimiter, c) {
+					// Nested comment.
+					(Delimiter::HashBracket, b'[') => CommentKind::Block(depth + 1, Delimiter::None),
+
+					// End of comment.
+					(Delimiter::BracketBracket, b'#') if depth == 1 => {
+						let token = self.token(cursor, cursor.offset() + 1);
+						return Transition::produce(self.state, token)
+					}
+
+					(Delimiter::BracketBracket, b'#') => CommentKind::Block(depth - 1, Delimiter::None),
+
+					(Delimiter::Hash, b'[') => CommentKind::Block(depth, Delimiter::HashBracket),
+					(Delimiter::Bracket | Delimiter::BracketBracket, b']') => {
+						CommentKind::Block(depth, Delimiter::BracketBracket)
+					}
+
+					(_, b'#') => CommentKind::Block(depth, Delimiter::Hash),
+					(_, b']') => CommentKind::Block(depth, Delimiter::Bracket),
+					_ => CommentKind::Block(depth, Delimiter::None),
+				};
+
+				Transition::step(self)
+			}
 		}
 	}
-}
 
 
-impl<S> From<S> for Comment<S> {
-	fn from(state: S) -> Self {
-		Self(state)
+	/// Produce the comment token, which ends at the given offset.
+	fn token(&self, cursor: &Cursor, end: usize) -> Token {
+		let text = &cursor.slice()[self.offset .. end];
+		let text = text.strip_suffix(b"\r").unwrap_or(text);
+
+		Token { kind: TokenKind::Comment(text.into()), pos: self.pos }
 	}
 }
 
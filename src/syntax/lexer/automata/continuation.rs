@@ -0,0 +1,41 @@
+use super::{Cursor, Error, Root, SourcePos, State, Transition};
+
+
+/// The state for lexing explicit line continuations, which are a backslash at the end of
+/// the line. Newlines are not significant in the language, so continuations are simply
+/// skipped, but they may make the intent of long expressions clearer.
+#[derive(Debug)]
+pub(super) struct LineContinuation {
+	/// Whether a carriage return has been scanned.
+	carriage_return: bool,
+	/// The position of the backslash.
+	pos: SourcePos,
+}
+
+
+impl LineContinuation {
+	pub fn at(cursor: &Cursor) -> Self {
+		Self { carriage_return: false, pos: cursor.pos() }
+	}
+
+
+	pub fn visit(self, cursor: &Cursor) -> Transition {
+		match cursor.peek() {
+			Some(b'\r') if !self.carriage_return => {
+				Transition::step(Self { carriage_return: true, ..self })
+			}
+
+			Some(b'\n') => Transition::step(Root),
+
+			// The backslash must be the last character in the line.
+			_ => Transition::resume_error(Root, Error::invalid_line_continuation(self.pos)),
+		}
+	}
+}
+
+
+impl From<LineContinuation> for State {
+	fn from(state: LineContinuation) -> State {
+		Self::LineContinuation(state)
+	}
+}
@@ -1,6 +1,7 @@
 mod argument;
 mod command;
 mod comment;
+mod continuation;
 mod expansion;
 mod number;
 mod root;
@@ -13,9 +14,10 @@ use self::{
 	expansion::Expansion,
 	command::Command,
 	comment::Comment,
+	continuation::LineContinuation,
 	number::NumberLiteral,
 	root::Root,
-	string::{ByteLiteral, StringLiteral},
+	string::{ByteLiteral, RawStringLiteral, StringLiteral},
 	symbol::{CommandSymbol, Symbol},
 	word::Word,
 };
@@ -144,8 +146,10 @@ enum State {
 	NumberLiteral(NumberLiteral),
 	ByteLiteral(ByteLiteral),
 	StringLiteral(StringLiteral),
+	RawStringLiteral(RawStringLiteral),
 	Word(Word),
 	Symbol(Symbol),
+	LineContinuation(LineContinuation),
 
 	// Command block lexer states:
 	Command(Command),
@@ -172,15 +176,22 @@ impl Default for State {
 
 
 impl State {
-	pub fn visit(self, cursor: &Cursor, interner: &mut SymbolInterner) -> Transition {
+	pub fn visit(
+		self,
+		cursor: &Cursor,
+		interner: &mut SymbolInterner,
+		interpolations: &mut Vec<SourcePos>,
+	) -> Transition {
 		match self {
-			Self::Root(state) => state.visit(cursor),
+			Self::Root(state) => state.visit(cursor, interpolations),
 			Self::Comment(state) => state.visit(cursor),
 			Self::NumberLiteral(state) => state.visit(cursor),
 			Self::ByteLiteral(state) => state.visit(cursor),
-			Self::StringLiteral(state) => state.visit(cursor),
+			Self::StringLiteral(state) => state.visit(cursor, interner, interpolations),
+			Self::RawStringLiteral(state) => state.visit(cursor),
 			Self::Word(state) => state.visit(cursor, interner),
 			Self::Symbol(state) => state.visit(cursor),
+			Self::LineContinuation(state) => state.visit(cursor),
 
 			Self::Command(state) => state.visit(cursor),
 			Self::CommandComment(state) => state.visit(cursor),
@@ -206,12 +217,22 @@ pub(super) struct Automata<'a, 'b> {
 	state: State,
 	cursor: Cursor<'a>,
 	interner: &'b mut SymbolInterner,
+	/// The positions of the string interpolations currently open, innermost last.
+	interpolations: Vec<SourcePos>,
+	/// Whether to produce comment tokens.
+	comments: bool,
 }
 
 
 impl<'a, 'b> Automata<'a, 'b> {
-	pub fn new(cursor: Cursor<'a>, interner: &'b mut SymbolInterner) -> Self {
-		Self { state: State::default(), cursor, interner }
+	pub fn new(cursor: Cursor<'a>, interner: &'b mut SymbolInterner, comments: bool) -> Self {
+		Self { state: State::default(), cursor, interner, interpolations: Vec::new(), comments }
+	}
+
+
+	/// The offset of the cursor, which is just past the last produced token.
+	pub fn offset(&self) -> usize {
+		self.cursor.offset()
 	}
 }
 
@@ -224,7 +245,7 @@ impl<'a, 'b> Iterator for Automata<'a, 'b> {
 			// We must temporarily take the state so that we can consume it.
 			let state = std::mem::take(&mut self.state);
 
-			let transition = state.visit(&self.cursor, self.interner);
+			let transition = state.visit(&self.cursor, self.interner, &mut self.interpolations);
 
 			self.state = transition.state;
 
@@ -233,8 +254,10 @@ impl<'a, 'b> Iterator for Automata<'a, 'b> {
 
 			transition.step.apply(&mut self.cursor);
 
-			if let Some(output) = transition.output {
-				return Some(output);
+			match transition.output {
+				Some(Ok(Token { kind: TokenKind::Comment(_), .. })) if !self.comments => (),
+				Some(output) => return Some(output),
+				None => (),
 			}
 
 			if eof {
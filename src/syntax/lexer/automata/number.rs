@@ -18,6 +18,10 @@ pub(super) struct NumberLiteral {
 	start_offset: usize,
 	consumed_decimal: Option<bool>,
 	consumed_exponent: Option<bool>,
+	/// The radix of prefixed integer literals (0x, 0o, 0b).
+	radix: Option<u32>,
+	/// The position of the previous character, if it was a digit separator.
+	separator: Option<SourcePos>,
 	pos: SourcePos,
 }
 
@@ -28,15 +32,54 @@ impl NumberLiteral {
 			start_offset: cursor.offset(),
 			consumed_decimal: None,
 			consumed_exponent: None,
+			radix: None,
+			separator: None,
 			pos: cursor.pos(),
 		}
 	}
 
 
 	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+		// A digit separator must be followed by a digit.
+		if let Some(pos) = self.separator.take() {
+			if !cursor.peek().is_some_and(|c| self.is_digit(c)) {
+				return Transition::error(Root, Error::invalid_digit_separator(pos));
+			}
+		}
+
 		let error = |error| Transition::error(Root, Error { error, pos: self.pos });
 
 		match (&self, cursor.peek()) {
+			// A digit separator must be preceded by a digit.
+			(_, Some(b'_')) => {
+				if self.follows_digit(cursor) {
+					self.separator = Some(cursor.pos());
+					Transition::step(self)
+				} else {
+					Transition::error(Root, Error::invalid_digit_separator(cursor.pos()))
+				}
+			}
+
+			// Radix prefix, which must directly follow a leading zero.
+			(&Self { radix: None, .. }, Some(c))
+				if cursor.offset() == self.start_offset + 1
+					&& cursor.slice()[self.start_offset] == b'0'
+					&& Self::prefix_radix(c).is_some() => {
+				self.radix = Self::prefix_radix(c);
+				Transition::step(self)
+			}
+
+			// Consume alphanumeric characters in prefixed literals, so that invalid digits are
+			// reported as part of the literal. These are validated when parsing.
+			(&Self { radix: Some(_), .. }, Some(value)) if value.is_ascii_alphanumeric() => {
+				Transition::step(self)
+			}
+
+			(&Self { radix: Some(radix), .. }, _) => match self.parse_radix(radix, cursor) {
+				Ok(token) => Transition::resume_produce(Root, token),
+				Err(error) => Transition::error(Root, error),
+			},
+
 			// There must be up to one dot, and it must precede the exponent.
 			(
 				&Self {
@@ -54,6 +97,13 @@ impl NumberLiteral {
 				Transition::step(self)
 			}
 
+			// The exponent may have a sign.
+			(&Self { consumed_exponent: Some(false), .. }, Some(b'+'))
+			| (&Self { consumed_exponent: Some(false), .. }, Some(b'-'))
+				if matches!(cursor.slice()[cursor.offset() - 1], b'e' | b'E') => {
+				Transition::step(self)
+			}
+
 			// Consume digits.
 			(_, Some(value)) if value.is_ascii_digit() => {
 				// If a dot or an exponent preceded, then set the according flag.
@@ -67,9 +117,15 @@ impl NumberLiteral {
 				Transition::step(self)
 			}
 
-			// A dot or an exponent must be followed by a digit.
-			(&Self { consumed_decimal: Some(false), .. }, value)
-			| (&Self { consumed_exponent: Some(false), .. }, value) => {
+			// An exponent must have digits. Don't consume the following character, as it may
+			// be the start of another token.
+			(&Self { consumed_exponent: Some(false), .. }, _) => {
+				let number = &cursor.slice()[self.start_offset .. cursor.offset()];
+				Transition::resume_error(Root, Error::invalid_number(number, self.pos))
+			}
+
+			// A dot must be followed by a digit.
+			(&Self { consumed_decimal: Some(false), .. }, value) => {
 				if let Some(value) = value {
 					error(ErrorKind::Unexpected(value))
 				} else {
@@ -93,7 +149,7 @@ impl NumberLiteral {
 		let literal = |literal| Ok(Token { kind: TokenKind::Literal(literal), pos: self.pos });
 
 		// There is no method in std to parse a number from a byte array.
-		let number_str = std::str::from_utf8(number)
+		let number_str = String::from_utf8(strip_separators(number))
 			.expect("number literals should be valid ascii, which should be valid utf8");
 
 		if self.is_float() {
@@ -110,6 +166,56 @@ impl NumberLiteral {
 	}
 
 
+	/// Parse the consumed characters of a prefixed integer literal.
+	fn parse_radix(&self, radix: u32, cursor: &Cursor) -> Result<Token, Error> {
+		let number = &cursor.slice()[self.start_offset .. cursor.offset()];
+		let digits = strip_separators(&number[2..]); // Skip the prefix.
+
+		let digits_str = std::str::from_utf8(&digits)
+			.expect("number literals should be valid ascii, which should be valid utf8");
+
+		// A prefix must be followed by digits.
+		if digits.is_empty() {
+			return Err(Error::invalid_number(number, self.pos));
+		}
+
+		match i64::from_str_radix(digits_str, radix) {
+			Ok(int) => Ok(Token { kind: TokenKind::Literal(Literal::Int(int)), pos: self.pos }),
+			Err(_) => Err(Error::invalid_number(number, self.pos)),
+		}
+	}
+
+
+	/// Check if a character is a digit of the literal. Prefixed literals may contain any
+	/// alphanumeric character, as digits are only validated when parsing.
+	fn is_digit(&self, c: u8) -> bool {
+		match self.radix {
+			Some(_) => c.is_ascii_alphanumeric(),
+			None => c.is_ascii_digit(),
+		}
+	}
+
+
+	/// Check if the previous character is a digit of the literal, excluding the radix prefix.
+	fn follows_digit(&self, cursor: &Cursor) -> bool {
+		let previous = cursor.offset() - 1;
+		let is_prefix = self.radix.is_some() && previous == self.start_offset + 1;
+
+		!is_prefix && self.is_digit(cursor.slice()[previous])
+	}
+
+
+	/// Get the radix for the character following a leading zero, if it is a prefix.
+	fn prefix_radix(c: u8) -> Option<u32> {
+		match c {
+			b'x' | b'X' => Some(16),
+			b'o' | b'O' => Some(8),
+			b'b' | b'B' => Some(2),
+			_ => None,
+		}
+	}
+
+
 	/// Check if the consumed characters constitue a float.
 	fn is_float(&self) -> bool {
 		self.consumed_decimal.is_some() || self.consumed_exponent.is_some()
@@ -117,6 +223,16 @@ impl NumberLiteral {
 }
 
 
+/// Remove the digit separators from a number literal.
+fn strip_separators(number: &[u8]) -> Vec<u8> {
+	number
+		.iter()
+		.copied()
+		.filter(|&c| c != b'_')
+		.collect()
+}
+
+
 impl From<NumberLiteral> for State {
 	fn from(state: NumberLiteral) -> State {
 		Self::NumberLiteral(state)
@@ -6,7 +6,9 @@ use super::{
 	Comment,
 	Cursor,
 	Error,
+	LineContinuation,
 	NumberLiteral,
+	SourcePos,
 	State,
 	StringLiteral,
 	Symbol,
@@ -23,17 +25,25 @@ pub(super) struct Root;
 
 
 impl Root {
-	pub fn visit(self, cursor: &Cursor) -> Transition {
+	pub fn visit(self, cursor: &Cursor, interpolations: &mut Vec<SourcePos>) -> Transition {
 		match cursor.peek() {
 			// Whitespace.
 			Some(c) if c.is_ascii_whitespace() => Transition::step(self),
 
+			// Line continuations.
+			Some(b'\\') => Transition::step(LineContinuation::at(cursor)),
+
 			// Comments.
-			Some(b'#') => Transition::step(Comment::from(self)),
+			Some(b'#') => Transition::step(Comment::at(self, cursor)),
 
 			// String literals.
 			Some(b'"') => Transition::step(StringLiteral::at(cursor)),
 
+			// End of string interpolation.
+			Some(b'}') if interpolations.pop().is_some() => {
+				Transition::step(StringLiteral::after_interpolation(cursor))
+			}
+
 			// Byte literals.
 			Some(b'\'') => Transition::step(ByteLiteral::at(cursor)),
 
@@ -41,7 +51,15 @@ impl Root {
 			Some(c) if c.is_ascii_digit() => Transition::step(NumberLiteral::at(cursor)),
 
 			// Identifier, keywords and word operators.
-			Some(c) if c.is_word_start() => Transition::resume(Word::at(cursor)),
+			Some(_) if cursor.is_word_start() => Transition::resume(Word::at(cursor)),
+
+			// Remaining bytes of a non-ascii character, which has already been reported.
+			Some(c) if c & 0xC0 == 0x80 && Self::follows_non_ascii(cursor) => Transition::step(self),
+
+			// Control and non-ascii characters.
+			Some(c) if !c.is_ascii_graphic() => {
+				Transition::error(self, Error::invalid_character(cursor.peek_utf8(), cursor.pos()))
+			}
 
 			// Symbols.
 			Some(c) => match SymbolChar::from_first(c) {
@@ -63,6 +81,14 @@ impl Root {
 			None => Transition::step(self),
 		}
 	}
+
+
+	/// Whether the previous byte is part of a non-ascii character.
+	fn follows_non_ascii(cursor: &Cursor) -> bool {
+		cursor.offset()
+			.checked_sub(1)
+			.is_some_and(|offset| !cursor.slice()[offset].is_ascii())
+	}
 }
 
 
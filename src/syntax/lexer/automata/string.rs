@@ -1,4 +1,16 @@
-use super::{Cursor, Error, Literal, Root, SourcePos, State, Token, TokenKind, Transition};
+use super::{
+	word::{self, IsWord},
+	Cursor,
+	Error,
+	Literal,
+	Root,
+	SourcePos,
+	State,
+	SymbolInterner,
+	Token,
+	TokenKind,
+	Transition,
+};
 
 
 /// The state for lexing byte literals.
@@ -6,8 +18,10 @@ use super::{Cursor, Error, Literal, Root, SourcePos, State, Token, TokenKind, Tr
 pub(super) struct ByteLiteral {
 	/// The parsed value, if any.
 	value: Option<u8>,
-	/// The position of the current escape sequence, if any.
-	escaping: Option<(usize, SourcePos)>,
+	/// Whether more than a single byte has been scanned.
+	too_long: bool,
+	/// The current escape sequence, if any.
+	escaping: Option<Escape>,
 	/// The position of the literal.
 	pos: SourcePos,
 }
@@ -15,7 +29,7 @@ pub(super) struct ByteLiteral {
 
 impl ByteLiteral {
 	pub fn at(cursor: &Cursor) -> Self {
-		Self { value: None, escaping: None, pos: cursor.pos() }
+		Self { value: None, too_long: false, escaping: None, pos: cursor.pos() }
 	}
 
 
@@ -24,6 +38,40 @@ impl ByteLiteral {
 			// EOF while scanning a literal is always an error.
 			(_, None) => Transition::error(Root, Error::unexpected_eof(cursor.pos())),
 
+			// Escaped character.
+			(&Self { escaping: Some(_), .. }, Some(value)) => {
+				let escape = self.escaping.take().expect("escape sequence missing");
+
+				match escape.visit(value, cursor) {
+					EscapeStep::Pending(escape) => {
+						self.escaping = Some(escape);
+						Transition::step(self)
+					}
+
+					EscapeStep::Done(c) => self.push(c),
+
+					EscapeStep::Char(c, _) if c.is_ascii() => self.push(c as u8),
+
+					// Unicode escapes for characters that are encoded in multiple bytes.
+					EscapeStep::Char(..) => {
+						self.value.get_or_insert(b'\0');
+						self.push_extra()
+					}
+
+					EscapeStep::Invalid { error, consumed } => {
+						// Use a placeholder to produce a valid literal after reporting the error. This
+						// won't get to be actually used, because the program won't be interpreted after
+						// parsing.
+						self.value.get_or_insert(b'\0');
+						if consumed {
+							Transition::error(self, error)
+						} else {
+							Transition::resume_error(self, error)
+						}
+					}
+				}
+			}
+
 			// Closing quote.
 			(&Self { value: Some(c), .. }, Some(b'\'')) => Transition::produce(
 				Root,
@@ -33,32 +81,9 @@ impl ByteLiteral {
 				},
 			),
 
-			// If a value has already been scanned (including incorrect escape sequences). There
-			// should be no further characters except for the closing quote.
-			(&Self { value: Some(_), .. }, Some(c)) => {
-				Transition::error(self, Error::unexpected(c, cursor.pos()))
-			}
-
-			// Escaped character.
-			(&Self { escaping: Some((offset, pos)), .. }, Some(value)) => {
-				self.escaping = None;
-
-				if let Some(c) = validate_escape(value) {
-					self.value = Some(c);
-					Transition::step(self)
-				} else {
-					// Use a placeholder to produce a valid literal after reporting the error. This
-					// won't get to be actually used, because the program won't be interpreted after
-					// parsing.
-					self.value = Some(b'\0');
-					let escape_sequence = &cursor.slice()[offset ..= cursor.offset()];
-					Transition::error(self, Error::invalid_escape_sequence(escape_sequence, pos))
-				}
-			}
-
 			// Begin of escape sequence.
 			(_, Some(b'\\')) => {
-				self.escaping = Some((cursor.offset(), cursor.pos()));
+				self.escaping = Some(Escape::at(cursor));
 				Transition::step(self)
 			}
 
@@ -68,10 +93,31 @@ impl ByteLiteral {
 			}
 
 			// Ordinary character.
-			(_, Some(value)) => {
-				self.value = Some(value);
-				Transition::step(self)
-			}
+			(_, Some(value)) => self.push(value),
+		}
+	}
+
+
+	/// Push a scanned byte. There should be only a single byte in the literal, so an error is
+	/// produced for the second byte, and further bytes are ignored.
+	fn push(mut self, value: u8) -> Transition {
+		if self.value.is_none() {
+			self.value = Some(value);
+			Transition::step(self)
+		} else {
+			self.push_extra()
+		}
+	}
+
+
+	/// Report that the literal has more than one byte, unless it has already been reported.
+	fn push_extra(mut self) -> Transition {
+		if self.too_long {
+			Transition::step(self)
+		} else {
+			self.too_long = true;
+			let pos = self.pos;
+			Transition::error(self, Error::byte_literal_too_long(pos))
 		}
 	}
 }
@@ -85,59 +131,163 @@ impl From<ByteLiteral> for State {
 
 
 /// The state for lexing string literals.
+/// Strings may contain interpolations, such as "${expr}" or "$identifier". For such, each
+/// literal piece preceding an interpolation is produced as a separate token, followed by
+/// the tokens of the embedded expression. The last piece is produced as an
+/// InterpolationEnd token.
 #[derive(Debug)]
 pub(super) struct StringLiteral {
 	/// The parsed bytes, if any.
 	value: Vec<u8>,
-	/// The position of the current escape sequence, if any.
-	escaping: Option<(usize, SourcePos)>,
-	/// The position of the literal.
+	/// The current escape sequence, if any.
+	escaping: Option<Escape>,
+	/// The current interpolation, if any.
+	dollar: Option<Dollar>,
+	/// Whether an interpolation has been produced.
+	interpolated: bool,
+	/// The position of the literal, or of the current piece if interpolated.
 	pos: SourcePos,
 }
 
 
+/// An interpolation being scanned in a string literal.
+#[derive(Debug, Clone, Copy)]
+enum Dollar {
+	/// Only the dollar, with its position.
+	Start(SourcePos),
+	/// An identifier, with its offset and position.
+	Identifier(usize, SourcePos),
+}
+
+
 impl StringLiteral {
 	pub fn at(cursor: &Cursor) -> Self {
 		Self {
 			value: Vec::with_capacity(8), // We expect most literals to not be empty.
 			escaping: None,
+			dollar: None,
+			interpolated: false,
 			pos: cursor.pos(),
 		}
 	}
 
 
-	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+	/// Resume a string literal after the closing brace of an interpolation.
+	pub fn after_interpolation(cursor: &Cursor) -> Self {
+		Self {
+			value: Vec::new(),
+			escaping: None,
+			dollar: None,
+			interpolated: true,
+			pos: cursor.pos(),
+		}
+	}
+
+
+	pub fn visit(
+		mut self,
+		cursor: &Cursor,
+		interner: &mut SymbolInterner,
+		interpolations: &mut Vec<SourcePos>,
+	) -> Transition {
 		match (&self, cursor.peek()) {
-			// EOF while scanning a literal is always an error.
-			(_, None) => Transition::error(Root, Error::unexpected_eof(cursor.pos())),
+			// Interpolated identifier.
+			(&Self { dollar: Some(Dollar::Identifier(offset, pos)), .. }, value) => match value {
+				Some(_) if cursor.is_word() => Transition::step(self),
+
+				_ => {
+					self.dollar = None;
+					let word = &cursor.slice()[offset .. cursor.offset()];
+					let token = Token { kind: word::to_token(word, interner), pos };
+
+					Transition::resume_produce(self, token)
+				}
+			},
+
+			// EOF while scanning a literal is always an error. Inside an interpolation, the
+			// literal is most likely a closing quote which is missing the closing brace, so
+			// the interpolation is reported instead.
+			(_, None) => match interpolations.last() {
+				Some(pos) => Transition::error(Root, Error::unterminated_interpolation(*pos)),
+				None => Transition::error(Root, Error::unterminated_string(self.pos)),
+			},
+
+			// Interpolation.
+			(&Self { dollar: Some(Dollar::Start(pos)), .. }, Some(value)) => match value {
+				b'{' => {
+					interpolations.push(pos);
+					Transition::produce(Root, self.piece())
+				}
+
+				_ if cursor.is_word_start() => {
+					let token = self.piece();
+					let state = Self {
+						dollar: Some(Dollar::Identifier(cursor.offset(), cursor.pos())),
+						..Self::after_interpolation(cursor)
+					};
+
+					Transition::resume_produce(state, token)
+				}
+
+				// A dollar which is not followed by an interpolation is a literal.
+				_ => {
+					self.dollar = None;
+					self.value.push(b'$');
+					Transition::resume(self)
+				}
+			},
 
 			// Escaped character.
-			(&Self { escaping: Some((offset, pos)), .. }, Some(value)) => {
-				self.escaping = None;
-
-				if let Some(c) = validate_escape(value) {
-					self.value.push(c);
-					Transition::step(self)
-				} else {
-					let escape_sequence = &cursor.slice()[offset ..= cursor.offset()];
-					Transition::error(self, Error::invalid_escape_sequence(escape_sequence, pos))
+			(&Self { escaping: Some(_), .. }, Some(value)) => {
+				let escape = self.escaping.take().expect("escape sequence missing");
+
+				match escape.visit(value, cursor) {
+					EscapeStep::Pending(escape) => {
+						self.escaping = Some(escape);
+						Transition::step(self)
+					}
+
+					EscapeStep::Done(c) => {
+						self.value.push(c);
+						Transition::step(self)
+					}
+
+					EscapeStep::Char(c, _) => {
+						let mut buffer = [0; 4];
+						self.value.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+						Transition::step(self)
+					}
+
+					EscapeStep::Invalid { error, consumed: true } => Transition::error(self, error),
+					EscapeStep::Invalid { error, consumed: false } => Transition::resume_error(self, error),
 				}
 			}
 
 			// Begin of escape sequence.
 			(_, Some(b'\\')) => {
-				self.escaping = Some((cursor.offset(), cursor.pos()));
+				self.escaping = Some(Escape::at(cursor));
+				Transition::step(self)
+			}
+
+			// Begin of interpolation.
+			(_, Some(b'$')) => {
+				self.dollar = Some(Dollar::Start(cursor.pos()));
 				Transition::step(self)
 			}
 
 			// Closing quote.
-			(_, Some(b'\"')) => Transition::produce(
-				Root,
-				Token {
-					kind: TokenKind::Literal(Literal::String(self.value.into_boxed_slice())),
-					pos: self.pos,
-				},
-			),
+			(_, Some(b'\"')) => {
+				let value = self.value.into_boxed_slice();
+
+				let kind =
+					if self.interpolated {
+						TokenKind::InterpolationEnd(value)
+					} else {
+						TokenKind::Literal(Literal::String(value))
+					};
+
+				Transition::produce(Root, Token { kind, pos: self.pos })
+			}
 
 			// Ordinary character.
 			(_, Some(value)) => {
@@ -146,6 +296,15 @@ impl StringLiteral {
 			}
 		}
 	}
+
+
+	/// Produce the literal piece preceding an interpolation.
+	fn piece(self) -> Token {
+		Token {
+			kind: TokenKind::Interpolation(self.value.into_boxed_slice()),
+			pos: self.pos,
+		}
+	}
 }
 
 
@@ -156,13 +315,219 @@ impl From<StringLiteral> for State {
 }
 
 
+/// The state for lexing raw string literals, such as r"..." or r#"..."#.
+/// Escape sequences are not interpreted, and the literal ends only at a quote followed by
+/// the same number of hashes used in the opening delimiter.
+#[derive(Debug)]
+pub(super) struct RawStringLiteral {
+	/// The number of hashes in the delimiter.
+	hashes: usize,
+	/// The offset of the first byte of the contents, once the opening quote has been scanned.
+	start: Option<usize>,
+	/// The offset of a candidate closing quote, and the number of hashes following it.
+	closing: Option<(usize, usize)>,
+	/// The position of the literal.
+	pos: SourcePos,
+}
+
+
+impl RawStringLiteral {
+	/// Begin a raw string at the character following the `r` prefix.
+	pub fn at(pos: SourcePos) -> Self {
+		Self { hashes: 0, start: None, closing: None, pos }
+	}
+
+
+	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+		match (&self, cursor.peek()) {
+			// Opening delimiter.
+			(&Self { start: None, .. }, Some(b'#')) => {
+				self.hashes += 1;
+				Transition::step(self)
+			}
+
+			(&Self { start: None, .. }, Some(b'"')) => {
+				self.start = Some(cursor.offset() + 1);
+				Transition::step(self)
+			}
+
+			(&Self { start: None, .. }, Some(c)) => {
+				Transition::resume_error(Root, Error::unexpected(c, cursor.pos()))
+			}
+
+			// Closing delimiter.
+			(&Self { start: Some(start), closing: Some((quote, hashes)), .. }, _)
+				if hashes == self.hashes => {
+				let value = &cursor.slice()[start .. quote];
+
+				Transition::resume_produce(
+					Root,
+					Token {
+						kind: TokenKind::Literal(Literal::String(value.into())),
+						pos: self.pos,
+					},
+				)
+			}
+
+			// EOF while scanning a literal is always an error.
+			(_, None) => Transition::error(Root, Error::unterminated_raw_string(self.pos)),
+
+			(&Self { closing: Some((quote, hashes)), .. }, Some(b'#')) => {
+				self.closing = Some((quote, hashes + 1));
+				Transition::step(self)
+			}
+
+			// Contents.
+			(_, Some(b'"')) => {
+				self.closing = Some((cursor.offset(), 0));
+				Transition::step(self)
+			}
+
+			(_, Some(_)) => {
+				self.closing = None;
+				Transition::step(self)
+			}
+		}
+	}
+}
+
+
+impl From<RawStringLiteral> for State {
+	fn from(state: RawStringLiteral) -> State {
+		Self::RawStringLiteral(state)
+	}
+}
+
+
+/// An escape sequence being scanned in a byte or string literal.
+#[derive(Debug)]
+struct Escape {
+	/// The offset of the backslash.
+	offset: usize,
+	/// The position of the backslash.
+	pos: SourcePos,
+	/// What has been scanned so far.
+	kind: EscapeKind,
+}
+
+
+#[derive(Debug)]
+enum EscapeKind {
+	/// Only the backslash.
+	Start,
+	/// A hex escape (\xNN), with the number of digits scanned so far and their value.
+	Hex(u8, u8),
+	/// A unicode escape, before the opening brace.
+	UnicodeStart,
+	/// A unicode escape (\u{N...}), with the number of digits scanned so far and their value.
+	Unicode(u8, u32),
+}
+
+
+/// The outcome of scanning a character in an escape sequence.
+#[derive(Debug)]
+enum EscapeStep {
+	/// The sequence requires more characters.
+	Pending(Escape),
+	/// The sequence is complete, producing the given byte.
+	Done(u8),
+	/// The sequence is a complete unicode escape, producing the given char.
+	Char(char, Escape),
+	/// The sequence is invalid. If the current character is not part of the sequence, it
+	/// has not been consumed.
+	Invalid { error: Error, consumed: bool },
+}
+
+
+impl Escape {
+	/// The maximum amount of digits in a unicode escape.
+	const MAX_UNICODE_DIGITS: u8 = 6;
+
+
+	/// Begin an escape sequence at the backslash.
+	fn at(cursor: &Cursor) -> Self {
+		Self { offset: cursor.offset(), pos: cursor.pos(), kind: EscapeKind::Start }
+	}
+
+
+	/// Scan the next character of the escape sequence.
+	fn visit(self, value: u8, cursor: &Cursor) -> EscapeStep {
+		let digit = (value as char).to_digit(16);
+
+		match self.kind {
+			EscapeKind::Start => match value {
+				b'x' => EscapeStep::Pending(Self { kind: EscapeKind::Hex(0, 0), ..self }),
+				b'u' => EscapeStep::Pending(Self { kind: EscapeKind::UnicodeStart, ..self }),
+				_ => match validate_escape(value) {
+					Some(c) => EscapeStep::Done(c),
+					None => self.invalid(cursor, true),
+				},
+			},
+
+			EscapeKind::Hex(digits, acc) => match digit {
+				Some(digit) if digits == 1 => EscapeStep::Done(acc * 16 + digit as u8),
+				Some(digit) => EscapeStep::Pending(Self { kind: EscapeKind::Hex(1, digit as u8), ..self }),
+				// Hex escapes require exactly two digits.
+				None => self.invalid(cursor, false),
+			},
+
+			EscapeKind::UnicodeStart => match value {
+				b'{' => EscapeStep::Pending(Self { kind: EscapeKind::Unicode(0, 0), ..self }),
+				_ => self.invalid(cursor, false),
+			},
+
+			EscapeKind::Unicode(digits, acc) => match (value, digit) {
+				(_, Some(digit)) if digits < Self::MAX_UNICODE_DIGITS => EscapeStep::Pending(
+					Self { kind: EscapeKind::Unicode(digits + 1, acc * 16 + digit), ..self }
+				),
+
+				(b'}', _) if digits == 0 => self.invalid(cursor, true),
+
+				(b'}', _) => match char::from_u32(acc) {
+					Some(c) => EscapeStep::Char(c, self),
+					None => EscapeStep::Invalid {
+						error: Error::invalid_unicode_escape(
+							&cursor.slice()[self.offset ..= cursor.offset()],
+							self.pos,
+						),
+						consumed: true,
+					},
+				},
+
+				_ => EscapeStep::Invalid {
+					error: Error::unterminated_unicode_escape(
+						&cursor.slice()[self.offset .. cursor.offset()],
+						self.pos,
+					),
+					consumed: false,
+				},
+			},
+		}
+	}
+
+
+	/// Produce an invalid escape sequence error, optionally including the current character
+	/// in the sequence.
+	fn invalid(self, cursor: &Cursor, consumed: bool) -> EscapeStep {
+		let end = if consumed { cursor.offset() + 1 } else { cursor.offset() };
+
+		EscapeStep::Invalid {
+			error: Error::invalid_escape_sequence(&cursor.slice()[self.offset .. end], self.pos),
+			consumed,
+		}
+	}
+}
+
+
 /// Check if a escape sequence is valid, producing the correspondent byte if so.
 fn validate_escape(sequence: u8) -> Option<u8> {
 	match sequence {
 		b'"' => Some(b'"'),
 		b'\'' => Some(b'\''),
+		b'$' => Some(b'$'),
 		b'n' => Some(b'\n'),
 		b't' => Some(b'\t'),
+		b'r' => Some(b'\r'),
 		b'0' => Some(b'\0'),
 		b'\\' => Some(b'\\'),
 		_ => None,
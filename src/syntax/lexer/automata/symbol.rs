@@ -13,23 +13,26 @@ use super::{
 };
 
 
-/// The state for lexing two-character symbols.
+/// The state for lexing two-character symbols, and the ellipsis.
 #[derive(Debug)]
 pub(super) struct Symbol {
 	first: u8,
+	/// Whether a second dot has been scanned, for the ellipsis.
+	dots: bool,
 	pos: SourcePos,
 }
 
 
 impl Symbol {
 	pub fn from_first(first: u8, cursor: &Cursor) -> Self {
-		Self { first, pos: cursor.pos() }
+		Self { first, dots: false, pos: cursor.pos() }
 	}
 
 
 	pub fn visit(self, cursor: &Cursor) -> Transition {
-		let unexpected = |input| Transition::resume_error(Root, Error::unexpected(input, self.pos));
-		let token = |token| Token { kind: token, pos: self.pos };
+		let pos = self.pos;
+		let unexpected = |input| Transition::resume_error(Root, Error::unexpected(input, pos));
+		let token = |token| Token { kind: token, pos };
 		let operator = |op| token(TokenKind::Operator(op));
 
 		let skip_produce = |output| Transition::resume_produce(Root, output);
@@ -44,6 +47,12 @@ impl Symbol {
 			(b'+', Some(b'+')) => Transition::produce(Root, operator(Operator::Concat)),
 			(b'+', _) => skip_produce(operator(Operator::Plus)),
 
+			(b'*', Some(b'*')) => Transition::produce(Root, operator(Operator::Pow)),
+			(b'*', _) => skip_produce(operator(Operator::Times)),
+
+			(b'/', Some(b'/')) => Transition::produce(Root, operator(Operator::FloorDiv)),
+			(b'/', _) => skip_produce(operator(Operator::Div)),
+
 			(b'=', Some(b'=')) => Transition::produce(Root, operator(Operator::Equals)),
 			(b'=', _) => skip_produce(operator(Operator::Assign)),
 
@@ -59,6 +68,14 @@ impl Symbol {
 			(b'&', Some(b'{')) => Transition::produce(Command, token(TokenKind::AsyncCommand)),
 			(b'&', _) => unexpected(self.first),
 
+			(b'|', Some(b'>')) => Transition::produce(Root, operator(Operator::Pipe)),
+			(b'|', _) => unexpected(self.first),
+
+			(b'.', Some(b'.')) if !self.dots => Transition::step(Self { dots: true, ..self }),
+			(b'.', Some(b'.')) => Transition::produce(Root, token(TokenKind::Ellipsis)),
+			(b'.', _) if self.dots => unexpected(b'.'),
+			(b'.', _) => skip_produce(operator(Operator::Dot)),
+
 			// We must have covered all possibilites for the first character. The peeked
 			// character is wildcarded, which will cover everthing including EOF (None).
 			_ => unreachable!("invalid first character in symbol state"),
@@ -145,13 +162,11 @@ impl SymbolChar {
 		match first {
 			// Single character.
 			b'-' => operator(Operator::Minus),
-			b'*' => operator(Operator::Times),
-			b'/' => operator(Operator::Div),
 			b'%' => operator(Operator::Mod),
-			b'.' => operator(Operator::Dot),
 			b'?' => operator(Operator::Try),
 			b':' => token(TokenKind::Colon),
 			b',' => token(TokenKind::Comma),
+			b';' => token(TokenKind::Semicolon),
 			b'(' => token(TokenKind::OpenParens),
 			b')' => token(TokenKind::CloseParens),
 			b'[' => token(TokenKind::OpenBracket),
@@ -162,11 +177,15 @@ impl SymbolChar {
 			b'>' => double(first),
 			b'<' => double(first),
 			b'+' => double(first),
+			b'*' => double(first),
+			b'/' => double(first),
 			b'=' => double(first),
 			b'!' => double(first),
 			b'@' => double(first),
 			b'$' => double(first),
 			b'&' => double(first),
+			b'.' => double(first),
+			b'|' => double(first),
 
 			// Not a symbol character:
 			_ => SymbolChar::None,
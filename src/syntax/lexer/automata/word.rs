@@ -1,8 +1,11 @@
+use unicode_xid::UnicodeXID;
+
 use super::{
 	Cursor,
 	Keyword,
 	Literal,
 	Operator,
+	RawStringLiteral,
 	Root,
 	SourcePos,
 	State,
@@ -31,8 +34,14 @@ impl Word {
 		// We don't need to check if the first character is a number here, because the Root
 		// state will only transition to this state if that is the case.
 		match cursor.peek() {
+			// Raw string prefix.
+			Some(b'"') | Some(b'#') if cursor.offset() == self.start_offset + 1
+				&& cursor.slice()[self.start_offset] == b'r' => {
+				Transition::resume(RawStringLiteral::at(self.pos))
+			}
+
 			// Word character.
-			Some(c) if c.is_word() => Transition::step(self),
+			Some(_) if cursor.is_word() => Transition::step(self),
 
 			// If we visit EOF or a non-identifier character, we should just produce.
 			_ => {
@@ -90,6 +99,7 @@ pub fn to_token(word: &[u8], interner: &mut SymbolInterner) -> TokenKind {
 
 
 /// Helper trait for checking if a character is a valid word constituent.
+/// Besides ascii, words may contain unicode characters as specified by UAX #31.
 pub trait IsWord {
 	fn is_word_start(&self) -> bool;
 	fn is_word(&self) -> bool;
@@ -105,3 +115,30 @@ impl IsWord for u8 {
 		self.is_ascii_alphanumeric() || *self == b'_'
 	}
 }
+
+
+/// Checks the character at the cursor, which may span multiple bytes.
+impl<'a> IsWord for Cursor<'a> {
+	fn is_word_start(&self) -> bool {
+		match self.peek() {
+			Some(c) if c.is_ascii() => c.is_word_start(),
+			Some(_) => self.peek_char().is_some_and(UnicodeXID::is_xid_start),
+			None => false,
+		}
+	}
+
+	fn is_word(&self) -> bool {
+		match self.peek() {
+			Some(c) if c.is_ascii() => c.is_word(),
+
+			// The remaining bytes of a character, which has already been accepted.
+			Some(c) if c & 0xC0 == 0x80 => self
+				.offset()
+				.checked_sub(1)
+				.is_some_and(|offset| !self.slice()[offset].is_ascii()),
+
+			Some(_) => self.peek_char().is_some_and(UnicodeXID::is_xid_continue),
+			None => false,
+		}
+	}
+}
@@ -36,16 +36,50 @@ impl<'a> Cursor<'a> {
 	}
 
 
+	/// The bytes of the UTF-8 sequence at the cursor, or a single byte if the sequence is
+	/// invalid. Empty if at EOF.
+	pub fn peek_utf8(&self) -> &'a [u8] {
+		let input = &self.input[self.offset ..];
+
+		let len = match input.first() {
+			Some(0xC0 ..= 0xDF) => 2,
+			Some(0xE0 ..= 0xEF) => 3,
+			Some(0xF0 ..= 0xF7) => 4,
+			Some(_) => 1,
+			None => return input,
+		};
+
+		match input.get(.. len) {
+			Some(bytes) if std::str::from_utf8(bytes).is_ok() => bytes,
+			_ => &input[.. 1],
+		}
+	}
+
+
+	/// The character at the cursor, if the input is valid UTF-8.
+	pub fn peek_char(&self) -> Option<char> {
+		std::str::from_utf8(self.peek_utf8())
+			.ok()?
+			.chars()
+			.next()
+	}
+
+
 	pub fn step(&mut self) {
 		if self.is_eof() {
 			return;
 		}
 
-		if self.input[self.offset] == b'\n' {
-			self.pos.line += 1;
-			self.pos.column = 0;
-		} else {
-			self.pos.column += 1;
+		match self.input[self.offset] {
+			b'\n' => {
+				self.pos.line += 1;
+				self.pos.column = 1;
+			}
+
+			// UTF-8 continuation bytes share the column of their character.
+			_ if self.input.get(self.offset + 1).is_some_and(|&byte| byte & 0xC0 == 0x80) => (),
+
+			_ => self.pos.column += 1,
 		}
 
 		self.offset += 1;
@@ -74,7 +108,7 @@ impl<'a> From<&'a Source> for Cursor<'a> {
 		Self {
 			input: &source.contents,
 			offset: 0,
-			pos: SourcePos { line: 1, column: 0, path: source.path }
+			pos: SourcePos { line: 1, column: 1, path: source.path }
 		}
 	}
 }
@@ -10,10 +10,24 @@ impl std::fmt::Display for ErrorKind {
 		match self {
 			Self::UnexpectedEof => "unexpected end of file".fmt(f)?,
 
-			Self::Unexpected(value) => write!(f, "unexpected '{}'", (*value as char).escape_debug())?,
+			Self::Unexpected(value) if value.is_ascii_graphic() => write!(f, "unexpected '{}'", *value as char)?,
+
+			Self::Unexpected(value) => write!(f, "unexpected byte 0x{:02x}", value)?,
+
+			Self::InvalidCharacter(bytes) => match std::str::from_utf8(bytes).map(|c| c.chars().next()) {
+				Ok(Some(c)) if !fmt::is_unprintable(c) => write!(f, "invalid character '{}'", c)?,
+				Ok(Some(c)) if c.is_ascii() => write!(f, "invalid character 0x{:02x}", c as u32)?,
+				Ok(Some(c)) => write!(f, "invalid character U+{:04X}", c as u32)?,
+				_ => write!(f, "invalid byte 0x{:02x}", bytes.first().copied().unwrap_or_default())?,
+			},
 
 			Self::EmptyByteLiteral => "empty char literal".fmt(f)?,
 
+			Self::ByteLiteralTooLong => {
+				"char literal must contain a single byte, use a string literal for multiple bytes or \
+				 non-ascii characters".fmt(f)?
+			}
+
 			Self::InvalidEscapeSequence(sequence) => {
 				write!(
 					f,
@@ -22,10 +36,38 @@ impl std::fmt::Display for ErrorKind {
 				)?;
 			}
 
+			Self::InvalidUnicodeEscape(sequence) => {
+				write!(
+					f,
+					"invalid unicode escape '{}', not a unicode scalar value",
+					String::from_utf8_lossy(sequence)
+				)?;
+			}
+
+			Self::UnterminatedUnicodeEscape(sequence) => {
+				write!(
+					f,
+					"unterminated unicode escape '{}', expected '}}'",
+					String::from_utf8_lossy(sequence)
+				)?;
+			}
+
+			Self::UnterminatedString => "unterminated string literal".fmt(f)?,
+
+			Self::UnterminatedInterpolation => "unterminated string interpolation, expected '}'".fmt(f)?,
+
+			Self::UnterminatedRawString => "unterminated raw string literal".fmt(f)?,
+
+			Self::UnterminatedBlockComment => "unterminated block comment".fmt(f)?,
+
+			Self::InvalidDigitSeparator => "digit separator '_' must be between digits".fmt(f)?,
+
 			Self::InvalidNumber(number) => {
 				write!(f, "invalid number '{}'", String::from_utf8_lossy(number))?;
 			}
 
+			Self::InvalidLineContinuation => "line continuation '\\' must be at the end of the line".fmt(f)?,
+
 			Self::InvalidIdentifier(ident) => {
 				write!(f, "invalid identifier '{}'", String::from_utf8_lossy(ident))?;
 			}
@@ -40,13 +82,14 @@ impl<'a> Display<'a> for Error {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		write!(f, "{} - {}.", fmt::Show(self.pos, context), self.error)
+		write!(f, "{} - {}.", fmt::Show(self.pos, context), self.error)?;
+		self.pos.snippet().fmt(f, context)
 	}
 }
 
 
 impl std::fmt::Display for Error {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		write!(f, "line {}, column {} - {}.", self.pos.line, self.pos.column, self.error)
+		write!(f, "{}:{} - {}.", self.pos.line, self.pos.column, self.error)
 	}
 }
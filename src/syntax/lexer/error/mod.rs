@@ -10,17 +10,64 @@ pub enum ErrorKind {
 	UnexpectedEof,
 	/// Unexpected character.
 	Unexpected(u8),
+	/// Character which is never valid outside of literals and comments, such as control
+	/// characters and non-ascii characters. Contains the bytes of the UTF-8 sequence, or a
+	/// single byte if the sequence is invalid.
+	InvalidCharacter(Box<[u8]>),
 	/// Empty byte literal ('').
 	EmptyByteLiteral,
+	/// Byte literal with more than a single byte, such as 'ab' or 'é'.
+	ByteLiteralTooLong,
 	/// Invalid escape sequence in byte literal, string literal, or argument literal.
 	InvalidEscapeSequence(Box<[u8]>),
+	/// Unicode escape sequence (\u{...}) which is not a valid unicode scalar value.
+	InvalidUnicodeEscape(Box<[u8]>),
+	/// Unicode escape sequence (\u{...}) missing the closing brace.
+	UnterminatedUnicodeEscape(Box<[u8]>),
+	/// String literal missing the closing quote.
+	UnterminatedString,
+	/// String interpolation (${...}) missing the closing brace.
+	UnterminatedInterpolation,
+	/// Raw string literal missing the closing delimiter.
+	UnterminatedRawString,
+	/// Block comment without a matching closing delimiter.
+	UnterminatedBlockComment,
+	/// Digit separator (_) which is not between digits in a number literal.
+	InvalidDigitSeparator,
 	/// Invalid number literal, both integer and floating point.
 	InvalidNumber(Box<[u8]>),
+	/// Backslash which is not at the end of the line.
+	InvalidLineContinuation,
 	/// Invalid identifier, only possible in dollar braces (${}).
 	InvalidIdentifier(Box<[u8]>),
 }
 
 
+impl ErrorKind {
+	/// A stable identifier for the kind of error.
+	pub fn code(&self) -> &'static str {
+		match self {
+			Self::UnexpectedEof => "unexpected-eof",
+			Self::Unexpected(_) => "unexpected-character",
+			Self::InvalidCharacter(_) => "invalid-character",
+			Self::EmptyByteLiteral => "empty-char-literal",
+			Self::ByteLiteralTooLong => "char-literal-too-long",
+			Self::InvalidEscapeSequence(_) => "invalid-escape-sequence",
+			Self::InvalidUnicodeEscape(_) => "invalid-unicode-escape",
+			Self::UnterminatedUnicodeEscape(_) => "unterminated-unicode-escape",
+			Self::UnterminatedString => "unterminated-string",
+			Self::UnterminatedInterpolation => "unterminated-interpolation",
+			Self::UnterminatedRawString => "unterminated-raw-string",
+			Self::UnterminatedBlockComment => "unterminated-block-comment",
+			Self::InvalidDigitSeparator => "invalid-digit-separator",
+			Self::InvalidNumber(_) => "invalid-number",
+			Self::InvalidLineContinuation => "invalid-line-continuation",
+			Self::InvalidIdentifier(_) => "invalid-identifier",
+		}
+	}
+}
+
+
 /// A lexical error.
 #[derive(Debug)]
 pub struct Error {
@@ -41,10 +88,21 @@ impl Error {
 		Self { error: ErrorKind::Unexpected(input), pos }
 	}
 
+	pub fn invalid_character(character: &[u8], pos: SourcePos) -> Self {
+		Self {
+			error: ErrorKind::InvalidCharacter(character.into()),
+			pos,
+		}
+	}
+
 	pub fn empty_byte_literal(pos: SourcePos) -> Self {
 		Self { error: ErrorKind::EmptyByteLiteral, pos }
 	}
 
+	pub fn byte_literal_too_long(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::ByteLiteralTooLong, pos }
+	}
+
 	pub fn invalid_escape_sequence(sequence: &[u8], pos: SourcePos) -> Self {
 		Self {
 			error: ErrorKind::InvalidEscapeSequence(sequence.into()),
@@ -52,6 +110,40 @@ impl Error {
 		}
 	}
 
+	pub fn invalid_unicode_escape(sequence: &[u8], pos: SourcePos) -> Self {
+		Self {
+			error: ErrorKind::InvalidUnicodeEscape(sequence.into()),
+			pos,
+		}
+	}
+
+	pub fn unterminated_unicode_escape(sequence: &[u8], pos: SourcePos) -> Self {
+		Self {
+			error: ErrorKind::UnterminatedUnicodeEscape(sequence.into()),
+			pos,
+		}
+	}
+
+	pub fn unterminated_string(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::UnterminatedString, pos }
+	}
+
+	pub fn unterminated_interpolation(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::UnterminatedInterpolation, pos }
+	}
+
+	pub fn unterminated_raw_string(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::UnterminatedRawString, pos }
+	}
+
+	pub fn unterminated_block_comment(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::UnterminatedBlockComment, pos }
+	}
+
+	pub fn invalid_digit_separator(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::InvalidDigitSeparator, pos }
+	}
+
 	pub fn invalid_number(number: &[u8], pos: SourcePos) -> Self {
 		Self {
 			error: ErrorKind::InvalidNumber(number.into()),
@@ -59,6 +151,10 @@ impl Error {
 		}
 	}
 
+	pub fn invalid_line_continuation(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::InvalidLineContinuation, pos }
+	}
+
 	pub fn invalid_identifier(ident: &[u8], pos: SourcePos) -> Self {
 		Self {
 			error: ErrorKind::InvalidIdentifier(ident.into()),
@@ -30,7 +30,19 @@ pub struct Lexer<'a, 'b>(Automata<'a, 'b>);
 
 impl<'a, 'b> Lexer<'a, 'b> {
 	pub fn new(cursor: Cursor<'a>, interner: &'b mut symbol::Interner) -> Self {
-		Self(Automata::new(cursor, interner))
+		Self(Automata::new(cursor, interner, false))
+	}
+
+
+	/// Create a lexer that also produces comment tokens, which the parser doesn't accept.
+	pub fn with_comments(cursor: Cursor<'a>, interner: &'b mut symbol::Interner) -> Self {
+		Self(Automata::new(cursor, interner, true))
+	}
+
+
+	/// The offset in the source just past the last produced token.
+	pub fn offset(&self) -> usize {
+		self.0.offset()
 	}
 }
 
@@ -2,6 +2,8 @@ use super::*;
 
 use assert_matches::assert_matches;
 
+use crate::{fmt, runtime::value::Float};
+
 
 macro_rules! token {
 	($kind:pat) => {
@@ -165,6 +167,9 @@ fn test_byte_literals() {
 		var = '\na'  # invalid literal with escape sequence 1
 		var = 'a\n'  # invalid literal with escape sequence 2
 		var = '\1a'  # invalid escape sequence followed by character
+		var = 'é'    # multi-byte character
+		var = 'a\''  # escaped quote in invalid literal
+		var = '\''
 	"#;
 
 	let mut interner = symbol::Interner::new();
@@ -194,26 +199,40 @@ fn test_byte_literals() {
 
 			token!(TokenKind::Identifier(_)),
 			token!(TokenKind::Operator(Operator::Assign)),
-			error!(ErrorKind::Unexpected(b'a')),
+			error!(ErrorKind::ByteLiteralTooLong),
 			token!(TokenKind::Literal(Literal::Byte(b'\n'))),
 
 			token!(TokenKind::Identifier(_)),
 			token!(TokenKind::Operator(Operator::Assign)),
-			error!(ErrorKind::Unexpected(b'\\')),
-			error!(ErrorKind::Unexpected(b'n')),
+			error!(ErrorKind::ByteLiteralTooLong),
 			token!(TokenKind::Literal(Literal::Byte(b'a'))),
 
 			token!(TokenKind::Identifier(_)),
 			token!(TokenKind::Operator(Operator::Assign)),
 			error!(ErrorKind::InvalidEscapeSequence(e2)),
-			error!(ErrorKind::Unexpected(b'a')),
+			error!(ErrorKind::ByteLiteralTooLong),
+			token!(TokenKind::Literal(Literal::Byte(_))),
+
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::ByteLiteralTooLong, pos }),
 			token!(TokenKind::Literal(Literal::Byte(_))),
+
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			error!(ErrorKind::ByteLiteralTooLong),
+			token!(TokenKind::Literal(Literal::Byte(b'a'))),
+
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Byte(b'\''))),
 		]
 			=> {
 				assert_symbol!(interner, var, "var");
 				assert_eq!(interner.len(), 2);
 				assert_eq!(e1.as_ref(), b"\\?");
 				assert_eq!(e2.as_ref(), b"\\1");
+				assert_eq!((pos.line, pos.column), (8, 9));
 			}
 	);
 }
@@ -255,6 +274,625 @@ fn test_string_literals() {
 }
 
 
+#[test]
+fn test_pipe_operator() {
+	let input = "x |> f(1) |>g";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Pipe)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::OpenParens),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			token!(TokenKind::CloseParens),
+			token!(TokenKind::Operator(Operator::Pipe)),
+			token!(TokenKind::Identifier(_)),
+		]
+	);
+}
+
+
+#[test]
+fn test_semicolons() {
+	let input = "a = 1; b;;";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Semicolon),
+		]
+	);
+}
+
+
+#[test]
+fn test_line_continuation() {
+	let input = "let a = 1 \\\n+ 2\nlet b = \\ 3";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			Ok(Token { kind: TokenKind::Operator(Operator::Plus), pos: plus_pos }),
+			token!(TokenKind::Literal(Literal::Int(2))),
+
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::InvalidLineContinuation, pos: error_pos }),
+			token!(TokenKind::Literal(Literal::Int(3))),
+		]
+			=> {
+				assert_eq!((plus_pos.line, plus_pos.column), (2, 1));
+				assert_eq!((error_pos.line, error_pos.column), (3, 9));
+			}
+	);
+}
+
+
+#[test]
+fn test_exponent_literals() {
+	let input = r#"
+		1e9 2.5E-3 1e+10 1e400
+		1e 1ex 1e+ 1e-y
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::Float(f1))),
+			token!(TokenKind::Literal(Literal::Float(f2))),
+			token!(TokenKind::Literal(Literal::Float(f3))),
+			token!(TokenKind::Literal(Literal::Float(f4))),
+
+			error!(ErrorKind::InvalidNumber(e1)),
+			error!(ErrorKind::InvalidNumber(e2)),
+			token!(TokenKind::Identifier(x)),
+			error!(ErrorKind::InvalidNumber(e3)),
+			error!(ErrorKind::InvalidNumber(e4)),
+			token!(TokenKind::Identifier(_)),
+		]
+			=> {
+				assert_eq!(*f1, 1e9);
+				assert_eq!(*f2, 2.5e-3);
+				assert_eq!(*f3, 1e10);
+				assert_eq!(*f4, f64::INFINITY);
+				assert_eq!(e1.as_ref(), b"1e");
+				assert_eq!(e2.as_ref(), b"1e");
+				assert_symbol!(interner, x, "x");
+				assert_eq!(e3.as_ref(), b"1e+");
+				assert_eq!(e4.as_ref(), b"1e-");
+			}
+	);
+}
+
+
+/// Floats must be lexed back to the same value they were formatted from.
+#[test]
+fn test_float_round_trip() {
+	for &float in &[ 1e9, 2.5e-3, 1e20, 1.5e-20, 123.456, f64::MAX, f64::MIN_POSITIVE ] {
+		let input = Float::from(float).to_string();
+
+		let mut interner = symbol::Interner::new();
+		let path = interner.get_or_intern("<test>");
+		let source = Source { path, contents: input.as_bytes().into() };
+		let cursor = Cursor::from(&source);
+		let lexer = Lexer::new(cursor, &mut interner);
+
+		let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+		assert_matches!(
+			&tokens[..],
+			[ token!(TokenKind::Literal(Literal::Float(value))) ] => assert_eq!(*value, float),
+			"{}", input
+		);
+	}
+}
+
+
+#[test]
+fn test_prefixed_number_literals() {
+	let input = r#"
+		0xff 0XfF 0o755 0b1010 0x7fffffffffffffff 010 0
+		0x8000000000000000 0b2 0x 0o8 0xfg
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::Int(255))),
+			token!(TokenKind::Literal(Literal::Int(255))),
+			token!(TokenKind::Literal(Literal::Int(0o755))),
+			token!(TokenKind::Literal(Literal::Int(10))),
+			token!(TokenKind::Literal(Literal::Int(i64::MAX))),
+			token!(TokenKind::Literal(Literal::Int(10))), // No accidental octal.
+			token!(TokenKind::Literal(Literal::Int(0))),
+
+			Err(Error { error: ErrorKind::InvalidNumber(e1), pos }),
+			error!(ErrorKind::InvalidNumber(e2)),
+			error!(ErrorKind::InvalidNumber(e3)),
+			error!(ErrorKind::InvalidNumber(e4)),
+			error!(ErrorKind::InvalidNumber(e5)),
+		]
+			=> {
+				assert_eq!(e1.as_ref(), b"0x8000000000000000");
+				assert_eq!((pos.line, pos.column), (3, 3));
+				assert_eq!(e2.as_ref(), b"0b2");
+				assert_eq!(e3.as_ref(), b"0x");
+				assert_eq!(e4.as_ref(), b"0o8");
+				assert_eq!(e5.as_ref(), b"0xfg");
+			}
+	);
+}
+
+
+#[test]
+fn test_digit_separators() {
+	let input = r#"
+		1_000_000 0xdead_beef 0b1010_1010 1_0.5_0e1_0 _1
+		1_ 1__0 1_.5 1._5 1_e5 1e_5 0x_ff
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::Int(1_000_000))),
+			token!(TokenKind::Literal(Literal::Int(0xdead_beef))),
+			token!(TokenKind::Literal(Literal::Int(0b1010_1010))),
+			token!(TokenKind::Literal(Literal::Float(f1))),
+			// A leading underscore makes an identifier.
+			token!(TokenKind::Identifier(ident)),
+
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos1 }),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos2 }),
+			token!(TokenKind::Literal(Literal::Int(0))),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos3 }),
+			token!(TokenKind::Literal(Literal::Int(5))),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos4 }),
+			token!(TokenKind::Literal(Literal::Int(5))),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos5 }),
+			token!(TokenKind::Literal(Literal::Int(5))),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos6 }),
+			token!(TokenKind::Literal(Literal::Int(5))),
+			Err(Error { error: ErrorKind::InvalidDigitSeparator, pos: pos7 }),
+			token!(TokenKind::Identifier(_)),
+		]
+			=> {
+				assert_eq!(*f1, 10.50e10);
+				assert_symbol!(interner, ident, "_1");
+				assert_eq!((pos1.line, pos1.column), (3, 4));
+				assert_eq!((pos2.line, pos2.column), (3, 7));
+				assert_eq!((pos3.line, pos3.column), (3, 12));
+				assert_eq!((pos4.line, pos4.column), (3, 18));
+				assert_eq!((pos5.line, pos5.column), (3, 22));
+				assert_eq!((pos6.line, pos6.column), (3, 28));
+				assert_eq!((pos7.line, pos7.column), (3, 33));
+			}
+	);
+}
+
+
+#[test]
+fn test_escape_sequences() {
+	let input = r#"
+		"\n\t\r\0\\\"\'" ++ "\x41\x7e\xFF" ++ '\r' ++ '\x0a'
+		"a\qb" "\xg1" "\x4" '\x4' "\"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::String(lit1))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::String(lit2))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::Byte(b'\r'))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::Byte(b'\n'))),
+
+			Err(Error { error: ErrorKind::InvalidEscapeSequence(e1), pos: pos1 }),
+			token!(TokenKind::Literal(Literal::String(_))),
+			Err(Error { error: ErrorKind::InvalidEscapeSequence(e2), pos: pos2 }),
+			token!(TokenKind::Literal(Literal::String(_))),
+			error!(ErrorKind::InvalidEscapeSequence(e3)),
+			token!(TokenKind::Literal(Literal::String(_))),
+			error!(ErrorKind::InvalidEscapeSequence(e4)),
+			token!(TokenKind::Literal(Literal::Byte(_))),
+
+			// Escape at the end of the input.
+			error!(ErrorKind::UnterminatedString),
+		]
+			=> {
+				assert_eq!(lit1.as_ref(), b"\n\t\r\0\\\"\'");
+				assert_eq!(lit2.as_ref(), b"A~\xff");
+				assert_eq!(e1.as_ref(), b"\\q");
+				assert_eq!((pos1.line, pos1.column), (3, 5));
+				assert_eq!(e2.as_ref(), b"\\x");
+				assert_eq!((pos2.line, pos2.column), (3, 11));
+				assert_eq!(e3.as_ref(), b"\\x4");
+				assert_eq!(e4.as_ref(), b"\\x4");
+			}
+	);
+}
+
+
+#[test]
+fn test_unicode_escapes() {
+	let input = r#"
+		"\u{e9}" "\u{1F600}" "\u{10FFFF}" '\u{41}'
+		"\u{110000}" "\u{D800}" "\u{41" '\u{e9}' "\u41" "\u{1234567}"
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::String(lit1))),
+			token!(TokenKind::Literal(Literal::String(lit2))),
+			token!(TokenKind::Literal(Literal::String(lit3))),
+			token!(TokenKind::Literal(Literal::Byte(b'A'))),
+
+			Err(Error { error: ErrorKind::InvalidUnicodeEscape(e1), pos: pos1 }),
+			token!(TokenKind::Literal(Literal::String(_))),
+			error!(ErrorKind::InvalidUnicodeEscape(e2)),
+			token!(TokenKind::Literal(Literal::String(_))),
+			Err(Error { error: ErrorKind::UnterminatedUnicodeEscape(e3), pos: pos3 }),
+			token!(TokenKind::Literal(Literal::String(_))),
+			// Non-ascii characters don't fit in a byte.
+			error!(ErrorKind::ByteLiteralTooLong),
+			token!(TokenKind::Literal(Literal::Byte(_))),
+			error!(ErrorKind::InvalidEscapeSequence(e5)),
+			token!(TokenKind::Literal(Literal::String(_))),
+			error!(ErrorKind::UnterminatedUnicodeEscape(e6)),
+			token!(TokenKind::Literal(Literal::String(_))),
+		]
+			=> {
+				assert_eq!(lit1.as_ref(), "\u{e9}".as_bytes());
+				assert_eq!(lit2.as_ref(), b"\xF0\x9F\x98\x80");
+				assert_eq!(lit3.as_ref(), b"\xF4\x8F\xBF\xBF");
+				assert_eq!(e1.as_ref(), b"\\u{110000}");
+				assert_eq!((pos1.line, pos1.column), (3, 4));
+				assert_eq!(e2.as_ref(), b"\\u{D800}");
+				assert_eq!(e3.as_ref(), b"\\u{41");
+				assert_eq!((pos3.line, pos3.column), (3, 28));
+				assert_eq!(e5.as_ref(), b"\\u");
+				assert_eq!(e6.as_ref(), b"\\u{123456");
+			}
+	);
+}
+
+
+#[test]
+fn test_multiline_and_raw_strings() {
+	let input = r###"
+		"first
+second" ++ r"\d+\n" ++ r#"say "hi"
+	done"# ++ r##""#"## ++ r"" r
+		let x = r#"unterminated "
+	"###;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Literal(Literal::String(lit1))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::String(lit2))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::String(lit3))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::String(lit4))),
+			token!(TokenKind::Operator(Operator::Concat)),
+			token!(TokenKind::Literal(Literal::String(lit5))),
+			token!(TokenKind::Identifier(r)),
+
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::UnterminatedRawString, pos }),
+		]
+			=> {
+				assert_eq!(lit1.as_ref(), b"first\nsecond");
+				assert_eq!(lit2.as_ref(), b"\\d+\\n");
+				assert_eq!(lit3.as_ref(), b"say \"hi\"\n\tdone");
+				assert_eq!(lit4.as_ref(), b"\"#");
+				assert!(lit5.is_empty());
+				assert_symbol!(interner, r, "r");
+				assert_eq!((pos.line, pos.column), (5, 11));
+			}
+	);
+}
+
+
+#[test]
+fn test_block_comments() {
+	let input = r#"
+		let a = 1 #[[ block
+		comment #[[ nested ]]# still
+		comment ]]# let b = 2
+		#[ line comment
+		{ echo #[[ in a command ]]# hi }
+		let c = @
+		#[[ unterminated
+		#[[ ]]#
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+
+			Ok(Token { kind: TokenKind::Keyword(Keyword::Let), pos: let_pos }),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(2))),
+
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::CloseCommand),
+
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::Unexpected(b'@'), pos: unexpected_pos }),
+			Err(Error { error: ErrorKind::UnterminatedBlockComment, pos: comment_pos }),
+		]
+			=> {
+				assert_eq!((let_pos.line, let_pos.column), (4, 15));
+				assert_eq!((unexpected_pos.line, unexpected_pos.column), (7, 11));
+				assert_eq!((comment_pos.line, comment_pos.column), (8, 3));
+			}
+	);
+}
+
+
+#[test]
+fn test_comment_tokens() {
+	let input = "# line \r\nlet a = 1 #[[ block\n]]# { echo # in a command\n }\n# last";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let mut lexer = Lexer::with_comments(cursor, &mut interner);
+
+	let mut tokens: Vec<Result<Token, Error>> = Vec::new();
+	let mut offsets = Vec::new();
+	while let Some(token) = lexer.next() {
+		tokens.push(token);
+		offsets.push(lexer.offset());
+	}
+
+	assert_matches!(
+		&tokens[..],
+		[
+			Ok(Token { kind: TokenKind::Comment(line), pos: line_pos }),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			Ok(Token { kind: TokenKind::Comment(block), pos: block_pos }),
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			Ok(Token { kind: TokenKind::Comment(command), .. }),
+			token!(TokenKind::CloseCommand),
+			Ok(Token { kind: TokenKind::Comment(last), .. }),
+		]
+			=> {
+				assert_eq!(line.as_ref(), b"# line ");
+				assert_eq!((line_pos.line, line_pos.column), (1, 1));
+				assert_eq!(block.as_ref(), b"#[[ block\n]]#");
+				assert_eq!((block_pos.line, block_pos.column), (2, 11));
+				assert_eq!(command.as_ref(), b"# in a command");
+				assert_eq!(last.as_ref(), b"# last");
+			}
+	);
+
+	// The offset is just past each token.
+	assert_eq!(&input[.. offsets[1]], "# line \r\nlet");
+	assert_eq!(&input[offsets[4] .. offsets[5]], " #[[ block\n]]#");
+	assert_eq!(offsets.last(), Some(&input.len()));
+}
+
+
+#[test]
+fn test_shebang() {
+	let input = "#!/usr/bin/env hush\nlet x = 1\nlet y = @\n#!not a shebang\n";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			Ok(Token { kind: TokenKind::Keyword(Keyword::Let), pos: let_pos }),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::Unexpected(b'@'), pos }),
+		]
+			=> {
+				assert_eq!((let_pos.line, let_pos.column), (2, 1));
+				assert_eq!((pos.line, pos.column), (3, 9));
+			}
+	);
+}
+
+
+#[test]
+fn test_string_interpolation() {
+	let input = r#"
+		"a ${x + 1} b $y.${ "c${z}" }\$ $"
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Interpolation(piece1)),
+			Ok(Token { kind: TokenKind::Identifier(x), pos: x_pos }),
+			token!(TokenKind::Operator(Operator::Plus)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			token!(TokenKind::Interpolation(piece2)),
+			Ok(Token { kind: TokenKind::Identifier(y), pos: y_pos }),
+			token!(TokenKind::Interpolation(piece3)),
+			token!(TokenKind::Interpolation(piece4)),
+			token!(TokenKind::Identifier(z)),
+			token!(TokenKind::InterpolationEnd(piece5)),
+			token!(TokenKind::InterpolationEnd(piece6)),
+		]
+			=> {
+				assert_eq!(piece1.as_ref(), b"a ");
+				assert_eq!(piece2.as_ref(), b" b ");
+				assert_eq!(piece3.as_ref(), b".");
+				assert_eq!(piece4.as_ref(), b"c");
+				assert_eq!(piece5.as_ref(), b"");
+				assert_eq!(piece6.as_ref(), b"$ $");
+				assert_symbol!(interner, x, "x");
+				assert_symbol!(interner, y, "y");
+				assert_symbol!(interner, z, "z");
+				assert_eq!((x_pos.line, x_pos.column), (2, 8));
+				assert_eq!((y_pos.line, y_pos.column), (2, 18));
+			}
+	);
+}
+
+
+
+#[test]
+fn test_unterminated_interpolation() {
+	// The quote opens a nested string, which is reported as the unclosed interpolation.
+	let input = "let s = \"a ${x\"\nlet y = 1\n";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Interpolation(_)),
+			token!(TokenKind::Identifier(_)),
+			Err(Error { error: ErrorKind::UnterminatedInterpolation, pos }),
+		]
+			=> assert_eq!((pos.line, pos.column), (1, 12))
+	);
+}
+
 #[test]
 fn test_number_literals() {
 	let input = r#"
@@ -470,3 +1108,168 @@ fn test_expansions() {
 			}
 	);
 }
+
+
+#[test]
+fn test_token_positions() {
+	// Columns count characters, and a tab is a single character.
+	let input = "let s = \"ação\" + x\n\t\ty = \"🦀\"\tz\n";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			Ok(Token { kind: TokenKind::Keyword(Keyword::Let), pos: let_pos }),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Ok(Token { kind: TokenKind::Literal(Literal::String(_)), pos: string_pos }),
+			Ok(Token { kind: TokenKind::Operator(Operator::Plus), pos: plus_pos }),
+			Ok(Token { kind: TokenKind::Identifier(_), pos: x_pos }),
+			Ok(Token { kind: TokenKind::Identifier(_), pos: y_pos }),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Ok(Token { kind: TokenKind::Literal(Literal::String(_)), pos: crab_pos }),
+			Ok(Token { kind: TokenKind::Identifier(_), pos: z_pos }),
+		]
+			=> {
+				assert_eq!((let_pos.line, let_pos.column), (1, 1));
+				assert_eq!((string_pos.line, string_pos.column), (1, 9));
+				assert_eq!((plus_pos.line, plus_pos.column), (1, 16));
+				assert_eq!((x_pos.line, x_pos.column), (1, 18));
+				assert_eq!((y_pos.line, y_pos.column), (2, 3));
+				assert_eq!((crab_pos.line, crab_pos.column), (2, 7));
+				assert_eq!((z_pos.line, z_pos.column), (2, 11));
+			}
+	);
+}
+
+
+#[test]
+fn test_invalid_characters() {
+	let input = "let x = 1\0\nlet € = ~\nlet s = \"abc";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			Err(Error { error: ErrorKind::InvalidCharacter(nul), pos: nul_pos }),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			// The whole UTF-8 sequence is reported once.
+			Err(Error { error: ErrorKind::InvalidCharacter(euro), pos: euro_pos }),
+			token!(TokenKind::Operator(Operator::Assign)),
+			error!(ErrorKind::Unexpected(b'~')),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			Err(Error { error: ErrorKind::UnterminatedString, pos: string_pos }),
+		]
+			=> {
+				assert_eq!(nul.as_ref(), b"\0");
+				assert_eq!((nul_pos.line, nul_pos.column), (1, 10));
+				assert_eq!(euro.as_ref(), "€".as_bytes());
+				assert_eq!((euro_pos.line, euro_pos.column), (2, 5));
+				assert_eq!((string_pos.line, string_pos.column), (3, 9));
+			}
+	);
+}
+
+
+#[test]
+fn test_unicode_identifiers() {
+	let input = "let café = 变量 + straße_Δ1\nlet x🦀 = 1\n";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(cafe)),
+			Ok(Token { kind: TokenKind::Operator(Operator::Assign), pos: assign_pos }),
+			token!(TokenKind::Identifier(variable)),
+			token!(TokenKind::Operator(Operator::Plus)),
+			Ok(Token { kind: TokenKind::Identifier(mixed), pos: mixed_pos }),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(x)),
+			Err(Error { error: ErrorKind::InvalidCharacter(emoji), pos: emoji_pos }),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+		]
+			=> {
+				assert_symbol!(interner, cafe, "café");
+				assert_symbol!(interner, variable, "变量");
+				assert_symbol!(interner, mixed, "straße_Δ1");
+				assert_symbol!(interner, x, "x");
+				assert_eq!((assign_pos.line, assign_pos.column), (1, 10));
+				assert_eq!((mixed_pos.line, mixed_pos.column), (1, 17));
+				assert_eq!(emoji.as_ref(), "🦀".as_bytes());
+				assert_eq!((emoji_pos.line, emoji_pos.column), (2, 6));
+			}
+	);
+}
+
+
+#[test]
+fn test_token_display() {
+	let input = "let f = function(x) x ++ \"a\" end\n{ echo $f; }";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Token> = lexer
+		.collect::<Result<_, _>>()
+		.expect("lexing should succeed");
+
+	let dump: Vec<String> = tokens
+		.iter()
+		.map(|token| fmt::Show(token, &interner).to_string())
+		.collect();
+
+	assert_eq!(
+		dump,
+		[
+			"<test>:1:1\tkeyword\tlet",
+			"<test>:1:5\tidentifier\tf",
+			"<test>:1:7\toperator\t=",
+			"<test>:1:9\tkeyword\tfunction",
+			"<test>:1:17\topen-parens\t(",
+			"<test>:1:18\tidentifier\tx",
+			"<test>:1:19\tclose-parens\t)",
+			"<test>:1:21\tidentifier\tx",
+			"<test>:1:23\toperator\t++",
+			"<test>:1:26\tliteral\t\"a\"",
+			"<test>:1:30\tkeyword\tend",
+			"<test>:2:1\tcommand\t{",
+			"<test>:2:3\targument\techo",
+			"<test>:2:8\targument\t${f}",
+			"<test>:2:10\tsemicolon\t;",
+			"<test>:2:12\tclose-command\t}",
+		]
+	);
+}
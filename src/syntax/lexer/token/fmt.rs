@@ -20,26 +20,7 @@ use crate::{
 
 impl std::fmt::Display for Keyword {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		color
-			::Fg(
-				color::Blue,
-				match self {
-					Self::Let => "let",
-					Self::If => "if",
-					Self::Then => "then",
-					Self::Else => "else",
-					Self::End => "end",
-					Self::For => "for",
-					Self::In => "in",
-					Self::Do => "do",
-					Self::While => "while",
-					Self::Function => "function",
-					Self::Return => "return",
-					Self::Break => "break",
-					Self::Self_ => "self",
-				}
-			)
-			.fmt(f)
+		color::Fg(color::Blue, self.as_str()).fmt(f)
 	}
 }
 
@@ -70,7 +51,9 @@ impl std::fmt::Display for Operator {
 			Self::Minus => color::Fg(color::Yellow, "-").fmt(f),
 			Self::Times => color::Fg(color::Yellow, "*").fmt(f),
 			Self::Div => color::Fg(color::Yellow, "/").fmt(f),
+			Self::FloorDiv => color::Fg(color::Yellow, "//").fmt(f),
 			Self::Mod => color::Fg(color::Yellow, "%").fmt(f),
+			Self::Pow => color::Fg(color::Yellow, "**").fmt(f),
 			Self::Equals => color::Fg(color::Yellow, "==").fmt(f),
 			Self::NotEquals => color::Fg(color::Yellow, "!=").fmt(f),
 			Self::Greater => color::Fg(color::Yellow, ">").fmt(f),
@@ -82,6 +65,7 @@ impl std::fmt::Display for Operator {
 			Self::Or => color::Fg(color::Blue, "or").fmt(f),
 			Self::Concat => color::Fg(color::Yellow, "++").fmt(f),
 			Self::Dot => color::Fg(color::Yellow, ".").fmt(f),
+			Self::Pipe => color::Fg(color::Yellow, "|>").fmt(f),
 			Self::Assign => "=".fmt(f),
 			Self::Try => color::Fg(color::Yellow, "?").fmt(f),
 		}
@@ -96,9 +80,9 @@ impl<'a> Display<'a> for ArgUnit {
 		match self {
 			Self::Literal(s) => String::from_utf8_lossy(s).escape_debug().fmt(f),
 			Self::Dollar { symbol, .. } => {
-				"${{".fmt(f)?;
+				"${".fmt(f)?;
 				symbol.fmt(f, context)?;
-				"}}".fmt(f)
+				"}".fmt(f)
 			}
 		}
 	}
@@ -202,11 +186,22 @@ impl<'a> Display<'a> for TokenKind {
 			Self::Literal(lit) => lit.fmt(f),
 			Self::Colon => ":".fmt(f),
 			Self::Comma => ",".fmt(f),
+			Self::Ellipsis => "...".fmt(f),
 			Self::OpenParens => "(".fmt(f),
 			Self::CloseParens => ")".fmt(f),
 			Self::OpenBracket => "[".fmt(f),
 			Self::OpenDict => "@[".fmt(f),
 			Self::CloseBracket => "]".fmt(f),
+			Self::Interpolation(s) => write!(
+				f,
+				"\"{}${{",
+				color::Bold(String::from_utf8_lossy(s).escape_debug())
+			),
+			Self::InterpolationEnd(s) => write!(
+				f,
+				"}}{}\"",
+				color::Bold(String::from_utf8_lossy(s).escape_debug())
+			),
 			Self::Command => "{".fmt(f),
 			Self::CaptureCommand => "${".fmt(f),
 			Self::AsyncCommand => "&{".fmt(f),
@@ -220,6 +215,7 @@ impl<'a> Display<'a> for TokenKind {
 			Self::CmdOperator(op) => op.fmt(f),
 			Self::Semicolon => ";".fmt(f),
 			Self::Pipe => color::Fg(color::Yellow, "|").fmt(f),
+			Self::Comment(text) => String::from_utf8_lossy(text).escape_debug().fmt(f),
 		}
 	}
 }
@@ -229,7 +225,7 @@ impl<'a> Display<'a> for Token {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		write!(f, "{}:\t", fmt::Show(self.pos, context))?;
+		write!(f, "{}\t{}\t", fmt::Show(self.pos, context), self.kind.name())?;
 		self.kind.fmt(f, context)
 	}
 }
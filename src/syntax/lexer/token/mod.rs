@@ -23,6 +23,46 @@ pub enum Keyword {
 }
 
 
+impl Keyword {
+	/// All keywords, ordered by their discriminant.
+	pub const ALL: [Keyword; 13] = [
+		Self::Let,
+		Self::If,
+		Self::Then,
+		Self::Else,
+		Self::End,
+		Self::For,
+		Self::In,
+		Self::Do,
+		Self::While,
+		Self::Function,
+		Self::Return,
+		Self::Break,
+		Self::Self_,
+	];
+
+
+	/// The keyword as it is written in the source code.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Let => "let",
+			Self::If => "if",
+			Self::Then => "then",
+			Self::Else => "else",
+			Self::End => "end",
+			Self::For => "for",
+			Self::In => "in",
+			Self::Do => "do",
+			Self::While => "while",
+			Self::Function => "function",
+			Self::Return => "return",
+			Self::Break => "break",
+			Self::Self_ => "self",
+		}
+	}
+}
+
+
 /// Literals for non-composite types.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
@@ -40,11 +80,13 @@ pub enum Literal {
 /// Non-command operators.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Operator {
-	Plus,  // +
-	Minus, // -
-	Times, // *
-	Div,   // /
-	Mod,   // %
+	Plus,     // +
+	Minus,    // -
+	Times,    // *
+	Div,      // /
+	FloorDiv, // //
+	Mod,      // %
+	Pow,      // **
 
 	Equals,        // ==
 	NotEquals,     // !=
@@ -59,6 +101,7 @@ pub enum Operator {
 
 	Concat, // ++
 	Dot,    // .
+	Pipe,   // |>
 
 	Assign, // =
 
@@ -88,9 +131,9 @@ impl Operator {
 	}
 
 
-	/// Multiplicative arithmetic operators (*, /, %).
+	/// Multiplicative arithmetic operators (*, /, //, %).
 	pub fn is_factor(&self) -> bool {
-		matches!(self, Self::Times | Self::Div | Self::Mod)
+		matches!(self, Self::Times | Self::Div | Self::FloorDiv | Self::Mod)
 	}
 
 
@@ -184,8 +227,9 @@ pub enum TokenKind {
 	Operator(Operator),
 	Literal(Literal),
 
-	Colon, // :
-	Comma, // ,
+	Colon,    // :
+	Comma,    // ,
+	Ellipsis, // ...
 
 	OpenParens,  // (
 	CloseParens, // )
@@ -194,6 +238,10 @@ pub enum TokenKind {
 	OpenDict,     // @[
 	CloseBracket, // ]
 
+	// Interpolated strings are split in pieces, such as "a ${b} c $d e":
+	Interpolation(Box<[u8]>),    // "a ${, } c $
+	InterpolationEnd(Box<[u8]>), // e"
+
 	// Command block tokens
 	Command,        // {
 	AsyncCommand,   // &{
@@ -207,10 +255,26 @@ pub enum TokenKind {
 	// commands, instead of being attributed to a single command.
 	Semicolon, // ;
 	Pipe,      // |
+
+	// Comments are only produced when requested from the lexer, as for formatting.
+	Comment(Box<[u8]>), // # ..., #[[ ... ]]#
 }
 
 
 impl TokenKind {
+	/// Check if the token is a statement separator or starts a statement that may not be
+	/// an expression.
+	pub fn is_statement_boundary(&self) -> bool {
+		matches!(
+			self,
+			TokenKind::Semicolon
+				| TokenKind::Keyword(
+					Keyword::Let | Keyword::Return | Keyword::Break | Keyword::While | Keyword::For
+				)
+		)
+	}
+
+
 	/// Check if the token terminates a statement block.
 	/// Currently, only the END and ELSE keywords do that.
 	pub fn is_block_terminator(&self) -> bool {
@@ -221,6 +285,56 @@ impl TokenKind {
 	}
 
 
+	/// The token that closes the construct opened by this token, if any.
+	pub fn closing(&self) -> Option<TokenKind> {
+		match self {
+			TokenKind::Keyword(Keyword::If | Keyword::While | Keyword::For | Keyword::Function) => {
+				Some(TokenKind::Keyword(Keyword::End))
+			}
+
+			TokenKind::OpenParens => Some(TokenKind::CloseParens),
+
+			TokenKind::OpenBracket | TokenKind::OpenDict => Some(TokenKind::CloseBracket),
+
+			TokenKind::Command | TokenKind::CaptureCommand | TokenKind::AsyncCommand => {
+				Some(TokenKind::CloseCommand)
+			}
+
+			_ => None,
+		}
+	}
+
+
+	/// A short description of the kind of token, without its contents.
+	pub fn name(&self) -> &'static str {
+		match self {
+			TokenKind::Identifier(_) => "identifier",
+			TokenKind::Keyword(_) => "keyword",
+			TokenKind::Operator(_) => "operator",
+			TokenKind::Literal(_) => "literal",
+			TokenKind::Colon => "colon",
+			TokenKind::Comma => "comma",
+			TokenKind::Ellipsis => "ellipsis",
+			TokenKind::OpenParens => "open-parens",
+			TokenKind::CloseParens => "close-parens",
+			TokenKind::OpenBracket => "open-bracket",
+			TokenKind::OpenDict => "open-dict",
+			TokenKind::CloseBracket => "close-bracket",
+			TokenKind::Interpolation(_) => "interpolation",
+			TokenKind::InterpolationEnd(_) => "interpolation-end",
+			TokenKind::Command => "command",
+			TokenKind::AsyncCommand => "async-command",
+			TokenKind::CaptureCommand => "capture-command",
+			TokenKind::CloseCommand => "close-command",
+			TokenKind::Argument(_) => "argument",
+			TokenKind::CmdOperator(_) => "command-operator",
+			TokenKind::Semicolon => "semicolon",
+			TokenKind::Pipe => "pipe",
+			TokenKind::Comment(_) => "comment",
+		}
+	}
+
+
 	/// Check if the token starts a command block.
 	pub fn is_command_block_starter(&self) -> bool {
 		matches!(
@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod error;
+pub mod format;
 pub mod lexer;
 pub mod parser;
 mod fmt;
@@ -13,8 +14,8 @@ use crate::symbol;
 pub use ast::Ast;
 pub use error::{Error, Errors};
 use lexer::Lexer;
-use parser::Parser;
-pub use source::{Source, SourcePos};
+use parser::{KeywordSymbols, Parser};
+pub use source::{Snippet, Source, SourcePos};
 pub use fmt::AnalysisDisplayContext;
 
 
@@ -31,6 +32,10 @@ pub struct Analysis {
 impl Analysis {
 	/// Perform syntax analysis in the given source.
 	pub fn analyze(source: &Source, interner: &mut symbol::Interner) -> Self {
+		interner.add_source(source.path, &source.contents);
+
+		let keywords = KeywordSymbols::new(interner);
+
 		let cursor = lexer::Cursor::from(source);
 		let lexer = Lexer::new(cursor, interner);
 
@@ -47,7 +52,7 @@ impl Analysis {
 			}
 		});
 
-		let parser = Parser::new(tokens, |error| {
+		let parser = Parser::new(tokens, keywords, |error| {
 			errors.borrow_mut().push(Error::Parser(error))
 		});
 
@@ -7,6 +7,7 @@ use super::{
 	CommandOperator as Operator,
 	Error,
 	ErrorReporter,
+	Expected,
 	Parser,
 	Token,
 	TokenKind,
@@ -22,6 +23,7 @@ where
 	pub(super) fn parse_command_block(&mut self) -> sync::Result<ast::CommandBlock, Error> {
 		let kind = self
 			.eat(
+				Expected::Message("command block"),
 				|token| ast::CommandBlockKind
 					::from_token(&token.kind)
 					.ok_or_else(
@@ -98,13 +100,13 @@ where
 				// The current token is a single unquoted number
 				Some(Token { kind: TokenKind::Argument(parts), .. })
 					if matches!(parts.as_ref(), &[ref part] if part.is_unquoted_number())
-					// And the next token is a redirection operator.
-					&& matches!(
-						self.cursor.peek(),
-						Some(Token { kind: TokenKind::CmdOperator(op), .. })
-							if op.is_redirection()
-					)
-			);
+			)
+				// And the next token is a redirection operator.
+				&& matches!(
+					self.peek(),
+					Some(Token { kind: TokenKind::CmdOperator(op), .. })
+						if op.is_redirection()
+				);
 
 			if is_redirection {
 				break;
@@ -136,7 +138,7 @@ where
 
 	/// Parse a single argument.
 	fn parse_argument(&mut self) -> Result<ast::Argument, Error> {
-		let (arg_parts, pos) = self.eat(|token| match token {
+		let (arg_parts, pos) = self.eat(Expected::Message("argument"), |token| match token {
 			Token { kind: TokenKind::Argument(parts), pos } => Ok((parts, pos)),
 			token => Err((Error::unexpected_msg(token.clone(), "argument"), token)),
 		})?;
@@ -151,7 +153,7 @@ where
 
 	/// Parse an env-assign.
 	fn parse_env_assign(&mut self) -> Option<(ast::ArgUnit, ast::Argument)> {
-		self.eat(|token| match token {
+		self.eat(Expected::Message("argument"), |token| match token {
 			Token { kind: TokenKind::Argument(parts), pos }
 			if matches!(&parts[..], [ ArgPart::Unquoted(_), ArgPart::EnvAssign, .. ]) => {
 				let mut parts = parts.into_vec(); // Use vec's owned iterator.
@@ -195,7 +197,7 @@ where
 					redirections.push(redirection);
 				}
 
-				None => return Err(Error::unexpected_eof()),
+				None => return Err(self.unexpected_eof(Expected::Token(TokenKind::CloseCommand))),
 			}
 		}
 
@@ -229,7 +231,7 @@ where
 				Ok(redirection)
 			}
 
-			None => Err(Error::unexpected_eof())
+			None => Err(self.unexpected_eof(Expected::Message("redirection")))
 				.with_sync(sync::Strategy::eof()),
 		}
 	}
@@ -265,7 +267,7 @@ where
 			Some(token) => Err(Error::unexpected_msg(token.clone(), "output redirection"))
 				.with_sync(sync::Strategy::skip_one()),
 
-			None => Err(Error::unexpected_eof())
+			None => Err(self.unexpected_eof(Expected::Message("output redirection")))
 				.with_sync(sync::Strategy::eof()),
 		}
 	}
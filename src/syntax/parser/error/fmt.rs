@@ -1,6 +1,6 @@
 use std::fmt::Display as _;
 
-use super::{Error, Expected, Token};
+use super::{Error, Expected, Message, Token};
 use crate::{
 	fmt::{self, Display},
 	symbol,
@@ -24,25 +24,63 @@ impl<'a> Display<'a> for Expected {
 }
 
 
-impl<'a> Display<'a> for Error {
+impl<'a> Display<'a> for Message<'_> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		match self {
-			Self::InvalidEnvAssign => "internal error: invalid env-assign".fmt(f),
+		match self.0 {
+			Error::InvalidEnvAssign => "internal error: invalid env-assign".fmt(f),
+
+			Error::UnexpectedEof { expected, open } => {
+				"unexpected end of file: expected ".fmt(f)?;
+				expected.fmt(f, context)?;
 
-			Self::UnexpectedEof => "unexpected end of file".fmt(f),
+				if let Some(Token { kind, pos }) = open {
+					match (expected, kind.closing()) {
+						(Expected::Token(expected), Some(closing)) if *expected == closing => {
+							" to close '".fmt(f)?
+						}
 
-			Self::Unexpected { token: Token { kind, pos }, expected } => {
-				write!(f, "{} - unexpected '", fmt::Show(pos, context))?;
+						_ => " in '".fmt(f)?,
+					}
+
+					kind.fmt(f, context)?;
+					write!(f, "' started at {}", fmt::Show(pos, context))?;
+				}
+
+				Ok(())
+			}
+
+			Error::Unexpected { token: Token { kind, .. }, expected } => {
+				"unexpected '".fmt(f)?;
 				kind.fmt(f, context)?;
 				"', expected ".fmt(f)?;
 				expected.fmt(f, context)
 			},
 
-			Self::EmptyCommandBlock { pos } => {
-				write!(f, "{} - empty command block", fmt::Show(pos, context))
-			}
+			Error::EmptyCommandBlock { .. } => "empty command block".fmt(f),
+
+			Error::TooDeep { max, .. } => write!(f, "nesting is too deep, the maximum depth is {}", max),
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Error {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		// Unexpected EOF refers to the position of the open construct in the message.
+		match (self, self.pos()) {
+			(Self::UnexpectedEof { .. }, _) | (_, None) => (),
+			(_, Some(pos)) => write!(f, "{} - ", fmt::Show(pos, context))?,
+		}
+
+		self.message().fmt(f, context)?;
+
+		match self.pos() {
+			Some(pos) => pos.snippet().fmt(f, context),
+			None => Ok(()),
 		}
 	}
 }
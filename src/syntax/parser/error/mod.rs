@@ -11,15 +11,22 @@ pub enum Expected {
 }
 
 
+/// The message of a parser error, without the position and snippet.
+#[derive(Debug, Clone, Copy)]
+pub struct Message<'a>(&'a Error);
+
+
 /// A parser error.
 #[derive(Debug)]
 pub enum Error {
-	/// Premature EOF.
-	UnexpectedEof,
+	/// Premature EOF, possibly inside an open construct, such as a block or parentheses.
+	UnexpectedEof { expected: Expected, open: Option<Token> },
 	/// Unexpected token.
 	Unexpected { token: Token, expected: Expected },
 	/// Command blocks must have at least one command.
 	EmptyCommandBlock { pos: SourcePos },
+	/// Constructs nested beyond the maximum depth.
+	TooDeep { pos: SourcePos, max: usize },
 	/// Invalid env-assign. This is a spurious error while parsing, and should be handled
 	/// internally.
 	InvalidEnvAssign,
@@ -27,9 +34,10 @@ pub enum Error {
 
 
 impl Error {
-	/// Create an error signaling unexpected EOF.
-	pub fn unexpected_eof() -> Self {
-		Self::UnexpectedEof
+	/// Create an error signaling unexpected EOF, and what was expected.
+	/// The innermost open construct, if any, should be supplied as well.
+	pub fn unexpected_eof(expected: Expected, open: Option<Token>) -> Self {
+		Self::UnexpectedEof { expected, open }
 	}
 
 
@@ -49,6 +57,42 @@ impl Error {
 	pub fn empty_command_block(pos: SourcePos) -> Self {
 		Self::EmptyCommandBlock { pos }
 	}
+
+
+	/// Create an error signaling the maximum nesting depth was exceeded.
+	pub fn too_deep(pos: SourcePos, max: usize) -> Self {
+		Self::TooDeep { pos, max }
+	}
+
+
+	/// The error message, without the position and snippet.
+	pub fn message(&self) -> Message<'_> {
+		Message(self)
+	}
+
+
+	/// A stable identifier for the kind of error.
+	pub fn code(&self) -> &'static str {
+		match self {
+			Self::UnexpectedEof { .. } => "unexpected-eof",
+			Self::Unexpected { .. } => "unexpected-token",
+			Self::EmptyCommandBlock { .. } => "empty-command-block",
+			Self::TooDeep { .. } => "nesting-too-deep",
+			Self::InvalidEnvAssign => "invalid-env-assign",
+		}
+	}
+
+
+	/// The position the error refers to, if any.
+	/// For an unexpected EOF, this is the position of the innermost open construct.
+	pub fn pos(&self) -> Option<SourcePos> {
+		match self {
+			Self::UnexpectedEof { open, .. } => open.as_ref().map(|token| token.pos),
+			Self::Unexpected { token, .. } => Some(token.pos),
+			Self::EmptyCommandBlock { pos } | Self::TooDeep { pos, .. } => Some(*pos),
+			Self::InvalidEnvAssign => None,
+		}
+	}
 }
 
 
@@ -2,7 +2,7 @@ mod command;
 mod error;
 mod sync;
 
-use std::iter::Peekable;
+use std::collections::VecDeque;
 
 use super::{
 	SourcePos,
@@ -18,7 +18,8 @@ use super::{
 	}
 };
 use sync::{ResultExt, WithSync, Synchronizable};
-pub use error::Error;
+use crate::symbol;
+pub use error::{Error, Expected};
 
 
 /// The parser may report multiple errors before finishing. Instead of allocating those in
@@ -38,6 +39,33 @@ where
 }
 
 
+/// The symbols for all keywords. Keywords are not interned by the lexer, but they may be
+/// used as field names, which are symbols.
+#[derive(Debug, Clone, Copy)]
+pub struct KeywordSymbols([ast::Symbol; Keyword::ALL.len()]);
+
+
+impl KeywordSymbols {
+	/// Intern all keywords.
+	pub fn new(interner: &mut symbol::Interner) -> Self {
+		Self(Keyword::ALL.map(|keyword| interner.get_or_intern(keyword.as_str())))
+	}
+
+
+	/// Get the symbol for a keyword.
+	fn get(&self, keyword: Keyword) -> ast::Symbol {
+		self.0[keyword as usize]
+	}
+}
+
+
+/// The maximum nesting depth of expressions, statements and patterns. As the parser is
+/// recursive, deeper inputs would overflow the stack. Each level may take tens of
+/// kilobytes of stack in debug builds, so this must fit the main thread's stack with room
+/// to spare.
+pub const MAX_DEPTH: usize = 128;
+
+
 /// The parser for Hush syntax.
 #[derive(Debug)]
 pub struct Parser<I, E>
@@ -46,8 +74,20 @@ where
 {
 	// We don't use a std::iter::Peekable instead of a (Iterator, Option<Token>) pair
 	// because we must be able to move from `token`, but Peekable only returns a reference.
-	cursor: Peekable<I>,
+	cursor: I,
+	/// Tokens that have been peeked, but not yet placed on `token`.
+	lookahead: VecDeque<Token>,
 	token: Option<Token>,
+	/// The line of the current token, and of the previous one.
+	lines: (u32, u32),
+	keywords: KeywordSymbols,
+	/// The constructs that are currently open, such as blocks and parentheses. These are
+	/// used to improve unexpected end of file errors.
+	open: Vec<Token>,
+	/// The current nesting depth, see `MAX_DEPTH`.
+	depth: usize,
+	/// The line of the last reported error.
+	error_line: Option<u32>,
 	error_reporter: E,
 }
 
@@ -58,27 +98,113 @@ where
 	E: ErrorReporter,
 {
 	/// Create a new parser for the given input.
-	pub fn new(mut cursor: I, error_reporter: E) -> Self {
+	pub fn new(mut cursor: I, keywords: KeywordSymbols, error_reporter: E) -> Self {
 		let token = cursor.next();
 
-		Self { cursor: cursor.peekable(), token, error_reporter }
+		let line = token.as_ref().map_or(0, |token| token.pos.line);
+
+		Self {
+			cursor,
+			lookahead: VecDeque::new(),
+			token,
+			lines: (line, line),
+			keywords,
+			open: Vec::new(),
+			depth: 0,
+			error_line: None,
+			error_reporter,
+		}
 	}
 
 
 	/// Peek the next token.
 	fn peek(&mut self) -> Option<&Token> {
-		self.cursor.peek()
+		self.peek_nth(0)
+	}
+
+
+	/// Peek the nth token after the next one.
+	fn peek_nth(&mut self, n: usize) -> Option<&Token> {
+		while self.lookahead.len() <= n {
+			let token = self.cursor.next()?;
+			self.lookahead.push_back(token);
+		}
+
+		self.lookahead.get(n)
 	}
 
 
 	/// Step the cursor, placing the next token on self.token.
 	fn step(&mut self) {
-		self.token = self.cursor.next();
+		self.token = self.lookahead
+			.pop_front()
+			.or_else(|| self.cursor.next());
+
+		let (current, _) = self.lines;
+		let line = self.token.as_ref().map_or(current, |token| token.pos.line);
+		self.lines = (line, current);
+	}
+
+
+	/// Check if the current token is in a different line than the previous one.
+	fn is_line_start(&self) -> bool {
+		let (current, previous) = self.lines;
+		current > previous
+	}
+
+
+	/// Parse a construct delimited by the given opening token, such as blocks and
+	/// parentheses. The opening token must have already been consumed.
+	fn delimited<F, T>(&mut self, opening: Token, parse: F) -> T
+	where
+		F: FnOnce(&mut Self) -> T,
+	{
+		self.open.push(opening);
+		let result = parse(self);
+		self.open.pop();
+
+		result
+	}
+
+
+	/// Parse a nested construct, such as an expression, a statement or a pattern, failing
+	/// if the maximum nesting depth is exceeded. In such case, the remaining input is
+	/// skipped, as parsing it would only produce spurious errors.
+	fn nested<F, T>(&mut self, parse: F) -> sync::Result<T, Error>
+	where
+		F: FnOnce(&mut Self) -> sync::Result<T, Error>,
+	{
+		match &self.token {
+			Some(Token { pos, .. }) if self.depth >= MAX_DEPTH => {
+				let error = Error::too_deep(*pos, MAX_DEPTH);
+
+				while self.token.is_some() {
+					self.step();
+				}
+
+				Err(error)
+					.with_sync(sync::Strategy::eof())
+			}
+
+			_ => {
+				self.depth += 1;
+				let result = parse(self);
+				self.depth -= 1;
+
+				result
+			}
+		}
 	}
 
 
-	/// Try and eat a token.
-	fn eat<F, T>(&mut self, eat: F) -> Result<T, Error>
+	/// Create an unexpected end of file error, with the innermost open construct.
+	fn unexpected_eof(&self, expected: Expected) -> Error {
+		Error::unexpected_eof(expected, self.open.last().cloned())
+	}
+
+
+	/// Try and eat a token. The expected token is only used for end of file errors.
+	fn eat<F, T>(&mut self, expected: Expected, eat: F) -> Result<T, Error>
 	where
 		F: FnOnce(Token) -> Result<T, (Error, Token)>,
 	{
@@ -97,14 +223,14 @@ where
 				}
 			}
 		} else {
-			Err(Error::unexpected_eof())
+			Err(self.unexpected_eof(expected))
 		}
 	}
 
 
 	/// Consume the expected token, or produce an error.
 	fn expect(&mut self, expected: TokenKind) -> Result<SourcePos, Error> {
-		self.eat(|token| match token {
+		self.eat(Expected::Token(expected.clone()), |token| match token {
 			Token { kind: token, pos } if token == expected => Ok(pos),
 			token => Err((Error::unexpected(token.clone(), expected), token)),
 		})
@@ -173,7 +299,16 @@ where
 	E: ErrorReporter,
 {
 	fn synchronize(&mut self, error: Error, mut strategy: sync::Strategy) {
-		self.error_reporter.report(error);
+		// Errors in the same line of a previous error are most likely caused by it, and
+		// are therefore not reported. The end of file counts as a line of its own.
+		let line = self.token
+			.as_ref()
+			.map_or(u32::MAX, |token| token.pos.line);
+
+		if self.error_line != Some(line) {
+			self.error_line = Some(line);
+			self.error_reporter.report(error);
+		}
 
 		while let Some(Token { kind: token, .. }) = &self.token {
 			if strategy.synchronized(token) {
@@ -218,6 +353,7 @@ where
 
 	/// Parse a block of statements, stopping when ELSE, END of EOF are reached, or after a
 	/// return is parsed. The Lua-like grammar requires stopping after such conditions.
+	/// Statements may be separated by semicolons, and empty statements are ignored.
 	/// This method synchronizes on all errors, producing an empty block if no statements
 	/// can be parsed.
 	fn parse_block(&mut self) -> ast::Block {
@@ -231,11 +367,21 @@ where
 				// Break on end of block.
 				Some(Token { kind: token, .. }) if token.is_block_terminator() => break,
 
+				// Statement separator.
+				Some(Token { kind: TokenKind::Semicolon, .. }) => self.step(),
+
 				Some(_) => {
-					let statement = self
-						.parse_statement()
-						.force_sync_skip() // Prevent the parser from getting stuck.
-						.synchronize(self);
+					let result = self.nested(Self::parse_statement);
+
+					// A statement cut short by the end of the block leaves the terminator to the
+					// enclosing construct, as skipping it would cause a bogus missing end error.
+					// Otherwise, skip a token to prevent the parser from getting stuck.
+					let result = match &self.token {
+						Some(Token { kind: token, .. }) if token.is_block_terminator() => result,
+						_ => result.force_sync_skip(),
+					};
+
+					let statement = result.synchronize(self);
 
 					let is_return = matches!(statement, ast::Statement::Return { .. });
 
@@ -243,6 +389,10 @@ where
 
 					if is_return {
 						// There may be no statements following a return in a block.
+						while let Some(Token { kind: TokenKind::Semicolon, .. }) = self.token {
+							self.step();
+						}
+
 						break;
 					}
 				}
@@ -255,7 +405,19 @@ where
 
 	/// Parse a single statement.
 	fn parse_statement(&mut self) -> sync::Result<ast::Statement, Error> {
+		// Destructuring assignment.
+		if self.is_destructuring() {
+			return self.parse_destructuring(false);
+		}
+
 		match self.token.take() {
+			// Let destructuring.
+			Some(Token { kind: TokenKind::Keyword(Keyword::Let), .. })
+				if matches!(self.peek(), Some(Token { kind: TokenKind::OpenBracket | TokenKind::OpenDict, .. })) => {
+					self.step();
+					self.parse_destructuring(true)
+				}
+
 			// Let.
 			Some(Token { kind: TokenKind::Keyword(Keyword::Let), .. }) => {
 				self.step();
@@ -289,7 +451,8 @@ where
 						.parse_identifier()
 						.expect("there should be an identifier");
 
-					let (params, body) = self.parse_function()?;
+					let opening = Token { kind: TokenKind::Keyword(Keyword::Function), pos };
+					let (params, body) = self.delimited(opening, Self::parse_function)?;
 
 					Ok(
 						ast::Statement::Let {
@@ -325,48 +488,53 @@ where
 			}
 
 			// While.
-			Some(Token { kind: TokenKind::Keyword(Keyword::While), pos }) => {
+			Some(token @ Token { kind: TokenKind::Keyword(Keyword::While), .. }) => {
+				let pos = token.pos;
 				self.step();
 
-				let condition = self.parse_expression()
-					.synchronize(self);
+				self.delimited(token, |parser| {
+					let condition = parser.parse_expression()
+						.synchronize(parser);
 
-				self.expect(TokenKind::Keyword(Keyword::Do))
-					.with_sync(sync::Strategy::keep())
-					.synchronize(self);
+					parser.expect(TokenKind::Keyword(Keyword::Do))
+						.with_sync(sync::Strategy::keep())
+						.synchronize(parser);
 
-				let block = self.parse_block();
+					let block = parser.parse_block();
 
-				self.expect(TokenKind::Keyword(Keyword::End))
-					.with_sync(sync::Strategy::keyword(Keyword::End))?;
+					parser.expect(TokenKind::Keyword(Keyword::End))
+						.with_sync(sync::Strategy::keyword(Keyword::End))?;
 
-				Ok(ast::Statement::While { condition, block, pos })
+					Ok(ast::Statement::While { condition, block, pos })
+				})
 			}
 
 			// For.
-			Some(Token { kind: TokenKind::Keyword(Keyword::For), .. }) => {
+			Some(token @ Token { kind: TokenKind::Keyword(Keyword::For), .. }) => {
 				self.step();
 
-				let (identifier, pos) = self.parse_identifier()
-					.synchronize(self);
+				self.delimited(token, |parser| {
+					let (identifier, pos) = parser.parse_identifier()
+						.synchronize(parser);
 
-				self.expect(TokenKind::Keyword(Keyword::In))
-					.with_sync(sync::Strategy::skip_one())
-					.synchronize(self);
+					parser.expect(TokenKind::Keyword(Keyword::In))
+						.with_sync(sync::Strategy::skip_one())
+						.synchronize(parser);
 
-				let expr = self.parse_expression()
-					.synchronize(self);
+					let expr = parser.parse_expression()
+						.synchronize(parser);
 
-				self.expect(TokenKind::Keyword(Keyword::Do))
-					.with_sync(sync::Strategy::keep())
-					.synchronize(self);
+					parser.expect(TokenKind::Keyword(Keyword::Do))
+						.with_sync(sync::Strategy::keep())
+						.synchronize(parser);
 
-				let block = self.parse_block();
+					let block = parser.parse_block();
 
-				self.expect(TokenKind::Keyword(Keyword::End))
-					.with_sync(sync::Strategy::keyword(Keyword::End))?;
+					parser.expect(TokenKind::Keyword(Keyword::End))
+						.with_sync(sync::Strategy::keyword(Keyword::End))?;
 
-				Ok(ast::Statement::For { identifier, expr, block, pos })
+					Ok(ast::Statement::For { identifier, expr, block, pos })
+				})
 			}
 
 			// Expr.
@@ -376,6 +544,11 @@ where
 				// Don't synchronize here because this expression may be the last part of the statement.
 				let expr = self.parse_expression()?;
 
+				// Multiple assignment, such as `a, b = b, a`.
+				if matches!(self.token, Some(Token { kind: TokenKind::Comma, .. })) {
+					return self.parse_multiple_assignment(expr);
+				}
+
 				let pos = match &self.token {
 					Some(Token { kind: TokenKind::Operator(Operator::Assign), pos }) => Some(*pos),
 					_ => None
@@ -396,12 +569,226 @@ where
 			}
 
 			// EOF.
-			None => Err(Error::unexpected_eof())
+			None => Err(self.unexpected_eof(Expected::Message("statement")))
+				.with_sync(sync::Strategy::eof()),
+		}
+	}
+
+
+	/// Parse a multiple assignment, after the first l-value.
+	fn parse_multiple_assignment(&mut self, first: ast::Expr) -> sync::Result<ast::Statement, Error> {
+		let mut left = vec![first];
+
+		while let Some(Token { kind: TokenKind::Comma, .. }) = self.token {
+			self.step();
+
+			left.push(
+				self.parse_expression()
+					.synchronize(self)
+			);
+		}
+
+		let pos = self.expect(TokenKind::Operator(Operator::Assign))
+			.with_sync(sync::Strategy::keep())?;
+
+		// Don't synchronize here because these expressions are the last part of the statement.
+		let mut right = vec![self.parse_expression()?];
+
+		while let Some(Token { kind: TokenKind::Comma, .. }) = self.token {
+			self.step();
+			right.push(self.parse_expression()?);
+		}
+
+		Ok(
+			ast::Statement::MultipleAssign {
+				left: left.into(),
+				right: right.into(),
+				pos,
+			}
+		)
+	}
+
+
+	/// Check if the current token starts a destructuring assignment, such as `[ a, b ] = c`.
+	/// This requires looking ahead for the matching close bracket, followed by an assignment.
+	fn is_destructuring(&mut self) -> bool {
+		let mut depth = 1;
+		// The depth of the outermost dict pattern, if any. Dict patterns may contain default
+		// values, and therefore arbitrary tokens.
+		let mut dict_depth = match &self.token {
+			Some(Token { kind: TokenKind::OpenBracket, .. }) => None,
+			Some(Token { kind: TokenKind::OpenDict, .. }) => Some(depth),
+			_ => return false,
+		};
+
+		let mut n = 0;
+
+		loop {
+			match self.peek_nth(n) {
+				Some(Token { kind: TokenKind::OpenBracket, .. }) => depth += 1,
+
+				Some(Token { kind: TokenKind::OpenDict, .. }) => {
+					depth += 1;
+					dict_depth = dict_depth.or(Some(depth));
+				}
+
+				Some(Token { kind: TokenKind::CloseBracket, .. }) if depth == 1 => {
+					return matches!(
+						self.peek_nth(n + 1),
+						Some(Token { kind: TokenKind::Operator(Operator::Assign), .. })
+					);
+				}
+
+				Some(Token { kind: TokenKind::CloseBracket, .. }) => {
+					if dict_depth == Some(depth) {
+						dict_depth = None;
+					}
+
+					depth -= 1;
+				}
+
+				// Tokens that may compose an array pattern.
+				Some(Token { kind: TokenKind::Identifier(_) | TokenKind::Comma | TokenKind::Ellipsis, .. }) => (),
+
+				Some(_) if dict_depth.is_some() => (),
+
+				_ => return false,
+			}
+
+			n += 1;
+		}
+	}
+
+
+	/// Parse a destructuring assignment, after the let keyword if declaring.
+	fn parse_destructuring(&mut self, declare: bool) -> sync::Result<ast::Statement, Error> {
+		let pattern = self.parse_pattern()
+			.synchronize(self);
+
+		let pos = self.expect(TokenKind::Operator(Operator::Assign))
+			.with_sync(sync::Strategy::keep())?;
+
+		// Don't synchronize here because this expression is the last part of the statement.
+		let right = self.parse_expression()?;
+
+		Ok(ast::Statement::Destructure { pattern, declare, right, pos })
+	}
+
+
+	/// Parse a destructuring pattern.
+	fn parse_pattern(&mut self) -> sync::Result<ast::Pattern, Error> {
+		match self.token.take() {
+			// Identifier.
+			Some(Token { kind: TokenKind::Identifier(identifier), pos }) => {
+				self.step();
+
+				Ok(ast::Pattern::Identifier { identifier, pos })
+			}
+
+			// Array.
+			Some(Token { kind: TokenKind::OpenBracket, pos }) => {
+				self.step();
+
+				let mut items = Vec::new();
+				let mut rest = None;
+
+				loop {
+					match &self.token {
+						Some(Token { kind: TokenKind::CloseBracket, .. }) => break,
+
+						// The rest pattern must be the last one.
+						Some(Token { kind: TokenKind::Ellipsis, .. }) => {
+							self.step();
+
+							let pattern = self.nested(Self::parse_pattern)
+								.synchronize(self);
+
+							rest = Some(Box::new(pattern));
+
+							break;
+						}
+
+						_ => items.push(
+							self.nested(Self::parse_pattern)
+								.synchronize(self)
+						),
+					}
+
+					match &self.token {
+						Some(Token { kind: TokenKind::Comma, .. }) => self.step(),
+						_ => break,
+					}
+				}
+
+				self.expect(TokenKind::CloseBracket)
+					.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
+
+				Ok(ast::Pattern::Array { items: items.into(), rest, pos })
+			}
+
+			// Dict.
+			Some(Token { kind: TokenKind::OpenDict, pos }) => {
+				self.step();
+
+				let items = self.comma_sep(
+					Self::parse_dict_pattern_item,
+					|token| *token == TokenKind::CloseBracket,
+				);
+
+				self.expect(TokenKind::CloseBracket)
+					.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
+
+				Ok(ast::Pattern::Dict { items, pos })
+			}
+
+			Some(token) => {
+				self.token = Some(token.clone());
+
+				Err(Error::unexpected_msg(token, "identifier or pattern"))
+					.with_sync(sync::Strategy::keep())
+			}
+
+			None => Err(self.unexpected_eof(Expected::Message("identifier or pattern")))
 				.with_sync(sync::Strategy::eof()),
 		}
 	}
 
 
+	/// Parse an item in a dict destructuring pattern, such as `key`, `key: pattern` or
+	/// `key = default`.
+	fn parse_dict_pattern_item(&mut self) -> sync::Result<ast::DictPatternItem, Error> {
+		let is_keyword = matches!(self.token, Some(Token { kind: TokenKind::Keyword(_), .. }));
+
+		let (key, pos) = self.parse_field_name()?;
+
+		let has_pattern = matches!(self.token, Some(Token { kind: TokenKind::Colon, .. }));
+
+		// Shorthand items bind a variable named after the key, and therefore keywords must
+		// be given a pattern.
+		let pattern =
+			if has_pattern || is_keyword {
+				self.expect(TokenKind::Colon)
+					.with_sync(sync::Strategy::keep())?;
+
+				self.nested(Self::parse_pattern)
+					.synchronize(self)
+			} else {
+				ast::Pattern::Identifier { identifier: key, pos }
+			};
+
+		let default = match &self.token {
+			Some(Token { kind: TokenKind::Operator(Operator::Assign), .. }) => {
+				self.step();
+				Some(self.parse_expression()?)
+			}
+
+			_ => None,
+		};
+
+		Ok(ast::DictPatternItem { key, pattern, default, pos })
+	}
+
+
 	/// Parse a single expression.
 	fn parse_expression(&mut self) -> sync::Result<ast::Expr, Error> {
 		macro_rules! binop {
@@ -413,12 +800,32 @@ where
 		let parse_factor     = binop!(Self::parse_prefix, Operator::is_factor);
 		let parse_term       = binop!(parse_factor,     Operator::is_term);
 		let parse_concat     = binop!(parse_term,       |&op| op == Operator::Concat);
-		let parse_comparison = binop!(parse_concat,     Operator::is_comparison);
+		let parse_pipe       = move |parser: &mut Self| parser.parse_pipe(parse_concat);
+		let parse_comparison = binop!(parse_pipe,       Operator::is_comparison);
 		let parse_equality   = binop!(parse_comparison, Operator::is_equality);
 		let parse_and        = binop!(parse_equality,   |&op| op == Operator::And);
 		let parse_or         = binop!(parse_and,        |&op| op == Operator::Or);
 
-		parse_or(self)
+		self.nested(parse_or)
+	}
+
+
+	/// Parse a function call argument, which may be spread, as in `f(...args)`.
+	fn parse_call_argument(&mut self) -> sync::Result<ast::Expr, Error> {
+		match self.token.take() {
+			Some(Token { kind: TokenKind::Ellipsis, pos }) => {
+				self.step();
+
+				let expr = self.parse_expression()?;
+
+				Ok(ast::Expr::Spread { expr: expr.into(), pos })
+			}
+
+			token => {
+				self.token = token;
+				self.parse_expression()
+			}
+		}
 	}
 
 
@@ -460,13 +867,58 @@ where
 	}
 
 
+	/// Parse a higher precedence expression, optionally followed by pipeline operators.
+	/// Pipelines are desugared into function calls: `x |> f` is equivalent to `f(x)`, and
+	/// `x |> f(a)` is equivalent to `f(x, a)`.
+	fn parse_pipe<P>(&mut self, mut parse_higher_prec_op: P) -> sync::Result<ast::Expr, Error>
+	where
+		P: FnMut(&mut Self) -> sync::Result<ast::Expr, Error>,
+	{
+		let mut expr = parse_higher_prec_op(self)?;
+
+		loop {
+			match self.token.take() {
+				Some(Token { kind: TokenKind::Operator(Operator::Pipe), pos }) => {
+					self.step();
+
+					let function = parse_higher_prec_op(self)?;
+
+					expr = match function {
+						// The piped value is inserted as the first argument.
+						ast::Expr::Call { function, args, pos } => ast::Expr::Call {
+							function,
+							args: std::iter::once(expr)
+								.chain(args.into_vec())
+								.collect(),
+							pos,
+						},
+
+						function => ast::Expr::Call {
+							function: function.into(),
+							args: vec![expr].into_boxed_slice(),
+							pos,
+						},
+					};
+				}
+
+				token => {
+					self.token = token;
+					break;
+				}
+			}
+		}
+
+		Ok(expr)
+	}
+
+
 	/// Parse a higher precedence expression, optionally starting with a prefix operator.
 	fn parse_prefix(&mut self) -> sync::Result<ast::Expr, Error> {
 		match self.token.take() {
 			Some(Token { kind: TokenKind::Operator(op), pos }) if op.is_prefix() => {
 				self.step();
 
-				let operand = self.parse_prefix()?;
+				let operand = self.nested(Self::parse_prefix)?;
 
 				Ok(ast::Expr::UnaryOp {
 					op: op.into(),
@@ -477,7 +929,35 @@ where
 
 			token => {
 				self.token = token;
-				self.parse_postfix()
+				self.parse_power()
+			}
+		}
+	}
+
+
+	/// Parse a postfix expression, optionally raised to a power. Exponentiation is right
+	/// associative, and binds tighter than prefix operators on its left: `-2 ** 2` is
+	/// `-(2 ** 2)`. Its right operand may still be prefixed, as in `2 ** -1`.
+	fn parse_power(&mut self) -> sync::Result<ast::Expr, Error> {
+		let base = self.parse_postfix()?;
+
+		match self.token.take() {
+			Some(Token { kind: TokenKind::Operator(Operator::Pow), pos }) => {
+				self.step();
+
+				let exponent = self.nested(Self::parse_prefix)?;
+
+				Ok(ast::Expr::BinaryOp {
+					left: base.into(),
+					op: ast::BinaryOp::Pow,
+					right: exponent.into(),
+					pos,
+				})
+			}
+
+			token => {
+				self.token = token;
+				Ok(base)
 			}
 		}
 	}
@@ -488,18 +968,28 @@ where
 		let mut expr = self.parse_primary()?;
 
 		loop {
+			// As statements are not delimited, a bracket starting a line could be either a
+			// subscript or a destructuring assignment. The latter is preferred.
+			if self.is_line_start() && self.is_destructuring() {
+				break;
+			}
+
 			match self.token.take() {
 				// Function call.
-				Some(Token { kind: TokenKind::OpenParens, pos }) => {
+				Some(token @ Token { kind: TokenKind::OpenParens, .. }) => {
+					let pos = token.pos;
 					self.step();
 
-					let args = self.comma_sep(
-						Self::parse_expression,
-						|token| *token == TokenKind::CloseParens,
-					);
+					let args = self.delimited(token, |parser| {
+						let args = parser.comma_sep(
+							Self::parse_call_argument,
+							|token| *token == TokenKind::CloseParens,
+						);
 
-					self.expect(TokenKind::CloseParens)
-						.with_sync(sync::Strategy::token(TokenKind::CloseParens))?;
+						parser.expect(TokenKind::CloseParens)
+							.with_sync(sync::Strategy::token(TokenKind::CloseParens))
+							.map(|_| args)
+					})?;
 
 					expr = ast::Expr::Call {
 						function: expr.into(),
@@ -509,14 +999,18 @@ where
 				},
 
 				// Subscript operator.
-				Some(Token { kind: TokenKind::OpenBracket, pos }) => {
+				Some(token @ Token { kind: TokenKind::OpenBracket, .. }) => {
+					let pos = token.pos;
 					self.step();
 
-					let field = self.parse_expression()
-						.synchronize(self);
+					let field = self.delimited(token, |parser| {
+						let field = parser.parse_expression()
+							.synchronize(parser);
 
-					self.expect(TokenKind::CloseBracket)
-						.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
+						parser.expect(TokenKind::CloseBracket)
+							.with_sync(sync::Strategy::token(TokenKind::CloseBracket))
+							.map(|_| field)
+					})?;
 
 					expr = ast::Expr::Access {
 						object: expr.into(),
@@ -531,7 +1025,7 @@ where
 
 					// Here, the identifier is a literal, and not a variable name. Hence, `var.id`
 					// is equivalent to `var["id"]`, and not from `var[id]`.
-					let (identifier, id_pos) = self.parse_identifier()?;
+					let (identifier, id_pos) = self.parse_field_name()?;
 
 					let field = ast::Expr::Literal {
 						literal: ast::Literal::Identifier(identifier),
@@ -591,56 +1085,115 @@ where
 				Ok(ast::Expr::Literal { literal: literal.into(), pos })
 			}
 
+			// Interpolated string.
+			Some(Token { kind: TokenKind::Interpolation(piece), pos }) => {
+				self.step();
+
+				let string = |piece, pos| ast::Expr::Literal {
+					literal: ast::Literal::String(piece),
+					pos,
+				};
+
+				let opening = Token { kind: TokenKind::Interpolation(piece.clone()), pos };
+				let mut parts = vec![ string(piece, pos) ];
+
+				self.delimited(opening, |parser| loop {
+					let expr = parser.parse_expression()
+						.synchronize(parser);
+
+					parts.push(expr);
+
+					let (piece, piece_pos, end) = parser
+						.eat(
+							Expected::Message("end of interpolation"),
+							|token| match token {
+								Token { kind: TokenKind::Interpolation(piece), pos } => Ok((piece, pos, false)),
+								Token { kind: TokenKind::InterpolationEnd(piece), pos } => Ok((piece, pos, true)),
+								token => Err((Error::unexpected_msg(token.clone(), "end of interpolation"), token)),
+							}
+						)
+						.with_sync(sync::Strategy::keep())?;
+
+					parts.push(string(piece, piece_pos));
+
+					if end {
+						break Ok(());
+					}
+				})?;
+
+				Ok(ast::Expr::Interpolation { parts: parts.into(), pos })
+			}
+
 			// Array literal.
-			Some(Token { kind: TokenKind::OpenBracket, pos }) => {
+			Some(token @ Token { kind: TokenKind::OpenBracket, .. }) => {
+				let pos = token.pos;
 				self.step();
 
-				let items = self.comma_sep(
-					Self::parse_expression,
-					|token| *token == TokenKind::CloseBracket,
-				);
+				self.delimited(token, |parser| {
+					let items = parser.comma_sep(
+						Self::parse_expression,
+						|token| *token == TokenKind::CloseBracket,
+					);
 
-				self.expect(TokenKind::CloseBracket)
-					.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
+					parser.expect(TokenKind::CloseBracket)
+						.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
 
-				Ok(ast::Expr::Literal {
-					literal: ast::Literal::Array(items),
-					pos,
+					Ok(ast::Expr::Literal {
+						literal: ast::Literal::Array(items),
+						pos,
+					})
 				})
 			}
 
 			// Dict literal.
-			Some(Token { kind: TokenKind::OpenDict, pos }) => {
+			Some(token @ Token { kind: TokenKind::OpenDict, .. }) => {
+				let pos = token.pos;
 				self.step();
 
-				let items = self.comma_sep(
-					|parser| {
-						let key = parser.parse_identifier()
-							.with_sync(sync::Strategy::skip_one())
-							.synchronize(parser);
+				self.delimited(token, |parser| {
+					let items = parser.comma_sep(
+						|parser| {
+							let is_keyword = matches!(
+								parser.token,
+								Some(Token { kind: TokenKind::Keyword(_), .. })
+							);
+
+							let key = parser.parse_dict_key();
+
+							// Shorthand fields, such as `@[ name ]`, are equivalent to `@[ name: name ]`.
+							if let (
+								&ast::DictKey::Identifier(identifier, pos),
+								Some(Token { kind: TokenKind::Comma | TokenKind::CloseBracket, .. }),
+							) = (&key, &parser.token) {
+								if !is_keyword {
+									return Ok((key, ast::Expr::Identifier { identifier, pos }));
+								}
+							}
 
-						parser.expect(TokenKind::Colon)
-							.with_sync(sync::Strategy::keep())
-							.synchronize(parser);
+							parser.expect(TokenKind::Colon)
+								.with_sync(sync::Strategy::keep())
+								.synchronize(parser);
 
-						let value = parser.parse_expression()?;
+							let value = parser.parse_expression()?;
 
-						Ok((key, value))
-					},
-					|token| *token == TokenKind::CloseBracket,
-				);
+							Ok((key, value))
+						},
+						|token| *token == TokenKind::CloseBracket,
+					);
 
-				self.expect(TokenKind::CloseBracket)
-					.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
+					parser.expect(TokenKind::CloseBracket)
+						.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
 
-				Ok(ast::Expr::Literal { literal: ast::Literal::Dict(items), pos })
+					Ok(ast::Expr::Literal { literal: ast::Literal::Dict(items), pos })
+				})
 			}
 
 			// Function literal.
-			Some(Token { kind: TokenKind::Keyword(Keyword::Function), pos }) => {
+			Some(token @ Token { kind: TokenKind::Keyword(Keyword::Function), .. }) => {
+				let pos = token.pos;
 				self.step();
 
-				let (params, body) = self.parse_function()?;
+				let (params, body) = self.delimited(token, Self::parse_function)?;
 
 				Ok(ast::Expr::Literal { literal: ast::Literal::Function { params, body }, pos })
 			}
@@ -648,9 +1201,9 @@ where
 			// Command blocks.
 			Some(token) if token.kind.is_command_block_starter() => {
 				let pos = token.pos;
-				self.token = Some(token);
+				self.token = Some(token.clone());
 
-				let block = self.parse_command_block()
+				let block = self.delimited(token, Self::parse_command_block)
 					.synchronize(self);
 
 				Ok(
@@ -662,60 +1215,66 @@ where
 			}
 
 			// If conditional.
-			Some(Token { kind: TokenKind::Keyword(Keyword::If), pos }) => {
+			Some(token @ Token { kind: TokenKind::Keyword(Keyword::If), .. }) => {
+				let pos = token.pos;
 				self.step();
 
-				let condition = self.parse_expression()
-					.synchronize(self);
-
-				self.expect(TokenKind::Keyword(Keyword::Then))
-					.with_sync(sync::Strategy::keep())
-					.synchronize(self);
-
-				let then = self.parse_block();
-
-				let otherwise = {
-					let has_else = self
-						.eat(
-							|token| match token {
-								Token { kind: TokenKind::Keyword(Keyword::End), .. } => Ok(false),
-								Token { kind: TokenKind::Keyword(Keyword::Else), .. } => Ok(true),
-								token => Err((Error::unexpected_msg(token.clone(), "end or else"), token)),
-							}
-						)
-						.with_sync(sync::Strategy::block_terminator())?;
-
-					if has_else {
-						let block = self.parse_block();
-
-						self.expect(TokenKind::Keyword(Keyword::End))
-							.with_sync(sync::Strategy::keyword(Keyword::End))?;
-
-						block
-					} else {
-						ast::Block::default()
-					}
-				};
+				self.delimited(token, |parser| {
+					let condition = parser.parse_expression()
+						.synchronize(parser);
+
+					parser.expect(TokenKind::Keyword(Keyword::Then))
+						.with_sync(sync::Strategy::keep())
+						.synchronize(parser);
+
+					let then = parser.parse_block();
+
+					let otherwise = {
+						let has_else = parser
+							.eat(
+								Expected::Message("'end' or 'else'"),
+								|token| match token {
+									Token { kind: TokenKind::Keyword(Keyword::End), .. } => Ok(false),
+									Token { kind: TokenKind::Keyword(Keyword::Else), .. } => Ok(true),
+									token => Err((Error::unexpected_msg(token.clone(), "end or else"), token)),
+								}
+							)
+							.with_sync(sync::Strategy::block_terminator())?;
+
+						if has_else {
+							let block = parser.parse_block();
+
+							parser.expect(TokenKind::Keyword(Keyword::End))
+								.with_sync(sync::Strategy::keyword(Keyword::End))?;
+
+							block
+						} else {
+							ast::Block::default()
+						}
+					};
 
-				Ok(ast::Expr::If {
-					condition: condition.into(),
-					then,
-					otherwise,
-					pos,
+					Ok(ast::Expr::If {
+						condition: condition.into(),
+						then,
+						otherwise,
+						pos,
+					})
 				})
 			}
 
 			// Parenthesis.
-			Some(Token { kind: TokenKind::OpenParens, .. }) => {
+			Some(token @ Token { kind: TokenKind::OpenParens, .. }) => {
 				self.step();
 
-				let expr = self.parse_expression()
-					.synchronize(self);
+				self.delimited(token, |parser| {
+					let expr = parser.parse_expression()
+						.synchronize(parser);
 
-				self.expect(TokenKind::CloseParens)
-					.with_sync(sync::Strategy::token(TokenKind::CloseParens))?;
+					parser.expect(TokenKind::CloseParens)
+						.with_sync(sync::Strategy::token(TokenKind::CloseParens))?;
 
-				Ok(expr)
+					Ok(expr)
+				})
 			}
 
 			// Some other unexpected token.
@@ -725,7 +1284,7 @@ where
 					.with_sync(sync::Strategy::keep())
 			}
 
-			None => Err(Error::unexpected_eof())
+			None => Err(self.unexpected_eof(Expected::Message("expression")))
 				.with_sync(sync::Strategy::eof()),
 		}
 	}
@@ -735,8 +1294,27 @@ where
 	fn parse_identifier(&mut self) -> sync::Result<(ast::Symbol, SourcePos), Error> {
 		self
 			.eat(
+				Expected::Message("identifier"),
+				|token| match token {
+					Token { kind: TokenKind::Identifier(symbol), pos } => Ok((symbol, pos)),
+					token => Err((Error::unexpected_msg(token.clone(), "identifier"), token)),
+				}
+			)
+			.with_sync(sync::Strategy::keep())
+	}
+
+
+	/// Parse a field name, which may be an identifier or a keyword. Keywords are reserved
+	/// everywhere else, but they are natural field names, such as in `range.end`.
+	fn parse_field_name(&mut self) -> sync::Result<(ast::Symbol, SourcePos), Error> {
+		let keywords = self.keywords;
+
+		self
+			.eat(
+				Expected::Message("identifier"),
 				|token| match token {
 					Token { kind: TokenKind::Identifier(symbol), pos } => Ok((symbol, pos)),
+					Token { kind: TokenKind::Keyword(keyword), pos } => Ok((keywords.get(keyword), pos)),
 					token => Err((Error::unexpected_msg(token.clone(), "identifier"), token)),
 				}
 			)
@@ -744,6 +1322,30 @@ where
 	}
 
 
+	/// Parse a dict literal key, which is either an identifier or a computed key between
+	/// brackets.
+	fn parse_dict_key(&mut self) -> ast::DictKey {
+		if let Some(Token { kind: TokenKind::OpenBracket, .. }) = &self.token {
+			self.step();
+
+			let key = self.parse_expression()
+				.synchronize(self);
+
+			self.expect(TokenKind::CloseBracket)
+				.with_sync(sync::Strategy::keep())
+				.synchronize(self);
+
+			ast::DictKey::Computed(key)
+		} else {
+			let (symbol, pos) = self.parse_field_name()
+				.with_sync(sync::Strategy::skip_one())
+				.synchronize(self);
+
+			ast::DictKey::Identifier(symbol, pos)
+		}
+	}
+
+
 	/// Parse a function literal after the function keyword.
 	/// Returns a pair of parameters and body.
 	#[allow(clippy::type_complexity)]
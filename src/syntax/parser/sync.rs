@@ -98,6 +98,16 @@ impl Strategy {
 			},
 
 			Self::Token { found: true, .. } => true,
+
+			// Statements and block terminators may not occur in parenthesized or bracketed
+			// expressions, except in function literals. Therefore, the closing token is most
+			// likely missing, and skipping further would discard valid statements, or the end
+			// of the enclosing block.
+			Self::Token {
+				token: TokenKind::CloseParens | TokenKind::CloseBracket,
+				..
+			} if token.is_statement_boundary() || token.is_block_terminator() => true,
+
 			Self::Token { token: expected, found } => {
 				*found = token == expected;
 				false
@@ -1,12 +1,13 @@
 use std::{
 	ffi::OsStr,
 	fs::File,
-	os::unix::ffi::OsStrExt,
 };
 
 use crate::{
+	ffi::OsStrExt,
 	fmt::{self, Display},
 	symbol::{self, Symbol},
+	term::color,
 };
 
 
@@ -48,6 +49,8 @@ impl Source {
 
 
 /// A human readable position in the source code.
+/// Lines and columns start at 1. Columns count characters rather than bytes, and a tab
+/// counts as a single character.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SourcePos {
 	pub line: u32,
@@ -56,16 +59,242 @@ pub struct SourcePos {
 }
 
 
+impl SourcePos {
+	/// The snippet of source code for this position.
+	pub fn snippet(self) -> Snippet {
+		Snippet(self)
+	}
+
+
+	/// The source text of each argument of the call whose opening parenthesis is at this
+	/// position. Comments are dropped, and whitespace outside of quotes is collapsed, so
+	/// that multi-line arguments are rendered in a single line.
+	/// Returns None if the position doesn't refer to a well-formed argument list.
+	pub fn call_arguments(self, source: &[u8]) -> Option<Vec<String>> {
+		let line = source
+			.split(|&c| c == b'\n')
+			.take(self.line.checked_sub(1)? as usize)
+			.map(|line| line.len() + 1)
+			.sum::<usize>();
+
+		// Columns count characters, so find the byte offset of the column's character.
+		let offset = source[line.min(source.len())..]
+			.iter()
+			.enumerate()
+			.filter(|(_, &byte)| byte & 0xC0 != 0x80)
+			.map(|(offset, _)| line + offset)
+			.nth((self.column as usize).checked_sub(1)?)?;
+
+		if source.get(offset) != Some(&b'(') {
+			return None;
+		}
+
+		let mut arguments = Vec::new();
+		let mut argument = Vec::new();
+		let mut depth = 0_usize;
+		let mut bytes = source[offset + 1 ..].iter().copied().peekable();
+
+		// Whitespace is only pushed when followed by something else.
+		let mut space = false;
+		let push = |argument: &mut Vec<u8>, space: &mut bool, byte: u8| {
+			if std::mem::take(space) && !argument.is_empty() {
+				argument.push(b' ');
+			}
+			argument.push(byte);
+		};
+
+		loop {
+			match bytes.next()? {
+				b')' if depth == 0 => break,
+
+				b',' if depth == 0 => {
+					arguments.push(std::mem::take(&mut argument));
+					space = false;
+				}
+
+				byte @ (b'(' | b'[' | b'{') => {
+					depth += 1;
+					push(&mut argument, &mut space, byte);
+				}
+
+				byte @ (b')' | b']' | b'}') => {
+					depth = depth.checked_sub(1)?;
+					push(&mut argument, &mut space, byte);
+				}
+
+				quote @ (b'"' | b'\'') => {
+					push(&mut argument, &mut space, quote);
+					loop {
+						let byte = bytes.next()?;
+						argument.push(byte);
+						match byte {
+							b'\\' => argument.push(bytes.next()?),
+							byte if byte == quote => break,
+							_ => (),
+						}
+					}
+				}
+
+				b'#' if bytes.peek() == Some(&b'[') => {
+					// Block comment, which ends in `]]#`.
+					let mut closing = 0;
+					loop {
+						closing = match (bytes.next()?, closing) {
+							(b']', 0 | 1) => closing + 1,
+							(b']', 2) => 2,
+							(b'#', 2) => break,
+							_ => 0,
+						};
+					}
+					space = true;
+				}
+
+				b'#' => {
+					bytes.find(|&byte| byte == b'\n')?;
+					space = true;
+				}
+
+				byte if byte.is_ascii_whitespace() => space = true,
+
+				byte => push(&mut argument, &mut space, byte),
+			}
+		}
+
+		if !argument.is_empty() || !arguments.is_empty() {
+			arguments.push(argument);
+		}
+
+		Some(
+			arguments
+				.into_iter()
+				.map(|argument| String::from_utf8_lossy(&argument).into_owned())
+				.collect()
+		)
+	}
+}
+
+
 impl<'a> Display<'a> for SourcePos {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		// Line zero refers to the whole file.
+		if self.line == 0 {
+			write!(f, "{}", fmt::Show(self.path, context))
+		} else {
+			write!(
+				f,
+				"{}:{}:{}",
+				fmt::Show(self.path, context),
+				self.line,
+				self.column
+			)
+		}
+	}
+}
+
+
+/// The source line of a position, with a caret beneath the column.
+/// Displays nothing if the source code is not available in the interner.
+/// When displayed, each line of the snippet is preceded by a line break, so that it may
+/// be appended to an error message.
+#[derive(Debug, Clone, Copy)]
+pub struct Snippet(pub SourcePos);
+
+
+impl Snippet {
+	/// How many columns a tab stop spans.
+	const TAB_WIDTH: usize = 4;
+	/// Lines longer than this are truncated around the column.
+	const MAX_WIDTH: usize = 80;
+	/// Marks truncated text.
+	const ELLIPSIS: &'static str = "...";
+
+
+	/// Expand tabs to spaces, so that the caret is aligned regardless of the terminal.
+	/// Other unprintable characters are replaced, as they would mess with the terminal.
+	fn expand(text: &str) -> Vec<char> {
+		let mut chars = Vec::with_capacity(text.len());
+
+		for c in text.chars() {
+			match c {
+				'\t' => {
+					let spaces = Self::TAB_WIDTH - chars.len() % Self::TAB_WIDTH;
+					chars.extend(std::iter::repeat_n(' ', spaces));
+				}
+
+				c if fmt::is_unprintable(c) => chars.push(char::REPLACEMENT_CHARACTER),
+
+				c => chars.push(c),
+			}
+		}
+
+		chars
+	}
+}
+
+
+impl<'a> Display<'a> for Snippet {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let SourcePos { line, column, path } = self.0;
+
+		let text = match (context.source(path), line.checked_sub(1)) {
+			(Some(source), Some(ix)) => match source.split(|&c| c == b'\n').nth(ix as usize) {
+				Some(text) => text.strip_suffix(b"\r").unwrap_or(text),
+				None => return Ok(()),
+			},
+
+			_ => return Ok(()),
+		};
+
+		// Columns count characters, so find the byte offset of the column's character.
+		let column = text
+			.iter()
+			.enumerate()
+			.filter(|(_, &byte)| byte & 0xC0 != 0x80)
+			.map(|(offset, _)| offset)
+			.nth((column as usize).saturating_sub(1))
+			.unwrap_or(text.len());
+		let mut caret = Self::expand(&String::from_utf8_lossy(&text[..column])).len();
+		let mut chars = Self::expand(&String::from_utf8_lossy(text));
+
+		// Keep the caret in the middle of the window when truncating.
+		if chars.len() > Self::MAX_WIDTH {
+			let start = caret
+				.saturating_sub(Self::MAX_WIDTH / 2)
+				.min(chars.len() - Self::MAX_WIDTH);
+			let end = start + Self::MAX_WIDTH;
+
+			let mut window: Vec<char> = Vec::with_capacity(Self::MAX_WIDTH + 2 * Self::ELLIPSIS.len());
+
+			if start > 0 {
+				window.extend(Self::ELLIPSIS.chars());
+			}
+
+			caret = caret - start + window.len();
+			window.extend(&chars[start..end]);
+
+			if end < chars.len() {
+				window.extend(Self::ELLIPSIS.chars());
+			}
+
+			chars = window;
+		}
+
+		let line = line.to_string();
+		let gutter = " ".repeat(line.len());
+		let text: String = chars.into_iter().collect();
+
+		write!(f, "\n{} {} {}", color::Fg(color::Blue, &line), color::Fg(color::Blue, "|"), text)?;
 		write!(
 			f,
-			"{} (line {}, column {})",
-			fmt::Show(self.path, context),
-			self.line,
-			self.column
+			"\n{} {} {}{}",
+			gutter,
+			color::Fg(color::Blue, "|"),
+			" ".repeat(caret),
+			color::Fg(color::Red, "^"),
 		)
 	}
 }
@@ -4,8 +4,8 @@ use std::{
 	os::unix::ffi::OsStrExt,
 };
 
-use crate::{fmt, symbol, syntax::AnalysisDisplayContext, tests};
-use super::{Analysis, Source};
+use crate::{fmt, symbol, syntax::AnalysisDisplayContext, term::color, tests};
+use super::{ast, parser, Analysis, Error, Source};
 
 
 fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
@@ -63,3 +63,385 @@ fn test_negative() -> io::Result<()> {
 		|analysis| !analysis.errors.is_empty(),
 	)
 }
+
+
+/// The positions of the errors in the given source.
+fn error_positions(contents: &[u8]) -> Vec<(u32, u32)> {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: contents.into() };
+
+	let analysis = Analysis::analyze(&source, &mut interner);
+
+	analysis.errors.0
+		.iter()
+		.map(
+			|error| match error {
+				Error::Lexer(error) => (error.pos.line, error.pos.column),
+				Error::Parser(parser::Error::Unexpected { token, .. }) => (token.pos.line, token.pos.column),
+				Error::Parser(parser::Error::EmptyCommandBlock { pos }) => (pos.line, pos.column),
+				Error::Parser(parser::Error::UnexpectedEof { .. }) => (u32::MAX, u32::MAX),
+				Error::Parser(error) => panic!("unexpected error: {:?}", error),
+			}
+		)
+		.collect()
+}
+
+
+#[test]
+fn test_error_recovery() {
+	let positions = error_positions(
+		b"let a = f(1 2)
+		let b = 1
+		if b then
+			let c = ]
+		end
+		let d = @[ a: , b: 2 ]
+		std.print(a, b)
+		"
+	);
+
+	assert_eq!(positions, [ (1, 13), (4, 12), (6, 17) ]);
+}
+
+
+#[test]
+fn test_error_cascade() {
+	let positions = error_positions(
+		b"let a = ) ) ] , = ) ( ]
+		let b = [ 1 2 3 4 5 6 ]
+		let c = (1 + ) * ( 2 +
+		let d = 4
+		"
+	);
+
+	assert_eq!(positions, [ (1, 9), (2, 15), (3, 16), (4, 3) ]);
+}
+
+
+#[test]
+fn test_independent_errors() {
+	// An incomplete statement at the end of a block doesn't consume the block's end.
+	let positions = error_positions(
+		b"function first()
+			let x = 1 +
+		end
+
+		function second(a)
+			std.print(a b)
+		end
+
+		if true then
+			let y =
+		else
+			let z = (1
+		end
+		"
+	);
+
+	assert_eq!(positions, [ (3, 3), (6, 16), (11, 3), (13, 3) ]);
+}
+
+
+fn unexpected_eof(contents: &[u8]) -> String {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: contents.into() };
+
+	let analysis = Analysis::analyze(&source, &mut interner);
+
+	let error = analysis.errors.0
+		.iter()
+		.find_map(
+			|error| match error {
+				Error::Parser(error @ parser::Error::UnexpectedEof { .. }) => Some(error),
+				_ => None,
+			}
+		)
+		.expect("expected an unexpected eof error");
+
+	// Only the message, without the source snippet.
+	fmt::Show(error, &interner)
+		.to_string()
+		.lines()
+		.next()
+		.unwrap_or_default()
+		.to_owned()
+}
+
+
+#[test]
+fn test_eof_missing_end() {
+	assert_eq!(
+		unexpected_eof(b"let x = 1\n\nwhile x < 3 do\n\tx = x + 1\n"),
+		"unexpected end of file: expected 'end' to close 'while' started at <test>:3:1",
+	);
+
+	assert_eq!(
+		unexpected_eof(b"if true then\n\tstd.print(1)\n"),
+		"unexpected end of file: expected 'end' or 'else' in 'if' started at <test>:1:1",
+	);
+}
+
+
+#[test]
+fn test_eof_unclosed_parens() {
+	assert_eq!(
+		unexpected_eof(b"let x = (1 + 2\n"),
+		"unexpected end of file: expected ')' to close '(' started at <test>:1:9",
+	);
+
+	assert_eq!(
+		unexpected_eof(b"std.print(1, 2\n"),
+		"unexpected end of file: expected ')' to close '(' started at <test>:1:10",
+	);
+}
+
+
+#[test]
+fn test_eof_unterminated_dict() {
+	assert_eq!(
+		unexpected_eof(b"let d = @[ a: 1, b: 2\n"),
+		"unexpected end of file: expected ']' to close '@[' started at <test>:1:9",
+	);
+}
+
+
+
+#[test]
+fn test_eof_unterminated_interpolation() {
+	assert_eq!(
+		unexpected_eof(b"let s = \"a ${x + 1\n"),
+		"unexpected end of file: expected end of interpolation in '\"a ${' started at <test>:1:9",
+	);
+
+	assert_eq!(
+		first_error(b"let s = \"a ${x\"\nlet y = 1\n"),
+		"<test>:1:12 - unterminated string interpolation, expected '}'.\n\
+		 1 | let s = \"a ${x\"\n  \
+		   |            ^",
+	);
+}
+
+
+fn first_error(contents: &[u8]) -> String {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: contents.into() };
+
+	let analysis = Analysis::analyze(&source, &mut interner);
+
+	let error = analysis.errors.0
+		.first()
+		.expect("expected a syntax error");
+
+	fmt::Show(error, &interner).to_string()
+}
+
+
+#[test]
+fn test_error_snippet() {
+	assert_eq!(
+		first_error(b"let x = 1\nif x == 1 then\n\tstd.print(x 2)\nend\n"),
+		"<test>:3:14 - unexpected '2', expected ')'\n\
+		 3 |     std.print(x 2)\n  \
+		   |                 ^",
+	);
+
+	// Tabs expand to the next tab stop.
+	assert_eq!(
+		first_error(b"\tlet x =\t)\n"),
+		"<test>:1:10 - unexpected ')', expected expression\n\
+		 1 |     let x = )\n  \
+		   |             ^",
+	);
+
+	// Multi-byte characters take a single column.
+	assert_eq!(
+		first_error("std.print(\"ü\" 2)\n".as_bytes()),
+		"<test>:1:15 - unexpected '2', expected ')'\n\
+		 1 | std.print(\"ü\" 2)\n  \
+		   |               ^",
+	);
+}
+
+
+#[test]
+fn test_error_colors() {
+	let source = b"let x = 1\nstd.print(x 2)\n";
+
+	color::set_mode(color::Mode::Never);
+	let plain = first_error(source);
+
+	color::set_mode(color::Mode::Always);
+	let colored = first_error(source);
+
+	color::set_mode(color::Mode::Never);
+
+	assert!(!plain.contains('\x1b'));
+	assert!(colored.contains('\x1b'));
+	assert_eq!(tests::util::strip_escapes(&colored), plain);
+}
+
+
+#[test]
+fn test_error_snippet_long_line() {
+	let line = format!("let x = [ {}1 2{} ]", "1, ".repeat(40), ", 1".repeat(40));
+
+	// The line is truncated to 80 columns centered on the offending token.
+	assert_eq!(
+		first_error(line.as_bytes()),
+		format!(
+			"<test>:1:133 - unexpected '2', expected ']'\n1 | ...{}...\n  | {}^",
+			&line[92 .. 172],
+			" ".repeat(43),
+		),
+	);
+}
+
+
+#[test]
+fn test_lexer_error_snippet() {
+	assert_eq!(
+		first_error(b"let x = 1\0\n"),
+		"<test>:1:10 - invalid character 0x00.\n\
+		 1 | let x = 1\u{FFFD}\n  \
+		   |          ^",
+	);
+
+	assert_eq!(
+		first_error(b"let x = 1\nlet s = \"abc\n"),
+		"<test>:2:9 - unterminated string literal.\n\
+		 2 | let s = \"abc\n  \
+		   |         ^",
+	);
+
+	assert_eq!(
+		first_error(b"let s = \"a\\qb\"\n"),
+		"<test>:1:11 - invalid escape sequence '\\q'.\n\
+		 1 | let s = \"a\\qb\"\n  \
+		   |           ^",
+	);
+}
+
+
+/// Analyze the given source, returning the errors' messages. This runs in a thread with a
+/// stack as large as the main thread's, because the default stack for tests is too small
+/// for deeply nested input in debug builds.
+fn nested_errors(contents: String) -> Vec<String> {
+	std::thread::Builder::new()
+		.stack_size(8 * 1024 * 1024)
+		.spawn(
+			move || {
+				let mut interner = symbol::Interner::new();
+				let path = interner.get_or_intern("<test>");
+				let source = Source { path, contents: contents.into_bytes().into() };
+
+				let analysis = Analysis::analyze(&source, &mut interner);
+
+				analysis.errors.0
+					.iter()
+					.map(|error| fmt::Show(error, &interner).to_string())
+					.collect()
+			}
+		)
+		.expect("failed to spawn thread")
+		.join()
+		.expect("parser panicked")
+}
+
+
+#[test]
+fn test_nesting_too_deep() {
+	const DEPTH: usize = 100_000;
+
+	let inputs = [
+		format!("let x = {}1{}", "(".repeat(DEPTH), ")".repeat(DEPTH)),
+		format!("let x = {}1", "-".repeat(DEPTH)),
+		format!("let x = {}{}", "[".repeat(DEPTH), "]".repeat(DEPTH)),
+		format!("let x = {}1{}", "@[ a: ".repeat(DEPTH), " ]".repeat(DEPTH)),
+		format!("{}{}", "if true then ".repeat(DEPTH), "end ".repeat(DEPTH)),
+		format!("let {} = x", "[".repeat(DEPTH)),
+	];
+
+	for input in inputs {
+		let errors = nested_errors(input);
+
+		assert_eq!(errors.len(), 1, "{:?}", errors);
+		assert!(errors[0].contains("nesting is too deep, the maximum depth is 128"), "{}", errors[0]);
+	}
+}
+
+
+#[test]
+fn test_reasonable_nesting() {
+	const DEPTH: usize = 50;
+
+	let inputs = [
+		format!("let x = {}1{}", "(".repeat(DEPTH), ")".repeat(DEPTH)),
+		format!("let x = {}1", "- ".repeat(DEPTH)),
+		format!("let x = {}{}", "[".repeat(DEPTH), "]".repeat(DEPTH)),
+		format!("let x = {}1{}", "@[ a: ".repeat(DEPTH), " ]".repeat(DEPTH)),
+		format!("{}{}", "if true then ".repeat(DEPTH), "end ".repeat(DEPTH)),
+		format!("let {}x{} = y", "[".repeat(DEPTH), "]".repeat(DEPTH)),
+	];
+
+	for input in inputs {
+		assert_eq!(nested_errors(input), Vec::<String>::new());
+	}
+}
+
+
+/// The single expression in the given source, fully parenthesized.
+fn expr_structure(contents: &[u8]) -> String {
+	fn structure(expr: &ast::Expr) -> String {
+		match expr {
+			ast::Expr::Literal { literal: ast::Literal::Int(int), .. } => int.to_string(),
+			ast::Expr::UnaryOp { op, operand, .. } => format!("({:?} {})", op, structure(operand)),
+			ast::Expr::BinaryOp { left, op, right, .. } => format!(
+				"({} {:?} {})",
+				structure(left),
+				op,
+				structure(right)
+			),
+			other => panic!("unexpected expression: {:?}", other),
+		}
+	}
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: contents.into() };
+
+	let analysis = Analysis::analyze(&source, &mut interner);
+	assert!(analysis.errors.is_empty(), "{:?}", analysis.errors.0);
+
+	match analysis.ast.statements {
+		ast::Block::Block(ref statements) => match statements.as_ref() {
+			[ ast::Statement::Expr(expr) ] => structure(expr),
+			other => panic!("expected a single expression: {:?}", other),
+		},
+		ast::Block::IllFormed => panic!("ill-formed block"),
+	}
+}
+
+
+#[test]
+fn test_arithmetic_precedence() {
+	let cases: &[(&[u8], &str)] = &[
+		// Exponentiation is right associative.
+		(b"2 ** 3 ** 2", "(2 Pow (3 Pow 2))"),
+		// Exponentiation binds tighter than prefix operators on its left, but not on its right.
+		(b"-2 ** 2", "(Minus (2 Pow 2))"),
+		(b"2 ** -1", "(2 Pow (Minus 1))"),
+		(b"2 ** -3 ** 2", "(2 Pow (Minus (3 Pow 2)))"),
+		(b"2 * 3 ** 2", "(2 Times (3 Pow 2))"),
+		// Floor division has the precedence of multiplication.
+		(b"7 // 2 * 3", "((7 FloorDiv 2) Times 3)"),
+		(b"1 + 7 // 2", "(1 Plus (7 FloorDiv 2))"),
+		(b"7 / 2 // 3 % 4", "(((7 Div 2) FloorDiv 3) Mod 4)"),
+	];
+
+	for (source, expected) in cases {
+		assert_eq!(expr_structure(source), *expected, "{}", String::from_utf8_lossy(source));
+	}
+}
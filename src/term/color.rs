@@ -1,28 +1,157 @@
 use std::{
-	io,
+	cell::Cell,
 	fmt::{self, Debug, Display},
+	str::FromStr,
 };
 
-pub use termion::color::{Black, Blue, Green, Red, Yellow};
+
+/// A terminal color, as an index in the 256 color palette.
+pub trait Color: Copy {
+	const INDEX: u8;
+}
+
+
+macro_rules! colors {
+	($($name: ident = $index: literal),* $(,)?) => {
+		$(
+			#[derive(Debug, Clone, Copy)]
+			pub struct $name;
+
+			impl Color for $name {
+				const INDEX: u8 = $index;
+			}
+		)*
+	}
+}
+
+
+colors!(Black = 0, Red = 1, Green = 2, Yellow = 3, Blue = 4);
+
+
+/// Escape codes, which are written verbatim.
+#[derive(Debug, Clone, Copy)]
+struct Escape(&'static str);
+
+
+impl Display for Escape {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.0)
+	}
+}
+
+
+const RESET_FG: Escape = Escape("\x1b[39m");
+const RESET_STYLE: Escape = Escape("\x1b[m");
+const BOLD: Escape = Escape("\x1b[1m");
+
+
+/// The escape code to set the foreground color.
+struct SetFg(u8);
+
+
+impl Display for SetFg {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "\x1b[38;5;{}m", self.0)
+	}
+}
+
+
+/// When to emit color escape codes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+	Always,
+	Never,
+	/// Color only if both stdout and stderr are terminals, and NO_COLOR is unset.
+	#[default]
+	Auto,
+}
+
+
+impl Mode {
+	/// Whether colors should be emitted in this mode.
+	pub fn enabled(self) -> bool {
+		match self {
+			Self::Always => true,
+			Self::Never => false,
+			Self::Auto => {
+				// As per https://no-color.org, an empty NO_COLOR is the same as unset.
+				let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+				!no_color && is_tty()
+			}
+		}
+	}
+}
+
+
+/// Whether both stdout and stderr are terminals.
+#[cfg(feature = "system")]
+fn is_tty() -> bool {
+	termion::is_tty(&std::io::stdout()) && termion::is_tty(&std::io::stderr())
+}
+
+
+/// Without the system feature, output never goes to a terminal.
+#[cfg(not(feature = "system"))]
+fn is_tty() -> bool {
+	false
+}
+
+
+impl FromStr for Mode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"always" => Ok(Self::Always),
+			"never" => Ok(Self::Never),
+			"auto" => Ok(Self::Auto),
+			other => Err(format!("invalid color mode: {}", other)),
+		}
+	}
+}
 
 
 thread_local! {
-	static IS_TTY: bool = termion::is_tty(&io::stdout())
-		&& termion::is_tty(&io::stderr());
+	/// Colors are disabled until enabled with `set_mode`, so that escape codes never end up
+	/// in writers of embedders which didn't opt in.
+	static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// Set when to emit color escape codes in the current thread.
+pub fn set_mode(mode: Mode) {
+	ENABLED.with(|enabled| enabled.set(mode.enabled()));
+}
+
+
+/// Whether color escape codes are emitted in the current thread.
+pub fn enabled() -> bool {
+	ENABLED.with(Cell::get)
+}
+
+
+/// Run the given function with colors disabled. This should be used when formatting text
+/// that is not written to the terminal, such as strings handed to scripts.
+pub fn plain<F, R>(f: F) -> R
+where
+	F: FnOnce() -> R,
+{
+	let enabled = ENABLED.with(|enabled| enabled.replace(false));
+	let result = f();
+	ENABLED.with(|cell| cell.set(enabled));
+	result
 }
 
 
 macro_rules! tty_fmt {
 	($f: expr, $open: expr, $value: expr, $close: expr) => {
-		IS_TTY.with(
-			|&is_tty| if is_tty {
-				write!($f, "{}", $open)?;
-				$value.fmt($f)?;
-				write!($f, "{}", $close)
-			} else {
-				$value.fmt($f)
-			}
-		)
+		if enabled() {
+			write!($f, "{}", $open)?;
+			$value.fmt($f)?;
+			write!($f, "{}", $close)
+		} else {
+			$value.fmt($f)
+		}
 	}
 }
 
@@ -33,15 +162,15 @@ pub struct Fg<C, T>(pub C, pub T);
 
 impl<C, T> Debug for Fg<C, T>
 where
-	C: termion::color::Color + Copy,
+	C: Color,
 	T: Debug,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		tty_fmt!(
 			f,
-			termion::color::Fg(self.0),
+			SetFg(C::INDEX),
 			self.1,
-			termion::color::Fg(termion::color::Reset)
+			RESET_FG
 		)
 	}
 }
@@ -49,15 +178,15 @@ where
 
 impl<C, T> Display for Fg<C, T>
 where
-	C: termion::color::Color + Copy,
+	C: Color,
 	T: Display,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		tty_fmt!(
 			f,
-			termion::color::Fg(self.0),
+			SetFg(C::INDEX),
 			self.1,
-			termion::color::Fg(termion::color::Reset)
+			RESET_FG
 		)
 	}
 }
@@ -77,7 +206,7 @@ where
 			f,
 			self.0,
 			self.1,
-			termion::style::Reset
+			RESET_STYLE
 		)
 	}
 }
@@ -93,7 +222,7 @@ where
 			f,
 			self.0,
 			self.1,
-			termion::style::Reset
+			RESET_STYLE
 		)
 	}
 }
@@ -108,7 +237,7 @@ where
 	T: Debug,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		Style(termion::style::Bold, &self.0).fmt(f)
+		Style(BOLD, &self.0).fmt(f)
 	}
 }
 
@@ -118,6 +247,6 @@ where
 	T: Display,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		Style(termion::style::Bold, &self.0).fmt(f)
+		Style(BOLD, &self.0).fmt(f)
 	}
 }
@@ -0,0 +1,88 @@
+use crate::{
+	diagnostic::{Format, Sink},
+	semantic::Warning,
+	symbol,
+	syntax::SourcePos,
+	term::color,
+};
+
+
+/// Report the warnings through a sink, returning the output lines.
+fn report(
+	warnings: &[Warning],
+	format: Format,
+	max_warnings: Option<usize>,
+	interner: &symbol::Interner,
+) -> Vec<String> {
+	let mut output = Vec::new();
+
+	color::plain(|| {
+		let mut sink = Sink::new(&mut output, format, max_warnings);
+
+		for warning in warnings {
+			sink.warning(warning, interner);
+		}
+
+		sink.finish();
+	});
+
+	String::from_utf8(output)
+		.expect("invalid utf-8 output")
+		.lines()
+		.map(ToOwned::to_owned)
+		.collect()
+}
+
+
+/// Unused variable warnings at the given lines.
+fn unused(lines: &[u32], interner: &mut symbol::Interner) -> Vec<Warning> {
+	let path = interner.get_or_intern("<test>");
+	let symbol = interner.get_or_intern("x");
+
+	lines
+		.iter()
+		.map(|&line| Warning::unused_variable(symbol, SourcePos { line, column: 1, path }))
+		.collect()
+}
+
+
+#[test]
+fn test_warning_dedup() {
+	let mut interner = symbol::Interner::new();
+	let warnings = unused(&[ 1, 2, 1, 1, 2, 3 ], &mut interner);
+
+	let output = report(&warnings, Format::Human, None, &interner);
+	assert_eq!(
+		output,
+		[
+			"Warning: <test>:1:1 - unused variable 'x'",
+			"Warning: <test>:2:1 - unused variable 'x'",
+			"Warning: <test>:3:1 - unused variable 'x'",
+			"Warning: 3 duplicate warnings suppressed",
+		]
+	);
+}
+
+
+#[test]
+fn test_warning_cap() {
+	let mut interner = symbol::Interner::new();
+	let lines: Vec<u32> = (1 ..= 50).chain(1 ..= 50).collect();
+	let warnings = unused(&lines, &mut interner);
+
+	let output = report(&warnings, Format::Human, Some(10), &interner);
+	assert_eq!(output.len(), 12);
+	assert_eq!(output[9], "Warning: <test>:10:1 - unused variable 'x'");
+	assert_eq!(output[10], "Warning: 40 more warnings suppressed");
+	assert_eq!(output[11], "Warning: 50 duplicate warnings suppressed");
+
+	let output = report(&warnings, Format::Json, Some(10), &interner);
+	assert_eq!(output.len(), 12);
+	let summary: serde_json::Value = serde_json::from_str(&output[10]).expect("invalid JSON");
+	assert_eq!(summary["code"], "warnings-suppressed");
+	assert_eq!(summary["message"], "40 more warnings suppressed");
+
+	// Without a cap, all distinct warnings are reported.
+	let output = report(&warnings, Format::Human, None, &interner);
+	assert_eq!(output.len(), 51);
+}
@@ -0,0 +1,184 @@
+use crate::{
+	engine::{Engine, Error},
+	runtime::{self, value::{Dict, Value}, Interpreter, Panic},
+	term::color,
+};
+
+
+#[test]
+fn test_eval_str() {
+	let mut engine = Engine::new();
+
+	assert_eq!(engine.eval_str("let x = 20\nx * 2 + 2").ok(), Some(Value::Int(42)));
+
+	// Values returned by previous evaluations remain usable.
+	let add = engine
+		.eval_str("function (a, b) a + b end")
+		.expect("unexpected error");
+	assert_eq!(
+		engine.runtime().call_value(&add, vec![ 1_i64.into(), 2_i64.into() ]).ok(),
+		Some(Value::Int(3)),
+	);
+}
+
+
+#[test]
+fn test_eval_input() {
+	let mut engine = Engine::new();
+
+	assert_eq!(engine.eval_input("let x = 1").ok(), Some(Value::Nil));
+	assert_eq!(engine.eval_input("x + 1").ok(), Some(Value::Int(2)));
+
+	// Functions may use and change the variables of previous inputs.
+	engine
+		.eval_input("let count = 0\nfunction increment() count = count + x end")
+		.expect("unexpected error");
+	engine.eval_input("increment()\nincrement()").expect("unexpected error");
+	assert_eq!(engine.eval_input("count").ok(), Some(Value::Int(2)));
+
+	// Inputs with errors declare nothing, and the session goes on.
+	assert!(matches!(engine.eval_input("let y = )"), Err(Error::Syntax(_))));
+	assert!(matches!(engine.eval_input("let y = 1\nundeclared"), Err(Error::Semantic(_))));
+	assert!(matches!(engine.eval_input("y"), Err(Error::Semantic(_))));
+	assert!(matches!(engine.eval_input("x = 5\nstd.assert(false)"), Err(Error::Panic(_))));
+	assert_eq!(engine.eval_input("x").ok(), Some(Value::Int(5)));
+
+	// Programs evaluated on their own don't see the session.
+	assert!(matches!(engine.eval_str("x"), Err(Error::Semantic(_))));
+	assert_eq!(engine.eval_str("let x = 10\nx").ok(), Some(Value::Int(10)));
+	assert_eq!(engine.eval_input("x").ok(), Some(Value::Int(5)));
+}
+
+
+#[test]
+fn test_deep_recursion() {
+	// The default maximum call depth fits in the stack it assumes.
+	let result = std::thread::Builder::new()
+		.stack_size(runtime::DEFAULT_STACK_SIZE)
+		.spawn(
+			|| {
+				let sources = [
+					"let f = function (n) return f(n + 1) end\nf(0)",
+					"let f = function (n) 1 + f(n + 1) end\nf(0)",
+					"let d = @[ ]\nd.f = function (n) [ @[ n: d.f(n + 1) ] ] end\nd.f(0)",
+				];
+
+				for interpreter in [ Interpreter::TreeWalker, Interpreter::Vm ] {
+					let mut engine = Engine::new();
+					engine.runtime().set_interpreter(interpreter);
+
+					for source in &sources {
+						match engine.eval_str(source) {
+							Err(Error::Panic(panic)) => assert!(
+								matches!(panic.untraced(), Panic::StackOverflow { .. }),
+								"{:?}: {:?}",
+								interpreter,
+								panic,
+							),
+							other => panic!("expected stack overflow, got {:?}", other),
+						}
+					}
+				}
+			}
+		)
+		.expect("failed to spawn thread")
+		.join();
+
+	if let Err(panic) = result {
+		std::panic::resume_unwind(panic);
+	}
+}
+
+
+#[test]
+fn test_errors() {
+	let mut engine = Engine::new();
+
+	let message = |engine: &Engine, error: &Error| color::plain(|| engine.format_error(error));
+
+	let error = engine.eval_str("let x = \"abc").expect_err("expected error");
+	assert!(matches!(error, Error::Syntax(_)));
+	assert!(message(&engine, &error).contains("<eval>:1:9"), "{}", message(&engine, &error));
+
+	let error = engine.eval_str("let x = (1 +").expect_err("expected error");
+	assert!(matches!(error, Error::Syntax(_)));
+
+	let error = engine.eval_str("\n  undeclared").expect_err("expected error");
+	assert!(matches!(error, Error::Semantic(_)));
+	assert!(message(&engine, &error).contains("<eval>:2:3"), "{}", message(&engine, &error));
+
+	let error = engine.eval_str("std.assert(false)").expect_err("expected error");
+	match &error {
+		Error::Panic(panic) => {
+			assert!(matches!(panic.untraced(), Panic::AssertionFailed { .. }), "{:?}", panic);
+			assert_eq!((panic.pos().line, panic.pos().column), (1, 11));
+		}
+		other => panic!("expected panic, got {:?}", other),
+	}
+	assert!(message(&engine, &error).starts_with("Panic in <eval>:1:11: assertion failed: false"));
+}
+
+
+#[test]
+fn test_compile_once() {
+	let mut engine = Engine::new();
+
+	let program = engine
+		.compile("twice.hsh", b"let array = [ 1 ]\nstd.push(array, 2)\narray")
+		.expect("unexpected error");
+
+	// Each evaluation starts from a clean state.
+	for _ in 0 .. 2 {
+		assert_eq!(
+			engine.eval(&program).ok(),
+			Some(Value::from(vec![ Value::Int(1), Value::Int(2) ])),
+		);
+	}
+
+	let error = engine.compile("broken.hsh", b"let x = )").expect_err("expected error");
+	assert!(color::plain(|| engine.format_error(&error)).contains("broken.hsh"));
+}
+
+
+#[test]
+fn test_host_members() {
+	let mut engine = Engine::new();
+
+	engine.runtime().register_fn(
+		"std.app.add",
+		2,
+		|context| match context.args() {
+			[ Value::Int(a), Value::Int(b) ] => a
+				.checked_add(*b)
+				.map(Value::Int)
+				.ok_or_else(|| Panic::integer_overflow(context.pos.copy())),
+			[ Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(1))),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "int", context.arg_pos(0))),
+			_ => unreachable!("arity is checked before calling"),
+		}
+	);
+
+	let config = Dict::default();
+	config.insert("name".into(), "hush".into());
+	engine.runtime().register_value("std.app.config", config.copy().into());
+	engine.runtime().register_value("std.app.version", Value::Int(3));
+
+	assert_eq!(
+		engine.eval_str("std.app.add(std.app.version, 39)").ok(),
+		Some(Value::Int(42)),
+	);
+	assert_eq!(
+		engine.eval_str("std.app.config.name ++ \"!\"").ok(),
+		Some(Value::from("hush!")),
+	);
+
+	// Registered values are shared with the host.
+	engine.eval_str("let config = std.app.config\nconfig.debug = true").expect("unexpected error");
+	assert_eq!(config.get(&"debug".into()).ok(), Some(Value::Bool(true)));
+
+	let error = engine.eval_str("std.app.add(1, \"2\")").expect_err("expected error");
+	assert!(matches!(error, Error::Panic(ref panic) if matches!(panic.untraced(), Panic::TypeError { .. })));
+
+	// The stdlib is untouched.
+	assert_eq!(engine.eval_str("std.len(\"abc\")").ok(), Some(Value::Int(3)));
+}
@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use serial_test::serial;
+
+use crate::{args::{self, Command}, run, ExitStatus};
+
+
+/// Run the interpreter on the given script, relative to the crate root.
+fn exit_status(script: &str) -> ExitStatus {
+	let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	path.push(script);
+	assert!(path.is_file(), "missing script {}", path.display());
+
+	let args = [ "hush".as_ref(), "--no-warnings".as_ref(), path.as_os_str() ];
+
+	match args::parse(args.iter().copied()) {
+		Ok(Command::Run(args)) => run(args),
+		other => panic!("unexpected command: {:?}", other),
+	}
+}
+
+
+/// Run the interpreter on the given source, written to a temporary script, with the given
+/// arguments.
+fn source_exit_status(name: &str, source: &str, flags: &[&str]) -> ExitStatus {
+	let mut path = std::env::temp_dir();
+	path.push(format!("hush-{}-{}.hsh", std::process::id(), name));
+	std::fs::write(&path, source).expect("failed to write script");
+
+	let mut args: Vec<&std::ffi::OsStr> = vec![ "hush".as_ref(), "--no-warnings".as_ref() ];
+	args.extend(flags.iter().map(std::ffi::OsStr::new));
+	args.push(path.as_os_str());
+
+	let status = match args::parse(args.iter().copied()) {
+		Ok(Command::Run(args)) => run(args),
+		other => panic!("unexpected command: {:?}", other),
+	};
+
+	std::fs::remove_file(&path).expect("failed to remove script");
+
+	status
+}
+
+
+#[test]
+#[serial]
+fn test_exit_status() {
+	assert_eq!(exit_status("src/runtime/tests/data/positive/assert.hsh"), ExitStatus::Success);
+	assert_eq!(
+		exit_status("src/syntax/tests/data/negative/command-block-1.hsh"),
+		ExitStatus::SyntaxError,
+	);
+	assert_eq!(
+		exit_status("src/semantic/tests/data/negative/undeclared-variable-1.hsh"),
+		ExitStatus::SemanticError,
+	);
+	assert_eq!(
+		exit_status("src/runtime/tests/data/negative/byte-arithmetic.hsh"),
+		ExitStatus::Panic,
+	);
+}
+
+
+#[test]
+#[serial]
+#[cfg(feature = "system")]
+fn test_status_from_result() {
+	let cases = [
+		("false", ExitStatus::Result(1)),
+		("true", ExitStatus::Success),
+		("0", ExitStatus::Success),
+		("42", ExitStatus::Result(42)),
+		("125", ExitStatus::Result(125)),
+		("126", ExitStatus::Result(1)),
+		("-1", ExitStatus::Result(1)),
+		("nil", ExitStatus::Success),
+		("\"failed\"", ExitStatus::Success),
+		("std.error(\"failed\", nil)", ExitStatus::Result(1)),
+		("{ false }", ExitStatus::Result(1)),
+		("{ true }", ExitStatus::Success),
+		("let x = 3", ExitStatus::Success),
+		("std.assert(false)\n0", ExitStatus::Panic),
+	];
+
+	for (ix, (source, expected)) in cases.iter().enumerate() {
+		let name = format!("status-{}", ix);
+
+		// Without the flag, the value is ignored.
+		let default = match expected {
+			ExitStatus::Panic => ExitStatus::Panic,
+			_ => ExitStatus::Success,
+		};
+		assert_eq!(source_exit_status(&name, source, &[]), default, "{}", source);
+
+		assert_eq!(
+			source_exit_status(&name, source, &[ "--status-from-result" ]),
+			*expected,
+			"{}",
+			source,
+		);
+	}
+
+	// Failed test cases take precedence over the value.
+	let source = "std.test.case(\"fails\", function () std.assert(false) end)\n0";
+	assert_eq!(
+		source_exit_status("status-test", source, &[ "--status-from-result", "--test" ]),
+		ExitStatus::TestFailure,
+	);
+}
+
+
+#[test]
+fn test_exit_codes() {
+	let codes: Vec<i32> = [
+		ExitStatus::Success,
+		ExitStatus::Panic,
+		ExitStatus::Result(42),
+		ExitStatus::Exit(200),
+		ExitStatus::SyntaxError,
+		ExitStatus::SemanticError,
+		ExitStatus::InternalError,
+	]
+	.iter()
+	.copied()
+	.map(Into::into)
+	.collect();
+
+	assert_eq!(codes, [ 0, 1, 42, 200, 2, 3, 101 ]);
+}
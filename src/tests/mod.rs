@@ -1 +1,6 @@
 pub mod util;
+mod diagnostic;
+mod engine;
+mod exit_status;
+mod repl;
+mod watch;
@@ -0,0 +1,208 @@
+use std::{
+	io::{self, Write},
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	repl::{complete, history, Repl},
+	runtime::Runtime,
+	symbol,
+	term::color,
+};
+use super::util::strip_escapes;
+
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+
+impl Buffer {
+	fn contents(&self) -> String {
+		strip_escapes(&String::from_utf8(self.0.lock().unwrap().clone()).unwrap())
+	}
+}
+
+
+impl Write for Buffer {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.lock().unwrap().write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+
+/// Run a scripted session, returning its stdout and stderr.
+fn session(input: &'static str) -> (String, String) {
+	let (_, stdout, stderr) = run(input);
+	(stdout, stderr)
+}
+
+
+/// Run a scripted session, returning the finished REPL, its stdout and stderr.
+fn run(input: &'static str) -> (Repl, String, String) {
+	let (repl, _, stdout, stderr) = run_status(input);
+	(repl, stdout, stderr)
+}
+
+
+/// Run a scripted session, also returning the status given to std.exit, if any.
+fn run_status(input: &'static str) -> (Repl, Option<u8>, String, String) {
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+
+	let stdout = Buffer::default();
+	let stderr = Buffer::default();
+	runtime.set_stdout(stdout.clone());
+	runtime.set_stderr(stderr.clone());
+	runtime.set_stdin(io::Cursor::new(input));
+
+	let mut repl = Repl::new(runtime);
+	let status = color::plain(|| repl.run()).expect("unexpected io error");
+
+	(repl, status, stdout.contents(), stderr.contents())
+}
+
+
+#[test]
+fn test_persistence() {
+	let (stdout, stderr) = session("let x = 20\nx * 2 + 2\nlet y = x + 1\ny\nstd.print(\"hi\")\n");
+
+	assert_eq!(stderr, "");
+	assert_eq!(stdout, ">> >> 42\n>> >> 21\n>> hi\n>> \n");
+}
+
+
+#[test]
+fn test_multi_line() {
+	let (stdout, stderr) = session(
+		"function add(a, b)\n\
+		   return a + b\n\
+		 end\n\
+		 add(1, 2)\n\
+		 let s = \"multi\n\
+		 line\"\n\
+		 s\n"
+	);
+
+	assert_eq!(stderr, "");
+	assert_eq!(stdout, ">> .. .. >> 3\n>> .. >> \"multi\\nline\"\n>> \n");
+}
+
+
+#[test]
+fn test_error_recovery() {
+	let (stdout, stderr) = session(
+		"let x = 1\n\
+		 undeclared\n\
+		 let y = )\n\
+		 std.assert(false)\n\
+		 let z = 2\n\
+		 x + z\n\
+		 y\n\
+		 function f()\n"
+	);
+
+	assert_eq!(stdout, ">> >> >> >> >> >> 3\n>> >> .. \n");
+
+	// Errors are reported, and rejected inputs declare nothing.
+	assert!(stderr.contains("<repl:2>:1:1 - undeclared variable 'undeclared'"), "{}", stderr);
+	assert!(stderr.contains("<repl:2>:1:9 - unexpected ')'"), "{}", stderr);
+	assert!(stderr.contains("Panic in <repl:2>:1:11: assertion failed"), "{}", stderr);
+	assert!(stderr.contains("<repl:5>:1:1 - undeclared variable 'y'"), "{}", stderr);
+
+	// The unfinished input is reported at the end of the input.
+	assert!(stderr.contains("expected 'end' to close 'function'"), "{}", stderr);
+}
+
+
+#[test]
+fn test_exit() {
+	// Inputs after std.exit are not evaluated, even when it's called within pcall.
+	let (_, status, stdout, stderr) = run_status(
+		"let f = function () std.exit(3) end\n\
+		 std.pcall(f)\n\
+		 std.print(\"unreachable\")\n"
+	);
+
+	assert_eq!(status, Some(3));
+	assert_eq!(stdout, ">> >> ");
+	assert_eq!(stderr, "");
+
+	// Invalid statuses are reported as any other panic.
+	let (_, status, _, stderr) = run_status("std.exit(-1)\n");
+	assert_eq!(status, None);
+	assert!(stderr.contains("expected exit status from 0 to 255"), "{}", stderr);
+}
+
+
+#[test]
+fn test_completion() {
+	let (repl, _, _) = run("let value = 1\nlet variable = 2\nlet rejected = undeclared\n");
+	let globals = repl.globals();
+	assert_eq!(globals, [ "std", "value", "variable" ]);
+
+	let complete = |line: &str| complete::candidates(line, line.len(), globals.iter().map(String::as_str));
+
+	assert_eq!(complete("let x = va"), (8, vec![ "value".into(), "variable".into() ]));
+	assert_eq!(complete("whi"), (0, vec![ "while".into() ]));
+	assert_eq!(complete("f"), (0, vec![ "false".into(), "for".into(), "function".into() ]));
+	assert_eq!(complete("std.pri"), (4, vec![ "print".into() ]));
+	assert!(complete("std.").1.contains(&"fs".to_string()));
+
+	// Only members of std are known, and std must be a whole word.
+	assert_eq!(complete("value.pri"), (6, Vec::new()));
+	assert_eq!(complete("mystd.pri"), (6, Vec::new()));
+
+	// The word ends at the cursor.
+	assert_eq!(
+		complete::candidates("value + 1", 2, globals.iter().map(String::as_str)),
+		(0, vec![ "value".into(), "variable".into() ]),
+	);
+}
+
+
+#[test]
+fn test_history_path() {
+	let env = |vars: &'static [(&'static str, &'static str)]| {
+		move |name: &str| vars
+			.iter()
+			.find(|(var, _)| *var == name)
+			.map(|(_, value)| value.into())
+	};
+
+	assert_eq!(
+		history::path(env(&[ ("HOME", "/home/user"), ("XDG_DATA_HOME", "/data") ])),
+		Some("/data/hush/history".into()),
+	);
+	assert_eq!(
+		history::path(env(&[ ("HOME", "/home/user"), ("XDG_DATA_HOME", "relative") ])),
+		Some("/home/user/.hush_history".into()),
+	);
+	assert_eq!(
+		history::path(env(&[ ("HOME", "/home/user"), ("XDG_DATA_HOME", "") ])),
+		Some("/home/user/.hush_history".into()),
+	);
+	assert_eq!(history::path(env(&[])), None);
+}
+
+
+#[test]
+fn test_history_round_trip() {
+	let dir = std::env::temp_dir().join(format!("hush-test-history-{}", std::process::id()));
+	let path = dir.join("hush").join("history");
+
+	assert_eq!(history::load(&path).ok(), Some(Vec::new()));
+
+	let entries = [ "let x = 1", "function f()\n\treturn \"a\\nb\"\nend", "x" ];
+	history::save(&path, entries.iter().copied()).expect("failed to save history");
+	assert_eq!(history::load(&path).ok(), Some(entries.iter().map(|entry| entry.to_string()).collect()));
+
+	// Only the latest entries are kept.
+	let many: Vec<String> = (0 .. history::MAX_ENTRIES + 10).map(|ix| ix.to_string()).collect();
+	history::save(&path, many.iter().map(String::as_str)).expect("failed to save history");
+	assert_eq!(history::load(&path).ok(), Some(many[10 ..].to_vec()));
+
+	std::fs::remove_dir_all(&dir).expect("failed to remove history");
+}
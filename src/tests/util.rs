@@ -33,3 +33,54 @@ where
 
 	run(&dir, &mut test)
 }
+
+
+/// Remove ANSI escape sequences from the given text.
+pub fn strip_escapes(text: &str) -> String {
+	let mut stripped = String::with_capacity(text.len());
+	let mut chars = text.chars();
+
+	while let Some(c) = chars.next() {
+		if c == '\x1b' {
+			// Skip the control sequence, up to and including the final letter.
+			for c in chars.by_ref() {
+				if c.is_ascii_alphabetic() {
+					break;
+				}
+			}
+		} else {
+			stripped.push(c);
+		}
+	}
+
+	stripped
+}
+
+
+/// The native stack size of threads spawned by `on_large_stack`. Only the pages actually
+/// used are committed.
+const LARGE_STACK_SIZE: usize = 256 << 20;
+
+
+/// The maximum call depth that fits in the stack of `on_large_stack`, even in unoptimized
+/// builds.
+pub const LARGE_STACK_CALL_DEPTH: usize = 1000;
+
+
+/// Run the given test on a thread with a large native stack. Calls recurse on the native
+/// stack, and unoptimized builds use far more of it per call than the test threads have
+/// to spare, which would abort the whole test binary on deep recursion.
+pub fn on_large_stack<F, R>(test: F) -> R
+where
+	F: FnOnce() -> R + Send,
+	R: Send,
+{
+	std::thread::scope(
+		|scope| std::thread::Builder::new()
+			.stack_size(LARGE_STACK_SIZE)
+			.spawn_scoped(scope, test)
+			.expect("failed to spawn test thread")
+			.join()
+			.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+	)
+}
@@ -0,0 +1,97 @@
+use std::{
+	fs,
+	path::Path,
+	time::{Duration, SystemTime},
+};
+
+use serial_test::serial;
+
+use crate::{args::{self, Command}, watch, watch_script, ExitStatus};
+
+
+/// Write the file, making sure its modification time changes even on coarse filesystems.
+fn write(path: &Path, contents: &str, run: usize) {
+	fs::write(path, contents).expect("failed to write file");
+
+	let mtime = SystemTime::now() + Duration::from_secs(run as u64);
+	fs::File::options()
+		.write(true)
+		.open(path)
+		.and_then(|file| file.set_modified(mtime))
+		.expect("failed to set modification time");
+}
+
+
+#[test]
+#[serial]
+fn test_watch() {
+	let mut dir = std::env::temp_dir();
+	dir.push(format!("hush-watch-{}", std::process::id()));
+	fs::create_dir_all(&dir).expect("failed to create temp dir");
+
+	let script = dir.join("main.hsh");
+	let module = dir.join("module.hsh");
+	write(&script, "std.import(\"module.hsh\") + 1", 0);
+	write(&module, "1", 0);
+
+	let args = [
+		"hush".as_ref(),
+		"--no-warnings".as_ref(),
+		"--status-from-result".as_ref(),
+		"--watch".as_ref(),
+		script.as_os_str(),
+	];
+
+	let args = match args::parse(args.iter().copied()) {
+		Ok(Command::Run(args)) => args,
+		other => panic!("unexpected command: {:?}", other),
+	};
+
+	let options = watch::Options {
+		poll: Duration::from_millis(10),
+		debounce: Duration::from_millis(20),
+	};
+
+	let mut statuses = Vec::new();
+
+	let status = watch_script(
+		args,
+		options,
+		|run, status| {
+			statuses.push(status);
+
+			match run {
+				// Imported modules are watched.
+				1 => write(&module, "10", run),
+				// Errors are reported, and the script is still watched.
+				2 => write(&script, "let x = ", run),
+				3 => write(&script, "std.import(\"module.hsh\") + 20", run),
+				_ => return false,
+			}
+
+			true
+		},
+	);
+
+	fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+
+	assert_eq!(
+		statuses,
+		[
+			ExitStatus::Result(2),
+			ExitStatus::Result(11),
+			ExitStatus::SyntaxError,
+			ExitStatus::Result(30),
+		]
+	);
+	assert_eq!(status, ExitStatus::Result(30));
+}
+
+
+#[test]
+fn test_watch_args() {
+	// Scripts from stdin or the command line can't be watched.
+	for args in [ &[ "hush", "--watch", "-" ][..], &[ "hush", "--watch", "-c", "1" ] ] {
+		assert!(args::parse(args.iter().copied()).is_err(), "{:?}", args);
+	}
+}
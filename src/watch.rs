@@ -0,0 +1,103 @@
+//! Detection of changes to scripts, by polling their modification times. This spares a
+//! dependency on platform specific notification mechanisms, at the cost of some latency.
+
+use std::{
+	collections::HashMap,
+	path::PathBuf,
+	time::{Duration, SystemTime},
+};
+
+
+/// How often files are checked for changes, and how long they must stay unchanged before
+/// being considered saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+	pub poll: Duration,
+	/// Editors may save files in several steps, and some save repeatedly in quick
+	/// succession, which must trigger a single run.
+	pub debounce: Duration,
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			poll: Duration::from_millis(250),
+			debounce: Duration::from_millis(100),
+		}
+	}
+}
+
+
+/// The modification times of a set of files.
+#[derive(Debug, Default)]
+pub struct Files {
+	/// The modification time of each file, or None if it couldn't be read, as when the file
+	/// is missing.
+	mtimes: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+
+impl Files {
+	/// Record the current modification time of the given file, if not yet watched.
+	pub fn watch(&mut self, path: PathBuf) {
+		self.mtimes
+			.entry(path)
+			.or_insert_with_key(|path| mtime(path));
+	}
+
+
+	/// How many files are watched.
+	pub fn len(&self) -> usize {
+		self.mtimes.len()
+	}
+
+
+	/// Whether no files are watched.
+	pub fn is_empty(&self) -> bool {
+		self.mtimes.is_empty()
+	}
+
+
+	/// Whether any file was modified, created or removed since it was recorded, updating
+	/// the recorded times.
+	fn changed(&mut self) -> bool {
+		let mut changed = false;
+
+		for (path, recorded) in self.mtimes.iter_mut() {
+			let current = mtime(path);
+
+			if current != *recorded {
+				*recorded = current;
+				changed = true;
+			}
+		}
+
+		changed
+	}
+
+
+	/// Block until some file changes, and then until no file changes for the debounce
+	/// period.
+	pub fn wait(&mut self, options: Options) {
+		while !self.changed() {
+			std::thread::sleep(options.poll);
+		}
+
+		loop {
+			std::thread::sleep(options.debounce);
+
+			if !self.changed() {
+				break;
+			}
+		}
+	}
+}
+
+
+/// The modification time of a file, if available.
+fn mtime(path: &std::path::Path) -> Option<SystemTime> {
+	std::fs::metadata(path)
+		.and_then(|metadata| metadata.modified())
+		.ok()
+}
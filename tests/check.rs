@@ -0,0 +1,108 @@
+//! With `--check`, scripts are analyzed, but never executed.
+
+mod util;
+
+use std::{
+	ffi::OsStr,
+	fs,
+	path::Path,
+};
+
+use serde_json::Value;
+
+use util::{hush, run, stderr, stdout, temp_path};
+
+
+/// A script that creates the given file when executed.
+fn side_effect(marker: &Path, rest: &str) -> String {
+	format!("std.fs.write(\"{}\", \"ran\")\n{}", marker.display(), rest)
+}
+
+
+#[test]
+fn test_check() {
+	let marker = temp_path("check-marker", "txt");
+
+	let clean = side_effect(&marker, "std.print(\"ran\")\n");
+	let output = run("check-clean", &clean, &[ "--check" ]);
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(stdout(&output), "");
+	assert_eq!(stderr(&output), "");
+
+	let syntax = side_effect(&marker, "let x = (1\n");
+	let output = run("check-syntax", &syntax, &[ "--check" ]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("Error"), "{}", stderr(&output));
+
+	let semantic = side_effect(&marker, "std.print(undeclared)\n");
+	let output = run("check-semantic", &semantic, &[ "--check" ]);
+	assert_eq!(output.status.code(), Some(3));
+	assert!(stderr(&output).contains("undeclared"), "{}", stderr(&output));
+
+	assert!(!marker.exists(), "a checked script was executed");
+}
+
+
+#[test]
+fn test_check_many() {
+	let scripts = [
+		("check-many-clean", "let x = 1\nstd.print(x)\n"),
+		("check-many-semantic", "std.print(undeclared)\n"),
+		("check-many-syntax", "let x = (1\n"),
+	];
+
+	let paths = scripts.map(
+		|(name, script)| {
+			let path = temp_path(name, "hsh");
+			fs::write(&path, script).expect("failed to write script");
+			path
+		}
+	);
+
+	let mut args = vec![ OsStr::new("--check") ];
+	args.extend(paths.iter().map(|path| path.as_os_str()));
+	let output = hush(args, "");
+
+	for path in &paths {
+		fs::remove_file(path).expect("failed to remove script");
+	}
+
+	// Every script is checked, and the first failure determines the exit status.
+	assert_eq!(output.status.code(), Some(3));
+	let stderr = stderr(&output);
+	assert!(stderr.contains("check-many-semantic.hsh"), "{}", stderr);
+	assert!(stderr.contains("check-many-syntax.hsh"), "{}", stderr);
+	assert!(!stderr.contains("check-many-clean.hsh"), "{}", stderr);
+}
+
+
+#[test]
+fn test_check_stdin() {
+	let output = hush([ "--check", "-" ], "std.print(\"ran\")\n");
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(stdout(&output), "");
+
+	let output = hush([ "--check", "-" ], "\n  undeclared\n");
+	assert_eq!(output.status.code(), Some(3));
+	assert!(stderr(&output).contains("<stdin>:2:3"), "{}", stderr(&output));
+}
+
+
+#[test]
+fn test_check_json() {
+	let output = run(
+		"check-json",
+		"let unused = 1\nstd.print(undeclared)\n",
+		&[ "--check", "--diagnostics=json" ],
+	);
+	assert_eq!(output.status.code(), Some(3));
+
+	let diagnostics: Vec<Value> = stderr(&output)
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("invalid JSON diagnostic"))
+		.collect();
+
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0]["severity"], "error");
+	assert_eq!(diagnostics[0]["line"], 2);
+}
@@ -0,0 +1,68 @@
+//! Scripts may be compiled to binary programs, either explicitly or through the cache.
+
+mod util;
+
+use std::fs;
+
+use util::{hush, run, stderr, stdout, temp_path};
+
+
+#[test]
+fn test_compile() {
+	let compiled = temp_path("compile", "out");
+	let script = "let f = function (x) x * 2 end\nstd.print(f(21))\n";
+
+	let output = run(
+		"compile",
+		script,
+		&[ "--compile", "-o", compiled.to_str().expect("temp path should be utf-8") ],
+	);
+	assert!(output.status.success(), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "");
+
+	let output = hush([ &compiled ], "");
+	assert!(output.status.success(), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "42\n");
+
+	// A corrupt program is refused.
+	let mut contents = fs::read(&compiled).expect("failed to read compiled program");
+	contents.truncate(contents.len() - 1);
+	fs::write(&compiled, contents).expect("failed to write compiled program");
+
+	let output = hush([ &compiled ], "");
+	assert!(!output.status.success());
+	assert!(stderr(&output).contains("compiled program is corrupt"));
+
+	fs::remove_file(&compiled).expect("failed to remove compiled program");
+}
+
+
+#[test]
+fn test_cache() {
+	let script = temp_path("cache", "hsh");
+	let cache = script.with_extension("hushc");
+	let _ = fs::remove_file(&cache);
+
+	fs::write(&script, "std.print(\"first\")\n").expect("failed to write script");
+
+	let output = hush([ "--cache".as_ref(), script.as_os_str() ], "");
+	assert!(output.status.success(), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "first\n");
+	assert!(cache.exists());
+
+	let output = hush([ "--cache".as_ref(), script.as_os_str() ], "");
+	assert_eq!(stdout(&output), "first\n");
+
+	// A stale cache is recompiled.
+	fs::write(&script, "std.print(\"second\")\n").expect("failed to write script");
+
+	let output = hush([ "--cache".as_ref(), script.as_os_str() ], "");
+	assert!(output.status.success(), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "second\n");
+
+	let output = hush([ &cache ], "");
+	assert_eq!(stdout(&output), "second\n");
+
+	fs::remove_file(&script).expect("failed to remove script");
+	fs::remove_file(&cache).expect("failed to remove cache");
+}
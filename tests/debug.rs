@@ -0,0 +1,94 @@
+//! With `--debug`, scripts run in a step debugger driven by commands read from stdin.
+
+mod util;
+
+use util::{run_with_stdin, stderr, stdout};
+
+
+const SCRIPT: &str = "let square = function (x)
+	let result = x * x
+	result
+end
+
+let total = 0
+for i in std.range(1, 4, 1) do
+	total = total + square(i)
+end
+
+std.print(total)
+";
+
+
+#[test]
+fn test_debug() {
+	let output = run_with_stdin(
+		"debug",
+		SCRIPT,
+		&[ "--debug" ],
+		"break 8\nrun\nprint i\nstep\nstep\nprint result\nprint total\nbacktrace\ncontinue\nprint i\ncontinue\ncontinue\n",
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "14\n");
+
+	let stderr = stderr(&output);
+	assert!(stderr.contains("breakpoint 1 at "), "{}", stderr);
+
+	// The breakpoint is hit on each iteration.
+	assert_eq!(stderr.matches("debug.hsh:8:").count(), 3 + 1, "{}", stderr);
+	assert!(stderr.contains("=>    8 | \ttotal = total + square(i)"), "{}", stderr);
+	assert!(stderr.contains("i = 1\n"), "{}", stderr);
+	assert!(stderr.contains("i = 2\n"), "{}", stderr);
+
+	// Stepping enters the call, where the locals are the function's.
+	assert!(stderr.contains("=>    2 | \tlet result = x * x"), "{}", stderr);
+	assert!(stderr.contains("=>    3 | \tresult"), "{}", stderr);
+	assert!(stderr.contains("result = 1\n"), "{}", stderr);
+	assert!(stderr.contains("no variable total in the current function"), "{}", stderr);
+
+	let backtrace: Vec<&str> = stderr
+		.lines()
+		.filter(|line| line.contains('#'))
+		.collect();
+	assert_eq!(backtrace.len(), 2, "{}", stderr);
+	assert!(backtrace[0].contains("debug.hsh:3:2 in function<"), "{}", stderr);
+	assert!(backtrace[1].ends_with("debug.hsh:8:24 in <main>"), "{}", stderr);
+}
+
+
+#[test]
+fn test_debug_next_finish() {
+	let output = run_with_stdin(
+		"debug-next",
+		SCRIPT,
+		&[ "--debug" ],
+		"break 8\nrun\nstep\nfinish\nprint total\nnext\nprint total\n",
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "14\n");
+
+	// Finishing returns to the loop, and next doesn't enter the call again.
+	let stderr = stderr(&output);
+	assert!(stderr.contains("total = 1\n"), "{}", stderr);
+	assert!(stderr.contains("total = 5\n"), "{}", stderr);
+	assert_eq!(stderr.matches("paused at").count(), 4, "{}", stderr);
+	assert_eq!(stderr.matches("=>    2 |").count(), 1, "{}", stderr);
+}
+
+
+#[test]
+fn test_debug_quit() {
+	let output = run_with_stdin("debug-quit", SCRIPT, &[ "--debug" ], "step\nquit\n");
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(stdout(&output), "");
+	assert!(stderr(&output).contains("debug-quit.hsh:1:"), "{}", stderr(&output));
+
+	let output = run_with_stdin(
+		"debug-invalid",
+		SCRIPT,
+		&[ "--debug" ],
+		"break nowhere\nstep\nfrobnicate\n",
+	);
+	assert_eq!(output.status.code(), Some(0));
+	assert!(stderr(&output).contains("invalid breakpoint: nowhere"), "{}", stderr(&output));
+	assert!(stderr(&output).contains("unknown command: frobnicate"), "{}", stderr(&output));
+}
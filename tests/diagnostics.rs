@@ -0,0 +1,97 @@
+//! With `--diagnostics=json`, each diagnostic is a JSON object in a line of stderr.
+
+mod util;
+
+use serde_json::Value;
+
+use util::{run, stderr, stdout};
+
+
+/// Run the script, and parse the diagnostics, checking the schema of each.
+fn diagnostics(name: &str, script: &str) -> Vec<Value> {
+	let output = run(name, script, &[ "--diagnostics=json" ]);
+	assert_eq!(stdout(&output), "");
+
+	let diagnostics: Vec<Value> = stderr(&output)
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("invalid JSON diagnostic"))
+		.collect();
+
+	for diagnostic in &diagnostics {
+		assert!(matches!(diagnostic["severity"].as_str(), Some("error" | "warning")));
+		assert!(diagnostic["code"].is_string(), "{}", diagnostic);
+		assert!(diagnostic["message"].is_string(), "{}", diagnostic);
+
+		// Some errors, like an unexpected end of file, have no position.
+		if diagnostic.get("line").is_some() {
+			assert!(diagnostic["file"].as_str().is_some_and(|file| file.ends_with(".hsh")));
+			assert!(diagnostic["line"].as_u64().is_some_and(|line| line > 0));
+			assert!(diagnostic["column"].as_u64().is_some_and(|column| column > 0));
+		}
+
+		for related in diagnostic["related"].as_array().into_iter().flatten() {
+			assert!(related["message"].is_string(), "{}", diagnostic);
+			assert!(related["file"].is_string(), "{}", diagnostic);
+			assert!(related["line"].is_u64(), "{}", diagnostic);
+			assert!(related["column"].is_u64(), "{}", diagnostic);
+		}
+	}
+
+	diagnostics
+}
+
+
+#[test]
+fn test_syntax_error() {
+	let diagnostics = diagnostics("json-syntax", "let x = (1\n");
+	assert_eq!(diagnostics.len(), 1);
+
+	let error = &diagnostics[0];
+	assert_eq!(error["severity"], "error");
+	assert_eq!(error["code"], "unexpected-eof");
+	assert_eq!((error["line"].as_u64(), error["column"].as_u64()), (Some(1), Some(9)));
+	assert_eq!(error["related"][0]["message"], "opened here");
+}
+
+
+#[test]
+fn test_lexer_error() {
+	let diagnostics = diagnostics("json-lexer", "let x = \"abc\n");
+	assert!(diagnostics.iter().any(|error| error["code"] == "unterminated-string"));
+}
+
+
+#[test]
+fn test_semantic_error() {
+	let diagnostics = diagnostics("json-semantic", "let a = 1\nlet a = 2\nstd.print(a)\n");
+	assert_eq!(diagnostics.len(), 1);
+
+	let error = &diagnostics[0];
+	assert_eq!(error["code"], "duplicate-variable");
+	assert!(error["message"].as_str().unwrap().starts_with("duplicate variable 'a'"));
+	assert_eq!((error["line"].as_u64(), error["column"].as_u64()), (Some(2), Some(5)));
+	assert_eq!(error["related"][0]["message"], "first declared here");
+	assert_eq!(error["related"][0]["line"], 1);
+}
+
+
+#[test]
+fn test_warning_and_panic() {
+	let diagnostics = diagnostics(
+		"json-panic",
+		"let unused = 1\nlet f = function(x)\n\tx + 1\nend\nf(\"a\")\n",
+	);
+	assert_eq!(diagnostics.len(), 2);
+
+	let warning = &diagnostics[0];
+	assert_eq!(warning["severity"], "warning");
+	assert_eq!(warning["code"], "unused-variable");
+	assert_eq!(warning["message"], "unused variable 'unused'");
+
+	let panic = &diagnostics[1];
+	assert_eq!(panic["severity"], "error");
+	assert_eq!(panic["code"], "invalid-operand");
+	assert_eq!((panic["line"].as_u64(), panic["column"].as_u64()), (Some(3), Some(2)));
+	assert_eq!(panic["related"][0]["message"], "called here");
+	assert_eq!(panic["related"][0]["line"], 5);
+}
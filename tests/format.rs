@@ -0,0 +1,58 @@
+//! With `--fmt`, scripts are formatted instead of executed.
+
+mod util;
+
+use std::fs;
+
+use util::{hush, stderr, stdout, temp_path};
+
+
+const UNFORMATTED: &str = "let x=1\nif x==1 then\nstd.print( x )\nend\n";
+const FORMATTED: &str = "let x = 1\nif x == 1 then\n\tstd.print(x)\nend\n";
+
+
+#[test]
+fn test_format() {
+	let path = temp_path("format", "hsh");
+	fs::write(&path, UNFORMATTED).expect("failed to write script");
+	let arg = path.to_str().expect("invalid path");
+
+	let output = hush([ "--fmt", arg ], "");
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), FORMATTED);
+
+	// Scripts are not rewritten unless requested.
+	assert_eq!(fs::read_to_string(&path).expect("failed to read script"), UNFORMATTED);
+
+	let output = hush([ "--fmt", "--check", arg ], "");
+	assert_eq!(output.status.code(), Some(5));
+	assert!(stderr(&output).contains("is not formatted"), "{}", stderr(&output));
+
+	let output = hush([ "--fmt", "--write", arg ], "");
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "");
+	assert_eq!(fs::read_to_string(&path).expect("failed to read script"), FORMATTED);
+
+	let output = hush([ "--fmt", "--check", arg ], "");
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "");
+
+	fs::remove_file(&path).expect("failed to remove script");
+}
+
+
+#[test]
+fn test_format_stdin() {
+	let output = hush([ "--fmt", "-" ], UNFORMATTED);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), FORMATTED);
+
+	let output = hush([ "--fmt", "--width", "10", "-" ], "f(first, second)\n");
+	assert_eq!(stdout(&output), "f(\n\tfirst,\n\tsecond,\n)\n");
+
+	// Scripts with syntax errors are left untouched.
+	let output = hush([ "--fmt", "-" ], "let x = (1\n");
+	assert_eq!(output.status.code(), Some(2));
+	assert_eq!(stdout(&output), "");
+	assert!(stderr(&output).contains("<stdin>"), "{}", stderr(&output));
+}
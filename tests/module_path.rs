@@ -0,0 +1,39 @@
+//! Modules are searched in the directories given with `--module-path`, and then in those
+//! of HUSH_PATH.
+
+mod util;
+
+use std::{fs, process::Command};
+
+use util::{stderr, stdout};
+
+
+#[test]
+fn test_module_path() {
+	let mut dir = std::env::temp_dir();
+	dir.push(format!("hush-module-path-cli-{}", std::process::id()));
+
+	for (name, origin) in [ ("flag", "flag"), ("env", "env"), ("env-only", "env") ] {
+		let module = dir.join(origin).join(format!("{}.hsh", name));
+		fs::create_dir_all(module.parent().expect("missing parent")).expect("failed to create dir");
+		fs::write(&module, format!("\"{}\"", origin)).expect("failed to write module");
+	}
+	fs::write(dir.join("env/flag.hsh"), "\"env\"").expect("failed to write module");
+
+	let hush_path = std::env::join_paths([ dir.join("missing"), dir.join("env") ])
+		.expect("invalid path");
+
+	let output = Command::new(env!("CARGO_BIN_EXE_hush"))
+		.env("HUSH_PATH", hush_path)
+		.arg("--module-path")
+		.arg(dir.join("flag"))
+		.arg("-c")
+		.arg("std.print(std.import(\"flag\"), std.import(\"env-only\"), std.try_import(\"other\"))")
+		.output()
+		.expect("failed to run hush");
+
+	fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "flag\tenv\tnil\n");
+}
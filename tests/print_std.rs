@@ -0,0 +1,88 @@
+//! With `--print-std`, the stdlib is described as JSON, without executing.
+
+mod util;
+
+use std::process::Command;
+
+use serde_json::Value;
+
+use util::{stderr, stdout};
+
+
+/// Run `hush --print-std`, returning the described entries.
+fn print_std() -> Vec<Value> {
+	let output = Command::new(env!("CARGO_BIN_EXE_hush"))
+		.arg("--print-std")
+		.output()
+		.expect("failed to run hush");
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+	match serde_json::from_str(&stdout(&output)).expect("invalid JSON") {
+		Value::Array(entries) => entries,
+		other => panic!("expected an array: {}", other),
+	}
+}
+
+
+fn entry<'a>(entries: &'a [Value], name: &str) -> &'a Value {
+	entries
+		.iter()
+		.find(|entry| entry["name"] == name)
+		.unwrap_or_else(|| panic!("missing entry: {}", name))
+}
+
+
+#[test]
+fn test_print_std() {
+	let entries = print_std();
+
+	let parse = entry(&entries, "std.json.parse");
+	assert_eq!(parse["kind"], "function");
+	assert_eq!(parse["min_args"], 1);
+	assert_eq!(parse["max_args"], 1);
+	assert_eq!(parse["variadic"], false);
+	assert_eq!(parse["doc"], "Parse a JSON string into a value.");
+
+	let range = entry(&entries, "std.range");
+	assert_eq!(range["min_args"], 2);
+	assert_eq!(range["max_args"], 3);
+
+	let sort = entry(&entries, "std.sort");
+	assert_eq!(sort["min_args"], 1);
+	assert_eq!(sort["max_args"], 2);
+
+	let print = entry(&entries, "std.print");
+	assert_eq!(print["min_args"], 0);
+	assert_eq!(print["max_args"], Value::Null);
+	assert_eq!(print["variadic"], true);
+
+	let pi = entry(&entries, "std.math.pi");
+	assert_eq!(pi["kind"], "constant");
+	assert!(pi.get("min_args").is_none(), "{}", pi);
+
+	// Functions which require the host are described even when unsupported.
+	assert_eq!(entry(&entries, "std.fs.read")["min_args"], 1);
+	assert_eq!(entry(&entries, "std.help")["min_args"], 1);
+}
+
+
+#[test]
+fn test_print_std_complete() {
+	let entries = print_std();
+
+	let names: Vec<&str> = entries
+		.iter()
+		.map(|entry| entry["name"].as_str().expect("missing name"))
+		.collect();
+
+	let mut sorted = names.clone();
+	sorted.sort_unstable();
+	sorted.dedup();
+	assert_eq!(names, sorted);
+
+	for entry in &entries {
+		let doc = entry["doc"].as_str().expect("missing doc");
+		assert!(!doc.is_empty() && !doc.contains('\n'), "{}", entry);
+		assert!(entry["name"].as_str().unwrap_or_default().starts_with("std."), "{}", entry);
+	}
+}
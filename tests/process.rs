@@ -0,0 +1,87 @@
+//! Scripts interact with their process through the environment, their arguments and the
+//! exit status.
+
+mod util;
+
+use util::{run, stderr, stdout};
+
+
+#[test]
+fn test_exit() {
+	for args in &[ &[][..], &[ "--tree-walker" ][..] ] {
+		// The script is unwound through functions and pcall, and output is not lost.
+		let output = run(
+			"exit",
+			"std.print(\"before\")\nlet f = function () std.exit(42) end\nstd.pcall(f)\nstd.print(\"after\")\n",
+			args,
+		);
+		assert_eq!(output.status.code(), Some(42), "{:?}: {}", args, stderr(&output));
+		assert_eq!(stdout(&output), "before\n");
+		assert_eq!(stderr(&output), "");
+
+		let output = run("exit-zero", "std.exit(0)\nstd.assert(false)\n", args);
+		assert_eq!(output.status.code(), Some(0), "{:?}: {}", args, stderr(&output));
+
+		// Exiting in a test case ends the test run, rather than failing the case.
+		let test_args: Vec<&str> = args.iter().copied().chain(Some("--test")).collect();
+		let output = run("exit-test", "std.test.case(\"exits\", function () std.exit(5) end)\n", &test_args);
+		assert_eq!(output.status.code(), Some(5), "{:?}: {}", args, stderr(&output));
+
+		let output = run("exit-invalid", "std.exit(256)\n", args);
+		assert_eq!(output.status.code(), Some(1));
+		assert!(stderr(&output).contains("expected exit status from 0 to 255"), "{}", stderr(&output));
+	}
+}
+
+
+#[cfg(feature = "system")]
+#[test]
+fn test_env() {
+	use std::{
+		ffi::OsStr,
+		os::unix::ffi::OsStrExt,
+		process::{Command, Stdio},
+	};
+
+	let script = "let value = std.env(\"HUSH_TEST_VALUE\")\n\
+		std.print(std.type(value), std.len(value), value == \"a\\xffb\")\n\
+		std.print(std.env(\"HUSH_TEST_UNSET\"))\n\
+		std.assert(std.export(\"HUSH_TEST_EXPORTED\", \"exported\") == nil)\n\
+		std.cd(\"/\")\n\
+		{ sh -c 'echo $HUSH_TEST_EXPORTED; pwd' }\n\
+		std.print(std.args())\n";
+
+	let mut path = std::env::temp_dir();
+	path.push(format!("hush-{}-env.hsh", std::process::id()));
+	std::fs::write(&path, script).expect("failed to write script");
+
+	// Values which are not valid UTF-8 are returned as raw byte strings.
+	let output = Command::new(env!("CARGO_BIN_EXE_hush"))
+		.env("NO_COLOR", "1")
+		.env("HUSH_TEST_VALUE", OsStr::from_bytes(b"a\xffb"))
+		.env_remove("HUSH_TEST_UNSET")
+		.arg(&path)
+		.args([ "one", "--two" ])
+		.stdin(Stdio::null())
+		.output()
+		.expect("failed to run hush");
+
+	std::fs::remove_file(&path).expect("failed to remove script");
+
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "string\t3\ttrue\nnil\nexported\n/\n[ \"one\", \"--two\" ]\n");
+}
+
+
+#[test]
+fn test_cd_failure() {
+	let output = run(
+		"cd-failure",
+		"let result = std.pcall(function () std.cd(\"/nonexistent/directory\") end)\n\
+		std.assert(not result.ok)\n\
+		std.print(std.cwd() != \"/nonexistent/directory\")\n",
+		&[],
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "true\n");
+}
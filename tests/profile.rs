@@ -0,0 +1,133 @@
+//! With `--profile`, the time spent in each function and command block is reported after
+//! executing.
+
+mod util;
+
+use std::fs;
+
+use serde_json::Value;
+
+use util::{run, stderr, stdout, temp_path};
+
+
+const SCRIPT: &str = "let slow = function ()
+	let i = 0
+	while i < 50000 do
+		i = i + 1
+	end
+	i
+end
+
+let fast = function (x)
+	x + 1
+end
+
+let i = 0
+while i < 5 do
+	fast(i)
+	if i % 2 == 0 then
+		slow()
+	end
+	i = i + 1
+end
+
+std.print(\"done\")
+";
+
+
+/// The entry of the report for the function defined at the given line.
+fn entry(report: &Value, line: u64) -> &Value {
+	report["entries"]
+		.as_array()
+		.expect("missing entries")
+		.iter()
+		.find(|entry| entry["kind"] == "function" && entry["line"] == line)
+		.unwrap_or_else(|| panic!("no entry for line {}: {}", line, report))
+}
+
+
+#[test]
+fn test_profile() {
+	let output = run("profile", SCRIPT, &[ "--profile" ]);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "done\n");
+
+	// The slow function is on top of the table.
+	let stderr = stderr(&output);
+	let mut lines = stderr.lines();
+	assert!(lines.next().unwrap_or_default().starts_with("Profile: "), "{}", stderr);
+	assert!(lines.next().unwrap_or_default().contains("exclusive"), "{}", stderr);
+
+	let top = lines.next().expect("empty profile");
+	assert!(top.contains("profile.hsh:1:12>"), "{}", stderr);
+	assert!(top.trim_start().starts_with("3 "), "{}", stderr);
+}
+
+
+#[test]
+fn test_profile_json() {
+	let out = temp_path("profile-json", "json");
+	let output = run(
+		"profile-json",
+		SCRIPT,
+		&[ "--profile", "--profile-out", out.to_str().expect("invalid path") ],
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+	let report: Value = serde_json::from_str(&fs::read_to_string(&out).expect("missing report"))
+		.expect("invalid JSON report");
+	fs::remove_file(&out).expect("failed to remove report");
+
+	let slow = entry(&report, 1);
+	let fast = entry(&report, 9);
+	assert_eq!(slow["calls"], 3);
+	assert_eq!(fast["calls"], 5);
+
+	let slow_ms = slow["exclusive_ms"].as_f64().expect("missing time");
+	let fast_ms = fast["exclusive_ms"].as_f64().expect("missing time");
+	assert!(slow_ms > 10.0 * fast_ms, "{}", report);
+	assert!(report["total_ms"].as_f64().expect("missing total") >= slow_ms, "{}", report);
+
+	let print = report["entries"]
+		.as_array()
+		.expect("missing entries")
+		.iter()
+		.find(|entry| entry["name"] == "std.print")
+		.expect("missing native function");
+	assert_eq!(print["calls"], 1);
+}
+
+
+#[cfg(feature = "system")]
+#[test]
+fn test_profile_commands() {
+	let out = temp_path("profile-commands", "json");
+	let output = run(
+		"profile-commands",
+		"let f = function () { true } end\nf()\nf()\n",
+		&[ "--profile", "--profile-out", out.to_str().expect("invalid path") ],
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+	let report: Value = serde_json::from_str(&fs::read_to_string(&out).expect("missing report"))
+		.expect("invalid JSON report");
+	fs::remove_file(&out).expect("failed to remove report");
+
+	let command = report["entries"]
+		.as_array()
+		.expect("missing entries")
+		.iter()
+		.find(|entry| entry["kind"] == "command")
+		.expect("missing command block");
+	assert_eq!(command["calls"], 2);
+	assert_eq!(command["line"], 1);
+
+	// Time spent in commands is not exclusive to the function.
+	let function = entry(&report, 1);
+	assert_eq!(function["calls"], 2);
+	assert!(
+		function["inclusive_ms"].as_f64() >= command["inclusive_ms"].as_f64(),
+		"{}",
+		report,
+	);
+}
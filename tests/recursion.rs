@@ -0,0 +1,25 @@
+//! Deep recursion panics with a stack overflow instead of crashing the interpreter.
+
+mod util;
+
+use util::{run, stderr};
+
+
+#[test]
+fn test_deep_recursion() {
+	let scripts = [
+		("tail", "let f = function (n)\n\tif n == 0 then\n\t\treturn 0\n\tend\n\treturn f(n - 1)\nend\n\nf(100000)\n"),
+		("nested", "let f = function (n)\n\tif n == 0 then\n\t\treturn 0\n\tend\n\treturn 1 + f(n - 1)\nend\n\nf(100000)\n"),
+	];
+
+	for (name, script) in &scripts {
+		for args in &[ &[][..], &[ "--tree-walker" ][..] ] {
+			let output = run(&format!("recursion-{}", name), script, args);
+			let stderr = stderr(&output);
+
+			assert_eq!(output.status.code(), Some(1), "{:?}: {}", args, stderr);
+			assert!(stderr.contains("stack overflow"), "{:?}: {}", args, stderr);
+			assert!(stderr.contains(":5:"), "{:?}: {}", args, stderr);
+		}
+	}
+}
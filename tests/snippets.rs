@@ -0,0 +1,73 @@
+//! Scripts may be given on the command line with `-c`, or piped to stdin with `-`.
+
+mod util;
+
+use std::{
+	io::Write,
+	process::{Command, Output, Stdio},
+};
+
+use util::{stderr, stdout};
+
+
+/// Run the interpreter with the given arguments, writing the given input to its stdin.
+fn hush(args: &[&str], stdin: &str) -> Output {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_hush"))
+		.env("NO_COLOR", "1")
+		.args(args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.expect("failed to run hush");
+
+	child.stdin
+		.take()
+		.expect("missing stdin")
+		.write_all(stdin.as_bytes())
+		.expect("failed to write stdin");
+
+	child.wait_with_output().expect("failed to run hush")
+}
+
+
+#[test]
+fn test_command_line() {
+	let output = hush(
+		&[ "-c", "for arg in std.iter(std.args()) do std.print(arg) end", "one", "-two", "--three" ],
+		"",
+	);
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "one\n-two\n--three\n");
+
+	// The script may still read stdin.
+	let output = hush(&[ "-c", "std.print(std.read())" ], "input\n");
+	assert_eq!(stdout(&output), "input\n\n");
+
+	let output = hush(&[ "-c", "let x = 1\nstd.assert(x == 2)" ], "");
+	assert_eq!(output.status.code(), Some(1));
+	assert!(stderr(&output).contains("Panic in <command-line>:2:11"), "{}", stderr(&output));
+
+	let output = hush(&[ "-c", "undeclared" ], "");
+	assert_eq!(output.status.code(), Some(3));
+	assert!(stderr(&output).contains("<command-line>:1:1"), "{}", stderr(&output));
+}
+
+
+#[test]
+fn test_stdin() {
+	let output = hush(&[ "-", "one", "two" ], "std.print(std.args())\n");
+	assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+	assert_eq!(stdout(&output), "[ \"one\", \"two\" ]\n");
+
+	let output = hush(&[ "-" ], "let x = 1\n  std.assert(x == 2)\n");
+	assert_eq!(output.status.code(), Some(1));
+	assert!(stderr(&output).contains("Panic in <stdin>:2:13"), "{}", stderr(&output));
+
+	// Without a terminal to read from, reading input is reported as unavailable.
+	if std::fs::File::open("/dev/tty").is_err() {
+		let output = hush(&[ "-" ], "std.read()\n");
+		assert_eq!(output.status.code(), Some(1));
+		assert!(stderr(&output).contains("stdin is unavailable"), "{}", stderr(&output));
+	}
+}
@@ -0,0 +1,52 @@
+//! The interpreter must keep stdout for the script's output, so that it can be redirected
+//! or piped, and report all diagnostics to stderr.
+
+mod util;
+
+use util::{run, stderr, stdout};
+
+
+#[test]
+#[cfg(feature = "system")]
+fn test_panic_streams() {
+	let output = run(
+		"panic",
+		"let unused = 1\nstd.print(\"out\")\n{ echo command }\nlet x = nil\nif x then end\n",
+		&[],
+	);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(stdout(&output), "out\ncommand\n");
+
+	let stderr = stderr(&output);
+	assert!(stderr.contains("Warning"), "{}", stderr);
+	assert!(stderr.contains("Panic"), "{}", stderr);
+}
+
+
+#[test]
+fn test_static_error_streams() {
+	let syntax = run("syntax", "std.print(\"out\"\n", &[]);
+	assert_eq!(syntax.status.code(), Some(2));
+	assert_eq!(stdout(&syntax), "");
+	assert!(!stderr(&syntax).is_empty());
+
+	let semantic = run("semantic", "std.print(undeclared)\n", &[]);
+	assert_eq!(semantic.status.code(), Some(3));
+	assert_eq!(stdout(&semantic), "");
+	assert!(stderr(&semantic).contains("undeclared"));
+}
+
+
+#[test]
+fn test_test_runner_streams() {
+	let output = run(
+		"test-runner",
+		"std.test.case(\"passes\", function() end)\n\
+		 std.test.case(\"fails\", function() std.assert(false) end)\n",
+		&[ "--test" ],
+	);
+
+	assert_eq!(stdout(&output), "PASS passes\nFAIL fails\n1 passed, 1 failed\n");
+	assert!(stderr(&output).contains("assertion failed"));
+}
@@ -0,0 +1,74 @@
+// Each integration test is a separate crate, which only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::{
+	ffi::OsStr,
+	fs,
+	io::Write,
+	path::PathBuf,
+	process::{Command, Output, Stdio},
+};
+
+
+/// A temporary path for the given test.
+pub fn temp_path(name: &str, extension: &str) -> PathBuf {
+	let mut path = std::env::temp_dir();
+	path.push(format!("hush-{}-{}.{}", std::process::id(), name, extension));
+	path
+}
+
+
+/// Run the interpreter with the given arguments, writing the given input to its stdin and
+/// capturing both streams.
+pub fn hush<I, S>(args: I, stdin: &str) -> Output
+where
+	I: IntoIterator<Item = S>,
+	S: AsRef<OsStr>,
+{
+	let mut child = Command::new(env!("CARGO_BIN_EXE_hush"))
+		.env("NO_COLOR", "1")
+		.args(args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.expect("failed to run hush");
+
+	child.stdin
+		.take()
+		.expect("missing stdin")
+		.write_all(stdin.as_bytes())
+		.expect("failed to write stdin");
+
+	child.wait_with_output().expect("failed to run hush")
+}
+
+
+/// Run the interpreter on the given script, written to a temporary file.
+pub fn run(name: &str, script: &str, args: &[&str]) -> Output {
+	run_with_stdin(name, script, args, "")
+}
+
+
+/// Like `run`, but writing the given input to the interpreter's stdin.
+pub fn run_with_stdin(name: &str, script: &str, args: &[&str], stdin: &str) -> Output {
+	let path = temp_path(name, "hsh");
+	fs::write(&path, script).expect("failed to write script");
+
+	let args = args.iter().map(OsStr::new).chain(Some(path.as_os_str()));
+	let output = hush(args, stdin);
+
+	fs::remove_file(&path).expect("failed to remove script");
+
+	output
+}
+
+
+pub fn stdout(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+
+pub fn stderr(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stderr).into_owned()
+}
@@ -0,0 +1,46 @@
+//! Warnings are capped, and may be denied.
+
+mod util;
+
+use util::{run, stderr};
+
+
+/// A script with the given number of unused variables.
+fn unused_variables(count: usize) -> String {
+	(0 .. count)
+		.map(|ix| format!("let x{} = {}\n", ix, ix))
+		.collect()
+}
+
+
+#[test]
+fn test_max_warnings() {
+	let script = unused_variables(150);
+
+	let output = run("max-warnings-default", &script, &[]);
+	assert!(output.status.success());
+	let lines: Vec<_> = stderr(&output).lines().map(ToOwned::to_owned).collect();
+	assert_eq!(lines.len(), 101);
+	assert_eq!(lines[100], "Warning: 50 more warnings suppressed");
+
+	let output = run("max-warnings", &script, &[ "--max-warnings=10" ]);
+	let lines: Vec<_> = stderr(&output).lines().map(ToOwned::to_owned).collect();
+	assert_eq!(lines.len(), 11);
+	assert_eq!(lines[10], "Warning: 140 more warnings suppressed");
+
+	let output = run("max-warnings-unlimited", &script, &[ "--max-warnings=0" ]);
+	assert_eq!(stderr(&output).lines().count(), 150);
+}
+
+
+#[test]
+fn test_deny_warnings() {
+	let output = run("deny-warnings", "let x = 1\nstd.print(\"ran\")\n", &[ "--deny-warnings" ]);
+	assert_eq!(output.status.code(), Some(3));
+	assert_eq!(util::stdout(&output), "");
+	assert!(stderr(&output).ends_with("Error: 1 warnings denied by --deny-warnings\n"));
+
+	let output = run("deny-no-warnings", "std.print(\"ran\")\n", &[ "--deny-warnings" ]);
+	assert!(output.status.success());
+	assert_eq!(util::stdout(&output), "ran\n");
+}